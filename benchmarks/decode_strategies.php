@@ -0,0 +1,104 @@
+<?php
+
+declare(strict_types=1);
+
+/**
+ * Decode Strategy Benchmark: DOM vs lazy iteration vs the automatic choice
+ */
+
+if (!class_exists('Sift')) {
+    die("ERROR: Sift extension not loaded.\n");
+}
+
+// Flat records and a deeply nested tree exercise the two strategies differently
+function flat_document(int $count): string {
+    $data = [];
+    for ($i = 0; $i < $count; $i++) {
+        $data[] = [
+            'id' => $i,
+            'name' => "User {$i}",
+            'email' => "user{$i}@example.com",
+            'active' => $i % 2 === 0,
+        ];
+    }
+    return json_encode(['users' => $data]);
+}
+
+function text_document(int $count, int $length): string {
+    $paragraph = str_repeat("Lorem ipsum \"dolor\" sit amet,\n consectetur \tadipiscing. ", intdiv($length, 64) + 1);
+    $data = [];
+    for ($i = 0; $i < $count; $i++) {
+        $data[] = ['id' => $i, 'body' => substr($paragraph, $i % 64, $length)];
+    }
+    return json_encode($data);
+}
+
+function nested_document(int $count, int $depth): string {
+    $node = ['value' => 1.5, 'tags' => ['a', 'b', 'c']];
+    for ($d = 0; $d < $depth; $d++) {
+        $node = ['level' => $d, 'child' => $node];
+    }
+    return json_encode(array_fill(0, $count, $node));
+}
+
+$datasets = [
+    'flat small' => flat_document(100),
+    'flat medium' => flat_document(10_000),
+    'flat large' => flat_document(100_000),
+    'nested medium' => nested_document(1_000, 32),
+    'long text (escaped)' => text_document(1_000, 16_384),
+];
+
+$iterations = 100;
+
+echo "Decode Strategy Benchmark: strategy dom vs lazy vs auto\n";
+echo str_repeat("=", 60) . "\n\n";
+
+foreach ($datasets as $label => $json) {
+    $jsonSize = strlen($json);
+
+    echo "Dataset: {$label} (" . number_format($jsonSize / 1024, 2) . " KB)\n";
+    echo str_repeat("-", 60) . "\n";
+
+    // Benchmark json_decode
+    $start = hrtime(true);
+    for ($i = 0; $i < $iterations; $i++) {
+        json_decode($json, true);
+    }
+    $jsonDecodeTime = (hrtime(true) - $start) / 1_000_000; // ms
+
+    // Benchmark Sift::decode forced through the DOM
+    $start = hrtime(true);
+    for ($i = 0; $i < $iterations; $i++) {
+        Sift::decode($json, ['strategy' => 'dom']);
+    }
+    $domTime = (hrtime(true) - $start) / 1_000_000; // ms
+
+    // Benchmark Sift::decode with the automatic strategy choice
+    $start = hrtime(true);
+    for ($i = 0; $i < $iterations; $i++) {
+        Sift::decode($json);
+    }
+    $autoTime = (hrtime(true) - $start) / 1_000_000; // ms
+
+    // Benchmark Sift::decodeLazy (lazy iteration)
+    $start = hrtime(true);
+    for ($i = 0; $i < $iterations; $i++) {
+        Sift::decodeLazy($json);
+    }
+    $lazyTime = (hrtime(true) - $start) / 1_000_000; // ms
+
+    // Benchmark Sift::get with an empty pointer (same lazy path)
+    $start = hrtime(true);
+    for ($i = 0; $i < $iterations; $i++) {
+        Sift::get($json, '');
+    }
+    $emptyPointerTime = (hrtime(true) - $start) / 1_000_000; // ms
+
+    printf("  json_decode:       %8.2f ms (%d iterations)\n", $jsonDecodeTime, $iterations);
+    printf("  strategy dom:      %8.2f ms\n", $domTime);
+    printf("  strategy auto:     %8.2f ms\n", $autoTime);
+    printf("  Sift::decodeLazy:  %8.2f ms\n", $lazyTime);
+    printf("  Sift::get(\$j,''): %8.2f ms\n", $emptyPointerTime);
+    printf("  Lazy vs DOM:       %8.2fx\n\n", $domTime / $lazyTime);
+}
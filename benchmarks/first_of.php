@@ -0,0 +1,64 @@
+<?php
+
+declare(strict_types=1);
+
+/**
+ * Pointer Fallback Benchmark: Sift::firstOf() vs sequential Sift::get() misses
+ */
+
+if (!class_exists('Sift')) {
+    die("ERROR: Sift extension not loaded.\n");
+}
+
+$records = array_map(
+    fn($i) => ['id' => $i, 'name' => "User {$i}", 'tags' => ['a', 'b', 'c']],
+    range(1, 50_000)
+);
+
+// The wanted value sits at the end of the document under the last
+// candidate, so every earlier candidate is a miss
+$datasets = [
+    'v1 layout, 3 candidates' => [
+        json_encode(['meta' => ['count' => 50_000], 'records' => $records, 'items' => [1, 2, 3]]),
+        ['/data/v3/items', '/data/items', '/items'],
+    ],
+    'v1 layout, 8 candidates' => [
+        json_encode(['meta' => ['count' => 50_000], 'records' => $records, 'items' => [1, 2, 3]]),
+        ['/v8/items', '/v7/items', '/v6/items', '/v5/items', '/v4/items', '/v3/items', '/data/items', '/items'],
+    ],
+];
+
+$iterations = 50;
+
+echo "Pointer Fallback Benchmark\n";
+echo str_repeat("=", 60) . "\n\n";
+
+foreach ($datasets as $label => [$json, $pointers]) {
+    $mb = strlen($json) / 1024 / 1024;
+
+    echo "Dataset: {$label} (" . number_format($mb, 2) . " MB)\n";
+    echo str_repeat("-", 60) . "\n";
+
+    $methods = [
+        'sequential get()' => function () use ($json, $pointers) {
+            foreach ($pointers as $pointer) {
+                try {
+                    return Sift::get($json, $pointer);
+                } catch (\Exception $e) {
+                }
+            }
+            return null;
+        },
+        'firstOf()' => fn() => Sift::firstOf($json, $pointers),
+    ];
+
+    foreach ($methods as $name => $fn) {
+        $start = hrtime(true);
+        for ($i = 0; $i < $iterations; $i++) {
+            $fn();
+        }
+        $ms = (hrtime(true) - $start) / 1_000_000;
+        printf("  %-20s %8.2f ms  %8.3f ms/op\n", $name . ':', $ms, $ms / $iterations);
+    }
+    echo "\n";
+}
@@ -0,0 +1,54 @@
+<?php
+
+declare(strict_types=1);
+
+/**
+ * Hashtable Presizing Benchmark: decode throughput on 100k+ element arrays
+ */
+
+if (!class_exists('Sift')) {
+    die("ERROR: Sift extension not loaded.\n");
+}
+
+$datasets = [
+    'ints (100k)' => json_encode(range(1, 100_000)),
+    'ints (1M)' => json_encode(range(1, 1_000_000)),
+    'wide object (200k keys)' => json_encode(array_combine(
+        array_map(fn($i) => "key{$i}", range(1, 200_000)),
+        range(1, 200_000)
+    )),
+    'records (100k)' => json_encode(array_map(
+        fn($i) => ['id' => $i, 'name' => "User {$i}"],
+        range(1, 100_000)
+    )),
+];
+
+$iterations = 20;
+
+echo "Hashtable Presizing Benchmark\n";
+echo str_repeat("=", 60) . "\n\n";
+
+foreach ($datasets as $label => $json) {
+    $mb = strlen($json) / 1024 / 1024;
+
+    echo "Dataset: {$label} (" . number_format($mb, 2) . " MB)\n";
+    echo str_repeat("-", 60) . "\n";
+
+    $methods = [
+        'json_decode' => fn() => json_decode($json, true),
+        'Sift::decode (dom)' => fn() => Sift::decode($json, ['strategy' => 'dom']),
+        'decodeLazy' => fn() => Sift::decodeLazy($json),
+        'decodeLazy presize' => fn() => Sift::decodeLazy($json, ['presize' => true]),
+    ];
+
+    foreach ($methods as $name => $fn) {
+        $start = hrtime(true);
+        for ($i = 0; $i < $iterations; $i++) {
+            $fn();
+        }
+        $ms = (hrtime(true) - $start) / 1_000_000;
+        $throughput = $mb * $iterations / ($ms / 1000);
+        printf("  %-20s %8.2f ms  %8.1f MB/s\n", $name . ':', $ms, $throughput);
+    }
+    echo "\n";
+}
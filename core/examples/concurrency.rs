@@ -0,0 +1,105 @@
+//! Concurrency benchmark: aggregate throughput of threads converting deep
+//! documents, which exercises the per-call arena (diagnostics paths and
+//! pointer node lists).
+//!
+//! Usage:
+//! ```text
+//! cargo run -p sift-core --release --example concurrency -- [threads] [iterations]
+//! cargo run -p sift-core --release --example concurrency --features no-arena -- [threads] [iterations]
+//! ```
+//!
+//! The second run is the baseline: every call gets a fresh arena instead
+//! of the thread's reused one. Results are in docs/BENCHMARKS.md.
+
+use sift_core::options::{DecodeOptions, Strategy};
+use sift_core::parser;
+use sift_core::value::{self, Value};
+use std::thread;
+use std::time::Instant;
+
+/// Nesting levels of each node, as in the PHP benchmark this replaces.
+const DEPTH: usize = 64;
+
+/// Deep, key-heavy document: every node pushes a diagnostics path segment.
+fn document() -> String {
+    let list: Vec<String> = (1..=20).map(|n| n.to_string()).collect();
+    let mut node = format!(r#"{{"leaf":1.5,"list":[{}]}}"#, list.join(","));
+    for d in 0..DEPTH {
+        node = format!(r#"{{"level~{d}":{node},"sibling/{d}":{d}}}"#);
+    }
+    format!("[{}]", vec![node; 50].join(","))
+}
+
+/// Pointer to the innermost leaf of the first node.
+fn deep_pointer() -> String {
+    let levels: String = (0..DEPTH).rev().map(|d| format!("/level~0{d}")).collect();
+    format!("/0{levels}/leaf")
+}
+
+/// The calls measured, each run on every thread in turn, with their
+/// iterations relative to the given count so each takes similar time.
+const OPERATIONS: [(&str, usize); 3] = [("decode (warnings)", 1), ("get", 100), ("firstOf", 10)];
+
+/// Runs operation `op` `iterations` times.
+fn run_worker(op: usize, json: &str, pointer: &str, iterations: usize) {
+    let options = DecodeOptions {
+        strategy: Strategy::Lazy,
+        warnings: true,
+        ..Default::default()
+    };
+    let candidates = ["/missing".to_string(), pointer.to_string()];
+    for _ in 0..iterations {
+        match op {
+            0 => {
+                let decoded = value::decode(json, &options).expect("document decodes");
+                assert!(matches!(decoded, Value::Array(_)));
+            }
+            1 => {
+                let leaf = parser::lazy_at(json, pointer).expect("pointer resolves");
+                assert_eq!(leaf.as_raw_str(), "1.5");
+            }
+            _ => {
+                let found = parser::first_of(json, &candidates).expect("document scans");
+                assert!(found.is_some());
+            }
+        }
+    }
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let threads: usize = args.next().and_then(|a| a.parse().ok()).unwrap_or(4).max(1);
+    let iterations: usize = args.next().and_then(|a| a.parse().ok()).unwrap_or(20);
+    let json = document();
+    let pointer = deep_pointer();
+
+    let arena = if cfg!(feature = "no-arena") {
+        "fresh per call"
+    } else {
+        "reused"
+    };
+    println!(
+        "Concurrency Benchmark ({threads} threads, {iterations} iterations each, arena {arena})"
+    );
+    println!("{}", "=".repeat(60));
+    println!("JSON size: {:.2} KB\n", json.len() as f64 / 1024.0);
+
+    for (op, &(name, scale)) in OPERATIONS.iter().enumerate() {
+        let iterations = iterations * scale;
+        // Warm up the code path once
+        run_worker(op, &json, &pointer, 1);
+
+        let start = Instant::now();
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| run_worker(op, &json, &pointer, iterations));
+            }
+        });
+        let ms = start.elapsed().as_secs_f64() * 1000.0;
+        let calls = threads * iterations;
+        println!(
+            "  {name:<18} {ms:10.2} ms  {:10.0} calls/s",
+            calls as f64 / (ms / 1000.0)
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Decode strategy benchmark: DOM against lazy conversion, with and
+//! without `presize`, across document sizes and shapes, in time and in
+//! peak memory. Its results set the `Strategy::Auto` threshold in
+//! `parser.rs`.
+//!
+//! Usage:
+//! ```text
+//! cargo run -p sift-core --release --example strategies
+//! ```
+//!
+//! Values are built as `value::Value`s rather than zvals, so the absolute
+//! times differ from the extension's, and growing a `Vec` costs less than
+//! growing a PHP array, which is what `presize` saves. Results are in
+//! docs/BENCHMARKS.md.
+
+use sift_core::options::{DecodeOptions, Strategy};
+use sift_core::value;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Document sizes measured, in KB.
+const SIZES_KB: [usize; 7] = [4, 16, 64, 256, 1024, 4096, 16384];
+
+/// Document shapes measured: records nested this many objects deep, or
+/// with `None` one wide array of numbers.
+const SHAPES: [(&str, Option<usize>); 4] = [
+    ("flat", Some(0)),
+    ("wide", None),
+    ("depth 8", Some(8)),
+    ("depth 32", Some(32)),
+];
+
+/// Time spent on each measurement.
+const BUDGET: Duration = Duration::from_millis(300);
+
+/// Bytes allocated now, and the most since the last reset.
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting live bytes.
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(current, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// A record nested `depth` objects deep; flat records, the shape of most
+/// API responses, at depth 0.
+fn record(n: usize, depth: usize) -> String {
+    if depth == 0 {
+        return format!(
+            r#"{{"id":{n},"name":"user {n}","tags":["a","b"],"score":1.5,"active":true}}"#
+        );
+    }
+    let mut node = format!(r#"{{"id":{n},"score":1.5}}"#);
+    for d in 0..depth {
+        node = format!(r#"{{"level{d}":{node},"n":{d}}}"#);
+    }
+    node
+}
+
+/// An array of records, or of numbers, grown to at least `size` bytes.
+fn document(size: usize, depth: Option<usize>) -> String {
+    let mut json = String::from("[");
+    let mut n = 0;
+    while json.len() < size {
+        if n > 0 {
+            json.push(',');
+        }
+        match depth {
+            Some(depth) => json.push_str(&record(n, depth)),
+            None => json.push_str(&(n * 7919).to_string()),
+        }
+        n += 1;
+    }
+    json.push(']');
+    json
+}
+
+/// Microseconds per decode of `json` under `options`, and the peak bytes
+/// one decode allocates.
+fn measure(json: &str, options: &DecodeOptions) -> (f64, usize) {
+    let base = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(base, Ordering::Relaxed);
+    value::decode(json, options).expect("document decodes");
+    let peak = PEAK.load(Ordering::Relaxed) - base;
+
+    let start = Instant::now();
+    let mut calls = 0u32;
+    while start.elapsed() < BUDGET {
+        value::decode(json, options).expect("document decodes");
+        calls += 1;
+    }
+    (start.elapsed().as_secs_f64() * 1e6 / f64::from(calls), peak)
+}
+
+fn main() {
+    let dom = DecodeOptions {
+        strategy: Strategy::Dom,
+        ..Default::default()
+    };
+    let lazy = DecodeOptions {
+        strategy: Strategy::Lazy,
+        ..Default::default()
+    };
+    let presized = DecodeOptions {
+        presize: true,
+        ..lazy
+    };
+
+    println!("Decode Strategy Benchmark (µs per decode; peak allocation / input size)");
+    println!("{}", "=".repeat(88));
+    println!(
+        "{:<8} {:>6} {:>12} {:>12} {:>12} {:>9} {:>9} {:>9}",
+        "Shape", "KB", "DOM", "Lazy", "Lazy+pre", "Lazy/DOM", "DOM mem", "Lazy mem"
+    );
+    for (shape, depth) in SHAPES {
+        for kb in SIZES_KB {
+            let json = document(kb * 1024, depth);
+            let (d, d_peak) = measure(&json, &dom);
+            let (l, l_peak) = measure(&json, &lazy);
+            let (p, _) = measure(&json, &presized);
+            let input = json.len() as f64;
+            println!(
+                "{shape:<8} {kb:>6} {d:>12.1} {l:>12.1} {p:>12.1} {:>9.2} {:>9.2} {:>9.2}",
+                l / d,
+                d_peak as f64 / input,
+                l_peak as f64 / input
+            );
+        }
+    }
+}
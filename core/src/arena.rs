@@ -0,0 +1,59 @@
+//! Per-call bump arena for transient conversion state.
+//!
+//! Conversions allocate small, short-lived buffers per node: pointer paths
+//! for diagnostics, pointer node lists, key escapes. Taking them from a
+//! thread-local bump arena that is reset after each call replaces those
+//! mallocs with pointer bumps, which also keeps worker threads from
+//! contending on the global allocator under ZTS.
+
+use bumpalo::Bump;
+use std::cell::RefCell;
+
+/// Arenas that grew beyond this during a call are freed instead of reset,
+/// so one huge document doesn't pin memory for the lifetime of the worker.
+const MAX_RETAINED_BYTES: usize = 1024 * 1024;
+
+thread_local! {
+    /// One arena per PHP thread, reused across calls.
+    static ARENA: RefCell<Bump> = RefCell::new(Bump::new());
+}
+
+/// Runs `f` with the thread's arena and resets it afterwards.
+/// Nothing allocated in the arena can outlive `f`.
+/// A nested call (the arena is already in use) gets a temporary arena, as
+/// does every call under the `no-arena` benchmark baseline.
+pub fn with_arena<R>(f: impl FnOnce(&Bump) -> R) -> R {
+    if cfg!(feature = "no-arena") {
+        return f(&Bump::new());
+    }
+    ARENA.with(|cell| match cell.try_borrow_mut() {
+        Ok(mut bump) => {
+            let result = f(&bump);
+            if bump.allocated_bytes() > MAX_RETAINED_BYTES {
+                *bump = Bump::new();
+            } else {
+                bump.reset();
+            }
+            result
+        }
+        Err(_) => f(&Bump::new()),
+    })
+}
+
+/// Bytes the thread's arena holds, 0 while a call is using it.
+pub fn allocated() -> usize {
+    ARENA.with(|cell| cell.try_borrow().map_or(0, |bump| bump.allocated_bytes()))
+}
+
+/// Free the thread's arena, returning how many bytes it held. An arena in
+/// use (a call on this thread is converting) is left alone.
+pub fn trim() -> usize {
+    ARENA.with(|cell| match cell.try_borrow_mut() {
+        Ok(mut bump) => {
+            let bytes = bump.allocated_bytes();
+            *bump = Bump::new();
+            bytes
+        }
+        Err(_) => 0,
+    })
+}
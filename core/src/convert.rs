@@ -0,0 +1,758 @@
+//! The conversion walk shared by every decode path.
+//!
+//! `convert` goes through a lazy value once, enforcing the depth limit and
+//! applying every decode option, recording lossy conversions, and builds
+//! the result through a `ValueSink`. `from_dom` does the same for a parsed
+//! `sonic_rs::Value`, for the options the DOM path supports.
+
+use crate::arena;
+use crate::diagnostics::{self, Diagnostics, WarningKind};
+use crate::error::Error;
+use crate::keys::KeyTransform;
+use crate::options::{BigNumbers, DecodeOptions, DuplicateKeys, Surrogates};
+use crate::parser::{self, JSON_ERROR_DEPTH, JSON_ERROR_INVALID_PROPERTY_NAME, JSON_ERROR_SYNTAX};
+use crate::parser::{iteration_error, MAX_DEPTH};
+use crate::precision::{self, NumberPolicies, NumberPolicy};
+use crate::sink::{Key, ValueSink};
+use crate::surrogates;
+use sonic_rs::{to_array_iter_unchecked, to_object_iter_unchecked};
+use sonic_rs::{JsonContainerTrait, JsonType, JsonValueTrait, LazyValue};
+use std::borrow::Cow;
+
+/// Full decode of `json`, through the DOM or lazily as
+/// `parser::chooses_lazy` decides.
+pub fn decode<S: ValueSink>(
+    json: &str,
+    options: &DecodeOptions,
+    sink: &mut S,
+) -> Result<S::Value, S::Error> {
+    diagnostics::start_decode();
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+
+    if parser::chooses_lazy(json, options) {
+        return decode_lazy(json, options, sink);
+    }
+
+    let value = parser::parse_dom(json)?;
+    from_dom(&value, sink)
+}
+
+/// Full decode through the lazy iterator instead of a DOM.
+/// Builds values straight from the input without an intermediate
+/// `sonic_rs::Value`, and keeps the raw number text the DOM discards, so
+/// strict mode ("-0"), lossy-conversion warnings and the options the DOM
+/// conversion doesn't implement (`DecodeOptions::needs_lazy`) always take
+/// this path.
+pub fn decode_lazy<S: ValueSink>(
+    json: &str,
+    options: &DecodeOptions,
+    sink: &mut S,
+) -> Result<S::Value, S::Error> {
+    let lazy = parser::document_root(json, options)?;
+    convert(lazy, options, sink)
+}
+
+/// Converts a lazy value. Primitives are extracted directly; arrays and
+/// objects are iterated without parsing them upfront. Lossy conversions
+/// are published for `Sift::lastWarnings()` when enabled.
+pub fn convert<S: ValueSink>(
+    lazy: LazyValue,
+    options: &DecodeOptions,
+    sink: &mut S,
+) -> Result<S::Value, S::Error> {
+    arena::with_arena(|bump| {
+        let mut diagnostics = Diagnostics::new(options.warnings, options.needs_pointers(), bump);
+        let value = convert_at(lazy, 0, options, sink, &mut diagnostics)?;
+        diagnostics.publish();
+        Ok(value)
+    })
+}
+
+/// Converts a lazy value `depth` levels below the root, with the pointer
+/// of its parent in `diagnostics`.
+pub fn convert_at<S: ValueSink>(
+    lazy: LazyValue,
+    depth: usize,
+    options: &DecodeOptions,
+    sink: &mut S,
+    diagnostics: &mut Diagnostics<'_>,
+) -> Result<S::Value, S::Error> {
+    // json_decode counts container nesting: 512 nested arrays are fine, 513 are not
+    if depth >= options.depth && (lazy.is_array() || lazy.is_object()) {
+        return Err(depth_exceeded(options).into());
+    }
+
+    if let Some((cutoff, leaves)) = options.cutoff {
+        if depth >= cutoff && (lazy.is_array() || lazy.is_object()) {
+            return sink.leaf(lazy.as_raw_str(), leaves);
+        }
+    }
+
+    if options.strict && lazy.as_raw_str() == "-0" {
+        // json_decode yields int(0) for "-0"; sonic-rs reads it as float -0.0
+        sink.int(0)
+    } else if let Some(policy) = number_policy(&lazy, options, diagnostics)? {
+        let raw = lazy.as_raw_str();
+        match policy {
+            NumberPolicy::String => sink.string(raw),
+            NumberPolicy::Places(places) => sink.float(
+                precision::round_literal(raw, places)
+                    .map_err(|e| e.at_pointer(diagnostics.pointer()))?,
+            ),
+        }
+    } else if lazy.is_null() {
+        sink.null()
+    } else if lazy.is_boolean() {
+        sink.bool(lazy.as_bool().unwrap())
+    } else if lazy.is_i64() {
+        sink.int(lazy.as_i64().unwrap())
+    } else if options.bigint_as_string
+        && lazy.get_type() == JsonType::Number
+        && lossy_float_kind(lazy.as_raw_str()) == Some(WarningKind::BigIntToFloat)
+    {
+        // Integers outside PHP's int range keep their digits, like
+        // JSON_BIGINT_AS_STRING
+        sink.string(lazy.as_raw_str())
+    } else if lazy.is_u64() {
+        let n = lazy.as_u64().unwrap();
+        // Check if value fits in i64 to prevent silent overflow
+        if n <= i64::MAX as u64 {
+            sink.int(n as i64)
+        } else if options.big_numbers.holds(WarningKind::BigIntToFloat) {
+            sink.big_number(lazy.as_raw_str(), options.big_numbers)
+        } else {
+            // Value too large for i64, convert to float to preserve precision
+            diagnostics.warn(WarningKind::BigIntToFloat);
+            sink.float(n as f64)
+        }
+    } else if lazy.is_f64() {
+        let n = lazy.as_f64().unwrap();
+        let raw = lazy.as_raw_str();
+        match lossy_float_kind(raw) {
+            Some(kind) if options.big_numbers.holds(kind) => {
+                sink.big_number(raw, options.big_numbers)
+            }
+            Some(kind) => {
+                diagnostics.warn(kind);
+                sink.float(n)
+            }
+            None => sink.float(n),
+        }
+    } else if lazy.is_str() {
+        let text = parser::control_chars(lazy.as_str().unwrap(), options)
+            .map_err(|e| e.at_pointer(diagnostics.pointer()))?;
+        let interpolated = match options.interpolate.apply(&text) {
+            Ok(Cow::Owned(interpolated)) => Some(interpolated),
+            Ok(Cow::Borrowed(_)) => None,
+            Err(e) => return Err(e.at_pointer(diagnostics.pointer()).into()),
+        };
+        let text = interpolated.map_or(text, Cow::Owned);
+        if options.surrogates == Surrogates::Wtf8 {
+            sink.bytes(&surrogates::to_wtf8(&text))
+        } else {
+            sink.string(&text)
+        }
+    } else if lazy.is_array() {
+        // Use lazy iteration - parses elements on-demand
+        let capacity = if options.presize {
+            // SAFETY: we've verified this is an array via is_array()
+            unsafe { to_array_iter_unchecked(lazy.as_raw_str()) }.count()
+        } else {
+            0
+        };
+        let mut array = sink.array(capacity)?;
+        // SAFETY: we've verified this is an array via is_array()
+        for (idx, item) in unsafe { to_array_iter_unchecked(lazy.as_raw_str()) }.enumerate() {
+            let item = item.map_err(|e| iteration_error(e, lazy.as_raw_str(), options))?;
+            diagnostics.push_index(idx);
+            let value = convert_at(item, depth + 1, options, sink, diagnostics)?;
+            diagnostics.pop();
+            sink.push(&mut array, value)?;
+        }
+        Ok(array)
+    } else if lazy.is_object() {
+        convert_object(lazy, depth, options, sink, diagnostics)
+    } else if (options.strict || options.big_numbers == BigNumbers::BcMath)
+        && lazy.get_type() == JsonType::Number
+    {
+        // Out-of-range literals such as 1e400: json_decode yields INF,
+        // BCMath\Number holds them exactly
+        let raw = lazy.as_raw_str();
+        if options.big_numbers.holds(WarningKind::FloatPrecision) {
+            sink.big_number(raw, options.big_numbers)
+        } else {
+            let n: f64 = raw
+                .parse()
+                .map_err(|_| Error::JsonError(JSON_ERROR_SYNTAX, "Syntax error".to_string()))?;
+            diagnostics.warn(WarningKind::FloatPrecision);
+            sink.float(n)
+        }
+    } else {
+        Err(Error::TypeError("Unknown JSON value type".to_string()).into())
+    }
+}
+
+/// Converts an object: an associative array, or a `stdClass` as
+/// `json_decode($json, false)` makes, depending on `options.assoc`.
+fn convert_object<S: ValueSink>(
+    lazy: LazyValue,
+    depth: usize,
+    options: &DecodeOptions,
+    sink: &mut S,
+    diagnostics: &mut Diagnostics<'_>,
+) -> Result<S::Value, S::Error> {
+    // Use lazy iteration - parses key/value pairs on-demand
+    let capacity = if options.presize && options.assoc {
+        // SAFETY: the caller has verified this is an object via is_object()
+        unsafe { to_object_iter_unchecked(lazy.as_raw_str()) }.count()
+    } else {
+        0
+    };
+    let mut object = sink.object(capacity)?;
+    // SAFETY: the caller has verified this is an object via is_object()
+    for entry in unsafe { to_object_iter_unchecked(lazy.as_raw_str()) } {
+        let (key, val) = entry.map_err(|e| iteration_error(e, lazy.as_raw_str(), options))?;
+        let cleaned = parser::clean_key(&key, options, diagnostics)?;
+        let key_text = options.strip_prefix.strip_from(&cleaned);
+        let separator = options.nest_keys.as_str();
+        if options.assoc && !separator.is_empty() && key_text.contains(separator) {
+            diagnostics.push_key(&key);
+            let value = convert_at(val, depth + 1, options, sink, diagnostics)?;
+            diagnostics.pop();
+            let segments = key_text
+                .split(separator)
+                .map(|segment| rename_key(segment, options, sink))
+                .collect::<Result<Vec<_>, _>>()?;
+            insert_nested(&mut object, &segments, value, depth + 1, options, sink)?;
+            continue;
+        }
+        let name = rename_key(key_text, options, sink)?;
+        let member = if options.assoc {
+            member_key(&name, options)
+        } else {
+            // Property names starting with NUL are reserved for mangled
+            // private/protected names
+            if name.starts_with('\0') {
+                return Err(if options.strict {
+                    Error::JsonError(
+                        JSON_ERROR_INVALID_PROPERTY_NAME,
+                        "The decoded property name is invalid".to_string(),
+                    )
+                } else {
+                    Error::ParseError("Invalid property name".to_string())
+                }
+                .into());
+            }
+            Key::Name(&name)
+        };
+        if options.duplicate_keys != DuplicateKeys::Last
+            && sink.contains(&object, &member)
+            && skip_duplicate(options)?
+        {
+            continue;
+        }
+        diagnostics.push_key(&key);
+        let value = convert_at(val, depth + 1, options, sink, diagnostics)?;
+        diagnostics.pop();
+        sink.insert(&mut object, member, value)?;
+    }
+    Ok(object)
+}
+
+/// The `numbers` policy of `lazy`, if it is a number one applies to.
+/// Rounding leaves integer literals alone.
+fn number_policy(
+    lazy: &LazyValue,
+    options: &DecodeOptions,
+    diagnostics: &Diagnostics<'_>,
+) -> Result<Option<NumberPolicy>, Error> {
+    if options.numbers == NumberPolicies::Off || lazy.get_type() != JsonType::Number {
+        return Ok(None);
+    }
+    let policy = options.numbers.lookup(diagnostics.pointer())?;
+    Ok(policy.filter(|policy| match policy {
+        NumberPolicy::String => true,
+        NumberPolicy::Places(_) => !precision::is_integer_literal(lazy.as_raw_str()),
+    }))
+}
+
+/// `key` under the `keys` option; callbacks are run by the sink.
+fn rename_key<'k, S: ValueSink>(
+    key: &'k str,
+    options: &DecodeOptions,
+    sink: &mut S,
+) -> Result<Cow<'k, str>, S::Error> {
+    match options.keys {
+        KeyTransform::Callback(index) => sink.rename_key(index, key).map(Cow::Owned),
+        transform => Ok(transform.apply(key).unwrap_or(Cow::Borrowed(key))),
+    }
+}
+
+/// The key an associative array stores member `name` under. Strict mode
+/// follows PHP's symtable rules: "123" becomes integer key 123.
+fn member_key<'k>(name: &'k str, options: &DecodeOptions) -> Key<'k> {
+    match php_numeric_key(name).filter(|_| options.strict) {
+        Some(index) => Key::Index(index),
+        None => Key::Name(name),
+    }
+}
+
+/// Inserts `value` at the nested path `segments` (a key split on the
+/// `nest_keys` separator), creating arrays on the way and merging into
+/// ones already there. An existing final key, or a non-array in the way,
+/// is a repeated key under `options.duplicate_keys`. `depth` is the depth
+/// of `object`'s values; every segment adds a level.
+fn insert_nested<S: ValueSink>(
+    object: &mut S::Value,
+    segments: &[Cow<'_, str>],
+    value: S::Value,
+    depth: usize,
+    options: &DecodeOptions,
+    sink: &mut S,
+) -> Result<(), S::Error> {
+    let (first, rest) = segments
+        .split_first()
+        .expect("str::split yields at least one segment");
+    if !rest.is_empty() && depth >= options.depth {
+        return Err(depth_exceeded(options).into());
+    }
+
+    let key = member_key(first, options);
+    if !rest.is_empty() {
+        if let Some(child) = sink.nested(object, &key) {
+            return insert_nested(child, rest, value, depth + 1, options, sink);
+        }
+    }
+    if sink.contains(object, &key) && skip_duplicate(options)? {
+        return Ok(());
+    }
+
+    let value = if rest.is_empty() {
+        value
+    } else {
+        let mut child = sink.object(0)?;
+        insert_nested(&mut child, rest, value, depth + 1, options, sink)?;
+        child
+    };
+    sink.insert(object, key, value)
+}
+
+/// Resolves a repeated object key under `options.duplicate_keys`:
+/// true keeps the earlier value, false overwrites it.
+pub fn skip_duplicate(options: &DecodeOptions) -> Result<bool, Error> {
+    match options.duplicate_keys {
+        DuplicateKeys::Last => Ok(false),
+        DuplicateKeys::First => Ok(true),
+        DuplicateKeys::Error => Err(Error::ParseError("Duplicate object key".to_string())),
+    }
+}
+
+/// The error for nesting deeper than `options.depth`: json_decode's in
+/// strict mode, one naming the limit otherwise.
+fn depth_exceeded(options: &DecodeOptions) -> Error {
+    if options.strict {
+        Error::JsonError(JSON_ERROR_DEPTH, "Maximum stack depth exceeded".to_string())
+    } else {
+        depth_error(options.depth)
+    }
+}
+
+/// The error for nesting deeper than `limit` outside strict mode.
+fn depth_error(limit: usize) -> Error {
+    Error::ParseError(format!("Maximum nesting depth ({}) exceeded", limit))
+}
+
+/// Classifies the loss when a number literal decodes to a float.
+/// Integer literals only become floats when they overflow PHP's int range;
+/// decimal literals lose precision beyond 17 significant digits.
+fn lossy_float_kind(raw: &str) -> Option<WarningKind> {
+    if raw == "-0" {
+        return None;
+    }
+    if !raw.contains(['.', 'e', 'E']) {
+        return Some(WarningKind::BigIntToFloat);
+    }
+    let mantissa = raw.split(['e', 'E']).next().unwrap_or(raw);
+    let digits: String = mantissa.chars().filter(|c| c.is_ascii_digit()).collect();
+    let significant = digits.trim_start_matches('0').trim_end_matches('0');
+    (significant.len() > 17).then_some(WarningKind::FloatPrecision)
+}
+
+/// Returns the integer a key maps to under PHP's array key rules
+/// (ZEND_HANDLE_NUMERIC_STR): "7" and "-3" are integer keys, while
+/// "07", "-0", "+1" and values outside the i64 range stay strings.
+pub fn php_numeric_key(key: &str) -> Option<i64> {
+    let digits = key.strip_prefix('-').unwrap_or(key);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if (digits.len() > 1 && digits.starts_with('0')) || key == "-0" {
+        return None;
+    }
+    key.parse::<i64>().ok()
+}
+
+/// Converts a parsed DOM. The DOM path is only taken for options it
+/// supports (see `DecodeOptions::needs_lazy`): objects become associative
+/// arrays with their keys as they are, and numbers are read as parsed.
+pub fn from_dom<S: ValueSink>(value: &sonic_rs::Value, sink: &mut S) -> Result<S::Value, S::Error> {
+    diagnostics::start_decode();
+    from_dom_at(value, 0, sink)
+}
+
+/// Converts a DOM value `depth` levels below the root.
+fn from_dom_at<S: ValueSink>(
+    value: &sonic_rs::Value,
+    depth: usize,
+    sink: &mut S,
+) -> Result<S::Value, S::Error> {
+    if depth >= MAX_DEPTH && (value.is_array() || value.is_object()) {
+        return Err(depth_error(MAX_DEPTH).into());
+    }
+
+    if value.is_null() {
+        sink.null()
+    } else if value.is_boolean() {
+        sink.bool(value.as_bool().unwrap())
+    } else if value.is_i64() {
+        sink.int(value.as_i64().unwrap())
+    } else if value.is_u64() {
+        let n = value.as_u64().unwrap();
+        // Check if value fits in i64 to prevent silent overflow
+        if n <= i64::MAX as u64 {
+            sink.int(n as i64)
+        } else {
+            // Value too large for i64, convert to float to preserve precision
+            sink.float(n as f64)
+        }
+    } else if value.is_f64() {
+        sink.float(value.as_f64().unwrap())
+    } else if value.is_str() {
+        sink.string(value.as_str().unwrap())
+    } else if value.is_array() {
+        let arr = value.as_array().unwrap();
+        // The DOM knows its length, so size the container once up front
+        let mut array = sink.array(arr.len())?;
+        for item in arr.iter() {
+            let item = from_dom_at(item, depth + 1, sink)?;
+            sink.push(&mut array, item)?;
+        }
+        Ok(array)
+    } else if value.is_object() {
+        let obj = value.as_object().unwrap();
+        let mut object = sink.object(obj.len())?;
+        for (key, val) in obj.iter() {
+            let val = from_dom_at(val, depth + 1, sink)?;
+            sink.insert(&mut object, Key::Name(key), val)?;
+        }
+        Ok(object)
+    } else {
+        Err(Error::TypeError("Unknown JSON value type".to_string()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::ControlChars;
+    use crate::value::{self, Value};
+
+    fn nested(levels: usize) -> String {
+        format!("{}{}", "[".repeat(levels), "]".repeat(levels))
+    }
+
+    #[test]
+    fn depth_limit_counts_containers() {
+        let options = DecodeOptions {
+            depth: 3,
+            ..Default::default()
+        };
+        assert!(value::decode(&nested(3), &options).is_ok());
+        let err = value::decode(&nested(4), &options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "JSON parse error: Maximum nesting depth (3) exceeded"
+        );
+    }
+
+    #[test]
+    fn strict_depth_limit_counts_containers_like_json_decode() {
+        let options = DecodeOptions {
+            strict: true,
+            depth: 3,
+            ..Default::default()
+        };
+        assert!(value::decode(&nested(3), &options).is_ok());
+        match value::decode(&nested(4), &options) {
+            Err(Error::JsonError(code, _)) => assert_eq!(code, JSON_ERROR_DEPTH),
+            other => panic!("expected a depth error, got {:?}", other),
+        }
+        // Scalars at the limit don't count
+        assert!(value::decode("[[[1]]]", &options).is_ok());
+    }
+
+    #[test]
+    fn nested_keys_count_towards_the_depth() {
+        let options = DecodeOptions {
+            depth: 2,
+            nest_keys: crate::keys::Affix::new(".").unwrap(),
+            ..Default::default()
+        };
+        assert!(value::decode(r#"{"a.b": 1}"#, &options).is_ok());
+        assert!(value::decode(r#"{"a.b.c": 1}"#, &options).is_err());
+    }
+
+    #[test]
+    fn big_numbers_mode_decides_what_stays_exact() {
+        assert!(!BigNumbers::Float.holds(WarningKind::BigIntToFloat));
+        assert!(BigNumbers::BcMath.holds(WarningKind::FloatPrecision));
+        assert!(BigNumbers::Gmp.holds(WarningKind::BigIntToFloat));
+        assert!(!BigNumbers::Gmp.holds(WarningKind::FloatPrecision));
+
+        let json = r#"[18446744073709551616, 0.12345678901234567890, 1e400, 1.5]"#;
+        let options = DecodeOptions {
+            big_numbers: BigNumbers::BcMath,
+            ..Default::default()
+        };
+        assert_eq!(
+            value::decode(json, &options).unwrap(),
+            Value::Array(vec![
+                Value::BigNumber("18446744073709551616".to_string()),
+                Value::BigNumber("0.12345678901234567890".to_string()),
+                Value::BigNumber("1e400".to_string()),
+                Value::Float(1.5),
+            ])
+        );
+
+        let options = DecodeOptions {
+            big_numbers: BigNumbers::Gmp,
+            ..Default::default()
+        };
+        let decoded = value::decode("[18446744073709551616, 0.12345678901234567890]", &options);
+        assert_eq!(
+            decoded.unwrap(),
+            Value::Array(vec![
+                Value::BigNumber("18446744073709551616".to_string()),
+                Value::Float(0.123_456_789_012_345_68),
+            ])
+        );
+    }
+
+    #[test]
+    fn lossy_numbers_are_warned_about_at_their_pointer() {
+        let options = DecodeOptions {
+            warnings: true,
+            ..Default::default()
+        };
+        let decoded = value::decode(r#"{"id": 18446744073709551616, "n": 2}"#, &options).unwrap();
+        assert_eq!(
+            decoded.get("id"),
+            Some(&Value::Float(18446744073709551616.0))
+        );
+        let warnings = diagnostics::last_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].pointer.as_deref(), Some("/id"));
+        assert_eq!(warnings[0].kind, WarningKind::BigIntToFloat);
+    }
+
+    #[test]
+    fn bigint_as_string_takes_precedence_over_big_numbers() {
+        let options = DecodeOptions {
+            bigint_as_string: true,
+            big_numbers: BigNumbers::BcMath,
+            ..Default::default()
+        };
+        assert_eq!(
+            value::decode("18446744073709551616", &options).unwrap(),
+            Value::String("18446744073709551616".to_string())
+        );
+    }
+
+    #[test]
+    fn strict_mode_follows_php_key_and_zero_rules() {
+        let options = DecodeOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let decoded = value::decode(r#"{"7": -0, "07": 1}"#, &options).unwrap();
+        assert_eq!(
+            decoded,
+            Value::Object(vec![
+                ("7".to_string(), Value::Int(0)),
+                ("07".to_string(), Value::Int(1)),
+            ])
+        );
+        assert_eq!(php_numeric_key("-3"), Some(-3));
+        assert_eq!(php_numeric_key("-0"), None);
+        assert_eq!(php_numeric_key("+1"), None);
+    }
+
+    #[test]
+    fn duplicate_keys_follow_the_policy() {
+        let json = r#"{"a": 1, "a": 2}"#;
+        let decode = |duplicate_keys| {
+            value::decode(
+                json,
+                &DecodeOptions {
+                    duplicate_keys,
+                    ..Default::default()
+                },
+            )
+        };
+        assert_eq!(
+            decode(DuplicateKeys::Last).unwrap().get("a"),
+            Some(&Value::Int(2))
+        );
+        assert_eq!(
+            decode(DuplicateKeys::First).unwrap().get("a"),
+            Some(&Value::Int(1))
+        );
+        assert!(decode(DuplicateKeys::Error).is_err());
+    }
+
+    #[test]
+    fn keys_are_renamed_after_the_prefix_is_stripped() {
+        let options = DecodeOptions {
+            keys: KeyTransform::Camel,
+            strip_prefix: crate::keys::Affix::new("x_").unwrap(),
+            ..Default::default()
+        };
+        let json = r#"{"x_first_name": "Ada", "x_": 1, "last-name": {"zip_code": 2}}"#;
+        assert_eq!(
+            value::decode(json, &options).unwrap(),
+            Value::Object(vec![
+                ("firstName".to_string(), Value::String("Ada".to_string())),
+                // A key that is only the prefix keeps it
+                ("x_".to_string(), Value::Int(1)),
+                (
+                    "lastName".to_string(),
+                    Value::Object(vec![("zipCode".to_string(), Value::Int(2))]),
+                ),
+            ])
+        );
+
+        let options = DecodeOptions {
+            keys: KeyTransform::Snake,
+            ..Default::default()
+        };
+        let decoded = value::decode(r#"{"firstName": 1}"#, &options).unwrap();
+        assert_eq!(decoded.get("first_name"), Some(&Value::Int(1)));
+    }
+
+    #[test]
+    fn nested_keys_build_and_merge_objects() {
+        let options = DecodeOptions {
+            nest_keys: crate::keys::Affix::new(".").unwrap(),
+            keys: KeyTransform::Camel,
+            ..Default::default()
+        };
+        let json =
+            r#"{"db": {"user": "u"}, "db.host": "h", "db.max_conn": 5, "app.name.full": "x"}"#;
+        assert_eq!(
+            value::decode(json, &options).unwrap(),
+            Value::Object(vec![
+                (
+                    "db".to_string(),
+                    Value::Object(vec![
+                        ("user".to_string(), Value::String("u".to_string())),
+                        ("host".to_string(), Value::String("h".to_string())),
+                        ("maxConn".to_string(), Value::Int(5)),
+                    ]),
+                ),
+                (
+                    "app".to_string(),
+                    Value::Object(vec![(
+                        "name".to_string(),
+                        Value::Object(vec![("full".to_string(), Value::String("x".to_string()))]),
+                    )]),
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn nested_key_conflicts_follow_duplicate_keys() {
+        let json = r#"{"a": 1, "a.b": 2}"#;
+        let decode = |duplicate_keys| {
+            value::decode(
+                json,
+                &DecodeOptions {
+                    nest_keys: crate::keys::Affix::new(".").unwrap(),
+                    duplicate_keys,
+                    ..Default::default()
+                },
+            )
+        };
+        assert_eq!(
+            decode(DuplicateKeys::Last).unwrap().get("a"),
+            Some(&Value::Object(vec![("b".to_string(), Value::Int(2))]))
+        );
+        assert_eq!(
+            decode(DuplicateKeys::First).unwrap().get("a"),
+            Some(&Value::Int(1))
+        );
+        assert!(decode(DuplicateKeys::Error).is_err());
+    }
+
+    #[test]
+    fn duplicate_keys_are_found_after_renaming() {
+        let json = r#"{"user_id": 1, "userId": 2}"#;
+        let decode = |duplicate_keys| {
+            value::decode(
+                json,
+                &DecodeOptions {
+                    keys: KeyTransform::Camel,
+                    duplicate_keys,
+                    ..Default::default()
+                },
+            )
+        };
+        assert_eq!(
+            decode(DuplicateKeys::First).unwrap(),
+            Value::Object(vec![("userId".to_string(), Value::Int(1))])
+        );
+        assert!(decode(DuplicateKeys::Error).is_err());
+    }
+
+    #[test]
+    fn control_chars_apply_to_keys_and_values() {
+        let json = r#"{"a\u0001b": ["x\u0007y\tz"]}"#;
+        let decode = |control_chars| {
+            value::decode(
+                json,
+                &DecodeOptions {
+                    control_chars,
+                    ..Default::default()
+                },
+            )
+        };
+        assert_eq!(
+            decode(ControlChars::Keep).unwrap(),
+            Value::Object(vec![(
+                "a\u{1}b".to_string(),
+                Value::Array(vec![Value::String("x\u{7}y\tz".to_string())]),
+            )])
+        );
+        // Tabs, line feeds and carriage returns are not affected
+        assert_eq!(
+            decode(ControlChars::Strip).unwrap(),
+            Value::Object(vec![(
+                "ab".to_string(),
+                Value::Array(vec![Value::String("xy\tz".to_string())]),
+            )])
+        );
+
+        let (_, context) = decode(ControlChars::Error).unwrap_err().into_parts();
+        assert_eq!(context.unwrap().pointer.as_deref(), Some("/a\u{1}b"));
+        let options = DecodeOptions {
+            control_chars: ControlChars::Error,
+            ..Default::default()
+        };
+        let err = value::decode(r#"{"list": ["ok", "bad\u0001"]}"#, &options).unwrap_err();
+        assert_eq!(
+            err.into_parts().1.unwrap().pointer.as_deref(),
+            Some("/list/1")
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Opt-in collection of lossy-conversion warnings.
+//!
+//! Usage:
+//! ```php
+//! $data = Sift::decode($json, ['warnings' => true]);
+//! foreach (Sift::lastWarnings() as $w) {
+//!     error_log("{$w['kind']} at " . ($w['pointer'] ?? "byte {$w['offset']}"));
+//! }
+//! ```
+//!
+//! Warnings belong to a call: the first decode step of a call (reading
+//! the input text or starting a conversion) drops those of earlier calls,
+//! so a call that fails or decodes without warnings leaves none behind.
+
+use bumpalo::collections::{String as BumpString, Vec as BumpVec};
+use bumpalo::Bump;
+use std::cell::{Cell, RefCell};
+use std::fmt::Write;
+
+thread_local! {
+    /// Warnings from the most recent decode call (request-scoped, one per PHP thread).
+    static LAST_WARNINGS: RefCell<Vec<Warning>> = const { RefCell::new(Vec::new()) };
+
+    /// Whether a call started since its first decode step.
+    static NEW_CALL: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Kind of lossy conversion applied to a value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Integer literal outside PHP's int range, decoded as float.
+    BigIntToFloat,
+    /// Number literal with more significant digits than a double can hold,
+    /// or too large to be represented at all (decoded as INF).
+    FloatPrecision,
+    /// Malformed UTF-8 in the input, replaced with U+FFFD or dropped under
+    /// the `utf8` option.
+    Utf8Substituted,
+    /// Lone surrogate or malformed `\u` escape replaced with U+FFFD under
+    /// the `surrogates` option.
+    SurrogateReplaced,
+}
+
+impl WarningKind {
+    /// Stable name exposed to PHP.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WarningKind::BigIntToFloat => "bigint_to_float",
+            WarningKind::FloatPrecision => "float_precision",
+            WarningKind::Utf8Substituted => "utf8_substituted",
+            WarningKind::SurrogateReplaced => "surrogate_replaced",
+        }
+    }
+}
+
+/// A single lossy conversion, located by JSON pointer, or by byte offset
+/// for repairs to the input text.
+#[derive(Clone, Debug)]
+pub struct Warning {
+    pub pointer: Option<String>,
+    /// Offset in the input of a UTF-8 repair, or in the repaired text of a
+    /// replaced escape
+    pub offset: Option<usize>,
+    pub kind: WarningKind,
+}
+
+impl Warning {
+    /// A repair to the input text at byte `offset`.
+    pub fn at_offset(kind: WarningKind, offset: usize) -> Self {
+        Self {
+            pointer: None,
+            offset: Some(offset),
+            kind,
+        }
+    }
+}
+
+/// Collector threaded through a conversion. Tracks the pointer of the value
+/// currently being converted so warnings and errors can say where they
+/// happened. When disabled, every method is a no-op so the hot path pays
+/// nothing.
+///
+/// The current pointer lives in a single arena-backed buffer that is
+/// truncated on the way back up, so descending into a node costs no malloc.
+pub struct Diagnostics<'a> {
+    /// Whether warnings are recorded
+    enabled: bool,
+    /// Whether the current pointer is tracked; always with `enabled`
+    tracking: bool,
+    /// Escaped JSON pointer of the current value, e.g. "/users/0/a~1b".
+    path: BumpString<'a>,
+    /// Length of `path` before each segment was pushed.
+    marks: BumpVec<'a, usize>,
+    warnings: Vec<Warning>,
+}
+
+impl<'a> Diagnostics<'a> {
+    /// A collector recording warnings if `enabled`, and tracking the
+    /// current pointer for them or, with `tracking`, for errors.
+    pub fn new(enabled: bool, tracking: bool, bump: &'a Bump) -> Self {
+        start_decode();
+        Self {
+            enabled,
+            tracking: tracking || enabled,
+            path: BumpString::new_in(bump),
+            marks: BumpVec::new_in(bump),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Descend into an object member.
+    pub fn push_key(&mut self, key: &str) {
+        if self.tracking {
+            self.marks.push(self.path.len());
+            self.path.push('/');
+            for c in key.chars() {
+                match c {
+                    '~' => self.path.push_str("~0"),
+                    '/' => self.path.push_str("~1"),
+                    c => self.path.push(c),
+                }
+            }
+        }
+    }
+
+    /// Descend into an array element.
+    pub fn push_index(&mut self, idx: usize) {
+        if self.tracking {
+            self.marks.push(self.path.len());
+            // Writing into a bump String can't fail
+            let _ = write!(self.path, "/{}", idx);
+        }
+    }
+
+    /// Return to the parent container.
+    pub fn pop(&mut self) {
+        if let Some(mark) = self.marks.pop() {
+            self.path.truncate(mark);
+        }
+    }
+
+    /// The pointer of the value being converted; empty unless tracking.
+    pub fn pointer(&self) -> &str {
+        self.path.as_str()
+    }
+
+    /// Record a lossy conversion at the current pointer.
+    pub fn warn(&mut self, kind: WarningKind) {
+        if self.enabled {
+            self.warnings.push(Warning {
+                pointer: Some(self.path.as_str().to_string()),
+                offset: None,
+                kind,
+            });
+        }
+    }
+
+    /// Make the collected warnings available to `Sift::lastWarnings()`,
+    /// after those recorded earlier in the same call.
+    pub fn publish(self) {
+        LAST_WARNINGS.with(|w| w.borrow_mut().extend(self.warnings));
+    }
+}
+
+/// Mark the start of a call (a PHP method, or `value::decode()`): its
+/// first decode step replaces the warnings of earlier calls.
+pub fn start_call() {
+    NEW_CALL.with(|new| new.set(true));
+}
+
+/// A decode step: forgets the warnings of earlier calls, once per call.
+pub fn start_decode() {
+    if NEW_CALL.with(|new| new.replace(false)) {
+        LAST_WARNINGS.with(|w| w.borrow_mut().clear());
+    }
+}
+
+/// Record warnings collected outside a `Diagnostics`, such as repairs to
+/// the input text, after those recorded earlier in the same call.
+pub fn record(warnings: Vec<Warning>) {
+    if !warnings.is_empty() {
+        LAST_WARNINGS.with(|w| w.borrow_mut().extend(warnings));
+    }
+}
+
+/// Warnings recorded by the most recent decode with diagnostics enabled.
+pub fn last_warnings() -> Vec<Warning> {
+    LAST_WARNINGS.with(|w| w.borrow().clone())
+}
@@ -0,0 +1,150 @@
+//! JSON text writing: string escaping shared with the extension's encoder,
+//! and the encoding of `value::Value`s.
+//!
+//! Output matches `json_encode($value, JSON_UNESCAPED_SLASHES |
+//! JSON_UNESCAPED_UNICODE | JSON_PRESERVE_ZERO_FRACTION)`, like the
+//! extension's: strings are written as UTF-8, and floats keep their
+//! fraction so they decode as floats.
+
+use crate::error::Error;
+use crate::number::{self, FloatNotation};
+use crate::parser::MAX_DEPTH;
+use crate::utf8;
+use crate::value::Value;
+use std::fmt::Write;
+
+/// Write a string, which must be UTF-8, as a JSON string.
+pub fn write_string(out: &mut String, bytes: &[u8]) -> Result<(), Error> {
+    let text = valid_text(bytes)?;
+
+    out.push('"');
+    let mut start = 0;
+    for (i, b) in text.bytes().enumerate() {
+        let escape = match b {
+            b'"' => "\\\"",
+            b'\\' => "\\\\",
+            b'\n' => "\\n",
+            b'\r' => "\\r",
+            b'\t' => "\\t",
+            0x08 => "\\b",
+            0x0c => "\\f",
+            0x00..=0x1f => "",
+            _ => continue,
+        };
+        out.push_str(&text[start..i]);
+        if escape.is_empty() {
+            let _ = write!(out, "\\u{:04x}", b);
+        } else {
+            out.push_str(escape);
+        }
+        start = i + 1;
+    }
+    out.push_str(&text[start..]);
+    out.push('"');
+    Ok(())
+}
+
+/// A string, which must be UTF-8, as a JSON string literal escaped by
+/// sonic-rs's SIMD serializer; without the quotes unless `quote`.
+/// Escapes match `write_string`.
+pub fn escape_string(bytes: &[u8], quote: bool) -> Result<String, Error> {
+    let text = valid_text(bytes)?;
+    let literal = sonic_rs::to_string(text)
+        .map_err(|e| Error::TypeError(format!("Failed to escape string: {}", e)))?;
+    if quote {
+        Ok(literal)
+    } else {
+        Ok(literal[1..literal.len() - 1].to_string())
+    }
+}
+
+/// `bytes` as text, or the error for its first malformed sequence.
+fn valid_text(bytes: &[u8]) -> Result<&str, Error> {
+    utf8::validate(bytes)
+        .map_err(|offset| Error::TypeError(format!("Malformed UTF-8 in string at byte {}", offset)))
+}
+
+/// Encodes `value` as JSON text. Exact numbers are written as their
+/// literal, unquoted; WTF-8 strings fail like any malformed UTF-8.
+pub fn encode(value: &Value) -> Result<String, Error> {
+    let mut out = String::new();
+    write_value(&mut out, value, 0)?;
+    Ok(out)
+}
+
+/// Internal: writes a value with depth tracking to prevent stack overflow.
+fn write_value(out: &mut String, value: &Value, depth: usize) -> Result<(), Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::TypeError(format!(
+            "Maximum nesting depth ({}) exceeded",
+            MAX_DEPTH
+        )));
+    }
+
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(true) => out.push_str("true"),
+        Value::Bool(false) => out.push_str("false"),
+        Value::Int(n) => {
+            let _ = write!(out, "{}", n);
+        }
+        Value::Float(n) => out.push_str(&number::format_float(*n, FloatNotation::Auto)?),
+        Value::String(s) => write_string(out, s.as_bytes())?,
+        Value::Bytes(b) => write_string(out, b)?,
+        Value::BigNumber(raw) => out.push_str(raw),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item, depth + 1)?;
+            }
+            out.push(']');
+        }
+        Value::Object(members) => {
+            out.push('{');
+            for (i, (name, item)) in members.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, name.as_bytes())?;
+                out.push(':');
+                write_value(out, item, depth + 1)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::DecodeOptions;
+    use crate::value;
+
+    #[test]
+    fn strings_escape_like_json_encode() {
+        let mut out = String::new();
+        write_string(&mut out, "a\"b\\c/d\n\u{1}é".as_bytes()).unwrap();
+        assert_eq!(out, r#""a\"b\\c/d\n\u0001é""#);
+        assert_eq!(escape_string(b"a\"b", false).unwrap(), r#"a\"b"#);
+        assert!(write_string(&mut out, b"\xff").is_err());
+    }
+
+    #[test]
+    fn decoded_values_encode_back_to_the_same_values() {
+        let json = r#"{"a": [1, -2.5, 1e20, "x\ty", null, true], "b": {"": 0.0}}"#;
+        let decoded = value::decode(json, &DecodeOptions::default()).unwrap();
+        let encoded = encode(&decoded).unwrap();
+        assert_eq!(
+            encoded,
+            r#"{"a":[1,-2.5,1.0e+20,"x\ty",null,true],"b":{"":0.0}}"#
+        );
+        assert_eq!(
+            value::decode(&encoded, &DecodeOptions::default()).unwrap(),
+            decoded
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Errors raised by the core, with structured context.
+//!
+//! The extension maps each variant onto its `SonicError` of the same
+//! name, so messages, codes and context reach PHP unchanged.
+
+use thiserror::Error;
+
+/// Errors that can occur during JSON operations.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("JSON parse error: {0}")]
+    ParseError(String),
+
+    #[error("Invalid JSON pointer: {0}")]
+    InvalidPointer(String),
+
+    #[error("Key not found: {0}")]
+    KeyNotFound(String),
+
+    #[error("Type conversion error: {0}")]
+    TypeError(String),
+
+    #[error("Invalid option: {0}")]
+    InvalidOption(String),
+
+    /// Strict-mode failure mirroring json_decode: carries the JSON_ERROR_*
+    /// code and message PHP's ext/json would report for the same input.
+    #[error("{1}")]
+    JsonError(i32, String),
+
+    /// The input exceeds the size limit; both values are in bytes.
+    #[error("Input size ({size} bytes) exceeds maximum allowed ({limit} bytes)")]
+    InputTooLarge { size: usize, limit: usize },
+
+    /// Any of the above with structured context attached; see
+    /// [`Error::at_pointer`] and friends.
+    #[error("{error}")]
+    WithContext {
+        error: Box<Error>,
+        context: ErrorContext,
+    },
+}
+
+/// Where and why an error happened, for programmatic handling.
+/// Every field is optional; errors fill in what they know.
+#[derive(Debug, Default, Clone)]
+pub struct ErrorContext {
+    /// JSON pointer of the value involved
+    pub pointer: Option<String>,
+    /// Byte offset into the input where parsing failed
+    pub offset: Option<usize>,
+    /// Type the caller asked for ("string", "integer", ...)
+    pub expected: Option<&'static str>,
+    /// Type actually found at the pointer
+    pub actual: Option<&'static str>,
+    /// Existing pointers close to a missing one
+    pub suggestions: Vec<String>,
+}
+
+impl Error {
+    /// Attaches the JSON pointer of the value involved.
+    pub fn at_pointer(self, pointer: impl Into<String>) -> Self {
+        let pointer = pointer.into();
+        self.with_context(|context| context.pointer = Some(pointer))
+    }
+
+    /// Attaches the byte offset where parsing failed.
+    pub fn at_offset(self, offset: usize) -> Self {
+        self.with_context(|context| context.offset = Some(offset))
+    }
+
+    /// The byte offset attached with `at_offset()`, if any.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::WithContext { context, .. } => context.offset,
+            _ => None,
+        }
+    }
+
+    /// Attaches the expected and actual JSON types of a failed extraction.
+    pub fn with_types(self, expected: &'static str, actual: &'static str) -> Self {
+        self.with_context(|context| {
+            context.expected = Some(expected);
+            context.actual = Some(actual);
+        })
+    }
+
+    /// Attaches pointers close to the one that wasn't found.
+    pub fn with_suggestions(self, suggestions: Vec<String>) -> Self {
+        self.with_context(|context| context.suggestions = suggestions)
+    }
+
+    fn with_context(self, update: impl FnOnce(&mut ErrorContext)) -> Self {
+        let (error, context) = self.into_parts();
+        let mut context = context.unwrap_or_default();
+        update(&mut context);
+        Error::WithContext {
+            error: Box::new(error),
+            context,
+        }
+    }
+
+    /// Splits off the context, if any, returning the underlying error.
+    pub fn into_parts(self) -> (Error, Option<ErrorContext>) {
+        match self {
+            Error::WithContext { error, context } => (*error, Some(context)),
+            error => (error, None),
+        }
+    }
+}
+
+impl From<sonic_rs::Error> for Error {
+    fn from(err: sonic_rs::Error) -> Self {
+        let offset = err.offset();
+        Error::ParseError(err.to_string()).at_offset(offset)
+    }
+}
+
+/// Errors that can name the JSON pointer they happened at. Core functions
+/// that run caller callbacks (`patch::replace_at`) are generic over the
+/// callback's error, and use this to attach the pointer to it.
+pub trait AtPointer {
+    /// Attaches the JSON pointer of the value involved.
+    fn at_pointer(self, pointer: &str) -> Self;
+}
+
+impl AtPointer for Error {
+    fn at_pointer(self, pointer: &str) -> Self {
+        Error::at_pointer(self, pointer)
+    }
+}
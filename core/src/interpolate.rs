@@ -0,0 +1,134 @@
+//! Placeholder substitution in decoded strings (the `interpolate` option).
+//!
+//! `${NAME}` and `{{ name }}` in string values are replaced by the
+//! variable's value; keys are left alone. Names are letters, digits and
+//! `_` (plus `.` and `-` between braces). A `$` or `{` that doesn't start
+//! a well-formed placeholder is kept as it is. A placeholder naming an
+//! undefined variable is an error, so a missing setting can't slip
+//! through as a literal.
+
+use crate::error::Error;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+thread_local! {
+    /// Variable maps given as the `interpolate` option, referenced by
+    /// index from `Interpolate::Map` so options stay `Copy`.
+    /// Request-scoped like the configured defaults.
+    static VARIABLES: RefCell<Vec<HashMap<String, String>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Where placeholder values come from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interpolate {
+    /// Strings are stored as they are.
+    #[default]
+    Off,
+    /// The process environment, as `getenv()` sees it.
+    Env,
+    /// A map of variables, by its index in the request's registry.
+    Map(usize),
+}
+
+impl Interpolate {
+    /// Placeholders resolved from `variables`, held for the rest of the
+    /// request (until `reset_variables()`).
+    pub fn from_variables(variables: HashMap<String, String>) -> Self {
+        Self::Map(register(variables))
+    }
+
+    /// Substitute the placeholders in `text`; borrowed when it has none.
+    /// Callers add the string's pointer to the error.
+    pub fn apply(self, text: &str) -> Result<Cow<'_, str>, Error> {
+        if self == Self::Off || !(text.contains("${") || text.contains("{{")) {
+            return Ok(Cow::Borrowed(text));
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some((start, end, name)) = next_placeholder(rest) {
+            out.push_str(&rest[..start]);
+            out.push_str(&self.lookup(name)?);
+            rest = &rest[end..];
+        }
+        if rest.len() == text.len() {
+            return Ok(Cow::Borrowed(text));
+        }
+        out.push_str(rest);
+        Ok(Cow::Owned(out))
+    }
+
+    /// The value of variable `name`.
+    fn lookup(self, name: &str) -> Result<String, Error> {
+        let value = match self {
+            Self::Off => None,
+            Self::Env => std::env::var(name).ok(),
+            Self::Map(index) => VARIABLES.with(|variables| {
+                variables
+                    .borrow()
+                    .get(index)
+                    .map(|map| map.get(name).cloned())
+                    .ok_or_else(|| {
+                        // Options holding variables don't outlive the request
+                        Error::InvalidOption(
+                            "Interpolation variables are no longer available".to_string(),
+                        )
+                    })
+            })?,
+        };
+        value
+            .ok_or_else(|| Error::KeyNotFound(format!("Undefined placeholder variable '{}'", name)))
+    }
+}
+
+/// The first well-formed placeholder in `text`: its byte range and the
+/// variable name it holds.
+fn next_placeholder(text: &str) -> Option<(usize, usize, &str)> {
+    let bytes = text.as_bytes();
+    let mut pos = 0;
+    while pos + 1 < bytes.len() {
+        let found = match &bytes[pos..pos + 2] {
+            b"${" => text[pos + 2..]
+                .find('}')
+                .map(|len| (pos + 2 + len + 1, &text[pos + 2..pos + 2 + len]))
+                .filter(|&(_, name)| is_name(name, false)),
+            b"{{" => text[pos + 2..]
+                .find("}}")
+                .map(|len| (pos + 2 + len + 2, text[pos + 2..pos + 2 + len].trim()))
+                .filter(|&(_, name)| is_name(name, true)),
+            _ => None,
+        };
+        if let Some((end, name)) = found {
+            return Some((pos, end, name));
+        }
+        pos += 1;
+    }
+    None
+}
+
+/// Whether `name` is a valid variable name; `dotted` also allows `.`
+/// and `-`, as in `{{ app.name }}`.
+fn is_name(name: &str, dotted: bool) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || (dotted && (b == b'.' || b == b'-')))
+}
+
+/// Store `variables` for the rest of the request, reusing the slot of an
+/// equal map so variables passed on every call are held once.
+fn register(variables: HashMap<String, String>) -> usize {
+    VARIABLES.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        if let Some(index) = registry.iter().position(|v| *v == variables) {
+            return index;
+        }
+        registry.push(variables);
+        registry.len() - 1
+    })
+}
+
+/// Drops the registered variable maps; runs at request shutdown.
+pub fn reset_variables() {
+    VARIABLES.with(|registry| registry.borrow_mut().clear());
+}
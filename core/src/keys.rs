@@ -0,0 +1,140 @@
+//! Object key renaming during conversion (the `keys`, `strip_prefix` and
+//! `nest_keys` options).
+//!
+//! Callback renaming runs code of the host: `KeyTransform::Callback` only
+//! names a callback, and the conversion asks its sink to run it.
+
+use std::borrow::Cow;
+
+/// Longest accepted `strip_prefix` or `nest_keys` string, in bytes.
+pub const MAX_AFFIX_LEN: usize = 32;
+
+/// A short string option held inline so options stay `Copy`; empty
+/// means unset.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Affix {
+    bytes: [u8; MAX_AFFIX_LEN],
+    len: u8,
+}
+
+impl Affix {
+    /// `text` as an option value, if it is 1 to `MAX_AFFIX_LEN` bytes.
+    pub fn new(text: &str) -> Option<Self> {
+        if !(1..=MAX_AFFIX_LEN).contains(&text.len()) {
+            return None;
+        }
+        let mut affix = Self::default();
+        affix.bytes[..text.len()].copy_from_slice(text.as_bytes());
+        affix.len = text.len() as u8;
+        Some(affix)
+    }
+
+    /// The string; empty when unset.
+    pub fn as_str(&self) -> &str {
+        // SAFETY: only ever filled from a whole `&str` in new()
+        unsafe { std::str::from_utf8_unchecked(&self.bytes[..self.len as usize]) }
+    }
+
+    /// Whether the option is unset.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// `key` without this prefix. Keys that don't start with it, or
+    /// consist only of it, are left alone.
+    pub fn strip_from<'k>(&self, key: &'k str) -> &'k str {
+        match key.strip_prefix(self.as_str()) {
+            Some(rest) if !rest.is_empty() => rest,
+            _ => key,
+        }
+    }
+}
+
+impl std::fmt::Debug for Affix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+/// How object keys are renamed before they are stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyTransform {
+    /// Keys are stored as they are.
+    #[default]
+    Keep,
+    /// `first_name` and `first-name` become `firstName`.
+    Camel,
+    /// `firstName` and `first-name` become `first_name`.
+    Snake,
+    /// A callback of the host, by its index in the host's registry.
+    Callback(usize),
+}
+
+impl KeyTransform {
+    /// The key to store `key` under; borrowed when it doesn't change.
+    /// None for `Callback`, which only the host can run.
+    pub fn apply(self, key: &str) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Keep => Some(Cow::Borrowed(key)),
+            Self::Camel => Some(camel_case(key)),
+            Self::Snake => Some(snake_case(key)),
+            Self::Callback(_) => None,
+        }
+    }
+}
+
+/// `snake_case` or `kebab-case` to `camelCase`. A separator is dropped
+/// and the next character upper-cased; leading and trailing separators
+/// (`_id`, `__typename`) are kept.
+fn camel_case(key: &str) -> Cow<'_, str> {
+    let trimmed = key.trim_start_matches(['_', '-']);
+    if !trimmed.trim_end_matches(['_', '-']).contains(['_', '-']) {
+        return Cow::Borrowed(key);
+    }
+
+    let body = trimmed.trim_end_matches(['_', '-']);
+    let mut out = String::with_capacity(key.len());
+    out.push_str(&key[..key.len() - trimmed.len()]);
+    let mut upper = false;
+    for c in body.chars() {
+        if c == '_' || c == '-' {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out.push_str(&trimmed[body.len()..]);
+    Cow::Owned(out)
+}
+
+/// `camelCase`, `PascalCase` or `kebab-case` to `snake_case`. Acronyms
+/// stay together: `userID` becomes `user_id`, `HTTPServer` `http_server`.
+fn snake_case(key: &str) -> Cow<'_, str> {
+    if !key.contains(|c: char| c.is_ascii_uppercase() || c == '-') {
+        return Cow::Borrowed(key);
+    }
+
+    let chars: Vec<char> = key.chars().collect();
+    let mut out = String::with_capacity(key.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '-' {
+            out.push('_');
+            continue;
+        }
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            let boundary = prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_lower);
+            if boundary && !out.ends_with('_') {
+                out.push('_');
+            }
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    Cow::Owned(out)
+}
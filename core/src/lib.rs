@@ -0,0 +1,30 @@
+//! Sift core: the PHP-free JSON engine behind the Sift extension.
+//!
+//! Everything here works on Rust strings and sonic-rs values: input limits
+//! and text policies, pointer resolution, path resolution with its prefix
+//! cache, byte-preserving patches, the option set that drives them, and
+//! the conversion walk, which builds values through a `ValueSink`.
+//! The extension (`sonic_php`) adds the PHP side: reading options from PHP
+//! arrays, building zvals, classes and exceptions. Keeping ext-php-rs out
+//! of this crate lets it be unit-tested and fuzzed without a PHP build.
+
+pub mod arena;
+pub mod convert;
+pub mod diagnostics;
+pub mod encode;
+pub mod error;
+pub mod interpolate;
+pub mod keys;
+pub mod number;
+pub mod options;
+pub mod parser;
+pub mod patch;
+pub mod precision;
+pub mod query;
+pub mod sink;
+pub mod suggest;
+pub mod surrogates;
+pub mod utf8;
+pub mod value;
+
+pub use error::{AtPointer, Error, ErrorContext};
@@ -0,0 +1,74 @@
+//! Locale-independent number formatting.
+//!
+//! Rust's float formatting and parsing never consult the C locale, so
+//! `setlocale(LC_ALL, 'de_DE')` can't turn `1.5` into `1,5` here the way it
+//! can in extensions built on `printf`/`strtod`. Everything that writes
+//! numbers as JSON text goes through this module.
+
+use crate::error::Error;
+
+/// Notation used when writing floats as JSON text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FloatNotation {
+    /// Decimal for magnitudes in [1e-4, 1e15), exponent outside it.
+    #[default]
+    Auto,
+    /// Always plain decimal digits: `1e25` is written out in full.
+    Decimal,
+    /// Always `d.ddde±x`.
+    Exponent,
+}
+
+/// Magnitudes from which `Auto` switches between notations.
+const AUTO_DECIMAL_MIN: f64 = 1e-4;
+const AUTO_DECIMAL_MAX: f64 = 1e15;
+
+impl FloatNotation {
+    /// Parse a notation name as accepted from PHP.
+    pub fn from_name(name: &str) -> Result<Self, Error> {
+        match name {
+            "auto" => Ok(Self::Auto),
+            "decimal" => Ok(Self::Decimal),
+            "exponent" => Ok(Self::Exponent),
+            _ => Err(Error::InvalidOption(format!(
+                "Float notation must be one of 'auto', 'decimal', 'exponent', got '{}'",
+                name
+            ))),
+        }
+    }
+}
+
+/// Formats a float as a JSON number with the shortest digits that parse
+/// back to the same value. The result always contains a '.' (and "e" in
+/// exponent form), so it decodes as a float rather than an int.
+/// NaN and infinities have no JSON representation and are rejected.
+pub fn format_float(n: f64, notation: FloatNotation) -> Result<String, Error> {
+    if !n.is_finite() {
+        return Err(Error::TypeError(format!(
+            "{} cannot be represented in JSON",
+            n
+        )));
+    }
+
+    let exponent = match notation {
+        FloatNotation::Auto => n != 0.0 && !(AUTO_DECIMAL_MIN..AUTO_DECIMAL_MAX).contains(&n.abs()),
+        FloatNotation::Decimal => false,
+        FloatNotation::Exponent => true,
+    };
+
+    if !exponent {
+        // Display never uses exponent notation and prints the shortest round-trip digits
+        let mut out = format!("{}", n);
+        if !out.contains('.') {
+            out.push_str(".0");
+        }
+        return Ok(out);
+    }
+
+    // LowerExp prints e.g. "1.5e3" / "1e-7"; normalize to "1.5e+3" / "1.0e-7"
+    let formatted = format!("{:e}", n);
+    let (mantissa, exp) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let dot = if mantissa.contains('.') { "" } else { ".0" };
+    let sign = if exp.starts_with('-') { "" } else { "+" };
+    Ok(format!("{}{}e{}{}", mantissa, dot, sign, exp))
+}
@@ -0,0 +1,227 @@
+//! Decode options: what a conversion produces and how it treats
+//! malformed or ambiguous input.
+//!
+//! The extension reads them from PHP option arrays (`['strict' => true]`);
+//! each field documents the option it comes from.
+
+use crate::diagnostics::WarningKind;
+use crate::interpolate::Interpolate;
+use crate::keys::{Affix, KeyTransform};
+use crate::parser::MAX_DEPTH;
+use crate::precision::NumberPolicies;
+
+/// How a full decode walks the document.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Pick per document from its size (see `parser::prefers_lazy`).
+    #[default]
+    Auto,
+    /// Parse into a `sonic_rs::Value` DOM, then convert.
+    Dom,
+    /// Convert straight from the input through lazy iteration.
+    Lazy,
+}
+
+/// What number literals a double can't hold exactly decode to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BigNumbers {
+    /// A (lossy) float, like json_decode.
+    #[default]
+    Float,
+    /// A `BCMath\Number` (PHP 8.4+), exact for integers and decimals.
+    BcMath,
+    /// A GMP object for integers beyond PHP's int range; decimals stay floats.
+    Gmp,
+}
+
+impl BigNumbers {
+    /// Whether a number literal a double can't hold, lossy as `kind`,
+    /// decodes to an exact number rather than a float.
+    pub fn holds(self, kind: WarningKind) -> bool {
+        match (self, kind) {
+            (BigNumbers::Float, _) => false,
+            (BigNumbers::BcMath, _) => true,
+            (BigNumbers::Gmp, kind) => kind == WarningKind::BigIntToFloat,
+        }
+    }
+}
+
+/// Largest accepted `depth` option. Conversion recurses once per level,
+/// so this bounds the native stack it can use.
+pub const MAX_DEPTH_LIMIT: usize = 2048;
+
+/// What malformed UTF-8 in the input turns into.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Utf8Policy {
+    /// Reject the input.
+    #[default]
+    Error,
+    /// Drop the malformed bytes (JSON_INVALID_UTF8_IGNORE).
+    Ignore,
+    /// Replace each malformed sequence with U+FFFD (JSON_INVALID_UTF8_SUBSTITUTE).
+    Substitute,
+}
+
+/// Which value an object keeps when a key repeats.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// The last one, like json_decode.
+    #[default]
+    Last,
+    /// The first one; later values are skipped without being converted.
+    First,
+    /// Reject the document.
+    Error,
+}
+
+/// What happens to control characters in decoded strings and keys:
+/// U+0000 to U+001F other than tab, line feed and carriage return, and
+/// U+007F. JSON can carry them escaped (`\u0000`); PHP strings hold them,
+/// but C APIs downstream stop at NUL.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ControlChars {
+    /// Keep them, like json_decode.
+    #[default]
+    Keep,
+    /// Remove them.
+    Strip,
+    /// Reject the document, naming the string's pointer.
+    Error,
+}
+
+/// What lone UTF-16 surrogates (`"\ud800"`) and malformed `\u` escapes
+/// in strings turn into.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Surrogates {
+    /// Reject the document, like json_decode (JSON_ERROR_UTF16).
+    #[default]
+    Error,
+    /// Replace each with U+FFFD.
+    Replace,
+    /// Pass lone surrogates through as their WTF-8 bytes in string values;
+    /// malformed escapes, and surrogates in keys, become U+FFFD.
+    Wtf8,
+}
+
+/// What containers beyond `Query::value($maxDepth)`'s limit become.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Leaves {
+    /// A Query over the container, to be hydrated on demand.
+    #[default]
+    Query,
+    /// The container's raw JSON text.
+    Raw,
+}
+
+/// Options controlling how JSON is converted into values.
+#[derive(Clone, Copy, Debug)]
+pub struct DecodeOptions {
+    /// Produce exactly what `json_decode($json, true, 512, JSON_THROW_ON_ERROR)`
+    /// would, including its error codes. See docs/COMPATIBILITY.md.
+    pub strict: bool,
+    /// Record lossy conversions for `Sift::lastWarnings()`.
+    pub warnings: bool,
+    /// Count each container's elements before converting it, so its hashtable
+    /// is allocated once instead of growing. Only affects the lazy path; the
+    /// DOM decode always knows its lengths.
+    pub presize: bool,
+    /// Decode strategy; `Auto` unless overridden with `'strategy' => 'dom'|'lazy'`.
+    /// Options only the lazy path implements always use it (`needs_lazy`).
+    pub strategy: Strategy,
+    /// Exact representation for numbers a double would round.
+    pub big_numbers: BigNumbers,
+    /// Decode objects as associative arrays; `false` gives `stdClass`
+    /// objects like `json_decode($json, false)`.
+    pub assoc: bool,
+    /// Decode integers outside PHP's int range as numeric strings
+    /// (`'bigint' => 'string'`, like JSON_BIGINT_AS_STRING). Takes
+    /// precedence over `big_numbers` for integers.
+    pub bigint_as_string: bool,
+    /// Maximum container nesting; `MAX_DEPTH` unless overridden.
+    pub depth: usize,
+    /// Handling of malformed UTF-8 in the input.
+    pub utf8: Utf8Policy,
+    /// Handling of repeated object keys.
+    pub duplicate_keys: DuplicateKeys,
+    /// Renaming of object keys (`'keys' => 'camel'|'snake'|callable`).
+    pub keys: KeyTransform,
+    /// Prefix removed from object keys before any other renaming.
+    pub strip_prefix: Affix,
+    /// Separator that splits object keys into nested arrays
+    /// (`"user.name"` becomes `['user' => ['name' => ...]]`).
+    pub nest_keys: Affix,
+    /// Handling of control characters in decoded strings and keys.
+    pub control_chars: ControlChars,
+    /// Handling of lone surrogates and malformed `\u` escapes.
+    pub surrogates: Surrogates,
+    /// Placeholder substitution in string values.
+    pub interpolate: Interpolate,
+    /// Per-pointer string or rounded decoding of numbers.
+    pub numbers: NumberPolicies,
+    /// Nesting depth from which containers are left unconverted, and what
+    /// they become instead. Set by `Query::value($maxDepth)`; not an option.
+    pub cutoff: Option<(usize, Leaves)>,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            warnings: false,
+            presize: false,
+            strategy: Strategy::default(),
+            big_numbers: BigNumbers::default(),
+            assoc: true,
+            bigint_as_string: false,
+            depth: MAX_DEPTH,
+            utf8: Utf8Policy::default(),
+            duplicate_keys: DuplicateKeys::default(),
+            keys: KeyTransform::default(),
+            strip_prefix: Affix::default(),
+            nest_keys: Affix::default(),
+            control_chars: ControlChars::default(),
+            surrogates: Surrogates::default(),
+            interpolate: Interpolate::default(),
+            numbers: NumberPolicies::default(),
+            cutoff: None,
+        }
+    }
+}
+
+impl DecodeOptions {
+    /// Whether conversion needs the raw number text the DOM discards,
+    /// which forces the lazy path.
+    pub fn needs_raw_numbers(&self) -> bool {
+        self.strict
+            || self.warnings
+            || self.big_numbers != BigNumbers::Float
+            || self.bigint_as_string
+            || self.numbers != NumberPolicies::Off
+    }
+
+    /// Whether conversion needs something only the lazy path implements:
+    /// raw number text, objects, a non-default depth or duplicate-key
+    /// policy, key renaming or nesting, or string rewriting. The DOM
+    /// conversion knows none of these.
+    pub fn needs_lazy(&self) -> bool {
+        self.needs_raw_numbers()
+            || !self.assoc
+            || self.depth != MAX_DEPTH
+            || self.duplicate_keys != DuplicateKeys::Last
+            || self.keys != KeyTransform::Keep
+            || !self.strip_prefix.is_empty()
+            || !self.nest_keys.is_empty()
+            || self.control_chars != ControlChars::Keep
+            || self.surrogates == Surrogates::Wtf8
+            || self.interpolate != Interpolate::Off
+    }
+
+    /// Whether conversion must track the pointer of the current value,
+    /// for warnings or errors that name it, or for per-pointer policies.
+    pub fn needs_pointers(&self) -> bool {
+        self.warnings
+            || self.control_chars == ControlChars::Error
+            || self.interpolate != Interpolate::Off
+            || self.numbers != NumberPolicies::Off
+    }
+}
@@ -0,0 +1,788 @@
+//! Input limits, text policies and pointer resolution shared by every
+//! decode path. Nothing here builds values; the extension converts what
+//! these functions find into PHP values.
+
+use crate::arena;
+use crate::diagnostics::{self, Diagnostics, Warning, WarningKind};
+use crate::error::Error;
+use crate::options::{ControlChars, DecodeOptions, Strategy, Surrogates, Utf8Policy};
+use crate::suggest;
+use crate::surrogates::{self, Problem};
+use crate::utf8;
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+use faststr::FastStr;
+use sonic_rs::error::Category;
+use sonic_rs::{to_array_iter_unchecked, to_object_iter_unchecked};
+use sonic_rs::{JsonValueTrait, LazyValue, PointerNode, Value};
+use std::borrow::Cow;
+
+/// Maximum allowed nesting depth to prevent stack overflow.
+/// PHP's default json_decode limit is 512. The `depth` option overrides it
+/// on the lazy path.
+pub const MAX_DEPTH: usize = 512;
+
+/// Maximum allowed JSON input size (64 MB).
+pub const MAX_INPUT_SIZE: usize = 64 * 1024 * 1024;
+
+/// Maximum allowed pointer segments to prevent DoS.
+pub const MAX_POINTER_SEGMENTS: usize = 256;
+
+/// json_decode error codes (JSON_ERROR_* constants) reported in strict mode.
+pub const JSON_ERROR_DEPTH: i32 = 1;
+pub const JSON_ERROR_CTRL_CHAR: i32 = 3;
+pub const JSON_ERROR_SYNTAX: i32 = 4;
+pub const JSON_ERROR_UTF8: i32 = 5;
+pub const JSON_ERROR_INVALID_PROPERTY_NAME: i32 = 9;
+pub const JSON_ERROR_UTF16: i32 = 10;
+
+/// Rejects inputs over `MAX_INPUT_SIZE` before any parsing work.
+pub fn check_input_size(json: impl AsRef<[u8]>) -> Result<(), Error> {
+    let size = json.as_ref().len();
+    if size > MAX_INPUT_SIZE {
+        return Err(Error::InputTooLarge {
+            size,
+            limit: MAX_INPUT_SIZE,
+        });
+    }
+    Ok(())
+}
+
+/// Input text with the warnings about its repairs. They are returned
+/// rather than recorded because text may be read on a worker thread;
+/// whoever reports the call's warnings publishes them.
+#[derive(Debug)]
+pub struct Text<'a> {
+    pub text: Cow<'a, str>,
+    /// Repairs under `options.warnings`, located by byte offset
+    pub warnings: Vec<Warning>,
+}
+
+impl<'a> Text<'a> {
+    /// Records the warnings for the current call and returns the text.
+    pub fn publish(self) -> Cow<'a, str> {
+        diagnostics::record(self.warnings);
+        self.text
+    }
+}
+
+/// Reads input bytes as JSON text under `options.utf8` and
+/// `options.surrogates`. Valid UTF-8 is borrowed as is; malformed sequences
+/// are rejected, dropped, or replaced with U+FFFD. Lone surrogates are
+/// replaced with U+FFFD under both `'replace'` and `'wtf8'`, since the
+/// text may be handed out as Rust strings; see `decode_text`. Each repair
+/// is warned about under `options.warnings`.
+pub fn input_text<'a>(bytes: &'a [u8], options: &DecodeOptions) -> Result<Text<'a>, Error> {
+    let policy = match options.surrogates {
+        Surrogates::Wtf8 => Surrogates::Replace,
+        policy => policy,
+    };
+    let mut warnings = Vec::new();
+    let text = utf8_text(bytes, options, &mut warnings)?;
+    let text = rewrite_escapes(text, policy, options.warnings, &mut warnings)?;
+    Ok(Text { text, warnings })
+}
+
+/// Like `input_text`, but keeps `'surrogates' => 'wtf8'`: lone surrogates
+/// become stand-ins that only the lazy conversion turns into WTF-8 bytes,
+/// so the text must go nowhere else.
+pub fn decode_text<'a>(bytes: &'a [u8], options: &DecodeOptions) -> Result<Text<'a>, Error> {
+    let mut warnings = Vec::new();
+    let text = utf8_text(bytes, options, &mut warnings)?;
+    let text = rewrite_escapes(text, options.surrogates, options.warnings, &mut warnings)?;
+    Ok(Text { text, warnings })
+}
+
+/// Applies a `surrogates` policy to input text, keeping it borrowed when
+/// nothing changes, and adds a warning for each replaced escape if `warn`.
+fn rewrite_escapes<'a>(
+    text: Cow<'a, str>,
+    policy: Surrogates,
+    warn: bool,
+    warnings: &mut Vec<Warning>,
+) -> Result<Cow<'a, str>, Error> {
+    let replaced = |offset| {
+        if warn {
+            warnings.push(Warning::at_offset(WarningKind::SurrogateReplaced, offset));
+        }
+    };
+    match text {
+        Cow::Borrowed(text) => surrogates::rewrite(text, policy, replaced),
+        Cow::Owned(text) => {
+            let rewritten = match surrogates::rewrite(&text, policy, replaced)? {
+                Cow::Owned(rewritten) => Some(rewritten),
+                Cow::Borrowed(_) => None,
+            };
+            Ok(Cow::Owned(rewritten.unwrap_or(text)))
+        }
+    }
+}
+
+/// Input bytes as text under `options.utf8`, adding a warning for each
+/// repair under `options.warnings`.
+fn utf8_text<'a>(
+    bytes: &'a [u8],
+    options: &DecodeOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Cow<'a, str>, Error> {
+    let valid_up_to = match utf8::validate(bytes) {
+        Ok(text) => return Ok(Cow::Borrowed(text)),
+        Err(offset) => offset,
+    };
+    let substitute = match options.utf8 {
+        Utf8Policy::Error if options.strict => {
+            return Err(Error::JsonError(
+                JSON_ERROR_UTF8,
+                "Malformed UTF-8 characters, possibly incorrectly encoded".to_string(),
+            ))
+        }
+        Utf8Policy::Error => {
+            return Err(
+                Error::ParseError("Malformed UTF-8 in input".to_string()).at_offset(valid_up_to)
+            )
+        }
+        Utf8Policy::Ignore => false,
+        Utf8Policy::Substitute => true,
+    };
+    // Don't copy input the size limit would reject anyway
+    check_input_size(bytes)?;
+    let mut text = String::with_capacity(bytes.len());
+    let mut offset = 0;
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        offset += chunk.valid().len();
+        if chunk.invalid().is_empty() {
+            continue;
+        }
+        if options.warnings {
+            warnings.push(Warning::at_offset(WarningKind::Utf8Substituted, offset));
+        }
+        if substitute {
+            text.push(char::REPLACEMENT_CHARACTER);
+        }
+        offset += chunk.invalid().len();
+    }
+    Ok(Cow::Owned(text))
+}
+
+/// Whether `c` falls under the `control_chars` option: C0 controls other
+/// than tab, line feed and carriage return, and DEL.
+fn is_control_char(c: char) -> bool {
+    matches!(c, '\0'..='\x08' | '\x0b' | '\x0c' | '\x0e'..='\x1f' | '\x7f')
+}
+
+/// Applies the `control_chars` option to a decoded string; strings
+/// without control characters are returned as they are. Callers add the
+/// string's pointer to the error.
+pub fn control_chars<'s>(text: &'s str, options: &DecodeOptions) -> Result<Cow<'s, str>, Error> {
+    if options.control_chars == ControlChars::Keep {
+        return Ok(Cow::Borrowed(text));
+    }
+    let Some(found) = text.chars().find(|&c| is_control_char(c)) else {
+        return Ok(Cow::Borrowed(text));
+    };
+    match options.control_chars {
+        ControlChars::Error => Err(Error::ParseError(format!(
+            "String contains control character U+{:04X}",
+            found as u32
+        ))),
+        _ => Ok(Cow::Owned(
+            text.chars().filter(|&c| !is_control_char(c)).collect(),
+        )),
+    }
+}
+
+/// Applies the `control_chars` option to an object key, and replaces the
+/// stand-ins of `'surrogates' => 'wtf8'`, since keys must be UTF-8. An
+/// error names the member's pointer.
+pub fn clean_key<'s>(
+    key: &'s str,
+    options: &DecodeOptions,
+    diagnostics: &mut Diagnostics<'_>,
+) -> Result<Cow<'s, str>, Error> {
+    diagnostics.push_key(key);
+    let cleaned = control_chars(key, options).map_err(|e| e.at_pointer(diagnostics.pointer()));
+    diagnostics.pop();
+    let cleaned = cleaned?;
+    if options.surrogates != Surrogates::Wtf8 {
+        return Ok(cleaned);
+    }
+    let replaced = match surrogates::replace_stand_ins(&cleaned) {
+        Cow::Owned(replaced) => Some(replaced),
+        Cow::Borrowed(_) => None,
+    };
+    Ok(replaced.map_or(cleaned, Cow::Owned))
+}
+
+/// Maps an error raised while iterating a container, whose raw text is
+/// `json`, to an `Error`.
+pub fn iteration_error(err: sonic_rs::Error, json: &str, options: &DecodeOptions) -> Error {
+    if options.strict {
+        strict_error(&err, json)
+    } else {
+        Error::from(err)
+    }
+}
+
+/// Maps a sonic-rs error raised on `json` to the JSON_ERROR_* code and
+/// message json_decode reports for the same input. sonic-rs keeps its
+/// error codes private, so past its category the cause is read from the
+/// string literal at the error's offset.
+pub fn strict_error(err: &sonic_rs::Error, json: &str) -> Error {
+    let code = match err.classify() {
+        Category::Syntax => {
+            string_around(json, err.offset()).map_or(JSON_ERROR_SYNTAX, string_error)
+        }
+        _ => JSON_ERROR_SYNTAX,
+    };
+    let message = match code {
+        JSON_ERROR_UTF16 => "Single unpaired UTF-16 surrogate in unicode escape",
+        JSON_ERROR_CTRL_CHAR => "Control character error, possibly incorrectly encoded",
+        _ => "Syntax error",
+    };
+    Error::JsonError(code, message.to_string())
+}
+
+/// The body of the string literal in `json` that starts at or contains
+/// `offset`. sonic-rs reports string errors at the opening quote, at the
+/// offending byte or just past the offending escape.
+fn string_around(json: &str, offset: usize) -> Option<&[u8]> {
+    let bytes = json.as_bytes();
+    let mut at = 0;
+    while let Some(found) = bytes.get(at..)?.iter().position(|&b| b == b'"') {
+        let start = at + found;
+        if start > offset {
+            return None;
+        }
+        let mut end = start + 1;
+        while end < bytes.len() && bytes[end] != b'"' {
+            end += if bytes[end] == b'\\' { 2 } else { 1 };
+        }
+        let end = end.min(bytes.len());
+        if offset <= end {
+            return Some(&bytes[start + 1..end]);
+        }
+        at = end + 1;
+    }
+    None
+}
+
+/// The JSON_ERROR_* code json_decode's scanner reports for a string
+/// literal body: the first raw control character, invalid escape or
+/// unpaired surrogate decides.
+fn string_error(body: &[u8]) -> i32 {
+    let mut at = 0;
+    while at < body.len() {
+        match body[at] {
+            0x00..=0x1f => return JSON_ERROR_CTRL_CHAR,
+            b'\\' => {
+                let escaped = body.get(at + 1);
+                if !matches!(
+                    escaped,
+                    Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' | b'u')
+                ) {
+                    return JSON_ERROR_SYNTAX;
+                }
+                match surrogates::inspect(body, at) {
+                    (_, Some(Problem::Lone(_))) => return JSON_ERROR_UTF16,
+                    (_, Some(Problem::Malformed)) => return JSON_ERROR_SYNTAX,
+                    (len, None) => at += len,
+                }
+            }
+            _ => at += 1,
+        }
+    }
+    JSON_ERROR_SYNTAX
+}
+
+/// Whether a full decode of `json` goes through the lazy iterator rather
+/// than the DOM: always for the options only the lazy path implements
+/// (`DecodeOptions::needs_lazy`), otherwise as `options.strategy` says.
+pub fn chooses_lazy(json: &str, options: &DecodeOptions) -> bool {
+    match options.strategy {
+        _ if options.needs_lazy() => true,
+        Strategy::Auto => prefers_lazy(json),
+        Strategy::Dom => false,
+        Strategy::Lazy => true,
+    }
+}
+
+/// Inputs from this size decode lazily under `Strategy::Auto`. The DOM
+/// is faster at every size and shape measured, by 3 to 13 times
+/// (`examples/strategies.rs`, results in docs/BENCHMARKS.md), but peaks at
+/// 10 to 17 times the input in memory against 6 to 13 for lazy conversion.
+/// From 16 MB that is 60 MB or more per call that PHP's `memory_limit`
+/// does not see, which is worth the time. Nesting depth changed neither
+/// ratio, so only the size is looked at.
+const LAZY_MIN_SIZE: usize = 16 * 1024 * 1024;
+
+/// Strategy heuristic for `Strategy::Auto`: whether this input should
+/// trade time for memory and go lazy.
+fn prefers_lazy(json: &str) -> bool {
+    json.len() >= LAZY_MIN_SIZE
+}
+
+/// The root value of a whole document, for a lazy decode.
+///
+/// `sonic_rs::get` with no pointer skips over the root with its checked
+/// skipper, so the returned value has been validated in full and may be
+/// walked with the unchecked iterators. It stops after the root; trailing
+/// bytes are rejected here like `from_str` does.
+pub fn document_root<'a>(json: &'a str, options: &DecodeOptions) -> Result<LazyValue<'a>, Error> {
+    // Validate input size to prevent DoS
+    check_input_size(json)?;
+    if json.is_empty() {
+        return Err(empty_input(options));
+    }
+
+    let lazy = sonic_rs::get(json, &[] as &[PointerNode]).map_err(|e| {
+        if options.strict {
+            strict_error(&e, json)
+        } else {
+            Error::from(e)
+        }
+    })?;
+
+    let trimmed = json.trim_matches([' ', '\t', '\n', '\r']);
+    if trimmed.len() != lazy.as_raw_str().len() {
+        return Err(if options.strict {
+            Error::JsonError(JSON_ERROR_SYNTAX, "Syntax error".to_string())
+        } else {
+            Error::ParseError("Trailing characters after JSON value".to_string())
+        });
+    }
+    Ok(lazy)
+}
+
+/// The error for empty input, which has no root value. sonic-rs is never
+/// asked for one: it underflows computing the root's start (a panic with
+/// overflow checks on, as in fuzzing builds).
+fn empty_input(options: &DecodeOptions) -> Error {
+    if options.strict {
+        Error::JsonError(JSON_ERROR_SYNTAX, "Syntax error".to_string())
+    } else {
+        Error::ParseError("EOF while parsing".to_string()).at_offset(0)
+    }
+}
+
+/// The root value of `json`, validated in full by the checked skip.
+fn root(json: &str) -> Result<LazyValue<'_>, Error> {
+    if json.is_empty() {
+        return Err(empty_input(&DecodeOptions::default()));
+    }
+    sonic_rs::get(json, &[] as &[PointerNode]).map_err(Error::from)
+}
+
+/// Parses an RFC 6901 pointer ("/users/0/email") into sonic-rs pointer
+/// nodes allocated in `bump`. The empty pointer yields no nodes (the root).
+/// Segments that parse as unsigned integers become array indexes.
+pub fn pointer_nodes<'b>(pointer: &str, bump: &'b Bump) -> Result<BumpVec<'b, PointerNode>, Error> {
+    if pointer.is_empty() {
+        return Ok(BumpVec::new_in(bump));
+    }
+
+    // Validate pointer format
+    if !pointer.starts_with('/') {
+        return Err(Error::InvalidPointer(
+            "Pointer must start with '/' or be empty".to_string(),
+        ));
+    }
+
+    // Validate pointer segment count to prevent DoS
+    let segment_count = pointer[1..].split('/').count();
+    if segment_count > MAX_POINTER_SEGMENTS {
+        return Err(Error::InvalidPointer(format!(
+            "Pointer has too many segments ({}, max {})",
+            segment_count, MAX_POINTER_SEGMENTS
+        )));
+    }
+
+    let mut nodes = BumpVec::with_capacity_in(segment_count, bump);
+    for part in pointer[1..].split('/') {
+        if let Ok(idx) = part.parse::<usize>() {
+            nodes.push(PointerNode::Index(idx));
+        } else if part.contains('~') {
+            nodes.push(PointerNode::Key(FastStr::new(
+                part.replace("~1", "/").replace("~0", "~"),
+            )));
+        } else {
+            nodes.push(PointerNode::Key(FastStr::new(part)));
+        }
+    }
+    Ok(nodes)
+}
+
+/// Parses a whole document into a sonic-rs DOM, enforcing the input size limit.
+pub fn parse_dom(json: &str) -> Result<Value, Error> {
+    // Validate input size to prevent DoS
+    check_input_size(json)?;
+    Ok(sonic_rs::from_str(json)?)
+}
+
+/// Resolves `pointer` to the unparsed value there, without converting
+/// anything. The empty pointer is the root. Like `document_root`, the
+/// value found has been validated in full, but text around it hasn't.
+pub fn lazy_at<'a>(json: &'a str, pointer: &str) -> Result<LazyValue<'a>, Error> {
+    if pointer.is_empty() {
+        return root(json);
+    }
+
+    arena::with_arena(|bump| {
+        let nodes = pointer_nodes(pointer, bump)?;
+
+        // Use sonic_rs::get for true lazy extraction (SIMD-accelerated skip)
+        sonic_rs::get(json, nodes.as_slice())
+            .map_err(|_| suggest::path_not_found(json, &nodes, pointer))
+    })
+}
+
+/// Number of elements of an array or members of an object, counted by
+/// skipping over them without conversion. None for other values.
+pub fn element_count(lazy: &LazyValue) -> Result<Option<usize>, Error> {
+    let count = if lazy.is_object() {
+        // SAFETY: we've verified this is an object via is_object()
+        unsafe { to_object_iter_unchecked(lazy.as_raw_str()) }
+            .try_fold(0, |count, entry| entry.map(|_| count + 1))
+    } else if lazy.is_array() {
+        // SAFETY: we've verified this is an array via is_array()
+        unsafe { to_array_iter_unchecked(lazy.as_raw_str()) }
+            .try_fold(0, |count, item| item.map(|_| count + 1))
+    } else {
+        return Ok(None);
+    };
+    count.map(Some).map_err(Error::from)
+}
+
+/// A `firstOf()` candidate: its position in the caller's list and the
+/// pointer nodes still to match below the current value.
+type Candidate<'p> = (usize, &'p [PointerNode]);
+
+/// Returns the value of the first pointer in `pointers` that exists,
+/// or `None` if none do.
+///
+/// All candidates are matched in one document-order scan: each container
+/// on the way is iterated once, and subtrees no candidate descends into
+/// are skipped without parsing. The scan stops as soon as no remaining
+/// candidate could beat the best match found so far.
+pub fn first_of<'a>(json: &'a str, pointers: &[String]) -> Result<Option<LazyValue<'a>>, Error> {
+    // Validate input size to prevent DoS
+    check_input_size(json)?;
+
+    arena::with_arena(|bump| {
+        let mut paths = BumpVec::with_capacity_in(pointers.len(), bump);
+        for pointer in pointers {
+            paths.push(pointer_nodes(pointer, bump)?);
+        }
+
+        // The empty pointer always exists, so only candidates listed before
+        // it need scanning
+        let root_order = paths.iter().position(|nodes| nodes.is_empty());
+        let limit = root_order.unwrap_or(paths.len());
+        let mut candidates = BumpVec::with_capacity_in(limit, bump);
+        candidates.extend(
+            paths[..limit]
+                .iter()
+                .enumerate()
+                .map(|(order, nodes)| (order, nodes.as_slice())),
+        );
+
+        let mut best = None;
+        if !candidates.is_empty() {
+            scan_first_of(json, &candidates, bump, &mut best)?;
+        }
+        match (best, root_order) {
+            (Some((_, value)), _) => Ok(Some(value)),
+            (None, Some(_)) => root(json).map(Some),
+            (None, None) => Ok(None),
+        }
+    })
+}
+
+/// Iterates the container `raw` once, handing each child the candidates
+/// whose next node selects it. Non-containers match nothing below them.
+fn scan_first_of<'a>(
+    raw: &'a str,
+    candidates: &[Candidate<'_>],
+    bump: &Bump,
+    best: &mut Option<(usize, LazyValue<'a>)>,
+) -> Result<(), Error> {
+    match raw
+        .trim_start_matches([' ', '\t', '\n', '\r'])
+        .as_bytes()
+        .first()
+    {
+        Some(b'{') => {
+            for entry in sonic_rs::to_object_iter(raw) {
+                let (key, value) = entry.map_err(Error::from)?;
+                let mut child = BumpVec::new_in(bump);
+                child.extend(
+                    candidates
+                        .iter()
+                        .filter_map(|&(order, nodes)| match &nodes[0] {
+                            PointerNode::Key(k) if *k == key && beats(order, best) => {
+                                Some((order, &nodes[1..]))
+                            }
+                            _ => None,
+                        }),
+                );
+                visit_first_of(value, &child, bump, best)?;
+                if !candidates.iter().any(|&(order, _)| beats(order, best)) {
+                    break;
+                }
+            }
+        }
+        Some(b'[') => {
+            for (idx, item) in sonic_rs::to_array_iter(raw).enumerate() {
+                let value = item.map_err(Error::from)?;
+                let mut child = BumpVec::new_in(bump);
+                child.extend(
+                    candidates
+                        .iter()
+                        .filter_map(|&(order, nodes)| match &nodes[0] {
+                            PointerNode::Index(i) if *i == idx && beats(order, best) => {
+                                Some((order, &nodes[1..]))
+                            }
+                            _ => None,
+                        }),
+                );
+                visit_first_of(value, &child, bump, best)?;
+                if !candidates.iter().any(|&(order, _)| beats(order, best)) {
+                    break;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Whether a match for candidate `order` would replace the best so far.
+fn beats(order: usize, best: &Option<(usize, LazyValue<'_>)>) -> bool {
+    best.as_ref().is_none_or(|(found, _)| order < *found)
+}
+
+/// Records candidates that end at `value`, then scans into it for the
+/// ones that continue.
+fn visit_first_of<'a>(
+    value: LazyValue<'a>,
+    candidates: &[Candidate<'_>],
+    bump: &Bump,
+    best: &mut Option<(usize, LazyValue<'a>)>,
+) -> Result<(), Error> {
+    if candidates.is_empty() {
+        return Ok(());
+    }
+    let ending = candidates.iter().filter(|(_, nodes)| nodes.is_empty());
+    if let Some(order) = ending.map(|&(order, _)| order).min() {
+        if beats(order, best) {
+            *best = Some((order, value.clone()));
+        }
+    }
+
+    let mut deeper = BumpVec::new_in(bump);
+    deeper.extend(
+        candidates
+            .iter()
+            .copied()
+            .filter(|&(order, nodes)| !nodes.is_empty() && beats(order, best)),
+    );
+    if deeper.is_empty() {
+        return Ok(());
+    }
+    // Values iterated from a borrowed &str always borrow from it, so the
+    // subtree keeps the input's lifetime
+    match value.as_raw_cow() {
+        Cow::Borrowed(raw) => scan_first_of(raw, &deeper, bump, best),
+        Cow::Owned(_) => Err(Error::ParseError(
+            "Unexpected owned value while scanning".to_string(),
+        )),
+    }
+}
+
+/// The string a JSON string literal stands for, with its escapes
+/// (including surrogate pairs) decoded. `literal` includes the quotes
+/// unless `quoted` is false.
+pub fn unescape_string(literal: &str, quoted: bool) -> Result<String, Error> {
+    check_input_size(literal)?;
+    if quoted {
+        return Ok(sonic_rs::from_str::<String>(literal)?);
+    }
+    let wrapped = format!("\"{}\"", literal);
+    // Offsets refer to `literal`, not the quotes added around it
+    sonic_rs::from_str::<String>(&wrapped).map_err(|e| {
+        let offset = e.offset().saturating_sub(1).min(literal.len());
+        Error::ParseError(e.to_string()).at_offset(offset)
+    })
+}
+
+/// Validate JSON syntax. Oversized inputs are `false`; see
+/// `is_valid_strict` to tell them apart from malformed JSON.
+/// Note: This currently does a full parse.
+pub fn is_valid(json: &str) -> bool {
+    // Reject oversized inputs to prevent DoS
+    if check_input_size(json).is_err() {
+        return false;
+    }
+    // TODO: sonic-rs doesn't have a dedicated validation-only function,
+    // so we have to do a full parse. Consider using a streaming validator
+    // for better performance on large inputs.
+    sonic_rs::from_str::<Value>(json).is_ok()
+}
+
+/// Like `is_valid`, but input the limits reject is an error rather than
+/// `false`: `false` always means malformed JSON.
+pub fn is_valid_strict(json: &str) -> Result<bool, Error> {
+    check_input_size(json)?;
+    Ok(is_valid(json))
+}
+
+/// Type name reported by `getType()` and in type error context.
+pub fn type_name(lazy: &LazyValue) -> &'static str {
+    if lazy.is_null() {
+        "null"
+    } else if lazy.is_boolean() {
+        "boolean"
+    } else if lazy.is_i64() || lazy.is_u64() {
+        "integer"
+    } else if lazy.is_f64() {
+        "float"
+    } else if lazy.is_str() {
+        "string"
+    } else if lazy.is_array() {
+        "array"
+    } else if lazy.is_object() {
+        "object"
+    } else {
+        "unknown"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf8_options(utf8: Utf8Policy, strict: bool) -> DecodeOptions {
+        DecodeOptions {
+            utf8,
+            strict,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn malformed_utf8_is_rejected_at_its_offset() {
+        let input = b"[\"ab\xff\"]";
+        let err = input_text(input, &utf8_options(Utf8Policy::Error, false)).unwrap_err();
+        assert_eq!(err.offset(), Some(4));
+        assert!(matches!(err.into_parts().0, Error::ParseError(_)));
+
+        let err = input_text(input, &utf8_options(Utf8Policy::Error, true)).unwrap_err();
+        assert!(matches!(err, Error::JsonError(JSON_ERROR_UTF8, _)));
+    }
+
+    #[test]
+    fn text_repairs_are_warned_about_at_their_offset() {
+        let options = DecodeOptions {
+            warnings: true,
+            utf8: Utf8Policy::Substitute,
+            surrogates: Surrogates::Replace,
+            ..Default::default()
+        };
+        diagnostics::start_call();
+        let text = input_text(b"[\"\xff\", \"\\ud800\"]", &options).unwrap();
+        let found: Vec<_> = text.warnings.iter().map(|w| (w.kind, w.offset)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (WarningKind::Utf8Substituted, Some(2)),
+                (WarningKind::SurrogateReplaced, Some(9)),
+            ]
+        );
+        // Nothing is recorded until the text is published
+        assert!(diagnostics::last_warnings().is_empty());
+        text.publish();
+        assert_eq!(diagnostics::last_warnings().len(), 2);
+    }
+
+    #[test]
+    fn malformed_utf8_is_dropped_or_substituted() {
+        let input = b"[\"ab\xffc\"]";
+        let ignored = input_text(input, &utf8_options(Utf8Policy::Ignore, false))
+            .unwrap()
+            .text;
+        assert_eq!(ignored, "[\"abc\"]");
+        let substituted = input_text(input, &utf8_options(Utf8Policy::Substitute, false))
+            .unwrap()
+            .text;
+        assert_eq!(substituted, "[\"ab\u{fffd}c\"]");
+        // Valid input is borrowed whatever the policy
+        let valid = input_text(b"[1]", &utf8_options(Utf8Policy::Substitute, false))
+            .unwrap()
+            .text;
+        assert!(matches!(valid, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn strict_errors_match_json_decode_codes() {
+        let strict = DecodeOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let code = |json: &str| match crate::value::decode(json, &strict) {
+            Err(Error::JsonError(code, _)) => code,
+            other => panic!("{json}: {other:?}"),
+        };
+        assert_eq!(code("[\"ab\u{1}c\"]"), JSON_ERROR_CTRL_CHAR);
+        assert_eq!(code(r#"[1, "a\ud800"]"#), JSON_ERROR_UTF16);
+        assert_eq!(code(r#"{"a": 1, "k\udc00": 2}"#), JSON_ERROR_UTF16);
+        assert_eq!(code(r#"["\ud83d\ude00", "\x"]"#), JSON_ERROR_SYNTAX);
+        assert_eq!(code("[1,]"), JSON_ERROR_SYNTAX);
+        assert_eq!(code(r#"["a" "b"]"#), JSON_ERROR_SYNTAX);
+    }
+
+    #[test]
+    fn pointers_resolve_with_escapes() {
+        let json = r#"{"users": [{"name": "Ada"}], "a/b": {"~": 1}}"#;
+        assert_eq!(
+            lazy_at(json, "/users/0/name").unwrap().as_str(),
+            Some("Ada")
+        );
+        assert_eq!(lazy_at(json, "/a~1b/~0").unwrap().as_raw_str(), "1");
+        assert_eq!(lazy_at(json, "").unwrap().as_raw_str(), json);
+    }
+
+    #[test]
+    fn missing_pointers_suggest_close_keys() {
+        let json = r#"{"users": [{"name": "Ada"}]}"#;
+        let (err, context) = lazy_at(json, "/users/0/nmae").unwrap_err().into_parts();
+        assert!(matches!(err, Error::KeyNotFound(_)));
+        let context = context.unwrap();
+        assert_eq!(context.pointer.as_deref(), Some("/users/0/nmae"));
+        assert_eq!(context.suggestions, vec!["/users/0/name".to_string()]);
+    }
+
+    #[test]
+    fn empty_input_has_no_root() {
+        assert!(document_root("", &DecodeOptions::default()).is_err());
+        assert!(lazy_at("", "").is_err());
+        assert!(first_of("", &["/a".to_string(), String::new()]).is_err());
+    }
+
+    #[test]
+    fn malformed_pointers_are_rejected() {
+        let err = lazy_at("{}", "users").unwrap_err();
+        assert!(matches!(err.into_parts().0, Error::InvalidPointer(_)));
+    }
+
+    #[test]
+    fn first_of_takes_the_earliest_listed_pointer() {
+        let json = r#"{"a": 1, "b": 2}"#;
+        let pointers = ["/missing", "/b", "/a"].map(String::from);
+        assert_eq!(
+            first_of(json, &pointers).unwrap().unwrap().as_raw_str(),
+            "2"
+        );
+        let none = ["/x", "/y"].map(String::from);
+        assert!(first_of(json, &none).unwrap().is_none());
+    }
+}
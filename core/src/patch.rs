@@ -0,0 +1,65 @@
+//! Byte-preserving value replacement, behind `Sift::patch()` and field
+//! encryption.
+
+use crate::error::{AtPointer, Error};
+use crate::parser;
+use sonic_rs::LazyValue;
+use std::ops::Range;
+
+/// A replacement: the byte range of the old value and the new value's text.
+struct Splice<'p> {
+    range: Range<usize>,
+    pointer: &'p str,
+    text: String,
+}
+
+/// Replace the value at each of `pointers` with the text `replace` returns
+/// for it (given the pointer's position and the old value), copying
+/// everything else, including whitespace, key order and number spelling,
+/// verbatim. Only existing values can be replaced, and the replaced values
+/// must not contain one another. Errors from `replace` get the pointer.
+pub fn replace_at<E>(
+    json: &str,
+    pointers: &[&str],
+    mut replace: impl FnMut(usize, &LazyValue) -> Result<String, E>,
+) -> Result<String, E>
+where
+    E: From<Error> + AtPointer,
+{
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+
+    let mut splices = Vec::with_capacity(pointers.len());
+    for (i, &pointer) in pointers.iter().enumerate() {
+        let old = parser::lazy_at(json, pointer)?;
+        // Values are slices of the document
+        let start = old.as_raw_str().as_ptr() as usize - json.as_ptr() as usize;
+        let text = replace(i, &old).map_err(|e| e.at_pointer(pointer))?;
+        splices.push(Splice {
+            range: start..start + old.as_raw_str().len(),
+            pointer,
+            text,
+        });
+    }
+
+    splices.sort_by_key(|splice| splice.range.start);
+    for pair in splices.windows(2) {
+        if pair[1].range.start < pair[0].range.end {
+            return Err(Error::InvalidPointer(format!(
+                "Changes at '{}' and '{}' overlap",
+                pair[0].pointer, pair[1].pointer
+            ))
+            .into());
+        }
+    }
+
+    let mut out = String::with_capacity(json.len());
+    let mut copied = 0;
+    for splice in &splices {
+        out.push_str(&json[copied..splice.range.start]);
+        out.push_str(&splice.text);
+        copied = splice.range.end;
+    }
+    out.push_str(&json[copied..]);
+    Ok(out)
+}
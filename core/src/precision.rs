@@ -0,0 +1,177 @@
+//! Per-pointer number decoding (the `numbers` option).
+//!
+//! Patterns are JSON pointers, relative to the value being decoded, in
+//! which a `*` segment matches any key or index. The first pattern matching a
+//! number's pointer decides how it decodes: `'string'` keeps its literal
+//! text, an integer rounds floats to that many decimal places, and
+//! `'float'` decodes it normally (to exempt part of a broader pattern).
+//! Numbers no pattern matches decode as the other options say.
+
+use crate::error::Error;
+use std::cell::RefCell;
+
+thread_local! {
+    /// Pattern lists given as the `numbers` option, referenced by index
+    /// from `NumberPolicies::Map` so options stay `Copy`.
+    /// Request-scoped like the configured defaults.
+    static PATTERNS: RefCell<Vec<Vec<Pattern>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Most decimal places a precision can ask for; doubles hold no more
+/// significant digits than this.
+pub const MAX_PLACES: u32 = 17;
+
+/// Which numbers decode by pointer-specific rules.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NumberPolicies {
+    /// Every number decodes by the other options.
+    #[default]
+    Off,
+    /// A pattern list, by its index in the request's registry.
+    Map(usize),
+}
+
+/// How a matched number decodes, other than normally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumberPolicy {
+    /// As a string of the literal's exact text.
+    String,
+    /// Floats rounded half away from zero to this many decimal places;
+    /// integer literals decode normally.
+    Places(u32),
+}
+
+/// A pointer pattern and the policy of the numbers it matches.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    /// Escaped pointer segments, as `Diagnostics` spells them; `None` is `*`
+    segments: Vec<Option<String>>,
+    /// `None` for 'float'
+    policy: Option<NumberPolicy>,
+}
+
+impl Pattern {
+    /// The pattern for `pointer`, in which a `*` segment matches any key
+    /// or index; `policy` is `None` for 'float'.
+    pub fn new(pointer: &str, policy: Option<NumberPolicy>) -> Self {
+        let segments = pointer
+            .split('/')
+            .skip(1)
+            .map(|part| (part != "*").then(|| part.to_string()))
+            .collect();
+        Self { segments, policy }
+    }
+
+    /// Whether `pointer` (escaped, e.g. "/amounts/0") has this shape.
+    fn matches(&self, pointer: &str) -> bool {
+        let mut parts = pointer.split('/').skip(1);
+        for segment in &self.segments {
+            match (parts.next(), segment) {
+                (None, _) => return false,
+                (Some(_), None) => {}
+                (Some(part), Some(expected)) if part == expected => {}
+                _ => return false,
+            }
+        }
+        parts.next().is_none()
+    }
+}
+
+impl NumberPolicies {
+    /// Policies by the first of `patterns` matching a number's pointer,
+    /// held for the rest of the request (until `reset_patterns()`).
+    pub fn from_patterns(patterns: Vec<Pattern>) -> Self {
+        Self::Map(register(patterns))
+    }
+
+    /// The policy of the number at `pointer`, unless it decodes normally.
+    pub fn lookup(self, pointer: &str) -> Result<Option<NumberPolicy>, Error> {
+        let Self::Map(index) = self else {
+            return Ok(None);
+        };
+        PATTERNS.with(|registry| {
+            let registry = registry.borrow();
+            // Options holding patterns don't outlive the request
+            let patterns = registry.get(index).ok_or_else(|| {
+                Error::InvalidOption("Number policies are no longer available".to_string())
+            })?;
+            Ok(patterns
+                .iter()
+                .find(|pattern| pattern.matches(pointer))
+                .and_then(|pattern| pattern.policy))
+        })
+    }
+}
+
+/// Whether a number literal is written as an integer (no fraction or
+/// exponent).
+pub fn is_integer_literal(raw: &str) -> bool {
+    !raw.bytes().any(|b| matches!(b, b'.' | b'e' | b'E'))
+}
+
+/// `raw` parsed and rounded half away from zero to `places` decimal
+/// places. Plain decimals are rounded on their digits, so `1.005` at 2
+/// places is `1.01` as written rather than the `1.0` its double would give.
+pub fn round_literal(raw: &str, places: u32) -> Result<f64, Error> {
+    let invalid = || Error::ParseError(format!("Invalid number '{}'", raw));
+    let value: f64 = raw.parse().map_err(|_| invalid())?;
+    let places = places as usize;
+    let (int_part, fraction) = raw.split_once('.').unwrap_or((raw, ""));
+    if raw.contains(['e', 'E']) {
+        // Exponent forms are rare here; the double is close enough
+        let scale = 10f64.powi(places as i32);
+        let rounded = (value * scale).round() / scale;
+        return Ok(if rounded.is_finite() { rounded } else { value });
+    }
+    if fraction.len() <= places {
+        return Ok(value);
+    }
+
+    let (sign, int_part) = match int_part.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", int_part),
+    };
+    let mut digits: Vec<u8> = int_part.bytes().chain(fraction[..places].bytes()).collect();
+    if fraction.as_bytes()[places] >= b'5' {
+        let mut carry = true;
+        for digit in digits.iter_mut().rev() {
+            if *digit == b'9' {
+                *digit = b'0';
+            } else {
+                *digit += 1;
+                carry = false;
+                break;
+            }
+        }
+        if carry {
+            digits.insert(0, b'1');
+        }
+    }
+    let (whole, kept) = digits.split_at(digits.len() - places);
+    // Only ASCII digits were pushed
+    let text = format!(
+        "{}{}.{}0",
+        sign,
+        std::str::from_utf8(whole).unwrap_or("0"),
+        std::str::from_utf8(kept).unwrap_or("")
+    );
+    text.parse().map_err(|_| invalid())
+}
+
+/// Store `patterns` for the rest of the request, reusing the slot of an
+/// equal list so patterns passed on every call are held once.
+fn register(patterns: Vec<Pattern>) -> usize {
+    PATTERNS.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        if let Some(index) = registry.iter().position(|p| *p == patterns) {
+            return index;
+        }
+        registry.push(patterns);
+        registry.len() - 1
+    })
+}
+
+/// Drops the registered pattern lists; runs at request shutdown.
+pub fn reset_patterns() {
+    PATTERNS.with(|registry| registry.borrow_mut().clear());
+}
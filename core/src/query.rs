@@ -0,0 +1,122 @@
+//! Path resolution behind `Sift\Query`: accumulated path segments, their
+//! pointer spelling, and the cache of containers already found along them.
+
+use crate::error::Error;
+use crate::suggest;
+use faststr::FastStr;
+use sonic_rs::{JsonValueTrait, LazyValue, PointerNode};
+use std::collections::HashMap;
+
+/// Most containers a `PrefixCache` records; paths past the limit are
+/// still resolved, just not recorded.
+pub const MAX_CACHED_PREFIXES: usize = 4096;
+
+/// A path segment for lazy path building.
+/// Uses FastStr for zero-copy key storage where possible.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(FastStr),
+    Index(usize),
+}
+
+impl PathSegment {
+    /// The segment as it reads in a pointer, unescaped.
+    pub fn text(&self) -> String {
+        match self {
+            PathSegment::Key(k) => k.to_string(),
+            PathSegment::Index(i) => i.to_string(),
+        }
+    }
+}
+
+/// `path` as an RFC 6901 pointer.
+pub fn segments_pointer(path: &[PathSegment]) -> String {
+    path.iter()
+        .map(|seg| match seg {
+            PathSegment::Key(k) => format!("/{}", k.replace('~', "~0").replace('/', "~1")),
+            PathSegment::Index(i) => format!("/{}", i),
+        })
+        .collect()
+}
+
+/// Byte ranges of containers already resolved, by layer and path, shared
+/// by every Query navigated from the same root. Reading fifty fields of
+/// `/users/123` finds the user once; each field is then looked up within
+/// it instead of from the top of the document.
+#[derive(Debug, Default)]
+pub struct PrefixCache {
+    /// Per layer (a document, or one of its defaults): container path to
+    /// the byte range of its text
+    layers: Vec<HashMap<Vec<PathSegment>, (usize, usize)>>,
+    /// Entries across all layers
+    len: usize,
+}
+
+impl PrefixCache {
+    /// The byte range of the container at `path` in layer `layer`.
+    pub fn get(&self, layer: usize, path: &[PathSegment]) -> Option<(usize, usize)> {
+        self.layers.get(layer)?.get(path).copied()
+    }
+
+    /// Record the container at `path` in layer `layer`, unless full.
+    pub fn insert(&mut self, layer: usize, path: &[PathSegment], range: (usize, usize)) {
+        if self.len >= MAX_CACHED_PREFIXES {
+            return;
+        }
+        if self.layers.len() <= layer {
+            self.layers.resize_with(layer + 1, HashMap::new);
+        }
+        if self.layers[layer].insert(path.to_vec(), range).is_none() {
+            self.len += 1;
+        }
+    }
+}
+
+/// Resolves the non-empty `path` in `json`, the text of layer `layer`.
+///
+/// Starts from the deepest container on the path that `cache` holds, and
+/// records each container found from there. A missing segment is
+/// `Error::KeyNotFound` with the full path's pointer and suggestions.
+pub fn resolve_path<'a>(
+    json: &'a str,
+    layer: usize,
+    path: &[PathSegment],
+    cache: &mut PrefixCache,
+) -> Result<LazyValue<'a>, Error> {
+    let (mut depth, mut text) = (1..path.len())
+        .rev()
+        .find_map(|len| {
+            cache
+                .get(layer, &path[..len])
+                .map(|(start, end)| (len, &json[start..end]))
+        })
+        .unwrap_or((0, json));
+    loop {
+        // FastStr clone is cheap (Arc-based)
+        let node = match &path[depth] {
+            PathSegment::Key(k) => PointerNode::Key(k.clone()),
+            PathSegment::Index(i) => PointerNode::Index(*i),
+        };
+        let lazy = sonic_rs::get(text, &[node]).map_err(|_| {
+            let parent = segments_pointer(&path[..depth]);
+            suggest::not_found(&segments_pointer(path), &parent, text, &path[depth].text())
+        })?;
+        depth += 1;
+        if lazy.is_array() || lazy.is_object() {
+            // The value's raw text is a slice of `json`
+            let raw = lazy.as_raw_str();
+            let start = raw.as_ptr() as usize - json.as_ptr() as usize;
+            cache.insert(layer, &path[..depth], (start, start + raw.len()));
+            if depth < path.len() {
+                text = &json[start..start + raw.len()];
+                continue;
+            }
+        }
+        if depth < path.len() {
+            return Err(
+                Error::KeyNotFound("Path not found".to_string()).at_pointer(segments_pointer(path))
+            );
+        }
+        return Ok(lazy);
+    }
+}
@@ -0,0 +1,81 @@
+//! The builder interface conversions write values through.
+//!
+//! `convert` walks a document once, applying depth limits, options and
+//! diagnostics, and hands every value it produces to a `ValueSink`. The
+//! extension's sink builds zvals; `value::Builder` builds plain Rust
+//! values, for tests, fuzzing and callers without PHP.
+
+use crate::error::Error;
+use crate::options::{BigNumbers, Leaves};
+
+/// The key of an object member as it will be stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key<'k> {
+    /// A member name that follows PHP's integer key rules in strict mode
+    /// ("7" and "-3", not "07"); see `convert::php_numeric_key`.
+    Index(i64),
+    /// Any other member name.
+    Name(&'k str),
+}
+
+/// Receives the values of a conversion, leaves first. Containers are
+/// created empty, then filled with their members as these are converted.
+pub trait ValueSink {
+    /// What a conversion builds.
+    type Value;
+    /// Errors of the sink; the walk's own errors convert into it.
+    type Error: From<Error>;
+
+    fn null(&mut self) -> Result<Self::Value, Self::Error>;
+
+    fn bool(&mut self, b: bool) -> Result<Self::Value, Self::Error>;
+
+    fn int(&mut self, n: i64) -> Result<Self::Value, Self::Error>;
+
+    fn float(&mut self, n: f64) -> Result<Self::Value, Self::Error>;
+
+    /// A decoded string, or the raw text of a number kept as a string.
+    fn string(&mut self, s: &str) -> Result<Self::Value, Self::Error>;
+
+    /// A string of WTF-8 bytes, under `'surrogates' => 'wtf8'`.
+    fn bytes(&mut self, b: &[u8]) -> Result<Self::Value, Self::Error>;
+
+    /// A number literal a double can't hold, as the exact number `mode`
+    /// calls for. Only asked when `mode` holds it (`BigNumbers::holds`).
+    fn big_number(&mut self, raw: &str, mode: BigNumbers) -> Result<Self::Value, Self::Error>;
+
+    /// A container beyond the hydration cutoff, given its raw JSON text.
+    fn leaf(&mut self, raw: &str, leaves: Leaves) -> Result<Self::Value, Self::Error>;
+
+    /// An empty array, sized for `capacity` elements when known (else 0).
+    fn array(&mut self, capacity: usize) -> Result<Self::Value, Self::Error>;
+
+    /// Appends `value` to `array`.
+    fn push(&mut self, array: &mut Self::Value, value: Self::Value) -> Result<(), Self::Error>;
+
+    /// An empty object, sized for `capacity` members when known (else 0).
+    fn object(&mut self, capacity: usize) -> Result<Self::Value, Self::Error>;
+
+    /// Whether `object` already has a member `key`.
+    fn contains(&self, object: &Self::Value, key: &Key<'_>) -> bool;
+
+    /// Stores `value` as member `key` of `object`, replacing any member
+    /// of that key in place.
+    fn insert(
+        &mut self,
+        object: &mut Self::Value,
+        key: Key<'_>,
+        value: Self::Value,
+    ) -> Result<(), Self::Error>;
+
+    /// Member `key` of `object` if it is a container further `nest_keys`
+    /// segments can be merged into.
+    fn nested<'v>(&self, object: &'v mut Self::Value, key: &Key<'_>)
+        -> Option<&'v mut Self::Value>;
+
+    /// Runs the host's key callback `index` (`KeyTransform::Callback`).
+    fn rename_key(&mut self, index: usize, key: &str) -> Result<String, Self::Error> {
+        let _ = (index, key);
+        Err(Error::InvalidOption("Key callbacks are not available here".to_string()).into())
+    }
+}
@@ -0,0 +1,132 @@
+//! "Did you mean" suggestions for pointers that miss an object key.
+//!
+//! ```php
+//! try {
+//!     Sift::get($json, '/user/emial');
+//! } catch (Sift\Exception $e) {
+//!     echo $e->getMessage();        // Key not found: Path not found (did you mean /user/email?)
+//!     print_r($e->getSuggestions()); // ['/user/email']
+//! }
+//! ```
+//!
+//! Only computed once a lookup has failed: the keys of the object where
+//! the path stopped are listed lazily, without converting their values,
+//! and ranked by edit distance to the missing segment.
+
+use crate::error::Error;
+use sonic_rs::{to_object_iter, PointerNode};
+
+/// Most suggestions attached to one error.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Most keys compared; the rest of a huge object is not listed.
+const MAX_CANDIDATES: usize = 10_000;
+
+/// The KeyNotFound error for `pointer`, whose `nodes` were looked up in
+/// `json`, with suggestions if the path stopped at an object.
+pub fn path_not_found(json: &str, nodes: &[PointerNode], pointer: &str) -> Error {
+    let mut text = json;
+    for (depth, node) in nodes.iter().enumerate() {
+        match sonic_rs::get(text, std::slice::from_ref(node)) {
+            Ok(lazy) => {
+                // The value's raw text is a slice of `text`
+                let raw = lazy.as_raw_str();
+                let start = raw.as_ptr() as usize - text.as_ptr() as usize;
+                text = &text[start..start + raw.len()];
+            }
+            Err(_) => {
+                let key = match node {
+                    PointerNode::Key(key) => key.to_string(),
+                    PointerNode::Index(index) => index.to_string(),
+                };
+                return not_found(pointer, &node_pointer(&nodes[..depth]), text, &key);
+            }
+        }
+    }
+    Error::KeyNotFound("Path not found".to_string()).at_pointer(pointer)
+}
+
+/// The KeyNotFound error for `pointer`, which misses `key` in the value
+/// `container` at `parent`, with the closest keys of `container` (if it
+/// is an object) suggested.
+pub fn not_found(pointer: &str, parent: &str, container: &str, key: &str) -> Error {
+    let suggestions = closest_keys(container, key)
+        .into_iter()
+        .map(|name| format!("{}/{}", parent, name.replace('~', "~0").replace('/', "~1")))
+        .collect::<Vec<_>>();
+    let message = match suggestions.as_slice() {
+        [] => "Path not found".to_string(),
+        [only] => format!("Path not found (did you mean {}?)", only),
+        [rest @ .., last] => format!(
+            "Path not found (did you mean {} or {}?)",
+            rest.join(", "),
+            last
+        ),
+    };
+    Error::KeyNotFound(message)
+        .at_pointer(pointer)
+        .with_suggestions(suggestions)
+}
+
+/// Up to `MAX_SUGGESTIONS` keys of the object `container` close to `key`,
+/// closest first. Anything but an object has none.
+fn closest_keys(container: &str, key: &str) -> Vec<String> {
+    if !container.trim_start().starts_with('{') {
+        return Vec::new();
+    }
+    let wanted: Vec<char> = key.to_lowercase().chars().collect();
+    // Roughly one edit per three characters, and at least one
+    let limit = wanted.len() / 3 + 1;
+    let mut ranked: Vec<(usize, String)> = Vec::new();
+    for member in to_object_iter(container).take(MAX_CANDIDATES) {
+        let Ok((name, _)) = member else { break };
+        let candidate: Vec<char> = name.to_lowercase().chars().collect();
+        if candidate.len().abs_diff(wanted.len()) > limit {
+            continue;
+        }
+        let distance = edit_distance(&wanted, &candidate);
+        // A repeated key is suggested once
+        let seen = ranked
+            .iter()
+            .any(|(_, seen)| seen.as_str() == name.as_str());
+        if distance <= limit && !seen {
+            ranked.push((distance, name.to_string()));
+        }
+    }
+    // Stable, so equally close keys keep document order
+    ranked.sort_by_key(|(distance, _)| *distance);
+    ranked.truncate(MAX_SUGGESTIONS);
+    ranked.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Optimal string alignment distance: insertions, deletions,
+/// substitutions and transpositions of adjacent characters.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+/// The pointer of the path `nodes`.
+fn node_pointer(nodes: &[PointerNode]) -> String {
+    nodes
+        .iter()
+        .map(|node| match node {
+            PointerNode::Key(key) => format!("/{}", key.replace('~', "~0").replace('/', "~1")),
+            PointerNode::Index(index) => format!("/{}", index),
+        })
+        .collect()
+}
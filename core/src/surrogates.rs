@@ -0,0 +1,207 @@
+//! Lone UTF-16 surrogates and malformed `\u` escapes (the `surrogates`
+//! option).
+//!
+//! Usage:
+//! ```php
+//! $page = Sift::decode($crawled, ['surrogates' => 'replace']); // "\ud800" becomes "\u{FFFD}"
+//! $raw = Sift::decode($crawled, ['surrogates' => 'wtf8']);     // "\ud800" becomes "\xED\xA0\x80"
+//! ```
+//!
+//! Both policies rewrite the input before it is parsed. With `wtf8`, each
+//! lone surrogate becomes a stand-in character from the end of plane 16
+//! (U+10F800 + its offset from U+D800), turned back into the surrogate's
+//! WTF-8 bytes when the string is stored. Documents that already contain
+//! such characters are rejected, since the stand-ins would be ambiguous.
+
+use crate::error::Error;
+use crate::options::Surrogates;
+use std::borrow::Cow;
+
+/// First of the stand-in characters for lone surrogates.
+const STAND_IN_BASE: u32 = 0x10F800;
+
+/// A problem found in a `\u` escape.
+pub(crate) enum Problem {
+    /// An unpaired surrogate code unit.
+    Lone(u32),
+    /// `\u` not followed by four hex digits.
+    Malformed,
+}
+
+/// The four hex digits at `at`, if there are four.
+fn hex4(bytes: &[u8], at: usize) -> Option<u32> {
+    let digits = bytes.get(at..at + 4)?;
+    if !digits.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    u32::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()
+}
+
+/// Look at the escape starting at the backslash `at`: its length, and
+/// what is wrong with it if anything.
+pub(crate) fn inspect(bytes: &[u8], at: usize) -> (usize, Option<Problem>) {
+    if bytes.get(at + 1) != Some(&b'u') {
+        // Other escapes are two bytes; the parser judges them
+        return (2, None);
+    }
+    let Some(unit) = hex4(bytes, at + 2) else {
+        let digits = bytes[at + 2..]
+            .iter()
+            .take(4)
+            .take_while(|b| b.is_ascii_hexdigit())
+            .count();
+        return (2 + digits, Some(Problem::Malformed));
+    };
+    match unit {
+        0xD800..=0xDBFF => {
+            let low = match bytes.get(at + 6..at + 8) {
+                Some(b"\\u") => hex4(bytes, at + 8),
+                _ => None,
+            };
+            match low {
+                Some(0xDC00..=0xDFFF) => (12, None),
+                _ => (6, Some(Problem::Lone(unit))),
+            }
+        }
+        0xDC00..=0xDFFF => (6, Some(Problem::Lone(unit))),
+        _ => (6, None),
+    }
+}
+
+/// The error for a `wtf8` document that already holds stand-ins.
+fn ambiguous() -> Error {
+    Error::ParseError(
+        "Option 'surrogates' => 'wtf8' can't decode documents containing U+10F800 to U+10FFFF"
+            .to_string(),
+    )
+}
+
+/// Whether `c` is one of the stand-ins `wtf8` uses.
+fn is_stand_in(c: char) -> bool {
+    c as u32 >= STAND_IN_BASE
+}
+
+/// Rewrite the lone surrogates and malformed `\u` escapes in `text` as
+/// `policy` says: U+FFFD for `Replace`, and for `Wtf8` a stand-in per
+/// surrogate (malformed escapes still become U+FFFD). With `Error` the
+/// text is returned as is and the parser rejects such escapes. `replaced`
+/// is called with the offset of each escape that became U+FFFD.
+pub fn rewrite(
+    text: &str,
+    policy: Surrogates,
+    mut replaced: impl FnMut(usize),
+) -> Result<Cow<'_, str>, Error> {
+    if policy == Surrogates::Error {
+        return Ok(Cow::Borrowed(text));
+    }
+    if policy == Surrogates::Wtf8 && text.chars().any(is_stand_in) {
+        return Err(ambiguous());
+    }
+
+    let bytes = text.as_bytes();
+    let mut out: Option<String> = None;
+    let mut copied = 0;
+    let mut at = 0;
+    while let Some(found) = bytes[at..].iter().position(|&b| b == b'\\') {
+        let start = at + found;
+        let (len, problem) = inspect(bytes, start);
+        // A backslash ending the text still counts as a two-byte escape
+        at = (start + len).min(bytes.len());
+        let replacement = match problem {
+            // An escaped pair for a stand-in is as ambiguous as the character
+            None if policy == Surrogates::Wtf8 && len == 12 => {
+                if hex4(bytes, start + 2).is_some_and(|high| high >= 0xDBFE) {
+                    return Err(ambiguous().at_offset(start));
+                }
+                continue;
+            }
+            None => continue,
+            Some(Problem::Lone(unit)) if policy == Surrogates::Wtf8 => {
+                char::from_u32(STAND_IN_BASE + (unit - 0xD800))
+                    .unwrap_or(char::REPLACEMENT_CHARACTER)
+            }
+            Some(_) => {
+                replaced(start);
+                char::REPLACEMENT_CHARACTER
+            }
+        };
+        let out = out.get_or_insert_with(|| String::with_capacity(text.len()));
+        out.push_str(&text[copied..start]);
+        out.push(replacement);
+        copied = at;
+    }
+
+    Ok(match out {
+        Some(mut out) => {
+            out.push_str(&text[copied..]);
+            Cow::Owned(out)
+        }
+        None => Cow::Borrowed(text),
+    })
+}
+
+/// The bytes to store for a decoded string: stand-ins become the WTF-8
+/// encoding of their surrogate (three bytes, ED A0 80 to ED BF BF).
+pub fn to_wtf8(text: &str) -> Cow<'_, [u8]> {
+    if !text.chars().any(is_stand_in) {
+        return Cow::Borrowed(text.as_bytes());
+    }
+    let mut out = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        if is_stand_in(c) {
+            let unit = c as u32 - STAND_IN_BASE + 0xD800;
+            out.extend_from_slice(&[
+                0xE0 | (unit >> 12) as u8,
+                0x80 | ((unit >> 6) & 0x3F) as u8,
+                0x80 | (unit & 0x3F) as u8,
+            ]);
+        } else {
+            let mut buf = [0; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// `text` with stand-ins replaced by U+FFFD, for object keys, which must
+/// be valid UTF-8.
+pub fn replace_stand_ins(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_stand_in) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(
+        text.chars()
+            .map(|c| {
+                if is_stand_in(c) {
+                    char::REPLACEMENT_CHARACTER
+                } else {
+                    c
+                }
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lone_surrogates_are_replaced() {
+        let text = r#"["\ud800", "\ud83d\ude00", "\u12"]"#;
+        let mut offsets = Vec::new();
+        assert_eq!(
+            rewrite(text, Surrogates::Replace, |at| offsets.push(at)).unwrap(),
+            "[\"\u{fffd}\", \"\\ud83d\\ude00\", \"\u{fffd}\"]"
+        );
+        assert_eq!(offsets, vec![2, 28]);
+    }
+
+    #[test]
+    fn truncated_escapes_end_the_scan() {
+        for text in ["\"", "\"\\", "\"\\u", "\"\\ud800\\"] {
+            assert!(rewrite(text, Surrogates::Replace, |_| {}).is_ok());
+            assert!(rewrite(text, Surrogates::Wtf8, |_| {}).is_ok());
+        }
+    }
+}
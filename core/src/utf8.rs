@@ -0,0 +1,38 @@
+//! SIMD UTF-8 validation.
+//!
+//! With the `portable` feature, validation uses the standard library's
+//! scalar implementation instead.
+//!
+//! Usage:
+//! ```php
+//! if (!Sift::validateUtf8($input)) {
+//!     $offset = Sift::invalidUtf8Offset($input); // first malformed byte
+//! }
+//! ```
+
+/// Whether `bytes` are valid UTF-8. Faster than `validate` because
+/// the error position isn't tracked.
+#[cfg(not(feature = "portable"))]
+pub fn is_valid(bytes: &[u8]) -> bool {
+    simdutf8::basic::from_utf8(bytes).is_ok()
+}
+
+/// Whether `bytes` are valid UTF-8.
+#[cfg(feature = "portable")]
+pub fn is_valid(bytes: &[u8]) -> bool {
+    std::str::from_utf8(bytes).is_ok()
+}
+
+/// `bytes` as text, or the offset of the first byte that isn't part of a
+/// valid UTF-8 sequence.
+#[cfg(not(feature = "portable"))]
+pub fn validate(bytes: &[u8]) -> Result<&str, usize> {
+    simdutf8::compat::from_utf8(bytes).map_err(|e| e.valid_up_to())
+}
+
+/// `bytes` as text, or the offset of the first byte that isn't part of a
+/// valid UTF-8 sequence.
+#[cfg(feature = "portable")]
+pub fn validate(bytes: &[u8]) -> Result<&str, usize> {
+    std::str::from_utf8(bytes).map_err(|e| e.valid_up_to())
+}
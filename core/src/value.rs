@@ -0,0 +1,151 @@
+//! Plain Rust values built by a conversion, for tests, fuzzing and callers
+//! without PHP.
+//!
+//! Usage:
+//! ```
+//! use sift_core::options::DecodeOptions;
+//! use sift_core::value::{self, Value};
+//!
+//! let user = value::decode(r#"{"id": 7}"#, &DecodeOptions::default()).unwrap();
+//! assert_eq!(user, Value::Object(vec![("id".to_string(), Value::Int(7))]));
+//! ```
+
+use crate::convert;
+use crate::diagnostics;
+use crate::error::Error;
+use crate::options::{BigNumbers, DecodeOptions, Leaves};
+use crate::sink::{Key, ValueSink};
+
+/// A decoded value, shaped like the PHP value the extension would build.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    /// WTF-8 text, under `'surrogates' => 'wtf8'`
+    Bytes(Vec<u8>),
+    /// The literal of a number kept exact under the `big_numbers` option
+    BigNumber(String),
+    Array(Vec<Value>),
+    /// Members in insertion order; integer keys are stored as their digits
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Member `name` of an object.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == name).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// Builds `Value`s. Containers beyond the hydration cutoff become their raw
+/// text under both `Leaves` modes, as there is no Query to defer them to.
+#[derive(Debug, Default)]
+pub struct Builder;
+
+impl ValueSink for Builder {
+    type Value = Value;
+    type Error = Error;
+
+    fn null(&mut self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn bool(&mut self, b: bool) -> Result<Value, Error> {
+        Ok(Value::Bool(b))
+    }
+
+    fn int(&mut self, n: i64) -> Result<Value, Error> {
+        Ok(Value::Int(n))
+    }
+
+    fn float(&mut self, n: f64) -> Result<Value, Error> {
+        Ok(Value::Float(n))
+    }
+
+    fn string(&mut self, s: &str) -> Result<Value, Error> {
+        Ok(Value::String(s.to_string()))
+    }
+
+    fn bytes(&mut self, b: &[u8]) -> Result<Value, Error> {
+        Ok(Value::Bytes(b.to_vec()))
+    }
+
+    fn big_number(&mut self, raw: &str, _mode: BigNumbers) -> Result<Value, Error> {
+        Ok(Value::BigNumber(raw.to_string()))
+    }
+
+    fn leaf(&mut self, raw: &str, _leaves: Leaves) -> Result<Value, Error> {
+        Ok(Value::String(raw.to_string()))
+    }
+
+    fn array(&mut self, capacity: usize) -> Result<Value, Error> {
+        Ok(Value::Array(Vec::with_capacity(capacity)))
+    }
+
+    fn push(&mut self, array: &mut Value, value: Value) -> Result<(), Error> {
+        match array {
+            Value::Array(items) => {
+                items.push(value);
+                Ok(())
+            }
+            _ => Err(Error::TypeError(
+                "Failed to push array item: not an array".to_string(),
+            )),
+        }
+    }
+
+    fn object(&mut self, capacity: usize) -> Result<Value, Error> {
+        Ok(Value::Object(Vec::with_capacity(capacity)))
+    }
+
+    fn contains(&self, object: &Value, key: &Key<'_>) -> bool {
+        object.get(&key_text(key)).is_some()
+    }
+
+    fn insert(&mut self, object: &mut Value, key: Key<'_>, value: Value) -> Result<(), Error> {
+        let Value::Object(members) = object else {
+            return Err(Error::TypeError(
+                "Failed to insert object key: not an object".to_string(),
+            ));
+        };
+        let name = key_text(&key);
+        match members.iter_mut().find(|(k, _)| *k == name) {
+            Some((_, existing)) => *existing = value,
+            None => members.push((name, value)),
+        }
+        Ok(())
+    }
+
+    fn nested<'v>(&self, object: &'v mut Value, key: &Key<'_>) -> Option<&'v mut Value> {
+        let Value::Object(members) = object else {
+            return None;
+        };
+        let name = key_text(key);
+        members
+            .iter_mut()
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| v)
+            .filter(|v| matches!(v, Value::Object(_)))
+    }
+}
+
+/// The member name `key` is stored under.
+fn key_text(key: &Key<'_>) -> String {
+    match key {
+        Key::Index(index) => index.to_string(),
+        Key::Name(name) => name.to_string(),
+    }
+}
+
+/// Decodes `json` into a `Value` as `Sift::decode()` would, as a call of
+/// its own for `diagnostics::last_warnings()`.
+pub fn decode(json: &str, options: &DecodeOptions) -> Result<Value, Error> {
+    diagnostics::start_call();
+    convert::decode(json, options, &mut Builder)
+}
@@ -0,0 +1,70 @@
+//! Full decodes through `sift_core`, with the walk the extension uses.
+//!
+//! The lazy path trusts the unchecked iterators because
+//! `parser::document_root` has run sonic-rs's checked skip over the whole
+//! root first. That validation covers the JSON grammar, string escapes and
+//! control characters, but not the range of numbers or the pairing of
+//! `\u` surrogates: those are left to the conversion. So with the
+//! `surrogates` and `big_numbers` options that handle both, a document
+//! the root accepts must convert without error (short of the depth limit).
+//! The DOM and lazy paths must also agree, and no option may panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sift_core::options::{BigNumbers, DecodeOptions, DuplicateKeys, Strategy, Surrogates};
+use sift_core::parser::{self, MAX_DEPTH};
+use sift_core::value::{self, Builder};
+use sift_core::{convert, Error};
+
+/// Whether `json` may nest deeper than `MAX_DEPTH`, counting brackets
+/// inside strings too.
+fn may_be_too_deep(json: &str) -> bool {
+    json.bytes().filter(|b| matches!(b, b'[' | b'{')).count() > MAX_DEPTH
+}
+
+fuzz_target!(|data: &[u8]| {
+    let tolerant = DecodeOptions {
+        surrogates: Surrogates::Replace,
+        big_numbers: BigNumbers::BcMath,
+        ..Default::default()
+    };
+    let Ok(text) = parser::decode_text(data, &tolerant).map(|text| text.text) else {
+        return;
+    };
+    let deep = may_be_too_deep(&text);
+
+    let dom_accepts = parser::parse_dom(&text).is_ok();
+    let root = parser::document_root(&text, &tolerant);
+    if dom_accepts {
+        assert!(root.is_ok(), "DOM parser accepted what the checked skip rejected");
+    }
+    if root.is_ok() && !deep {
+        if let Err(e) = convert::decode_lazy(&text, &tolerant, &mut Builder) {
+            panic!("validated document failed to convert: {}", e);
+        }
+    }
+
+    // Both strategies build the same values from what the DOM accepts
+    let dom = value::decode(&text, &DecodeOptions { strategy: Strategy::Dom, ..Default::default() });
+    let lazy = value::decode(&text, &DecodeOptions { strategy: Strategy::Lazy, ..Default::default() });
+    match (dom, lazy) {
+        (Ok(dom), Ok(lazy)) => assert_eq!(dom, lazy, "DOM and lazy conversions differ"),
+        (Ok(_), Err(e)) => panic!("lazy conversion failed where the DOM succeeded: {}", e),
+        (Err(_), _) => {}
+    }
+
+    // Every other option must fail cleanly, if at all
+    for options in [
+        DecodeOptions { strict: true, ..Default::default() },
+        DecodeOptions { assoc: false, warnings: true, ..Default::default() },
+        DecodeOptions { presize: true, duplicate_keys: DuplicateKeys::Error, ..Default::default() },
+        DecodeOptions { big_numbers: BigNumbers::Gmp, bigint_as_string: true, ..Default::default() },
+        DecodeOptions { surrogates: Surrogates::Wtf8, depth: 4, ..Default::default() },
+    ] {
+        let Ok(text) = parser::decode_text(data, &options).map(|text| text.text) else {
+            continue;
+        };
+        let _: Result<_, Error> = value::decode(&text, &options);
+    }
+});
@@ -0,0 +1,34 @@
+//! Encoding through `sift_core::encode`, with the string escaping the
+//! extension's encoder uses.
+//!
+//! Whatever decodes must encode, and the encoded text must decode back to
+//! the same value.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sift_core::options::DecodeOptions;
+use sift_core::{encode, value};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(json) = std::str::from_utf8(data) else {
+        return;
+    };
+    let options = DecodeOptions::default();
+    let Ok(decoded) = value::decode(json, &options) else {
+        return;
+    };
+    let encoded = encode::encode(&decoded).expect("decoded value encodes");
+    let again = value::decode(&encoded, &options).expect("encoded text decodes");
+    assert_eq!(again, decoded, "round trip changed the value: {}", encoded);
+
+    // Escaped strings are literals that unescape to the original
+    if let value::Value::String(text) = &decoded {
+        let literal = encode::escape_string(text.as_bytes(), true).expect("valid UTF-8");
+        assert_eq!(parser_unescape(&literal), *text);
+    }
+});
+
+fn parser_unescape(literal: &str) -> String {
+    sift_core::parser::unescape_string(literal, true).expect("escaped literal unescapes")
+}
@@ -0,0 +1,43 @@
+//! Byte-preserving replacement through `sift_core::patch`, behind
+//! `Sift::patch()` and field encryption.
+//!
+//! Input is a pointer, a newline, a replacement value, a newline, then a
+//! document. Patching a valid document must give a valid document with
+//! the replacement at the pointer and every byte outside the old value
+//! unchanged.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sift_core::{parser, patch, Error};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut parts = input.splitn(3, '\n');
+    let (Some(pointer), Some(replacement), Some(json)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return;
+    };
+    let Ok(replacement) = parser::parse_dom(replacement).map(|_| replacement.trim()) else {
+        return;
+    };
+
+    let patched = patch::replace_at(json, &[pointer], |_, _| Ok::<_, Error>(replacement.to_string()));
+    if parser::parse_dom(json).is_err() {
+        return;
+    }
+    let Ok(patched) = patched else {
+        return;
+    };
+    let old = parser::lazy_at(json, pointer).expect("replaced value exists");
+    let start = old.as_raw_str().as_ptr() as usize - json.as_ptr() as usize;
+    let end = start + old.as_raw_str().len();
+    assert_eq!(&patched[..start], &json[..start], "bytes before the value changed");
+    assert_eq!(&patched[start + replacement.len()..], &json[end..], "bytes after the value changed");
+
+    parser::parse_dom(&patched).expect("patched document is valid JSON");
+    let new = parser::lazy_at(&patched, pointer).expect("pointer still resolves");
+    assert_eq!(new.as_raw_str(), replacement);
+});
@@ -0,0 +1,49 @@
+//! Pointer resolution through `sift_core::parser`, behind `Sift::get()`.
+//!
+//! Input is a pointer, a newline, then a document. `parser::lazy_at` must
+//! find a value exactly when a lookup in the parsed DOM does, and the
+//! value it finds must decode through the core like the DOM's.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sift_core::arena;
+use sift_core::options::{DecodeOptions, Strategy};
+use sift_core::{parser, value};
+use sonic_rs::JsonValueTrait;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Some((pointer, json)) = input.split_once('\n') else {
+        return;
+    };
+    let Ok(dom) = parser::parse_dom(json) else {
+        // Invalid documents must still be rejected without panicking
+        let _ = parser::lazy_at(json, pointer);
+        return;
+    };
+    let Ok(nodes) = arena::with_arena(|bump| {
+        parser::pointer_nodes(pointer, bump).map(|nodes| nodes.to_vec())
+    }) else {
+        assert!(parser::lazy_at(json, pointer).is_err(), "malformed pointer resolved");
+        return;
+    };
+
+    // Which duplicate key wins may differ, but both must find a value
+    let lazy = parser::lazy_at(json, pointer).ok();
+    let expected = dom.pointer(&nodes);
+    match (lazy, expected) {
+        (Some(lazy), Some(_)) => {
+            let options = DecodeOptions { strategy: Strategy::Lazy, ..Default::default() };
+            value::decode(lazy.as_raw_str(), &options).expect("lazy lookup returned valid JSON");
+        }
+        (None, None) => {}
+        (lazy, expected) => panic!(
+            "lazy lookup found {:?}, DOM lookup found a value: {}",
+            lazy.map(|v| v.as_raw_str().to_string()),
+            expected.is_some()
+        ),
+    }
+});
@@ -0,0 +1,347 @@
+//! Rule-driven anonymization for `Sift::anonymize()`.
+//!
+//! Usage:
+//! ```php
+//! $safe = Sift::anonymize($archived, [
+//!     'email'            => 'hash',
+//!     '/users/*/name'    => ['fake', $names],
+//!     '/users/*/balance' => 'zero',
+//!     '/users/*/address' => 'null',
+//! ]);
+//! ```
+//!
+//! A rule key starting with `/` is a JSON pointer in which `*` matches any
+//! one segment; any other key is a member name matched at every depth. The
+//! first matching rule wins. Actions:
+//!
+//! - `'hash'` / `['hash', $salt]`: strings and numbers become the hex
+//!   SHA-256 of their text (after the salt)
+//! - `['fake', $words]`: strings become a word from the list, picked by a
+//!   hash of the string so equal values get equal replacements
+//! - `'zero'`: numbers become `0`, strings `""`, booleans `false`
+//! - `'null'`: the value, containers included, becomes `null`
+//!
+//! Other than `'null'`, a rule matching an array or object applies to the
+//! scalars inside it. The document is walked once and replaced values are
+//! spliced into a verbatim copy of the input, as in `patch`, so whitespace,
+//! key order and untouched values keep their bytes.
+
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::parser::{self, MAX_DEPTH, MAX_POINTER_SEGMENTS};
+use ext_php_rs::types::{ArrayKey, ZendHashTable, Zval};
+use sha2::{Digest, Sha256};
+use sonic_rs::{to_array_iter, to_object_iter, JsonType, JsonValueTrait, LazyValue};
+use std::ops::Range;
+
+/// What a rule does to the values it matches.
+#[derive(Debug)]
+enum Action {
+    Hash(String),
+    Fake(Vec<String>),
+    Zero,
+    Null,
+}
+
+/// Where a rule applies.
+#[derive(Debug)]
+enum Target {
+    /// Pointer segments; `None` is `*`
+    Pointer(Vec<Option<String>>),
+    /// A member name at any depth
+    Name(String),
+}
+
+#[derive(Debug)]
+struct Rule {
+    target: Target,
+    action: Action,
+}
+
+/// One path segment of the walk.
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+impl Step {
+    fn matches(&self, segment: &str) -> bool {
+        match self {
+            Step::Key(key) => key == segment,
+            Step::Index(index) => segment.parse::<usize>().ok() == Some(*index),
+        }
+    }
+}
+
+fn rule_error(rule: &str, message: impl std::fmt::Display) -> SonicError {
+    SonicError::InvalidOption(format!(
+        "Invalid anonymization rule '{}': {}",
+        rule, message
+    ))
+}
+
+/// Compile the rules array.
+fn compile(rules: &ZendHashTable) -> Result<Vec<Rule>, SonicError> {
+    rules
+        .iter()
+        .map(|(key, action)| {
+            let key = match key {
+                ArrayKey::String(key) => key,
+                ArrayKey::Long(index) => index.to_string(),
+            };
+            Ok(Rule {
+                target: compile_target(&key)?,
+                action: compile_action(&key, action)?,
+            })
+        })
+        .collect()
+}
+
+fn compile_target(key: &str) -> Result<Target, SonicError> {
+    let Some(rest) = key.strip_prefix('/') else {
+        return Ok(Target::Name(key.to_string()));
+    };
+    let segments: Vec<Option<String>> = rest
+        .split('/')
+        .map(|part| match part {
+            "*" => None,
+            _ => Some(part.replace("~1", "/").replace("~0", "~")),
+        })
+        .collect();
+    if segments.len() > MAX_POINTER_SEGMENTS {
+        return Err(rule_error(
+            key,
+            format!("too many pointer segments (max {})", MAX_POINTER_SEGMENTS),
+        ));
+    }
+    Ok(Target::Pointer(segments))
+}
+
+fn compile_action(key: &str, action: &Zval) -> Result<Action, SonicError> {
+    if let Some(name) = action.str() {
+        return match name {
+            "hash" => Ok(Action::Hash(String::new())),
+            "zero" => Ok(Action::Zero),
+            "null" => Ok(Action::Null),
+            "fake" => Err(rule_error(
+                key,
+                "'fake' needs a word list: ['fake', $words]",
+            )),
+            _ => Err(rule_error(
+                key,
+                format!(
+                    "unknown action '{}' (expected hash, fake, zero or null)",
+                    name
+                ),
+            )),
+        };
+    }
+    let parts: Vec<&Zval> = action.array().map_or_else(Vec::new, |parts| {
+        parts.iter().map(|(_, part)| part).collect()
+    });
+    match (parts.first().and_then(|name| name.str()), parts.get(1)) {
+        (Some("hash"), Some(salt)) if parts.len() == 2 => salt
+            .string()
+            .map(Action::Hash)
+            .ok_or_else(|| rule_error(key, "the hash salt must be a string")),
+        (Some("fake"), Some(words)) if parts.len() == 2 => {
+            let words: Option<Vec<String>> = words
+                .array()
+                .map(|words| words.iter().map(|(_, word)| word.string()).collect());
+            match words {
+                Some(words) if !words.is_empty() => Ok(Action::Fake(words)),
+                _ => Err(rule_error(
+                    key,
+                    "the fake word list must be a non-empty array of strings",
+                )),
+            }
+        }
+        _ => Err(rule_error(
+            key,
+            "expected 'hash', 'zero', 'null', ['hash', $salt] or ['fake', $words]",
+        )),
+    }
+}
+
+/// Anonymize `json` by `rules` (`pattern => action`).
+pub fn anonymize(json: &str, rules: &ZendHashTable) -> Result<String, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+    let rules = compile(rules)?;
+    // Subtrees no rule can reach are skipped, so validate them up front
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+
+    let mut walk = Walk {
+        json,
+        rules: &rules,
+        descend_all: rules
+            .iter()
+            .any(|rule| matches!(rule.target, Target::Name(_))),
+        path: Vec::new(),
+        splices: Vec::new(),
+    };
+    walk.visit(&parser::lazy_at(json, "")?, 0)?;
+
+    let mut out = String::with_capacity(json.len());
+    let mut copied = 0;
+    for (range, text) in &walk.splices {
+        out.push_str(&json[copied..range.start]);
+        out.push_str(text);
+        copied = range.end;
+    }
+    out.push_str(&json[copied..]);
+    Ok(out)
+}
+
+struct Walk<'j, 'r> {
+    json: &'j str,
+    rules: &'r [Rule],
+    /// Name rules can match anywhere, so every container is entered
+    descend_all: bool,
+    path: Vec<Step>,
+    /// Replacements in document order
+    splices: Vec<(Range<usize>, String)>,
+}
+
+impl<'r> Walk<'_, 'r> {
+    /// Whether pointer `segments` match the current path, or with
+    /// `below`, start with it.
+    fn follows(&self, segments: &[Option<String>], below: bool) -> bool {
+        let fits = if below {
+            segments.len() > self.path.len()
+        } else {
+            segments.len() == self.path.len()
+        };
+        fits && segments.iter().zip(&self.path).all(|(segment, step)| {
+            segment
+                .as_deref()
+                .map_or(true, |segment| step.matches(segment))
+        })
+    }
+
+    /// The first rule matching the current path.
+    fn rule(&self) -> Option<&'r Rule> {
+        self.rules.iter().find(|rule| match &rule.target {
+            Target::Name(name) => matches!(self.path.last(), Some(Step::Key(key)) if key == name),
+            Target::Pointer(segments) => self.follows(segments, false),
+        })
+    }
+
+    /// Whether a rule can match below the current path.
+    fn reaches_below(&self) -> bool {
+        self.descend_all
+            || self.rules.iter().any(|rule| match &rule.target {
+                Target::Pointer(segments) => self.follows(segments, true),
+                Target::Name(_) => false,
+            })
+    }
+
+    fn visit(&mut self, value: &LazyValue, depth: usize) -> Result<(), SonicError> {
+        match self.rule() {
+            Some(rule) => self.apply(value, &rule.action, depth),
+            None if self.reaches_below() => self.children(value, depth, None),
+            None => Ok(()),
+        }
+    }
+
+    /// Apply `action` to `value`, or to the scalars inside it.
+    fn apply(
+        &mut self,
+        value: &LazyValue,
+        action: &'r Action,
+        depth: usize,
+    ) -> Result<(), SonicError> {
+        let text = match (action, value.get_type()) {
+            (Action::Null, _) => "null".to_string(),
+            (_, JsonType::Object | JsonType::Array) => {
+                return self.children(value, depth, Some(action))
+            }
+            (Action::Hash(salt), JsonType::String) => {
+                hex_string(&digest(salt, value.as_str().unwrap_or_default()))
+            }
+            (Action::Hash(salt), JsonType::Number) => {
+                hex_string(&digest(salt, value.as_raw_str().trim()))
+            }
+            (Action::Fake(words), JsonType::String) => {
+                let hash = digest("", value.as_str().unwrap_or_default());
+                let mut seed = [0u8; 8];
+                seed.copy_from_slice(&hash[..8]);
+                let word = &words[(u64::from_le_bytes(seed) % words.len() as u64) as usize];
+                let mut text = String::new();
+                encoder::write_string(&mut text, word.as_bytes())?;
+                text
+            }
+            (Action::Zero, JsonType::Number) => "0".to_string(),
+            (Action::Zero, JsonType::String) => "\"\"".to_string(),
+            (Action::Zero, JsonType::Boolean) => "false".to_string(),
+            _ => return Ok(()),
+        };
+        // Values are slices of the document
+        let raw = value.as_raw_str().trim();
+        let start = raw.as_ptr() as usize - self.json.as_ptr() as usize;
+        self.splices.push((start..start + raw.len(), text));
+        Ok(())
+    }
+
+    /// Visit the members or elements of `value`, applying `action` to all
+    /// of them when given.
+    fn children(
+        &mut self,
+        value: &LazyValue,
+        depth: usize,
+        action: Option<&'r Action>,
+    ) -> Result<(), SonicError> {
+        if depth >= MAX_DEPTH {
+            return Err(SonicError::ParseError(format!(
+                "Maximum nesting depth ({}) exceeded",
+                MAX_DEPTH
+            )));
+        }
+        let raw = value.as_raw_str();
+        let visit = |walk: &mut Self, step: Step, item: &LazyValue| {
+            walk.path.push(step);
+            let result = match action {
+                Some(action) => walk.apply(item, action, depth + 1),
+                None => walk.visit(item, depth + 1),
+            };
+            walk.path.pop();
+            result
+        };
+        match value.get_type() {
+            JsonType::Object => {
+                for member in to_object_iter(raw) {
+                    let (key, item) = member?;
+                    visit(self, Step::Key(key.to_string()), &item)?;
+                }
+            }
+            JsonType::Array => {
+                for (index, item) in to_array_iter(raw).enumerate() {
+                    visit(self, Step::Index(index), &item?)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// SHA-256 of `salt` followed by `text`.
+fn digest(salt: &str, text: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(text.as_bytes());
+    hasher.finalize().into()
+}
+
+/// `bytes` as a quoted lowercase hex JSON string.
+fn hex_string(bytes: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut text = String::with_capacity(bytes.len() * 2 + 2);
+    text.push('"');
+    for byte in bytes {
+        text.push(HEX[usize::from(byte >> 4)] as char);
+        text.push(HEX[usize::from(byte & 0xf)] as char);
+    }
+    text.push('"');
+    text
+}
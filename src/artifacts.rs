@@ -0,0 +1,115 @@
+//! Compiled schemas and extraction templates kept in APCu for
+//! `Sift::cacheCompiled()`.
+//!
+//! Usage:
+//! ```php
+//! // bootstrap.php
+//! Sift::cacheCompiled();
+//!
+//! // Compiled by the first worker to see this template, read back by
+//! // the others until the next deploy
+//! $extractor = Sift::compileExtractor(['id' => '/id', 'tags' => '/tags/*/name']);
+//! ```
+//!
+//! Entries are keyed by the kind of artifact, the extension version and a
+//! SHA-256 of the source (the schema text, or the template as JSON), so
+//! an edited schema or an upgraded extension never reads a stale entry.
+//! They are stored without a TTL; APCu evicts them under memory pressure
+//! and a restart clears them. A missing, unusable or corrupt entry means
+//! compiling as usual, so the cache never changes a result or an error.
+//!
+//! Template pointers are cached as part of their extractor. A lone pointer
+//! is not cached: splitting it is cheaper than an APCu round trip.
+
+use crate::errors::SonicError;
+use ext_php_rs::types::ZendCallable;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Prefix of every APCu key the cache writes.
+const KEY_PREFIX: &str = "sift:artifact";
+
+/// Whether `Sift::cacheCompiled()` turned the cache on. Process-wide: a
+/// worker keeps it for every later request.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Something compiled from a source that can be stored as text.
+pub trait Artifact: Sized {
+    /// Names the kind in the cache key.
+    const KIND: &'static str;
+
+    /// The compiled form as text for the cache.
+    fn to_artifact(&self) -> Result<String, SonicError>;
+
+    /// Rebuild the compiled form from its cached text.
+    fn from_artifact(text: &str) -> Result<Self, SonicError>;
+}
+
+/// Turn the cache on or off; whether it is now in use, which needs APCu
+/// to be loaded and enabled.
+pub fn set_enabled(enabled: bool) -> bool {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    enabled && apcu_enabled()
+}
+
+/// Whether the cache is on.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// The artifact compiled from `source`: from APCu if the cache is on and
+/// holds it, otherwise from `compile`, stored for the next request.
+pub fn fetch_or_compile<T: Artifact>(
+    source: &[u8],
+    compile: impl FnOnce() -> Result<T, SonicError>,
+) -> Result<T, SonicError> {
+    if !enabled() {
+        return compile();
+    }
+    let key = cache_key(T::KIND, source);
+    if let Some(artifact) = fetch(&key).and_then(|text| T::from_artifact(&text).ok()) {
+        return Ok(artifact);
+    }
+    let artifact = compile()?;
+    // A failed store only means compiling again next time
+    if let Ok(text) = artifact.to_artifact() {
+        store(&key, &text);
+    }
+    Ok(artifact)
+}
+
+/// `sift:artifact:<kind>:<version>:<sha256 of source>`.
+fn cache_key(kind: &str, source: &[u8]) -> String {
+    let digest = Sha256::digest(source);
+    let mut key = format!("{}:{}:{}:", KEY_PREFIX, kind, env!("CARGO_PKG_VERSION"));
+    for byte in digest {
+        let _ = write!(key, "{:02x}", byte);
+    }
+    key
+}
+
+/// Whether `apcu_enabled()` exists and says APCu can be used.
+fn apcu_enabled() -> bool {
+    ZendCallable::try_from_name("apcu_enabled")
+        .ok()
+        .and_then(|enabled| enabled.try_call(vec![]).ok())
+        .and_then(|enabled| enabled.bool())
+        .unwrap_or(false)
+}
+
+/// The text stored under `key`, if APCu is loaded and has it.
+fn fetch(key: &str) -> Option<String> {
+    let value = ZendCallable::try_from_name("apcu_fetch")
+        .ok()?
+        .try_call(vec![&key])
+        .ok()?;
+    value.str().map(str::to_string)
+}
+
+/// Store `text` under `key`, if APCu is loaded.
+fn store(key: &str, text: &str) {
+    if let Ok(apcu_store) = ZendCallable::try_from_name("apcu_store") {
+        let _ = apcu_store.try_call(vec![&key, &text]);
+    }
+}
@@ -0,0 +1,135 @@
+//! Base64 decoding for `Query::base64()`, and encoding for the
+//! ciphertexts of `Sift::encryptFields()`.
+//!
+//! Decodes straight from the JSON input into the output buffer, so a blob
+//! is copied once into Rust and once into the PHP string.
+
+use crate::errors::SonicError;
+
+/// Which 62nd/63rd characters the encoded text uses (RFC 4648).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Alphabet {
+    /// `+` and `/`
+    #[default]
+    Standard,
+    /// `-` and `_`, as in URLs and JWTs
+    UrlSafe,
+}
+
+impl Alphabet {
+    /// Parses the PHP-facing alphabet name.
+    pub fn from_name(name: &str) -> Result<Self, SonicError> {
+        match name {
+            "standard" => Ok(Alphabet::Standard),
+            "url" => Ok(Alphabet::UrlSafe),
+            _ => Err(SonicError::InvalidOption(
+                "base64 alphabet must be one of 'standard', 'url'".to_string(),
+            )),
+        }
+    }
+
+    fn symbols(self) -> &'static [u8; 64] {
+        match self {
+            Alphabet::Standard => {
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/"
+            }
+            Alphabet::UrlSafe => {
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_"
+            }
+        }
+    }
+
+    fn value(self, byte: u8) -> Option<u8> {
+        match (byte, self) {
+            (b'A'..=b'Z', _) => Some(byte - b'A'),
+            (b'a'..=b'z', _) => Some(byte - b'a' + 26),
+            (b'0'..=b'9', _) => Some(byte - b'0' + 52),
+            (b'+', Alphabet::Standard) | (b'-', Alphabet::UrlSafe) => Some(62),
+            (b'/', Alphabet::Standard) | (b'_', Alphabet::UrlSafe) => Some(63),
+            _ => None,
+        }
+    }
+}
+
+/// Why a string isn't valid base64.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A byte outside the alphabet, at this offset.
+    InvalidCharacter(usize),
+    /// Misplaced or excess `=`, a truncated final group, or a final group
+    /// whose unused bits aren't zero.
+    InvalidPadding,
+}
+
+impl DecodeError {
+    pub fn describe(&self) -> String {
+        match self {
+            DecodeError::InvalidCharacter(offset) => {
+                format!("invalid character at offset {}", offset)
+            }
+            DecodeError::InvalidPadding => "invalid padding".to_string(),
+        }
+    }
+}
+
+/// Decodes `input`. Padding is optional, but when present it must
+/// complete the final 4-character group. Whitespace is not skipped.
+pub fn decode(input: &str, alphabet: Alphabet) -> Result<Vec<u8>, DecodeError> {
+    let bytes = input.as_bytes();
+    let data = bytes
+        .strip_suffix(b"==")
+        .or_else(|| bytes.strip_suffix(b"="))
+        .unwrap_or(bytes);
+    if data.len() != bytes.len() && bytes.len() % 4 != 0 {
+        return Err(DecodeError::InvalidPadding);
+    }
+    if data.len() % 4 == 1 {
+        return Err(DecodeError::InvalidPadding);
+    }
+
+    let mut out = Vec::with_capacity(data.len() / 4 * 3 + 2);
+    let mut acc: u32 = 0;
+    for (i, &byte) in data.iter().enumerate() {
+        let Some(value) = alphabet.value(byte) else {
+            return Err(if byte == b'=' {
+                DecodeError::InvalidPadding
+            } else {
+                DecodeError::InvalidCharacter(i)
+            });
+        };
+        acc = (acc << 6) | u32::from(value);
+        if i % 4 == 3 {
+            out.extend_from_slice(&acc.to_be_bytes()[1..]);
+            acc = 0;
+        }
+    }
+
+    // 2 or 3 leftover characters carry 1 or 2 bytes; the rest of their
+    // bits must be zero or the text isn't the canonical encoding.
+    match data.len() % 4 {
+        2 if acc & 0xF == 0 => out.push((acc >> 4) as u8),
+        3 if acc & 0x3 == 0 => out.extend_from_slice(&((acc >> 2) as u16).to_be_bytes()),
+        0 => {}
+        _ => return Err(DecodeError::InvalidPadding),
+    }
+    Ok(out)
+}
+
+/// Encodes `input` with padding.
+pub fn encode(input: &[u8], alphabet: Alphabet) -> String {
+    let symbols = alphabet.symbols();
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for group in input.chunks(3) {
+        let mut bytes = [0u8; 3];
+        bytes[..group.len()].copy_from_slice(group);
+        let acc = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= group.len() {
+                out.push(symbols[((acc >> (18 - 6 * i)) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,228 @@
+//! Built-in micro-benchmark for `Sift::bench()`.
+//!
+//! Usage:
+//! ```php
+//! $report = Sift::bench($payload, ['iterations' => 500]);
+//! printf("%s: decode %.1f MB/s, %.1fx json_decode\n",
+//!     $report['simd'], $report['results']['decode']['mb_per_s'], $report['speedup']);
+//! ```
+
+use crate::errors::SonicError;
+use crate::info;
+use crate::options::{DecodeOptions, PhpOptions};
+use crate::parser;
+use crate::query::Query;
+use ext_php_rs::error::Error;
+use ext_php_rs::types::{ArrayKey, ZendCallable, ZendHashTable, Zval};
+use sonic_rs::{to_array_iter_unchecked, to_object_iter_unchecked};
+use sonic_rs::{JsonValueTrait, PointerNode};
+use std::hint::black_box;
+use std::time::Instant;
+
+/// Records in the bundled fixture.
+const FIXTURE_RECORDS: usize = 1_000;
+
+/// How a benchmark run is set up.
+#[derive(Clone, Debug)]
+struct BenchOptions {
+    /// Timed calls per operation
+    iterations: usize,
+    /// Pointer looked up by `get` and `query`; by default the last
+    /// top-level entry, so the lookup skips over the rest of the document
+    pointer: Option<String>,
+}
+
+impl BenchOptions {
+    /// Parse an options array such as `['iterations' => 500]`.
+    fn from_array(options: Option<&ZendHashTable>) -> Result<Self, SonicError> {
+        let mut opts = Self {
+            iterations: 100,
+            pointer: None,
+        };
+        let Some(options) = options else {
+            return Ok(opts);
+        };
+
+        for (key, value) in options.iter() {
+            let name = match key {
+                ArrayKey::String(name) => name,
+                ArrayKey::Long(idx) => {
+                    return Err(SonicError::InvalidOption(format!(
+                        "Option keys must be strings, got {}",
+                        idx
+                    )))
+                }
+            };
+            match name.as_str() {
+                "iterations" => {
+                    opts.iterations = value
+                        .long()
+                        .and_then(|n| usize::try_from(n).ok())
+                        .filter(|n| *n >= 1)
+                        .ok_or_else(|| {
+                            SonicError::InvalidOption(
+                                "Option 'iterations' must be an integer of at least 1".to_string(),
+                            )
+                        })?
+                }
+                "pointer" => {
+                    opts.pointer = Some(value.str().map(str::to_string).ok_or_else(|| {
+                        SonicError::InvalidOption("Option 'pointer' must be a string".to_string())
+                    })?)
+                }
+                _ => {
+                    return Err(SonicError::InvalidOption(format!(
+                        "Unknown option '{}'",
+                        name
+                    )))
+                }
+            }
+        }
+        Ok(opts)
+    }
+}
+
+/// The bundled fixture: a list of user records, as an API might return.
+fn fixture() -> String {
+    let users: Vec<String> = (0..FIXTURE_RECORDS)
+        .map(|i| {
+            format!(
+                r#"{{"id":{i},"name":"User {i}","email":"user{i}@example.com","active":{},"score":{}.5,"tags":["a","b","c"]}}"#,
+                i % 2 == 0,
+                i % 100
+            )
+        })
+        .collect();
+    format!(
+        r#"{{"users":[{}],"total":{}}}"#,
+        users.join(","),
+        FIXTURE_RECORDS
+    )
+}
+
+/// Pointer to the last top-level entry of `json`, or the root if it has
+/// none.
+fn default_pointer(json: &str) -> Result<String, SonicError> {
+    let root = sonic_rs::get(json, &[] as &[PointerNode])?;
+    if root.is_object() {
+        let mut last = None;
+        // SAFETY: we've verified this is an object via is_object()
+        for entry in unsafe { to_object_iter_unchecked(root.as_raw_str()) } {
+            last = Some(entry?.0);
+        }
+        Ok(last.map_or_else(String::new, |key| {
+            format!("/{}", key.replace('~', "~0").replace('/', "~1"))
+        }))
+    } else if root.is_array() {
+        // SAFETY: we've verified this is an array via is_array()
+        let count = unsafe { to_array_iter_unchecked(root.as_raw_str()) }.count();
+        Ok(match count {
+            0 => String::new(),
+            n => format!("/{}", n - 1),
+        })
+    } else {
+        Ok(String::new())
+    }
+}
+
+/// Run `op` once untimed, then `iterations` times, and report
+/// `['ms' => total, 'per_op_us' => mean, 'mb_per_s' => throughput]`.
+fn measure<T>(
+    bytes: usize,
+    iterations: usize,
+    mut op: impl FnMut() -> Result<T, SonicError>,
+) -> Result<(f64, ZendHashTable), SonicError> {
+    black_box(op()?);
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(op()?);
+    }
+    let seconds = start.elapsed().as_secs_f64();
+
+    let ms = seconds * 1e3;
+    let per_op_us = seconds * 1e6 / iterations as f64;
+    let mb_per_s = if seconds > 0.0 {
+        (bytes * iterations) as f64 / seconds / 1e6
+    } else {
+        0.0
+    };
+    let mut timing = ZendHashTable::new();
+    timing
+        .insert("ms", ms)
+        .and_then(|_| timing.insert("per_op_us", per_op_us))
+        .and_then(|_| timing.insert("mb_per_s", mb_per_s))
+        .map_err(insert_error)?;
+    Ok((ms, timing))
+}
+
+/// Error for a failed insert into a result array.
+fn insert_error(e: Error) -> SonicError {
+    SonicError::TypeError(format!("Failed to insert object key: {}", e))
+}
+
+/// Time `decode`, `get` and `query` (a pointer lookup read back as raw
+/// JSON) on `json`, or on the bundled fixture, alongside `json_decode`
+/// when it is available. Decode options are the configured defaults.
+pub fn bench(json: Option<&str>, options: Option<&ZendHashTable>) -> Result<Zval, SonicError> {
+    let opts = BenchOptions::from_array(options)?;
+    let bundled;
+    let json = match json {
+        Some(json) => json,
+        None => {
+            bundled = fixture();
+            &bundled
+        }
+    };
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+    let pointer = match opts.pointer {
+        Some(pointer) => pointer,
+        None => default_pointer(json)?,
+    };
+    let decode_options = DecodeOptions::configured();
+    let (bytes, iterations) = (json.len(), opts.iterations);
+
+    let mut results = ZendHashTable::new();
+    let (decode_ms, decode) = measure(bytes, iterations, || parser::decode(json, &decode_options))?;
+    results.insert("decode", decode).map_err(insert_error)?;
+    let (_, get) = measure(bytes, iterations, || {
+        parser::get_by_pointer(json, &pointer, &decode_options)
+    })?;
+    results.insert("get", get).map_err(insert_error)?;
+    let (_, query) = measure(bytes, iterations, || {
+        Query::new(json.to_string(), decode_options)
+            .pointer(&pointer)?
+            .raw()
+    })?;
+    results.insert("query", query).map_err(insert_error)?;
+
+    let mut speedup = Zval::new();
+    if let Ok(json_decode) = ZendCallable::try_from_name("json_decode") {
+        let (baseline_ms, baseline) = measure(bytes, iterations, || {
+            json_decode
+                .try_call(vec![&json, &true])
+                .map_err(|e| SonicError::TypeError(format!("json_decode failed: {}", e)))
+        })?;
+        results
+            .insert("json_decode", baseline)
+            .map_err(insert_error)?;
+        if decode_ms > 0.0 {
+            speedup.set_double(baseline_ms / decode_ms);
+        }
+    }
+
+    let mut report = ZendHashTable::new();
+    report
+        .insert("simd", info::compiled_simd())
+        .and_then(|_| report.insert("cpu_simd", info::cpu_simd()))
+        .and_then(|_| report.insert("bytes", bytes as i64))
+        .and_then(|_| report.insert("iterations", iterations as i64))
+        .and_then(|_| report.insert("pointer", pointer))
+        .and_then(|_| report.insert("results", results))
+        .and_then(|_| report.insert("speedup", speedup))
+        .map_err(insert_error)?;
+    let mut zval = Zval::new();
+    zval.set_hashtable(report);
+    Ok(zval)
+}
@@ -0,0 +1,143 @@
+//! Exact big-number decoding into `BCMath\Number` or GMP objects, and
+//! their encoding back as JSON numbers.
+//!
+//! Usage:
+//! ```php
+//! $data = Sift::decode('{"amount": 12345678901234567890.12}', ['big_numbers' => 'bcmath']);
+//! $data['amount']; // BCMath\Number('12345678901234567890.12')
+//! Sift::encodeLines([$data])['lines']; // {"amount":12345678901234567890.12}
+//! ```
+
+use crate::errors::SonicError;
+use crate::options::BigNumbers;
+use ext_php_rs::convert::IntoZval;
+use ext_php_rs::types::{ZendCallable, ZendObject, Zval};
+use ext_php_rs::zend::ClassEntry;
+
+/// Largest decimal exponent expanded into plain notation. BCMath\Number
+/// doesn't accept exponents, and expanding 1e999999999 would allocate a
+/// gigabyte, so beyond this the number is rejected.
+const MAX_EXPANDED_EXPONENT: i64 = 4096;
+
+/// Builds the object a number literal decodes to under `mode`. Callers
+/// ask only for numbers the mode holds (`BigNumbers::holds`).
+pub fn to_zval(raw: &str, mode: BigNumbers) -> Result<Zval, SonicError> {
+    match mode {
+        BigNumbers::Gmp => gmp_number(raw),
+        _ => bcmath_number(raw),
+    }
+}
+
+/// Whether `object` is a `BCMath\Number` or GMP number.
+pub fn is_number(object: &ZendObject) -> bool {
+    ["BCMath\\Number", "GMP"]
+        .into_iter()
+        .any(|name| ClassEntry::try_find(name).is_some_and(|ce| object.instance_of(ce)))
+}
+
+/// The JSON number a `BCMath\Number` (`(string) $n`) or GMP object
+/// (`gmp_strval($n)`) encodes as, so exact numbers decoded with
+/// `big_numbers` encode back to the same digits; None for other values.
+pub fn literal(value: &Zval) -> Result<Option<String>, SonicError> {
+    let Some(object) = value.object() else {
+        return Ok(None);
+    };
+    let is = |name: &str| ClassEntry::try_find(name).is_some_and(|ce| object.instance_of(ce));
+    let digits = if is("BCMath\\Number") {
+        object.try_call_method("__toString", vec![]).map_err(|e| {
+            SonicError::TypeError(format!("Failed to convert BCMath\\Number: {}", e))
+        })?
+    } else if is("GMP") {
+        ZendCallable::try_from_name("gmp_strval")
+            .map_err(|_| SonicError::TypeError("gmp_strval() is unavailable".to_string()))?
+            .try_call(vec![value])
+            .map_err(|e| SonicError::TypeError(format!("Failed to convert GMP number: {}", e)))?
+    } else {
+        return Ok(None);
+    };
+    digits
+        .string()
+        .map(Some)
+        .ok_or_else(|| SonicError::TypeError("Big number did not convert to a string".to_string()))
+}
+
+/// `new BCMath\Number($decimal)`; available from PHP 8.4 with ext-bcmath.
+fn bcmath_number(raw: &str) -> Result<Zval, SonicError> {
+    let ce = ClassEntry::try_find("BCMath\\Number").ok_or_else(|| {
+        SonicError::InvalidOption(
+            "big_numbers 'bcmath' requires PHP 8.4+ with the bcmath extension".to_string(),
+        )
+    })?;
+    let decimal = plain_decimal(raw)?;
+    let object = ZendObject::new(ce);
+    object
+        .try_call_method("__construct", vec![&decimal])
+        .map_err(|e| SonicError::TypeError(format!("Failed to create BCMath\\Number: {}", e)))?;
+    object
+        .into_zval(false)
+        .map_err(|e| SonicError::TypeError(e.to_string()))
+}
+
+/// `gmp_init($integer)`.
+fn gmp_number(raw: &str) -> Result<Zval, SonicError> {
+    let gmp_init = ZendCallable::try_from_name("gmp_init").map_err(|_| {
+        SonicError::InvalidOption("big_numbers 'gmp' requires the gmp extension".to_string())
+    })?;
+    gmp_init
+        .try_call(vec![&raw])
+        .map_err(|e| SonicError::TypeError(format!("Failed to create GMP number: {}", e)))
+}
+
+/// Rewrites a JSON number literal without an exponent, as BCMath expects:
+/// "1.5e3" becomes "1500", "-25E-4" becomes "-0.0025".
+fn plain_decimal(raw: &str) -> Result<String, SonicError> {
+    let Some(e_pos) = raw.find(['e', 'E']) else {
+        return Ok(raw.to_string());
+    };
+    let (mantissa, exponent) = (&raw[..e_pos], &raw[e_pos + 1..]);
+    let exponent: i64 = exponent
+        .parse()
+        .ok()
+        .filter(|e: &i64| e.abs() <= MAX_EXPANDED_EXPONENT)
+        .ok_or_else(|| {
+            SonicError::TypeError(format!(
+                "Number exponent out of range for exact decimal conversion (max {})",
+                MAX_EXPANDED_EXPONENT
+            ))
+        })?;
+
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", mantissa),
+    };
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", int_part, frac_part);
+    // Position of the decimal point within `digits` after shifting
+    let point = int_part.len() as i64 + exponent;
+
+    let (int_digits, frac_digits) = if point <= 0 {
+        (
+            "0".to_string(),
+            format!("{}{}", "0".repeat((-point) as usize), digits),
+        )
+    } else if point as usize >= digits.len() {
+        (
+            format!("{}{}", digits, "0".repeat(point as usize - digits.len())),
+            String::new(),
+        )
+    } else {
+        let (i, f) = digits.split_at(point as usize);
+        (i.to_string(), f.to_string())
+    };
+
+    let int_digits = match int_digits.trim_start_matches('0') {
+        "" => "0",
+        trimmed => trimmed,
+    };
+    let frac_digits = frac_digits.trim_end_matches('0');
+    if frac_digits.is_empty() {
+        Ok(format!("{}{}", sign, int_digits))
+    } else {
+        Ok(format!("{}{}.{}", sign, int_digits, frac_digits))
+    }
+}
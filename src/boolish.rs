@@ -0,0 +1,112 @@
+//! Tolerant boolean extraction for APIs that encode booleans loosely.
+//!
+//! Usage:
+//! ```php
+//! $active = Sift::query($json)->get('active')->boolish();   // "yes", 1, "TRUE", true...
+//! $flag = $q->boolish(['enabled'], ['disabled']);           // custom table
+//! ```
+
+use crate::errors::SonicError;
+use ext_php_rs::types::{ZendHashTable, Zval};
+use sonic_rs::{JsonValueTrait, LazyValue};
+
+/// Values read as true when no custom table is given (case-insensitive).
+const DEFAULT_TRUTHY: &[&str] = &["true", "1", "yes", "y", "on"];
+
+/// Values read as false when no custom table is given (case-insensitive).
+const DEFAULT_FALSY: &[&str] = &["false", "0", "no", "n", "off"];
+
+/// Truthy/falsy lookup table. Entries are stored trimmed and lowercased.
+pub struct BoolTable {
+    truthy: Vec<String>,
+    falsy: Vec<String>,
+}
+
+impl BoolTable {
+    /// Build a table from optional PHP lists of strings/ints, falling back
+    /// to the defaults for either side that isn't given.
+    pub fn from_arrays(
+        truthy: Option<&ZendHashTable>,
+        falsy: Option<&ZendHashTable>,
+    ) -> Result<Self, SonicError> {
+        let table = Self {
+            truthy: table_entries(truthy, DEFAULT_TRUTHY)?,
+            falsy: table_entries(falsy, DEFAULT_FALSY)?,
+        };
+        if let Some(both) = table.truthy.iter().find(|t| table.falsy.contains(t)) {
+            return Err(SonicError::InvalidOption(format!(
+                "'{}' is listed as both truthy and falsy",
+                both
+            )));
+        }
+        Ok(table)
+    }
+
+    /// Read a JSON value as a boolean. JSON booleans always map to
+    /// themselves; strings and numbers are looked up in the table by their
+    /// text. Anything else, or text in neither list, is ambiguous.
+    pub fn read(&self, lazy: &LazyValue) -> Result<bool, SonicError> {
+        if let Some(b) = lazy.as_bool() {
+            return Ok(b);
+        }
+        let token = if let Some(s) = lazy.as_str() {
+            normalize(s)
+        } else if lazy.is_number() {
+            lazy.as_raw_str().to_string()
+        } else {
+            return Err(SonicError::TypeError(format!(
+                "Ambiguous boolean value: {} cannot be read as a boolean",
+                type_name(lazy)
+            )));
+        };
+
+        if self.truthy.contains(&token) {
+            Ok(true)
+        } else if self.falsy.contains(&token) {
+            Ok(false)
+        } else {
+            Err(SonicError::TypeError(format!(
+                "Ambiguous boolean value '{}'",
+                token
+            )))
+        }
+    }
+}
+
+/// Collect table entries from a PHP list, or use `defaults` when absent.
+fn table_entries(
+    entries: Option<&ZendHashTable>,
+    defaults: &[&str],
+) -> Result<Vec<String>, SonicError> {
+    let Some(entries) = entries else {
+        return Ok(defaults.iter().map(|s| s.to_string()).collect());
+    };
+    entries.iter().map(|(_, value)| table_entry(value)).collect()
+}
+
+/// A single table entry: a string (matched case-insensitively) or an int.
+fn table_entry(value: &Zval) -> Result<String, SonicError> {
+    if let Some(s) = value.str() {
+        Ok(normalize(s))
+    } else if let Some(n) = value.long() {
+        Ok(n.to_string())
+    } else {
+        Err(SonicError::InvalidOption(
+            "Boolean table entries must be strings or integers".to_string(),
+        ))
+    }
+}
+
+fn normalize(s: &str) -> String {
+    s.trim().to_lowercase()
+}
+
+fn type_name(lazy: &LazyValue) -> &'static str {
+    if lazy.is_null() {
+        "null"
+    } else if lazy.is_array() {
+        "array"
+    } else {
+        "object"
+    }
+}
@@ -0,0 +1,198 @@
+//! Framework serializer bridges backed by the encoder and decoder.
+//!
+//! Usage:
+//! ```php
+//! // Symfony: the encoder interfaces are userland, so declare them once
+//! final class SiftJsonEncoder extends Sift\Bridge\SymfonyEncoder implements EncoderInterface, DecoderInterface {}
+//! // services.yaml: App\Serializer\SiftJsonEncoder: { tags: [serializer.encoder] }
+//!
+//! // Laravel: a cast class, usable as is
+//! protected $casts = ['settings' => Sift\Bridge\LaravelJson::class];
+//! protected $casts = ['payload' => Sift\Bridge\LaravelJson::class . ':query'];
+//! ```
+//!
+//! Both only translate between the frameworks' calling conventions and
+//! `encoder` / `parser`; the output is what `Sift::decode()` and the
+//! encoder produce for the same values.
+
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::guard::guard;
+use crate::options::{DecodeOptions, PhpOptions};
+use crate::parser;
+use crate::query::Query;
+use ext_php_rs::binary_slice::BinarySlice;
+use ext_php_rs::convert::{FromZval, IntoZval};
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendHashTable, Zval};
+
+/// The only format the Symfony encoder handles.
+const JSON_FORMAT: &str = "json";
+
+/// SymfonyEncoder - `encode()` / `decode()` with the signatures of
+/// Symfony's `EncoderInterface` and `DecoderInterface` for the `json`
+/// format.
+#[php_class(name = "Sift\\Bridge\\SymfonyEncoder")]
+#[derive(Debug)]
+pub struct SymfonyEncoder {
+    /// Options values are decoded with
+    options: DecodeOptions,
+}
+
+#[php_impl]
+impl SymfonyEncoder {
+    /// Create an encoder; `options` take the same keys as
+    /// `Sift::decode()` and apply to `decode()`.
+    #[constructor]
+    pub fn __construct(options: Option<&ZendHashTable>) -> Result<Self, SonicError> {
+        guard(|| {
+            Ok(Self {
+                options: DecodeOptions::from_array(options)?,
+            })
+        })
+    }
+
+    /// Encode `data`, as normalized by the serializer, to JSON.
+    pub fn encode(
+        &self,
+        data: &Zval,
+        _format: &str,
+        _context: Option<&ZendHashTable>,
+    ) -> Result<String, SonicError> {
+        guard(|| {
+            let mut out = String::new();
+            encoder::encode_into(&mut out, data)?;
+            Ok(out)
+        })
+    }
+
+    /// Decode `data`. Objects become arrays, as Symfony's `JsonEncoder`
+    /// decodes them, unless the context sets `json_decode_associative`
+    /// to false.
+    pub fn decode(
+        &self,
+        data: BinarySlice<u8>,
+        _format: &str,
+        context: Option<&ZendHashTable>,
+    ) -> Result<Zval, SonicError> {
+        guard(|| {
+            let assoc = context
+                .and_then(|context| context.get("json_decode_associative"))
+                .and_then(Zval::bool)
+                .unwrap_or(true);
+            let options = DecodeOptions {
+                assoc,
+                ..self.options
+            };
+            let json = parser::decode_text(&data, &options)?;
+            parser::decode(&json, &options)
+        })
+    }
+
+    /// Whether `format` is `json`. The context is accepted for the
+    /// context-aware variants of the interfaces.
+    pub fn supports_encoding(&self, format: &str, _context: Option<&ZendHashTable>) -> bool {
+        format == JSON_FORMAT
+    }
+
+    /// Whether `format` is `json`.
+    pub fn supports_decoding(&self, format: &str, _context: Option<&ZendHashTable>) -> bool {
+        format == JSON_FORMAT
+    }
+}
+
+/// What a `LaravelJson` cast turns the stored JSON into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CastMode {
+    /// Associative arrays, like Laravel's `array` cast
+    Array,
+    /// `stdClass` objects, like Laravel's `object` cast
+    Object,
+    /// A lazy `Sift\Query` over the stored text
+    Query,
+}
+
+/// LaravelJson - an Eloquent attribute cast with the `get()` / `set()`
+/// methods of Laravel's `CastsAttributes`.
+#[php_class(name = "Sift\\Bridge\\LaravelJson")]
+#[derive(Debug)]
+pub struct LaravelJson {
+    mode: CastMode,
+}
+
+#[php_impl]
+impl LaravelJson {
+    /// Create a cast; Laravel passes the argument after the colon in
+    /// `LaravelJson::class . ':object'`: `array` (the default), `object`
+    /// or `query`.
+    #[constructor]
+    pub fn __construct(mode: Option<String>) -> Result<Self, SonicError> {
+        guard(|| {
+            let mode = match mode.as_deref() {
+                None | Some("array") => CastMode::Array,
+                Some("object") => CastMode::Object,
+                Some("query") => CastMode::Query,
+                Some(other) => {
+                    return Err(SonicError::InvalidOption(format!(
+                        "Cast mode must be 'array', 'object' or 'query', got '{}'",
+                        other
+                    )))
+                }
+            };
+            Ok(Self { mode })
+        })
+    }
+
+    /// Decode the stored JSON; null stays null.
+    pub fn get(
+        &self,
+        _model: &Zval,
+        _key: &str,
+        value: &Zval,
+        _attributes: &Zval,
+    ) -> Result<Zval, SonicError> {
+        guard(|| {
+            let Some(bytes) = value.binary_slice::<u8>() else {
+                return Ok(Zval::new());
+            };
+            let options = DecodeOptions {
+                assoc: self.mode != CastMode::Object,
+                ..DecodeOptions::configured()
+            };
+            match self.mode {
+                CastMode::Query => {
+                    let json = parser::input_text(bytes, &options)?;
+                    Query::new(json.into_owned(), options)
+                        .into_zval(false)
+                        .map_err(|e| SonicError::TypeError(e.to_string()))
+                }
+                _ => {
+                    let json = parser::decode_text(bytes, &options)?;
+                    parser::decode(&json, &options)
+                }
+            }
+        })
+    }
+
+    /// Encode the value for storage; null stays null, and a `Sift\Query`
+    /// is stored as the JSON text it points at.
+    pub fn set(
+        &self,
+        _model: &Zval,
+        _key: &str,
+        value: &Zval,
+        _attributes: &Zval,
+    ) -> Result<Option<String>, SonicError> {
+        guard(|| {
+            if value.is_null() {
+                return Ok(None);
+            }
+            if let Some(query) = <&Query>::from_zval(value) {
+                return query.raw_text().map(Some);
+            }
+            let mut out = String::new();
+            encoder::encode_into(&mut out, value)?;
+            Ok(Some(out))
+        })
+    }
+}
@@ -0,0 +1,109 @@
+//! Buffer limits for the stream classes, `FrameReader` and `Lines`.
+//!
+//! Usage:
+//! ```php
+//! $reader = new Sift\FrameReader(null, ['max_bytes' => 1 << 20]);
+//! $lines = Sift::lines($path, buffer: ['max_bytes' => 4 << 20, 'when_full' => 'error']);
+//! ```
+//!
+//! `max_bytes` caps the input a stream holds that its consumer hasn't
+//! taken yet. `when_full` says what happens when the producer would go
+//! past it: `'block'` makes the producer wait until the consumer catches
+//! up, `'error'` throws `Sift\InputTooLargeException` (with the limit as
+//! its `limit`). A FrameReader's producer is the code calling `push()`, so
+//! it can only error.
+
+use crate::errors::SonicError;
+use ext_php_rs::types::{ArrayKey, ZendHashTable};
+
+/// What a full buffer does to its producer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WhenFull {
+    /// Wait until the consumer frees enough space
+    Block,
+    /// Fail with `InputTooLarge`
+    Error,
+}
+
+impl WhenFull {
+    pub fn from_name(name: &str) -> Result<Self, SonicError> {
+        match name {
+            "block" => Ok(WhenFull::Block),
+            "error" => Ok(WhenFull::Error),
+            _ => Err(SonicError::InvalidOption(format!(
+                "Unknown when_full policy '{}', expected 'block' or 'error'",
+                name
+            ))),
+        }
+    }
+}
+
+/// How much a stream may buffer, and what happens beyond that.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferLimit {
+    /// Bytes of input held for the consumer at most
+    pub max_bytes: usize,
+    pub when_full: WhenFull,
+}
+
+impl BufferLimit {
+    /// Parse a buffer array such as `['max_bytes' => 1 << 20]`; missing
+    /// keys keep the values of `default`.
+    pub fn from_array(
+        buffer: Option<&ZendHashTable>,
+        default: BufferLimit,
+    ) -> Result<Self, SonicError> {
+        let mut limit = default;
+        let Some(buffer) = buffer else {
+            return Ok(limit);
+        };
+
+        for (key, value) in buffer.iter() {
+            let name = match key {
+                ArrayKey::String(name) => name,
+                ArrayKey::Long(idx) => {
+                    return Err(SonicError::InvalidOption(format!(
+                        "Buffer option keys must be strings, got {}",
+                        idx
+                    )))
+                }
+            };
+            match name.as_str() {
+                "max_bytes" => {
+                    limit.max_bytes = value
+                        .long()
+                        .and_then(|n| usize::try_from(n).ok())
+                        .filter(|n| *n >= 1)
+                        .ok_or_else(|| {
+                            SonicError::InvalidOption(
+                                "Buffer option 'max_bytes' must be a positive integer".to_string(),
+                            )
+                        })?
+                }
+                "when_full" => {
+                    let policy = value.str().ok_or_else(|| {
+                        SonicError::InvalidOption(
+                            "Buffer option 'when_full' must be 'block' or 'error'".to_string(),
+                        )
+                    })?;
+                    limit.when_full = WhenFull::from_name(policy)?;
+                }
+                _ => {
+                    return Err(SonicError::InvalidOption(format!(
+                        "Unknown buffer option '{}'",
+                        name
+                    )))
+                }
+            }
+        }
+        Ok(limit)
+    }
+
+    /// The error for a buffer that would hold `size` bytes.
+    pub fn exceeded(&self, size: usize) -> SonicError {
+        SonicError::InputTooLarge {
+            size,
+            limit: self.max_bytes,
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Chunked hydration of large arrays for `Query::chunks()`.
+//!
+//! Usage:
+//! ```php
+//! foreach (Sift::query($export)->get('rows')->chunks(1000) as $rows) {
+//!     $db->insertBatch($rows);   // At most 1000 hydrated rows alive at once
+//! }
+//! ```
+
+use crate::errors::SonicError;
+use crate::guard::guard;
+use crate::leaks::Live;
+use crate::parser;
+use crate::query::Query;
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendHashTable, Zval};
+use ext_php_rs::zend::ce;
+use sonic_rs::{LazyValue, PointerNode};
+
+/// Where reading resumes, as a byte offset into the JSON.
+#[derive(Clone, Copy, Debug)]
+enum Cursor {
+    /// Just past the array's opening '['
+    Start(usize),
+    /// Just past the last element read
+    After(usize),
+    /// The closing ']', at this offset, has been read
+    Done(usize),
+}
+
+impl Cursor {
+    /// The byte offset reading resumes from.
+    fn offset(self) -> usize {
+        match self {
+            Cursor::Start(pos) | Cursor::After(pos) | Cursor::Done(pos) => pos,
+        }
+    }
+}
+
+/// Chunks - iterates over the array at a Query's path, yielding lists of
+/// up to `size` hydrated elements.
+/// Each chunk is read from where the previous one stopped, so the whole
+/// array is scanned once and only the current chunk exists as PHP values.
+#[php_class(name = "Sift\\Chunks")]
+#[implements(ce::iterator())]
+#[derive(Debug)]
+pub struct Chunks {
+    /// The array being chunked
+    query: Query,
+    /// Maximum elements per chunk
+    size: usize,
+    /// Where iteration starts, from a previous `tell()`; the first
+    /// element when None
+    resume: Option<usize>,
+    /// Where the next chunk starts; None until the array is resolved
+    cursor: Option<Cursor>,
+    /// The Query layer the array was resolved in, which cursor offsets
+    /// point into
+    layer: usize,
+    /// The chunk `current()` returns; None once the array is exhausted
+    chunk: Option<Zval>,
+    /// Index of the current chunk
+    key: i64,
+    /// Counts this object for `sift.leak_check` (see `leaks.rs`)
+    live: Live,
+}
+
+impl Chunks {
+    pub fn new(query: Query, size: usize, resume: Option<usize>) -> Self {
+        Self {
+            query,
+            size,
+            resume,
+            cursor: None,
+            layer: 0,
+            chunk: None,
+            key: 0,
+            live: Live::new("Sift\\Chunks"),
+        }
+    }
+
+    /// Resolve the array and read the first chunk, unless already done.
+    fn start(&mut self) -> Result<(), SonicError> {
+        if self.cursor.is_none() {
+            let (layer, start, end) = self.query.array_bounds()?;
+            let cursor = match self.resume {
+                None => Cursor::Start(start),
+                Some(pos) if pos == start => Cursor::Start(start),
+                Some(pos) if pos > start && pos <= end => Cursor::After(pos),
+                Some(pos) => {
+                    return Err(SonicError::InvalidOption(format!(
+                        "Offset {} is outside the array (bytes {} to {})",
+                        pos, start, end
+                    )))
+                }
+            };
+            self.layer = layer;
+            self.cursor = Some(cursor);
+            self.key = 0;
+            self.read_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Replace the current chunk with up to `size` further elements.
+    fn read_chunk(&mut self) -> Result<(), SonicError> {
+        let Some(mut cursor) = self.cursor else {
+            return Ok(());
+        };
+        let json = self.query.layer_json(self.layer);
+        let options = self.query.options();
+
+        let mut list = ZendHashTable::new();
+        for _ in 0..self.size {
+            let Some(element) = next_element(json, &mut cursor)? else {
+                break;
+            };
+            list.push(parser::lazyvalue_to_zval(element, options)?)
+                .map_err(|e| SonicError::TypeError(format!("Failed to push array item: {}", e)))?;
+        }
+        self.cursor = Some(cursor);
+
+        self.chunk = if list.is_empty() {
+            None
+        } else {
+            let mut zval = Zval::new();
+            zval.set_hashtable(list);
+            Some(zval)
+        };
+        Ok(())
+    }
+}
+
+#[php_impl]
+impl Chunks {
+    /// Start over from the first element of the array, or the offset
+    /// iteration was resumed at.
+    pub fn rewind(&mut self) -> Result<(), SonicError> {
+        guard(|| {
+            self.cursor = None;
+            self.start()
+        })
+    }
+
+    /// Whether there is a current chunk.
+    pub fn valid(&mut self) -> Result<bool, SonicError> {
+        guard(|| {
+            self.start()?;
+            Ok(self.chunk.is_some())
+        })
+    }
+
+    /// The current chunk: a list of up to `size` hydrated elements.
+    /// Returns null once the array is exhausted.
+    pub fn current(&mut self) -> Result<Zval, SonicError> {
+        guard(|| {
+            self.start()?;
+            Ok(self
+                .chunk
+                .as_ref()
+                .map(Zval::shallow_clone)
+                .unwrap_or_default())
+        })
+    }
+
+    /// Index of the current chunk (0, 1, 2, ...), or null once exhausted.
+    pub fn key(&mut self) -> Result<Option<i64>, SonicError> {
+        guard(|| {
+            self.start()?;
+            Ok(self.chunk.as_ref().map(|_| self.key))
+        })
+    }
+
+    /// Move on to the next chunk.
+    pub fn next(&mut self) -> Result<(), SonicError> {
+        guard(|| {
+            self.start()?;
+            if self.chunk.is_some() {
+                self.key += 1;
+                self.read_chunk()?;
+            }
+            Ok(())
+        })
+    }
+
+    /// The byte offset in the document just past the current chunk: pass
+    /// it to `Query::chunks()` to resume after the chunks processed so far.
+    pub fn tell(&mut self) -> Result<i64, SonicError> {
+        guard(|| {
+            self.start()?;
+            Ok(self.cursor.map_or(0, Cursor::offset) as i64)
+        })
+    }
+}
+
+/// Skip JSON whitespace from `pos`.
+fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    while matches!(bytes.get(pos), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+        pos += 1;
+    }
+    pos
+}
+
+/// Read the array element at `cursor` and advance past it, or return
+/// None once the closing ']' is reached.
+fn next_element<'a>(
+    json: &'a str,
+    cursor: &mut Cursor,
+) -> Result<Option<LazyValue<'a>>, SonicError> {
+    let bytes = json.as_bytes();
+    let pos = match *cursor {
+        Cursor::Done(_) => return Ok(None),
+        Cursor::Start(pos) => {
+            let pos = skip_whitespace(bytes, pos);
+            if bytes.get(pos) == Some(&b']') {
+                *cursor = Cursor::Done(pos);
+                return Ok(None);
+            }
+            pos
+        }
+        Cursor::After(pos) => {
+            let pos = skip_whitespace(bytes, pos);
+            match bytes.get(pos) {
+                Some(b',') => skip_whitespace(bytes, pos + 1),
+                Some(b']') => {
+                    *cursor = Cursor::Done(pos);
+                    return Ok(None);
+                }
+                _ => {
+                    return Err(SonicError::ParseError(
+                        "Expected ',' or ']' after array element".to_string(),
+                    )
+                    .at_offset(pos))
+                }
+            }
+        }
+    };
+
+    // sonic_rs::get stops after the first value, so this parses just the
+    // element; its raw text starts at `pos` since whitespace was skipped.
+    let element = sonic_rs::get(&json[pos..], &[] as &[PointerNode])
+        .map_err(|e| SonicError::ParseError(e.to_string()).at_offset(pos + e.offset()))?;
+    *cursor = Cursor::After(pos + element.as_raw_str().len());
+    Ok(Some(element))
+}
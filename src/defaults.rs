@@ -0,0 +1,109 @@
+//! Filling missing object members from a template for
+//! `Sift::defaultsDeep()`.
+//!
+//! Usage:
+//! ```php
+//! $settings = Sift::defaultsDeep($userSettings, file_get_contents('defaults.json'));
+//! ```
+//!
+//! The complement of a merge: a member the document has always wins, even
+//! when it is null, and only keys it lacks are added. Where both sides
+//! hold an object at the same path, the objects are combined the same way,
+//! member by member; anything else is copied from the document as it is.
+//! Combined objects are written compactly, the document's members first
+//! in its order, then the added ones in the defaults' order.
+
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::parser::{self, MAX_DEPTH};
+use faststr::FastStr;
+use sonic_rs::{to_object_iter, JsonValueTrait, LazyValue};
+use std::collections::HashMap;
+
+/// `json` with the members it lacks, at any depth, filled in from
+/// `defaults`.
+pub fn defaults_deep(json: &str, defaults: &str) -> Result<String, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+    parser::check_input_size(defaults)?;
+    // Values are copied unparsed, so validate both up front
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+    sonic_rs::from_str::<sonic_rs::Value>(defaults)
+        .map_err(|e| SonicError::ParseError(format!("Invalid defaults: {}", e)))?;
+
+    let mut out = String::with_capacity(json.len() + defaults.len());
+    write_filled(
+        &mut out,
+        &parser::lazy_at(json, "")?,
+        &parser::lazy_at(defaults, "")?,
+        0,
+    )?;
+    Ok(out)
+}
+
+/// Write `value`, filled from `defaults` if both are objects.
+fn write_filled(
+    out: &mut String,
+    value: &LazyValue,
+    defaults: &LazyValue,
+    depth: usize,
+) -> Result<(), SonicError> {
+    if depth > MAX_DEPTH {
+        return Err(SonicError::ParseError(format!(
+            "Maximum nesting depth ({}) exceeded",
+            MAX_DEPTH
+        )));
+    }
+    if !value.is_object() || !defaults.is_object() {
+        out.push_str(value.as_raw_str().trim());
+        return Ok(());
+    }
+
+    let mut fallbacks: Vec<(FastStr, LazyValue)> = Vec::new();
+    let mut positions: HashMap<FastStr, usize> = HashMap::new();
+    for member in to_object_iter(defaults.as_raw_str()) {
+        let (key, item) = member?;
+        // A repeated key decodes to its last value
+        match positions.get(&key) {
+            Some(&position) => fallbacks[position].1 = item,
+            None => {
+                positions.insert(key.clone(), fallbacks.len());
+                fallbacks.push((key, item));
+            }
+        }
+    }
+
+    let mut used = vec![false; fallbacks.len()];
+    out.push('{');
+    let mut first = true;
+    for member in to_object_iter(value.as_raw_str()) {
+        let (key, item) = member?;
+        if !first {
+            out.push(',');
+        }
+        first = false;
+        encoder::write_string(out, key.as_bytes())?;
+        out.push(':');
+        match positions.get(&key) {
+            Some(&position) => {
+                used[position] = true;
+                write_filled(out, &item, &fallbacks[position].1, depth + 1)?;
+            }
+            None => out.push_str(item.as_raw_str().trim()),
+        }
+    }
+    for ((key, item), used) in fallbacks.iter().zip(used) {
+        if used {
+            continue;
+        }
+        if !first {
+            out.push(',');
+        }
+        first = false;
+        encoder::write_string(out, key.as_bytes())?;
+        out.push(':');
+        out.push_str(item.as_raw_str().trim());
+    }
+    out.push('}');
+    Ok(())
+}
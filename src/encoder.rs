@@ -0,0 +1,228 @@
+//! PHP value to JSON text encoding.
+//!
+//! Usage:
+//! ```php
+//! $result = Sift::encodeLines($rows);
+//! file_put_contents('export.ndjson', $result['lines']);
+//! foreach ($result['errors'] as $position => $message) { /* log bad row */ }
+//! ```
+//!
+//! Output matches `json_encode($value, JSON_UNESCAPED_SLASHES |
+//! JSON_UNESCAPED_UNICODE | JSON_PRESERVE_ZERO_FRACTION)`: strings are
+//! written as UTF-8, and floats keep their fraction so they decode as floats.
+
+use crate::bignum;
+use crate::errors::SonicError;
+use crate::number::{self, FloatNotation};
+use crate::parser::MAX_DEPTH;
+use ext_php_rs::flags::DataType;
+use ext_php_rs::types::{ArrayKey, Iterable, ZendCallable, ZendHashTable, ZendObject, Zval};
+use ext_php_rs::zend::ClassEntry;
+use sift_core::encode;
+use std::fmt::Write;
+
+/// Output buffered before it is handed to a writer callback.
+const FLUSH_SIZE: usize = 64 * 1024;
+
+/// Append `value` as JSON text to `out`. On error `out` may hold a partial
+/// value; callers truncate it.
+pub fn encode_into(out: &mut String, value: &Zval) -> Result<(), SonicError> {
+    write_value(out, value, 0)
+}
+
+/// Append the PHP array `array` as JSON text to `out`, as `encode_into`
+/// would for a value holding it.
+pub fn encode_array(out: &mut String, array: &ZendHashTable) -> Result<(), SonicError> {
+    write_array(out, array, 0)
+}
+
+/// Internal: writes a value with depth tracking to prevent stack overflow.
+fn write_value(out: &mut String, value: &Zval, depth: usize) -> Result<(), SonicError> {
+    if depth > MAX_DEPTH {
+        return Err(SonicError::TypeError(format!(
+            "Maximum nesting depth ({}) exceeded",
+            MAX_DEPTH
+        )));
+    }
+
+    let value = value.dereference();
+    match value.get_type() {
+        DataType::Null | DataType::Undef => out.push_str("null"),
+        DataType::True => out.push_str("true"),
+        DataType::False => out.push_str("false"),
+        DataType::Long => {
+            let _ = write!(out, "{}", value.long().unwrap_or_default());
+        }
+        DataType::Double => {
+            out.push_str(&number::format_float(
+                value.double().unwrap_or_default(),
+                FloatNotation::Auto,
+            )?);
+        }
+        DataType::String => {
+            let bytes = value.zend_str().map(|s| s.as_bytes()).unwrap_or_default();
+            write_string(out, bytes)?;
+        }
+        DataType::Array => {
+            if let Some(array) = value.array() {
+                write_array(out, array, depth)?;
+            }
+        }
+        DataType::Object(_) => {
+            if let Some(digits) = bignum::literal(value)? {
+                // Exact numbers are written as numbers, not as objects
+                out.push_str(&digits);
+            } else if let Some(object) = value.object() {
+                write_object(out, object, depth)?;
+            }
+        }
+        other => {
+            return Err(SonicError::TypeError(format!(
+                "Type {} cannot be encoded as JSON",
+                other
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// Write a PHP string, which must be UTF-8, as a JSON string; see
+/// `sift_core::encode::write_string`.
+pub fn write_string(out: &mut String, bytes: &[u8]) -> Result<(), SonicError> {
+    Ok(encode::write_string(out, bytes)?)
+}
+
+/// A PHP string, which must be UTF-8, as a JSON string literal; see
+/// `sift_core::encode::escape_string`.
+pub fn escape_string(bytes: &[u8], quote: bool) -> Result<String, SonicError> {
+    Ok(encode::escape_string(bytes, quote)?)
+}
+
+/// Write an array: a JSON array if its keys are 0, 1, 2, ... in order,
+/// an object otherwise.
+fn write_array(out: &mut String, array: &ZendHashTable, depth: usize) -> Result<(), SonicError> {
+    if array.has_sequential_keys() {
+        out.push('[');
+        for (i, (_, item)) in array.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write_value(out, item, depth + 1)?;
+        }
+        out.push(']');
+        return Ok(());
+    }
+
+    out.push('{');
+    for (i, (key, item)) in array.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        match key {
+            ArrayKey::Long(index) => {
+                let _ = write!(out, "\"{}\"", index);
+            }
+            ArrayKey::String(name) => write_string(out, name.as_bytes())?,
+        }
+        out.push(':');
+        write_value(out, item, depth + 1)?;
+    }
+    out.push('}');
+    Ok(())
+}
+
+/// Write an object: what `jsonSerialize()` returns for a JsonSerializable,
+/// the public properties otherwise.
+fn write_object(out: &mut String, object: &ZendObject, depth: usize) -> Result<(), SonicError> {
+    let serializable = ClassEntry::try_find("JsonSerializable");
+    if serializable.is_some_and(|ce| object.instance_of(ce)) {
+        let data = object
+            .try_call_method("jsonSerialize", vec![])
+            .map_err(|e| SonicError::TypeError(format!("jsonSerialize() failed: {}", e)))?;
+        return write_value(out, &data, depth + 1);
+    }
+
+    let properties = object
+        .get_properties()
+        .map_err(|e| SonicError::TypeError(format!("Failed to read object properties: {}", e)))?;
+    out.push('{');
+    let mut first = true;
+    for (key, item) in properties.iter() {
+        if matches!(item.dereference().get_type(), DataType::Undef) {
+            // Typed property that was never initialized
+            continue;
+        }
+        let name = match key {
+            // Private and protected names are mangled with a leading NUL
+            ArrayKey::String(name) if name.starts_with('\0') => continue,
+            ArrayKey::String(name) => name,
+            ArrayKey::Long(index) => index.to_string(),
+        };
+        if !first {
+            out.push(',');
+        }
+        first = false;
+        write_string(out, name.as_bytes())?;
+        out.push(':');
+        write_value(out, item, depth + 1)?;
+    }
+    out.push('}');
+    Ok(())
+}
+
+/// Encode each row of `rows` as one line of NDJSON, returning
+/// `['lines' => string, 'count' => int, 'errors' => [position => message]]`.
+/// Rows that fail are left out and reported by position instead of
+/// failing the export. With `write`, output is handed over in chunks of
+/// about `FLUSH_SIZE` bytes and `lines` stays empty.
+pub fn encode_lines(rows: &mut Iterable, write: Option<&ZendCallable>) -> Result<Zval, SonicError> {
+    let iter = rows
+        .iter()
+        .ok_or_else(|| SonicError::TypeError("Rows could not be rewound".to_string()))?;
+
+    let mut out = String::new();
+    let mut count: i64 = 0;
+    let mut errors = ZendHashTable::new();
+    for (position, (_, row)) in iter.enumerate() {
+        let start = out.len();
+        match encode_into(&mut out, row) {
+            Ok(()) => {
+                out.push('\n');
+                count += 1;
+            }
+            Err(e) => {
+                out.truncate(start);
+                errors
+                    .insert_at_index(position as u64, e.to_string())
+                    .map_err(|e| {
+                        SonicError::TypeError(format!("Failed to insert object key: {}", e))
+                    })?;
+            }
+        }
+        if let Some(write) = write.filter(|_| out.len() >= FLUSH_SIZE) {
+            flush(write, &mut out)?;
+        }
+    }
+    if let Some(write) = write.filter(|_| !out.is_empty()) {
+        flush(write, &mut out)?;
+    }
+
+    let mut result = ZendHashTable::new();
+    result
+        .insert("lines", out)
+        .and_then(|_| result.insert("count", count))
+        .and_then(|_| result.insert("errors", errors))
+        .map_err(|e| SonicError::TypeError(format!("Failed to insert object key: {}", e)))?;
+    let mut zval = Zval::new();
+    zval.set_hashtable(result);
+    Ok(zval)
+}
+
+/// Hand the buffered output to the writer callback and clear it.
+fn flush(write: &ZendCallable, out: &mut String) -> Result<(), SonicError> {
+    write
+        .try_call(vec![&out.as_str()])
+        .map_err(|e| SonicError::TypeError(format!("Line writer failed: {}", e)))?;
+    out.clear();
+    Ok(())
+}
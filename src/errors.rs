@@ -1,9 +1,15 @@
 //! Custom PHP Exception mapping for sonic-php errors.
 
+use ext_php_rs::builders::{ClassBuilder, FunctionBuilder};
 use ext_php_rs::exception::PhpException;
-use ext_php_rs::zend::ce;
+use ext_php_rs::flags::{DataType, MethodFlags, PropertyFlags};
+use ext_php_rs::types::{ZendHashTable, ZendObject, Zval};
+use ext_php_rs::zend::{ce, ClassEntry, ExecuteData, ExecutorGlobals};
+use ext_php_rs::{convert::IntoZval, zend_fastcall};
 use thiserror::Error;
 
+pub use sift_core::ErrorContext;
+
 /// Errors that can occur during JSON operations.
 #[derive(Error, Debug)]
 pub enum SonicError {
@@ -21,11 +27,210 @@ pub enum SonicError {
 
     #[error("IO error: {0}")]
     IoError(String),
+
+    #[error("Invalid option: {0}")]
+    InvalidOption(String),
+
+    /// Strict-mode failure mirroring json_decode: carries the JSON_ERROR_*
+    /// code and message PHP's ext/json would report for the same input.
+    #[error("{1}")]
+    JsonError(i32, String),
+
+    /// A string failed a format check (`Query::uuid()`, `email()`, ...).
+    /// The message names the pointer of the offending value.
+    #[error("{0}")]
+    FormatError(String),
+
+    /// The input exceeds the size limit; both values are in bytes.
+    #[error("Input size ({size} bytes) exceeds maximum allowed ({limit} bytes)")]
+    InputTooLarge { size: usize, limit: usize },
+
+    /// A panic caught at an entry point (see `guard.rs`): a bug in the
+    /// extension, not in the input. The message only names where it
+    /// happened.
+    #[error("{0}")]
+    Internal(String),
+
+    /// A handle to a document that has since been released or evicted
+    /// (see `handles.rs`); reading through it would reach another one.
+    #[error("{0}")]
+    StaleHandle(String),
+
+    /// Objects still alive at a `Sift::checkLeaks()` checkpoint (see
+    /// `leaks.rs`).
+    #[error("{0}")]
+    Leaked(String),
+
+    /// Any of the above with structured context attached; see
+    /// [`SonicError::at_pointer`] and friends.
+    #[error("{error}")]
+    WithContext {
+        error: Box<SonicError>,
+        context: ErrorContext,
+    },
+}
+
+/// Stable, machine-readable error codes. Exposed to PHP as
+/// `Sift\Exception::getErrorCode()` / `getCode()` and as class constants.
+/// Values are part of the public API: never renumber, only append.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Parse = 1,
+    InvalidPointer = 2,
+    KeyNotFound = 3,
+    Type = 4,
+    Io = 5,
+    InvalidOption = 6,
+    Format = 7,
+    InputTooLarge = 8,
+    Internal = 9,
+    StaleHandle = 10,
+    Leaked = 11,
+}
+
+impl ErrorCode {
+    const ALL: [ErrorCode; 11] = [
+        ErrorCode::Parse,
+        ErrorCode::InvalidPointer,
+        ErrorCode::KeyNotFound,
+        ErrorCode::Type,
+        ErrorCode::Io,
+        ErrorCode::InvalidOption,
+        ErrorCode::Format,
+        ErrorCode::InputTooLarge,
+        ErrorCode::Internal,
+        ErrorCode::StaleHandle,
+        ErrorCode::Leaked,
+    ];
+
+    /// Name of the `Sift\Exception` class constant.
+    fn constant_name(self) -> &'static str {
+        match self {
+            ErrorCode::Parse => "PARSE_ERROR",
+            ErrorCode::InvalidPointer => "INVALID_POINTER",
+            ErrorCode::KeyNotFound => "KEY_NOT_FOUND",
+            ErrorCode::Type => "TYPE_ERROR",
+            ErrorCode::Io => "IO_ERROR",
+            ErrorCode::InvalidOption => "INVALID_OPTION",
+            ErrorCode::Format => "FORMAT_ERROR",
+            ErrorCode::InputTooLarge => "INPUT_TOO_LARGE",
+            ErrorCode::Internal => "INTERNAL_ERROR",
+            ErrorCode::StaleHandle => "STALE_HANDLE",
+            ErrorCode::Leaked => "LEAKED_OBJECTS",
+        }
+    }
+}
+
+impl SonicError {
+    /// The stable code for this error. Strict-mode `JsonError`s keep
+    /// json_decode's JSON_ERROR_* code instead.
+    pub fn code(&self) -> i32 {
+        let code = match self {
+            SonicError::ParseError(_) => ErrorCode::Parse,
+            SonicError::InvalidPointer(_) => ErrorCode::InvalidPointer,
+            SonicError::KeyNotFound(_) => ErrorCode::KeyNotFound,
+            SonicError::TypeError(_) => ErrorCode::Type,
+            SonicError::IoError(_) => ErrorCode::Io,
+            SonicError::InvalidOption(_) => ErrorCode::InvalidOption,
+            SonicError::FormatError(_) => ErrorCode::Format,
+            SonicError::InputTooLarge { .. } => ErrorCode::InputTooLarge,
+            SonicError::Internal(_) => ErrorCode::Internal,
+            SonicError::StaleHandle(_) => ErrorCode::StaleHandle,
+            SonicError::Leaked(_) => ErrorCode::Leaked,
+            SonicError::JsonError(code, _) => return *code,
+            SonicError::WithContext { error, .. } => return error.code(),
+        };
+        code as i32
+    }
+
+    /// Attaches the JSON pointer of the value involved.
+    pub fn at_pointer(self, pointer: impl Into<String>) -> Self {
+        let pointer = pointer.into();
+        self.with_context(|context| context.pointer = Some(pointer))
+    }
+
+    /// Attaches the byte offset where parsing failed.
+    pub fn at_offset(self, offset: usize) -> Self {
+        self.with_context(|context| context.offset = Some(offset))
+    }
+
+    /// The byte offset attached with `at_offset()`, if any.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            SonicError::WithContext { context, .. } => context.offset,
+            _ => None,
+        }
+    }
+
+    /// Attaches the expected and actual JSON types of a failed extraction.
+    pub fn with_types(self, expected: &'static str, actual: &'static str) -> Self {
+        self.with_context(|context| {
+            context.expected = Some(expected);
+            context.actual = Some(actual);
+        })
+    }
+
+    /// Attaches pointers close to the one that wasn't found.
+    pub fn with_suggestions(self, suggestions: Vec<String>) -> Self {
+        self.with_context(|context| context.suggestions = suggestions)
+    }
+
+    fn with_context(self, update: impl FnOnce(&mut ErrorContext)) -> Self {
+        let (error, mut context) = self.into_parts();
+        update(&mut context);
+        SonicError::WithContext {
+            error: Box::new(error),
+            context,
+        }
+    }
+
+    /// Splits off the context, returning the underlying error.
+    fn into_parts(self) -> (SonicError, ErrorContext) {
+        match self {
+            SonicError::WithContext { error, context } => (*error, context),
+            error => (error, ErrorContext::default()),
+        }
+    }
 }
 
 impl From<sonic_rs::Error> for SonicError {
     fn from(err: sonic_rs::Error) -> Self {
-        SonicError::ParseError(err.to_string())
+        let offset = err.offset();
+        SonicError::ParseError(err.to_string()).at_offset(offset)
+    }
+}
+
+impl From<sift_core::Error> for SonicError {
+    fn from(err: sift_core::Error) -> Self {
+        let (error, context) = err.into_parts();
+        let error = match error {
+            sift_core::Error::ParseError(message) => SonicError::ParseError(message),
+            sift_core::Error::InvalidPointer(message) => SonicError::InvalidPointer(message),
+            sift_core::Error::KeyNotFound(message) => SonicError::KeyNotFound(message),
+            sift_core::Error::TypeError(message) => SonicError::TypeError(message),
+            sift_core::Error::InvalidOption(message) => SonicError::InvalidOption(message),
+            sift_core::Error::JsonError(code, message) => SonicError::JsonError(code, message),
+            sift_core::Error::InputTooLarge { size, limit } => {
+                SonicError::InputTooLarge { size, limit }
+            }
+            // `into_parts()` never leaves the context wrapper on
+            error @ sift_core::Error::WithContext { .. } => {
+                SonicError::Internal(error.to_string())
+            }
+        };
+        match context {
+            Some(context) => SonicError::WithContext {
+                error: Box::new(error),
+                context,
+            },
+            None => error,
+        }
+    }
+}
+
+impl sift_core::AtPointer for SonicError {
+    fn at_pointer(self, pointer: &str) -> Self {
+        SonicError::at_pointer(self, pointer)
     }
 }
 
@@ -37,9 +242,252 @@ impl From<std::io::Error> for SonicError {
 
 impl From<SonicError> for PhpException {
     fn from(err: SonicError) -> Self {
-        PhpException::new(err.to_string(), 0, ce::exception())
+        let code = err.code();
+        let (error, context) = err.into_parts();
+        match error {
+            // Thrown as \JsonException so strict callers can keep their
+            // existing `catch (JsonException $e)` blocks and code checks.
+            SonicError::JsonError(code, message) => PhpException::new(
+                message,
+                code,
+                ClassEntry::try_find("JsonException").unwrap_or_else(ce::exception),
+            ),
+            error => {
+                let class = match error {
+                    SonicError::FormatError(_) => FORMAT_EXCEPTION,
+                    SonicError::Internal(_) => INTERNAL_EXCEPTION,
+                    SonicError::InputTooLarge { .. } => INPUT_TOO_LARGE_EXCEPTION,
+                    SonicError::StaleHandle(_) => STALE_HANDLE_EXCEPTION,
+                    _ => SIFT_EXCEPTION,
+                };
+                let message = error.to_string();
+                match ClassEntry::try_find(class) {
+                    Some(class_entry) => {
+                        let object = exception_object(class_entry, &error, context);
+                        let mut exception = PhpException::new(message, code, class_entry);
+                        exception.set_object(object.ok());
+                        exception
+                    }
+                    None => PhpException::new(message, code, ce::exception()),
+                }
+            }
+        }
+    }
+}
+
+const SIFT_EXCEPTION: &str = "Sift\\Exception";
+const FORMAT_EXCEPTION: &str = "Sift\\FormatException";
+const INPUT_TOO_LARGE_EXCEPTION: &str = "Sift\\InputTooLargeException";
+const INTERNAL_EXCEPTION: &str = "Sift\\InternalException";
+const STALE_HANDLE_EXCEPTION: &str = "Sift\\StaleHandleException";
+
+/// Instantiates a Sift exception with its context properties, and any
+/// properties specific to `error`'s subclass, filled in.
+///
+/// The object is created through Zend's own exception constructor so it
+/// gets its file, line and trace like any exception thrown from PHP. The
+/// properties are protected; they are written with `Sift\Exception` as the
+/// calling scope, as `zend_update_property()` does.
+fn exception_object(
+    class_entry: &ClassEntry,
+    error: &SonicError,
+    context: ErrorContext,
+) -> ext_php_rs::error::Result<Zval> {
+    let mut object = ZendObject::new(class_entry);
+    let scope = ClassEntry::try_find(SIFT_EXCEPTION).unwrap_or(class_entry);
+    let previous = std::mem::replace(
+        &mut ExecutorGlobals::get_mut().fake_scope,
+        scope as *const ClassEntry as *mut ClassEntry,
+    );
+    let written = object
+        .set_property("message", error.to_string())
+        .and_then(|_| object.set_property("code", error.code()))
+        .and_then(|_| object.set_property("pointer", context.pointer))
+        .and_then(|_| object.set_property("offset", context.offset.map(|o| o as i64)))
+        .and_then(|_| object.set_property("expectedType", context.expected))
+        .and_then(|_| object.set_property("actualType", context.actual))
+        .and_then(|_| object.set_property("suggestions", context.suggestions))
+        .and_then(|_| match *error {
+            SonicError::InputTooLarge { size, limit } => object
+                .set_property("size", size as i64)
+                .and_then(|_| object.set_property("limit", limit as i64)),
+            _ => Ok(()),
+        });
+    ExecutorGlobals::get_mut().fake_scope = previous;
+    written?;
+    object.into_zval(false)
+}
+
+/// Registers `Sift\Exception` and its subclasses.
+///
+/// These are built by hand rather than with `#[php_class]`: a class with
+/// Rust-side state replaces the object constructor, which would skip the
+/// file/line/trace initialisation every exception needs. Context lives in
+/// ordinary protected properties instead.
+pub fn register_exceptions() -> ext_php_rs::error::Result<()> {
+    let mut builder = ClassBuilder::new(SIFT_EXCEPTION).extends(ce::exception());
+    for code in ErrorCode::ALL {
+        builder = builder.constant(code.constant_name(), code as i64)?;
+    }
+    for property in [
+        "pointer",
+        "offset",
+        "expectedType",
+        "actualType",
+        "suggestions",
+    ] {
+        builder = builder.property(property, (), PropertyFlags::Protected);
+    }
+    let builder = with_getters(
+        builder,
+        [
+            (
+                FunctionBuilder::new("getErrorCode", get_error_code),
+                DataType::Long,
+                false,
+            ),
+            (
+                FunctionBuilder::new("getPointer", get_pointer),
+                DataType::String,
+                true,
+            ),
+            (
+                FunctionBuilder::new("getOffset", get_offset),
+                DataType::Long,
+                true,
+            ),
+            (
+                FunctionBuilder::new("getExpectedType", get_expected_type),
+                DataType::String,
+                true,
+            ),
+            (
+                FunctionBuilder::new("getActualType", get_actual_type),
+                DataType::String,
+                true,
+            ),
+            (
+                FunctionBuilder::new("getSuggestions", get_suggestions),
+                DataType::Array,
+                false,
+            ),
+        ],
+    )?;
+    let parent: &'static ClassEntry = builder.build()?;
+
+    ClassBuilder::new(FORMAT_EXCEPTION)
+        .extends(parent)
+        .build()?;
+
+    ClassBuilder::new(INTERNAL_EXCEPTION)
+        .extends(parent)
+        .build()?;
+
+    ClassBuilder::new(STALE_HANDLE_EXCEPTION)
+        .extends(parent)
+        .build()?;
+
+    let mut builder = ClassBuilder::new(INPUT_TOO_LARGE_EXCEPTION).extends(parent);
+    for property in ["size", "limit"] {
+        builder = builder.property(property, (), PropertyFlags::Protected);
+    }
+    let builder = with_getters(
+        builder,
+        [
+            (
+                FunctionBuilder::new("getSize", get_size),
+                DataType::Long,
+                false,
+            ),
+            (
+                FunctionBuilder::new("getLimit", get_limit),
+                DataType::Long,
+                false,
+            ),
+        ],
+    )?;
+    builder.build()?;
+    Ok(())
+}
+
+/// Adds public getter methods: each builder with its return type and
+/// whether it may return null.
+fn with_getters<const N: usize>(
+    mut builder: ClassBuilder,
+    getters: [(FunctionBuilder<'static>, DataType, bool); N],
+) -> ext_php_rs::error::Result<ClassBuilder> {
+    for (getter, returns, nullable) in getters {
+        let method = getter.returns(returns, false, nullable).build()?;
+        builder = builder.method(method, MethodFlags::Public);
+    }
+    Ok(builder)
+}
+
+/// Returns the named property of `$this`, or null.
+fn read_property(ex: &mut ExecuteData, retval: &mut Zval, name: &str) {
+    if ex.parser().parse().is_err() {
+        return;
+    }
+    match ex
+        .get_self()
+        .and_then(|this| this.get_property::<&Zval>(name).ok())
+    {
+        Some(value) => *retval = value.shallow_clone(),
+        None => retval.set_null(),
+    }
+}
+
+zend_fastcall! {
+    extern fn get_error_code(ex: &mut ExecuteData, retval: &mut Zval) {
+        read_property(ex, retval, "code");
+    }
+}
+
+zend_fastcall! {
+    extern fn get_pointer(ex: &mut ExecuteData, retval: &mut Zval) {
+        read_property(ex, retval, "pointer");
+    }
+}
+
+zend_fastcall! {
+    extern fn get_offset(ex: &mut ExecuteData, retval: &mut Zval) {
+        read_property(ex, retval, "offset");
+    }
+}
+
+zend_fastcall! {
+    extern fn get_expected_type(ex: &mut ExecuteData, retval: &mut Zval) {
+        read_property(ex, retval, "expectedType");
+    }
+}
+
+zend_fastcall! {
+    extern fn get_actual_type(ex: &mut ExecuteData, retval: &mut Zval) {
+        read_property(ex, retval, "actualType");
+    }
+}
+
+zend_fastcall! {
+    extern fn get_suggestions(ex: &mut ExecuteData, retval: &mut Zval) {
+        read_property(ex, retval, "suggestions");
+        // Exceptions constructed in PHP never had the property set
+        if retval.is_null() {
+            retval.set_hashtable(ZendHashTable::new());
+        }
+    }
+}
+
+zend_fastcall! {
+    extern fn get_size(ex: &mut ExecuteData, retval: &mut Zval) {
+        read_property(ex, retval, "size");
+    }
+}
+
+zend_fastcall! {
+    extern fn get_limit(ex: &mut ExecuteData, retval: &mut Zval) {
+        read_property(ex, retval, "limit");
     }
 }
 
 // Note: Error handling is tested through PHP integration tests in
-// tests/php/SonicTest.php and tests/php/SiftTest.php
\ No newline at end of file
+// tests/php/SonicTest.php and tests/php/SiftTest.php
@@ -0,0 +1,395 @@
+//! Declarative template extraction for `Sift::extract()`.
+//!
+//! Usage:
+//! ```php
+//! $view = Sift::extract($response, [
+//!     'user'  => '/data/user/name',
+//!     'items' => ['/data/items/*', ['id' => '/id', 'qty' => '/qty']],
+//!     'tags'  => '/data/items/*/tag',
+//! ]);
+//! ```
+//!
+//! A template is compiled before the document is touched, so mistakes in
+//! it are reported even for documents that would never reach them. The
+//! document is then parsed once and every pointer of the template is a
+//! walk down that tree; only the values the template names are converted.
+//!
+//! Template forms:
+//! - a string is a JSON pointer, relative to the current value; a `*`
+//!   segment maps the rest of the pointer over every element (or member)
+//!   there, giving a list (or a map keyed like the object)
+//! - `[pattern, template]`, a two-element list whose first element has a
+//!   `*` segment, applies `template` to each value `pattern` selects
+//! - any other array is built key for key from its templates
+//!
+//! A pointer that doesn't resolve yields null, so optional fields don't
+//! need guarding.
+//!
+//! `Sift::compileExtractor()` keeps the compiled template in an
+//! `Extractor`, so worker loops pay for it once:
+//! ```php
+//! $extractor = Sift::compileExtractor(['id' => '/id', 'tags' => '/tags/*/name']);
+//! foreach ($messages as $message) {
+//!     $rows[] = $extractor->extract($message);
+//! }
+//! ```
+
+use crate::artifacts::{self, Artifact};
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::guard::guard;
+use crate::parser::{self, MAX_DEPTH, MAX_POINTER_SEGMENTS};
+use ext_php_rs::error::Error;
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ArrayKey, ZendHashTable, Zval};
+use sonic_rs::{JsonContainerTrait, JsonValueTrait, Value};
+use std::fmt::Write;
+
+/// One segment of a template pointer.
+#[derive(Debug)]
+enum Segment {
+    /// An object key, or an array index when it parses as one
+    Name(String),
+    /// `*`: every element of an array or member of an object
+    Wildcard,
+}
+
+/// A compiled template.
+#[derive(Debug)]
+enum Template {
+    /// The value (or values, with wildcards) at a pointer
+    Pointer(Vec<Segment>),
+    /// `[pattern, template]`: `item` applied to each value `pattern` selects
+    Each {
+        pattern: Vec<Segment>,
+        item: Box<Template>,
+    },
+    /// An array built from the templates under its keys
+    Shape(Vec<(ArrayKey, Template)>),
+}
+
+/// Error for a template that can't be compiled.
+fn template_error(detail: String) -> SonicError {
+    SonicError::InvalidOption(format!("Invalid extraction template: {}", detail))
+}
+
+/// Error for a failed insert into a result array.
+fn insert_error(e: Error) -> SonicError {
+    SonicError::TypeError(format!("Failed to insert object key: {}", e))
+}
+
+/// Split a template pointer into segments.
+fn compile_pointer(pointer: &str) -> Result<Vec<Segment>, SonicError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(template_error(format!(
+            "pointer '{}' must start with '/' or be empty",
+            pointer
+        )));
+    };
+    let segments: Vec<Segment> = rest
+        .split('/')
+        .map(|part| match part {
+            "*" => Segment::Wildcard,
+            _ => Segment::Name(part.replace("~1", "/").replace("~0", "~")),
+        })
+        .collect();
+    if segments.len() > MAX_POINTER_SEGMENTS {
+        return Err(template_error(format!(
+            "pointer '{}' has too many segments (max {})",
+            pointer, MAX_POINTER_SEGMENTS
+        )));
+    }
+    Ok(segments)
+}
+
+/// Compile a template from its PHP form.
+fn compile(template: &Zval, depth: usize) -> Result<Template, SonicError> {
+    if depth > MAX_DEPTH {
+        return Err(template_error(format!(
+            "nesting exceeds maximum depth ({})",
+            MAX_DEPTH
+        )));
+    }
+    if let Some(pointer) = template.str() {
+        return Ok(Template::Pointer(compile_pointer(pointer)?));
+    }
+    match template.array() {
+        Some(entries) => compile_array(entries, depth),
+        None => Err(template_error(format!(
+            "expected a pointer string or an array, got {}",
+            template.get_type()
+        ))),
+    }
+}
+
+/// Compile an array template: `[pattern, template]` or a shape.
+fn compile_array(entries: &ZendHashTable, depth: usize) -> Result<Template, SonicError> {
+    // `[pattern, template]` with a wildcard in the pattern
+    if entries.len() == 2 && entries.has_sequential_keys() {
+        if let (Some(pattern), Some(item)) = (
+            entries.get_index(0).and_then(Zval::str),
+            entries.get_index(1),
+        ) {
+            let pattern = compile_pointer(pattern)?;
+            if pattern.iter().any(|s| matches!(s, Segment::Wildcard)) {
+                return Ok(Template::Each {
+                    pattern,
+                    item: Box::new(compile(item, depth + 1)?),
+                });
+            }
+        }
+    }
+
+    let mut shape = Vec::with_capacity(entries.len());
+    for (key, value) in entries.iter() {
+        shape.push((key, compile(value, depth + 1)?));
+    }
+    Ok(Template::Shape(shape))
+}
+
+/// Error for a cached template that can't be read back.
+fn corrupt_artifact() -> SonicError {
+    SonicError::Internal("Corrupt cached extraction template".to_string())
+}
+
+/// Write `template` in its cached form: `["p", segment, ...]` for a
+/// pointer, with null for `*`; `["e", [segment, ...], item]` for a
+/// pattern; `["s", key, template, ...]` for a shape.
+fn write_template(out: &mut String, template: &Template) -> Result<(), SonicError> {
+    match template {
+        Template::Pointer(segments) => {
+            out.push_str("[\"p\"");
+            for segment in segments {
+                out.push(',');
+                write_segment(out, segment)?;
+            }
+        }
+        Template::Each { pattern, item } => {
+            out.push_str("[\"e\",[");
+            for (i, segment) in pattern.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_segment(out, segment)?;
+            }
+            out.push_str("],");
+            write_template(out, item)?;
+        }
+        Template::Shape(shape) => {
+            out.push_str("[\"s\"");
+            for (key, template) in shape {
+                out.push(',');
+                match key {
+                    ArrayKey::Long(index) => {
+                        let _ = write!(out, "{}", index);
+                    }
+                    ArrayKey::String(name) => encoder::write_string(out, name.as_bytes())?,
+                }
+                out.push(',');
+                write_template(out, template)?;
+            }
+        }
+    }
+    out.push(']');
+    Ok(())
+}
+
+/// Write one pointer segment of a cached template.
+fn write_segment(out: &mut String, segment: &Segment) -> Result<(), SonicError> {
+    match segment {
+        Segment::Name(name) => encoder::write_string(out, name.as_bytes()),
+        Segment::Wildcard => {
+            out.push_str("null");
+            Ok(())
+        }
+    }
+}
+
+/// Read a template back from its cached form (see `write_template`).
+fn read_template(value: &Value, depth: usize) -> Result<Template, SonicError> {
+    let items = value.as_array().ok_or_else(corrupt_artifact)?;
+    if depth > MAX_DEPTH || items.is_empty() {
+        return Err(corrupt_artifact());
+    }
+    let rest = || items.iter().skip(1);
+    match items.get(0).and_then(|tag| tag.as_str()) {
+        Some("p") => Ok(Template::Pointer(read_segments(rest())?)),
+        Some("e") if items.len() == 3 => {
+            let pattern = items
+                .get(1)
+                .and_then(|p| p.as_array())
+                .ok_or_else(corrupt_artifact)?;
+            let item = items.get(2).ok_or_else(corrupt_artifact)?;
+            Ok(Template::Each {
+                pattern: read_segments(pattern.iter())?,
+                item: Box::new(read_template(item, depth + 1)?),
+            })
+        }
+        Some("s") if items.len() % 2 == 1 => {
+            let entries: Vec<&Value> = rest().collect();
+            let mut shape = Vec::with_capacity(entries.len() / 2);
+            for pair in entries.chunks(2) {
+                let key = match (pair[0].as_i64(), pair[0].as_str()) {
+                    (Some(index), _) => ArrayKey::Long(index),
+                    (None, Some(name)) => ArrayKey::String(name.to_string()),
+                    (None, None) => return Err(corrupt_artifact()),
+                };
+                shape.push((key, read_template(pair[1], depth + 1)?));
+            }
+            Ok(Template::Shape(shape))
+        }
+        _ => Err(corrupt_artifact()),
+    }
+}
+
+/// Read the pointer segments of a cached template.
+fn read_segments<'a>(
+    segments: impl Iterator<Item = &'a Value>,
+) -> Result<Vec<Segment>, SonicError> {
+    segments
+        .map(|segment| match segment.as_str() {
+            Some(name) => Ok(Segment::Name(name.to_string())),
+            None if segment.is_null() => Ok(Segment::Wildcard),
+            None => Err(corrupt_artifact()),
+        })
+        .collect()
+}
+
+impl Artifact for Template {
+    const KIND: &'static str = "extractor";
+
+    fn to_artifact(&self) -> Result<String, SonicError> {
+        let mut out = String::new();
+        write_template(&mut out, self)?;
+        Ok(out)
+    }
+
+    fn from_artifact(text: &str) -> Result<Self, SonicError> {
+        read_template(&parser::parse_dom(text)?, 0)
+    }
+}
+
+/// Walk `segments` from `value`, passing each value reached to `leaf`.
+/// A wildcard gives a list for an array and a map for an object; a path
+/// that doesn't resolve gives null.
+fn select(
+    value: &Value,
+    segments: &[Segment],
+    leaf: &dyn Fn(&Value) -> Result<Zval, SonicError>,
+) -> Result<Zval, SonicError> {
+    let mut current = value;
+    for (i, segment) in segments.iter().enumerate() {
+        let next = match segment {
+            Segment::Name(name) => match current.as_array() {
+                Some(items) => name.parse::<usize>().ok().and_then(|i| items.get(i)),
+                None => current.as_object().and_then(|members| members.get(name)),
+            },
+            Segment::Wildcard => {
+                let rest = &segments[i + 1..];
+                let mut results = ZendHashTable::new();
+                if let Some(items) = current.as_array() {
+                    for item in items.iter() {
+                        results
+                            .push(select(item, rest, leaf)?)
+                            .map_err(insert_error)?;
+                    }
+                } else if let Some(members) = current.as_object() {
+                    for (name, item) in members.iter() {
+                        results
+                            .insert(name, select(item, rest, leaf)?)
+                            .map_err(insert_error)?;
+                    }
+                } else {
+                    return Ok(Zval::new());
+                }
+                let mut zval = Zval::new();
+                zval.set_hashtable(results);
+                return Ok(zval);
+            }
+        };
+        match next {
+            Some(next) => current = next,
+            None => return Ok(Zval::new()),
+        }
+    }
+    leaf(current)
+}
+
+/// Apply a compiled template to `value`.
+fn apply(template: &Template, value: &Value) -> Result<Zval, SonicError> {
+    match template {
+        Template::Pointer(segments) => select(value, segments, &parser::value_to_zval),
+        Template::Each { pattern, item } => select(value, pattern, &|v| apply(item, v)),
+        Template::Shape(shape) => {
+            let mut result = ZendHashTable::with_capacity(shape.len() as u32);
+            for (key, template) in shape {
+                let value = apply(template, value)?;
+                match key {
+                    ArrayKey::Long(index) => result.insert_at_index(*index as u64, value),
+                    ArrayKey::String(name) => result.insert(name, value),
+                }
+                .map_err(insert_error)?;
+            }
+            let mut zval = Zval::new();
+            zval.set_hashtable(result);
+            Ok(zval)
+        }
+    }
+}
+
+/// Extractor - a compiled extraction template, reusable across documents.
+/// Pointer parsing and template validation happen once, in
+/// `Sift::compileExtractor()`; each `extract()` only parses its document.
+#[php_class(name = "Sift\\Extractor")]
+#[derive(Debug)]
+pub struct Extractor {
+    template: Template,
+}
+
+impl Extractor {
+    /// Compile `template`, reporting any mistake in it, or read it back
+    /// from the artifact cache, keyed by the template as JSON.
+    pub fn compile(template: &ZendHashTable) -> Result<Self, SonicError> {
+        if !artifacts::enabled() {
+            return Ok(Self {
+                template: compile_array(template, 0)?,
+            });
+        }
+        let mut source = String::new();
+        let compiled = match encoder::encode_array(&mut source, template) {
+            Ok(()) => {
+                artifacts::fetch_or_compile(source.as_bytes(), || compile_array(template, 0))?
+            }
+            // Not valid as JSON, so not cached; compiling says what is wrong
+            Err(_) => compile_array(template, 0)?,
+        };
+        Ok(Self { template: compiled })
+    }
+
+    /// Apply the compiled template to `json`.
+    pub fn apply(&self, json: &str) -> Result<Zval, SonicError> {
+        let root = parser::parse_dom(json)?;
+        apply(&self.template, &root)
+    }
+}
+
+#[php_impl]
+impl Extractor {
+    /// Apply the template to `json`, as `Sift::extract()` would.
+    ///
+    /// # Example
+    /// ```php
+    /// $row = $extractor->extract($message);
+    /// ```
+    pub fn extract(&self, json: &str) -> Result<Zval, SonicError> {
+        guard(|| self.apply(json))
+    }
+}
+
+/// Compile `template` and apply it to `json`.
+pub fn extract(json: &str, template: &ZendHashTable) -> Result<Zval, SonicError> {
+    Extractor::compile(template)?.apply(json)
+}
@@ -0,0 +1,95 @@
+//! Field-level encryption for `Sift::encryptFields()` and
+//! `Sift::decryptFields()`.
+//!
+//! Usage:
+//! ```php
+//! $key = sodium_crypto_secretbox_keygen();
+//! $stored = Sift::encryptFields($json, ['/user/ssn', '/card'], $key);
+//! $json = Sift::decryptFields($stored, ['/user/ssn', '/card'], $key);
+//! ```
+//!
+//! Each value's raw JSON text is sealed with XSalsa20-Poly1305 under a
+//! fresh random nonce and replaced by the base64 (standard alphabet) of
+//! nonce followed by the box, which is libsodium's `crypto_secretbox`
+//! format, so PHP can open a single field with
+//! `sodium_crypto_secretbox_open(substr($raw, 24), substr($raw, 0, 24), $key)`.
+//! Any JSON value can be encrypted and comes back byte for byte; the rest
+//! of the document is copied verbatim, as in `Sift::patch()`.
+
+use crate::base64::{self, Alphabet};
+use crate::errors::SonicError;
+use crate::patch;
+use crypto_secretbox::aead::{Aead, AeadCore, KeyInit, OsRng};
+use crypto_secretbox::{Nonce, XSalsa20Poly1305};
+use sonic_rs::JsonValueTrait;
+
+/// `SODIUM_CRYPTO_SECRETBOX_KEYBYTES`
+const KEY_BYTES: usize = 32;
+
+/// `SODIUM_CRYPTO_SECRETBOX_NONCEBYTES`
+const NONCE_BYTES: usize = 24;
+
+/// `SODIUM_CRYPTO_SECRETBOX_MACBYTES`
+const MAC_BYTES: usize = 16;
+
+fn cipher(key: &[u8]) -> Result<XSalsa20Poly1305, SonicError> {
+    if key.len() != KEY_BYTES {
+        return Err(SonicError::InvalidOption(format!(
+            "Encryption key must be {} bytes (SODIUM_CRYPTO_SECRETBOX_KEYBYTES)",
+            KEY_BYTES
+        )));
+    }
+    XSalsa20Poly1305::new_from_slice(key)
+        .map_err(|e| SonicError::InvalidOption(format!("Invalid encryption key: {}", e)))
+}
+
+/// Replace the values at `pointers` with their encrypted, base64-encoded
+/// JSON text.
+pub fn encrypt_fields(json: &str, pointers: &[&str], key: &[u8]) -> Result<String, SonicError> {
+    let cipher = cipher(key)?;
+    patch::replace_at(json, pointers, |_, value| {
+        let nonce = XSalsa20Poly1305::generate_nonce(&mut OsRng);
+        let sealed = cipher
+            .encrypt(&nonce, value.as_raw_str().as_bytes())
+            .map_err(|_| SonicError::Internal("Field encryption failed".to_string()))?;
+        let mut raw = Vec::with_capacity(NONCE_BYTES + sealed.len());
+        raw.extend_from_slice(&nonce);
+        raw.extend_from_slice(&sealed);
+        // Base64 needs no escaping
+        Ok(format!("\"{}\"", base64::encode(&raw, Alphabet::Standard)))
+    })
+}
+
+/// Replace the encrypted strings at `pointers` with the JSON they hold.
+pub fn decrypt_fields(json: &str, pointers: &[&str], key: &[u8]) -> Result<String, SonicError> {
+    let cipher = cipher(key)?;
+    patch::replace_at(json, pointers, |_, value| {
+        let text = value.as_str().ok_or_else(|| {
+            SonicError::FormatError("Encrypted field must be a base64 string".to_string())
+        })?;
+        let raw = base64::decode(text, Alphabet::Standard).map_err(|e| {
+            SonicError::FormatError(format!("Encrypted field is not base64: {}", e.describe()))
+        })?;
+        if raw.len() < NONCE_BYTES + MAC_BYTES {
+            return Err(SonicError::FormatError(
+                "Encrypted field is too short".to_string(),
+            ));
+        }
+        let (nonce, sealed) = raw.split_at(NONCE_BYTES);
+        let plain = cipher
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .map_err(|_| {
+                SonicError::FormatError(
+                    "Encrypted field failed authentication (wrong key or tampered)".to_string(),
+                )
+            })?;
+        let plain = String::from_utf8(plain).map_err(|_| {
+            SonicError::FormatError("Decrypted field is not UTF-8 JSON".to_string())
+        })?;
+        // Authenticated, but spliced unchecked it could break the document
+        sonic_rs::from_str::<sonic_rs::Value>(&plain).map_err(|_| {
+            SonicError::FormatError("Decrypted field is not valid JSON".to_string())
+        })?;
+        Ok(plain)
+    })
+}
@@ -0,0 +1,121 @@
+//! String format validators used by the Query hydrators
+//! (`uuid()`, `email()`, `url()`, `ip()`).
+//!
+//! The checks are deliberately syntactic: they confirm a value is shaped
+//! like the format, without DNS lookups or scheme-specific rules.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Maximum length of an email address (RFC 5321 forward-path limit).
+const MAX_EMAIL_LEN: usize = 254;
+
+/// Maximum length of an email local part (RFC 5321).
+const MAX_LOCAL_PART_LEN: usize = 64;
+
+/// Maximum length of a single DNS label.
+const MAX_LABEL_LEN: usize = 63;
+
+/// Canonical textual UUID: 8-4-4-4-12 hex digits, either case.
+pub fn is_uuid(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() == 36
+        && bytes.iter().enumerate().all(|(i, &b)| match i {
+            8 | 13 | 18 | 23 => b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        })
+}
+
+/// Addr-spec email with a dot-atom local part and a dotted domain name,
+/// e.g. "first.last+tag@example.co.uk". Quoted local parts and IP-literal
+/// domains are rejected.
+pub fn is_email(s: &str) -> bool {
+    if s.len() > MAX_EMAIL_LEN {
+        return false;
+    }
+    let Some((local, domain)) = s.rsplit_once('@') else {
+        return false;
+    };
+    local.len() <= MAX_LOCAL_PART_LEN
+        && is_dot_atom(local)
+        && domain.contains('.')
+        && is_hostname(domain)
+}
+
+/// Absolute URL with a scheme and a non-empty authority,
+/// e.g. "https://example.com/path?q=1". Whitespace and control
+/// characters are rejected anywhere.
+pub fn is_url(s: &str) -> bool {
+    if s.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return false;
+    }
+    let Some((scheme, rest)) = s.split_once("://") else {
+        return false;
+    };
+    let mut scheme_chars = scheme.chars();
+    let scheme_ok = scheme_chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme_chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !scheme_ok {
+        return false;
+    }
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = if let Some(bracketed) = host_port.strip_prefix('[') {
+        // IPv6 literal: [::1]:8080
+        match bracketed.split_once(']') {
+            Some((ip, rest)) if ip.parse::<Ipv6Addr>().is_ok() => {
+                return rest.is_empty() || rest.strip_prefix(':').is_some_and(is_port);
+            }
+            _ => return false,
+        }
+    } else {
+        match host_port.rsplit_once(':') {
+            Some((host, port)) => {
+                if !is_port(port) {
+                    return false;
+                }
+                host
+            }
+            None => host_port,
+        }
+    };
+    host.parse::<Ipv4Addr>().is_ok() || is_hostname(host)
+}
+
+/// IPv4 or IPv6 address, optionally restricted to one version.
+pub fn is_ip(s: &str, version: Option<u8>) -> bool {
+    match (s.parse::<IpAddr>(), version) {
+        (Ok(IpAddr::V4(_)), None | Some(4)) => true,
+        (Ok(IpAddr::V6(_)), None | Some(6)) => true,
+        _ => false,
+    }
+}
+
+/// Dot-separated atoms of RFC 5322 atext characters.
+fn is_dot_atom(s: &str) -> bool {
+    !s.is_empty()
+        && s.split('.').all(|atom| {
+            !atom.is_empty()
+                && atom.chars().all(|c| {
+                    c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c)
+                })
+        })
+}
+
+/// DNS hostname: labels of letters, digits and inner hyphens. Non-ASCII
+/// letters are allowed so internationalized names pass without punycode.
+fn is_hostname(s: &str) -> bool {
+    let s = s.strip_suffix('.').unwrap_or(s);
+    !s.is_empty()
+        && s.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= MAX_LABEL_LEN
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        })
+}
+
+fn is_port(s: &str) -> bool {
+    !s.is_empty() && s.len() <= 5 && s.parse::<u16>().is_ok()
+}
@@ -0,0 +1,211 @@
+//! Incremental Server-Sent Events reader for JSON streams.
+//!
+//! Usage:
+//! ```php
+//! $reader = new Sift\FrameReader();
+//! curl_setopt($ch, CURLOPT_WRITEFUNCTION, function ($ch, $chunk) use ($reader) {
+//!     foreach ($reader->push($chunk) as $event) {
+//!         echo $event['choices'][0]['delta']['content'] ?? '';
+//!     }
+//!     return strlen($chunk);
+//! });
+//! ```
+
+use crate::buffering::{BufferLimit, WhenFull};
+use crate::errors::SonicError;
+use crate::guard::guard;
+use crate::leaks::Live;
+use crate::options::{DecodeOptions, PhpOptions};
+use crate::parser;
+use ext_php_rs::binary_slice::BinarySlice;
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendHashTable, Zval};
+
+/// The `data:` payload streaming APIs send to mark the end of a stream.
+const DONE_SENTINEL: &[u8] = b"[DONE]";
+
+/// FrameReader - reassembles SSE events from arbitrarily split chunks of
+/// a stream and decodes the JSON in their `data:` fields.
+/// Follows the HTML event-stream format: lines end in LF, CRLF or CR,
+/// `data:` lines of one event are joined with LF, a blank line ends the
+/// event, and comments and other fields are skipped.
+#[php_class(name = "Sift\\FrameReader")]
+#[derive(Debug)]
+pub struct FrameReader {
+    /// Options frames are decoded with
+    options: DecodeOptions,
+    /// Received bytes not yet forming a complete line
+    buffer: Vec<u8>,
+    /// `data:` lines of the current event, joined with LF
+    data: Vec<u8>,
+    /// Whether the current event has a `data:` line (it may be empty)
+    has_data: bool,
+    /// Whether a `[DONE]` event has been seen
+    done: bool,
+    /// Most bytes of incomplete events held, if limited below the input
+    /// size limit
+    limit: Option<BufferLimit>,
+    /// Counts this object for `sift.leak_check` (see `leaks.rs`)
+    live: Live,
+}
+
+impl FrameReader {
+    /// Handle one line (without its terminator), decoding the event it
+    /// completes, if any, into `frames`.
+    fn line(&mut self, line: &[u8], frames: &mut Vec<Zval>) -> Result<(), SonicError> {
+        if line.is_empty() {
+            return self.dispatch(frames);
+        }
+        if line[0] == b':' {
+            // Comment, e.g. a keep-alive
+            return Ok(());
+        }
+
+        let (field, value) = match line.iter().position(|&b| b == b':') {
+            Some(colon) => {
+                let value = &line[colon + 1..];
+                (&line[..colon], value.strip_prefix(b" ").unwrap_or(value))
+            }
+            None => (line, &[][..]),
+        };
+        if field == b"data" {
+            if self.has_data {
+                self.data.push(b'\n');
+            }
+            self.data.extend_from_slice(value);
+            self.has_data = true;
+            parser::check_input_size(&self.data)?;
+        }
+        Ok(())
+    }
+
+    /// End the current event, decoding its data into `frames`.
+    fn dispatch(&mut self, frames: &mut Vec<Zval>) -> Result<(), SonicError> {
+        if !std::mem::take(&mut self.has_data) {
+            return Ok(());
+        }
+        let data = std::mem::take(&mut self.data);
+        if data == DONE_SENTINEL {
+            self.done = true;
+            return Ok(());
+        }
+        let json = parser::input_text(&data, &self.options)?;
+        frames.push(parser::decode(&json, &self.options)?);
+        Ok(())
+    }
+
+    /// Handle every complete line in the buffer.
+    fn drain_lines(&mut self, frames: &mut Vec<Zval>) -> Result<(), SonicError> {
+        let buffer = std::mem::take(&mut self.buffer);
+        let mut start = 0;
+        let result = loop {
+            let Some(offset) = buffer[start..]
+                .iter()
+                .position(|&b| b == b'\n' || b == b'\r')
+            else {
+                break Ok(());
+            };
+            let end = start + offset;
+            let next = match buffer[end] {
+                b'\r' if end + 1 == buffer.len() => break Ok(()), // CRLF may be split
+                b'\r' if buffer[end + 1] == b'\n' => end + 2,
+                _ => end + 1,
+            };
+            if let Err(e) = self.line(&buffer[start..end], frames) {
+                start = next;
+                break Err(e);
+            }
+            start = next;
+        };
+        self.buffer = buffer[start..].to_vec();
+        result
+    }
+}
+
+#[php_impl]
+impl FrameReader {
+    /// Create a reader; frames are decoded with `options`, which take the
+    /// same keys as `Sift::decode()`. `buffer` limits the bytes of
+    /// incomplete events held between pushes; only `'error'` applies, as
+    /// the caller of `push()` can't be made to wait.
+    #[constructor]
+    pub fn __construct(
+        options: Option<&ZendHashTable>,
+        buffer: Option<&ZendHashTable>,
+    ) -> Result<Self, SonicError> {
+        guard(|| {
+            let limit = match buffer {
+                Some(buffer) => {
+                    let default = BufferLimit {
+                        max_bytes: parser::MAX_INPUT_SIZE,
+                        when_full: WhenFull::Error,
+                    };
+                    Some(BufferLimit::from_array(Some(buffer), default)?)
+                }
+                None => None,
+            };
+            if limit.is_some_and(|limit| limit.when_full == WhenFull::Block) {
+                return Err(SonicError::InvalidOption(
+                    "FrameReader can't block the caller of push(); use 'error'".to_string(),
+                ));
+            }
+            Ok(Self {
+                options: DecodeOptions::from_array(options)?,
+                buffer: Vec::new(),
+                data: Vec::new(),
+                has_data: false,
+                done: false,
+                limit,
+                live: Live::new("Sift\\FrameReader"),
+            })
+        })
+    }
+
+    /// Feed the next chunk of the stream, split anywhere. Returns the
+    /// decoded values of the events it completes, in order.
+    pub fn push(&mut self, chunk: BinarySlice<u8>) -> Result<Vec<Zval>, SonicError> {
+        guard(|| {
+            self.buffer.extend_from_slice(&chunk);
+            let mut frames = Vec::new();
+            self.drain_lines(&mut frames)?;
+            // A line that never ends would otherwise grow without bound
+            parser::check_input_size(&self.buffer)?;
+            if let Some(limit) = self.limit {
+                let pending = self.buffer.len() + self.data.len();
+                if pending > limit.max_bytes {
+                    // Drop the event in progress so the reader can go on
+                    self.buffer.clear();
+                    self.data.clear();
+                    self.has_data = false;
+                    return Err(limit.exceeded(pending));
+                }
+            }
+            Ok(frames)
+        })
+    }
+
+    /// Signal the end of the stream. An unterminated last line and an
+    /// event without its closing blank line are still decoded.
+    pub fn finish(&mut self) -> Result<Vec<Zval>, SonicError> {
+        guard(|| {
+            let mut frames = Vec::new();
+            let rest = std::mem::take(&mut self.buffer);
+            let rest = rest.strip_suffix(b"\r").unwrap_or(&rest);
+            if !rest.is_empty() {
+                self.line(rest, &mut frames)?;
+            }
+            self.dispatch(&mut frames)?;
+            Ok(frames)
+        })
+    }
+
+    /// Whether a `data: [DONE]` event has been received.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Bytes received that don't yet form a complete event.
+    pub fn pending(&self) -> i64 {
+        (self.buffer.len() + self.data.len()) as i64
+    }
+}
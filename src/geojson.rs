@@ -0,0 +1,369 @@
+//! GeoJSON (RFC 7946) helpers for `Sift::geoBounds()` and `Query::geometry()`.
+//!
+//! Coordinates are read straight from the JSON into doubles; no PHP value
+//! is created per position.
+//!
+//! Usage:
+//! ```php
+//! [$minX, $minY, $maxX, $maxY] = Sift::geoBounds($featureCollection);
+//! $g = Sift::query($feature)->geometry();   // ['type', 'dims', 'data', 'counts']
+//! ```
+
+use crate::errors::SonicError;
+use crate::parser;
+use ext_php_rs::types::{ZendHashTable, ZendStr, Zval};
+use sonic_rs::{to_array_iter_unchecked, to_object_iter_unchecked};
+use sonic_rs::{JsonValueTrait, LazyValue, PointerNode};
+
+/// How many arrays deep the positions of each geometry type's
+/// `coordinates` are nested.
+fn nesting(kind: &str) -> Option<usize> {
+    match kind {
+        "Point" => Some(0),
+        "MultiPoint" | "LineString" => Some(1),
+        "MultiLineString" | "Polygon" => Some(2),
+        "MultiPolygon" => Some(3),
+        _ => None,
+    }
+}
+
+/// The members of a GeoJSON object the helpers use; the rest are skipped.
+#[derive(Default)]
+struct GeoObject<'a> {
+    kind: Option<String>,
+    coordinates: Option<LazyValue<'a>>,
+    geometry: Option<LazyValue<'a>>,
+    features: Option<LazyValue<'a>>,
+    geometries: Option<LazyValue<'a>>,
+}
+
+impl<'a> GeoObject<'a> {
+    /// Read the object `lazy` (located at `pointer`) in one pass.
+    fn read(lazy: &'a LazyValue, pointer: &str) -> Result<Self, SonicError> {
+        if !lazy.is_object() {
+            return Err(
+                SonicError::TypeError("GeoJSON value is not an object".to_string())
+                    .at_pointer(pointer)
+                    .with_types("object", parser::type_name(lazy)),
+            );
+        }
+        let mut object = GeoObject::default();
+        // SAFETY: we've verified this is an object via is_object()
+        for entry in unsafe { to_object_iter_unchecked(lazy.as_raw_str()) } {
+            let (key, value) = entry?;
+            match &*key {
+                "type" => object.kind = value.as_str().map(str::to_string),
+                "coordinates" => object.coordinates = Some(value),
+                "geometry" => object.geometry = Some(value),
+                "features" => object.features = Some(value),
+                "geometries" => object.geometries = Some(value),
+                _ => {}
+            }
+        }
+        Ok(object)
+    }
+
+    /// The `type` member, which every GeoJSON object must have.
+    fn kind(&self, pointer: &str) -> Result<&str, SonicError> {
+        self.kind.as_deref().ok_or_else(|| {
+            SonicError::TypeError("GeoJSON object has no string 'type'".to_string())
+                .at_pointer(pointer)
+        })
+    }
+}
+
+/// A required member, which must be an array.
+fn array_member<'v, 'a>(
+    value: &'v Option<LazyValue<'a>>,
+    name: &str,
+    pointer: &str,
+) -> Result<&'v LazyValue<'a>, SonicError> {
+    let pointer = format!("{}/{}", pointer, name);
+    match value {
+        Some(lazy) if lazy.is_array() => Ok(lazy),
+        Some(lazy) => Err(SonicError::TypeError(format!("'{}' is not an array", name))
+            .at_pointer(pointer)
+            .with_types("array", parser::type_name(lazy))),
+        None => Err(
+            SonicError::KeyNotFound(format!("GeoJSON object has no '{}'", name))
+                .at_pointer(pointer),
+        ),
+    }
+}
+
+/// Receives what `walk_coordinates()` reads.
+trait Visitor {
+    /// An array `level` arrays below `coordinates` (1 = its elements)
+    /// holding `len` children.
+    fn array(&mut self, level: usize, len: usize);
+    /// A position, at least two numbers.
+    fn position(
+        &mut self,
+        position: &[f64],
+        pointer: &dyn Fn() -> String,
+    ) -> Result<(), SonicError>;
+}
+
+/// Walk a geometry's `coordinates`, whose positions are `nesting` arrays
+/// deep. `indices` holds the path below `pointer` for error messages.
+fn walk_coordinates(
+    lazy: &LazyValue,
+    nesting: usize,
+    pointer: &str,
+    indices: &mut Vec<usize>,
+    position: &mut Vec<f64>,
+    visitor: &mut dyn Visitor,
+) -> Result<(), SonicError> {
+    let here = |indices: &[usize]| -> String {
+        indices
+            .iter()
+            .fold(pointer.to_string(), |p, i| format!("{}/{}", p, i))
+    };
+    if !lazy.is_array() {
+        return Err(
+            SonicError::TypeError("Coordinates must be arrays".to_string())
+                .at_pointer(here(indices))
+                .with_types("array", parser::type_name(lazy)),
+        );
+    }
+
+    if indices.len() == nesting {
+        position.clear();
+        // SAFETY: we've verified this is an array via is_array()
+        for item in unsafe { to_array_iter_unchecked(lazy.as_raw_str()) } {
+            let item = item?;
+            let n = item.as_f64().ok_or_else(|| {
+                SonicError::TypeError("Position coordinates must be numbers".to_string())
+                    .at_pointer(format!("{}/{}", here(indices), position.len()))
+                    .with_types("float", parser::type_name(&item))
+            })?;
+            position.push(n);
+        }
+        if position.len() < 2 {
+            return Err(SonicError::TypeError(
+                "Position must have at least 2 coordinates".to_string(),
+            )
+            .at_pointer(here(indices)));
+        }
+        return visitor.position(position, &|| here(indices));
+    }
+
+    let mut len = 0;
+    // SAFETY: we've verified this is an array via is_array()
+    for (i, item) in unsafe { to_array_iter_unchecked(lazy.as_raw_str()) }.enumerate() {
+        let item = item?;
+        indices.push(i);
+        walk_coordinates(&item, nesting, pointer, indices, position, visitor)?;
+        indices.pop();
+        len += 1;
+    }
+    if !indices.is_empty() {
+        visitor.array(indices.len(), len);
+    }
+    Ok(())
+}
+
+/// Running bounding box.
+#[derive(Default)]
+struct Bounds(Option<[f64; 4]>);
+
+impl Visitor for Bounds {
+    fn array(&mut self, _level: usize, _len: usize) {}
+
+    fn position(
+        &mut self,
+        position: &[f64],
+        _pointer: &dyn Fn() -> String,
+    ) -> Result<(), SonicError> {
+        let (x, y) = (position[0], position[1]);
+        let bbox = self.0.get_or_insert([x, y, x, y]);
+        bbox[0] = bbox[0].min(x);
+        bbox[1] = bbox[1].min(y);
+        bbox[2] = bbox[2].max(x);
+        bbox[3] = bbox[3].max(y);
+        Ok(())
+    }
+}
+
+/// Accumulate the bounds of the GeoJSON object `lazy` at `pointer`.
+fn object_bounds(
+    lazy: &LazyValue,
+    pointer: &str,
+    depth: usize,
+    bounds: &mut Bounds,
+) -> Result<(), SonicError> {
+    if depth > parser::MAX_DEPTH {
+        return Err(SonicError::ParseError(format!(
+            "Maximum nesting depth ({}) exceeded",
+            parser::MAX_DEPTH
+        )));
+    }
+    let object = GeoObject::read(lazy, pointer)?;
+    let (children, name) = match object.kind(pointer)? {
+        "FeatureCollection" => (&object.features, "features"),
+        "GeometryCollection" => (&object.geometries, "geometries"),
+        "Feature" => {
+            return match &object.geometry {
+                // Unlocated features have a null geometry
+                Some(geometry) if !geometry.is_null() => object_bounds(
+                    geometry,
+                    &format!("{}/geometry", pointer),
+                    depth + 1,
+                    bounds,
+                ),
+                _ => Ok(()),
+            };
+        }
+        kind => {
+            let nesting = nesting(kind).ok_or_else(|| unknown_type(kind, pointer))?;
+            let coordinates = array_member(&object.coordinates, "coordinates", pointer)?;
+            let pointer = format!("{}/coordinates", pointer);
+            return walk_coordinates(
+                coordinates,
+                nesting,
+                &pointer,
+                &mut Vec::new(),
+                &mut Vec::new(),
+                bounds,
+            );
+        }
+    };
+
+    let children = array_member(children, name, pointer)?;
+    // SAFETY: array_member() has verified this is an array
+    for (i, child) in unsafe { to_array_iter_unchecked(children.as_raw_str()) }.enumerate() {
+        let child = child?;
+        object_bounds(
+            &child,
+            &format!("{}/{}/{}", pointer, name, i),
+            depth + 1,
+            bounds,
+        )?;
+    }
+    Ok(())
+}
+
+fn unknown_type(kind: &str, pointer: &str) -> SonicError {
+    SonicError::TypeError(format!("Unknown GeoJSON type '{}'", kind)).at_pointer(pointer)
+}
+
+/// The bounding box `[minX, minY, maxX, maxY]` of every position in a
+/// GeoJSON document (Feature, FeatureCollection or geometry), or None if
+/// it has none. Altitudes are ignored.
+pub fn bounds(json: &str) -> Result<Option<[f64; 4]>, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+
+    let root = sonic_rs::get(json, &[] as &[PointerNode])?;
+    let mut bounds = Bounds::default();
+    object_bounds(&root, "", 0, &mut bounds)?;
+    Ok(bounds.0)
+}
+
+/// Positions packed as doubles, with the length of every array between
+/// them and `coordinates`.
+#[derive(Default)]
+struct Packed {
+    data: Vec<u8>,
+    dims: usize,
+    counts: Vec<Vec<usize>>,
+}
+
+impl Visitor for Packed {
+    fn array(&mut self, level: usize, len: usize) {
+        if self.counts.len() < level {
+            self.counts.resize_with(level, Vec::new);
+        }
+        self.counts[level - 1].push(len);
+    }
+
+    fn position(
+        &mut self,
+        position: &[f64],
+        pointer: &dyn Fn() -> String,
+    ) -> Result<(), SonicError> {
+        if self.data.is_empty() {
+            self.dims = position.len();
+        } else if position.len() != self.dims {
+            return Err(SonicError::TypeError(format!(
+                "Position has {} coordinates, expected {}",
+                position.len(),
+                self.dims
+            ))
+            .at_pointer(pointer()));
+        }
+        for n in position {
+            self.data.extend_from_slice(&n.to_ne_bytes());
+        }
+        Ok(())
+    }
+}
+
+/// Pack the coordinates of the geometry (or Feature's geometry) `lazy` at
+/// `pointer` into `["type", "dims", "data", "counts"]`; see
+/// `Query::geometry()`. A Feature without a geometry yields null.
+pub fn geometry(lazy: &LazyValue, pointer: &str) -> Result<Zval, SonicError> {
+    let object = GeoObject::read(lazy, pointer)?;
+    if object.kind(pointer)? != "Feature" {
+        return pack_geometry(&object, pointer);
+    }
+
+    let pointer = format!("{}/geometry", pointer);
+    match &object.geometry {
+        Some(inner) if inner.is_null() => Ok(Zval::new()),
+        Some(inner) => pack_geometry(&GeoObject::read(inner, &pointer)?, &pointer),
+        None => Err(
+            SonicError::KeyNotFound("GeoJSON Feature has no 'geometry'".to_string())
+                .at_pointer(pointer),
+        ),
+    }
+}
+
+/// Pack a single geometry for `geometry()`.
+fn pack_geometry(object: &GeoObject, pointer: &str) -> Result<Zval, SonicError> {
+    let kind = object.kind(pointer)?;
+    let nesting = nesting(kind).ok_or_else(|| match kind {
+        "Feature" | "FeatureCollection" | "GeometryCollection" => {
+            SonicError::TypeError(format!("GeoJSON {} is not a single geometry", kind))
+                .at_pointer(pointer)
+        }
+        _ => unknown_type(kind, pointer),
+    })?;
+    let coordinates = array_member(&object.coordinates, "coordinates", pointer)?;
+    let mut packed = Packed::default();
+    walk_coordinates(
+        coordinates,
+        nesting,
+        &format!("{}/coordinates", pointer),
+        &mut Vec::new(),
+        &mut Vec::new(),
+        &mut packed,
+    )?;
+
+    // Levels with no arrays at all (e.g. a Polygon without rings) still
+    // get an entry, so `counts` always has `nesting - 1` levels
+    packed
+        .counts
+        .resize_with(nesting.saturating_sub(1), Vec::new);
+    let mut counts = ZendHashTable::new();
+    for level in packed.counts {
+        let lens: Vec<i64> = level.into_iter().map(|len| len as i64).collect();
+        counts
+            .push(lens)
+            .map_err(|e| SonicError::TypeError(format!("Failed to push array item: {}", e)))?;
+    }
+
+    let mut data = Zval::new();
+    data.set_zend_string(ZendStr::new(packed.data, false));
+    let mut result = ZendHashTable::new();
+    result
+        .insert("type", kind)
+        .and_then(|_| result.insert("dims", packed.dims as i64))
+        .and_then(|_| result.insert("data", data))
+        .and_then(|_| result.insert("counts", counts))
+        .map_err(|e| SonicError::TypeError(format!("Failed to insert object key: {}", e)))?;
+
+    let mut zval = Zval::new();
+    zval.set_hashtable(result);
+    Ok(zval)
+}
@@ -0,0 +1,55 @@
+//! Panic containment for PHP entry points.
+//!
+//! A panic unwinding out of a PHP function handler aborts the process, so
+//! a bug reachable from malformed input would take down the whole FPM
+//! worker. Every `#[php_impl]` method that does more than read a field
+//! runs its work through `guard()`, which catches the panic and throws
+//! `Sift\InternalException` (code `INTERNAL_ERROR`) naming where it
+//! happened; the panic hook installed at startup logs the same location.
+//! It also marks the start of a call for `Sift::lastWarnings()`.
+//!
+//! This relies on the default `panic = "unwind"`; a build with
+//! `panic = "abort"` still aborts.
+
+use crate::errors::SonicError;
+use sift_core::diagnostics;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+
+thread_local! {
+    /// Where the last panic on this thread happened (`file:line`).
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Route panic reports through the logger instead of stderr, and record
+/// where they happened for `guard()`. Only the location is kept: panic
+/// messages can quote the input (slicing errors do), and user data is
+/// never logged.
+pub fn install_hook() {
+    panic::set_hook(Box::new(|info| {
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}", l.file(), l.line()));
+        match &location {
+            Some(location) => log::error!("sift: panic at {}", location),
+            None => log::error!("sift: panic at an unknown location"),
+        }
+        LAST_PANIC.with(|last| *last.borrow_mut() = location);
+    }));
+}
+
+/// Run `f`, turning a panic into an `Internal` error. Values `f` was
+/// building are dropped as the panic unwinds, so nothing half-built
+/// reaches PHP.
+pub fn guard<T>(f: impl FnOnce() -> Result<T, SonicError>) -> Result<T, SonicError> {
+    diagnostics::start_call();
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        let location = LAST_PANIC
+            .with(|last| last.borrow_mut().take())
+            .unwrap_or_else(|| "an unknown location".to_string());
+        Err(SonicError::Internal(format!(
+            "Internal error in the Sift extension at {}; please report it",
+            location
+        )))
+    })
+}
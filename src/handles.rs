@@ -0,0 +1,67 @@
+//! Generation-checked document handles.
+//!
+//! Usage:
+//! ```php
+//! $id = $pool->addDocument($body);
+//! $pool->clear();
+//! $pool->get($id, '/type');   // throws Sift\StaleHandleException
+//! ```
+//!
+//! A container that hands out integer ids for documents it can later drop
+//! (`Pool::clear()`) keeps a generation, bumped whenever ids are reused.
+//! Each id packs the generation it was issued in above the slot index, so
+//! an id kept across a reset is recognised as stale instead of silently
+//! naming whichever document took its slot. The first generation is 0,
+//! so a fresh container's ids are 0, 1, 2, ...
+//!
+//! Containers that own their document outright (`Query`, `Snapshot`, via
+//! `Rc` / `Arc`) need no handles: a PHP object keeps what it reads alive.
+
+use crate::errors::SonicError;
+
+/// Bits of an id holding the slot index.
+const INDEX_BITS: u32 = 32;
+
+/// Highest generation; ids stay positive. Past it, generations wrap to 0.
+const MAX_GENERATION: u32 = i32::MAX as u32;
+
+/// The generation of a container's ids.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Generation(u32);
+
+impl Generation {
+    /// Retire every id issued so far.
+    pub fn advance(&mut self) {
+        self.0 = if self.0 == MAX_GENERATION {
+            0
+        } else {
+            self.0 + 1
+        };
+    }
+
+    /// The id of slot `index` in this generation.
+    pub fn handle(self, index: usize) -> i64 {
+        (i64::from(self.0) << INDEX_BITS) | index as i64
+    }
+
+    /// The slot `id` names, if it was issued in this generation for one
+    /// of the `len` slots. Ids from an earlier generation are stale.
+    pub fn resolve(self, id: i64, len: usize) -> Result<usize, SonicError> {
+        let unknown = || SonicError::KeyNotFound(format!("Unknown document id {}", id));
+        if id < 0 {
+            return Err(unknown());
+        }
+        let generation = (id >> INDEX_BITS) as u32;
+        let index = (id & ((1 << INDEX_BITS) - 1)) as usize;
+        if generation < self.0 {
+            return Err(SonicError::StaleHandle(format!(
+                "Document id {} is stale: its document has been released",
+                id
+            )));
+        }
+        if generation > self.0 || index >= len {
+            return Err(unknown());
+        }
+        Ok(index)
+    }
+}
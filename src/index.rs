@@ -0,0 +1,282 @@
+//! Cacheable structural index for `Sift::index()` / `Sift::getWithIndex()`.
+//!
+//! Usage:
+//! ```php
+//! $index = $cache->get($key) ?? Sift::index($json);
+//! $email = Sift::getWithIndex($json, $index, '/users/1200/email');
+//! ```
+//!
+//! The index is a binary string of little-endian u32 words recording the
+//! byte range of every value and, for each container, a table of its
+//! members: arrays in order, objects sorted by key. A pointer lookup is
+//! then one array index or binary search per segment instead of a scan
+//! of the document.
+//!
+//! Layout, in words:
+//! - magic `SIX1`, document length, root value entry (3 words)
+//! - container nodes: header (member count, high bit set for objects),
+//!   then per member `[key_start, key_end]` (objects only, the raw key
+//!   between its quotes) and `[start, end, node]`, where `node` is the
+//!   word offset of the member's own node or `NO_NODE` for scalars.
+//!
+//! An index only describes the document it was built from. The length and
+//! every container delimiter a lookup passes through are checked, but not
+//! every byte, so pairing an index with edited JSON of the same length can
+//! resolve the wrong range.
+
+use crate::arena;
+use crate::errors::SonicError;
+use crate::options::DecodeOptions;
+use crate::parser;
+use crate::tokens::{Scanner, TokenType};
+use ext_php_rs::types::Zval;
+use sonic_rs::PointerNode;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// First word of every index.
+const MAGIC: &[u8; 4] = b"SIX1";
+
+/// `node` of a member with no node of its own (a scalar).
+const NO_NODE: u32 = u32::MAX;
+
+/// Header bit marking an object node.
+const OBJECT_BIT: u32 = 1 << 31;
+
+/// Words before the first node: magic, length, root entry.
+const HEADER_WORDS: usize = 5;
+
+/// A value's byte range and node.
+#[derive(Clone, Copy)]
+struct Entry {
+    start: u32,
+    end: u32,
+    node: u32,
+}
+
+/// An open container while building.
+struct Frame {
+    object: bool,
+    start: u32,
+    /// Members so far, with their raw key range in objects
+    members: Vec<(Option<(u32, u32)>, Entry)>,
+    /// Key of the member whose value comes next
+    key: Option<(u32, u32)>,
+}
+
+/// The text a raw key (between its quotes) stands for.
+fn key_text(raw: &str) -> Result<Cow<'_, str>, SonicError> {
+    if raw.contains('\\') {
+        Ok(Cow::Owned(parser::unescape_string(raw, false)?))
+    } else {
+        Ok(Cow::Borrowed(raw))
+    }
+}
+
+/// Build the index of `json`, validating it first.
+pub fn build(json: &str) -> Result<Vec<u8>, SonicError> {
+    // Validate input size to prevent DoS (and keep offsets within u32)
+    parser::check_input_size(json)?;
+    // The scanner trusts the structure, so the whole document is checked first
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+
+    let bytes = json.as_bytes();
+    let mut words: Vec<u32> = vec![0; HEADER_WORDS];
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut root = None;
+    let mut scanner = Scanner::default();
+    while let Some(token) = scanner.next_token(bytes) {
+        let (start, end) = (token.start as u32, token.end as u32);
+        let entry = match token.kind {
+            TokenType::BeginObject | TokenType::BeginArray => {
+                stack.push(Frame {
+                    object: token.kind == TokenType::BeginObject,
+                    start,
+                    members: Vec::new(),
+                    key: None,
+                });
+                continue;
+            }
+            TokenType::Key => {
+                if let Some(frame) = stack.last_mut() {
+                    frame.key = Some((start + 1, end - 1));
+                }
+                continue;
+            }
+            TokenType::Colon | TokenType::Comma => continue,
+            TokenType::EndObject | TokenType::EndArray => {
+                let Some(frame) = stack.pop() else {
+                    return Err(SonicError::ParseError("Unbalanced container".to_string()));
+                };
+                Entry {
+                    start: frame.start,
+                    end,
+                    node: write_node(json, &mut words, frame)?,
+                }
+            }
+            _ => Entry {
+                start,
+                end,
+                node: NO_NODE,
+            },
+        };
+        match stack.last_mut() {
+            Some(frame) => {
+                let key = frame.key.take();
+                frame.members.push((key, entry));
+            }
+            None => root = Some(entry),
+        }
+    }
+    let root = root.ok_or_else(|| SonicError::ParseError("Empty document".to_string()))?;
+
+    words[0] = u32::from_le_bytes(*MAGIC);
+    words[1] = json.len() as u32;
+    words[2] = root.start;
+    words[3] = root.end;
+    words[4] = root.node;
+    Ok(words.iter().flat_map(|w| w.to_le_bytes()).collect())
+}
+
+/// Append the node of a closed container and return its word offset.
+/// Object members are sorted by key; the sort is stable, so the first of
+/// duplicate keys is found first, as `Sift::get()` finds it.
+fn write_node(json: &str, words: &mut Vec<u32>, mut frame: Frame) -> Result<u32, SonicError> {
+    if frame.object {
+        let mut keyed = Vec::with_capacity(frame.members.len());
+        for (key, entry) in frame.members {
+            let (key_start, key_end) = key.unwrap_or((0, 0));
+            let text = key_text(&json[key_start as usize..key_end as usize])?;
+            keyed.push((text, (key_start, key_end), entry));
+        }
+        keyed.sort_by(|a, b| a.0.cmp(&b.0));
+        frame.members = keyed
+            .into_iter()
+            .map(|(_, key, entry)| (Some(key), entry))
+            .collect();
+    }
+    let offset = words.len() as u32;
+    let header = frame.members.len() as u32 | if frame.object { OBJECT_BIT } else { 0 };
+    words.push(header);
+    for (key, entry) in frame.members {
+        if let Some((key_start, key_end)) = key {
+            words.extend([key_start, key_end]);
+        }
+        words.extend([entry.start, entry.end, entry.node]);
+    }
+    Ok(offset)
+}
+
+/// A checked view of an index paired with its document.
+struct Index<'a> {
+    json: &'a str,
+    index: &'a [u8],
+}
+
+/// Error for an index that is corrupt or belongs to another document.
+fn mismatch(detail: &str) -> SonicError {
+    SonicError::FormatError(format!("Index does not match the JSON: {}", detail))
+}
+
+impl<'a> Index<'a> {
+    fn new(json: &'a str, index: &'a [u8]) -> Result<Self, SonicError> {
+        if index.len() < HEADER_WORDS * 4 || index.len() % 4 != 0 || &index[..4] != MAGIC {
+            return Err(SonicError::FormatError(
+                "Not a Sift index; build one with Sift::index()".to_string(),
+            ));
+        }
+        let view = Self { json, index };
+        if view.word(1)? as usize != json.len() {
+            return Err(mismatch("length differs"));
+        }
+        Ok(view)
+    }
+
+    fn word(&self, at: usize) -> Result<u32, SonicError> {
+        self.index
+            .get(at * 4..at * 4 + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| mismatch("truncated"))
+    }
+
+    /// The entry stored at word `at`.
+    fn entry(&self, at: usize) -> Result<Entry, SonicError> {
+        Ok(Entry {
+            start: self.word(at)?,
+            end: self.word(at + 1)?,
+            node: self.word(at + 2)?,
+        })
+    }
+
+    /// `json[start..end]`, checked against the document.
+    fn text(&self, start: u32, end: u32) -> Result<&'a str, SonicError> {
+        self.json
+            .get(start as usize..end as usize)
+            .ok_or_else(|| mismatch("offset out of range"))
+    }
+
+    /// The member of `entry` that `node` selects, if any.
+    fn child(&self, entry: Entry, node: &PointerNode) -> Result<Option<Entry>, SonicError> {
+        if entry.node == NO_NODE {
+            return Ok(None);
+        }
+        let at = entry.node as usize;
+        let header = self.word(at)?;
+        let object = header & OBJECT_BIT != 0;
+        let count = (header & !OBJECT_BIT) as usize;
+        let delimiter = if object { b'{' } else { b'[' };
+        if self.json.as_bytes().get(entry.start as usize) != Some(&delimiter) {
+            return Err(mismatch("container moved"));
+        }
+        match (object, node) {
+            (false, PointerNode::Index(idx)) if *idx < count => {
+                self.entry(at + 1 + idx * 3).map(Some)
+            }
+            (true, PointerNode::Key(key)) => {
+                let (mut low, mut high) = (0, count);
+                while low < high {
+                    let mid = (low + high) / 2;
+                    let member = at + 1 + mid * 5;
+                    let raw = self.text(self.word(member)?, self.word(member + 1)?)?;
+                    match key_text(raw)?.as_ref().cmp(key.as_str()) {
+                        Ordering::Less => low = mid + 1,
+                        _ => high = mid,
+                    }
+                }
+                if low == count {
+                    return Ok(None);
+                }
+                let member = at + 1 + low * 5;
+                let raw = self.text(self.word(member)?, self.word(member + 1)?)?;
+                if key_text(raw)?.as_ref() != key.as_str() {
+                    return Ok(None);
+                }
+                self.entry(member + 2).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Resolve `pointer` through `index` and convert the value there.
+/// Pointer segments are read as `Sift::get()` reads them.
+pub fn get(
+    json: &str,
+    index: &[u8],
+    pointer: &str,
+    options: &DecodeOptions,
+) -> Result<Zval, SonicError> {
+    let view = Index::new(json, index)?;
+    let entry = arena::with_arena(|bump| {
+        let mut entry = view.entry(2)?;
+        for node in parser::pointer_nodes(pointer, bump)?.iter() {
+            entry = view.child(entry, node)?.ok_or_else(|| {
+                SonicError::KeyNotFound("Path not found".to_string()).at_pointer(pointer)
+            })?;
+        }
+        Ok::<_, SonicError>(entry)
+    })?;
+    let raw = view.text(entry.start, entry.end)?;
+    let lazy = sonic_rs::get(raw, &[] as &[PointerNode]).map_err(|_| mismatch("value moved"))?;
+    parser::lazyvalue_to_zval(lazy, options)
+}
@@ -0,0 +1,165 @@
+//! Build and CPU capability report for `Sift::info()`.
+//!
+//! Usage:
+//! ```php
+//! $info = Sift::info();
+//! if ($info['simd'] === 'fallback') {
+//!     error_log("sift built without SIMD; CPU supports {$info['cpu_simd']}");
+//! }
+//! ```
+//!
+//! sonic-rs picks its SIMD implementation when it is compiled, from the
+//! target features enabled for the build, so a build for a generic target
+//! runs the portable fallback even on CPUs with AVX2. The `portable`
+//! cargo feature leaves that choice alone; it only drops the SIMD UTF-8
+//! validator and the startup warning.
+
+use crate::errors::SonicError;
+use crate::options::MAX_DEPTH_LIMIT;
+use crate::parser::{MAX_DEPTH, MAX_INPUT_SIZE, MAX_POINTER_SEGMENTS};
+use ext_php_rs::error::Error;
+use ext_php_rs::types::{ZendHashTable, Zval};
+
+/// The JSON engine the extension is built on.
+const ENGINE: &str = "sonic-rs 0.3";
+
+/// The SIMD instruction set sonic-rs was compiled to use.
+pub fn compiled_simd() -> &'static str {
+    if cfg!(all(
+        target_arch = "x86_64",
+        target_feature = "pclmulqdq",
+        target_feature = "avx2",
+        target_feature = "sse2"
+    )) {
+        "avx2"
+    } else if cfg!(all(target_arch = "aarch64", target_feature = "neon")) {
+        "neon"
+    } else {
+        "fallback"
+    }
+}
+
+/// The best of those instruction sets this CPU supports. When it differs
+/// from `compiled_simd()`, a build with `-C target-cpu=native` is faster.
+pub fn cpu_simd() -> &'static str {
+    let features = cpu_features();
+    let has = |name| features.iter().any(|&(n, on)| n == name && on);
+    if has("avx2") && has("pclmulqdq") {
+        "avx2"
+    } else if has("neon") {
+        "neon"
+    } else {
+        "fallback"
+    }
+}
+
+/// The SIMD features sonic-rs can use, and whether this CPU has them.
+#[cfg(target_arch = "x86_64")]
+fn cpu_features() -> Vec<(&'static str, bool)> {
+    vec![
+        ("sse2", std::arch::is_x86_feature_detected!("sse2")),
+        ("sse4.2", std::arch::is_x86_feature_detected!("sse4.2")),
+        (
+            "pclmulqdq",
+            std::arch::is_x86_feature_detected!("pclmulqdq"),
+        ),
+        ("avx2", std::arch::is_x86_feature_detected!("avx2")),
+    ]
+}
+
+/// The SIMD features sonic-rs can use, and whether this CPU has them.
+#[cfg(target_arch = "aarch64")]
+fn cpu_features() -> Vec<(&'static str, bool)> {
+    vec![("neon", std::arch::is_aarch64_feature_detected!("neon"))]
+}
+
+/// No SIMD implementation exists for other architectures.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn cpu_features() -> Vec<(&'static str, bool)> {
+    Vec::new()
+}
+
+/// The SIMD target features enabled for this build.
+fn build_features() -> Vec<&'static str> {
+    [
+        ("sse2", cfg!(target_feature = "sse2")),
+        ("sse4.2", cfg!(target_feature = "sse4.2")),
+        ("pclmulqdq", cfg!(target_feature = "pclmulqdq")),
+        ("avx2", cfg!(target_feature = "avx2")),
+        ("neon", cfg!(target_feature = "neon")),
+    ]
+    .into_iter()
+    .filter_map(|(name, on)| on.then_some(name))
+    .collect()
+}
+
+/// Log a warning at startup when the build runs the portable fallback,
+/// naming the instruction set a native build would use instead. Portable
+/// builds run it on purpose and aren't warned about.
+pub fn warn_if_fallback() {
+    if cfg!(feature = "portable") || compiled_simd() != "fallback" {
+        return;
+    }
+    match cpu_simd() {
+        "fallback" => log::warn!("sift: no SIMD support on this CPU, using the portable parser"),
+        simd => log::warn!(
+            "sift: built without SIMD, using the portable parser although this CPU supports {}; \
+             rebuild with RUSTFLAGS=\"-C target-cpu=native\"",
+            simd
+        ),
+    }
+}
+
+/// Error for a failed insert into a result array.
+fn insert_error(e: Error) -> SonicError {
+    SonicError::TypeError(format!("Failed to insert object key: {}", e))
+}
+
+/// The version, engine, SIMD paths (compiled and supported), detected CPU
+/// features, limits and build flags, as a PHP array.
+pub fn info() -> Result<Zval, SonicError> {
+    let mut cpu = ZendHashTable::new();
+    for (name, on) in cpu_features() {
+        cpu.insert(name, on).map_err(insert_error)?;
+    }
+
+    let mut limits = ZendHashTable::new();
+    limits
+        .insert("max_input_size", MAX_INPUT_SIZE as i64)
+        .and_then(|_| limits.insert("max_depth", MAX_DEPTH as i64))
+        .and_then(|_| limits.insert("max_depth_option", MAX_DEPTH_LIMIT as i64))
+        .and_then(|_| limits.insert("max_pointer_segments", MAX_POINTER_SEGMENTS as i64))
+        .map_err(insert_error)?;
+
+    let mut features = ZendHashTable::new();
+    for name in build_features() {
+        features.push(name).map_err(insert_error)?;
+    }
+    let profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    let mut build = ZendHashTable::new();
+    build
+        .insert("profile", profile)
+        .and_then(|_| build.insert("portable", cfg!(feature = "portable")))
+        .and_then(|_| build.insert("arch", std::env::consts::ARCH))
+        .and_then(|_| build.insert("os", std::env::consts::OS))
+        .and_then(|_| build.insert("target_features", features))
+        .map_err(insert_error)?;
+
+    let mut report = ZendHashTable::new();
+    report
+        .insert("version", env!("CARGO_PKG_VERSION"))
+        .and_then(|_| report.insert("engine", ENGINE))
+        .and_then(|_| report.insert("simd", compiled_simd()))
+        .and_then(|_| report.insert("cpu_simd", cpu_simd()))
+        .and_then(|_| report.insert("cpu", cpu))
+        .and_then(|_| report.insert("limits", limits))
+        .and_then(|_| report.insert("build", build))
+        .map_err(insert_error)?;
+    let mut zval = Zval::new();
+    zval.set_hashtable(report);
+    Ok(zval)
+}
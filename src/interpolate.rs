@@ -0,0 +1,43 @@
+//! Reading the `interpolate` option from PHP; substitution itself is
+//! `sift_core::interpolate`.
+//!
+//! Usage:
+//! ```php
+//! // {"dsn": "mysql:host=${DB_HOST}", "greeting": "Hello {{ name }}"}
+//! $config = Sift::decode($json, ['interpolate' => 'env']);
+//! $page = Sift::decode($json, ['interpolate' => ['name' => 'Ada']]);
+//! ```
+
+use crate::errors::SonicError;
+use ext_php_rs::types::{ArrayKey, Zval};
+use std::collections::HashMap;
+
+pub use sift_core::interpolate::{reset_variables, Interpolate};
+
+/// Read the `interpolate` option: "env" or an array of string variables.
+pub fn from_zval(name: &str, value: &Zval) -> Result<Interpolate, SonicError> {
+    if value.str() == Some("env") {
+        return Ok(Interpolate::Env);
+    }
+    let Some(entries) = value.array() else {
+        return Err(SonicError::InvalidOption(format!(
+            "Option '{}' must be 'env' or an array of variables",
+            name
+        )));
+    };
+    let mut variables = HashMap::with_capacity(entries.len());
+    for (key, value) in entries.iter() {
+        let key = match key {
+            ArrayKey::String(key) => key,
+            ArrayKey::Long(index) => index.to_string(),
+        };
+        let value = value.string().ok_or_else(|| {
+            SonicError::InvalidOption(format!(
+                "Option '{}' variable '{}' must be a string",
+                name, key
+            ))
+        })?;
+        variables.insert(key, value);
+    }
+    Ok(Interpolate::from_variables(variables))
+}
@@ -0,0 +1,266 @@
+//! JSON:API (https://jsonapi.org) compound document helpers.
+//!
+//! Usage:
+//! ```php
+//! $doc = Sift::jsonApi($json);
+//! $article = $doc->resource();
+//! $author = $doc->related('author');                // Resolved from "included"
+//! $person = $doc->included('people', '9');           // Hash lookup, not a scan
+//! ```
+
+use crate::errors::{ErrorCode, SonicError};
+use crate::guard::guard;
+use crate::parser;
+use crate::query::Query;
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendHashTable, Zval};
+use sonic_rs::{to_array_iter_unchecked, to_object_iter_unchecked};
+use sonic_rs::{JsonValueTrait, LazyValue, PointerNode};
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// A resource's `(type, id)` identity.
+type Identity = (String, String);
+
+/// The `type` and `id` members of a resource or resource identifier,
+/// if it is an object with both as strings.
+fn identity(lazy: &LazyValue) -> Result<Option<Identity>, SonicError> {
+    if !lazy.is_object() {
+        return Ok(None);
+    }
+    let (mut kind, mut id) = (None, None);
+    // SAFETY: we've verified this is an object via is_object()
+    for entry in unsafe { to_object_iter_unchecked(lazy.as_raw_str()) } {
+        let (key, value) = entry?;
+        match &*key {
+            "type" => kind = value.as_str().map(str::to_string),
+            "id" => id = value.as_str().map(str::to_string),
+            _ => {}
+        }
+    }
+    Ok(kind.zip(id))
+}
+
+/// JsonApi - lookups over a JSON:API compound document.
+/// Included resources are indexed by `(type, id)` on the first lookup, in
+/// one pass that records where each one is in the document; lookups then
+/// parse and hydrate only the resource they return.
+#[php_class(name = "Sift\\JsonApi")]
+#[derive(Debug)]
+pub struct JsonApi {
+    /// The whole document
+    query: Query,
+    /// Byte range of each included resource, built on first use
+    included: OnceCell<HashMap<Identity, Range<usize>>>,
+}
+
+impl JsonApi {
+    pub fn new(query: Query) -> Self {
+        Self {
+            query,
+            included: OnceCell::new(),
+        }
+    }
+
+    /// The unparsed value at `pointer`.
+    fn lazy_at(&self, pointer: &str) -> Result<LazyValue<'_>, SonicError> {
+        // Validate input size to prevent DoS
+        parser::check_input_size(self.query.json())?;
+        parser::lazy_at(self.query.json(), pointer)
+    }
+
+    /// Hydrate a value with the document's options.
+    fn hydrate(&self, lazy: LazyValue) -> Result<Zval, SonicError> {
+        parser::lazyvalue_to_zval(lazy, self.query.options())
+    }
+
+    /// Pointer to the primary resource: `/data` for a single-resource
+    /// document, `/data/{index}` for a collection.
+    fn resource_pointer(&self, index: Option<i64>) -> Result<String, SonicError> {
+        let data = self.lazy_at("/data")?;
+        let Some(i) = index else {
+            if data.is_array() {
+                return Err(SonicError::InvalidOption(
+                    "Primary data is a collection; pass the index of a resource".to_string(),
+                ));
+            }
+            if !data.is_object() {
+                return Err(
+                    SonicError::TypeError("Primary data is not a resource".to_string())
+                        .at_pointer("/data")
+                        .with_types("object", parser::type_name(&data)),
+                );
+            }
+            return Ok("/data".to_string());
+        };
+
+        if !data.is_array() {
+            return Err(
+                SonicError::TypeError("Primary data is not a collection".to_string())
+                    .at_pointer("/data")
+                    .with_types("array", parser::type_name(&data)),
+            );
+        }
+        if i < 0 {
+            return Err(SonicError::InvalidOption(format!(
+                "Resource index must be non-negative, got {}",
+                i
+            )));
+        }
+        Ok(format!("/data/{}", i))
+    }
+
+    /// The `relationships` object of the primary resource, if it has one.
+    fn relationships_of(
+        &self,
+        index: Option<i64>,
+    ) -> Result<(Option<LazyValue<'_>>, String), SonicError> {
+        let pointer = format!("{}/relationships", self.resource_pointer(index)?);
+        match self.lazy_at(&pointer) {
+            Ok(lazy) if lazy.is_object() => Ok((Some(lazy), pointer)),
+            Ok(lazy) => Err(
+                SonicError::TypeError("Relationships must be an object".to_string())
+                    .at_pointer(pointer)
+                    .with_types("object", parser::type_name(&lazy)),
+            ),
+            Err(e) if e.code() == ErrorCode::KeyNotFound as i32 => Ok((None, pointer)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Index the `included` array, once.
+    fn included_index(&self) -> Result<&HashMap<Identity, Range<usize>>, SonicError> {
+        if let Some(index) = self.included.get() {
+            return Ok(index);
+        }
+
+        let json = self.query.json();
+        let mut index = HashMap::new();
+        match self.lazy_at("/included") {
+            Ok(included) if included.is_array() => {
+                // SAFETY: we've verified this is an array via is_array()
+                for item in unsafe { to_array_iter_unchecked(included.as_raw_str()) } {
+                    let item = item?;
+                    if let Some(identity) = identity(&item)? {
+                        // Resources are slices of the document
+                        let start = item.as_raw_str().as_ptr() as usize - json.as_ptr() as usize;
+                        index
+                            .entry(identity)
+                            .or_insert(start..start + item.as_raw_str().len());
+                    }
+                }
+            }
+            Ok(included) => {
+                return Err(
+                    SonicError::TypeError("'included' must be an array".to_string())
+                        .at_pointer("/included")
+                        .with_types("array", parser::type_name(&included)),
+                );
+            }
+            Err(e) if e.code() == ErrorCode::KeyNotFound as i32 => {}
+            Err(e) => return Err(e),
+        }
+        Ok(self.included.get_or_init(|| index))
+    }
+
+    /// Hydrate the included resource with this identity, or null.
+    fn hydrate_included(&self, identity: &Identity) -> Result<Zval, SonicError> {
+        let Some(range) = self.included_index()?.get(identity) else {
+            return Ok(Zval::new());
+        };
+        let resource = sonic_rs::get(&self.query.json()[range.clone()], &[] as &[PointerNode])?;
+        self.hydrate(resource)
+    }
+
+    /// Hydrate the included resource a resource identifier refers to,
+    /// or null.
+    fn resolve(&self, identifier: &LazyValue) -> Result<Zval, SonicError> {
+        match identity(identifier)? {
+            Some(identity) => self.hydrate_included(&identity),
+            None => Ok(Zval::new()),
+        }
+    }
+}
+
+#[php_impl]
+impl JsonApi {
+    /// The primary data: the resource, the list of resources, or null.
+    /// With `index`, the resource at that position of a collection.
+    pub fn resource(&self, index: Option<i64>) -> Result<Zval, SonicError> {
+        guard(|| {
+            let pointer = match index {
+                None => "/data".to_string(),
+                Some(_) => self.resource_pointer(index)?,
+            };
+            self.hydrate(self.lazy_at(&pointer)?)
+        })
+    }
+
+    /// Relationship name => linkage (a resource identifier, a list of
+    /// them, or null) for the primary resource. Relationships without
+    /// `data` (links only) are left out.
+    pub fn relationships(&self, index: Option<i64>) -> Result<Zval, SonicError> {
+        guard(|| {
+            let mut result = ZendHashTable::new();
+            if let (Some(relationships), _) = self.relationships_of(index)? {
+                // SAFETY: relationships_of() has verified this is an object
+                for entry in unsafe { to_object_iter_unchecked(relationships.as_raw_str()) } {
+                    let (name, relationship) = entry?;
+                    let linkage = relationship
+                        .get("data")
+                        .map(|data| self.hydrate(data))
+                        .transpose()?;
+                    if let Some(linkage) = linkage {
+                        result.insert(&*name, linkage).map_err(|e| {
+                            SonicError::TypeError(format!("Failed to insert object key: {}", e))
+                        })?;
+                    }
+                }
+            }
+
+            let mut zval = Zval::new();
+            zval.set_hashtable(result);
+            Ok(zval)
+        })
+    }
+
+    /// The included resource with this type and id, or null.
+    pub fn included(&self, r#type: &str, id: &str) -> Result<Zval, SonicError> {
+        guard(|| self.hydrate_included(&(r#type.to_string(), id.to_string())))
+    }
+
+    /// The included resources a relationship of the primary resource links
+    /// to: one resource (or null) for a to-one relationship, a list for a
+    /// to-many one, with null where a resource isn't included.
+    pub fn related(&self, relationship: &str, index: Option<i64>) -> Result<Zval, SonicError> {
+        guard(|| {
+            let (relationships, pointer) = self.relationships_of(index)?;
+            let member = relationships.as_ref().and_then(|r| r.get(relationship));
+            let linkage = member.as_ref().and_then(|m| m.get("data")).ok_or_else(|| {
+                SonicError::KeyNotFound(format!("No linkage for relationship '{}'", relationship))
+                    .at_pointer(format!("{}/{}/data", pointer, relationship))
+            })?;
+
+            if !linkage.is_array() {
+                return self.resolve(&linkage);
+            }
+            let mut list = ZendHashTable::new();
+            // SAFETY: we've verified this is an array via is_array()
+            for identifier in unsafe { to_array_iter_unchecked(linkage.as_raw_str()) } {
+                list.push(self.resolve(&identifier?)?).map_err(|e| {
+                    SonicError::TypeError(format!("Failed to push array item: {}", e))
+                })?;
+            }
+            let mut zval = Zval::new();
+            zval.set_hashtable(list);
+            Ok(zval)
+        })
+    }
+
+    /// A Query over the whole document, for members such as `meta` and
+    /// `links`.
+    pub fn query(&self) -> Query {
+        self.query.clone()
+    }
+}
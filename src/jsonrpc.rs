@@ -0,0 +1,311 @@
+//! JSON-RPC 2.0 (https://www.jsonrpc.org/specification) envelope parsing.
+//!
+//! Usage:
+//! ```php
+//! $request = Sift\JsonRpc::parseRequest($body);
+//! if (!$request->isValid()) {
+//!     echo json_encode($request->errorResponse());
+//! } else {
+//!     $rows = $request->params()->get('rows')->columns(['id', 'name']);
+//! }
+//! ```
+
+use crate::errors::SonicError;
+use crate::guard::guard;
+use crate::options::{DecodeOptions, PhpOptions};
+use crate::parser;
+use crate::query::Query;
+use ext_php_rs::binary_slice::BinarySlice;
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendHashTable, Zval};
+use sonic_rs::{to_array_iter_unchecked, to_object_iter_unchecked};
+use sonic_rs::{JsonValueTrait, LazyValue, PointerNode};
+
+/// Error code for a body that isn't valid JSON.
+const PARSE_ERROR: i64 = -32700;
+/// Error code for valid JSON that isn't a valid request.
+const INVALID_REQUEST: i64 = -32600;
+
+/// Why an envelope was rejected.
+#[derive(Clone, Debug)]
+struct Rejection {
+    /// JSON-RPC error code
+    code: i64,
+    /// What was wrong, reported as the error's `data`
+    detail: String,
+}
+
+impl Rejection {
+    /// The error's `message`, as the specification words it.
+    fn message(&self) -> &'static str {
+        match self.code {
+            PARSE_ERROR => "Parse error",
+            _ => "Invalid Request",
+        }
+    }
+}
+
+/// RpcRequest - one JSON-RPC 2.0 request envelope.
+/// `params` stays unparsed JSON behind a Query; a malformed envelope
+/// carries the error object to answer it with instead.
+#[php_class(name = "Sift\\RpcRequest")]
+#[derive(Clone, Debug)]
+pub struct RpcRequest {
+    /// The `method` member, None if the envelope was rejected
+    method: Option<String>,
+    /// A Query over the `params` member, if present
+    params: Option<Query>,
+    /// The `id` to answer with as JSON text, None for a notification
+    id: Option<String>,
+    /// Why the envelope was rejected, if it was
+    rejection: Option<Rejection>,
+    /// Options `id` is hydrated with
+    options: DecodeOptions,
+}
+
+impl RpcRequest {
+    /// A rejected envelope, answered with `id` (JSON text).
+    fn rejected(code: i64, detail: impl Into<String>, id: &str, options: DecodeOptions) -> Self {
+        Self {
+            method: None,
+            params: None,
+            id: Some(id.to_string()),
+            rejection: Some(Rejection {
+                code,
+                detail: detail.into(),
+            }),
+            options,
+        }
+    }
+
+    /// The rejection for a body that isn't valid JSON.
+    fn parse_error(error: SonicError, options: DecodeOptions) -> Self {
+        Self::rejected(PARSE_ERROR, error.to_string(), "null", options)
+    }
+
+    /// Validate one envelope. A rejected envelope is answered with its
+    /// `id` if that is a valid id, null otherwise.
+    fn from_lazy(envelope: &LazyValue, options: DecodeOptions) -> Result<Self, SonicError> {
+        if !envelope.is_object() {
+            return Ok(Self::rejected(
+                INVALID_REQUEST,
+                "Request must be an object",
+                "null",
+                options,
+            ));
+        }
+
+        let (mut version, mut method, mut params, mut id) = (None, None, None, None);
+        // SAFETY: we've verified this is an object via is_object()
+        for entry in unsafe { to_object_iter_unchecked(envelope.as_raw_str()) } {
+            let (key, value) = entry?;
+            match &*key {
+                "jsonrpc" => version = Some(value),
+                "method" => method = Some(value),
+                "params" => params = Some(value),
+                "id" => id = Some(value),
+                _ => {}
+            }
+        }
+
+        let id_valid = id
+            .as_ref()
+            .is_none_or(|id| id.is_str() || id.is_number() || id.is_null());
+        let answer = match &id {
+            Some(id) if id_valid => id.as_raw_str(),
+            _ => "null",
+        };
+        let problem = if version.as_ref().and_then(|v| v.as_str()) != Some("2.0") {
+            Some("'jsonrpc' must be exactly \"2.0\"")
+        } else if !method.as_ref().is_some_and(|m| m.is_str()) {
+            Some("'method' must be a string")
+        } else if params
+            .as_ref()
+            .is_some_and(|p| !p.is_array() && !p.is_object())
+        {
+            Some("'params' must be an array or object")
+        } else if !id_valid {
+            Some("'id' must be a string, number or null")
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            return Ok(Self::rejected(INVALID_REQUEST, problem, answer, options));
+        }
+
+        Ok(Self {
+            method: method.as_ref().and_then(|m| m.as_str()).map(str::to_string),
+            params: params.map(|p| Query::new(p.as_raw_str().to_string(), options)),
+            id: id.map(|id| id.as_raw_str().to_string()),
+            rejection: None,
+            options,
+        })
+    }
+}
+
+/// The root value of a request body. The whole body is validated first,
+/// since envelopes are then read lazily.
+fn parse_body(text: &str) -> Result<LazyValue<'_>, SonicError> {
+    sonic_rs::from_str::<sonic_rs::Value>(text)?;
+    Ok(sonic_rs::get(text, &[] as &[PointerNode])?)
+}
+
+#[php_impl]
+impl RpcRequest {
+    /// Whether the envelope is a valid request.
+    pub fn is_valid(&self) -> bool {
+        self.rejection.is_none()
+    }
+
+    /// Whether this is a notification (a valid request without `id`),
+    /// which must not be answered.
+    pub fn is_notification(&self) -> bool {
+        self.id.is_none()
+    }
+
+    /// The method name, or null for a rejected envelope.
+    pub fn method(&self) -> Option<String> {
+        self.method.clone()
+    }
+
+    /// A lazy Query over `params`, or null if there are none. Pointers in
+    /// its error messages are relative to `params`.
+    pub fn params(&self) -> Option<Query> {
+        self.params.clone()
+    }
+
+    /// The request id (string, number or null); null for a notification.
+    pub fn id(&self) -> Result<Zval, SonicError> {
+        guard(|| match &self.id {
+            Some(id) => parser::decode(id, &self.options),
+            None => Ok(Zval::new()),
+        })
+    }
+
+    /// The error object for a rejected envelope
+    /// (`['code' => -32600, 'message' => 'Invalid Request', 'data' => ...]`),
+    /// or null for a valid request.
+    pub fn error(&self) -> Result<Zval, SonicError> {
+        guard(|| {
+            let Some(rejection) = &self.rejection else {
+                return Ok(Zval::new());
+            };
+            let mut error = ZendHashTable::new();
+            error
+                .insert("code", rejection.code)
+                .and_then(|_| error.insert("message", rejection.message()))
+                .and_then(|_| error.insert("data", rejection.detail.as_str()))
+                .map_err(|e| {
+                    SonicError::TypeError(format!("Failed to insert object key: {}", e))
+                })?;
+
+            let mut zval = Zval::new();
+            zval.set_hashtable(error);
+            Ok(zval)
+        })
+    }
+
+    /// The complete response to a rejected envelope, ready to encode, or
+    /// null for a valid request.
+    pub fn error_response(&self) -> Result<Zval, SonicError> {
+        guard(|| {
+            if self.rejection.is_none() {
+                return Ok(Zval::new());
+            }
+            let (error, id) = (self.error()?, self.id()?);
+            let mut response = ZendHashTable::new();
+            response
+                .insert("jsonrpc", "2.0")
+                .and_then(|_| response.insert("error", error))
+                .and_then(|_| response.insert("id", id))
+                .map_err(|e| {
+                    SonicError::TypeError(format!("Failed to insert object key: {}", e))
+                })?;
+
+            let mut zval = Zval::new();
+            zval.set_hashtable(response);
+            Ok(zval)
+        })
+    }
+}
+
+/// JsonRpc - JSON-RPC 2.0 envelope parsing.
+/// Envelopes are validated in Rust and `params` is handed out as a lazy
+/// Query, so a large payload is never decoded just to route the call.
+#[php_class(name = "Sift\\JsonRpc")]
+pub struct JsonRpc;
+
+#[php_impl]
+impl JsonRpc {
+    /// Parse a single request. A malformed body or envelope doesn't
+    /// throw: the result is not valid and carries the error object
+    /// (-32700 Parse error, -32600 Invalid Request) to answer with.
+    /// Takes the same options as `Sift::query()`.
+    #[php_static]
+    pub fn parse_request(
+        json: BinarySlice<u8>,
+        options: Option<&ZendHashTable>,
+    ) -> Result<RpcRequest, SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            // Oversized bodies throw rather than being answered
+            parser::check_input_size(&*json)?;
+            let text = match parser::input_text(&json, &options) {
+                Ok(text) => text,
+                Err(e) => return Ok(RpcRequest::parse_error(e, options)),
+            };
+            match parse_body(&text) {
+                Ok(envelope) if envelope.is_array() => Ok(RpcRequest::rejected(
+                    INVALID_REQUEST,
+                    "Batch requests must be parsed with parseBatch()",
+                    "null",
+                    options,
+                )),
+                Ok(envelope) => RpcRequest::from_lazy(&envelope, options),
+                Err(e) => Ok(RpcRequest::parse_error(e, options)),
+            }
+        })
+    }
+
+    /// Parse a batch: one RpcRequest per element, in order. A body that
+    /// isn't an array is parsed as a single request; an empty batch or a
+    /// malformed body gives a single rejected request, since the
+    /// specification answers those with one error response.
+    #[php_static]
+    pub fn parse_batch(
+        json: BinarySlice<u8>,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Vec<RpcRequest>, SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            // Oversized bodies throw rather than being answered
+            parser::check_input_size(&*json)?;
+            let text = match parser::input_text(&json, &options) {
+                Ok(text) => text,
+                Err(e) => return Ok(vec![RpcRequest::parse_error(e, options)]),
+            };
+            let batch = match parse_body(&text) {
+                Ok(batch) => batch,
+                Err(e) => return Ok(vec![RpcRequest::parse_error(e, options)]),
+            };
+            if !batch.is_array() {
+                return Ok(vec![RpcRequest::from_lazy(&batch, options)?]);
+            }
+
+            let mut requests = Vec::new();
+            // SAFETY: we've verified this is an array via is_array()
+            for envelope in unsafe { to_array_iter_unchecked(batch.as_raw_str()) } {
+                requests.push(RpcRequest::from_lazy(&envelope?, options)?);
+            }
+            if requests.is_empty() {
+                requests.push(RpcRequest::rejected(
+                    INVALID_REQUEST,
+                    "Batch must not be empty",
+                    "null",
+                    options,
+                ));
+            }
+            Ok(requests)
+        })
+    }
+}
@@ -0,0 +1,51 @@
+//! JWT payload inspection for `Sift::jwtClaims()`.
+//!
+//! Splits a JWS compact serialization (RFC 7515) and base64url-decodes its
+//! header and payload. The signature is NOT verified: this is for peeking
+//! at claims such as `exp` or `sub` before full validation elsewhere.
+
+use crate::base64::{self, Alphabet};
+use crate::errors::SonicError;
+use crate::parser;
+use sonic_rs::{JsonValueTrait, PointerNode};
+
+/// Decode one base64url segment of a token.
+fn decode_segment(segment: &str, what: &str) -> Result<Vec<u8>, SonicError> {
+    base64::decode(segment, Alphabet::UrlSafe).map_err(|e| {
+        SonicError::FormatError(format!(
+            "JWT {} is not valid base64url: {}",
+            what,
+            e.describe()
+        ))
+    })
+}
+
+/// The payload (claims) JSON of `token`, after checking that the token has
+/// three segments and a header that decodes to a JSON object. The payload
+/// isn't checked for UTF-8 here, so the caller's `utf8` option applies.
+pub fn claims(token: &str) -> Result<Vec<u8>, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(token)?;
+
+    let segments: Vec<&str> = token.trim().split('.').collect();
+    let [header, payload, _signature] = segments[..] else {
+        return Err(SonicError::FormatError(if segments.len() == 5 {
+            "Encrypted JWTs (JWE) are not supported".to_string()
+        } else {
+            format!("JWT must have 3 segments, got {}", segments.len())
+        }));
+    };
+
+    let header = decode_segment(header, "header")?;
+    let is_object = std::str::from_utf8(&header)
+        .ok()
+        .and_then(|header| sonic_rs::get(header, &[] as &[PointerNode]).ok())
+        .is_some_and(|lazy| lazy.is_object());
+    if !is_object {
+        return Err(SonicError::FormatError(
+            "JWT header is not a JSON object".to_string(),
+        ));
+    }
+
+    decode_segment(payload, "payload")
+}
@@ -0,0 +1,94 @@
+//! Reading the `keys`, `strip_prefix` and `nest_keys` options from PHP,
+//! and the key callbacks only PHP can run; the built-in renamings are
+//! `sift_core::keys`.
+//!
+//! Usage:
+//! ```php
+//! $user = Sift::decode('{"first_name": "Ada"}', ['keys' => 'camel']);
+//! $user['firstName']; // "Ada"
+//! $data = Sift::decode($json, ['keys' => fn (string $key) => strtoupper($key)]);
+//! $row = Sift::decode('{"attr_user.name": "Ada"}', ['strip_prefix' => 'attr_', 'nest_keys' => '.']);
+//! $row['user']['name']; // "Ada"
+//! ```
+
+use crate::errors::SonicError;
+use ext_php_rs::error::Error;
+use ext_php_rs::types::Zval;
+use sift_core::keys::{Affix, KeyTransform, MAX_AFFIX_LEN};
+use std::cell::RefCell;
+
+thread_local! {
+    /// Callables given as the `keys` option, referenced by index from
+    /// `KeyTransform::Callback` so options stay `Copy`. Request-scoped
+    /// like the configured defaults.
+    static CALLBACKS: RefCell<Vec<Zval>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Read a string option of 1 to `MAX_AFFIX_LEN` bytes.
+pub fn affix_from_zval(name: &str, value: &Zval) -> Result<Affix, SonicError> {
+    value.str().and_then(Affix::new).ok_or_else(|| {
+        SonicError::InvalidOption(format!(
+            "Option '{}' must be a string of 1 to {} bytes",
+            name, MAX_AFFIX_LEN
+        ))
+    })
+}
+
+/// Read the `keys` option: "keep", "camel", "snake" or a callable
+/// taking and returning a string. The names win over functions that
+/// happen to share them.
+pub fn transform_from_zval(name: &str, value: &Zval) -> Result<KeyTransform, SonicError> {
+    match value.str() {
+        Some("keep") => return Ok(KeyTransform::Keep),
+        Some("camel") => return Ok(KeyTransform::Camel),
+        Some("snake") => return Ok(KeyTransform::Snake),
+        _ => {}
+    }
+    if !value.is_callable() {
+        return Err(SonicError::InvalidOption(format!(
+            "Option '{}' must be one of 'keep', 'camel', 'snake' or a callable",
+            name
+        )));
+    }
+    Ok(KeyTransform::Callback(register(value)))
+}
+
+/// Store `callback` for the rest of the request, reusing the slot of an
+/// identical callable so a callback passed on every call is held once.
+fn register(callback: &Zval) -> usize {
+    CALLBACKS.with(|callbacks| {
+        let mut callbacks = callbacks.borrow_mut();
+        if let Some(index) = callbacks.iter().position(|c| c.is_identical(callback)) {
+            return index;
+        }
+        callbacks.push(callback.shallow_clone());
+        callbacks.len() - 1
+    })
+}
+
+/// Run the callback at `index` on `key`; the conversion calls this
+/// through `ZvalSink::rename_key`.
+pub fn call(index: usize, key: &str) -> Result<String, SonicError> {
+    let callback = CALLBACKS
+        .with(|callbacks| callbacks.borrow().get(index).map(Zval::shallow_clone))
+        .ok_or_else(|| {
+            // Options holding a callback don't outlive the request
+            SonicError::InvalidOption("Key callback is no longer available".to_string())
+        })?;
+    let renamed = callback.try_call(vec![&key]).map_err(|e| match e {
+        Error::Exception(_) => SonicError::TypeError("Key callback threw an exception".to_string()),
+        e => SonicError::TypeError(format!("Key callback failed: {}", e)),
+    })?;
+    renamed.string().ok_or_else(|| {
+        SonicError::TypeError(format!(
+            "Key callback must return a string, got {:?} for key '{}'",
+            renamed.get_type(),
+            key
+        ))
+    })
+}
+
+/// Drops the registered callbacks; runs at request shutdown.
+pub fn reset_callbacks() {
+    CALLBACKS.with(|callbacks| callbacks.borrow_mut().clear());
+}
@@ -0,0 +1,193 @@
+//! Live-object accounting for the `sift.leak_check` INI flag,
+//! `Sift::liveObjects()` and `Sift::checkLeaks()`.
+//!
+//! Usage:
+//! ```ini
+//! ; development only
+//! sift.leak_check = 1
+//! ```
+//! ```php
+//! // RoadRunner / Swoole: objects made while booting are meant to stay
+//! Sift::checkLeaks(false);
+//! while ($request = $worker->waitRequest()) {
+//!     $worker->respond(handle($request));
+//!     Sift::checkLeaks();   // throws if this request left Queries behind
+//! }
+//! ```
+//!
+//! With the flag on, every Query, Pool, Snapshot and iterator carries a
+//! `Live` token that counts it per class while it exists. Counting starts
+//! over at each checkpoint: the end of a request, and every
+//! `checkLeaks()`. An object that is still alive at the next checkpoint
+//! was kept by something outliving the work that made it, such as a
+//! static property, a cache array or a reference cycle; in a long-running
+//! worker that is memory never given back.
+//!
+//! At the end of a request, objects its global variables held are already
+//! gone, so what is left is reported with `log::warn!`; exceptions can't
+//! be thrown there. Objects from before a checkpoint are forgotten, not
+//! reported again, when they are eventually freed.
+//!
+//! The flag is read once per request, on the first tracked object.
+//! With it off (the default), a token is a class name and nothing is
+//! counted.
+
+use crate::errors::SonicError;
+use ext_php_rs::types::{ZendCallable, ZendHashTable, Zval};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Name of the INI flag.
+pub const INI_NAME: &str = "sift.leak_check";
+
+thread_local! {
+    /// `sift.leak_check` for the current request; None until first read.
+    static ENABLED: Cell<Option<bool>> = const { Cell::new(None) };
+    /// Bumped at every checkpoint; tokens from earlier ones aren't counted.
+    static EPOCH: Cell<u64> = const { Cell::new(0) };
+    /// Objects of each class created since the last checkpoint and alive.
+    static LIVE: RefCell<BTreeMap<&'static str, usize>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// Counts its owner as a live object of `class` while leak checks are on.
+/// A clone is counted as another object.
+#[derive(Debug)]
+pub struct Live {
+    class: &'static str,
+    /// The checkpoint the owner was counted in; None if it wasn't
+    epoch: Option<u64>,
+}
+
+impl Live {
+    /// Token for a new object of the PHP class `class`.
+    pub fn new(class: &'static str) -> Self {
+        if !enabled() {
+            return Self { class, epoch: None };
+        }
+        LIVE.with_borrow_mut(|live| *live.entry(class).or_default() += 1);
+        Self {
+            class,
+            epoch: Some(EPOCH.get()),
+        }
+    }
+}
+
+impl Clone for Live {
+    fn clone(&self) -> Self {
+        Self::new(self.class)
+    }
+}
+
+impl Drop for Live {
+    fn drop(&mut self) {
+        // Objects freed while the thread exits have nobody to report to
+        let current = EPOCH.try_with(Cell::get).ok();
+        if self.epoch.is_none() || self.epoch != current {
+            return;
+        }
+        let _ = LIVE.try_with(|live| {
+            let mut live = live.borrow_mut();
+            if let Some(count) = live.get_mut(self.class) {
+                *count -= 1;
+                if *count == 0 {
+                    live.remove(self.class);
+                }
+            }
+        });
+    }
+}
+
+/// Whether `sift.leak_check` is on, reading it on first use in a request.
+fn enabled() -> bool {
+    if let Some(enabled) = ENABLED.get() {
+        return enabled;
+    }
+    let enabled = read_flag();
+    ENABLED.set(Some(enabled));
+    enabled
+}
+
+/// `sift.leak_check` as PHP's ini parser reads booleans; off if unset.
+fn read_flag() -> bool {
+    let Some(value) = ZendCallable::try_from_name("ini_get")
+        .ok()
+        .and_then(|ini_get| ini_get.try_call(vec![&INI_NAME]).ok())
+    else {
+        return false;
+    };
+    match value.str() {
+        Some(flag) => matches!(
+            flag.trim().to_ascii_lowercase().as_str(),
+            "1" | "on" | "yes" | "true"
+        ),
+        None => false,
+    }
+}
+
+/// Forget the objects counted so far and start counting again.
+fn checkpoint() -> BTreeMap<&'static str, usize> {
+    EPOCH.set(EPOCH.get().wrapping_add(1));
+    LIVE.take()
+}
+
+/// "2 Sift\Query, 1 Sift\Pool", with the total.
+fn describe(live: &BTreeMap<&'static str, usize>) -> (usize, String) {
+    let mut list = String::new();
+    for (class, count) in live {
+        if !list.is_empty() {
+            list.push_str(", ");
+        }
+        let _ = write!(list, "{} {}", count, class);
+    }
+    (live.values().sum(), list)
+}
+
+/// `[class => count]` of objects created since the last checkpoint that
+/// are still alive; empty with leak checks off.
+pub fn live_objects() -> Result<Zval, SonicError> {
+    let mut objects = ZendHashTable::new();
+    LIVE.with_borrow(|live| {
+        live.iter()
+            .try_for_each(|(class, count)| objects.insert(*class, *count as i64))
+    })
+    .map_err(|e| SonicError::TypeError(format!("Failed to insert object key: {}", e)))?;
+    let mut zval = Zval::new();
+    zval.set_hashtable(objects);
+    Ok(zval)
+}
+
+/// Start a new checkpoint. If objects from the previous one are still
+/// alive, fails listing them when `throw` is set; otherwise returns how
+/// many there are.
+pub fn check(throw: bool) -> Result<i64, SonicError> {
+    if !enabled() {
+        return Ok(0);
+    }
+    let live = checkpoint();
+    let (total, list) = describe(&live);
+    if throw && total > 0 {
+        return Err(SonicError::Leaked(format!(
+            "{} Sift object(s) outlived the last checkpoint: {}",
+            total, list
+        )));
+    }
+    Ok(total as i64)
+}
+
+/// End-of-request check: log what the request left alive, then start
+/// over, re-reading the flag in the next request.
+pub fn request_shutdown() {
+    if ENABLED.get() == Some(true) {
+        let live = checkpoint();
+        let (total, list) = describe(&live);
+        if total > 0 {
+            log::warn!(
+                "sift: {} object(s) still alive at the end of the request: {}",
+                total,
+                list
+            );
+        }
+    }
+    ENABLED.set(None);
+}
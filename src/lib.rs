@@ -2,13 +2,89 @@
 //!
 //! This extension provides high-performance JSON operations using the sonic-rs engine.
 
+mod anonymize;
+mod artifacts;
+mod base64;
+mod bench;
+mod bignum;
+mod boolish;
+mod bridge;
+mod buffering;
+mod chunks;
+mod defaults;
+mod encoder;
 mod errors;
+mod extract;
+mod fields;
+mod formats;
+mod frames;
+mod geojson;
+mod guard;
+mod handles;
+mod index;
+mod info;
+mod interpolate;
+mod jsonapi;
+mod jsonrpc;
+mod jwt;
+mod keys;
+mod leaks;
+mod lines;
+mod members;
+mod memory;
+mod missing;
+mod msgpack;
+mod ndjson;
+mod options;
+mod order;
 mod parser;
+mod patch;
+mod pool;
+mod precision;
+mod preview;
 mod query;
+mod quote;
+mod randomize;
+mod records;
+mod refs;
+mod request;
+mod rename;
+mod sampling;
+mod schema;
+mod session;
+mod shards;
+mod signed;
+mod snapshot;
+mod source;
+mod sniff;
+mod spill;
+mod strip;
+mod tokens;
+mod truncate;
+mod zip;
 
+use buffering::BufferLimit;
+use ext_php_rs::binary::Binary;
+use ext_php_rs::binary_slice::BinarySlice;
+use ext_php_rs::boxed::ZBox;
+use ext_php_rs::flags::IniEntryPermission;
 use ext_php_rs::prelude::*;
-use ext_php_rs::types::Zval;
+use ext_php_rs::types::{Iterable, ZendCallable, ZendHashTable, Zval};
+use ext_php_rs::zend::IniEntryDef;
+use extract::Extractor;
+use guard::guard;
+use jsonapi::JsonApi;
+use lines::Lines;
+use options::{DecodeOptions, PhpOptions};
+use pool::Pool;
 use query::Query;
+use sift_core::{arena, diagnostics, number, surrogates, utf8};
+use snapshot::Snapshot;
+use tokens::Tokens;
+
+/// Counts the extension's heap for `Sift::memoryUsage()` (see `memory.rs`).
+#[global_allocator]
+static ALLOCATOR: memory::CountingAllocator = memory::CountingAllocator;
 
 /// Sift class - main entry point for lazy JSON operations.
 /// Stays in Rust domain until explicit hydration.
@@ -18,6 +94,8 @@ pub struct Sift;
 #[php_impl]
 impl Sift {
     /// Create a lazy Query object for navigating JSON without creating PHP values.
+    /// Takes the same options as `decode()`; they apply when the Query (or
+    /// any Query derived from it) hydrates values.
     ///
     /// # Example
     /// ```php
@@ -27,27 +105,1106 @@ impl Sift {
     /// $email = $q->get("users")->index(5000)->get("email")->string();
     /// ```
     #[php_static]
-    pub fn query(json: String) -> Query {
-        Query::new(json)
+    pub fn query(
+        json: BinarySlice<u8>,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Query, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            let json = parser::input_text(&json, &options)?;
+            memory::reserve(json.len())?;
+            Ok(Query::new(json.into_owned(), options))
+        })
+    }
+
+    /// Create a Query from wherever the JSON is: JSON text, an
+    /// `SplFileInfo`, a stream resource or a PSR-7 style stream object
+    /// (read from its current position), or an already decoded array or
+    /// object, which is encoded first. A string is always JSON text; use
+    /// `fromFile()` for paths. Takes the same options as `query()`.
+    ///
+    /// # Example
+    /// ```php
+    /// $q = Sift::from(fopen('php://input', 'r'));
+    /// $q = Sift::from($request->getBody());
+    /// ```
+    #[php_static]
+    pub fn from(
+        source: &Zval,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Query, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            let bytes = source::read(source)?;
+            // Validate input size to prevent DoS
+            parser::check_input_size(&bytes)?;
+            let json = parser::input_text(&bytes, &options)?;
+            memory::reserve(json.len())?;
+            Ok(Query::new(json.into_owned(), options))
+        })
+    }
+
+    /// Create a Query from the local file at `path`, which must be
+    /// allowed by `open_basedir`. Takes the same options as `query()`.
+    ///
+    /// # Example
+    /// ```php
+    /// $config = Sift::fromFile('/etc/app/config.json');
+    /// ```
+    #[php_static]
+    pub fn from_file(
+        path: &str,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Query, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            let bytes = source::read_file(path)?;
+            // Validate input size to prevent DoS
+            parser::check_input_size(&bytes)?;
+            let json = parser::input_text(&bytes, &options)?;
+            memory::reserve(json.len())?;
+            Ok(Query::new(json.into_owned(), options))
+        })
+    }
+
+    /// Create a Query from the request body (`php://input`), inflating a
+    /// `Content-Encoding: gzip` body. Bodies over `max_bytes` (64 MB by
+    /// default), before or after inflating, throw. Takes the same options
+    /// as `query()`.
+    ///
+    /// # Example
+    /// ```php
+    /// $body = Sift::fromRequest(1 << 20);
+    /// $email = $body->pointer('/user/email')->string();
+    /// ```
+    #[php_static]
+    pub fn from_request(
+        max_bytes: Option<i64>,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Query, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            let body = request::read_body(max_bytes)?;
+            let json = parser::input_text(&body, &options)?;
+            memory::reserve(json.len())?;
+            Ok(Query::new(json.into_owned(), options))
+        })
+    }
+
+    /// Wrap a JSON:API compound document for resource, relationship and
+    /// included-resource lookups. Takes the same options as `query()`.
+    ///
+    /// # Example
+    /// ```php
+    /// $doc = Sift::jsonApi($json);
+    /// $author = $doc->related('author');
+    /// ```
+    #[php_static]
+    pub fn json_api(
+        json: BinarySlice<u8>,
+        options: Option<&ZendHashTable>,
+    ) -> Result<JsonApi, errors::SonicError> {
+        guard(|| Sift::query(json, options).map(JsonApi::new))
+    }
+
+    /// Decode the claims of a JWT into a lazy Query, e.g. to peek at `exp`
+    /// or `sub`. The signature is NOT verified - never trust the claims
+    /// without validating the token elsewhere. Takes the same options as
+    /// `query()`.
+    ///
+    /// # Example
+    /// ```php
+    /// if (Sift::jwtClaims($token)->get('exp')->int() < time()) {
+    ///     // Expired: reject before the full validation
+    /// }
+    /// ```
+    #[php_static]
+    pub fn jwt_claims(
+        token: &str,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Query, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            let payload = jwt::claims(token)?;
+            // Validate input size to prevent DoS
+            parser::check_input_size(&payload)?;
+            let json = parser::input_text(&payload, &options)?;
+            memory::reserve(json.len())?;
+            Ok(Query::new(json.into_owned(), options))
+        })
+    }
+
+    /// Parse JSON once into an immutable Rust-side tree.
+    /// Pointer reads convert only what they touch; `toArray()` converts everything.
+    ///
+    /// # Example
+    /// ```php
+    /// $snap = Sift::snapshot($json);
+    /// $name = $snap->get('/app/name');
+    /// ```
+    #[php_static]
+    pub fn snapshot(json: &str) -> Result<Snapshot, errors::SonicError> {
+        guard(|| Snapshot::parse(json))
+    }
+
+    /// An empty pool for many small documents sharing one buffer, read by
+    /// id and pointer. Takes the same options as `decode()`; they apply to
+    /// every document added and every value read.
+    ///
+    /// # Example
+    /// ```php
+    /// $pool = Sift::pool();
+    /// $id = $pool->addDocument($body);
+    /// $type = $pool->get($id, '/event/type');
+    /// ```
+    #[php_static]
+    pub fn pool(options: Option<&ZendHashTable>) -> Result<Pool, errors::SonicError> {
+        guard(|| Ok(Pool::new(DecodeOptions::from_array(options)?)))
     }
 
     /// Quick extraction by pointer - convenience method.
     /// For single extractions, this is simpler than creating a Query.
+    /// Takes the same options as `decode()`.
     #[php_static]
-    pub fn get(json: &str, pointer: &str) -> Result<Zval, errors::SonicError> {
-        parser::get_by_pointer(json, pointer)
+    pub fn get(
+        json: BinarySlice<u8>,
+        pointer: &str,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Zval, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            let json = parser::decode_text(&json, &options)?;
+            parser::get_by_pointer(&json, pointer, &options)
+        })
+    }
+
+    /// List the keys of the object at `pointer`, or the indices of the array
+    /// there, without converting any values.
+    ///
+    /// # Example
+    /// ```php
+    /// if (in_array('billing', Sift::keysAt($json, '/order'), true)) {
+    ///     // v2 payload
+    /// }
+    /// ```
+    #[php_static]
+    pub fn keys_at(json: &str, pointer: &str) -> Result<Zval, errors::SonicError> {
+        guard(|| parser::keys_at(json, pointer))
+    }
+
+    /// Build a structural index of `json`: the byte range of every value
+    /// and a sorted member table for every container, as a binary string
+    /// to cache next to the JSON for `getWithIndex()`.
+    ///
+    /// # Example
+    /// ```php
+    /// $index = $cache->get($key) ?? Sift::index($json);
+    /// ```
+    #[php_static]
+    pub fn index(json: &str) -> Result<Binary<u8>, errors::SonicError> {
+        guard(|| index::build(json).map(Binary::new))
+    }
+
+    /// `get()` through an index from `index()`: each pointer segment is an
+    /// array lookup or a binary search instead of a scan of the document.
+    /// The index must have been built from this same JSON.
+    ///
+    /// # Example
+    /// ```php
+    /// $email = Sift::getWithIndex($json, $index, '/users/1200/email');
+    /// ```
+    #[php_static]
+    pub fn get_with_index(
+        json: &str,
+        index: BinarySlice<u8>,
+        pointer: &str,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Zval, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            index::get(json, &index, pointer, &options)
+        })
+    }
+
+    /// Count the members of the object, or elements of the array, at
+    /// `pointer` without decoding them.
+    ///
+    /// # Example
+    /// ```php
+    /// $pages = intdiv(Sift::countAt($json, '/items') + 49, 50);
+    /// ```
+    #[php_static]
+    pub fn count_at(json: &str, pointer: &str) -> Result<i64, errors::SonicError> {
+        guard(|| parser::count_at(json, pointer).map(|count| count as i64))
+    }
+
+    /// Compute the bounding box `[minX, minY, maxX, maxY]` of a GeoJSON
+    /// Feature, FeatureCollection or geometry, reading coordinates without
+    /// hydrating them. Returns null if it has no positions.
+    ///
+    /// # Example
+    /// ```php
+    /// [$minX, $minY, $maxX, $maxY] = Sift::geoBounds($tile);
+    /// ```
+    #[php_static]
+    pub fn geo_bounds(json: &str) -> Result<Option<Vec<f64>>, errors::SonicError> {
+        guard(|| geojson::bounds(json).map(|bounds| bounds.map(Vec::from)))
+    }
+
+    /// Extract the value of the first pointer in `pointers` that exists.
+    /// All candidates are resolved in a single pass over the document,
+    /// instead of one scan per miss.
+    ///
+    /// # Example
+    /// ```php
+    /// $items = Sift::firstOf($json, ['/data/items', '/items']);
+    /// ```
+    #[php_static]
+    pub fn first_of(json: &str, pointers: Vec<String>) -> Result<Zval, errors::SonicError> {
+        guard(|| match parser::first_of(json, &pointers)? {
+            Some(value) => parser::lazyvalue_to_zval(value, &DecodeOptions::configured()),
+            None => Err(errors::SonicError::KeyNotFound(
+                "None of the pointers were found".to_string(),
+            )),
+        })
     }
 
     /// Full JSON decode.
+    ///
+    /// # Options
+    /// * `strict` - match `json_decode($json, true)` exactly, throwing
+    ///   `JsonException` with json_decode's error codes (see docs/COMPATIBILITY.md)
+    /// * `warnings` - record lossy conversions for `Sift::lastWarnings()`
+    /// * `presize` - count container elements before converting them so each
+    ///   array is allocated once (lazy conversion only)
+    /// * `strategy` - `'auto'` (default) picks DOM or lazy conversion from the
+    ///   document's size (lazy from 16 MB); `'dom'` or `'lazy'` forces one
+    /// * `big_numbers` - `'bcmath'` or `'gmp'` decodes numbers a float can't hold
+    ///   exactly into `BCMath\Number` / GMP objects instead (default `'float'`)
+    /// * `assoc` - `false` decodes objects as `stdClass` (default `true`)
+    /// * `bigint` - `'string'` decodes integers outside PHP's int range as
+    ///   numeric strings, like `JSON_BIGINT_AS_STRING` (default `'float'`)
+    /// * `depth` - maximum nesting depth, 1 to 2048 (default 512)
+    /// * `utf8` - `'ignore'` drops malformed UTF-8, `'substitute'` replaces it
+    ///   with U+FFFD (default `'error'`)
+    /// * `duplicate_keys` - `'first'` keeps the first of repeated object keys,
+    ///   `'error'` rejects them (default `'last'`, like json_decode)
+    /// * `keys` - `'camel'` or `'snake'` renames object keys while they are
+    ///   inserted, or a callable `fn (string $key): string` does (default `'keep'`)
+    /// * `strip_prefix` - removes this prefix from object keys, before `keys`
+    /// * `nest_keys` - splits object keys on this separator into nested arrays,
+    ///   `"user.name"` becoming `['user' => ['name' => ...]]`
+    /// * `control_chars` - `'strip'` removes control characters (other than
+    ///   tab, LF and CR) from strings and keys, `'error'` rejects them with
+    ///   the string's pointer (default `'keep'`, like json_decode)
+    /// * `surrogates` - `'replace'` turns lone UTF-16 surrogates and malformed
+    ///   `\u` escapes into U+FFFD, `'wtf8'` keeps lone surrogates in string
+    ///   values as WTF-8 bytes (default `'error'`, like json_decode)
+    ///
+    /// # Example
+    /// ```php
+    /// $data = Sift::decode($json, ['strict' => true]);
+    /// ```
     #[php_static]
-    pub fn decode(json: &str) -> Result<Zval, errors::SonicError> {
-        parser::decode(json)
+    pub fn decode(
+        json: BinarySlice<u8>,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Zval, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            let json = parser::decode_text(&json, &options)?;
+            parser::decode(&json, &options)
+        })
+    }
+
+    /// Decode JSON from any source `from()` accepts, such as a PSR-7
+    /// request body, without the caller reading it into a string first.
+    /// Takes the same options as `decode()`.
+    ///
+    /// # Example
+    /// ```php
+    /// $data = Sift::decodeFrom($request->getBody());
+    /// ```
+    #[php_static]
+    pub fn decode_from(
+        source: &Zval,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Zval, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            let bytes = source::read(source)?;
+            // Validate input size to prevent DoS
+            parser::check_input_size(&bytes)?;
+            let json = parser::decode_text(&bytes, &options)?;
+            parser::decode(&json, &options)
+        })
+    }
+
+    /// Decode a payload whatever its format: JSON, newline-delimited JSON,
+    /// either of those gzip-compressed, or a MessagePack map or array.
+    /// The format is sniffed from the bytes, not declared. Returns
+    /// `['format' => 'json'|'ndjson'|'msgpack', 'gzip' => bool, 'data' => mixed]`;
+    /// NDJSON data is the list of decoded lines. Options apply to JSON
+    /// text as for `decode()`.
+    ///
+    /// # Example
+    /// ```php
+    /// $result = Sift::decodeAuto(file_get_contents('php://input'));
+    /// $rows = $result['format'] === 'ndjson' ? $result['data'] : [$result['data']];
+    /// ```
+    #[php_static]
+    pub fn decode_auto(
+        bytes: BinarySlice<u8>,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Zval, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            sniff::decode_auto(&bytes, &options)
+        })
+    }
+
+    /// Decode a top-level array, or NDJSON, one element at a time so a
+    /// malformed element fails alone: it is left out (`on_error` `'skip'`,
+    /// the default) or replaced with null (`'null'`), and reported in
+    /// `errors` as `['index' => ..., 'message' => ..., 'offset' => ...]`.
+    /// Returns `['data' => list, 'errors' => list]`.
+    ///
+    /// # Example
+    /// ```php
+    /// $result = Sift::decodeRecords($export, onError: 'null');
+    /// foreach ($result['errors'] as $e) { /* log $e['index'], $e['message'] */ }
+    /// ```
+    #[php_static]
+    pub fn decode_records(
+        bytes: BinarySlice<u8>,
+        options: Option<&ZendHashTable>,
+        on_error: Option<String>,
+    ) -> Result<Zval, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            let on_error = match on_error {
+                Some(name) => records::OnError::from_name(&name)?,
+                None => records::OnError::Skip,
+            };
+            records::decode_records(&bytes, &options, on_error)
+        })
+    }
+
+    /// Verify an HMAC-SHA256 signature before decoding: over the whole
+    /// document when `signature` (hex) is given, otherwise over the
+    /// `payload` of an envelope `{"payload": ..., "signature": "<hex>"}`,
+    /// which is what's returned. The MAC covers `canonicalize()`'s form, so
+    /// formatting and key order don't matter. Throws on a mismatch.
+    ///
+    /// # Example
+    /// ```php
+    /// $data = Sift::decodeSigned($cached, $_ENV['CACHE_KEY']);
+    /// ```
+    #[php_static]
+    pub fn decode_signed(
+        json: BinarySlice<u8>,
+        key: BinarySlice<u8>,
+        signature: Option<String>,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Zval, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            let json = parser::decode_text(&json, &options)?;
+            signed::decode_signed(&json, &key, signature.as_deref(), &options)
+        })
+    }
+
+    /// The canonical form `decodeSigned()` signs: no whitespace, object
+    /// members sorted by key, strings re-escaped, numbers as written.
+    ///
+    /// # Example
+    /// ```php
+    /// $signature = hash_hmac('sha256', Sift::canonicalize($json), $key);
+    /// ```
+    #[php_static]
+    pub fn canonicalize(json: &str) -> Result<String, errors::SonicError> {
+        guard(|| {
+            parser::check_input_size(json)?;
+            signed::canonicalize(json)
+        })
+    }
+
+    /// Whether two JSON arrays hold the same elements in any order, or
+    /// with `key`, elements with the same values of that member. Elements
+    /// compare by canonical form, so object key order doesn't matter.
+    ///
+    /// # Example
+    /// ```php
+    /// assert(Sift::sameMembers($expected, $actual, 'id'));
+    /// ```
+    #[php_static]
+    pub fn same_members(
+        left: &str,
+        right: &str,
+        key: Option<String>,
+    ) -> Result<bool, errors::SonicError> {
+        guard(|| members::same_members(left, right, key.as_deref()))
+    }
+
+    /// Combine two JSON arrays element by element into a JSON array of
+    /// `[a, b]` pairs, or with `strategy` "merge", of objects holding the
+    /// members of both. The shorter array is padded with nulls. Elements
+    /// are copied as text, never hydrated.
+    ///
+    /// # Example
+    /// ```php
+    /// $rows = Sift::zip($orders, $shipments, 'merge');
+    /// ```
+    #[php_static]
+    pub fn zip(
+        left: &str,
+        right: &str,
+        strategy: Option<String>,
+    ) -> Result<String, errors::SonicError> {
+        guard(|| {
+            let strategy = match strategy.as_deref() {
+                Some(name) => zip::Strategy::from_name(name)?,
+                None => zip::Strategy::Pairs,
+            };
+            zip::zip(left, right, strategy)
+        })
+    }
+
+    /// Split a top-level JSON array into JSON arrays of at most
+    /// `max_bytes` bytes each, keeping element order. Elements are copied
+    /// as text, never hydrated; one too large for a shard on its own
+    /// throws.
+    ///
+    /// # Example
+    /// ```php
+    /// foreach (Sift::splitTopLevel($events, 1 << 20) as $batch) {
+    ///     $http->post('/ingest', ['body' => $batch]);
+    /// }
+    /// ```
+    #[php_static]
+    pub fn split_top_level(json: &str, max_bytes: i64) -> Result<Vec<String>, errors::SonicError> {
+        guard(|| shards::split_top_level(json, max_bytes))
+    }
+
+    /// Join JSON arrays into one array holding all their elements, in
+    /// order, by splicing their text; the arrays are validated but never
+    /// hydrated.
+    ///
+    /// # Example
+    /// ```php
+    /// $all = Sift::concatArrays([$page1, $page2, $page3]);
+    /// ```
+    #[php_static]
+    pub fn concat_arrays(docs: Vec<String>) -> Result<String, errors::SonicError> {
+        guard(|| shards::concat_arrays(&docs))
+    }
+
+    /// A document with the same structure as `json` and randomized
+    /// scalars: strings keep their length and character classes, numbers
+    /// their digit counts, keys are unchanged. Reproducible with `seed`.
+    ///
+    /// # Example
+    /// ```php
+    /// file_put_contents('fixture.json', Sift::randomize($payload, 42));
+    /// ```
+    #[php_static]
+    pub fn randomize(json: &str, seed: Option<i64>) -> Result<String, errors::SonicError> {
+        guard(|| {
+            let mut rng = seed.map_or_else(sampling::Rng::from_entropy, |seed| {
+                sampling::Rng::new(seed as u64)
+            });
+            randomize::randomize(json, &mut rng)
+        })
+    }
+
+    /// Set default decode options for the rest of the request.
+    ///
+    /// Takes the same keys as `decode()`. The defaults apply to
+    /// `decode()`, `decodeLazy()`, `get()`, `firstOf()`, Query hydration
+    /// and the `Sonic` equivalents; options passed to a call still override
+    /// them. Each call replaces the previous configuration, and it is reset
+    /// when the request ends.
+    ///
+    /// # Example
+    /// ```php
+    /// Sift::configure(['big_numbers' => 'bcmath', 'warnings' => true]);
+    /// ```
+    #[php_static]
+    pub fn configure(options: &ZendHashTable) -> Result<(), errors::SonicError> {
+        guard(|| DecodeOptions::configure(options))
+    }
+
+    /// Full JSON decode via lazy iteration.
+    ///
+    /// Same result as `decode()`, but always converts straight from the
+    /// input instead of building an intermediate DOM first, like
+    /// `decode($json, ['strategy' => 'lazy'])`. See docs/BENCHMARKS.md.
+    /// Accepts the same options as `decode()`; `strategy` is ignored.
+    ///
+    /// # Example
+    /// ```php
+    /// $data = Sift::decodeLazy($json);
+    /// ```
+    #[php_static]
+    pub fn decode_lazy(
+        json: BinarySlice<u8>,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Zval, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            let json = parser::decode_text(&json, &options)?;
+            parser::decode_lazy(&json, &options)
+        })
+    }
+
+    /// Replace values by JSON pointer while copying every other byte of
+    /// `json` verbatim, so untouched regions stay byte-identical (e.g. for
+    /// signed documents). New values are encoded like `encodeLines()` rows.
+    ///
+    /// # Example
+    /// ```php
+    /// $patched = Sift::patch($json, ['/status' => 'paid', '/items/0/qty' => 3]);
+    /// ```
+    #[php_static]
+    pub fn patch(json: &str, changes: &ZendHashTable) -> Result<String, errors::SonicError> {
+        guard(|| patch::patch(json, changes))
+    }
+
+    /// A copy of `json` with the values matched by `rules` hashed, faked,
+    /// zeroed or nulled, in one pass over the text. Rule keys are JSON
+    /// pointers (`*` matches any segment) or member names matched at any
+    /// depth; everything no rule touches is copied verbatim.
+    ///
+    /// # Example
+    /// ```php
+    /// $safe = Sift::anonymize($json, ['email' => 'hash', '/users/*/age' => 'zero']);
+    /// ```
+    #[php_static]
+    pub fn anonymize(json: &str, rules: &ZendHashTable) -> Result<String, errors::SonicError> {
+        guard(|| anonymize::anonymize(json, rules))
+    }
+
+    /// Encrypt the values at `pointers` with `key` (libsodium secretbox),
+    /// replacing each with a base64 string of nonce and box. Other bytes
+    /// are copied verbatim; `decryptFields()` restores the original text.
+    ///
+    /// # Example
+    /// ```php
+    /// $stored = Sift::encryptFields($json, ['/user/ssn'], $key);
+    /// ```
+    #[php_static]
+    pub fn encrypt_fields(
+        json: &str,
+        pointers: Vec<String>,
+        key: BinarySlice<u8>,
+    ) -> Result<String, errors::SonicError> {
+        guard(|| {
+            let pointers: Vec<&str> = pointers.iter().map(String::as_str).collect();
+            fields::encrypt_fields(json, &pointers, &key)
+        })
+    }
+
+    /// Decrypt the fields `encryptFields()` sealed at `pointers`, splicing
+    /// the original JSON back in. Throws if a field was tampered with or
+    /// `key` is wrong.
+    ///
+    /// # Example
+    /// ```php
+    /// $json = Sift::decryptFields($stored, ['/user/ssn'], $key);
+    /// ```
+    #[php_static]
+    pub fn decrypt_fields(
+        json: &str,
+        pointers: Vec<String>,
+        key: BinarySlice<u8>,
+    ) -> Result<String, errors::SonicError> {
+        guard(|| {
+            let pointers: Vec<&str> = pointers.iter().map(String::as_str).collect();
+            fields::decrypt_fields(json, &pointers, &key)
+        })
+    }
+
+    /// A copy of `json` with numbers turned into strings of their literal
+    /// text, so JavaScript clients can parse it without losing digits:
+    /// every number under `pointers`, or those in the whole document whose
+    /// magnitude exceeds `threshold` (default 2^53 - 1). Nothing else is
+    /// decoded or changed.
+    ///
+    /// # Example
+    /// ```php
+    /// $safe = Sift::convertNumbersToStrings($json, ['/ids']);
+    /// ```
+    #[php_static]
+    pub fn convert_numbers_to_strings(
+        json: &str,
+        pointers: Option<Vec<String>>,
+        threshold: Option<f64>,
+    ) -> Result<String, errors::SonicError> {
+        guard(|| quote::convert_numbers_to_strings(json, pointers.as_deref(), threshold))
+    }
+
+    /// The largest valid JSON summary of `json` that fits in `max_bytes`,
+    /// for log lines and error reports with size limits. Long strings are
+    /// cut short with "…" and arrays/objects that don't fit end with a
+    /// `"… N more"` marker; a document that fits is returned unchanged.
+    ///
+    /// # Example
+    /// ```php
+    /// $logger->error('Upstream rejected request', ['body' => Sift::truncate($body, 2048)]);
+    /// ```
+    #[php_static]
+    pub fn truncate(json: &str, max_bytes: i64) -> Result<String, errors::SonicError> {
+        guard(|| truncate::truncate(json, max_bytes))
+    }
+
+    /// A compact one-line preview of `json` for debug output and log
+    /// lines: keys, scalars, strings cut to `string` characters, at most
+    /// `items` entries per array/object with a count of the rest, and
+    /// only the length of containers deeper than `depth`. Built in one
+    /// pass over the document without hydrating it.
+    ///
+    /// # Example
+    /// ```php
+    /// $logger->debug('Webhook received: ' . Sift::preview($body, ['depth' => 3]));
+    /// ```
+    #[php_static]
+    pub fn preview(
+        json: &str,
+        options: Option<&ZendHashTable>,
+    ) -> Result<String, errors::SonicError> {
+        guard(|| preview::preview(json, options))
+    }
+
+    /// Remove object members whose value is null, in nested objects too
+    /// unless `recursive` is false, and with `empties` also those holding
+    /// `{}` or `[]` (including ones emptied by the removal). Array elements
+    /// are kept. Nothing is hydrated.
+    ///
+    /// # Example
+    /// ```php
+    /// $http->post($url, ['body' => Sift::stripNulls($payload)]);
+    /// ```
+    #[php_static]
+    pub fn strip_nulls(
+        json: &str,
+        recursive: Option<bool>,
+        empties: Option<bool>,
+    ) -> Result<String, errors::SonicError> {
+        guard(|| strip::strip_nulls(json, recursive.unwrap_or(true), empties.unwrap_or(false)))
+    }
+
+    /// Fill in the object members `json` lacks from `defaults`, merging
+    /// nested objects the same way. Values `json` has always win, null
+    /// included; added members follow its own, in the defaults' order.
+    /// Values are copied as text, never hydrated.
+    ///
+    /// # Example
+    /// ```php
+    /// $settings = Sift::defaultsDeep($userSettings, $canonicalSettings);
+    /// ```
+    #[php_static]
+    pub fn defaults_deep(json: &str, defaults: &str) -> Result<String, errors::SonicError> {
+        guard(|| defaults::defaults_deep(json, defaults))
+    }
+
+    /// Rename object keys per `map` (old name => new name), at any depth
+    /// under `pointer` or in the whole document. Only the key tokens are
+    /// rewritten: order, values and whitespace keep their bytes.
+    ///
+    /// # Example
+    /// ```php
+    /// $v2 = Sift::renameKeys($v1, ['userName' => 'username'], '/data');
+    /// ```
+    #[php_static]
+    pub fn rename_keys(
+        json: &str,
+        map: &ZendHashTable,
+        pointer: Option<&str>,
+    ) -> Result<String, errors::SonicError> {
+        guard(|| rename::rename_keys(json, map, pointer))
+    }
+
+    /// Encode `value` as JSON with each object's keys in the order of the
+    /// same object in `original`, the document it was decoded from; keys
+    /// `original` doesn't have follow in PHP order. Keeps the key order of
+    /// files under review after PHP code sorted or rebuilt arrays.
+    ///
+    /// # Example
+    /// ```php
+    /// $config = Sift::decode($original);
+    /// $config['services']['cache']['ttl'] = 60;
+    /// file_put_contents('config.json', Sift::encodeOrdered($config, $original));
+    /// ```
+    #[php_static]
+    pub fn encode_ordered(value: &Zval, original: &str) -> Result<String, errors::SonicError> {
+        guard(|| order::encode_ordered(value, original))
+    }
+
+    /// Encode each element of an array or Traversable as one line of
+    /// newline-delimited JSON. A row that can't be encoded (malformed
+    /// UTF-8, INF/NAN, resources, excessive nesting) is left out and its
+    /// position reported in `errors`, rather than failing the export.
+    /// Returns `['lines' => string, 'count' => int, 'errors' => [position => message]]`.
+    /// With `write`, the output is passed to it in chunks of about 64 KiB
+    /// as it is produced and `lines` is empty.
+    ///
+    /// # Example
+    /// ```php
+    /// $fp = fopen('export.ndjson', 'w');
+    /// $result = Sift::encodeLines($repository->cursor(), fn (string $chunk) => fwrite($fp, $chunk));
+    /// foreach ($result['errors'] as $position => $message) { /* log */ }
+    /// ```
+    #[php_static]
+    pub fn encode_lines(
+        mut rows: Iterable,
+        write: Option<ZendCallable>,
+    ) -> Result<Zval, errors::SonicError> {
+        guard(|| encoder::encode_lines(&mut rows, write.as_ref()))
+    }
+
+    /// The non-blank lines of an NDJSON document as one JSON array. Each
+    /// line is validated and copied as it is, without being decoded.
+    ///
+    /// # Example
+    /// ```php
+    /// $batch = Sift::linesToArray(file_get_contents('events.ndjson'));
+    /// ```
+    #[php_static]
+    pub fn lines_to_array(ndjson: &str) -> Result<String, errors::SonicError> {
+        guard(|| ndjson::lines_to_array(ndjson))
+    }
+
+    /// The elements of a top-level JSON array as NDJSON, one per line.
+    /// Elements are copied as they are; only line breaks between their
+    /// tokens are removed.
+    ///
+    /// # Example
+    /// ```php
+    /// file_put_contents('events.ndjson', Sift::arrayToLines($body));
+    /// ```
+    #[php_static]
+    pub fn array_to_lines(json: &str) -> Result<String, errors::SonicError> {
+        guard(|| ndjson::array_to_lines(json))
+    }
+
+    /// Iterate over the records of an NDJSON file, keyed by the byte
+    /// offset of their line. Reading and parsing run on background
+    /// threads (`workers` of them parse, one per CPU up to 4 by default)
+    /// that stay a few batches of lines ahead of the loop, so PHP only
+    /// converts parsed records while the rest of the file is processed.
+    /// Records come in file order; blank lines are skipped. Takes the
+    /// same options as `decode()`; `offset` resumes from a `tell()`.
+    /// `buffer` limits how far the threads read ahead:
+    /// `['max_bytes' => int, 'when_full' => 'block'|'error']`, 16 MiB
+    /// and blocking by default.
+    ///
+    /// # Example
+    /// ```php
+    /// foreach (Sift::lines('/data/events.ndjson') as $offset => $event) {
+    ///     $importer->add($event);
+    /// }
+    /// ```
+    #[php_static]
+    pub fn lines(
+        path: &str,
+        options: Option<&ZendHashTable>,
+        workers: Option<i64>,
+        offset: Option<i64>,
+        buffer: Option<&ZendHashTable>,
+    ) -> Result<Lines, errors::SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            let buffer = BufferLimit::from_array(buffer, lines::DEFAULT_BUFFER)?;
+            Lines::new(path.to_string(), options, workers, offset, buffer)
+        })
+    }
+
+    /// The extension version, JSON engine, SIMD instruction set in use
+    /// and supported by this CPU, detected CPU features, limits and build
+    /// flags, to tell a slow build from a slow workload.
+    ///
+    /// # Example
+    /// ```php
+    /// $info = Sift::info();
+    /// if ($info['simd'] === 'fallback') {
+    ///     error_log("sift built without SIMD; CPU supports {$info['cpu_simd']}");
+    /// }
+    /// ```
+    #[php_static]
+    pub fn info() -> Result<Zval, errors::SonicError> {
+        guard(|| info::info())
+    }
+
+    /// Memory held by the extension outside the Zend memory manager, which
+    /// `memory_get_usage()` doesn't see: `['current' => int, 'peak' =>
+    /// int, 'arena' => int]` in bytes. The peak is reset at the end of each
+    /// request. Queries, Pools and Snapshots created from documents are
+    /// also checked against what `memory_limit` leaves after PHP's usage.
+    ///
+    /// # Example
+    /// ```php
+    /// $metrics->gauge('memory', memory_get_usage() + Sift::memoryUsage()['current']);
+    /// ```
+    #[php_static]
+    pub fn memory_usage() -> Result<Zval, errors::SonicError> {
+        guard(memory::report)
+    }
+
+    /// Free the conversion arena kept between calls and return unused
+    /// heap to the OS (with glibc), for long-running workers that once
+    /// handled a large document. Returns the arena bytes freed. Memory of
+    /// live Queries is untouched; `Query::release()` them first.
+    ///
+    /// # Example
+    /// ```php
+    /// $server->on('workerIdle', fn() => Sift::trimMemory());
+    /// ```
+    #[php_static]
+    pub fn trim_memory() -> Result<i64, errors::SonicError> {
+        guard(|| Ok(memory::trim() as i64))
+    }
+
+    /// Queries, Pools, Snapshots and iterators created since the last
+    /// checkpoint (the start of the request, or `checkLeaks()`) that are
+    /// still alive, as `[class => count]`. Counted only with the
+    /// `sift.leak_check` INI flag on; empty otherwise.
+    ///
+    /// # Example
+    /// ```php
+    /// $live = Sift::liveObjects();   // ['Sift\Query' => 3]
+    /// ```
+    #[php_static]
+    pub fn live_objects() -> Result<Zval, errors::SonicError> {
+        guard(leaks::live_objects)
+    }
+
+    /// Start a new leak-check checkpoint, throwing if objects created
+    /// since the previous one are still alive (code `LEAKED_OBJECTS`).
+    /// With `$throw` false their count is returned instead, e.g. to
+    /// accept what a worker keeps from booting. A no-op returning 0
+    /// unless the `sift.leak_check` INI flag is on.
+    ///
+    /// # Example
+    /// ```php
+    /// Sift::checkLeaks(false);
+    /// foreach ($jobs as $job) {
+    ///     handle($job);
+    ///     Sift::checkLeaks();
+    /// }
+    /// ```
+    #[php_static]
+    pub fn check_leaks(throw: Option<bool>) -> Result<i64, errors::SonicError> {
+        guard(|| leaks::check(throw.unwrap_or(true)))
+    }
+
+    /// Extract a shaped result from `json` in one pass: every string in
+    /// `template` is a JSON pointer, replaced by the value there (null when
+    /// missing). A `*` segment maps the rest of a pointer over an array or
+    /// object, and `[pattern, template]` applies a template to each value a
+    /// wildcard pattern selects. The template is validated before the
+    /// document is parsed.
+    ///
+    /// # Example
+    /// ```php
+    /// $view = Sift::extract($json, [
+    ///     'user'  => '/data/user/name',
+    ///     'items' => ['/data/items/*', ['id' => '/id', 'qty' => '/qty']],
+    /// ]);
+    /// ```
+    #[php_static]
+    pub fn extract(json: &str, template: &ZendHashTable) -> Result<Zval, errors::SonicError> {
+        guard(|| extract::extract(json, template))
+    }
+
+    /// Compile an extraction template once for reuse: the returned
+    /// `Extractor` applies it to any number of documents with the same
+    /// results as `extract()`, without re-parsing the template each time.
+    ///
+    /// # Example
+    /// ```php
+    /// $extractor = Sift::compileExtractor(['id' => '/id', 'user' => '/user/name']);
+    /// foreach ($messages as $message) {
+    ///     $rows[] = $extractor->extract($message);
+    /// }
+    /// ```
+    #[php_static]
+    pub fn compile_extractor(template: &ZendHashTable) -> Result<Extractor, errors::SonicError> {
+        guard(|| Extractor::compile(template))
+    }
+
+    /// Keep compiled schemas and extraction templates in APCu, keyed by a
+    /// hash of their source, so FPM workers compile each once per deploy
+    /// instead of once per request. Off by default; the setting lasts for
+    /// the worker's lifetime. Returns whether the cache is in use, which
+    /// needs APCu loaded and enabled (`apc.enable_cli` on the CLI).
+    ///
+    /// # Example
+    /// ```php
+    /// Sift::cacheCompiled();
+    /// $extractor = Sift::compileExtractor($template);
+    /// ```
+    #[php_static]
+    pub fn cache_compiled(enabled: Option<bool>) -> bool {
+        artifacts::set_enabled(enabled.unwrap_or(true))
+    }
+
+    /// Iterate over the tokens of `json` as `[type, start, end]`: the
+    /// token type and its byte range, end exclusive. Types are
+    /// `begin_object`, `end_object`, `begin_array`, `end_array`, `colon`,
+    /// `comma`, `key`, `string`, `number`, `true`, `false` and `null`.
+    /// The whole document is validated first.
+    ///
+    /// # Example
+    /// ```php
+    /// foreach (Sift::tokenize($json) as [$type, $start, $end]) {
+    ///     $html .= highlight($type, substr($json, $start, $end - $start));
+    /// }
+    /// ```
+    #[php_static]
+    pub fn tokenize(json: &str) -> Result<Tokens, errors::SonicError> {
+        guard(|| Tokens::new(json))
+    }
+
+    /// Time `decode()`, `get()` and a Query lookup on `json` (a bundled
+    /// fixture if null) next to `json_decode()`, and report the SIMD
+    /// instruction set in use, to check the fast paths on this machine.
+    ///
+    /// # Example
+    /// ```php
+    /// $report = Sift::bench($payload, ['iterations' => 500]);
+    /// echo $report['simd'], ' ', $report['speedup'], "x\n";
+    /// ```
+    #[php_static]
+    pub fn bench(
+        json: Option<&str>,
+        options: Option<&ZendHashTable>,
+    ) -> Result<Zval, errors::SonicError> {
+        guard(|| bench::bench(json, options))
+    }
+
+    /// SIMD-accelerated UTF-8 validation of any string, JSON or not.
+    ///
+    /// # Example
+    /// ```php
+    /// if (!Sift::validateUtf8($name)) {
+    ///     throw new InvalidArgumentException('name must be UTF-8');
+    /// }
+    /// ```
+    #[php_static]
+    pub fn validate_utf8(string: BinarySlice<u8>) -> Result<bool, errors::SonicError> {
+        guard(|| Ok(utf8::is_valid(&string)))
+    }
+
+    /// Offset of the first byte of `string` that isn't part of a valid
+    /// UTF-8 sequence, or null if it is all valid UTF-8.
+    #[php_static]
+    pub fn invalid_utf8_offset(string: BinarySlice<u8>) -> Result<Option<i64>, errors::SonicError> {
+        guard(|| Ok(utf8::validate(&string).err().map(|offset| offset as i64)))
+    }
+
+    /// Escape `string` as a JSON string literal (quotes included unless
+    /// `quote` is false) with the SIMD escaper, for JSON built by
+    /// concatenation. Slashes and non-ASCII characters are kept as they
+    /// are, like `JSON_UNESCAPED_SLASHES | JSON_UNESCAPED_UNICODE`.
+    ///
+    /// # Example
+    /// ```php
+    /// $json = '{"name":' . Sift::escapeString($name) . '}';
+    /// ```
+    #[php_static]
+    pub fn escape_string(
+        string: BinarySlice<u8>,
+        quote: Option<bool>,
+    ) -> Result<String, errors::SonicError> {
+        guard(|| encoder::escape_string(&string, quote.unwrap_or(true)))
+    }
+
+    /// Decode a JSON string literal (quotes included unless `quoted` is
+    /// false) into the string it stands for.
+    ///
+    /// # Example
+    /// ```php
+    /// Sift::unescapeString('"caf\u00e9\n"'); // "café\n"
+    /// ```
+    #[php_static]
+    pub fn unescape_string(
+        literal: &str,
+        quoted: Option<bool>,
+    ) -> Result<String, errors::SonicError> {
+        guard(|| parser::unescape_string(literal, quoted.unwrap_or(true)))
     }
 
     /// SIMD-accelerated JSON validation.
     #[php_static]
-    pub fn is_valid(json: &str) -> bool {
-        parser::is_valid(json)
+    pub fn is_valid(json: &str) -> Result<bool, errors::SonicError> {
+        guard(|| Ok(parser::is_valid(json)))
+    }
+
+    /// JSON validation that throws on limit violations instead of
+    /// returning false, so false only ever means a syntax error.
+    ///
+    /// # Example
+    /// ```php
+    /// try {
+    ///     $ok = Sift::isValidStrict($payload);
+    /// } catch (Sift\InputTooLargeException $e) {
+    ///     // configuration problem, not bad JSON
+    /// }
+    /// ```
+    #[php_static]
+    pub fn is_valid_strict(json: &str) -> Result<bool, errors::SonicError> {
+        guard(|| parser::is_valid_strict(json))
+    }
+
+    /// Format a float as JSON number text, independent of the C locale.
+    ///
+    /// # Arguments
+    /// * `notation` - `'auto'` (default), `'decimal'` or `'exponent'`
+    ///
+    /// # Example
+    /// ```php
+    /// setlocale(LC_ALL, 'de_DE.UTF-8');
+    /// Sift::formatFloat(1.5);               // "1.5", never "1,5"
+    /// Sift::formatFloat(1e25);              // "1.0e+25"
+    /// Sift::formatFloat(1e25, 'decimal');   // "10000000000000000000000000.0"
+    /// ```
+    #[php_static]
+    pub fn format_float(value: f64, notation: Option<&str>) -> Result<String, errors::SonicError> {
+        guard(|| {
+            let notation = match notation {
+                Some(name) => number::FloatNotation::from_name(name)?,
+                None => number::FloatNotation::default(),
+            };
+            Ok(number::format_float(value, notation)?)
+        })
+    }
+
+    /// Lossy conversions recorded by the most recent decode, when it was
+    /// called with `['warnings' => true]`. Each entry has a `pointer` and a
+    /// `kind`, and an `offset`: repairs to the input text under the `utf8`
+    /// and `surrogates` options have a byte offset instead of a pointer.
+    ///
+    /// # Example
+    /// ```php
+    /// $data = Sift::decode('{"id": 18446744073709551615}', ['warnings' => true]);
+    /// Sift::lastWarnings(); // [['pointer' => '/id', 'kind' => 'bigint_to_float', 'offset' => null]]
+    /// ```
+    #[php_static]
+    pub fn last_warnings() -> Result<Vec<ZBox<ZendHashTable>>, errors::SonicError> {
+        guard(|| {
+            diagnostics::last_warnings()
+                .into_iter()
+                .map(|warning| {
+                    let mut entry = ZendHashTable::new();
+                    entry
+                        .insert("pointer", warning.pointer)
+                        .and_then(|_| entry.insert("kind", warning.kind.as_str()))
+                        .and_then(|_| entry.insert("offset", warning.offset.map(|at| at as i64)))
+                        .map_err(|e| errors::SonicError::TypeError(e.to_string()))?;
+                    Ok(entry)
+                })
+                .collect()
+        })
     }
 }
 
@@ -72,9 +1229,11 @@ impl Sonic {
     /// ```
     #[php_static]
     pub fn get(json: &str, pointer: &str) -> Result<Zval, errors::SonicError> {
-        // Note: Don't log pointer - it may contain sensitive field names
-        log::debug!("Sonic::get called");
-        parser::get_by_pointer(json, pointer)
+        guard(|| {
+            // Note: Don't log pointer - it may contain sensitive field names
+            log::debug!("Sonic::get called");
+            parser::get_by_pointer(json, pointer, &DecodeOptions::configured())
+        })
     }
 
     /// Full JSON decode - high-speed replacement for json_decode.
@@ -91,8 +1250,10 @@ impl Sonic {
     /// ```
     #[php_static]
     pub fn decode(json: &str) -> Result<Zval, errors::SonicError> {
-        log::debug!("Sonic::decode called");
-        parser::decode(json)
+        guard(|| {
+            log::debug!("Sonic::decode called");
+            parser::decode(json, &DecodeOptions::configured())
+        })
     }
 
     /// SIMD-accelerated JSON validation.
@@ -110,9 +1271,9 @@ impl Sonic {
     /// }
     /// ```
     #[php_static]
-    pub fn is_valid(json: &str) -> bool {
+    pub fn is_valid(json: &str) -> Result<bool, errors::SonicError> {
         log::debug!("Sonic::isValid called");
-        parser::is_valid(json)
+        guard(|| Ok(parser::is_valid(json)))
     }
 }
 
@@ -126,15 +1287,47 @@ fn init_logger() {
         .try_init();
 }
 
+/// Registers what the class macros can't express: the exception
+/// hierarchy (see `errors::register_exceptions`), the `sift_json`
+/// session serializer (see `session::register_handler`) and the INI
+/// entries.
+#[php_startup]
+pub fn startup(_ty: i32, module_number: i32) {
+    guard::install_hook();
+    errors::register_exceptions().expect("Failed to register Sift exception classes");
+    session::register_handler();
+    // php.ini only: the flag is read once per request (see `leaks.rs`)
+    IniEntryDef::register(
+        vec![IniEntryDef::new(
+            leaks::INI_NAME.to_string(),
+            "0".to_string(),
+            IniEntryPermission::System,
+        )],
+        module_number,
+    );
+}
+
+/// Clears request-scoped state: the `Sift::configure()` defaults and
+/// key callbacks, and reports objects left alive for `sift.leak_check`.
+extern "C" fn request_shutdown(_type: i32, _module_number: i32) -> i32 {
+    DecodeOptions::reset_configured();
+    memory::reset_peak();
+    leaks::request_shutdown();
+    0
+}
+
 /// PHP module registration.
 #[php_module]
 pub fn get_module(module: ModuleBuilder) -> ModuleBuilder {
     init_logger();
     log::info!("sonic-php extension loaded");
-    module
+    info::warn_if_fallback();
+    module.request_shutdown_function(request_shutdown)
 }
 
-// Note: Rust unit tests require PHP to be linked (ext-php-rs dependency).
+// Note: Rust unit tests require PHP to be linked (ext-php-rs dependency), so
+// they live in sift-core (make test-core), which covers the conversion walk,
+// limits and pointer resolution against the native value builder.
 // The comprehensive test suite is in tests/php/SonicTest.php and
 // tests/php/SiftTest.php which test all functionality through the PHP extension.
 // Run tests with: make test-php (or make docker-test for containerized testing)
\ No newline at end of file
@@ -0,0 +1,586 @@
+//! Parallel NDJSON file reading for `Sift::lines()`.
+//!
+//! Usage:
+//! ```php
+//! foreach (Sift::lines('/data/events.ndjson', workers: 4) as $offset => $event) {
+//!     $importer->add($event);
+//! }
+//! ```
+//!
+//! A reader thread reads the file in batches of lines and hands them out
+//! round-robin to worker threads, which validate each line's UTF-8 and
+//! parse it into a `sonic_rs::Value`. The PHP thread takes the batches back
+//! in the same round-robin order, so lines arrive in file order, and only
+//! converts each DOM into PHP values. Reading and parsing overlap with the
+//! PHP code consuming the rows. The end of the file is an explicit message
+//! down the pipeline, so a thread dying early throws rather than ending
+//! iteration as if the file were shorter. How far the threads may get
+//! ahead is the `buffer` limit on line text read but not yet returned
+//! (16 MiB by default): under `'block'` the reader waits for PHP to catch
+//! up, under `'error'` iteration throws once the records before it are
+//! consumed.
+//!
+//! Options that need raw text (strict mode, key renaming, ...) can't use
+//! the DOM, so with those the workers only split and validate the UTF-8
+//! and the PHP thread decodes each line as `Sift::decode()` would.
+//! The worker threads never touch PHP values or `Sift::lastWarnings()`:
+//! warnings about a line's UTF-8 and surrogate repairs travel with its
+//! record and are published when the PHP thread returns it.
+
+use crate::buffering::{BufferLimit, WhenFull};
+use crate::diagnostics::{self, Warning};
+use crate::errors::SonicError;
+use crate::guard::guard;
+use crate::leaks::Live;
+use crate::options::{DecodeOptions, Strategy};
+use crate::parser;
+use crate::source;
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::Zval;
+use ext_php_rs::zend::ce;
+use sift_core::parser as core;
+use sonic_rs::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread;
+
+/// Most lines in one batch.
+const BATCH_LINES: usize = 512;
+
+/// Batches are closed once their lines reach this many bytes, or less
+/// under a small buffer limit.
+const BATCH_BYTES: usize = 1024 * 1024;
+
+/// Read-ahead allowed when no buffer limit is given.
+pub const DEFAULT_BUFFER: BufferLimit = BufferLimit {
+    max_bytes: 16 * 1024 * 1024,
+    when_full: WhenFull::Block,
+};
+
+/// Largest accepted `workers`.
+const MAX_WORKERS: usize = 32;
+
+/// Most workers started by default, whatever the number of CPUs.
+const DEFAULT_WORKERS: usize = 4;
+
+/// Read buffer size of the reader thread.
+const READ_BUFFER: usize = 64 * 1024;
+
+/// A non-blank line read from the file.
+struct RawLine {
+    /// Byte offset of the line's first byte
+    start: u64,
+    /// Byte offset just past its line feed (or the end of the file)
+    end: u64,
+    bytes: Vec<u8>,
+}
+
+/// A line as a worker leaves it for the PHP thread.
+struct ParsedLine {
+    start: u64,
+    end: u64,
+    record: Result<Record, SonicError>,
+}
+
+/// What's left to do for a line on the PHP thread.
+struct Record {
+    pending: Pending,
+    /// Repairs made to the line's text, at byte offsets in the file
+    warnings: Vec<Warning>,
+}
+
+/// How a line's value is made on the PHP thread.
+enum Pending {
+    /// Convert the parsed DOM
+    Dom(Value),
+    /// Decode the text with the lazy path's options
+    Text(String),
+}
+
+/// Lines read together.
+struct Batch<T> {
+    lines: Vec<T>,
+    /// Bytes of line text, as counted against the buffer limit
+    bytes: usize,
+}
+
+/// A batch, None once the whole file is read, or the error that ended
+/// reading. Nothing follows the last two.
+type Message<T> = Result<Option<Batch<T>>, SonicError>;
+
+/// The bytes of line text read but not yet consumed on the PHP thread,
+/// shared by the reader and the pipeline.
+struct ReadAhead {
+    limit: BufferLimit,
+    /// Bytes in flight, and whether the pipeline is gone
+    state: Mutex<(usize, bool)>,
+    freed: Condvar,
+}
+
+impl ReadAhead {
+    /// Count a batch of `bytes` about to be sent. Waits for room under
+    /// `WhenFull::Block`, and returns false if the pipeline is dropped
+    /// meanwhile. A batch is let through whatever its size when nothing
+    /// else is in flight.
+    fn reserve(&self, bytes: usize) -> Result<bool, SonicError> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            let (used, closed) = *state;
+            if closed {
+                return Ok(false);
+            }
+            if used == 0 || used + bytes <= self.limit.max_bytes {
+                state.0 += bytes;
+                return Ok(true);
+            }
+            if self.limit.when_full == WhenFull::Error {
+                return Err(self.limit.exceeded(used + bytes));
+            }
+            state = self
+                .freed
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Give back the bytes of a consumed batch.
+    fn release(&self, bytes: usize) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.0 = state.0.saturating_sub(bytes);
+        self.freed.notify_all();
+    }
+
+    /// Wake up and stop a waiting reader.
+    fn close(&self) {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).1 = true;
+        self.freed.notify_all();
+    }
+}
+
+/// The running threads of one pass over the file and the batch being
+/// consumed.
+struct Pipeline {
+    /// One queue of finished batches per worker, taken in turn
+    queues: Vec<Receiver<Message<ParsedLine>>>,
+    /// The queue the next batch comes from
+    turn: usize,
+    /// Lines of the current batch not yet returned
+    batch: std::vec::IntoIter<ParsedLine>,
+    /// Bytes the current batch holds against the limit
+    batch_bytes: usize,
+    /// Whether the end of the file or an error was received
+    finished: bool,
+    read_ahead: Arc<ReadAhead>,
+}
+
+impl Pipeline {
+    /// Start reading `path` from byte `offset` with `workers` parsing
+    /// threads, reading at most `limit` ahead.
+    fn spawn(
+        path: &str,
+        offset: u64,
+        workers: usize,
+        limit: BufferLimit,
+        dom: bool,
+        options: DecodeOptions,
+    ) -> Result<Self, SonicError> {
+        let mut file = source::open_file(path)?;
+        file.seek(SeekFrom::Start(offset))?;
+
+        let mut inputs = Vec::with_capacity(workers);
+        let mut queues = Vec::with_capacity(workers);
+        for _ in 0..workers {
+            // Unbounded: the read-ahead limit holds back the reader instead
+            let (input, work) = channel::<Message<RawLine>>();
+            let (output, queue) = channel::<Message<ParsedLine>>();
+            thread::Builder::new()
+                .name("sift-lines-worker".to_string())
+                .spawn(move || parse_batches(work, output, dom, options))?;
+            inputs.push(input);
+            queues.push(queue);
+        }
+        let read_ahead = Arc::new(ReadAhead {
+            limit,
+            state: Mutex::new((0, false)),
+            freed: Condvar::new(),
+        });
+        let reader = Reader {
+            file: BufReader::with_capacity(READ_BUFFER, file),
+            position: offset,
+            // Small enough for every worker to have a batch in flight
+            batch_bytes: (limit.max_bytes / (2 * workers)).clamp(1, BATCH_BYTES),
+            read_ahead: Arc::clone(&read_ahead),
+        };
+        thread::Builder::new()
+            .name("sift-lines-reader".to_string())
+            .spawn(move || reader.run(inputs))?;
+
+        Ok(Self {
+            queues,
+            turn: 0,
+            batch: Vec::new().into_iter(),
+            batch_bytes: 0,
+            finished: false,
+            read_ahead,
+        })
+    }
+
+    /// The next line in file order, or None at the end of the file.
+    fn next_line(&mut self) -> Result<Option<ParsedLine>, SonicError> {
+        loop {
+            if let Some(line) = self.batch.next() {
+                return Ok(Some(line));
+            }
+            self.read_ahead
+                .release(std::mem::take(&mut self.batch_bytes));
+            if self.finished {
+                return Ok(None);
+            }
+            // Batches were dealt round-robin, and the end of the file is
+            // marked on the queue in turn after the last one, so a queue
+            // closing first means a thread died
+            let message = self.queues[self.turn].recv().unwrap_or_else(|_| {
+                Err(SonicError::Internal(
+                    "NDJSON worker thread stopped before the end of the file; please report it"
+                        .to_string(),
+                ))
+            });
+            self.turn = (self.turn + 1) % self.queues.len();
+            let batch = match message {
+                Ok(Some(batch)) => batch,
+                Ok(None) => {
+                    self.finished = true;
+                    return Ok(None);
+                }
+                Err(e) => {
+                    self.finished = true;
+                    return Err(e);
+                }
+            };
+            self.batch = batch.lines.into_iter();
+            self.batch_bytes = batch.bytes;
+        }
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        // The workers stop once their queues are gone; the reader may be
+        // waiting for room
+        self.read_ahead.close();
+    }
+}
+
+/// The reader thread's state.
+struct Reader {
+    file: BufReader<File>,
+    /// Offset of the next byte to read
+    position: u64,
+    /// Bytes of text after which a batch is closed
+    batch_bytes: usize,
+    read_ahead: Arc<ReadAhead>,
+}
+
+impl Reader {
+    /// Split the file into batches of non-blank lines, dealt to the
+    /// workers in turn. Stops at the end of the file or on an error, both
+    /// passed on in place of the next batch, or once the PHP side is gone.
+    fn run(mut self, inputs: Vec<Sender<Message<RawLine>>>) {
+        let mut turn = 0;
+        loop {
+            let message = match self.read_batch() {
+                Ok(batch) if batch.lines.is_empty() => Ok(None),
+                Ok(batch) => match self.read_ahead.reserve(batch.bytes) {
+                    Ok(true) => Ok(Some(batch)),
+                    Ok(false) => return,
+                    Err(e) => Err(e.at_offset(batch.lines[0].start as usize)),
+                },
+                Err(e) => Err(e),
+            };
+            let last = !matches!(message, Ok(Some(_)));
+            if inputs[turn].send(message).is_err() || last {
+                return;
+            }
+            turn = (turn + 1) % inputs.len();
+        }
+    }
+
+    /// Read up to a batch of non-blank lines; empty at the end of the file.
+    fn read_batch(&mut self) -> Result<Batch<RawLine>, SonicError> {
+        let mut lines = Vec::new();
+        let mut bytes = 0;
+        while lines.len() < BATCH_LINES && bytes < self.batch_bytes {
+            let mut line = Vec::new();
+            // A line without a line feed would otherwise be read whole
+            let read = self
+                .file
+                .by_ref()
+                .take(parser::MAX_INPUT_SIZE as u64 + 1)
+                .read_until(b'\n', &mut line)?;
+            if read == 0 {
+                break;
+            }
+            let start = self.position;
+            self.position += read as u64;
+            if line.last() != Some(&b'\n') && read > parser::MAX_INPUT_SIZE {
+                return Err(SonicError::InputTooLarge {
+                    size: read,
+                    limit: parser::MAX_INPUT_SIZE,
+                }
+                .at_offset(start as usize));
+            }
+            let content = line.strip_suffix(b"\n").unwrap_or(&line);
+            let content = content.strip_suffix(b"\r").unwrap_or(content);
+            if content.iter().all(|b| matches!(b, b' ' | b'\t' | b'\r')) {
+                continue;
+            }
+            let length = content.len();
+            line.truncate(length);
+            bytes += length;
+            lines.push(RawLine {
+                start,
+                end: self.position,
+                bytes: line,
+            });
+        }
+        Ok(Batch { lines, bytes })
+    }
+}
+
+/// Worker thread: parse every line of each batch it is given, and pass
+/// on the end of the file or the error that ended reading. A panic while
+/// parsing a line becomes that line's error rather than killing the
+/// thread.
+fn parse_batches(
+    work: Receiver<Message<RawLine>>,
+    output: Sender<Message<ParsedLine>>,
+    dom: bool,
+    options: DecodeOptions,
+) {
+    for message in work {
+        let last = !matches!(message, Ok(Some(_)));
+        let parsed = message.map(|batch| {
+            batch.map(|batch| Batch {
+                lines: batch
+                    .lines
+                    .into_iter()
+                    .map(|line| ParsedLine {
+                        start: line.start,
+                        end: line.end,
+                        record: guard(|| {
+                            parse_line(&line.bytes, line.start, dom, &options)
+                                .map_err(|e| line_error(e, line.start))
+                        }),
+                    })
+                    .collect(),
+                bytes: batch.bytes,
+            })
+        });
+        if output.send(parsed).is_err() || last {
+            return;
+        }
+    }
+}
+
+/// Validate the text of the line starting at byte `start` and, with
+/// `dom`, parse it. The core's text functions are called directly, as the
+/// extension's would record the warnings on this thread.
+fn parse_line(
+    bytes: &[u8],
+    start: u64,
+    dom: bool,
+    options: &DecodeOptions,
+) -> Result<Record, SonicError> {
+    let text = if dom {
+        core::input_text(bytes, options)?
+    } else {
+        // Decoded on the PHP thread, which needs text the lazy path accepts
+        core::decode_text(bytes, options)?
+    };
+    let mut warnings = text.warnings;
+    for offset in warnings.iter_mut().filter_map(|w| w.offset.as_mut()) {
+        *offset += start as usize;
+    }
+    let pending = if dom {
+        Pending::Dom(sonic_rs::from_str(&text.text)?)
+    } else {
+        Pending::Text(text.text.into_owned())
+    };
+    Ok(Record { pending, warnings })
+}
+
+/// `error` for the line starting at byte `start` of the file.
+fn line_error(error: SonicError, start: u64) -> SonicError {
+    SonicError::ParseError(format!("NDJSON line at byte {}: {}", start, error))
+        .at_offset(start as usize)
+}
+
+/// Lines - iterates over the records of an NDJSON file, keyed by the byte
+/// offset of each line. Blank lines are skipped; a malformed line throws.
+#[php_class(name = "Sift\\Lines")]
+#[implements(ce::iterator())]
+pub struct Lines {
+    path: String,
+    options: DecodeOptions,
+    workers: usize,
+    buffer: BufferLimit,
+    /// Where the file is read from, from a previous `tell()`
+    offset: u64,
+    /// The current pass; None until iteration starts
+    pipeline: Option<Pipeline>,
+    /// The current record and its line's byte range; None once exhausted
+    current: Option<(Zval, u64, u64)>,
+    /// Where the line after the current one starts
+    position: u64,
+    /// Counts this object for `sift.leak_check` (see `leaks.rs`)
+    live: Live,
+}
+
+impl Lines {
+    /// Iterate over `path` from byte `offset` (the start when `None`) with
+    /// `workers` parsing threads (one per CPU, up to `DEFAULT_WORKERS`,
+    /// when `None`), reading ahead at most `buffer`.
+    pub fn new(
+        path: String,
+        options: DecodeOptions,
+        workers: Option<i64>,
+        offset: Option<i64>,
+        buffer: BufferLimit,
+    ) -> Result<Self, SonicError> {
+        let workers = match workers {
+            None => thread::available_parallelism().map_or(1, |n| n.get().min(DEFAULT_WORKERS)),
+            Some(n) if (1..=MAX_WORKERS as i64).contains(&n) => n as usize,
+            Some(n) => {
+                return Err(SonicError::InvalidOption(format!(
+                    "Workers must be between 1 and {}, got {}",
+                    MAX_WORKERS, n
+                )))
+            }
+        };
+        let offset = match offset {
+            None => 0,
+            Some(offset) if offset >= 0 => offset as u64,
+            Some(offset) => {
+                return Err(SonicError::InvalidOption(format!(
+                    "Offset must be non-negative, got {}",
+                    offset
+                )))
+            }
+        };
+        Ok(Self {
+            path,
+            options,
+            workers,
+            buffer,
+            offset,
+            pipeline: None,
+            current: None,
+            position: offset,
+            live: Live::new("Sift\\Lines"),
+        })
+    }
+
+    /// Start reading and decode the first record, unless already started.
+    fn start(&mut self) -> Result<(), SonicError> {
+        if self.pipeline.is_none() {
+            // Strategy::Lazy asks for lazy conversion even where the DOM works
+            let dom = !self.options.needs_lazy() && self.options.strategy != Strategy::Lazy;
+            self.pipeline = Some(Pipeline::spawn(
+                &self.path,
+                self.offset,
+                self.workers,
+                self.buffer,
+                dom,
+                self.options,
+            )?);
+            self.position = self.offset;
+            self.advance()?;
+        }
+        Ok(())
+    }
+
+    /// Replace the current record with the next one.
+    fn advance(&mut self) -> Result<(), SonicError> {
+        self.current = None;
+        let Some(pipeline) = self.pipeline.as_mut() else {
+            return Ok(());
+        };
+        let Some(line) = pipeline.next_line()? else {
+            return Ok(());
+        };
+        let record = line.record?;
+        // The record's conversion is this call's decode; its warnings
+        // follow those about the line's text
+        diagnostics::start_decode();
+        diagnostics::record(record.warnings);
+        let value = match record.pending {
+            Pending::Dom(value) => parser::value_to_zval(&value),
+            Pending::Text(text) => parser::decode(&text, &self.options),
+        }
+        .map_err(|e| line_error(e, line.start))?;
+        self.position = line.end;
+        self.current = Some((value, line.start, line.end));
+        Ok(())
+    }
+}
+
+#[php_impl]
+impl Lines {
+    /// Start over from the beginning of the file, or the offset iteration
+    /// was resumed at.
+    pub fn rewind(&mut self) -> Result<(), SonicError> {
+        guard(|| {
+            // Dropping the queues stops the old threads
+            self.pipeline = None;
+            self.start()
+        })
+    }
+
+    /// Whether there is a current record.
+    pub fn valid(&mut self) -> Result<bool, SonicError> {
+        guard(|| {
+            self.start()?;
+            Ok(self.current.is_some())
+        })
+    }
+
+    /// The current record, decoded. Returns null once the file is
+    /// exhausted.
+    pub fn current(&mut self) -> Result<Zval, SonicError> {
+        guard(|| {
+            self.start()?;
+            Ok(self
+                .current
+                .as_ref()
+                .map(|(value, _, _)| value.shallow_clone())
+                .unwrap_or_default())
+        })
+    }
+
+    /// Byte offset of the current record's line, or null once exhausted.
+    pub fn key(&mut self) -> Result<Option<i64>, SonicError> {
+        guard(|| {
+            self.start()?;
+            Ok(self.current.as_ref().map(|&(_, start, _)| start as i64))
+        })
+    }
+
+    /// Move on to the next record.
+    pub fn next(&mut self) -> Result<(), SonicError> {
+        guard(|| {
+            self.start()?;
+            self.advance()
+        })
+    }
+
+    /// The byte offset just past the current record's line: pass it to
+    /// `Sift::lines()` to resume after the records processed so far.
+    pub fn tell(&mut self) -> Result<i64, SonicError> {
+        guard(|| {
+            self.start()?;
+            Ok(self.position as i64)
+        })
+    }
+}
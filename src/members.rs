@@ -0,0 +1,80 @@
+//! Order-insensitive array comparison for `Sift::sameMembers()`.
+//!
+//! Usage:
+//! ```php
+//! // Contract test: same users, any order
+//! assert(Sift::sameMembers($expected, $response->getBody()));
+//! // Same ids, whatever the rest of each record holds
+//! assert(Sift::sameMembers($expected, $actual, 'id'));
+//! ```
+//!
+//! Elements are compared by their canonical form (see `signed`), so object
+//! key order and whitespace don't matter, numbers compare as written (`1`
+//! and `1.0` differ), and only the top-level array is unordered: nested
+//! arrays must match element by element. Each side is counted into a map,
+//! so the comparison is linear rather than pairwise.
+
+use crate::errors::SonicError;
+use crate::parser;
+use crate::signed;
+use sonic_rs::{to_array_iter, JsonValueTrait, LazyValue};
+use std::collections::HashMap;
+
+/// Whether `left` and `right` are arrays holding the same multiset of
+/// elements, or with `key`, of values of that member.
+pub fn same_members(left: &str, right: &str, key: Option<&str>) -> Result<bool, SonicError> {
+    let mut counts: HashMap<String, isize> = HashMap::new();
+    let left_len = count(left, key, &mut counts, 1)?;
+    let right_len = count(right, key, &mut counts, -1)?;
+    Ok(left_len == right_len && counts.values().all(|&n| n == 0))
+}
+
+/// Add `step` to the count of each element's identity in `json`; returns
+/// the number of elements.
+fn count(
+    json: &str,
+    key: Option<&str>,
+    counts: &mut HashMap<String, isize>,
+    step: isize,
+) -> Result<usize, SonicError> {
+    parser::check_input_size(json)?;
+    // Pointer lookups don't look past the value they find
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+    let root = parser::lazy_at(json, "")?;
+    if !root.is_array() {
+        return Err(SonicError::TypeError(
+            "sameMembers() expects JSON arrays".to_string(),
+        ));
+    }
+    let mut len = 0;
+    for (index, item) in to_array_iter(root.as_raw_str()).enumerate() {
+        let item = item?;
+        let identity = identity(&item, key, index)?;
+        *counts.entry(identity).or_insert(0) += step;
+        len += 1;
+    }
+    Ok(len)
+}
+
+/// Canonical form of the element, or of its `key` member.
+fn identity(item: &LazyValue, key: Option<&str>, index: usize) -> Result<String, SonicError> {
+    let mut out = String::new();
+    match key {
+        None => signed::write_canonical(&mut out, item, 1)?,
+        Some(key) => {
+            let member = if item.is_object() {
+                item.get(key)
+            } else {
+                None
+            };
+            let member = member.ok_or_else(|| {
+                SonicError::KeyNotFound(format!(
+                    "Element {} has no '{}' member to match by",
+                    index, key
+                ))
+            })?;
+            signed::write_canonical(&mut out, &member, 2)?;
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,206 @@
+//! Accounting for the extension's own heap, `Sift::memoryUsage()` and
+//! `Sift::trimMemory()`.
+//!
+//! Usage:
+//! ```php
+//! $usage = Sift::memoryUsage();
+//! $metrics->gauge('php.memory', memory_get_usage() + $usage['current']);
+//!
+//! // Swoole: between requests, or on a timer in an idle worker
+//! $server->on('workerIdle', fn() => Sift::trimMemory());
+//! ```
+//!
+//! Rust allocations don't go through the Zend memory manager, so Query
+//! texts, Pool buffers and Snapshot trees are invisible to
+//! `memory_get_usage()` and `memory_limit`. Routing them through emalloc
+//! isn't an option: the Zend heap is reset at the end of every request,
+//! while persisted Snapshots and other process-wide state must outlive it.
+//! Instead the global allocator counts every byte the extension holds, so
+//! it can be reported, and documents copied into Rust are checked against
+//! what `memory_limit` leaves after PHP's own usage. The counters are
+//! process-wide: under ZTS they cover every thread.
+//!
+//! Conversions also keep a per-thread arena (see `arena.rs`) warm between
+//! calls, and the system allocator keeps freed pages mapped. Neither
+//! matters in FPM, where workers are recycled, but a long-running worker
+//! that once converted a large document holds on to that high-water mark.
+//! Trimming frees the arena and, with glibc, asks malloc to return free
+//! pages at the top of the heap to the OS. Memory still owned by live
+//! Queries, Pools and Snapshots is unaffected; release those first.
+
+use crate::errors::SonicError;
+use ext_php_rs::types::{ZendCallable, ZendHashTable, Zval};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Bytes currently allocated by the extension.
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Most bytes allocated at once since the last `reset_peak()`.
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Documents smaller than this skip the `memory_limit` check, which costs
+/// two PHP function calls.
+const MIN_CHECKED: usize = 64 * 1024;
+
+/// The system allocator, counting what it hands out.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn grew(size: usize) {
+        let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(current, Ordering::Relaxed);
+    }
+
+    fn shrank(size: usize) {
+        CURRENT.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: every call is forwarded to `System` unchanged; the counters
+// only observe sizes
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::grew(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::grew(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::shrank(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::shrank(layout.size());
+            Self::grew(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Bytes the extension holds now, and at most since the peak was reset.
+pub fn usage() -> (usize, usize) {
+    (
+        CURRENT.load(Ordering::Relaxed),
+        PEAK.load(Ordering::Relaxed),
+    )
+}
+
+/// `['current' => int, 'peak' => int, 'arena' => int]`: bytes held now,
+/// the peak, and how much of the current usage is the thread's idle
+/// conversion arena.
+pub fn report() -> Result<Zval, SonicError> {
+    let (current, peak) = usage();
+    let mut report = ZendHashTable::new();
+    report
+        .insert("current", current as i64)
+        .and_then(|_| report.insert("peak", peak as i64))
+        .and_then(|_| report.insert("arena", crate::arena::allocated() as i64))
+        .map_err(|e| SonicError::TypeError(format!("Failed to insert object key: {}", e)))?;
+    let mut zval = Zval::new();
+    zval.set_hashtable(report);
+    Ok(zval)
+}
+
+/// Start a new peak from the current usage; done at the end of each
+/// request, so the peak is per request in FPM.
+pub fn reset_peak() {
+    PEAK.store(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// Fail if holding `bytes` more in Rust would take PHP's usage plus the
+/// extension's past `memory_limit`. Without a limit (`-1`), or if either
+/// can't be read, anything goes; so do documents under `MIN_CHECKED`.
+pub fn reserve(bytes: usize) -> Result<(), SonicError> {
+    if bytes < MIN_CHECKED {
+        return Ok(());
+    }
+    let Some(limit) = memory_limit() else {
+        return Ok(());
+    };
+    let php = php_usage().unwrap_or(0);
+    let (current, _) = usage();
+    let available = limit.saturating_sub(php.saturating_add(current));
+    if bytes > available {
+        return Err(SonicError::InputTooLarge {
+            size: bytes,
+            limit: available,
+        });
+    }
+    Ok(())
+}
+
+/// `memory_limit` in bytes, or None when it is unlimited.
+fn memory_limit() -> Option<usize> {
+    let value = ZendCallable::try_from_name("ini_get")
+        .ok()?
+        .try_call(vec![&"memory_limit"])
+        .ok()?;
+    parse_size(value.str()?)
+}
+
+/// An ini size such as `128M`, `1G`, `512k` or `1048576`; None for
+/// negative (unlimited) and unparsable values.
+fn parse_size(value: &str) -> Option<usize> {
+    let value = value.trim();
+    let (digits, unit) = match value.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&value[..i], c.to_ascii_lowercase()),
+        _ => (value, ' '),
+    };
+    let number: usize = digits.trim().parse().ok()?;
+    let multiplier: usize = match unit {
+        ' ' => 1,
+        'k' => 1 << 10,
+        'm' => 1 << 20,
+        'g' => 1 << 30,
+        _ => return None,
+    };
+    Some(number.saturating_mul(multiplier))
+}
+
+/// What `memory_get_usage()` reports: bytes allocated through the Zend
+/// memory manager.
+fn php_usage() -> Option<usize> {
+    let usage = ZendCallable::try_from_name("memory_get_usage")
+        .ok()?
+        .try_call(vec![])
+        .ok()?;
+    usage.long().and_then(|bytes| usize::try_from(bytes).ok())
+}
+
+/// Free the thread's arena and return unused heap to the OS where the
+/// allocator supports it. The arena's size in bytes is returned.
+pub fn trim() -> usize {
+    let freed = crate::arena::trim();
+    release_to_os();
+    freed
+}
+
+/// Ask glibc to give free heap pages back to the OS.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn release_to_os() {
+    extern "C" {
+        fn malloc_trim(pad: usize) -> std::ffi::c_int;
+    }
+    // SAFETY: malloc_trim only walks the allocator's own free lists
+    unsafe {
+        malloc_trim(0);
+    }
+}
+
+/// Other allocators return memory on their own schedule.
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+fn release_to_os() {}
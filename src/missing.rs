@@ -0,0 +1,75 @@
+//! The null object returned for absent paths by `Query::lookup()`.
+//!
+//! Usage:
+//! ```php
+//! $phone = Sift::query($json)->lookup('/user/phone');
+//! if ($phone->isMissing()) {
+//!     // No "phone" key at all; a JSON null would be a Query
+//! }
+//! $city = Sift::query($json)->lookup('/user/address/city')->orElse('unknown');
+//! ```
+//!
+//! A MissingValue answers the same `isMissing()` / `orElse()` calls as a
+//! Query, so callers branch on absence without catching exceptions and
+//! without mistaking `"key": null` for a key that isn't there.
+
+use crate::errors::SonicError;
+use crate::guard::guard;
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::Zval;
+
+/// MissingValue - stands in for a Query whose path doesn't exist.
+#[php_class(name = "Sift\\MissingValue")]
+#[derive(Clone, Debug)]
+pub struct MissingValue {
+    /// The RFC 6901 pointer that was looked up
+    pointer: String,
+}
+
+impl MissingValue {
+    /// A MissingValue for the absent path `pointer`.
+    pub fn new(pointer: String) -> Self {
+        Self { pointer }
+    }
+}
+
+#[php_impl]
+impl MissingValue {
+    /// Always true; `Query::isMissing()` is always false.
+    pub fn is_missing(&self) -> bool {
+        true
+    }
+
+    /// Return `default`, as there is no value.
+    ///
+    /// # Example
+    /// ```php
+    /// $limit = $q->lookup('/settings/limit')->orElse(100);
+    /// ```
+    pub fn or_else(&self, default: &Zval) -> Zval {
+        default.shallow_clone()
+    }
+
+    /// Look up `pointer` below the absent path, which is absent too, so
+    /// chained lookups stay soft.
+    ///
+    /// # Example
+    /// ```php
+    /// $zip = $q->lookup('/user')->lookup('/address/zip')->orElse(null);
+    /// ```
+    pub fn lookup(&self, pointer: &str) -> Result<MissingValue, SonicError> {
+        guard(|| {
+            if !pointer.is_empty() && !pointer.starts_with('/') {
+                return Err(SonicError::InvalidPointer(
+                    "Pointer must start with '/' or be empty".to_string(),
+                ));
+            }
+            Ok(MissingValue::new(format!("{}{}", self.pointer, pointer)))
+        })
+    }
+
+    /// The pointer that was looked up, from the root of the document.
+    pub fn path(&self) -> String {
+        self.pointer.clone()
+    }
+}
@@ -0,0 +1,171 @@
+//! MessagePack decoding for `Sift::decodeAuto()`.
+//!
+//! Maps become PHP arrays (keys must be strings or integers), `bin` and
+//! `str` both become PHP strings, and unsigned integers beyond PHP's int
+//! range become floats as they do for JSON. Extension types (timestamps
+//! included) are rejected.
+
+use crate::errors::SonicError;
+use crate::parser::{MAX_DEPTH, MAX_INPUT_SIZE};
+use ext_php_rs::types::{ZendHashTable, Zval};
+
+/// Error for malformed input, at byte `offset`.
+fn malformed(detail: &str, offset: usize) -> SonicError {
+    SonicError::ParseError(format!("Malformed MessagePack: {}", detail)).at_offset(offset)
+}
+
+/// Error for a failed insert into a result array.
+fn insert_error(e: ext_php_rs::error::Error) -> SonicError {
+    SonicError::TypeError(format!("Failed to insert object key: {}", e))
+}
+
+/// Whether `byte` starts a map or an array, the only top-level values
+/// `Sift::decodeAuto()` recognizes as MessagePack (scalars are
+/// indistinguishable from text).
+pub fn starts_container(byte: u8) -> bool {
+    matches!(byte, 0x80..=0x9f | 0xdc..=0xdf)
+}
+
+/// Decode one MessagePack value filling all of `bytes`.
+pub fn decode(bytes: &[u8]) -> Result<Zval, SonicError> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let value = reader.value(0)?;
+    if reader.pos != bytes.len() {
+        return Err(malformed("trailing bytes after the value", reader.pos));
+    }
+    Ok(value)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SonicError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| malformed("unexpected end of input", self.bytes.len()))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn uint(&mut self, width: usize) -> Result<u64, SonicError> {
+        Ok(self
+            .take(width)?
+            .iter()
+            .fold(0u64, |n, &b| (n << 8) | u64::from(b)))
+    }
+
+    fn int(&mut self, width: usize) -> Result<i64, SonicError> {
+        let n = self.uint(width)?;
+        // Sign-extend from `width` bytes
+        let shift = 64 - width * 8;
+        Ok(((n << shift) as i64) >> shift)
+    }
+
+    /// A container or string length. Lengths beyond the input size limit
+    /// are rejected before anything is allocated for them.
+    fn len(&mut self, width: usize) -> Result<usize, SonicError> {
+        let at = self.pos;
+        let len = self.uint(width)? as usize;
+        if len > MAX_INPUT_SIZE {
+            return Err(malformed("length exceeds the input size limit", at));
+        }
+        Ok(len)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Zval, SonicError> {
+        if depth > MAX_DEPTH {
+            return Err(SonicError::ParseError(format!(
+                "Maximum nesting depth ({}) exceeded",
+                MAX_DEPTH
+            )));
+        }
+        let at = self.pos;
+        let marker = self.take(1)?[0];
+        let mut zval = Zval::new();
+        match marker {
+            0x00..=0x7f => zval.set_long(i64::from(marker)),
+            0xe0..=0xff => zval.set_long(i64::from(marker as i8)),
+            0xc0 => zval.set_null(),
+            0xc2 => zval.set_bool(false),
+            0xc3 => zval.set_bool(true),
+            0xcc => zval.set_long(self.uint(1)? as i64),
+            0xcd => zval.set_long(self.uint(2)? as i64),
+            0xce => zval.set_long(self.uint(4)? as i64),
+            0xcf => match self.uint(8)? {
+                // Too large for PHP's int, as for JSON
+                n if n > i64::MAX as u64 => zval.set_double(n as f64),
+                n => zval.set_long(n as i64),
+            },
+            0xd0 => zval.set_long(self.int(1)?),
+            0xd1 => zval.set_long(self.int(2)?),
+            0xd2 => zval.set_long(self.int(4)?),
+            0xd3 => zval.set_long(self.int(8)?),
+            0xca => zval.set_double(f32::from_bits(self.uint(4)? as u32)),
+            0xcb => zval.set_double(f64::from_bits(self.uint(8)?)),
+            0xa0..=0xbf => return self.string(usize::from(marker & 0x1f)),
+            0xd9 | 0xc4 => return self.len(1).and_then(|len| self.string(len)),
+            0xda | 0xc5 => return self.len(2).and_then(|len| self.string(len)),
+            0xdb | 0xc6 => return self.len(4).and_then(|len| self.string(len)),
+            0x90..=0x9f => return self.array(usize::from(marker & 0x0f), depth),
+            0xdc => return self.len(2).and_then(|len| self.array(len, depth)),
+            0xdd => return self.len(4).and_then(|len| self.array(len, depth)),
+            0x80..=0x8f => return self.map(usize::from(marker & 0x0f), depth),
+            0xde => return self.len(2).and_then(|len| self.map(len, depth)),
+            0xdf => return self.len(4).and_then(|len| self.map(len, depth)),
+            0xc7..=0xc9 | 0xd4..=0xd8 => {
+                return Err(malformed("extension types are not supported", at))
+            }
+            0xc1 => return Err(malformed("reserved marker 0xc1", at)),
+        }
+        Ok(zval)
+    }
+
+    fn string(&mut self, len: usize) -> Result<Zval, SonicError> {
+        let mut zval = Zval::new();
+        zval.set_binary(self.take(len)?.to_vec());
+        Ok(zval)
+    }
+
+    fn array(&mut self, len: usize, depth: usize) -> Result<Zval, SonicError> {
+        // Every element takes at least a byte, which bounds the allocation
+        let mut list = ZendHashTable::with_capacity(len.min(self.bytes.len() - self.pos) as u32);
+        for _ in 0..len {
+            let item = self.value(depth + 1)?;
+            list.push(item).map_err(insert_error)?;
+        }
+        let mut zval = Zval::new();
+        zval.set_hashtable(list);
+        Ok(zval)
+    }
+
+    fn map(&mut self, len: usize, depth: usize) -> Result<Zval, SonicError> {
+        let mut map = ZendHashTable::new();
+        for _ in 0..len {
+            let at = self.pos;
+            let key = self.value(depth + 1)?;
+            let item = self.value(depth + 1)?;
+            if let Some(index) = key.long() {
+                // Negative keys round-trip through zend_ulong as in PHP
+                map.insert_at_index(index as u64, item)
+                    .map_err(insert_error)?;
+            } else if key.is_string() {
+                // Keys that aren't UTF-8 can't be array keys through this API
+                let name = key
+                    .str()
+                    .ok_or_else(|| malformed("map key is not valid UTF-8", at))?;
+                map.insert(name, item).map_err(insert_error)?;
+            } else {
+                return Err(malformed("map keys must be strings or integers", at));
+            }
+        }
+        let mut zval = Zval::new();
+        zval.set_hashtable(map);
+        Ok(zval)
+    }
+}
@@ -0,0 +1,97 @@
+//! Raw conversion between NDJSON and JSON arrays for
+//! `Sift::linesToArray()` and `Sift::arrayToLines()`.
+//!
+//! Usage:
+//! ```php
+//! $batch = Sift::linesToArray(file_get_contents('events.ndjson')); // "[{...},{...}]"
+//! file_put_contents('events.ndjson', Sift::arrayToLines($response->getBody()));
+//! ```
+//!
+//! Records are copied as text: each is validated but never converted to
+//! PHP values. An array element written over several lines is compacted
+//! onto one by dropping the whitespace between its tokens; everything
+//! else keeps its bytes.
+
+use crate::errors::SonicError;
+use crate::parser;
+use sonic_rs::{to_array_iter, JsonValueTrait};
+
+/// Join the non-blank lines of `ndjson` into a JSON array.
+pub fn lines_to_array(ndjson: &str) -> Result<String, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(ndjson)?;
+
+    let mut out = String::with_capacity(ndjson.len() + 2);
+    out.push('[');
+    let mut offset = 0;
+    for (number, line) in ndjson.split('\n').enumerate() {
+        let start = offset;
+        offset += line.len() + 1;
+        let record = line.trim_matches([' ', '\t', '\r']);
+        if record.is_empty() {
+            continue;
+        }
+        sonic_rs::from_str::<sonic_rs::Value>(record).map_err(|e| {
+            SonicError::ParseError(format!("NDJSON line {}: {}", number + 1, e)).at_offset(start)
+        })?;
+        if out.len() > 1 {
+            out.push(',');
+        }
+        out.push_str(record);
+    }
+    out.push(']');
+    Ok(out)
+}
+
+/// Write each element of the JSON array `json` on its own line, each
+/// line ending in `\n`.
+pub fn array_to_lines(json: &str) -> Result<String, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+    // Elements are copied without being parsed, so validate them up front
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+
+    let root = parser::lazy_at(json, "")?;
+    if !root.is_array() {
+        return Err(
+            SonicError::TypeError("Expected a top-level array".to_string())
+                .with_types("array", parser::type_name(&root)),
+        );
+    }
+    let mut out = String::with_capacity(json.len());
+    for element in to_array_iter(root.as_raw_str()) {
+        let element = element?;
+        let raw = element.as_raw_str().trim();
+        // Strings can't hold raw line breaks, so these are between tokens
+        if raw.contains(['\n', '\r']) {
+            push_compact(&mut out, raw);
+        } else {
+            out.push_str(raw);
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Append the JSON text `raw` without the whitespace between its tokens.
+fn push_compact(out: &mut String, raw: &str) {
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in raw.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else {
+            match c {
+                ' ' | '\t' | '\n' | '\r' => continue,
+                '"' => in_string = true,
+                _ => {}
+            }
+        }
+        out.push(c);
+    }
+}
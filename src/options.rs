@@ -0,0 +1,239 @@
+//! Decode options parsed from PHP option arrays; the options themselves
+//! are `sift_core::options`.
+//!
+//! Usage:
+//! ```php
+//! Sift::configure(['big_numbers' => 'bcmath']); // request-wide defaults
+//! $data = Sift::decode($json, ['strict' => true]);
+//! ```
+
+use crate::errors::SonicError;
+use crate::interpolate;
+use crate::keys;
+use crate::precision;
+use ext_php_rs::types::{ArrayKey, ZendHashTable, Zval};
+use std::cell::Cell;
+
+pub use sift_core::options::{
+    BigNumbers, ControlChars, DecodeOptions, DuplicateKeys, Leaves, Strategy, Surrogates,
+    Utf8Policy, MAX_DEPTH_LIMIT,
+};
+
+thread_local! {
+    /// Defaults set by `Sift::configure()`, reset at request shutdown
+    /// (request-scoped, one per PHP thread).
+    static CONFIGURED: Cell<DecodeOptions> = Cell::new(DecodeOptions::default());
+}
+
+/// `DecodeOptions` as PHP code gives them: option arrays on top of the
+/// request's `Sift::configure()` defaults.
+pub trait PhpOptions: Sized {
+    /// Parse an options array such as `['strict' => true]`, on top of the
+    /// request's configured defaults.
+    fn from_array(options: Option<&ZendHashTable>) -> Result<Self, SonicError>;
+
+    /// The request's defaults: the built-in ones unless `Sift::configure()`
+    /// changed them.
+    fn configured() -> Self;
+
+    /// Replaces the request's defaults with the built-in ones overridden by
+    /// `options`. Nothing changes if `options` is invalid.
+    fn configure(options: &ZendHashTable) -> Result<(), SonicError>;
+
+    /// Drops the `Sift::configure()` defaults and the key callbacks,
+    /// variables and number patterns they may refer to; runs at request
+    /// shutdown.
+    fn reset_configured();
+}
+
+impl PhpOptions for DecodeOptions {
+    fn from_array(options: Option<&ZendHashTable>) -> Result<Self, SonicError> {
+        merge(Self::configured(), options)
+    }
+
+    fn configured() -> Self {
+        CONFIGURED.with(Cell::get)
+    }
+
+    fn configure(options: &ZendHashTable) -> Result<(), SonicError> {
+        let opts = merge(Self::default(), Some(options))?;
+        CONFIGURED.with(|configured| configured.set(opts));
+        Ok(())
+    }
+
+    fn reset_configured() {
+        CONFIGURED.with(|configured| configured.set(Self::default()));
+        keys::reset_callbacks();
+        interpolate::reset_variables();
+        precision::reset_patterns();
+    }
+}
+
+/// Applies the keys set in `options` to `opts`.
+/// Unknown keys are rejected so typos don't silently fall back to defaults.
+fn merge(
+    mut opts: DecodeOptions,
+    options: Option<&ZendHashTable>,
+) -> Result<DecodeOptions, SonicError> {
+    let Some(options) = options else {
+        return Ok(opts);
+    };
+
+    for (key, value) in options.iter() {
+        let name = match key {
+            ArrayKey::String(name) => name,
+            ArrayKey::Long(idx) => {
+                return Err(SonicError::InvalidOption(format!(
+                    "Option keys must be strings, got {}",
+                    idx
+                )))
+            }
+        };
+        match name.as_str() {
+            "strict" => opts.strict = option_bool(&name, value)?,
+            "warnings" => opts.warnings = option_bool(&name, value)?,
+            "presize" => opts.presize = option_bool(&name, value)?,
+            "strategy" => opts.strategy = option_strategy(&name, value)?,
+            "big_numbers" => opts.big_numbers = option_big_numbers(&name, value)?,
+            "assoc" => opts.assoc = option_bool(&name, value)?,
+            "bigint" => opts.bigint_as_string = option_bigint(&name, value)?,
+            // `max_depth` reads like the limit it is, and is what callers
+            // of Sift::configure() tend to write
+            "depth" | "max_depth" => opts.depth = option_depth(&name, value)?,
+            "utf8" => opts.utf8 = option_utf8(&name, value)?,
+            "duplicate_keys" => opts.duplicate_keys = option_duplicate_keys(&name, value)?,
+            "keys" => opts.keys = keys::transform_from_zval(&name, value)?,
+            "strip_prefix" => opts.strip_prefix = keys::affix_from_zval(&name, value)?,
+            "nest_keys" => opts.nest_keys = keys::affix_from_zval(&name, value)?,
+            "control_chars" => opts.control_chars = option_control_chars(&name, value)?,
+            "surrogates" => opts.surrogates = option_surrogates(&name, value)?,
+            "interpolate" => opts.interpolate = interpolate::from_zval(&name, value)?,
+            "numbers" => opts.numbers = precision::from_zval(&name, value)?,
+            _ => {
+                return Err(SonicError::InvalidOption(format!(
+                    "Unknown option '{}'",
+                    name
+                )))
+            }
+        }
+    }
+
+    if !opts.nest_keys.is_empty() && !opts.assoc {
+        return Err(SonicError::InvalidOption(
+            "Option 'nest_keys' requires 'assoc' => true".to_string(),
+        ));
+    }
+    Ok(opts)
+}
+
+/// Read a boolean option value without PHP's loose truthiness.
+fn option_bool(name: &str, value: &Zval) -> Result<bool, SonicError> {
+    value
+        .bool()
+        .ok_or_else(|| SonicError::InvalidOption(format!("Option '{}' must be a boolean", name)))
+}
+
+/// Read the `strategy` option: "auto", "dom" or "lazy".
+fn option_strategy(name: &str, value: &Zval) -> Result<Strategy, SonicError> {
+    match value.str() {
+        Some("auto") => Ok(Strategy::Auto),
+        Some("dom") => Ok(Strategy::Dom),
+        Some("lazy") => Ok(Strategy::Lazy),
+        _ => Err(SonicError::InvalidOption(format!(
+            "Option '{}' must be one of 'auto', 'dom', 'lazy'",
+            name
+        ))),
+    }
+}
+
+/// Read the `big_numbers` option: "float", "bcmath" or "gmp".
+fn option_big_numbers(name: &str, value: &Zval) -> Result<BigNumbers, SonicError> {
+    match value.str() {
+        Some("float") => Ok(BigNumbers::Float),
+        Some("bcmath") => Ok(BigNumbers::BcMath),
+        Some("gmp") => Ok(BigNumbers::Gmp),
+        _ => Err(SonicError::InvalidOption(format!(
+            "Option '{}' must be one of 'float', 'bcmath', 'gmp'",
+            name
+        ))),
+    }
+}
+
+/// Read the `bigint` option: "float" or "string". Returns whether
+/// out-of-range integers decode as strings.
+fn option_bigint(name: &str, value: &Zval) -> Result<bool, SonicError> {
+    match value.str() {
+        Some("float") => Ok(false),
+        Some("string") => Ok(true),
+        _ => Err(SonicError::InvalidOption(format!(
+            "Option '{}' must be one of 'float', 'string'",
+            name
+        ))),
+    }
+}
+
+/// Read the `depth` option: an integer from 1 to `MAX_DEPTH_LIMIT`.
+fn option_depth(name: &str, value: &Zval) -> Result<usize, SonicError> {
+    value
+        .long()
+        .filter(|depth| (1..=MAX_DEPTH_LIMIT as i64).contains(depth))
+        .map(|depth| depth as usize)
+        .ok_or_else(|| {
+            SonicError::InvalidOption(format!(
+                "Option '{}' must be an integer between 1 and {}",
+                name, MAX_DEPTH_LIMIT
+            ))
+        })
+}
+
+/// Read the `control_chars` option: "keep", "strip" or "error".
+fn option_control_chars(name: &str, value: &Zval) -> Result<ControlChars, SonicError> {
+    match value.str() {
+        Some("keep") => Ok(ControlChars::Keep),
+        Some("strip") => Ok(ControlChars::Strip),
+        Some("error") => Ok(ControlChars::Error),
+        _ => Err(SonicError::InvalidOption(format!(
+            "Option '{}' must be one of 'keep', 'strip', 'error'",
+            name
+        ))),
+    }
+}
+
+/// Read the `surrogates` option: "error", "replace" or "wtf8".
+fn option_surrogates(name: &str, value: &Zval) -> Result<Surrogates, SonicError> {
+    match value.str() {
+        Some("error") => Ok(Surrogates::Error),
+        Some("replace") => Ok(Surrogates::Replace),
+        Some("wtf8") => Ok(Surrogates::Wtf8),
+        _ => Err(SonicError::InvalidOption(format!(
+            "Option '{}' must be one of 'error', 'replace', 'wtf8'",
+            name
+        ))),
+    }
+}
+
+/// Read the `utf8` option: "error", "ignore" or "substitute".
+fn option_utf8(name: &str, value: &Zval) -> Result<Utf8Policy, SonicError> {
+    match value.str() {
+        Some("error") => Ok(Utf8Policy::Error),
+        Some("ignore") => Ok(Utf8Policy::Ignore),
+        Some("substitute") => Ok(Utf8Policy::Substitute),
+        _ => Err(SonicError::InvalidOption(format!(
+            "Option '{}' must be one of 'error', 'ignore', 'substitute'",
+            name
+        ))),
+    }
+}
+
+/// Read the `duplicate_keys` option: "last", "first" or "error".
+fn option_duplicate_keys(name: &str, value: &Zval) -> Result<DuplicateKeys, SonicError> {
+    match value.str() {
+        Some("last") => Ok(DuplicateKeys::Last),
+        Some("first") => Ok(DuplicateKeys::First),
+        Some("error") => Ok(DuplicateKeys::Error),
+        _ => Err(SonicError::InvalidOption(format!(
+            "Option '{}' must be one of 'last', 'first', 'error'",
+            name
+        ))),
+    }
+}
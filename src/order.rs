@@ -0,0 +1,149 @@
+//! Key-order-preserving encoding for `Sift::encodeOrdered()`.
+//!
+//! Usage:
+//! ```php
+//! $config = Sift::decode($original);
+//! ksort($config['services']);               // or unset() and re-add a key
+//! $config['services']['cache']['ttl'] = 60;
+//! file_put_contents('config.json', Sift::encodeOrdered($config, $original));
+//! ```
+//!
+//! Decoding keeps member order, as PHP arrays keep insertion order, but
+//! code that sorts, rebuilds or unsets and re-adds keys moves them. Here
+//! the original document serves as the order: each object is written with
+//! the keys the original has at the same path first, in its order, then
+//! keys the original doesn't have, in PHP order. Everything else encodes
+//! as `encoder` does, so the diff of a re-saved file shows only the values
+//! that changed.
+
+use crate::bignum;
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::parser::{self, MAX_DEPTH};
+use ext_php_rs::flags::DataType;
+use ext_php_rs::types::{ArrayKey, Zval};
+use ext_php_rs::zend::ClassEntry;
+use sonic_rs::{JsonValueTrait, Value};
+use std::collections::HashMap;
+
+/// Encode `value` with object keys in the order of the JSON document
+/// `original`.
+pub fn encode_ordered(value: &Zval, original: &str) -> Result<String, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(original)?;
+    let template: Value = sonic_rs::from_str(original)?;
+    let mut out = String::new();
+    write_value(&mut out, value, Some(&template), 0)?;
+    Ok(out)
+}
+
+/// Internal: write `value`, ordering its keys like `template`, the value
+/// at the same path in the original document, if there is one.
+fn write_value(
+    out: &mut String,
+    value: &Zval,
+    template: Option<&Value>,
+    depth: usize,
+) -> Result<(), SonicError> {
+    if depth > MAX_DEPTH {
+        return Err(SonicError::TypeError(format!(
+            "Maximum nesting depth ({}) exceeded",
+            MAX_DEPTH
+        )));
+    }
+    let value = value.dereference();
+    let Some(template) = template.filter(|t| t.is_object() || t.is_array()) else {
+        return encoder::encode_into(out, value);
+    };
+
+    let members = match value.get_type() {
+        DataType::Array => {
+            let Some(array) = value.array() else {
+                return encoder::encode_into(out, value);
+            };
+            if array.has_sequential_keys() {
+                out.push('[');
+                for (i, (_, item)) in array.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_value(out, item, template.get(i), depth + 1)?;
+                }
+                out.push(']');
+                return Ok(());
+            }
+            array
+                .iter()
+                .map(|(key, item)| (key_name(key), item))
+                .collect::<Vec<_>>()
+        }
+        DataType::Object(_) => {
+            let Some(object) = value.object() else {
+                return encoder::encode_into(out, value);
+            };
+            // jsonSerialize() decides what is written, and exact numbers
+            // are written as numbers, so nothing to order
+            let serializable = ClassEntry::try_find("JsonSerializable");
+            if serializable.is_some_and(|ce| object.instance_of(ce))
+                || bignum::is_number(object)
+            {
+                return encoder::encode_into(out, value);
+            }
+            let properties = object.get_properties().map_err(|e| {
+                SonicError::TypeError(format!("Failed to read object properties: {}", e))
+            })?;
+            properties
+                .iter()
+                .filter(|(_, item)| !matches!(item.dereference().get_type(), DataType::Undef))
+                .filter_map(|(key, item)| match key {
+                    // Private and protected names are mangled with a leading NUL
+                    ArrayKey::String(name) if name.starts_with('\0') => None,
+                    key => Some((key_name(key), item)),
+                })
+                .collect::<Vec<_>>()
+        }
+        _ => return encoder::encode_into(out, value),
+    };
+    write_members(out, members, template, depth)
+}
+
+/// Write the members of an object, those `template` has first in its
+/// order, then the rest in their own.
+fn write_members(
+    out: &mut String,
+    mut members: Vec<(String, &Zval)>,
+    template: &Value,
+    depth: usize,
+) -> Result<(), SonicError> {
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    if let Some(object) = template.as_object() {
+        for (position, (key, _)) in object.iter().enumerate() {
+            // A repeated key decodes at its first position
+            positions.entry(key).or_insert(position);
+        }
+    }
+    // Stable, so keys the template lacks keep their PHP order
+    members.sort_by_key(|(key, _)| positions.get(key.as_str()).copied().unwrap_or(usize::MAX));
+
+    let object = template.as_object();
+    out.push('{');
+    for (i, (key, item)) in members.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        encoder::write_string(out, key.as_bytes())?;
+        out.push(':');
+        let child = object.and_then(|object| object.get(&key.as_str()));
+        write_value(out, item, child, depth + 1)?;
+    }
+    out.push('}');
+    Ok(())
+}
+
+/// A PHP key as a JSON object key.
+fn key_name(key: ArrayKey) -> String {
+    match key {
+        ArrayKey::Long(index) => index.to_string(),
+        ArrayKey::String(name) => name,
+    }
+}
@@ -1,275 +1,516 @@
 //! Sonic-rs logic wrappers for JSON parsing operations.
+//!
+//! Limits, text policies and pointer resolution live in `sift_core::parser`,
+//! and the conversion walk in `sift_core::convert`; this module is the
+//! sink that walk builds zvals through, and wraps the core functions the
+//! rest of the extension calls so they report `SonicError`.
 
+use crate::arena;
+use crate::bignum;
+use crate::diagnostics::{self, Diagnostics};
 use crate::errors::SonicError;
+use crate::keys;
+use crate::options::{BigNumbers, DecodeOptions, Leaves};
+use crate::query::Query;
 use ext_php_rs::convert::IntoZval;
-use ext_php_rs::types::Zval;
-use sonic_rs::{JsonContainerTrait, JsonValueTrait, LazyValue, PointerNode, Value};
+use ext_php_rs::boxed::ZBox;
+use ext_php_rs::flags::DataType;
+use ext_php_rs::types::{PropertyQuery, ZendHashTable, ZendObject, ZendStr, Zval};
+use sonic_rs::{JsonValueTrait, LazyValue, PointerNode, Value};
 use sonic_rs::{to_array_iter_unchecked, to_object_iter_unchecked};
-use faststr::FastStr;
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+use sift_core::convert::{self, skip_duplicate};
+use sift_core::parser::{self as core, iteration_error};
+use sift_core::sink::{Key, ValueSink};
+use std::borrow::Cow;
 
-/// Maximum allowed nesting depth to prevent stack overflow.
-/// PHP's default json_decode limit is 512.
-const MAX_DEPTH: usize = 512;
+pub use sift_core::parser::{is_valid, type_name, MAX_DEPTH, MAX_INPUT_SIZE, MAX_POINTER_SEGMENTS};
 
-/// Maximum allowed JSON input size (64 MB).
-const MAX_INPUT_SIZE: usize = 64 * 1024 * 1024;
+/// Builds zvals for `sift_core::convert`: objects become associative
+/// arrays or `stdClass` per `options.assoc`, exact numbers `BCMath\Number`
+/// or GMP objects, and containers beyond the cutoff Queries or text.
+pub struct ZvalSink<'o> {
+    pub options: &'o DecodeOptions,
+}
+
+impl ValueSink for ZvalSink<'_> {
+    type Value = Zval;
+    type Error = SonicError;
+
+    fn null(&mut self) -> Result<Zval, SonicError> {
+        let mut zval = Zval::new();
+        zval.set_null();
+        Ok(zval)
+    }
+
+    fn bool(&mut self, b: bool) -> Result<Zval, SonicError> {
+        b.into_zval(false)
+            .map_err(|e| SonicError::TypeError(e.to_string()))
+    }
+
+    fn int(&mut self, n: i64) -> Result<Zval, SonicError> {
+        n.into_zval(false)
+            .map_err(|e| SonicError::TypeError(e.to_string()))
+    }
+
+    fn float(&mut self, n: f64) -> Result<Zval, SonicError> {
+        let mut zval = Zval::new();
+        zval.set_double(n);
+        Ok(zval)
+    }
+
+    fn string(&mut self, s: &str) -> Result<Zval, SonicError> {
+        // `s` borrows the input (no escapes) or sonic-rs' unescape buffer,
+        // so copying it into the zend_string is the only copy
+        let mut zval = Zval::new();
+        zval.set_zend_string(ZendStr::new(s, false));
+        Ok(zval)
+    }
+
+    fn bytes(&mut self, b: &[u8]) -> Result<Zval, SonicError> {
+        let mut zval = Zval::new();
+        zval.set_zend_string(ZendStr::new(b, false));
+        Ok(zval)
+    }
+
+    fn big_number(&mut self, raw: &str, mode: BigNumbers) -> Result<Zval, SonicError> {
+        bignum::to_zval(raw, mode)
+    }
+
+    /// A container beyond the hydration cutoff: a Query over its text,
+    /// which hydrates in full with the other options, or the text itself.
+    fn leaf(&mut self, raw: &str, leaves: Leaves) -> Result<Zval, SonicError> {
+        match leaves {
+            Leaves::Raw => self.string(raw),
+            Leaves::Query => {
+                let options = DecodeOptions {
+                    cutoff: None,
+                    ..*self.options
+                };
+                Query::new(raw.to_string(), options)
+                    .into_zval(false)
+                    .map_err(|e| SonicError::TypeError(e.to_string()))
+            }
+        }
+    }
+
+    fn array(&mut self, capacity: usize) -> Result<Zval, SonicError> {
+        let mut zval = Zval::new();
+        zval.set_hashtable(hashtable_with_capacity(capacity));
+        Ok(zval)
+    }
+
+    fn push(&mut self, array: &mut Zval, value: Zval) -> Result<(), SonicError> {
+        let table = array.array_mut().ok_or_else(|| {
+            SonicError::TypeError("Failed to push array item: not an array".to_string())
+        })?;
+        table
+            .push(value)
+            .map_err(|e| SonicError::TypeError(format!("Failed to push array item: {}", e)))
+    }
 
-/// Maximum allowed pointer segments to prevent DoS.
-const MAX_POINTER_SEGMENTS: usize = 256;
+    /// An associative array, or a `stdClass` as `json_decode($json, false)`
+    /// makes.
+    fn object(&mut self, capacity: usize) -> Result<Zval, SonicError> {
+        if self.options.assoc {
+            return self.array(capacity);
+        }
+        ZendObject::new_stdclass()
+            .into_zval(false)
+            .map_err(|e| SonicError::TypeError(e.to_string()))
+    }
+
+    fn contains(&self, object: &Zval, key: &Key<'_>) -> bool {
+        if let Some(table) = object.array() {
+            return match *key {
+                Key::Index(idx) => table.get_index(idx as u64).is_some(),
+                Key::Name(name) => table.get(name).is_some(),
+            };
+        }
+        match (object.object(), key) {
+            (Some(object), Key::Name(name)) => object
+                .has_property(name, PropertyQuery::Exists)
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    fn insert(&mut self, object: &mut Zval, key: Key<'_>, value: Zval) -> Result<(), SonicError> {
+        if let Some(table) = object.array_mut() {
+            return match key {
+                Key::Index(idx) => table.insert_at_index(idx as u64, value),
+                Key::Name(name) => table.insert(name, value),
+            }
+            .map_err(|e| SonicError::TypeError(format!("Failed to insert object key: {}", e)));
+        }
+        let (Some(object), Key::Name(name)) = (object.object_mut(), key) else {
+            return Err(SonicError::TypeError(
+                "Failed to set object property: not an object".to_string(),
+            ));
+        };
+        object
+            .set_property(name, value)
+            .map_err(|e| SonicError::TypeError(format!("Failed to set object property: {}", e)))
+    }
+
+    fn nested<'v>(&self, object: &'v mut Zval, key: &Key<'_>) -> Option<&'v mut Zval> {
+        let table = object.array_mut()?;
+        match *key {
+            Key::Index(idx) => table.get_index_mut(idx as u64),
+            Key::Name(name) => table.get_mut(name),
+        }
+        .filter(|zval| zval.is_array())
+    }
+
+    fn rename_key(&mut self, index: usize, key: &str) -> Result<String, SonicError> {
+        keys::call(index, key)
+    }
+}
 
 /// Converts a sonic_rs LazyValue to a PHP Zval with depth tracking.
 /// LazyValue wraps unparsed JSON - primitives are extracted directly,
 /// arrays/objects use lazy iteration to avoid full parsing upfront.
-fn lazyvalue_to_zval(lazy: LazyValue) -> Result<Zval, SonicError> {
-    lazyvalue_to_zval_with_depth(lazy, 0)
+/// Lossy conversions are published for `Sift::lastWarnings()` when enabled.
+pub fn lazyvalue_to_zval(lazy: LazyValue, options: &DecodeOptions) -> Result<Zval, SonicError> {
+    convert::convert(lazy, options, &mut ZvalSink { options })
 }
 
-/// Internal: converts LazyValue to Zval with depth tracking to prevent stack overflow.
-fn lazyvalue_to_zval_with_depth(lazy: LazyValue, depth: usize) -> Result<Zval, SonicError> {
-    if depth > MAX_DEPTH {
-        return Err(SonicError::ParseError(format!(
-            "Maximum nesting depth ({}) exceeded",
-            MAX_DEPTH
-        )));
-    }
+/// Converts an array of objects into one list per requested key,
+/// `[name => [row0, row1, ...]]`, in a single pass. Rows missing a key get
+/// null so the lists stay parallel; other members are skipped unconverted.
+/// `names` must be distinct; `pointer` locates the array for errors.
+pub fn columns(
+    lazy: LazyValue,
+    names: &[String],
+    pointer: &str,
+    options: &DecodeOptions,
+) -> Result<Zval, SonicError> {
+    arena::with_arena(|bump| {
+        let mut diagnostics = Diagnostics::new(options.warnings, options.needs_pointers(), bump);
+        let mut sink = ZvalSink { options };
+        let mut lists: Vec<ZBox<ZendHashTable>> =
+            names.iter().map(|_| ZendHashTable::new()).collect();
+        let mut row: Vec<Option<Zval>> = Vec::with_capacity(names.len());
 
-    let mut zval = Zval::new();
+        // SAFETY: the caller has verified this is an array via is_array()
+        for (idx, item) in unsafe { to_array_iter_unchecked(lazy.as_raw_str()) }.enumerate() {
+            let item = item.map_err(|e| iteration_error(e, lazy.as_raw_str(), options))?;
+            if !item.is_object() {
+                return Err(SonicError::TypeError(format!("Element {} is not an object", idx))
+                    .at_pointer(format!("{}/{}", pointer, idx))
+                    .with_types("object", type_name(&item)));
+            }
 
-    if lazy.is_null() {
-        zval.set_null();
-    } else if lazy.is_boolean() {
-        let b = lazy.as_bool().unwrap();
-        b.set_zval(&mut zval, false)
-            .map_err(|e| SonicError::TypeError(e.to_string()))?;
-    } else if lazy.is_i64() {
-        let n = lazy.as_i64().unwrap();
-        n.set_zval(&mut zval, false)
-            .map_err(|e| SonicError::TypeError(e.to_string()))?;
-    } else if lazy.is_u64() {
-        let n = lazy.as_u64().unwrap();
-        // Check if value fits in i64 to prevent silent overflow
-        if n <= i64::MAX as u64 {
-            (n as i64)
-                .set_zval(&mut zval, false)
-                .map_err(|e| SonicError::TypeError(e.to_string()))?;
-        } else {
-            // Value too large for i64, convert to float to preserve precision
-            (n as f64)
-                .set_zval(&mut zval, false)
-                .map_err(|e| SonicError::TypeError(e.to_string()))?;
+            row.clear();
+            row.resize_with(names.len(), || None);
+            diagnostics.push_index(idx);
+            // SAFETY: we've verified this is an object via is_object()
+            for entry in unsafe { to_object_iter_unchecked(item.as_raw_str()) } {
+                let (key, val) = entry.map_err(|e| iteration_error(e, item.as_raw_str(), options))?;
+                let Some(col) = names.iter().position(|name| *name == *key) else {
+                    continue;
+                };
+                if row[col].is_some() && skip_duplicate(options)? {
+                    continue;
+                }
+                diagnostics.push_key(&key);
+                let value = convert::convert_at(val, 2, options, &mut sink, &mut diagnostics)?;
+                diagnostics.pop();
+                row[col] = Some(value);
+            }
+            diagnostics.pop();
+
+            for (list, value) in lists.iter_mut().zip(row.drain(..)) {
+                list.push(value.unwrap_or_default()).map_err(|e| {
+                    SonicError::TypeError(format!("Failed to push array item: {}", e))
+                })?;
+            }
         }
-    } else if lazy.is_f64() {
-        let n = lazy.as_f64().unwrap();
-        n.set_zval(&mut zval, false)
-            .map_err(|e| SonicError::TypeError(e.to_string()))?;
-    } else if lazy.is_str() {
-        let s = lazy.as_str().unwrap();
-        s.set_zval(&mut zval, false)
-            .map_err(|e| SonicError::TypeError(e.to_string()))?;
-    } else if lazy.is_array() {
-        // Use lazy iteration - parses elements on-demand
-        let mut php_arr = ext_php_rs::types::ZendHashTable::new();
-        // SAFETY: we've verified this is an array via is_array()
-        for item in unsafe { to_array_iter_unchecked(lazy.as_raw_str()) } {
-            let item = item.map_err(|e| SonicError::ParseError(e.to_string()))?;
-            let item_zval = lazyvalue_to_zval_with_depth(item, depth + 1)?;
-            php_arr.push(item_zval).map_err(|e| {
-                SonicError::TypeError(format!("Failed to push array item: {}", e))
+
+        let mut result = ZendHashTable::new();
+        for (name, list) in names.iter().zip(lists) {
+            result.insert(name.as_str(), list).map_err(|e| {
+                SonicError::TypeError(format!("Failed to insert object key: {}", e))
             })?;
         }
-        php_arr
+        diagnostics.publish();
+
+        let mut zval = Zval::new();
+        result
             .set_zval(&mut zval, false)
             .map_err(|e| SonicError::TypeError(e.to_string()))?;
-    } else if lazy.is_object() {
-        // Use lazy iteration - parses key/value pairs on-demand
-        let mut php_arr = ext_php_rs::types::ZendHashTable::new();
-        // SAFETY: we've verified this is an object via is_object()
-        for entry in unsafe { to_object_iter_unchecked(lazy.as_raw_str()) } {
-            let (key, val) = entry.map_err(|e| SonicError::ParseError(e.to_string()))?;
-            let val_zval = lazyvalue_to_zval_with_depth(val, depth + 1)?;
-            php_arr.insert(&*key, val_zval).map_err(|e| {
-                SonicError::TypeError(format!("Failed to insert object key: {}", e))
+        Ok(zval)
+    })
+}
+
+/// Packs a 2D array of numbers into `["data" => string, "rows" => int,
+/// "cols" => int]`, where `data` holds the values row-major as doubles in
+/// machine byte order (the layout of `pack('d*', ...)`). No Zval is created
+/// per element. Every row must have the same length; `pointer` locates the
+/// array for errors.
+pub fn float_matrix(lazy: LazyValue, pointer: &str) -> Result<Zval, SonicError> {
+    let mut data: Vec<u8> = Vec::new();
+    let mut rows: usize = 0;
+    let mut cols: Option<usize> = None;
+
+    // SAFETY: the caller has verified this is an array via is_array()
+    for (i, row) in unsafe { to_array_iter_unchecked(lazy.as_raw_str()) }.enumerate() {
+        let row = row?;
+        if !row.is_array() {
+            return Err(SonicError::TypeError(format!("Row {} is not an array", i))
+                .at_pointer(format!("{}/{}", pointer, i))
+                .with_types("array", type_name(&row)));
+        }
+
+        let mut len: usize = 0;
+        // SAFETY: we've verified this is an array via is_array()
+        for (j, item) in unsafe { to_array_iter_unchecked(row.as_raw_str()) }.enumerate() {
+            let item = item?;
+            let n = item.as_f64().ok_or_else(|| {
+                SonicError::TypeError(format!("Element [{}][{}] is not a number", i, j))
+                    .at_pointer(format!("{}/{}/{}", pointer, i, j))
+                    .with_types("float", type_name(&item))
             })?;
+            data.extend_from_slice(&n.to_ne_bytes());
+            len += 1;
         }
-        php_arr
-            .set_zval(&mut zval, false)
-            .map_err(|e| SonicError::TypeError(e.to_string()))?;
-    } else {
-        return Err(SonicError::TypeError("Unknown JSON value type".to_string()));
+
+        match cols {
+            None => cols = Some(len),
+            Some(expected) if expected != len => {
+                return Err(SonicError::TypeError(format!(
+                    "Row {} has {} elements, expected {}",
+                    i, len, expected
+                ))
+                .at_pointer(format!("{}/{}", pointer, i)));
+            }
+            Some(_) => {}
+        }
+        rows += 1;
     }
 
+    let mut buffer = Zval::new();
+    buffer.set_zend_string(ZendStr::new(data, false));
+    let mut result = ZendHashTable::new();
+    result
+        .insert("data", buffer)
+        .and_then(|_| result.insert("rows", rows as i64))
+        .and_then(|_| result.insert("cols", cols.unwrap_or(0) as i64))
+        .map_err(|e| SonicError::TypeError(format!("Failed to insert object key: {}", e)))?;
+
+    let mut zval = Zval::new();
+    result
+        .set_zval(&mut zval, false)
+        .map_err(|e| SonicError::TypeError(e.to_string()))?;
     Ok(zval)
 }
 
+/// A decoded string on its way back to PHP, copied once into its
+/// zend_string. Hydrators returning one instead of a `String` skip the
+/// Rust-side copy (and its allocation) that would be made only to be
+/// copied again into a zend_string, which dominates for multi-megabyte
+/// values.
+///
+/// The copy into the zend_string itself can't be avoided: its bytes live
+/// inline after the header and are freed by the Zend allocator, and PHP
+/// has no string type that borrows foreign memory.
+pub struct PhpString(ZBox<ZendStr>);
+
+impl PhpString {
+    pub fn new(text: &str) -> Self {
+        Self(ZendStr::new(text, false))
+    }
+}
+
+impl IntoZval for PhpString {
+    const TYPE: DataType = DataType::String;
+
+    fn set_zval(self, zv: &mut Zval, _persistent: bool) -> ext_php_rs::error::Result<()> {
+        zv.set_zend_string(self.0);
+        Ok(())
+    }
+}
+
+/// Allocates a hashtable sized for `len` elements, so filling a large
+/// container doesn't rehash it repeatedly as it grows.
+fn hashtable_with_capacity(len: usize) -> ZBox<ZendHashTable> {
+    ZendHashTable::with_capacity(u32::try_from(len).unwrap_or(u32::MAX))
+}
+
 /// Converts a sonic_rs Value to a PHP Zval with depth tracking.
 pub fn value_to_zval(value: &Value) -> Result<Zval, SonicError> {
-    value_to_zval_with_depth(value, 0)
+    convert::from_dom(value, &mut ZvalSink { options: &DecodeOptions::default() })
 }
 
-/// Internal: converts Value to Zval with depth tracking to prevent stack overflow.
-fn value_to_zval_with_depth(value: &Value, depth: usize) -> Result<Zval, SonicError> {
-    if depth > MAX_DEPTH {
-        return Err(SonicError::ParseError(format!(
-            "Maximum nesting depth ({}) exceeded",
-            MAX_DEPTH
-        )));
+/// Converts a DOM value under `options`. Options only the lazy conversion
+/// implements (`DecodeOptions::needs_lazy`) convert the value's JSON text
+/// instead. The DOM kept its numbers as 64-bit integers or doubles, not
+/// as written, so options that read the number text are rejected rather
+/// than applied to a re-serialized copy.
+pub fn dom_to_zval(value: &Value, options: &DecodeOptions) -> Result<Zval, SonicError> {
+    if options.needs_raw_numbers() {
+        return Err(SonicError::InvalidOption(
+            "Options 'strict', 'warnings', 'big_numbers', 'bigint' and 'numbers' need the \
+             original number text, which a Snapshot doesn't keep (Sift::configure() defaults \
+             included); use Sift::decode() or Sift::get() on the JSON instead"
+                .to_string(),
+        ));
     }
+    if options.needs_lazy() {
+        return decode_lazy(&sonic_rs::to_string(value)?, options);
+    }
+    convert::from_dom(value, &mut ZvalSink { options })
+}
 
-    let mut zval = Zval::new();
+/// Full JSON decode - parses entire JSON string into PHP value.
+pub fn decode(json: &str, options: &DecodeOptions) -> Result<Zval, SonicError> {
+    convert::decode(json, options, &mut ZvalSink { options })
+}
 
-    if value.is_null() {
-        zval.set_null();
-    } else if value.is_boolean() {
-        let b = value.as_bool().unwrap();
-        b.set_zval(&mut zval, false)
-            .map_err(|e| SonicError::TypeError(e.to_string()))?;
-    } else if value.is_i64() {
-        let n = value.as_i64().unwrap();
-        n.set_zval(&mut zval, false)
-            .map_err(|e| SonicError::TypeError(e.to_string()))?;
-    } else if value.is_u64() {
-        let n = value.as_u64().unwrap();
-        // Check if value fits in i64 to prevent silent overflow
-        if n <= i64::MAX as u64 {
-            (n as i64)
-                .set_zval(&mut zval, false)
-                .map_err(|e| SonicError::TypeError(e.to_string()))?;
-        } else {
-            // Value too large for i64, convert to float to preserve precision
-            (n as f64)
-                .set_zval(&mut zval, false)
-                .map_err(|e| SonicError::TypeError(e.to_string()))?;
-        }
-    } else if value.is_f64() {
-        let n = value.as_f64().unwrap();
-        n.set_zval(&mut zval, false)
-            .map_err(|e| SonicError::TypeError(e.to_string()))?;
-    } else if value.is_str() {
-        let s = value.as_str().unwrap();
-        s.set_zval(&mut zval, false)
-            .map_err(|e| SonicError::TypeError(e.to_string()))?;
-    } else if value.is_array() {
-        let arr = value.as_array().unwrap();
-        let mut php_arr = ext_php_rs::types::ZendHashTable::new();
-        for item in arr.iter() {
-            let item_zval = value_to_zval_with_depth(item, depth + 1)?;
-            php_arr.push(item_zval).map_err(|e| {
-                SonicError::TypeError(format!("Failed to push array item: {}", e))
-            })?;
+/// Full decode through the lazy iterator instead of a DOM; see
+/// `sift_core::convert::decode_lazy`.
+pub fn decode_lazy(json: &str, options: &DecodeOptions) -> Result<Zval, SonicError> {
+    convert::decode_lazy(json, options, &mut ZvalSink { options })
+}
+
+/// Lazy get - extracts a value by JSON pointer WITHOUT full decode.
+/// Uses sonic_rs::get() which uses SIMD to skip irrelevant content.
+/// Pointer format: "/users/0/email" (RFC 6901)
+pub fn get_by_pointer(json: &str, pointer: &str, options: &DecodeOptions) -> Result<Zval, SonicError> {
+    // Validate input size to prevent DoS
+    check_input_size(json)?;
+
+    // Empty pointer means return the whole document, through the same lazy
+    // conversion as non-empty pointers
+    if pointer.is_empty() {
+        return decode_lazy(json, options);
+    }
+
+    let lazy_value = lazy_at(json, pointer)?;
+    lazyvalue_to_zval(lazy_value, options)
+}
+
+/// Key names of the object at `pointer` in document order, or the indices
+/// of the array there. Members are iterated and skipped; none of their
+/// values is converted.
+pub fn keys_at(json: &str, pointer: &str) -> Result<Zval, SonicError> {
+    // Validate input size to prevent DoS
+    check_input_size(json)?;
+
+    let lazy = lazy_at(json, pointer)?;
+    let mut keys = ZendHashTable::new();
+    if lazy.is_object() {
+        // SAFETY: we've verified this is an object via is_object()
+        for entry in unsafe { to_object_iter_unchecked(lazy.as_raw_str()) } {
+            let (key, _) = entry.map_err(SonicError::from)?;
+            keys.push(&*key)
+                .map_err(|e| SonicError::TypeError(format!("Failed to push key: {}", e)))?;
         }
-        php_arr
-            .set_zval(&mut zval, false)
-            .map_err(|e| SonicError::TypeError(e.to_string()))?;
-    } else if value.is_object() {
-        let obj = value.as_object().unwrap();
-        let mut php_arr = ext_php_rs::types::ZendHashTable::new();
-        for (key, val) in obj.iter() {
-            let val_zval = value_to_zval_with_depth(val, depth + 1)?;
-            php_arr.insert(key, val_zval).map_err(|e| {
-                SonicError::TypeError(format!("Failed to insert object key: {}", e))
-            })?;
+    } else if lazy.is_array() {
+        // SAFETY: we've verified this is an array via is_array()
+        for (idx, item) in unsafe { to_array_iter_unchecked(lazy.as_raw_str()) }.enumerate() {
+            item.map_err(SonicError::from)?;
+            keys.push(idx as i64)
+                .map_err(|e| SonicError::TypeError(format!("Failed to push index: {}", e)))?;
         }
-        php_arr
-            .set_zval(&mut zval, false)
-            .map_err(|e| SonicError::TypeError(e.to_string()))?;
     } else {
-        return Err(SonicError::TypeError("Unknown JSON value type".to_string()));
+        return Err(not_a_container(&lazy, pointer));
     }
 
+    let mut zval = Zval::new();
+    keys.set_zval(&mut zval, false)
+        .map_err(|e| SonicError::TypeError(e.to_string()))?;
     Ok(zval)
 }
 
-/// Full JSON decode - parses entire JSON string into PHP value.
-pub fn decode(json: &str) -> Result<Zval, SonicError> {
+/// Number of members of the object, or elements of the array, at
+/// `pointer`. They are counted by skipping over them with the lazy
+/// iterators; nothing is converted.
+pub fn count_at(json: &str, pointer: &str) -> Result<usize, SonicError> {
     // Validate input size to prevent DoS
-    if json.len() > MAX_INPUT_SIZE {
-        return Err(SonicError::ParseError(format!(
-            "Input size ({} bytes) exceeds maximum allowed ({} bytes)",
-            json.len(),
-            MAX_INPUT_SIZE
-        )));
-    }
+    check_input_size(json)?;
 
-    let value: Value = sonic_rs::from_str(json)?;
-    value_to_zval(&value)
+    let lazy = lazy_at(json, pointer)?;
+    element_count(&lazy)?.ok_or_else(|| not_a_container(&lazy, pointer))
 }
 
-/// Lazy get - extracts a value by JSON pointer WITHOUT full decode.
-/// Uses sonic_rs::get() which uses SIMD to skip irrelevant content.
-/// Pointer format: "/users/0/email" (RFC 6901)
-pub fn get_by_pointer(json: &str, pointer: &str) -> Result<Zval, SonicError> {
-    // Validate input size to prevent DoS
-    if json.len() > MAX_INPUT_SIZE {
-        return Err(SonicError::ParseError(format!(
-            "Input size ({} bytes) exceeds maximum allowed ({} bytes)",
-            json.len(),
-            MAX_INPUT_SIZE
-        )));
-    }
+/// The error for `keys_at()`/`count_at()` on a scalar.
+fn not_a_container(lazy: &LazyValue, pointer: &str) -> SonicError {
+    SonicError::TypeError("Value is not an object or array".to_string())
+        .at_pointer(pointer)
+        .with_types("object", type_name(lazy))
+}
 
-    // Validate pointer format
-    if !pointer.is_empty() && !pointer.starts_with('/') {
-        return Err(SonicError::InvalidPointer(
-            "Pointer must start with '/' or be empty".to_string()
-        ));
-    }
+/// Rejects inputs over `MAX_INPUT_SIZE` before any parsing work.
+pub fn check_input_size(json: impl AsRef<[u8]>) -> Result<(), SonicError> {
+    Ok(core::check_input_size(json)?)
+}
 
-    // Empty pointer means return the whole document
-    if pointer.is_empty() {
-        let value: Value = sonic_rs::from_str(json)?;
-        return value_to_zval(&value);
-    }
+/// Reads the bytes of a PHP string as JSON text; see `core::input_text`.
+/// Being the first decode step of a call, it drops the warnings of
+/// earlier calls, then records those about its repairs.
+pub fn input_text<'a>(bytes: &'a [u8], options: &DecodeOptions) -> Result<Cow<'a, str>, SonicError> {
+    diagnostics::start_decode();
+    Ok(core::input_text(bytes, options)?.publish())
+}
 
-    // Parse RFC 6901 pointer into path segments with owned strings
-    let segments: Vec<String> = pointer[1..]
-        .split('/')
-        .map(|part| part.replace("~1", "/").replace("~0", "~"))
-        .collect();
-
-    // Validate pointer segment count to prevent DoS
-    if segments.len() > MAX_POINTER_SEGMENTS {
-        return Err(SonicError::InvalidPointer(format!(
-            "Pointer has too many segments ({}, max {})",
-            segments.len(),
-            MAX_POINTER_SEGMENTS
-        )));
-    }
+/// Like `input_text`, keeping `'surrogates' => 'wtf8'` stand-ins for the
+/// lazy conversion; see `core::decode_text`.
+pub fn decode_text<'a>(
+    bytes: &'a [u8],
+    options: &DecodeOptions,
+) -> Result<Cow<'a, str>, SonicError> {
+    diagnostics::start_decode();
+    Ok(core::decode_text(bytes, options)?.publish())
+}
 
-    // Build pointer nodes - need to determine if each segment is an index or key
-    // Use owned FastStr to avoid lifetime issues
-    let nodes: Vec<PointerNode> = segments
-        .into_iter()
-        .map(|seg| {
-            if let Ok(idx) = seg.parse::<usize>() {
-                PointerNode::Index(idx)
-            } else {
-                PointerNode::Key(FastStr::new(seg))
-            }
-        })
-        .collect();
+/// Applies the `control_chars` option to a decoded string.
+pub fn control_chars<'s>(
+    text: &'s str,
+    options: &DecodeOptions,
+) -> Result<Cow<'s, str>, SonicError> {
+    Ok(core::control_chars(text, options)?)
+}
 
-    // Use sonic_rs::get for true lazy extraction (SIMD-accelerated skip)
-    let lazy_value = sonic_rs::get(json, nodes.as_slice()).map_err(|_| {
-        SonicError::KeyNotFound("Path not found".to_string())
-    })?;
+/// Parses an RFC 6901 pointer into sonic-rs pointer nodes in `bump`.
+pub fn pointer_nodes<'b>(
+    pointer: &str,
+    bump: &'b Bump,
+) -> Result<BumpVec<'b, PointerNode>, SonicError> {
+    Ok(core::pointer_nodes(pointer, bump)?)
+}
 
-    lazyvalue_to_zval(lazy_value)
+/// Parses a whole document into a sonic-rs DOM, enforcing the input size limit.
+pub fn parse_dom(json: &str) -> Result<Value, SonicError> {
+    Ok(core::parse_dom(json)?)
 }
 
-/// Validate JSON syntax.
-/// Note: This currently does a full parse. For very large inputs,
-/// consider checking size first in the calling code.
-pub fn is_valid(json: &str) -> bool {
-    // Reject oversized inputs to prevent DoS
-    if json.len() > MAX_INPUT_SIZE {
-        return false;
-    }
-    // TODO: sonic-rs doesn't have a dedicated validation-only function,
-    // so we have to do a full parse. Consider using a streaming validator
-    // for better performance on large inputs.
-    sonic_rs::from_str::<Value>(json).is_ok()
+/// Resolves `pointer` to the unparsed value there, without converting
+/// anything. The empty pointer is the root.
+pub fn lazy_at<'a>(json: &'a str, pointer: &str) -> Result<LazyValue<'a>, SonicError> {
+    Ok(core::lazy_at(json, pointer)?)
 }
 
-// Note: Rust unit tests are limited because ext-php-rs types (Zval) require
-// PHP to be linked. The comprehensive test suite is in tests/php/SonicTest.php
-// and tests/php/SiftTest.php which test all functionality through the PHP extension.
+/// Number of elements of an array or members of an object, counted by
+/// skipping over them without conversion. None for other values.
+pub fn element_count(lazy: &LazyValue) -> Result<Option<usize>, SonicError> {
+    Ok(core::element_count(lazy)?)
+}
+
+/// Returns the value of the first pointer in `pointers` that exists,
+/// or `None` if none do.
+pub fn first_of<'a>(json: &'a str, pointers: &[String]) -> Result<Option<LazyValue<'a>>, SonicError> {
+    Ok(core::first_of(json, pointers)?)
+}
+
+/// The string a JSON string literal stands for, with its escapes decoded.
+pub fn unescape_string(literal: &str, quoted: bool) -> Result<String, SonicError> {
+    Ok(core::unescape_string(literal, quoted)?)
+}
+
+/// Like `is_valid`, but input the limits reject is an error rather than
+/// `false`.
+pub fn is_valid_strict(json: &str) -> Result<bool, SonicError> {
+    Ok(core::is_valid_strict(json)?)
+}
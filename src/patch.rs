@@ -0,0 +1,41 @@
+//! Byte-preserving value replacement for `Sift::patch()`.
+//!
+//! Usage:
+//! ```php
+//! $patched = Sift::patch($signedJson, ['/status' => 'paid', '/items/0/qty' => 3]);
+//! // Every byte outside the two replaced values is copied from the input
+//! ```
+
+use crate::encoder;
+use crate::errors::SonicError;
+use ext_php_rs::types::{ArrayKey, ZendHashTable, Zval};
+
+pub use sift_core::patch::replace_at;
+
+/// Replace the value at each pointer of `changes` (`pointer => new value`)
+/// with the new value encoded as JSON. Everything else, including
+/// whitespace, key order and number spelling, is copied verbatim.
+/// Only existing values can be replaced, and the changed values must not
+/// contain one another.
+pub fn patch(json: &str, changes: &ZendHashTable) -> Result<String, SonicError> {
+    let changes: Vec<(String, &Zval)> = changes
+        .iter()
+        .map(|(key, value)| match key {
+            ArrayKey::String(pointer) => Ok((pointer, value)),
+            ArrayKey::Long(index) => Err(SonicError::InvalidPointer(format!(
+                "Change keys must be JSON pointers, got {}",
+                index
+            ))),
+        })
+        .collect::<Result<_, _>>()?;
+    let pointers: Vec<&str> = changes
+        .iter()
+        .map(|(pointer, _)| pointer.as_str())
+        .collect();
+
+    replace_at(json, &pointers, |i, _| {
+        let mut text = String::new();
+        encoder::encode_into(&mut text, changes[i].1)?;
+        Ok(text)
+    })
+}
@@ -0,0 +1,163 @@
+//! Many small documents in one buffer for `Sift::pool()`.
+//!
+//! Usage:
+//! ```php
+//! $pool = Sift::pool();
+//! foreach ($webhooks as $body) {
+//!     $ids[] = $pool->addDocument($body);
+//! }
+//! foreach ($ids as $id) {
+//!     dispatch($pool->get($id, '/event/type'), $pool->get($id, '/data'));
+//! }
+//! ```
+
+use crate::errors::{ErrorCode, SonicError};
+use crate::guard::guard;
+use crate::handles::Generation;
+use crate::leaks::Live;
+use crate::memory;
+use crate::options::DecodeOptions;
+use crate::parser::{self, MAX_INPUT_SIZE};
+use ext_php_rs::binary_slice::BinarySlice;
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendHashTable, Zval};
+
+/// Pool - validated JSON documents appended to a single buffer.
+/// A document costs one append instead of its own string allocation, and
+/// `clear()` keeps the buffer's capacity for the next batch. Documents stay
+/// raw until read; only the values `get()` returns become PHP values.
+/// Ids are generation-checked handles (see `handles.rs`), so an id kept
+/// past `clear()` throws instead of reading a newer document.
+#[php_class(name = "Sift\\Pool")]
+#[derive(Debug)]
+pub struct Pool {
+    /// Every document, back to back
+    buffer: String,
+    /// Byte range of each document in `buffer`, indexed by slot
+    documents: Vec<(usize, usize)>,
+    /// Generation of the ids handed out since the last `clear()`
+    generation: Generation,
+    /// Conversion options for `get()`
+    options: DecodeOptions,
+    /// Counts this object for `sift.leak_check` (see `leaks.rs`)
+    live: Live,
+}
+
+impl Pool {
+    pub fn new(options: DecodeOptions) -> Self {
+        Self {
+            buffer: String::new(),
+            documents: Vec::new(),
+            generation: Generation::default(),
+            options,
+            live: Live::new("Sift\\Pool"),
+        }
+    }
+
+    /// The text of document `id`.
+    fn document(&self, id: i64) -> Result<&str, SonicError> {
+        let slot = self.generation.resolve(id, self.documents.len())?;
+        let (start, end) = self.documents[slot];
+        Ok(&self.buffer[start..end])
+    }
+}
+
+#[php_impl]
+impl Pool {
+    /// Validate `json` and append it to the pool, returning its id (0, 1,
+    /// 2, ... until the first `clear()`). The pool as a whole is held to
+    /// the input size limit.
+    ///
+    /// # Example
+    /// ```php
+    /// $id = $pool->addDocument($request->getContent());
+    /// ```
+    pub fn add_document(&mut self, json: BinarySlice<u8>) -> Result<i64, SonicError> {
+        guard(|| {
+            let text = parser::input_text(&json, &self.options)?;
+            let size = self.buffer.len() + text.len();
+            if size > MAX_INPUT_SIZE {
+                return Err(SonicError::InputTooLarge {
+                    size,
+                    limit: MAX_INPUT_SIZE,
+                });
+            }
+            memory::reserve(text.len())?;
+            sonic_rs::from_str::<sonic_rs::Value>(&text)?;
+            let start = self.buffer.len();
+            self.buffer.push_str(&text);
+            self.documents.push((start, self.buffer.len()));
+            Ok(self.generation.handle(self.documents.len() - 1))
+        })
+    }
+
+    /// Extract a value from document `id` by JSON pointer, converting only
+    /// that value. Throws for an unknown or stale id or a missing path.
+    ///
+    /// # Example
+    /// ```php
+    /// $type = $pool->get($id, '/event/type');
+    /// ```
+    pub fn get(&self, id: i64, pointer: &str) -> Result<Zval, SonicError> {
+        guard(|| {
+            let lazy = parser::lazy_at(self.document(id)?, pointer)?;
+            parser::lazyvalue_to_zval(lazy, &self.options)
+        })
+    }
+
+    /// Check whether document `id` has a value at `pointer`, without
+    /// converting anything. Throws for an unknown or stale id.
+    pub fn has(&self, id: i64, pointer: &str) -> Result<bool, SonicError> {
+        guard(|| match parser::lazy_at(self.document(id)?, pointer) {
+            Ok(_) => Ok(true),
+            Err(e) if e.code() == ErrorCode::KeyNotFound as i32 => Ok(false),
+            Err(e) => Err(e),
+        })
+    }
+
+    /// Extract the same pointer from every document, as `[id => value]`;
+    /// documents without it are left out.
+    ///
+    /// # Example
+    /// ```php
+    /// $types = $pool->getAll('/event/type');
+    /// ```
+    pub fn get_all(&self, pointer: &str) -> Result<Zval, SonicError> {
+        guard(|| {
+            let mut values = ZendHashTable::with_capacity(self.documents.len() as u32);
+            for slot in 0..self.documents.len() {
+                let id = self.generation.handle(slot);
+                let lazy = match parser::lazy_at(self.document(id)?, pointer) {
+                    Ok(lazy) => lazy,
+                    Err(e) if e.code() == ErrorCode::KeyNotFound as i32 => continue,
+                    Err(e) => return Err(e),
+                };
+                let value = parser::lazyvalue_to_zval(lazy, &self.options)?;
+                values.insert_at_index(id as u64, value).map_err(|e| {
+                    SonicError::TypeError(format!("Failed to insert object key: {}", e))
+                })?;
+            }
+            let mut zval = Zval::new();
+            zval.set_hashtable(values);
+            Ok(zval)
+        })
+    }
+
+    /// Number of documents in the pool.
+    pub fn count(&self) -> i64 {
+        self.documents.len() as i64
+    }
+
+    /// Bytes of JSON held by the pool.
+    pub fn size(&self) -> i64 {
+        self.buffer.len() as i64
+    }
+
+    /// Remove every document, keeping the buffer's capacity for reuse.
+    /// Ids handed out so far become stale.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.documents.clear();
+        self.generation.advance();
+    }
+}
@@ -0,0 +1,67 @@
+//! Reading the `numbers` option from PHP; matching and rounding are
+//! `sift_core::precision`.
+//!
+//! Usage:
+//! ```php
+//! $order = Sift::decode($json, ['numbers' => [
+//!     '/amounts/*' => 'string', // "19.90" stays "19.90"
+//!     '/rate'      => 4,        // 0.123456789 becomes 0.1235
+//! ]]);
+//! ```
+
+use crate::errors::SonicError;
+use ext_php_rs::types::{ArrayKey, Zval};
+use sift_core::precision::{Pattern, MAX_PLACES};
+
+pub use sift_core::precision::{reset_patterns, NumberPolicies, NumberPolicy};
+
+/// Read the `numbers` option: an array of pointer pattern => 'string',
+/// 'float' or a number of decimal places.
+pub fn from_zval(name: &str, value: &Zval) -> Result<NumberPolicies, SonicError> {
+    let Some(entries) = value.array() else {
+        return Err(SonicError::InvalidOption(format!(
+            "Option '{}' must be an array of pointer => policy",
+            name
+        )));
+    };
+    let mut patterns = Vec::with_capacity(entries.len());
+    for (key, policy) in entries.iter() {
+        let pointer = match key {
+            ArrayKey::String(pointer) if pointer.is_empty() || pointer.starts_with('/') => pointer,
+            ArrayKey::String(pointer) => {
+                return Err(SonicError::InvalidOption(format!(
+                    "Option '{}' keys must be JSON pointers, got '{}'",
+                    name, pointer
+                )))
+            }
+            ArrayKey::Long(index) => {
+                return Err(SonicError::InvalidOption(format!(
+                    "Option '{}' keys must be JSON pointers, got {}",
+                    name, index
+                )))
+            }
+        };
+        let policy = policy_from_zval(policy).ok_or_else(|| {
+            SonicError::InvalidOption(format!(
+                "Option '{}' policy for '{}' must be 'string', 'float' or 0 to {} decimal places",
+                name, pointer, MAX_PLACES
+            ))
+        })?;
+        patterns.push(Pattern::new(&pointer, policy));
+    }
+    Ok(NumberPolicies::from_patterns(patterns))
+}
+
+/// Read one policy: "string", "float" (`Some(None)`) or an integer
+/// number of places.
+fn policy_from_zval(value: &Zval) -> Option<Option<NumberPolicy>> {
+    match value.str() {
+        Some("string") => Some(Some(NumberPolicy::String)),
+        Some("float") => Some(None),
+        Some(_) => None,
+        None => value
+            .long()
+            .filter(|places| (0..=MAX_PLACES as i64).contains(places))
+            .map(|places| Some(NumberPolicy::Places(places as u32))),
+    }
+}
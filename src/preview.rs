@@ -0,0 +1,239 @@
+//! One-line human-readable previews of JSON for `Sift::preview()`.
+//!
+//! Usage:
+//! ```php
+//! $logger->debug('Webhook received: ' . Sift::preview($body));
+//! // {id: 42, type: "invoice.paid", data: {object: {… 31 keys}}, tags: ["a", "b", … 8 more]}
+//! ```
+
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::parser::{self, MAX_DEPTH};
+use ext_php_rs::types::{ArrayKey, ZendHashTable, Zval};
+use sonic_rs::{to_array_iter_unchecked, to_object_iter_unchecked};
+use sonic_rs::{JsonValueTrait, LazyValue, PointerNode};
+
+/// Limits on how much of the document a preview shows.
+#[derive(Clone, Copy, Debug)]
+struct PreviewOptions {
+    /// Container levels shown; deeper arrays/objects only show their length
+    depth: usize,
+    /// Elements shown per array, members per object
+    items: usize,
+    /// Characters shown per string or key
+    string: usize,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            depth: 2,
+            items: 5,
+            string: 40,
+        }
+    }
+}
+
+impl PreviewOptions {
+    /// Parse an options array such as `['depth' => 3, 'items' => 10]`.
+    fn from_array(options: Option<&ZendHashTable>) -> Result<Self, SonicError> {
+        let mut opts = Self::default();
+        let Some(options) = options else {
+            return Ok(opts);
+        };
+
+        for (key, value) in options.iter() {
+            let name = match key {
+                ArrayKey::String(name) => name,
+                ArrayKey::Long(idx) => {
+                    return Err(SonicError::InvalidOption(format!(
+                        "Option keys must be strings, got {}",
+                        idx
+                    )))
+                }
+            };
+            match name.as_str() {
+                // Deeper levels than the parser allows can't occur
+                "depth" => opts.depth = option_limit(&name, value, 0)?.min(MAX_DEPTH),
+                "items" => opts.items = option_limit(&name, value, 0)?,
+                "string" => opts.string = option_limit(&name, value, 1)?,
+                _ => {
+                    return Err(SonicError::InvalidOption(format!(
+                        "Unknown option '{}'",
+                        name
+                    )))
+                }
+            }
+        }
+        Ok(opts)
+    }
+}
+
+/// Read an integer option of at least `min`.
+fn option_limit(name: &str, value: &Zval, min: usize) -> Result<usize, SonicError> {
+    value
+        .long()
+        .and_then(|n| usize::try_from(n).ok())
+        .filter(|n| *n >= min)
+        .ok_or_else(|| {
+            SonicError::InvalidOption(format!(
+                "Option '{}' must be an integer of at least {}",
+                name, min
+            ))
+        })
+}
+
+/// A one-line preview of `json`: keys and scalars as they are, strings
+/// cut to `string` characters, and at most `items` elements per array
+/// or object, followed by a count of the rest. Arrays and objects below
+/// `depth` levels show only their length. Nothing is hydrated.
+pub fn preview(json: &str, options: Option<&ZendHashTable>) -> Result<String, SonicError> {
+    let opts = PreviewOptions::from_array(options)?;
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+    // Values are previewed lazily, so the whole document is checked first
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+
+    let root = sonic_rs::get(json, &[] as &[PointerNode])?;
+    let mut out = String::new();
+    write_value(&mut out, &root, 0, &opts)?;
+    Ok(out)
+}
+
+/// Internal: append the preview of `value`, a container `depth` levels down.
+fn write_value(
+    out: &mut String,
+    value: &LazyValue,
+    depth: usize,
+    opts: &PreviewOptions,
+) -> Result<(), SonicError> {
+    if let Some(text) = value.as_str() {
+        write_text(out, text, opts)
+    } else if value.is_array() {
+        write_array(out, value, depth, opts)
+    } else if value.is_object() {
+        write_object(out, value, depth, opts)
+    } else {
+        // Numbers, booleans and null are shown as written
+        out.push_str(value.as_raw_str());
+        Ok(())
+    }
+}
+
+/// Write a string as JSON, cut to `opts.string` characters with "…" and
+/// its full length.
+fn write_text(out: &mut String, text: &str, opts: &PreviewOptions) -> Result<(), SonicError> {
+    match text.char_indices().nth(opts.string) {
+        None => encoder::write_string(out, text.as_bytes()),
+        Some((end, _)) => {
+            let mut cut = String::with_capacity(end + 3);
+            cut.push_str(&text[..end]);
+            cut.push('…');
+            encoder::write_string(out, cut.as_bytes())?;
+            out.push_str(&format!(" ({} chars)", text.chars().count()));
+            Ok(())
+        }
+    }
+}
+
+/// Write an object key: bare if it looks like an identifier, as a JSON
+/// string otherwise.
+fn write_key(out: &mut String, key: &str, opts: &PreviewOptions) -> Result<(), SonicError> {
+    let bare = key.chars().count() <= opts.string
+        && key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if bare {
+        out.push_str(key);
+        Ok(())
+    } else {
+        write_text(out, key, opts)
+    }
+}
+
+/// Write `… N noun` for the entries of a container that aren't shown,
+/// after the `shown` ones.
+fn write_rest(out: &mut String, shown: usize, left_out: usize, noun: &str) {
+    if left_out == 0 {
+        return;
+    }
+    if shown > 0 {
+        out.push_str(", ");
+    }
+    out.push_str(&format!("… {} {}", left_out, noun));
+}
+
+/// Write up to `opts.items` elements of an array, or below the depth
+/// limit just its length (`[… 12 items]`).
+fn write_array(
+    out: &mut String,
+    value: &LazyValue,
+    depth: usize,
+    opts: &PreviewOptions,
+) -> Result<(), SonicError> {
+    out.push('[');
+    // SAFETY: the caller has verified this is an array via is_array()
+    let items = unsafe { to_array_iter_unchecked(value.as_raw_str()) };
+    if depth >= opts.depth {
+        let count = parser::element_count(value)?.unwrap_or(0);
+        write_rest(out, 0, count, if count == 1 { "item" } else { "items" });
+        out.push(']');
+        return Ok(());
+    }
+
+    let (mut shown, mut left_out) = (0, 0);
+    for item in items {
+        let item = item?;
+        if shown == opts.items {
+            left_out += 1;
+            continue;
+        }
+        if shown > 0 {
+            out.push_str(", ");
+        }
+        write_value(out, &item, depth + 1, opts)?;
+        shown += 1;
+    }
+    write_rest(out, shown, left_out, "more");
+    out.push(']');
+    Ok(())
+}
+
+/// Write up to `opts.items` members of an object, or below the depth
+/// limit just its size (`{… 4 keys}`).
+fn write_object(
+    out: &mut String,
+    value: &LazyValue,
+    depth: usize,
+    opts: &PreviewOptions,
+) -> Result<(), SonicError> {
+    out.push('{');
+    // SAFETY: the caller has verified this is an object via is_object()
+    let members = unsafe { to_object_iter_unchecked(value.as_raw_str()) };
+    if depth >= opts.depth {
+        let count = parser::element_count(value)?.unwrap_or(0);
+        write_rest(out, 0, count, if count == 1 { "key" } else { "keys" });
+        out.push('}');
+        return Ok(());
+    }
+
+    let (mut shown, mut left_out) = (0, 0);
+    for entry in members {
+        let (key, item) = entry?;
+        if shown == opts.items {
+            left_out += 1;
+            continue;
+        }
+        if shown > 0 {
+            out.push_str(", ");
+        }
+        write_key(out, &key, opts)?;
+        out.push_str(": ");
+        write_value(out, &item, depth + 1, opts)?;
+        shown += 1;
+    }
+    write_rest(out, shown, left_out, "more");
+    out.push('}');
+    Ok(())
+}
@@ -8,85 +8,362 @@
 //! $email = $q->get("users")->index(5)->get("email")->string();
 //! ```
 
-use crate::errors::SonicError;
-use crate::parser;
+use crate::base64;
+use crate::boolish::BoolTable;
+use crate::chunks::Chunks;
+use crate::errors::{ErrorCode, SonicError};
+use crate::formats;
+use crate::geojson;
+use crate::guard::guard;
+use crate::leaks::Live;
+use crate::missing::MissingValue;
+use crate::options::{DecodeOptions, Leaves};
+use crate::parser::{self, PhpString};
+use crate::refs;
+use crate::sampling::{self, Rng};
+use crate::schema;
+use crate::spill;
+use ext_php_rs::binary::Binary;
+use ext_php_rs::binary_slice::BinarySlice;
+use ext_php_rs::convert::IntoZval;
 use ext_php_rs::prelude::*;
-use ext_php_rs::types::Zval;
+use ext_php_rs::types::{ZendHashTable, Zval};
+use ext_php_rs::zend::ce;
 use faststr::FastStr;
-use sonic_rs::{JsonValueTrait, PointerNode};
+use sift_core::query::{self as paths, segments_pointer, PathSegment, PrefixCache};
+use sonic_rs::{to_array_iter_unchecked, JsonType, JsonValueTrait, LazyValue, PointerNode};
+use std::borrow::Cow;
+use std::cell::{OnceCell, RefCell};
+use std::rc::Rc;
 use std::sync::Arc;
 
-/// Maximum allowed JSON input size (64 MB).
-const MAX_INPUT_SIZE: usize = 64 * 1024 * 1024;
-
 /// Maximum allowed path segments to prevent DoS.
 const MAX_PATH_SEGMENTS: usize = 256;
 
-/// A path segment for lazy path building.
-/// Uses FastStr for zero-copy key storage where possible.
-#[derive(Clone, Debug)]
-enum PathSegment {
-    Key(FastStr),
-    Index(usize),
-}
+/// Property names that read like hydration (`$q->price->value`), so they
+/// throw instead of navigating to a key of that name.
+const HYDRATION_NAMES: [&str; 6] = ["value", "string", "int", "float", "bool", "raw"];
+
+/// PHP name of `Query`.
+const CLASS: &str = "Sift\\Query";
 
 /// Query - a lazy JSON cursor that stays in Rust until hydration.
 /// Path segments are accumulated and only resolved on hydration.
 /// Uses Arc for zero-copy JSON sharing across navigations.
 #[php_class(name = "Sift\\Query")]
+#[implements(ce::arrayaccess())]
 #[derive(Clone, Debug)]
 pub struct Query {
     /// The original JSON string (shared via Arc for zero-copy)
     json: Arc<String>,
     /// Accumulated path segments (lazy - not resolved until hydration)
     path: Vec<PathSegment>,
+    /// Conversion options for hydration, shared by derived Queries
+    options: DecodeOptions,
+    /// Whether resolution follows internal `$ref`s (see `follow_refs()`)
+    follow_refs: bool,
+    /// Whether property and index reads navigate (see `property_access()`)
+    properties: bool,
+    /// Whether hydrating releases this Query's buffer (see `weak()`)
+    weak: bool,
+    /// Whether `release()` dropped the buffer; reads then throw
+    released: bool,
+    /// Fallback documents, tried in order when the path is missing from
+    /// `json` (see `with_defaults_from()`)
+    defaults: Vec<Arc<String>>,
+    /// The path's value and its layer once resolved, so further hydrations
+    /// on this Query don't scan the input again (see `resolve_layer()`)
+    resolved: OnceCell<(usize, LazyValue<'static>)>,
+    /// Containers resolved by this Query and its relatives (see
+    /// `resolve_in()`)
+    prefixes: Rc<RefCell<PrefixCache>>,
+    /// Counts this object for `sift.leak_check` (see `leaks.rs`)
+    live: Live,
 }
 
 impl Query {
     /// Create a new Query from a JSON string.
     /// Note: Input size is validated on hydration, not creation,
     /// to allow Query objects to be created without immediate validation.
-    pub fn new(json: String) -> Self {
+    /// Values are hydrated with `options`.
+    pub fn new(json: String, options: DecodeOptions) -> Self {
         Self {
             json: Arc::new(json),
             path: Vec::new(),
+            options,
+            follow_refs: false,
+            properties: false,
+            weak: false,
+            released: false,
+            defaults: Vec::new(),
+            resolved: OnceCell::new(),
+            prefixes: Rc::default(),
+            live: Live::new(CLASS),
         }
     }
 
+    /// The JSON text this Query navigates.
+    pub fn json(&self) -> &str {
+        self.json.as_str()
+    }
+
+    /// The text of layer `layer`: 0 is the document itself, 1.. its
+    /// defaults in order.
+    pub fn layer_json(&self, layer: usize) -> &str {
+        match layer {
+            0 => self.json.as_str(),
+            _ => self.defaults[layer - 1].as_str(),
+        }
+    }
+
+    /// The options values are hydrated with.
+    pub fn options(&self) -> &DecodeOptions {
+        &self.options
+    }
+
+    /// The layer holding the array at this path (see `layer_json()`), and
+    /// the byte offsets just past its opening '[' and of its closing ']'.
+    pub fn array_bounds(&self) -> Result<(usize, usize, usize), SonicError> {
+        let (layer, lazy) = self.resolve_layer()?;
+        if !lazy.is_array() {
+            return Err(self.type_error(&lazy, "array", "Value is not an array"));
+        }
+        // The resolved value's raw text is a slice of that layer's text
+        let json = self.layer_json(layer);
+        let raw = lazy.as_raw_str().trim_end();
+        let open = raw.as_ptr() as usize - json.as_ptr() as usize;
+        Ok((layer, open + 1, open + raw.len() - 1))
+    }
+
     /// Validate input size before processing.
     fn validate_input_size(&self) -> Result<(), SonicError> {
-        if self.json.len() > MAX_INPUT_SIZE {
-            return Err(SonicError::ParseError(format!(
-                "Input size ({} bytes) exceeds maximum allowed ({} bytes)",
-                self.json.len(),
-                MAX_INPUT_SIZE
-            )));
-        }
-        Ok(())
+        parser::check_input_size(self.json.as_str())
     }
 
     /// Internal: resolve the accumulated path
     fn resolve(&self) -> Result<sonic_rs::LazyValue<'_>, SonicError> {
+        self.resolve_layer().map(|(_, lazy)| lazy)
+    }
+
+    /// Internal: resolve the accumulated path, returning the layer it was
+    /// found in. The first success is memoized, so calling several
+    /// hydrators on one Query scans the input once; failures are not.
+    fn resolve_layer(&self) -> Result<(usize, LazyValue<'_>), SonicError> {
+        if let Some((layer, lazy)) = self.resolved.get() {
+            return Ok((*layer, lazy.clone()));
+        }
+        let (layer, lazy) = self.resolve_layers()?;
+        // SAFETY: `lazy` borrows the text of one of this Query's layers.
+        // Each is an Arc<String> that is never mutated, and every Query
+        // holding the memo (this one or a clone) holds that Arc, so the
+        // text outlives the memo.
+        let lazy = unsafe { std::mem::transmute::<LazyValue<'_>, LazyValue<'static>>(lazy) };
+        let _ = self.resolved.set((layer, lazy.clone()));
+        Ok((layer, lazy))
+    }
+
+    /// Internal: resolve the accumulated path in the document, then in each
+    /// default in turn while it is missing, returning the layer it was
+    /// found in. A path found nowhere reports the miss in the document.
+    fn resolve_layers(&self) -> Result<(usize, LazyValue<'_>), SonicError> {
+        if self.released {
+            return Err(
+                SonicError::StaleHandle("Query has been released".to_string())
+                    .at_pointer(self.pointer_string()),
+            );
+        }
         // Validate input size on resolution
         self.validate_input_size()?;
 
+        let mut layers = std::iter::once(&self.json)
+            .chain(&self.defaults)
+            .enumerate();
+        let (_, json) = layers.next().expect("the document is always a layer");
+        let miss = match self.resolve_in(0, json) {
+            Err(e) if e.code() == ErrorCode::KeyNotFound as i32 => e,
+            resolved => return resolved.map(|lazy| (0, lazy)),
+        };
+        for (layer, json) in layers {
+            match self.resolve_in(layer, json) {
+                Err(e) if e.code() == ErrorCode::KeyNotFound as i32 => continue,
+                resolved => return resolved.map(|lazy| (layer, lazy)),
+            }
+        }
+        Err(miss)
+    }
+
+    /// Internal: resolve the accumulated path in layer `layer`, whose text
+    /// is `json`
+    fn resolve_in<'a>(&self, layer: usize, json: &'a str) -> Result<LazyValue<'a>, SonicError> {
+        if self.follow_refs {
+            let segments = self.path.iter().map(PathSegment::text);
+            return refs::resolve(json, segments)?.ok_or_else(|| {
+                SonicError::KeyNotFound("Path not found".to_string())
+                    .at_pointer(self.pointer_string())
+            });
+        }
+
         if self.path.is_empty() {
-            return sonic_rs::get(self.json.as_str(), &[] as &[PointerNode])
-                .map_err(|e| SonicError::ParseError(e.to_string()));
+            return sonic_rs::get(json, &[] as &[PointerNode]).map_err(SonicError::from);
         }
 
-        // Build pointer nodes from accumulated path - FastStr clone is cheap (Arc-based)
-        let nodes: Vec<PointerNode> = self
-            .path
-            .iter()
-            .map(|seg| match seg {
-                PathSegment::Key(k) => PointerNode::Key(k.clone()),
-                PathSegment::Index(i) => PointerNode::Index(*i),
-            })
-            .collect();
+        // Start from the deepest container on the path that this Query or
+        // a sibling already found, and record each one found from there
+        let mut cache = self.prefixes.borrow_mut();
+        Ok(paths::resolve_path(json, layer, &self.path, &mut cache)?)
+    }
+
+    /// Resolve the path, requiring an array there.
+    fn resolve_array(&self) -> Result<LazyValue<'_>, SonicError> {
+        let lazy = self.resolve()?;
+        if !lazy.is_array() {
+            return Err(self.type_error(&lazy, "array", "Value is not an array"));
+        }
+        Ok(lazy)
+    }
+
+    /// Append the elements of the array whose text is `array`, `depth`
+    /// levels below the one being flattened, to `out`. Elements that are
+    /// arrays are replaced by their own elements at the top level, and at
+    /// every level when `recursive`.
+    fn flatten_into<'a>(
+        array: &'a str,
+        recursive: bool,
+        depth: usize,
+        out: &mut Vec<LazyValue<'a>>,
+    ) -> Result<(), SonicError> {
+        if depth > parser::MAX_DEPTH {
+            return Err(SonicError::ParseError(format!(
+                "Maximum nesting depth ({}) exceeded",
+                parser::MAX_DEPTH
+            )));
+        }
+        // SAFETY: callers only pass the text of arrays
+        for item in unsafe { to_array_iter_unchecked(array) } {
+            let item = item?;
+            if item.is_array() && (recursive || depth == 0) {
+                // Elements are slices of the array's text
+                let raw = item.as_raw_str();
+                let start = raw.as_ptr() as usize - array.as_ptr() as usize;
+                Self::flatten_into(&array[start..start + raw.len()], recursive, depth + 1, out)?;
+            } else {
+                out.push(item);
+            }
+        }
+        Ok(())
+    }
+
+    /// The accumulated path as an RFC 6901 pointer, for error messages.
+    fn pointer_string(&self) -> String {
+        segments_pointer(&self.path)
+    }
+
+    /// A type mismatch at the current path, with its pointer and the
+    /// expected and actual types attached.
+    fn type_error(&self, lazy: &LazyValue, expected: &'static str, message: &str) -> SonicError {
+        SonicError::TypeError(message.to_string())
+            .at_pointer(self.pointer_string())
+            .with_types(expected, parser::type_name(lazy))
+    }
+
+    /// Drop this Query's references to its text, defaults and prefix
+    /// cache; the memory is freed once no other Query shares it.
+    fn release_buffer(&mut self) {
+        // The memo borrows the text, so it goes first
+        self.resolved = OnceCell::new();
+        self.json = Arc::default();
+        self.defaults = Vec::new();
+        self.prefixes = Rc::default();
+        self.released = true;
+    }
+
+    /// Pass a hydration's result through, releasing the buffer after a
+    /// successful one if this Query is weak.
+    fn settle<T>(&mut self, result: Result<T, SonicError>) -> Result<T, SonicError> {
+        if self.weak && result.is_ok() {
+            self.release_buffer();
+        }
+        result
+    }
+
+    /// The string at this path as a Rust `String`.
+    fn string_value(&self) -> Result<String, SonicError> {
+        self.with_string(Cow::into_owned)
+    }
+
+    /// Run `f` on the string at this path where it lies: in the input, or
+    /// in the buffer sonic-rs unescaped it into if it has escapes. Only
+    /// stripping control characters makes a copy.
+    fn with_string<R>(&self, f: impl FnOnce(Cow<'_, str>) -> R) -> Result<R, SonicError> {
+        let lazy = self.resolve()?;
+        let text = lazy
+            .as_str()
+            .ok_or_else(|| self.type_error(&lazy, "string", "Value is not a string"))?;
+        parser::control_chars(text, &self.options)
+            .map(f)
+            .map_err(|e| e.at_pointer(self.pointer_string()))
+    }
+
+    /// The raw JSON text at this path as a Rust `String`.
+    pub fn raw_text(&self) -> Result<String, SonicError> {
+        let lazy = self.resolve()?;
+        Ok(lazy.as_raw_str().to_string())
+    }
 
-        sonic_rs::get(self.json.as_str(), nodes.as_slice())
-            .map_err(|_| SonicError::KeyNotFound("Path not found".to_string()))
+    /// Fail unless property and index reads were enabled with
+    /// `property_access()`.
+    fn check_property_access(&self) -> Result<(), SonicError> {
+        if self.properties {
+            Ok(())
+        } else {
+            Err(SonicError::InvalidOption(
+                "Property access is off; call propertyAccess() first, or use get() and index()"
+                    .to_string(),
+            ))
+        }
+    }
+
+    /// Navigate to the key or index `offset` of an index read.
+    fn offset_query(&self, offset: &Zval) -> Result<Query, SonicError> {
+        self.check_property_access()?;
+        if let Some(index) = offset.long() {
+            self.index(index)
+        } else if let Some(key) = offset.str() {
+            self.get(key)
+        } else {
+            Err(SonicError::InvalidPointer(
+                "Offset must be an int or a string".to_string(),
+            ))
+        }
+    }
+
+    /// Whether this Query's path exists and isn't null, as `isset()` asks.
+    fn is_set(&self) -> Result<bool, SonicError> {
+        match self.resolve() {
+            Ok(lazy) => Ok(!lazy.is_null()),
+            Err(e) if e.code() == ErrorCode::KeyNotFound as i32 => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Extract a string and check it with `valid`, throwing a
+    /// FormatException that names the pointer when it fails.
+    fn formatted_string(
+        &self,
+        what: &str,
+        valid: impl Fn(&str) -> bool,
+    ) -> Result<String, SonicError> {
+        let s = self.string_value()?;
+        if valid(&s) {
+            Ok(s)
+        } else {
+            let pointer = self.pointer_string();
+            Err(SonicError::FormatError(format!(
+                "Value at '{}' is not a valid {}",
+                pointer, what
+            ))
+            .at_pointer(pointer))
+        }
     }
 }
 
@@ -100,38 +377,284 @@ impl Query {
     /// $q = Sift::query($json)->pointer("/users/0/email");
     /// ```
     pub fn pointer(&self, ptr: &str) -> Result<Query, SonicError> {
-        if ptr.is_empty() {
-            return Ok(self.clone());
+        guard(|| {
+            if ptr.is_empty() {
+                return Ok(self.clone());
+            }
+
+            if !ptr.starts_with('/') {
+                return Err(SonicError::InvalidPointer(
+                    "Pointer must start with '/' or be empty".to_string()
+                ));
+            }
+
+            // Parse and accumulate segments
+            let mut new_path = self.path.clone();
+            for part in ptr[1..].split('/') {
+                // Check path segment limit
+                if new_path.len() >= MAX_PATH_SEGMENTS {
+                    return Err(SonicError::InvalidPointer(format!(
+                        "Path has too many segments (max {})",
+                        MAX_PATH_SEGMENTS
+                    )));
+                }
+
+                let unescaped = part.replace("~1", "/").replace("~0", "~");
+                if let Ok(idx) = unescaped.parse::<usize>() {
+                    new_path.push(PathSegment::Index(idx));
+                } else {
+                    new_path.push(PathSegment::Key(FastStr::new(unescaped)));
+                }
+            }
+
+            Ok(Query {
+                json: Arc::clone(&self.json),
+                path: new_path,
+                options: self.options,
+                follow_refs: self.follow_refs,
+                properties: self.properties,
+                weak: self.weak,
+                released: self.released,
+                defaults: self.defaults.clone(),
+                resolved: OnceCell::new(),
+                prefixes: Rc::clone(&self.prefixes),
+                live: Live::new(CLASS),
+            })
+        })
+    }
+
+    /// Like `pointer()`, but resolves the path at once and returns null
+    /// if it is missing, instead of a Query whose reads throw.
+    ///
+    /// # Example
+    /// ```php
+    /// $city = Sift::query($json)->tryPointer("/user/address/city")?->string();
+    /// ```
+    pub fn try_pointer(&self, ptr: &str) -> Result<Option<Query>, SonicError> {
+        guard(|| {
+            let query = self.pointer(ptr)?;
+            // The resolution is memoized for the returned Query's reads
+            let found = match query.resolve() {
+                Ok(_) => true,
+                Err(e) if e.code() == ErrorCode::KeyNotFound as i32 => false,
+                Err(e) => return Err(e),
+            };
+            Ok(found.then_some(query))
+        })
+    }
+
+    /// Like `tryPointer()`, but a missing path gives a `Sift\MissingValue`
+    /// instead of null, which answers `isMissing()` and `orElse()` like a
+    /// Query does. A JSON null is found, and gives a Query.
+    ///
+    /// # Example
+    /// ```php
+    /// $city = Sift::query($json)->lookup("/user/address/city")->orElse("unknown");
+    /// ```
+    pub fn lookup(&self, ptr: &str) -> Result<Zval, SonicError> {
+        guard(|| {
+            let query = self.pointer(ptr)?;
+            let found = match query.resolve() {
+                Ok(_) => true,
+                Err(e) if e.code() == ErrorCode::KeyNotFound as i32 => false,
+                Err(e) => return Err(e),
+            };
+            let zval = if found {
+                query.into_zval(false)
+            } else {
+                MissingValue::new(query.pointer_string()).into_zval(false)
+            };
+            zval.map_err(|e| SonicError::TypeError(e.to_string()))
+        })
+    }
+
+    /// Always false; `MissingValue::isMissing()` is always true.
+    pub fn is_missing(&self) -> bool {
+        false
+    }
+
+    /// Hydrate like `value()`, or return `default` if the path is missing.
+    /// Other errors still throw.
+    ///
+    /// # Example
+    /// ```php
+    /// $limit = Sift::query($json)->pointer("/settings/limit")->orElse(100);
+    /// ```
+    pub fn or_else(&self, default: &Zval) -> Result<Zval, SonicError> {
+        guard(|| match self.resolve() {
+            Ok(lazy) => parser::lazyvalue_to_zval(lazy, &self.options),
+            Err(e) if e.code() == ErrorCode::KeyNotFound as i32 => Ok(default.shallow_clone()),
+            Err(e) => Err(e),
+        })
+    }
+
+    /// Follow internal `$ref`s (`{"$ref": "#/components/schemas/User"}`)
+    /// while resolving: before each path step and at the end of the path,
+    /// a reference object is replaced by its target. Circular references
+    /// throw. Numeric segments also match object keys, as in
+    /// `/responses/200`. Passing false turns it off again.
+    ///
+    /// # Example
+    /// ```php
+    /// $spec = Sift::query($openapi)->followRefs();
+    /// $email = $spec->pointer("/components/schemas/Order/properties/customer/properties/email")->value();
+    /// ```
+    pub fn follow_refs(&self, enabled: Option<bool>) -> Query {
+        Query {
+            follow_refs: enabled.unwrap_or(true),
+            resolved: OnceCell::new(),
+            ..self.clone()
         }
+    }
 
-        if !ptr.starts_with('/') {
-            return Err(SonicError::InvalidPointer(
-                "Pointer must start with '/' or be empty".to_string()
-            ));
+    /// Let property reads navigate like `get()` and index reads like
+    /// `index()` (or `get()` for string offsets), on this Query and every
+    /// Query derived from it. Off by default, so typos in method chains
+    /// don't silently become key lookups. Passing false turns it off again.
+    ///
+    /// # Example
+    /// ```php
+    /// $q = Sift::query($json)->propertyAccess();
+    /// $email = $q->users[3]->email->string();
+    /// ```
+    pub fn property_access(&self, enabled: Option<bool>) -> Query {
+        Query {
+            properties: enabled.unwrap_or(true),
+            ..self.clone()
         }
+    }
 
-        // Parse and accumulate segments
-        let mut new_path = self.path.clone();
-        for part in ptr[1..].split('/') {
-            // Check path segment limit
-            if new_path.len() >= MAX_PATH_SEGMENTS {
-                return Err(SonicError::InvalidPointer(format!(
-                    "Path has too many segments (max {})",
-                    MAX_PATH_SEGMENTS
+    /// Make this Query, and every Query derived from it, let go of the
+    /// JSON text after its first successful hydration (`value()`,
+    /// `string()`, `int()`, `float()`, `bool()`, `raw()`), as `release()`
+    /// does. Meant for one-shot reads in long-running workers, where a
+    /// lingering Query would keep a whole document alive. Passing false
+    /// turns it off again.
+    ///
+    /// # Example
+    /// ```php
+    /// $q = Sift::query($message)->weak();
+    /// $id = $q->get("id")->int();     // that child Query is released now
+    /// ```
+    pub fn weak(&self, enabled: Option<bool>) -> Query {
+        Query {
+            weak: enabled.unwrap_or(true),
+            ..self.clone()
+        }
+    }
+
+    /// Drop this Query's reference to its JSON text, defaults and cached
+    /// containers. The memory is freed once no other Query shares it;
+    /// Queries derived earlier keep their own references. Every later
+    /// read throws `Sift\StaleHandleException`. Releasing twice is a no-op.
+    ///
+    /// # Example
+    /// ```php
+    /// $q = Sift::query($payload);
+    /// $order = $q->get("order")->value();
+    /// $q->release();
+    /// ```
+    pub fn release(&mut self) {
+        self.release_buffer();
+    }
+
+    /// Alias of `release()`, for code that closes resources.
+    pub fn close(&mut self) {
+        self.release_buffer();
+    }
+
+    /// Whether `release()` (or a weak hydration) dropped the buffer.
+    pub fn is_released(&self) -> bool {
+        self.released
+    }
+
+    /// `$q->name`: navigate into the key `name`, as `get()` does. Names
+    /// of hydration methods (`value`, `string`, ...) throw; use `get()`
+    /// for keys with those names.
+    pub fn __get(&self, name: &str) -> Result<Query, SonicError> {
+        guard(|| {
+            self.check_property_access()?;
+            if HYDRATION_NAMES.contains(&name) {
+                return Err(SonicError::InvalidOption(format!(
+                    "'{}' is a hydration method, not a property; \
+                     use get('{}') for the key or {}() for the value",
+                    name, name, name
                 )));
             }
+            self.get(name)
+        })
+    }
 
-            let unescaped = part.replace("~1", "/").replace("~0", "~");
-            if let Ok(idx) = unescaped.parse::<usize>() {
-                new_path.push(PathSegment::Index(idx));
-            } else {
-                new_path.push(PathSegment::Key(FastStr::new(unescaped)));
+    /// `isset($q->name)`: whether the key exists and isn't null. Always
+    /// false without property access.
+    pub fn __isset(&self, name: &str) -> Result<bool, SonicError> {
+        guard(|| {
+            if !self.properties || HYDRATION_NAMES.contains(&name) {
+                return Ok(false);
             }
-        }
+            self.get(name)?.is_set()
+        })
+    }
+
+    /// Queries are read-only.
+    pub fn __set(&self, name: &str, _value: &Zval) -> Result<(), SonicError> {
+        Err(SonicError::TypeError(format!(
+            "Cannot set '{}': Query is read-only",
+            name
+        )))
+    }
+
+    /// `$q[3]` or `$q['key']`: navigate like `index()` or `get()`.
+    pub fn offset_get(&self, offset: &Zval) -> Result<Query, SonicError> {
+        guard(|| self.offset_query(offset))
+    }
 
-        Ok(Query {
-            json: Arc::clone(&self.json),
-            path: new_path,
+    /// `isset($q[3])`: whether the element exists and isn't null. Always
+    /// false without property access.
+    pub fn offset_exists(&self, offset: &Zval) -> Result<bool, SonicError> {
+        guard(|| {
+            if !self.properties {
+                return Ok(false);
+            }
+            self.offset_query(offset)?.is_set()
+        })
+    }
+
+    /// Queries are read-only.
+    pub fn offset_set(&self, _offset: &Zval, _value: &Zval) -> Result<(), SonicError> {
+        Err(SonicError::TypeError("Query is read-only".to_string()))
+    }
+
+    /// Queries are read-only.
+    pub fn offset_unset(&self, _offset: &Zval) -> Result<(), SonicError> {
+        Err(SonicError::TypeError("Query is read-only".to_string()))
+    }
+
+    /// Add a fallback document: a path missing from this Query's document
+    /// (and from earlier defaults) is resolved in `json` instead, for
+    /// settings overlays. Layers are chosen per resolved path, so a value
+    /// comes whole from one document - objects are not merged. Each call
+    /// adds a default below those already added.
+    ///
+    /// # Example
+    /// ```php
+    /// $settings = Sift::query($user)->withDefaultsFrom($team)->withDefaultsFrom($global);
+    /// $theme = $settings->pointer("/ui/theme")->string();
+    /// ```
+    pub fn with_defaults_from(&self, json: BinarySlice<u8>) -> Result<Query, SonicError> {
+        guard(|| {
+            let json = parser::decode_text(&json, &self.options)?;
+            parser::check_input_size(json.as_ref())?;
+            let mut defaults = self.defaults.clone();
+            defaults.push(Arc::new(json.into_owned()));
+            Ok(Query {
+                defaults,
+                resolved: OnceCell::new(),
+                // Cached layers are indexed by position, which now names a
+                // document siblings with other defaults don't have
+                prefixes: Rc::default(),
+                ..self.clone()
+            })
         })
     }
 
@@ -143,19 +666,30 @@ impl Query {
     /// $q = Sift::query($json)->get("users")->get("email");
     /// ```
     pub fn get(&self, key: &str) -> Result<Query, SonicError> {
-        // Check path segment limit
-        if self.path.len() >= MAX_PATH_SEGMENTS {
-            return Err(SonicError::InvalidPointer(format!(
-                "Path has too many segments (max {})",
-                MAX_PATH_SEGMENTS
-            )));
-        }
+        guard(|| {
+            // Check path segment limit
+            if self.path.len() >= MAX_PATH_SEGMENTS {
+                return Err(SonicError::InvalidPointer(format!(
+                    "Path has too many segments (max {})",
+                    MAX_PATH_SEGMENTS
+                )));
+            }
 
-        let mut new_path = self.path.clone();
-        new_path.push(PathSegment::Key(FastStr::new(key)));
-        Ok(Query {
-            json: Arc::clone(&self.json),
-            path: new_path,
+            let mut new_path = self.path.clone();
+            new_path.push(PathSegment::Key(FastStr::new(key)));
+            Ok(Query {
+                json: Arc::clone(&self.json),
+                path: new_path,
+                options: self.options,
+                follow_refs: self.follow_refs,
+                properties: self.properties,
+                weak: self.weak,
+                released: self.released,
+                defaults: self.defaults.clone(),
+                resolved: OnceCell::new(),
+                prefixes: Rc::clone(&self.prefixes),
+                live: Live::new(CLASS),
+            })
         })
     }
 
@@ -167,113 +701,566 @@ impl Query {
     /// $q = Sift::query($json)->get("users")->index(5)->get("email");
     /// ```
     pub fn index(&self, idx: i64) -> Result<Query, SonicError> {
-        // Validate non-negative index
-        if idx < 0 {
-            return Err(SonicError::InvalidPointer(format!(
-                "Array index must be non-negative, got {}",
-                idx
-            )));
-        }
+        guard(|| {
+            // Validate non-negative index
+            if idx < 0 {
+                return Err(SonicError::InvalidPointer(format!(
+                    "Array index must be non-negative, got {}",
+                    idx
+                )));
+            }
 
-        // Check path segment limit
-        if self.path.len() >= MAX_PATH_SEGMENTS {
-            return Err(SonicError::InvalidPointer(format!(
-                "Path has too many segments (max {})",
-                MAX_PATH_SEGMENTS
-            )));
-        }
+            // Check path segment limit
+            if self.path.len() >= MAX_PATH_SEGMENTS {
+                return Err(SonicError::InvalidPointer(format!(
+                    "Path has too many segments (max {})",
+                    MAX_PATH_SEGMENTS
+                )));
+            }
+
+            let mut new_path = self.path.clone();
+            new_path.push(PathSegment::Index(idx as usize));
+            Ok(Query {
+                json: Arc::clone(&self.json),
+                path: new_path,
+                options: self.options,
+                follow_refs: self.follow_refs,
+                properties: self.properties,
+                weak: self.weak,
+                released: self.released,
+                defaults: self.defaults.clone(),
+                resolved: OnceCell::new(),
+                prefixes: Rc::clone(&self.prefixes),
+                live: Live::new(CLASS),
+            })
+        })
+    }
 
-        let mut new_path = self.path.clone();
-        new_path.push(PathSegment::Index(idx as usize));
-        Ok(Query {
-            json: Arc::clone(&self.json),
-            path: new_path,
+    /// Step into JSON that is embedded as a string value
+    /// (`"payload": "{\"a\":1}"`). Resolves the current path, unescapes the
+    /// string and returns a new Query rooted in it; the embedded document
+    /// itself is only parsed on hydration, like any other Query.
+    /// Paths in later errors are relative to the embedded document.
+    ///
+    /// # Example
+    /// ```php
+    /// $id = Sift::query($webhook)->get("payload")->jsonString()->get("id")->int();
+    /// ```
+    pub fn json_string(&self) -> Result<Query, SonicError> {
+        guard(|| {
+            let embedded = self.string_value()?;
+            Ok(Query::new(embedded, self.options))
         })
     }
 
     // === Hydration methods - these resolve the path and create PHP values ===
 
-    /// Extract as PHP string. Only now is the path resolved.
-    pub fn string(&self) -> Result<String, SonicError> {
-        let lazy = self.resolve()?;
-        lazy.as_str()
-            .map(|s| s.to_string())
-            .ok_or_else(|| SonicError::TypeError("Value is not a string".to_string()))
+    /// Extract as PHP string. Only now is the path resolved. The
+    /// zend_string is filled straight from the input (or the unescaped
+    /// text, for strings with escapes) with one copy.
+    pub fn string(&mut self) -> Result<PhpString, SonicError> {
+        let result = guard(|| self.with_string(|text| PhpString::new(&text)));
+        self.settle(result)
+    }
+
+    /// Extract a string and require it to be a UUID
+    /// (8-4-4-4-12 hex digits, either case).
+    /// Throws `Sift\FormatException` naming the pointer otherwise.
+    pub fn uuid(&self) -> Result<String, SonicError> {
+        guard(|| self.formatted_string("UUID", formats::is_uuid))
+    }
+
+    /// Extract a string and require it to be an email address
+    /// (dot-atom local part, dotted domain name).
+    pub fn email(&self) -> Result<String, SonicError> {
+        guard(|| self.formatted_string("email address", formats::is_email))
+    }
+
+    /// Extract a string and require it to be an absolute URL with a host,
+    /// e.g. "https://example.com/path".
+    pub fn url(&self) -> Result<String, SonicError> {
+        guard(|| self.formatted_string("URL", formats::is_url))
+    }
+
+    /// Extract a string and require it to be an IP address.
+    /// Pass 4 or 6 to accept only that version.
+    ///
+    /// # Example
+    /// ```php
+    /// $ip = Sift::query($json)->get('client_ip')->ip(4);
+    /// ```
+    pub fn ip(&self, version: Option<i64>) -> Result<String, SonicError> {
+        guard(|| {
+            let version = match version {
+                None => None,
+                Some(4) => Some(4),
+                Some(6) => Some(6),
+                Some(v) => {
+                    return Err(SonicError::InvalidOption(format!(
+                        "IP version must be 4 or 6, got {}",
+                        v
+                    )))
+                }
+            };
+            let what = match version {
+                Some(4) => "IPv4 address",
+                Some(6) => "IPv6 address",
+                _ => "IP address",
+            };
+            self.formatted_string(what, |s| formats::is_ip(s, version))
+        })
+    }
+
+    /// Extract a base64-encoded string as the decoded binary string.
+    /// Decoded in Rust straight from the JSON input, without an intermediate
+    /// PHP string. `alphabet` is `'standard'` (default) or `'url'`.
+    /// Throws `Sift\FormatException` naming the pointer on invalid input.
+    ///
+    /// # Example
+    /// ```php
+    /// $png = Sift::query($payload)->get('attachment')->base64();
+    /// ```
+    pub fn base64(&self, alphabet: Option<&str>) -> Result<Binary<u8>, SonicError> {
+        guard(|| {
+            let alphabet = match alphabet {
+                Some(name) => base64::Alphabet::from_name(name)?,
+                None => base64::Alphabet::default(),
+            };
+            let lazy = self.resolve()?;
+            let encoded = lazy
+                .as_str()
+                .ok_or_else(|| self.type_error(&lazy, "string", "Value is not a string"))?;
+            base64::decode(encoded, alphabet).map(Binary::new).map_err(|e| {
+                let pointer = self.pointer_string();
+                SonicError::FormatError(format!(
+                    "Value at '{}' is not valid base64: {}",
+                    pointer,
+                    e.describe()
+                ))
+                .at_pointer(pointer)
+            })
+        })
     }
 
     /// Extract as PHP integer.
-    pub fn int(&self) -> Result<i64, SonicError> {
-        let lazy = self.resolve()?;
-        lazy.as_i64()
-            .ok_or_else(|| SonicError::TypeError("Value is not an integer".to_string()))
+    pub fn int(&mut self) -> Result<i64, SonicError> {
+        let result = guard(|| {
+            let lazy = self.resolve()?;
+            lazy.as_i64()
+                .ok_or_else(|| self.type_error(&lazy, "integer", "Value is not an integer"))
+        });
+        self.settle(result)
     }
 
     /// Extract as PHP float.
-    pub fn float(&self) -> Result<f64, SonicError> {
-        let lazy = self.resolve()?;
-        lazy.as_f64()
-            .ok_or_else(|| SonicError::TypeError("Value is not a float".to_string()))
+    pub fn float(&mut self) -> Result<f64, SonicError> {
+        let result = guard(|| {
+            let lazy = self.resolve()?;
+            lazy.as_f64()
+                .ok_or_else(|| self.type_error(&lazy, "float", "Value is not a float"))
+        });
+        self.settle(result)
     }
 
     /// Extract as PHP boolean.
-    pub fn bool(&self) -> Result<bool, SonicError> {
-        let lazy = self.resolve()?;
-        lazy.as_bool()
-            .ok_or_else(|| SonicError::TypeError("Value is not a boolean".to_string()))
+    pub fn bool(&mut self) -> Result<bool, SonicError> {
+        let result = guard(|| {
+            let lazy = self.resolve()?;
+            lazy.as_bool()
+                .ok_or_else(|| self.type_error(&lazy, "boolean", "Value is not a boolean"))
+        });
+        self.settle(result)
+    }
+
+    /// Extract as PHP integer, accepting nothing but an integer literal in
+    /// PHP's int range: floats with an integral value (`1.0`, `1e3`),
+    /// negative zero, numeric strings and every other type throw, with the
+    /// reason and the value's pointer in the error.
+    ///
+    /// # Example
+    /// ```php
+    /// $cents = Sift::query($payment)->get('amount_cents')->intStrict();
+    /// ```
+    pub fn int_strict(&self) -> Result<i64, SonicError> {
+        guard(|| {
+            let lazy = self.resolve()?;
+            let raw = lazy.as_raw_str();
+            let message = match lazy.get_type() {
+                JsonType::Number if raw.contains(['.', 'e', 'E']) => {
+                    "Value is a float, not an integer"
+                }
+                JsonType::Number if raw == "-0" => "Value is negative zero, not an integer",
+                JsonType::Number => match lazy.as_i64() {
+                    Some(n) => return Ok(n),
+                    None => "Value is outside PHP's int range",
+                },
+                JsonType::String => "Value is a string, not an integer",
+                _ => "Value is not an integer",
+            };
+            Err(self.type_error(&lazy, "integer", message))
+        })
+    }
+
+    /// Extract as PHP boolean, accepting nothing but `true` and `false`:
+    /// `1`, `"true"`, `"yes"` and null throw, with the reason and the
+    /// value's pointer in the error. See `boolish()` for the lenient
+    /// counterpart.
+    ///
+    /// # Example
+    /// ```php
+    /// $isAdmin = Sift::query($claims)->get('admin')->boolStrict();
+    /// ```
+    pub fn bool_strict(&self) -> Result<bool, SonicError> {
+        guard(|| {
+            let lazy = self.resolve()?;
+            let message = match lazy.get_type() {
+                JsonType::Boolean => return Ok(lazy.as_bool() == Some(true)),
+                JsonType::Number => "Value is a number, not a boolean",
+                JsonType::String => "Value is a string, not a boolean",
+                JsonType::Null => "Value is null, not a boolean",
+                _ => "Value is not a boolean",
+            };
+            Err(self.type_error(&lazy, "boolean", message))
+        })
+    }
+
+    /// Extract as PHP boolean, tolerating common encodings such as
+    /// "true"/"false", "1"/0 and "yes"/"no" (case-insensitive).
+    /// Custom `truthy`/`falsy` lists of strings or ints replace the defaults.
+    /// Throws on values in neither list, null, arrays and objects.
+    ///
+    /// # Example
+    /// ```php
+    /// $active = Sift::query($json)->get('active')->boolish();
+    /// $enabled = $q->boolish(['enabled', 'active'], ['disabled', 'inactive']);
+    /// ```
+    pub fn boolish(
+        &self,
+        truthy: Option<&ZendHashTable>,
+        falsy: Option<&ZendHashTable>,
+    ) -> Result<bool, SonicError> {
+        guard(|| {
+            let table = BoolTable::from_arrays(truthy, falsy)?;
+            let lazy = self.resolve()?;
+            table
+                .read(&lazy)
+                .map_err(|e| e.at_pointer(self.pointer_string()))
+        })
+    }
+
+    /// Length without hydration: the byte length of a string (its code
+    /// points with `codepoints`), the element count of an array or the
+    /// member count of an object. Containers are counted by skipping over
+    /// their contents, so nothing is converted.
+    ///
+    /// # Example
+    /// ```php
+    /// if ($q->get("items")->len() > 10000) { /* too big to hydrate */ }
+    /// ```
+    pub fn len(&self, codepoints: Option<bool>) -> Result<i64, SonicError> {
+        guard(|| {
+            let lazy = self.resolve()?;
+            if let Some(text) = lazy.as_str() {
+                let len = match codepoints {
+                    Some(true) => text.chars().count(),
+                    _ => text.len(),
+                };
+                return Ok(len as i64);
+            }
+            match parser::element_count(&lazy)? {
+                Some(count) => Ok(count as i64),
+                None => Err(self.type_error(&lazy, "string", "Value has no length")),
+            }
+        })
     }
 
     /// Check if the value is null.
     pub fn is_null(&self) -> Result<bool, SonicError> {
-        let lazy = self.resolve()?;
-        Ok(lazy.is_null())
+        guard(|| {
+            let lazy = self.resolve()?;
+            Ok(lazy.is_null())
+        })
     }
 
     /// Get the raw JSON string at this path without parsing.
-    /// Useful for passing JSON subsets to other systems.
-    pub fn raw(&self) -> Result<String, SonicError> {
-        let lazy = self.resolve()?;
-        Ok(lazy.as_raw_str().to_string())
+    /// Useful for passing JSON subsets to other systems. The text is
+    /// copied once, from the input into the returned string.
+    pub fn raw(&mut self) -> Result<PhpString, SonicError> {
+        let result = guard(|| Ok(PhpString::new(self.resolve()?.as_raw_str())));
+        self.settle(result)
+    }
+
+    /// `raw()` with bounded memory: text up to `threshold` bytes is
+    /// returned as a string, anything larger is written to a new temp file
+    /// (in `directory`, or the system temp directory) and its path returned
+    /// instead, as `['size' => int, 'raw' => ?string, 'path' => ?string]`.
+    /// The caller removes the file.
+    ///
+    /// # Example
+    /// ```php
+    /// $raw = Sift::query($upstream)->get("export")->rawSpill(1 << 20);
+    /// $raw['path'] !== null ? readfile($raw['path']) : print($raw['raw']);
+    /// ```
+    pub fn raw_spill(&self, threshold: i64, directory: Option<String>) -> Result<Zval, SonicError> {
+        guard(|| {
+            if threshold < 0 {
+                return Err(SonicError::InvalidOption(format!(
+                    "Spill threshold must be non-negative, got {}",
+                    threshold
+                )));
+            }
+            let lazy = self.resolve()?;
+            let raw = lazy.as_raw_str();
+            let (text, path) = if raw.len() as u64 > threshold as u64 {
+                (None, Some(spill::spill(raw, directory.as_deref())?))
+            } else {
+                (Some(raw), None)
+            };
+            let mut result = ZendHashTable::new();
+            result
+                .insert("size", raw.len() as i64)
+                .and_then(|_| result.insert("raw", text))
+                .and_then(|_| result.insert("path", path))
+                .map_err(|e| {
+                    SonicError::TypeError(format!("Failed to insert object key: {}", e))
+                })?;
+            let mut zval = Zval::new();
+            zval.set_hashtable(result);
+            Ok(zval)
+        })
     }
 
     /// Full hydration to PHP array/value. Use sparingly.
-    pub fn value(&self) -> Result<Zval, SonicError> {
-        let lazy = self.resolve()?;
-        parser::decode(lazy.as_raw_str())
+    /// The resolved LazyValue is converted directly, so the subtree is
+    /// walked once instead of being re-parsed from its raw slice.
+    ///
+    /// With `max_depth`, only that many levels of containers are
+    /// converted; arrays and objects below them become Queries to expand
+    /// on demand, or their raw JSON text with `leaves` "raw".
+    ///
+    /// # Example
+    /// ```php
+    /// $top = Sift::query($json)->value(2);
+    /// $more = $top["users"][0]["address"]->value();
+    /// ```
+    pub fn value(
+        &mut self,
+        max_depth: Option<i64>,
+        leaves: Option<String>,
+    ) -> Result<Zval, SonicError> {
+        let result = guard(|| {
+            let leaves = match leaves.as_deref() {
+                None | Some("query") => Leaves::Query,
+                Some("raw") => Leaves::Raw,
+                Some(other) => {
+                    return Err(SonicError::InvalidOption(format!(
+                        "Leaves must be 'query' or 'raw', got '{}'",
+                        other
+                    )))
+                }
+            };
+            let options = match max_depth {
+                None => self.options,
+                Some(depth) if depth < 1 => {
+                    return Err(SonicError::InvalidOption(format!(
+                        "Maximum depth must be at least 1, got {}",
+                        depth
+                    )))
+                }
+                Some(depth) => DecodeOptions {
+                    cutoff: Some((depth as usize, leaves)),
+                    ..self.options
+                },
+            };
+            let lazy = self.resolve()?;
+            parser::lazyvalue_to_zval(lazy, &options)
+        });
+        self.settle(result)
+    }
+
+    /// Iterate over the array at this path in hydrated chunks of up to
+    /// `size` elements, for batch processing of huge arrays.
+    /// The array is resolved when iteration starts. With `offset`, a
+    /// value of `Chunks::tell()`, iteration resumes where that left off.
+    ///
+    /// # Example
+    /// ```php
+    /// foreach (Sift::query($json)->get("rows")->chunks(500) as $i => $rows) {
+    ///     $db->insertBatch($rows);
+    /// }
+    /// ```
+    pub fn chunks(&self, size: i64, offset: Option<i64>) -> Result<Chunks, SonicError> {
+        guard(|| {
+            if size < 1 {
+                return Err(SonicError::InvalidOption(format!(
+                    "Chunk size must be at least 1, got {}",
+                    size
+                )));
+            }
+            let offset = match offset {
+                Some(offset) if offset < 0 => {
+                    return Err(SonicError::InvalidOption(format!(
+                        "Offset must not be negative, got {}",
+                        offset
+                    )))
+                }
+                offset => offset.map(|offset| offset as usize),
+            };
+            Ok(Chunks::new(self.clone(), size as usize, offset))
+        })
+    }
+
+    /// Validate the value at this path against a JSON Schema, returning
+    /// its violations as `['pointer' => ..., 'keyword' => ..., 'message'
+    /// => ...]` with pointers relative to this path; an empty list means
+    /// it matches. Only this subtree is parsed.
+    ///
+    /// # Example
+    /// ```php
+    /// $violations = Sift::query($envelope)->get("order")->matchesSchema($orderSchema);
+    /// ```
+    pub fn matches_schema(&self, schema: &str) -> Result<Zval, SonicError> {
+        guard(|| {
+            let schema = schema::compile(schema)?;
+            let lazy = self.resolve()?;
+            let value: sonic_rs::Value = sonic_rs::from_str(lazy.as_raw_str())?;
+            schema::violations_to_zval(schema::validate(&value, &schema)?)
+        })
+    }
+
+    /// Pack the coordinates of the GeoJSON geometry (or Feature) at this
+    /// path into `["type" => string, "dims" => int, "data" => string,
+    /// "counts" => list]`: positions as doubles in machine byte order,
+    /// plus the lengths of the rings/lines/polygons between them. A
+    /// Feature with a null geometry yields null.
+    ///
+    /// # Example
+    /// ```php
+    /// $g = Sift::query($json)->get("features")->index(0)->geometry();
+    /// $xy = unpack('d*', $g['data']);
+    /// ```
+    pub fn geometry(&self) -> Result<Zval, SonicError> {
+        guard(|| {
+            let lazy = self.resolve()?;
+            geojson::geometry(&lazy, &self.pointer_string())
+        })
+    }
+
+    /// For an array of objects, hydrate the given keys column-wise:
+    /// `["id" => [...], "price" => [...]]`, built in one pass over the
+    /// array. Rows missing a key contribute null, so the lists stay
+    /// parallel; other members are never converted.
+    ///
+    /// # Example
+    /// ```php
+    /// $cols = Sift::query($json)->get("orders")->columns(["id", "price"]);
+    /// ```
+    pub fn columns(&self, keys: Vec<String>) -> Result<Zval, SonicError> {
+        guard(|| {
+            let lazy = self.resolve_array()?;
+
+            let mut names: Vec<String> = Vec::with_capacity(keys.len());
+            for key in keys {
+                if !names.contains(&key) {
+                    names.push(key);
+                }
+            }
+            parser::columns(lazy, &names, &self.pointer_string(), &self.options)
+        })
+    }
+
+    /// Pack the 2D numeric array at this path into a flat buffer of
+    /// doubles: `["data" => string, "rows" => int, "cols" => int]`. The
+    /// buffer is row-major in machine byte order, ready for FFI or
+    /// `unpack('d*', ...)`, and no per-element Zvals are created.
+    ///
+    /// # Example
+    /// ```php
+    /// $m = Sift::query($json)->get("embeddings")->floatMatrix();
+    /// $ffiBuf = FFI::new("double[{$m['rows']}][{$m['cols']}]");
+    /// FFI::memcpy($ffiBuf, $m['data'], strlen($m['data']));
+    /// ```
+    pub fn float_matrix(&self) -> Result<Zval, SonicError> {
+        guard(|| {
+            let lazy = self.resolve_array()?;
+            parser::float_matrix(lazy, &self.pointer_string())
+        })
+    }
+
+    /// Hydrate `n` elements sampled uniformly from the array at this path,
+    /// in document order. The array is read in one streaming pass and only
+    /// the sampled elements are converted. Passing a `seed` makes the
+    /// sample reproducible.
+    ///
+    /// # Example
+    /// ```php
+    /// $rows = Sift::query($export)->get("rows")->sample(100, seed: 42);
+    /// ```
+    pub fn sample(&self, n: i64, seed: Option<i64>) -> Result<Vec<Zval>, SonicError> {
+        guard(|| {
+            if n < 1 {
+                return Err(SonicError::InvalidOption(format!(
+                    "Sample size must be at least 1, got {}",
+                    n
+                )));
+            }
+            let lazy = self.resolve_array()?;
+
+            let mut rng = seed.map_or_else(Rng::from_entropy, |seed| Rng::new(seed as u64));
+            // SAFETY: we've verified this is an array via is_array()
+            let items = unsafe { to_array_iter_unchecked(lazy.as_raw_str()) };
+            sampling::reservoir(items, n as usize, &mut rng)?
+                .into_iter()
+                .map(|item| parser::lazyvalue_to_zval(item, &self.options))
+                .collect()
+        })
+    }
+
+    /// Hydrate the array of arrays at this path as one list: the elements
+    /// of each inner array in order, for paginated responses delivered as
+    /// pages of rows. Only one level is flattened unless `recursive`, which
+    /// flattens arrays at any depth; elements that aren't arrays are kept.
+    ///
+    /// # Example
+    /// ```php
+    /// $rows = Sift::query($json)->get("pages")->flattenArrays();
+    /// ```
+    pub fn flatten_arrays(&self, recursive: Option<bool>) -> Result<Vec<Zval>, SonicError> {
+        guard(|| {
+            let lazy = self.resolve_array()?;
+            let recursive = recursive.unwrap_or(false);
+
+            let mut items = Vec::new();
+            Self::flatten_into(lazy.as_raw_str(), recursive, 0, &mut items)?;
+            items
+                .into_iter()
+                .map(|item| parser::lazyvalue_to_zval(item, &self.options))
+                .collect()
+        })
     }
 
     /// Check if this points to an array.
     pub fn is_array(&self) -> Result<bool, SonicError> {
-        let lazy = self.resolve()?;
-        Ok(lazy.is_array())
+        guard(|| {
+            let lazy = self.resolve()?;
+            Ok(lazy.is_array())
+        })
     }
 
     /// Check if this points to an object.
     pub fn is_object(&self) -> Result<bool, SonicError> {
-        let lazy = self.resolve()?;
-        Ok(lazy.is_object())
+        guard(|| {
+            let lazy = self.resolve()?;
+            Ok(lazy.is_object())
+        })
     }
 
     /// Get the type of the current value as a string.
     pub fn get_type(&self) -> Result<String, SonicError> {
-        let lazy = self.resolve()?;
-        let t = if lazy.is_null() {
-            "null"
-        } else if lazy.is_boolean() {
-            "boolean"
-        } else if lazy.is_i64() || lazy.is_u64() {
-            "integer"
-        } else if lazy.is_f64() {
-            "float"
-        } else if lazy.is_str() {
-            "string"
-        } else if lazy.is_array() {
-            "array"
-        } else if lazy.is_object() {
-            "object"
-        } else {
-            "unknown"
-        };
-        Ok(t.to_string())
+        guard(|| {
+            let lazy = self.resolve()?;
+            Ok(parser::type_name(&lazy).to_string())
+        })
     }
 }
 
@@ -0,0 +1,134 @@
+//! Number quoting for `Sift::convertNumbersToStrings()`.
+//!
+//! Usage:
+//! ```php
+//! // Integers a JavaScript Number would round become strings
+//! $safe = Sift::convertNumbersToStrings($json);
+//! // Every number under /ids, and /total
+//! $safe = Sift::convertNumbersToStrings($json, ['/ids', '/total']);
+//! ```
+//!
+//! Quoted numbers keep their literal text (`12345678901234567890` becomes
+//! `"12345678901234567890"`), and everything else is copied verbatim, as
+//! in `patch`. Without pointers the whole document is scanned and numbers
+//! whose magnitude exceeds the threshold are quoted; the threshold
+//! defaults to `Number.MAX_SAFE_INTEGER`, so the output is safe to
+//! `JSON.parse()` without losing digits.
+
+use crate::errors::SonicError;
+use crate::parser::{self, MAX_DEPTH};
+use sonic_rs::{to_array_iter, to_object_iter, JsonType, LazyValue};
+use std::ops::Range;
+
+/// `Number.MAX_SAFE_INTEGER`, 2^53 - 1.
+pub const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// Quote the numbers in `json` under each of `pointers` (the whole
+/// document when `None`) whose magnitude exceeds `threshold`. Without a
+/// threshold, all numbers under `pointers` are quoted, or those beyond
+/// `MAX_SAFE_INTEGER` in the whole document.
+pub fn convert_numbers_to_strings(
+    json: &str,
+    pointers: Option<&[String]>,
+    threshold: Option<f64>,
+) -> Result<String, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+    if threshold.is_some_and(|threshold| threshold.is_nan() || threshold < 0.0) {
+        return Err(SonicError::InvalidOption(
+            "Threshold must be a non-negative number".to_string(),
+        ));
+    }
+    // Subtrees outside the pointers are skipped, so validate them up front
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+
+    let mut scan = Scan {
+        json,
+        threshold: match pointers {
+            Some(_) => threshold,
+            None => Some(threshold.unwrap_or(MAX_SAFE_INTEGER)),
+        },
+        ranges: Vec::new(),
+    };
+    match pointers {
+        Some(pointers) => {
+            for pointer in pointers {
+                let value = parser::lazy_at(json, pointer)?;
+                scan.visit(&value, 0)
+                    .map_err(|e| e.at_pointer(pointer.as_str()))?;
+            }
+        }
+        None => scan.visit(&parser::lazy_at(json, "")?, 0)?,
+    }
+
+    // Numbers under two pointers are found twice
+    scan.ranges.sort_by_key(|range| range.start);
+    scan.ranges.dedup();
+
+    let mut out = String::with_capacity(json.len() + scan.ranges.len() * 2);
+    let mut copied = 0;
+    for range in &scan.ranges {
+        out.push_str(&json[copied..range.start]);
+        // Number literals need no escaping
+        out.push('"');
+        out.push_str(&json[range.clone()]);
+        out.push('"');
+        copied = range.end;
+    }
+    out.push_str(&json[copied..]);
+    Ok(out)
+}
+
+struct Scan<'j> {
+    json: &'j str,
+    /// Magnitude a number must exceed to be quoted; `None` quotes all
+    threshold: Option<f64>,
+    /// Byte ranges of the numbers to quote
+    ranges: Vec<Range<usize>>,
+}
+
+impl Scan<'_> {
+    fn visit(&mut self, value: &LazyValue, depth: usize) -> Result<(), SonicError> {
+        if depth > MAX_DEPTH {
+            return Err(SonicError::ParseError(format!(
+                "Maximum nesting depth ({}) exceeded",
+                MAX_DEPTH
+            )));
+        }
+        let raw = value.as_raw_str();
+        match value.get_type() {
+            JsonType::Number => {
+                let raw = raw.trim();
+                if self.exceeds(raw) {
+                    // Values are slices of the document
+                    let start = raw.as_ptr() as usize - self.json.as_ptr() as usize;
+                    self.ranges.push(start..start + raw.len());
+                }
+            }
+            JsonType::Object => {
+                for member in to_object_iter(raw) {
+                    let (_, item) = member?;
+                    self.visit(&item, depth + 1)?;
+                }
+            }
+            JsonType::Array => {
+                for item in to_array_iter(raw) {
+                    self.visit(&item?, depth + 1)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Whether the number literal `raw` is beyond the threshold.
+    fn exceeds(&self, raw: &str) -> bool {
+        match self.threshold {
+            None => true,
+            // Out-of-range literals parse as infinity, which exceeds any threshold
+            Some(threshold) => raw
+                .parse::<f64>()
+                .map_or(true, |value| value.abs() > threshold),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Fixture generation for `Sift::randomize()`.
+//!
+//! Usage:
+//! ```php
+//! // Anonymize a production payload before attaching it to a bug report
+//! file_put_contents('fixture.json', Sift::randomize($payload, 42));
+//! ```
+//!
+//! The output has the same structure as the input: the same keys in the
+//! same order, the same array lengths and the same type at every path.
+//! Scalars are replaced:
+//!
+//! - strings keep their length in characters; letters become random letters
+//!   of the same case, digits random digits, and everything else
+//!   (punctuation, whitespace, `@`, `-`) is kept, so emails, dates and
+//!   UUIDs keep their shape
+//! - numbers keep their sign, digit counts, decimal point and exponent, so
+//!   integers stay integers of the same magnitude
+//! - booleans are random, null stays null
+//!
+//! Keys are not changed. The same seed always gives the same output.
+
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::parser::{self, MAX_DEPTH};
+use crate::sampling::Rng;
+use sonic_rs::{to_array_iter, to_object_iter, JsonType, JsonValueTrait, LazyValue};
+
+const LOWER: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPER: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"0123456789";
+
+/// A compact document shaped like `json` with randomized scalars.
+pub fn randomize(json: &str, rng: &mut Rng) -> Result<String, SonicError> {
+    parser::check_input_size(json)?;
+    // Pointer lookups don't look past the value they find
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+    let root = parser::lazy_at(json, "")?;
+    let mut out = String::with_capacity(json.len());
+    write_random(&mut out, &root, rng, 0)?;
+    Ok(out)
+}
+
+/// Pick a byte from `set`.
+fn pick(rng: &mut Rng, set: &[u8]) -> char {
+    set[rng.below(set.len() as u64) as usize] as char
+}
+
+/// Append a randomized copy of `value` to `out`.
+fn write_random(
+    out: &mut String,
+    value: &LazyValue,
+    rng: &mut Rng,
+    depth: usize,
+) -> Result<(), SonicError> {
+    if depth > MAX_DEPTH {
+        return Err(SonicError::ParseError(format!(
+            "Maximum nesting depth ({}) exceeded",
+            MAX_DEPTH
+        )));
+    }
+    match value.get_type() {
+        JsonType::Object => {
+            out.push('{');
+            for (i, member) in to_object_iter(value.as_raw_str()).enumerate() {
+                let (key, item) = member?;
+                if i > 0 {
+                    out.push(',');
+                }
+                encoder::write_string(out, key.as_bytes())?;
+                out.push(':');
+                write_random(out, &item, rng, depth + 1)?;
+            }
+            out.push('}');
+        }
+        JsonType::Array => {
+            out.push('[');
+            for (i, item) in to_array_iter(value.as_raw_str()).enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_random(out, &item?, rng, depth + 1)?;
+            }
+            out.push(']');
+        }
+        JsonType::String => {
+            let text = value.as_str().unwrap_or_default();
+            let random: String = text
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_digit() {
+                        pick(rng, DIGITS)
+                    } else if c.is_uppercase() {
+                        pick(rng, UPPER)
+                    } else if c.is_alphabetic() {
+                        pick(rng, LOWER)
+                    } else {
+                        c
+                    }
+                })
+                .collect();
+            encoder::write_string(out, random.as_bytes())?;
+        }
+        JsonType::Number => write_number(out, value.as_raw_str().trim(), rng),
+        JsonType::Boolean => out.push_str(if rng.below(2) == 0 { "false" } else { "true" }),
+        JsonType::Null => out.push_str("null"),
+    }
+    Ok(())
+}
+
+/// Append a number with the same shape as `raw`: mantissa digits are
+/// replaced, without a leading zero in a multi-digit integer part, and the
+/// sign, decimal point and exponent are kept.
+fn write_number(out: &mut String, raw: &str, rng: &mut Rng) {
+    let (mantissa, exponent) = match raw.find(['e', 'E']) {
+        Some(at) => raw.split_at(at),
+        None => (raw, ""),
+    };
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", mantissa),
+    };
+    let integer_len = mantissa.find('.').unwrap_or(mantissa.len());
+    out.push_str(sign);
+    for (i, c) in mantissa.chars().enumerate() {
+        if !c.is_ascii_digit() {
+            out.push(c);
+        } else if i == 0 && integer_len > 1 {
+            out.push(pick(rng, &DIGITS[1..]));
+        } else {
+            out.push(pick(rng, DIGITS));
+        }
+    }
+    out.push_str(exponent);
+}
@@ -0,0 +1,198 @@
+//! Error-tolerant decoding of record streams for `Sift::decodeRecords()`.
+//!
+//! Usage:
+//! ```php
+//! $result = Sift::decodeRecords(file_get_contents('export.json'));
+//! $importer->insert($result['data']);
+//! foreach ($result['errors'] as $e) {
+//!     $log->warning("record {$e['index']} at byte {$e['offset']}: {$e['message']}");
+//! }
+//! ```
+//!
+//! The input is split into elements without parsing it as a whole: a
+//! top-level array at its top-level commas (tracking strings and nesting),
+//! anything else at newlines as NDJSON. Each element is then decoded on
+//! its own, so a malformed or unconvertible one costs that element only.
+//! Splitting is best effort: an element with unbalanced brackets can
+//! swallow the elements after it, which are then reported with it.
+
+use crate::errors::SonicError;
+use crate::options::DecodeOptions;
+use crate::parser;
+use ext_php_rs::error::Error;
+use ext_php_rs::types::{ZendHashTable, Zval};
+
+/// What takes a failed element's place in the data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnError {
+    /// Leave it out; later elements move up
+    Skip,
+    /// Keep its position with a null
+    Null,
+}
+
+impl OnError {
+    pub fn from_name(name: &str) -> Result<Self, SonicError> {
+        match name {
+            "skip" => Ok(OnError::Skip),
+            "null" => Ok(OnError::Null),
+            _ => Err(SonicError::InvalidOption(format!(
+                "Unknown onError mode '{}', expected 'skip' or 'null'",
+                name
+            ))),
+        }
+    }
+}
+
+/// Error for a failed insert into a result array.
+fn insert_error(e: Error) -> SonicError {
+    SonicError::TypeError(format!("Failed to insert object key: {}", e))
+}
+
+/// Whether `b` is JSON whitespace.
+fn is_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+/// The byte range of each element of the array opening at `open`, and
+/// an error for what follows it: content after the closing bracket, or
+/// the missing bracket of a truncated array.
+fn array_elements(
+    bytes: &[u8],
+    open: usize,
+) -> (Vec<(usize, usize)>, Option<(&'static str, usize)>) {
+    let mut elements = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = open + 1;
+    let mut pos = open + 1;
+    while pos < bytes.len() {
+        let b = bytes[pos];
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+        } else {
+            match b {
+                b'"' => in_string = true,
+                b'[' | b'{' => depth += 1,
+                // A stray closer inside an element doesn't end the array
+                b'}' => depth = depth.saturating_sub(1),
+                b']' if depth > 0 => depth -= 1,
+                b']' => {
+                    // `[]` and a trailing `[... ]` close without a new element
+                    if !elements.is_empty() || bytes[start..pos].iter().any(|&b| !is_space(b)) {
+                        elements.push((start, pos));
+                    }
+                    let rest = bytes[pos + 1..].iter().position(|&b| !is_space(b));
+                    let tail = rest.map(|r| ("Unexpected content after the array", pos + 1 + r));
+                    return (elements, tail);
+                }
+                b',' if depth == 0 => {
+                    elements.push((start, pos));
+                    start = pos + 1;
+                }
+                _ => {}
+            }
+        }
+        pos += 1;
+    }
+    // Truncated array: the rest is the last element, and may still decode
+    if bytes[start..].iter().any(|&b| !is_space(b)) {
+        elements.push((start, bytes.len()));
+    }
+    (elements, Some(("Unterminated array", bytes.len())))
+}
+
+/// The byte range of each non-blank line.
+fn lines(bytes: &[u8]) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for (pos, &b) in bytes.iter().enumerate() {
+        if b == b'\n' {
+            ranges.push((start, pos));
+            start = pos + 1;
+        }
+    }
+    ranges.push((start, bytes.len()));
+    ranges.retain(|&(start, end)| bytes[start..end].iter().any(|&b| !is_space(b)));
+    ranges
+}
+
+/// Decode one element, with error offsets relative to the whole input.
+fn decode_element(
+    bytes: &[u8],
+    (start, end): (usize, usize),
+    options: &DecodeOptions,
+) -> Result<Zval, (String, usize)> {
+    let slice = &bytes[start..end];
+    let leading = slice.iter().take_while(|&&b| is_space(b)).count();
+    if leading == slice.len() {
+        return Err(("Empty element".to_string(), start));
+    }
+    parser::decode_text(slice, options)
+        .and_then(|text| parser::decode(&text, options))
+        .map_err(|e| {
+            let offset = start + e.offset().unwrap_or(leading);
+            (e.to_string(), offset)
+        })
+}
+
+/// Decode the elements of a top-level array, or the lines of NDJSON, one
+/// at a time, as `['data' => list, 'errors' => list]`. Each error is
+/// `['index' => int|null, 'message' => string, 'offset' => int]`, where
+/// `index` is the element's position in the input (null for problems
+/// with the array itself) and `offset` is a byte offset into the input.
+pub fn decode_records(
+    bytes: &[u8],
+    options: &DecodeOptions,
+    on_error: OnError,
+) -> Result<Zval, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(bytes)?;
+
+    let first = bytes.iter().position(|&b| !is_space(b));
+    let (elements, tail) = match first {
+        Some(open) if bytes[open] == b'[' => array_elements(bytes, open),
+        _ => (lines(bytes), None),
+    };
+
+    let mut data = ZendHashTable::with_capacity(elements.len() as u32);
+    let mut errors = ZendHashTable::new();
+    let mut add_error = |index: Option<usize>, message: String, offset: usize| {
+        let mut error = ZendHashTable::new();
+        error
+            .insert("index", index.map(|i| i as i64))
+            .and_then(|_| error.insert("message", message))
+            .and_then(|_| error.insert("offset", offset as i64))
+            .map_err(insert_error)?;
+        errors.push(error).map_err(insert_error)
+    };
+    for (index, range) in elements.into_iter().enumerate() {
+        match decode_element(bytes, range, options) {
+            Ok(value) => data.push(value).map_err(insert_error)?,
+            Err((message, offset)) => {
+                add_error(Some(index), message, offset)?;
+                if on_error == OnError::Null {
+                    data.push(()).map_err(insert_error)?;
+                }
+            }
+        }
+    }
+    if let Some((message, offset)) = tail {
+        add_error(None, message.to_string(), offset)?;
+    }
+
+    let mut result = ZendHashTable::new();
+    result
+        .insert("data", data)
+        .and_then(|_| result.insert("errors", errors))
+        .map_err(insert_error)?;
+    let mut zval = Zval::new();
+    zval.set_hashtable(result);
+    Ok(zval)
+}
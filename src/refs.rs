@@ -0,0 +1,137 @@
+//! Internal `$ref` resolution (JSON Reference, as used by OpenAPI and JSON
+//! Schema) for `Query::followRefs()`.
+//!
+//! Usage:
+//! ```php
+//! $spec = Sift::query($openapi)->followRefs();
+//! // "schema": {"$ref": "#/components/schemas/User"} is followed transparently
+//! $props = $spec->pointer('/paths/~1users/get/responses/200/content/application~1json/schema/properties')->value();
+//! ```
+
+use crate::errors::SonicError;
+use crate::parser;
+use faststr::FastStr;
+use sonic_rs::{JsonValueTrait, LazyValue, PointerNode};
+
+/// `value`'s text as a slice of `json`, which it was read from, so the
+/// result can outlive `value`.
+fn text_in<'a>(json: &'a str, value: &LazyValue) -> &'a str {
+    let raw = value.as_raw_str();
+    let start = raw.as_ptr() as usize - json.as_ptr() as usize;
+    &json[start..start + raw.len()]
+}
+
+/// One step down from `value`: the member `segment` of an object, or the
+/// element of an array if `segment` is an index. Numeric segments are
+/// keys on objects, as in `/responses/200`.
+fn step<'a>(json: &'a str, value: &LazyValue, segment: &str) -> Option<LazyValue<'a>> {
+    let node = if value.is_array() {
+        PointerNode::Index(segment.parse().ok()?)
+    } else if value.is_object() {
+        PointerNode::Key(FastStr::new(segment))
+    } else {
+        return None;
+    };
+    sonic_rs::get(text_in(json, value), &[node]).ok()
+}
+
+/// The reference `value` makes, if it is a `{"$ref": "..."}` object.
+fn reference(value: &LazyValue) -> Option<String> {
+    if !value.is_object() {
+        return None;
+    }
+    value.get("$ref")?.as_str().map(str::to_string)
+}
+
+/// Decode %XX escapes in a URI fragment.
+fn percent_decode(fragment: &str) -> Option<String> {
+    let bytes = fragment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Follow `value` through any chain of references. `stack` holds the
+/// references being resolved by the callers; meeting one again is a cycle.
+fn follow<'a>(
+    json: &'a str,
+    mut value: LazyValue<'a>,
+    stack: &mut Vec<String>,
+) -> Result<LazyValue<'a>, SonicError> {
+    let entered = stack.len();
+    while let Some(reference) = reference(&value) {
+        if stack.contains(&reference) {
+            return Err(SonicError::InvalidPointer(format!(
+                "Circular $ref '{}'",
+                reference
+            )));
+        }
+        if stack.len() >= parser::MAX_DEPTH {
+            return Err(SonicError::InvalidPointer(format!(
+                "Too many nested $refs (max {})",
+                parser::MAX_DEPTH
+            )));
+        }
+        stack.push(reference.clone());
+        value = target(json, &reference, stack)?;
+    }
+    stack.truncate(entered);
+    Ok(value)
+}
+
+/// The value an internal reference (`#/components/schemas/User`) points
+/// to. References met on the way are followed.
+fn target<'a>(
+    json: &'a str,
+    reference: &str,
+    stack: &mut Vec<String>,
+) -> Result<LazyValue<'a>, SonicError> {
+    let Some(fragment) = reference.strip_prefix('#') else {
+        return Err(SonicError::InvalidPointer(format!(
+            "External $ref '{}' is not supported",
+            reference
+        )));
+    };
+    let pointer = percent_decode(fragment)
+        .filter(|pointer| pointer.is_empty() || pointer.starts_with('/'))
+        .ok_or_else(|| SonicError::InvalidPointer(format!("Invalid $ref '{}'", reference)))?;
+
+    let mut value = sonic_rs::get(json, &[] as &[PointerNode])?;
+    for segment in pointer.split('/').skip(1) {
+        let segment = segment.replace("~1", "/").replace("~0", "~");
+        value = follow(json, value, stack)?;
+        value = step(json, &value, &segment).ok_or_else(|| {
+            SonicError::KeyNotFound(format!("$ref '{}' not found", reference)).at_pointer(&pointer)
+        })?;
+    }
+    Ok(value)
+}
+
+/// Resolve the path `segments` (unescaped) from the root of `json`,
+/// following references before each step and at the end. Returns None if
+/// the path doesn't exist.
+pub fn resolve<'a>(
+    json: &'a str,
+    segments: impl Iterator<Item = String>,
+) -> Result<Option<LazyValue<'a>>, SonicError> {
+    let mut stack = Vec::new();
+    let mut value = sonic_rs::get(json, &[] as &[PointerNode])?;
+    for segment in segments {
+        value = follow(json, value, &mut stack)?;
+        let Some(next) = step(json, &value, &segment) else {
+            return Ok(None);
+        };
+        value = next;
+    }
+    follow(json, value, &mut stack).map(Some)
+}
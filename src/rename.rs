@@ -0,0 +1,131 @@
+//! Structural object key renaming for `Sift::renameKeys()`.
+//!
+//! Usage:
+//! ```php
+//! // v1 -> v2 adapter: only the renamed keys change
+//! $v2 = Sift::renameKeys($v1, ['userName' => 'username', 'mail' => 'email']);
+//! $v2 = Sift::renameKeys($v1, ['id' => 'uuid'], '/data/items');
+//! ```
+//!
+//! Every object key equal to one of the mapped names, at any depth under
+//! the pointer (the whole document by default), is replaced by its new
+//! name. Only the key tokens are rewritten; member order, values and
+//! whitespace keep their bytes, as in `patch`. Keys are matched after
+//! unescaping, so `"user"` matches `user`.
+
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::parser::{self, MAX_DEPTH};
+use ext_php_rs::types::{ArrayKey, ZendHashTable};
+use sonic_rs::{to_array_iter, to_object_iter, JsonType, LazyValue};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Rename the object keys of `json` under `pointer` per `map`
+/// (old name => new name).
+pub fn rename_keys(
+    json: &str,
+    map: &ZendHashTable,
+    pointer: Option<&str>,
+) -> Result<String, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+    let names = compile(map)?;
+    // Key tokens are located in the raw text, so validate it up front
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+
+    let pointer = pointer.unwrap_or("");
+    let mut walk = Walk {
+        json,
+        names: &names,
+        splices: Vec::new(),
+    };
+    walk.visit(&parser::lazy_at(json, pointer)?, 0)
+        .map_err(|e| e.at_pointer(pointer))?;
+
+    let mut out = String::with_capacity(json.len());
+    let mut copied = 0;
+    for (range, text) in &walk.splices {
+        out.push_str(&json[copied..range.start]);
+        out.push_str(text);
+        copied = range.end;
+    }
+    out.push_str(&json[copied..]);
+    Ok(out)
+}
+
+/// Read the mapping into old name => new key token (quoted and escaped).
+fn compile(map: &ZendHashTable) -> Result<HashMap<String, String>, SonicError> {
+    let mut names = HashMap::with_capacity(map.len());
+    for (key, value) in map.iter() {
+        let old = match key {
+            ArrayKey::String(name) => name,
+            // PHP turns numeric string keys into integers
+            ArrayKey::Long(index) => index.to_string(),
+        };
+        let Some(new) = value.str() else {
+            return Err(SonicError::InvalidOption(format!(
+                "New name for key '{}' must be a string",
+                old
+            )));
+        };
+        let mut token = String::with_capacity(new.len() + 2);
+        encoder::write_string(&mut token, new.as_bytes())?;
+        names.insert(old, token);
+    }
+    Ok(names)
+}
+
+struct Walk<'a> {
+    json: &'a str,
+    names: &'a HashMap<String, String>,
+    /// Key token ranges and their replacements, in document order
+    splices: Vec<(Range<usize>, String)>,
+}
+
+impl Walk<'_> {
+    /// Offset of the JSON text `raw` (trimmed) in the document.
+    fn offset(&self, raw: &str) -> usize {
+        // Values are slices of the document
+        raw.as_ptr() as usize - self.json.as_ptr() as usize
+    }
+
+    fn visit(&mut self, value: &LazyValue, depth: usize) -> Result<(), SonicError> {
+        if depth > MAX_DEPTH {
+            return Err(SonicError::ParseError(format!(
+                "Maximum nesting depth ({}) exceeded",
+                MAX_DEPTH
+            )));
+        }
+        let raw = value.as_raw_str().trim();
+        match value.get_type() {
+            JsonType::Object => {
+                // After '{', then after each member's value
+                let mut gap_start = self.offset(raw) + 1;
+                for member in to_object_iter(raw) {
+                    let (key, item) = member?;
+                    let item_raw = item.as_raw_str().trim();
+                    let item_start = self.offset(item_raw);
+                    if let Some(token) = self.names.get(key.as_str()) {
+                        // Between the two values there is only whitespace,
+                        // a comma, the key token and the colon
+                        let gap = &self.json[gap_start..item_start];
+                        if let (Some(open), Some(close)) = (gap.find('"'), gap.rfind('"')) {
+                            let range = gap_start + open..gap_start + close + 1;
+                            self.splices.push((range, token.clone()));
+                        }
+                    }
+                    self.visit(&item, depth + 1)?;
+                    gap_start = item_start + item_raw.len();
+                }
+            }
+            JsonType::Array => {
+                for item in to_array_iter(raw) {
+                    self.visit(&item?, depth + 1)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+//! Request body reading for `Sift::fromRequest()`.
+//!
+//! Usage:
+//! ```php
+//! $body = Sift::fromRequest();              // php://input, up to 64 MB
+//! $body = Sift::fromRequest(1 << 20);       // at most 1 MiB
+//! $email = $body->pointer('/user/email')->string();
+//! ```
+//!
+//! The body is read from `php://input` into Rust and handed to a Query, so
+//! no PHP string holding it outlives the call. A `Content-Encoding: gzip`
+//! body is inflated. The limit applies to the bytes read and to the
+//! inflated text alike, so a compressed body can't expand past it.
+
+use crate::errors::SonicError;
+use crate::parser::MAX_INPUT_SIZE;
+use crate::sniff;
+use ext_php_rs::types::ZendCallable;
+use ext_php_rs::zend::ProcessGlobals;
+
+/// The request body, inflated if it was sent gzip-encoded, if it is at
+/// most `max_bytes` long.
+pub fn read_body(max_bytes: Option<i64>) -> Result<Vec<u8>, SonicError> {
+    let limit = match max_bytes {
+        None => MAX_INPUT_SIZE,
+        Some(bytes) if bytes < 1 || bytes as u64 > MAX_INPUT_SIZE as u64 => {
+            return Err(SonicError::InvalidOption(format!(
+                "Body size limit must be between 1 and {}, got {}",
+                MAX_INPUT_SIZE, bytes
+            )))
+        }
+        Some(bytes) => bytes as usize,
+    };
+
+    let body = read_input(limit)?;
+    if body.len() > limit {
+        // Reading stopped just past the limit; the real size is unknown
+        return Err(SonicError::InputTooLarge {
+            size: body.len(),
+            limit,
+        });
+    }
+    let body = match content_encoding().as_deref() {
+        None | Some("" | "identity") => body,
+        Some("gzip" | "x-gzip") => sniff::inflate(&body, limit)?,
+        Some(other) => {
+            return Err(SonicError::ParseError(format!(
+                "Unsupported Content-Encoding '{}'",
+                other
+            )))
+        }
+    };
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Err(SonicError::ParseError("Empty request body".to_string()));
+    }
+    Ok(body)
+}
+
+/// Up to `limit + 1` bytes of `php://input`.
+fn read_input(limit: usize) -> Result<Vec<u8>, SonicError> {
+    let file_get_contents = ZendCallable::try_from_name("file_get_contents")
+        .map_err(|_| SonicError::Internal("file_get_contents() is unavailable".to_string()))?;
+    let contents = file_get_contents
+        .try_call(vec![
+            &"php://input",
+            &false,
+            &(),
+            &0i64,
+            &(limit as i64 + 1),
+        ])
+        .map_err(|e| SonicError::IoError(format!("Cannot read the request body: {}", e)))?;
+    match contents.binary_slice::<u8>() {
+        Some(bytes) => Ok(bytes.to_vec()),
+        None => Err(SonicError::IoError(
+            "Cannot read the request body".to_string(),
+        )),
+    }
+}
+
+/// The request's `Content-Encoding` header, lowercased, if it has one.
+fn content_encoding() -> Option<String> {
+    let globals = ProcessGlobals::get();
+    let encoding = globals
+        .http_server_vars()?
+        .get("HTTP_CONTENT_ENCODING")?
+        .str()?
+        .trim()
+        .to_ascii_lowercase();
+    Some(encoding)
+}
@@ -0,0 +1,63 @@
+//! Reservoir sampling for `Query::sample()`.
+//!
+//! Picks a uniform sample from a stream of unknown length in one pass,
+//! holding only the sampled items (Vitter's Algorithm R).
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// SplitMix64 - a small, fast generator; more than enough for sampling
+/// and reproducible from a caller-chosen seed.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// A generator seeded from the per-process random keys std uses for
+    /// HashMap, so unseeded samples differ between calls and processes.
+    pub fn from_entropy() -> Self {
+        Self::new(RandomState::new().build_hasher().finish())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound` (multiply-shift; bias is below 2^-32 for
+    /// any bound that fits in memory).
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        ((u128::from(self.next_u64()) * u128::from(bound)) >> 64) as u64
+    }
+}
+
+/// Sample up to `n` items uniformly from `items`, returned in their
+/// original order. Stops at the first error.
+pub fn reservoir<T, E>(
+    items: impl Iterator<Item = Result<T, E>>,
+    n: usize,
+    rng: &mut Rng,
+) -> Result<Vec<T>, E> {
+    let mut sample: Vec<(usize, T)> = Vec::with_capacity(n.min(1024));
+    for (i, item) in items.enumerate() {
+        let item = item?;
+        if sample.len() < n {
+            sample.push((i, item));
+        } else {
+            let j = rng.below(i as u64 + 1) as usize;
+            if j < n {
+                sample[j] = (i, item);
+            }
+        }
+    }
+    sample.sort_unstable_by_key(|&(i, _)| i);
+    Ok(sample.into_iter().map(|(_, item)| item).collect())
+}
@@ -0,0 +1,604 @@
+//! JSON Schema validation of a subtree for `Query::matchesSchema()`.
+//!
+//! Usage:
+//! ```php
+//! foreach ($envelope->get('order')->matchesSchema($orderSchema) as $v) {
+//!     $errors[] = "{$v['pointer']}: {$v['message']}";
+//! }
+//! ```
+//!
+//! Supports the structural keywords of draft 2020-12 (and their draft 7
+//! spellings): `type`, `enum`, `const`, numeric and length bounds,
+//! `multipleOf`, `required`, `properties`, `additionalProperties`,
+//! `prefixItems`, `items`, `uniqueItems`, `allOf`, `anyOf`, `oneOf`, `not`
+//! and internal `$ref`s. Keywords needing a regex engine (`pattern`,
+//! `patternProperties`) are rejected when the schema is compiled rather
+//! than silently passing; `format` is an annotation and is not checked.
+
+use crate::artifacts::{self, Artifact};
+use crate::errors::SonicError;
+use crate::parser::{self, MAX_DEPTH};
+use ext_php_rs::error::Error;
+use ext_php_rs::types::{ZendHashTable, Zval};
+use sonic_rs::{JsonContainerTrait, JsonType, JsonValueTrait, Value};
+
+/// Keywords this validator cannot check.
+const UNSUPPORTED: &[&str] = &[
+    "pattern",
+    "patternProperties",
+    "dependentSchemas",
+    "unevaluatedProperties",
+    "unevaluatedItems",
+    "$dynamicRef",
+];
+
+/// Type names a schema may use.
+const TYPES: &[&str] = &[
+    "null", "boolean", "object", "array", "number", "integer", "string",
+];
+
+/// A failed check: where in the subtree, which keyword, and why.
+pub struct Violation {
+    pointer: String,
+    keyword: &'static str,
+    message: String,
+}
+
+/// Error for a schema this validator cannot use.
+fn schema_error(at: &str, detail: String) -> SonicError {
+    SonicError::InvalidOption(format!("Invalid schema at '{}': {}", at, detail))
+}
+
+/// Append an escaped RFC 6901 segment to `pointer`.
+fn push_segment(pointer: &mut String, segment: &str) {
+    pointer.push('/');
+    pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+}
+
+/// Parse `schema_json` and check that every keyword in it can be
+/// validated, or read it back checked from the artifact cache.
+pub fn compile(schema_json: &str) -> Result<Value, SonicError> {
+    artifacts::fetch_or_compile(schema_json.as_bytes(), || {
+        let schema = parser::parse_dom(schema_json)?;
+        check(&schema, &mut String::new(), 0)?;
+        Ok(schema)
+    })
+}
+
+impl Artifact for Value {
+    const KIND: &'static str = "schema";
+
+    fn to_artifact(&self) -> Result<String, SonicError> {
+        Ok(sonic_rs::to_string(self)?)
+    }
+
+    /// Only parsed: the schema was checked before it was stored.
+    fn from_artifact(text: &str) -> Result<Self, SonicError> {
+        parser::parse_dom(text)
+    }
+}
+
+/// Check a (sub)schema at `at` in the schema document.
+fn check(schema: &Value, at: &mut String, depth: usize) -> Result<(), SonicError> {
+    if depth > MAX_DEPTH {
+        return Err(schema_error(at, "nesting too deep".to_string()));
+    }
+    if schema.is_boolean() {
+        return Ok(());
+    }
+    let Some(object) = schema.as_object() else {
+        return Err(schema_error(
+            at,
+            "a schema must be an object or a boolean".to_string(),
+        ));
+    };
+    for (keyword, value) in object.iter() {
+        if UNSUPPORTED.contains(&keyword) {
+            return Err(schema_error(
+                at,
+                format!("keyword '{}' is not supported", keyword),
+            ));
+        }
+        let len = at.len();
+        push_segment(at, keyword);
+        match keyword {
+            "type" => {
+                let names: Vec<&Value> = match value.as_array() {
+                    Some(list) => list.iter().collect(),
+                    None => vec![value],
+                };
+                for name in names {
+                    if !name.as_str().is_some_and(|n| TYPES.contains(&n)) {
+                        return Err(schema_error(at, "unknown type".to_string()));
+                    }
+                }
+            }
+            "$ref" => {
+                if !value.as_str().is_some_and(|r| r.starts_with('#')) {
+                    return Err(schema_error(
+                        at,
+                        "only internal references ('#/...') are supported".to_string(),
+                    ));
+                }
+            }
+            "not" | "additionalProperties" | "items" => check(value, at, depth + 1)?,
+            "allOf" | "anyOf" | "oneOf" | "prefixItems" => {
+                let Some(list) = value.as_array() else {
+                    return Err(schema_error(at, "expected an array of schemas".to_string()));
+                };
+                for (i, item) in list.iter().enumerate() {
+                    let len = at.len();
+                    push_segment(at, &i.to_string());
+                    check(item, at, depth + 1)?;
+                    at.truncate(len);
+                }
+            }
+            "properties" | "$defs" | "definitions" => {
+                let Some(members) = value.as_object() else {
+                    return Err(schema_error(
+                        at,
+                        "expected an object of schemas".to_string(),
+                    ));
+                };
+                for (name, item) in members.iter() {
+                    let len = at.len();
+                    push_segment(at, name);
+                    check(item, at, depth + 1)?;
+                    at.truncate(len);
+                }
+            }
+            _ => {}
+        }
+        at.truncate(len);
+    }
+    Ok(())
+}
+
+/// Validate `value` against the compiled `schema`. Violation pointers are
+/// relative to `value`; an empty list means it matches.
+pub fn validate(value: &Value, schema: &Value) -> Result<Vec<Violation>, SonicError> {
+    let mut validator = Validator {
+        root: schema,
+        violations: Vec::new(),
+    };
+    validator.validate(value, schema, &mut String::new(), 0)?;
+    Ok(validator.violations)
+}
+
+/// The violations of `value` as a list of
+/// `['pointer' => ..., 'keyword' => ..., 'message' => ...]` arrays.
+pub fn violations_to_zval(violations: Vec<Violation>) -> Result<Zval, SonicError> {
+    let insert_error =
+        |e: Error| SonicError::TypeError(format!("Failed to insert object key: {}", e));
+    let mut list = ZendHashTable::new();
+    for violation in violations {
+        let mut entry = ZendHashTable::new();
+        entry
+            .insert("pointer", violation.pointer)
+            .and_then(|_| entry.insert("keyword", violation.keyword))
+            .and_then(|_| entry.insert("message", violation.message))
+            .map_err(insert_error)?;
+        list.push(entry).map_err(insert_error)?;
+    }
+    let mut zval = Zval::new();
+    zval.set_hashtable(list);
+    Ok(zval)
+}
+
+struct Validator<'s> {
+    /// The whole schema, for `$ref`
+    root: &'s Value,
+    violations: Vec<Violation>,
+}
+
+impl<'s> Validator<'s> {
+    fn fail(&mut self, pointer: &str, keyword: &'static str, message: String) {
+        self.violations.push(Violation {
+            pointer: pointer.to_string(),
+            keyword,
+            message,
+        });
+    }
+
+    /// Whether `value` matches `schema`, without recording violations.
+    fn matches(
+        &mut self,
+        value: &Value,
+        schema: &'s Value,
+        depth: usize,
+    ) -> Result<bool, SonicError> {
+        let before = self.violations.len();
+        self.validate(value, schema, &mut String::new(), depth)?;
+        let matched = self.violations.len() == before;
+        self.violations.truncate(before);
+        Ok(matched)
+    }
+
+    fn validate(
+        &mut self,
+        value: &Value,
+        schema: &'s Value,
+        pointer: &mut String,
+        depth: usize,
+    ) -> Result<(), SonicError> {
+        // `$ref` cycles that never descend into the value end here too
+        if depth > MAX_DEPTH {
+            return Err(SonicError::InvalidOption(format!(
+                "Schema nesting exceeds maximum depth ({})",
+                MAX_DEPTH
+            )));
+        }
+        if let Some(allowed) = schema.as_bool() {
+            if !allowed {
+                self.fail(pointer, "false", "No value is allowed here".to_string());
+            }
+            return Ok(());
+        }
+        let Some(keywords) = schema.as_object() else {
+            return Ok(());
+        };
+
+        if let Some(reference) = keywords.get(&"$ref").and_then(|r| r.as_str()) {
+            let target = self.resolve_ref(reference)?;
+            self.validate(value, target, pointer, depth + 1)?;
+        }
+        if let Some(types) = keywords.get(&"type") {
+            let names: Vec<&str> = match types.as_array() {
+                Some(list) => list.iter().filter_map(|t| t.as_str()).collect(),
+                None => types.as_str().into_iter().collect(),
+            };
+            if !names.iter().any(|name| type_matches(value, name)) {
+                self.fail(
+                    pointer,
+                    "type",
+                    format!("Expected {}, got {}", names.join(" or "), type_name(value)),
+                );
+            }
+        }
+        if let Some(allowed) = keywords.get(&"enum").and_then(|e| e.as_array()) {
+            if !allowed.iter().any(|candidate| json_equal(value, candidate)) {
+                self.fail(
+                    pointer,
+                    "enum",
+                    "Value is not one of the allowed values".to_string(),
+                );
+            }
+        }
+        if let Some(expected) = keywords.get(&"const") {
+            if !json_equal(value, expected) {
+                self.fail(
+                    pointer,
+                    "const",
+                    "Value does not equal the constant".to_string(),
+                );
+            }
+        }
+
+        if let Some(n) = value.as_f64() {
+            self.check_number(n, keywords, pointer);
+        }
+        if let Some(text) = value.as_str() {
+            let length = text.chars().count() as f64;
+            if let Some(min) = keywords.get(&"minLength").and_then(|m| m.as_f64()) {
+                if length < min {
+                    self.fail(
+                        pointer,
+                        "minLength",
+                        format!("String is shorter than {} characters", min),
+                    );
+                }
+            }
+            if let Some(max) = keywords.get(&"maxLength").and_then(|m| m.as_f64()) {
+                if length > max {
+                    self.fail(
+                        pointer,
+                        "maxLength",
+                        format!("String is longer than {} characters", max),
+                    );
+                }
+            }
+        }
+        if let Some(items) = value.as_array() {
+            self.check_array(items, keywords, pointer, depth)?;
+        }
+        if let Some(members) = value.as_object() {
+            self.check_object(members, keywords, pointer, depth)?;
+        }
+
+        if let Some(all) = keywords.get(&"allOf").and_then(|a| a.as_array()) {
+            for sub in all.iter() {
+                self.validate(value, sub, pointer, depth + 1)?;
+            }
+        }
+        if let Some(any) = keywords.get(&"anyOf").and_then(|a| a.as_array()) {
+            let mut matched = false;
+            for sub in any.iter() {
+                if self.matches(value, sub, depth + 1)? {
+                    matched = true;
+                    break;
+                }
+            }
+            if !matched {
+                self.fail(
+                    pointer,
+                    "anyOf",
+                    "Value matches none of the schemas".to_string(),
+                );
+            }
+        }
+        if let Some(one) = keywords.get(&"oneOf").and_then(|a| a.as_array()) {
+            let mut count = 0;
+            for sub in one.iter() {
+                if self.matches(value, sub, depth + 1)? {
+                    count += 1;
+                }
+            }
+            if count != 1 {
+                self.fail(
+                    pointer,
+                    "oneOf",
+                    format!(
+                        "Value matches {} of the schemas, expected exactly one",
+                        count
+                    ),
+                );
+            }
+        }
+        if let Some(not) = keywords.get(&"not") {
+            if self.matches(value, not, depth + 1)? {
+                self.fail(
+                    pointer,
+                    "not",
+                    "Value matches a schema it must not".to_string(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn check_number(&mut self, n: f64, keywords: &sonic_rs::Object, pointer: &str) {
+        let bound = |name: &str| keywords.get(&name).and_then(|b| b.as_f64());
+        if let Some(min) = bound("minimum") {
+            if n < min {
+                self.fail(
+                    pointer,
+                    "minimum",
+                    format!("Value must be at least {}", min),
+                );
+            }
+        }
+        if let Some(max) = bound("maximum") {
+            if n > max {
+                self.fail(pointer, "maximum", format!("Value must be at most {}", max));
+            }
+        }
+        if let Some(min) = bound("exclusiveMinimum") {
+            if n <= min {
+                self.fail(
+                    pointer,
+                    "exclusiveMinimum",
+                    format!("Value must be greater than {}", min),
+                );
+            }
+        }
+        if let Some(max) = bound("exclusiveMaximum") {
+            if n >= max {
+                self.fail(
+                    pointer,
+                    "exclusiveMaximum",
+                    format!("Value must be less than {}", max),
+                );
+            }
+        }
+        if let Some(step) = bound("multipleOf").filter(|s| *s > 0.0) {
+            let quotient = n / step;
+            if (quotient - quotient.round()).abs() > 1e-9 {
+                self.fail(
+                    pointer,
+                    "multipleOf",
+                    format!("Value must be a multiple of {}", step),
+                );
+            }
+        }
+    }
+
+    fn check_array(
+        &mut self,
+        items: &sonic_rs::Array,
+        keywords: &'s sonic_rs::Object,
+        pointer: &mut String,
+        depth: usize,
+    ) -> Result<(), SonicError> {
+        let count = items.len() as f64;
+        if let Some(min) = keywords.get(&"minItems").and_then(|m| m.as_f64()) {
+            if count < min {
+                self.fail(
+                    pointer,
+                    "minItems",
+                    format!("Array has fewer than {} items", min),
+                );
+            }
+        }
+        if let Some(max) = keywords.get(&"maxItems").and_then(|m| m.as_f64()) {
+            if count > max {
+                self.fail(
+                    pointer,
+                    "maxItems",
+                    format!("Array has more than {} items", max),
+                );
+            }
+        }
+        if keywords.get(&"uniqueItems").and_then(|u| u.as_bool()) == Some(true) {
+            let duplicate = items
+                .iter()
+                .enumerate()
+                .any(|(i, a)| items[..i].iter().any(|b| json_equal(a, b)));
+            if duplicate {
+                self.fail(
+                    pointer,
+                    "uniqueItems",
+                    "Array items are not unique".to_string(),
+                );
+            }
+        }
+
+        // Draft 7 spells `prefixItems` as an array-valued `items`
+        let items_keyword = keywords.get(&"items");
+        let (prefix, rest) = match items_keyword.and_then(|i| i.as_array()) {
+            Some(tuple) => (Some(tuple), keywords.get(&"additionalItems")),
+            None => (
+                keywords.get(&"prefixItems").and_then(|p| p.as_array()),
+                items_keyword,
+            ),
+        };
+        let prefix_len = prefix.map_or(0, |p| p.len());
+        for (i, item) in items.iter().enumerate() {
+            let sub = match prefix {
+                Some(prefix) if i < prefix_len => &prefix[i],
+                _ => match rest {
+                    Some(sub) => sub,
+                    None => continue,
+                },
+            };
+            let len = pointer.len();
+            push_segment(pointer, &i.to_string());
+            self.validate(item, sub, pointer, depth + 1)?;
+            pointer.truncate(len);
+        }
+        Ok(())
+    }
+
+    fn check_object(
+        &mut self,
+        members: &sonic_rs::Object,
+        keywords: &'s sonic_rs::Object,
+        pointer: &mut String,
+        depth: usize,
+    ) -> Result<(), SonicError> {
+        let count = members.len() as f64;
+        if let Some(min) = keywords.get(&"minProperties").and_then(|m| m.as_f64()) {
+            if count < min {
+                self.fail(
+                    pointer,
+                    "minProperties",
+                    format!("Object has fewer than {} properties", min),
+                );
+            }
+        }
+        if let Some(max) = keywords.get(&"maxProperties").and_then(|m| m.as_f64()) {
+            if count > max {
+                self.fail(
+                    pointer,
+                    "maxProperties",
+                    format!("Object has more than {} properties", max),
+                );
+            }
+        }
+        if let Some(required) = keywords.get(&"required").and_then(|r| r.as_array()) {
+            for name in required.iter().filter_map(|r| r.as_str()) {
+                if members.get(&name).is_none() {
+                    self.fail(
+                        pointer,
+                        "required",
+                        format!("Missing required property '{}'", name),
+                    );
+                }
+            }
+        }
+
+        let properties = keywords.get(&"properties").and_then(|p| p.as_object());
+        let additional = keywords.get(&"additionalProperties");
+        for (name, item) in members.iter() {
+            let len = pointer.len();
+            push_segment(pointer, name);
+            match properties.and_then(|p| p.get(&name)) {
+                Some(sub) => self.validate(item, sub, pointer, depth + 1)?,
+                None => match additional {
+                    Some(sub) if sub.as_bool() == Some(false) => {
+                        self.fail(
+                            pointer,
+                            "additionalProperties",
+                            format!("Property '{}' is not allowed", name),
+                        );
+                    }
+                    Some(sub) => self.validate(item, sub, pointer, depth + 1)?,
+                    None => {}
+                },
+            }
+            pointer.truncate(len);
+        }
+        Ok(())
+    }
+
+    /// The subschema an internal reference (`#/$defs/Money`) points at.
+    fn resolve_ref(&self, reference: &str) -> Result<&'s Value, SonicError> {
+        let path = reference.strip_prefix('#').unwrap_or(reference);
+        let mut target = self.root;
+        if !path.is_empty() {
+            let Some(path) = path.strip_prefix('/') else {
+                return Err(schema_error(
+                    reference,
+                    "unresolvable reference".to_string(),
+                ));
+            };
+            for segment in path.split('/') {
+                let segment = segment.replace("~1", "/").replace("~0", "~");
+                let next = match target.as_array() {
+                    Some(list) => segment.parse::<usize>().ok().and_then(|i| list.get(i)),
+                    None => target.as_object().and_then(|o| o.get(&segment)),
+                };
+                target = next
+                    .ok_or_else(|| schema_error(reference, "unresolvable reference".to_string()))?;
+            }
+        }
+        Ok(target)
+    }
+}
+
+/// Whether `value` is an instance of the JSON Schema type `name`.
+fn type_matches(value: &Value, name: &str) -> bool {
+    match name {
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => type_name(value) == name,
+    }
+}
+
+/// The JSON Schema type of `value`, with integers reported as numbers.
+fn type_name(value: &Value) -> &'static str {
+    match value.get_type() {
+        JsonType::Null => "null",
+        JsonType::Boolean => "boolean",
+        JsonType::Number => "number",
+        JsonType::String => "string",
+        JsonType::Object => "object",
+        JsonType::Array => "array",
+    }
+}
+
+/// JSON equality: numbers compare by value (`1` equals `1.0`) and object
+/// members regardless of order.
+fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a.get_type(), b.get_type()) {
+        (JsonType::Number, JsonType::Number) => match (a.as_i64(), b.as_i64()) {
+            (Some(x), Some(y)) => x == y,
+            _ => match (a.as_u64(), b.as_u64()) {
+                (Some(x), Some(y)) => x == y,
+                _ => a.as_f64() == b.as_f64(),
+            },
+        },
+        (JsonType::Array, JsonType::Array) => {
+            let (x, y) = (a.as_array().unwrap(), b.as_array().unwrap());
+            x.len() == y.len() && x.iter().zip(y.iter()).all(|(a, b)| json_equal(a, b))
+        }
+        (JsonType::Object, JsonType::Object) => {
+            let (x, y) = (a.as_object().unwrap(), b.as_object().unwrap());
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(key, item)| y.get(&key).is_some_and(|other| json_equal(item, other)))
+        }
+        (ta, tb) if ta == tb => a == b,
+        _ => false,
+    }
+}
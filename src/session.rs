@@ -0,0 +1,218 @@
+//! The `sift_json` session serialize handler and `Sift\SessionSerializer`.
+//!
+//! Usage:
+//! ```ini
+//! session.serialize_handler = sift_json
+//! ```
+//! ```php
+//! // Reading a stored session outside of the session module
+//! $vars = Sift\SessionSerializer::decode($redis->get("PHPREDIS_SESSION:$id"));
+//! ```
+//!
+//! Session data is stored as a JSON object of the top-level session
+//! variables, written by `encoder` and read by `parser`. Values JSON can't
+//! represent (objects other than JsonSerializable and plain property bags,
+//! resources, closures) fail the write; objects come back as arrays.
+//!
+//! The handler is registered at startup through the session module's C
+//! API, looked up at runtime: a PHP built without ext/session, or loading
+//! it as a shared extension after Sift, just doesn't get `sift_json`.
+
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::guard::guard;
+use crate::options::DecodeOptions;
+use crate::parser;
+use ext_php_rs::binary_slice::BinarySlice;
+use ext_php_rs::boxed::ZBox;
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ArrayKey, ZendHashTable, ZendStr, Zval};
+use ext_php_rs::zend::ExecutorGlobals;
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The handler's `session.serialize_handler` name.
+const HANDLER_NAME: &CStr = c"sift_json";
+
+/// `SUCCESS` / `FAILURE` of `zend_result`.
+const SUCCESS: c_int = 0;
+const FAILURE: c_int = -1;
+
+type EncodeFn = extern "C" fn() -> *mut ZendStr;
+type DecodeFn = extern "C" fn(*const c_char, usize) -> c_int;
+type RegisterFn = unsafe extern "C" fn(*const c_char, EncodeFn, DecodeFn) -> c_int;
+type SetVarFn = unsafe extern "C" fn(*mut ZendStr, *mut Zval, *mut c_void) -> *mut Zval;
+
+/// Whether `sift_json` was registered at startup.
+static REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// `php_set_session_var()`, resolved at startup.
+static mut SET_SESSION_VAR: Option<SetVarFn> = None;
+
+/// Register `sift_json` with the session module, if it is there.
+pub fn register_handler() {
+    let register = lookup(c"php_session_register_serializer");
+    let set_var = lookup(c"php_set_session_var");
+    if register.is_null() || set_var.is_null() {
+        log::info!("sift: ext/session not available, sift_json handler not registered");
+        return;
+    }
+    // SAFETY: both are the session module's exported functions, with
+    // these signatures since PHP 7; startup runs before any request
+    unsafe {
+        SET_SESSION_VAR = Some(std::mem::transmute::<*mut c_void, SetVarFn>(set_var));
+        let register = std::mem::transmute::<*mut c_void, RegisterFn>(register);
+        if register(HANDLER_NAME.as_ptr(), encode_session, decode_session) == SUCCESS {
+            REGISTERED.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The address of the global symbol `name`, or null.
+#[cfg(unix)]
+fn lookup(name: &CStr) -> *mut c_void {
+    extern "C" {
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    }
+    #[cfg(target_os = "macos")]
+    const RTLD_DEFAULT: *mut c_void = -2isize as *mut c_void;
+    #[cfg(not(target_os = "macos"))]
+    const RTLD_DEFAULT: *mut c_void = std::ptr::null_mut();
+    // SAFETY: dlsym only reads the NUL-terminated name
+    unsafe { dlsym(RTLD_DEFAULT, name.as_ptr()) }
+}
+
+/// The address of the global symbol `name`, or null.
+#[cfg(not(unix))]
+fn lookup(_name: &CStr) -> *mut c_void {
+    std::ptr::null_mut()
+}
+
+/// Encode the session variables `vars` (an array) as JSON.
+fn encode_vars(vars: &Zval) -> Result<String, SonicError> {
+    if vars.array().is_none() {
+        return Err(SonicError::TypeError(
+            "Session data must be an array".to_string(),
+        ));
+    }
+    let mut out = String::new();
+    encoder::encode_into(&mut out, vars)?;
+    // An empty session encodes as [], which reads back the same
+    Ok(out)
+}
+
+/// Decode stored session data into the array of its variables.
+fn decode_vars(data: &[u8]) -> Result<Zval, SonicError> {
+    // Built-in defaults: Sift::configure() must not change how sessions read back
+    let options = DecodeOptions::default();
+    let text = parser::decode_text(data, &options)?;
+    if text.trim().is_empty() {
+        let mut empty = Zval::new();
+        empty.set_hashtable(ZendHashTable::new());
+        return Ok(empty);
+    }
+    let vars = parser::decode(&text, &options)?;
+    if vars.array().is_none() {
+        return Err(SonicError::TypeError(
+            "Session data must be a JSON object or array".to_string(),
+        ));
+    }
+    Ok(vars)
+}
+
+/// `encode` of the `sift_json` handler: `$_SESSION` as JSON, or null
+/// (which fails the write) if it can't be encoded.
+extern "C" fn encode_session() -> *mut ZendStr {
+    let encoded = guard(|| {
+        let globals = ExecutorGlobals::get();
+        let vars = globals
+            .symbol_table
+            .get("_SESSION")
+            .map(|var| var.indirect().unwrap_or(var).dereference())
+            .ok_or_else(|| SonicError::TypeError("$_SESSION is not set".to_string()))?;
+        encode_vars(vars)
+    });
+    match encoded {
+        // The session module releases the string once it is written
+        Ok(json) => ZendStr::new(&json, false).into_raw() as *mut ZendStr,
+        Err(e) => {
+            log::warn!("sift: failed to encode session data: {}", e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// `decode` of the `sift_json` handler: set each stored variable in the
+/// session.
+extern "C" fn decode_session(val: *const c_char, len: usize) -> c_int {
+    let data = if val.is_null() || len == 0 {
+        &[][..]
+    } else {
+        // SAFETY: the session module passes the stored data and its length
+        unsafe { std::slice::from_raw_parts(val.cast::<u8>(), len) }
+    };
+    let decoded = guard(|| {
+        let vars = decode_vars(data)?;
+        let Some(set_var) = (unsafe { SET_SESSION_VAR }) else {
+            return Err(SonicError::Internal(
+                "Session module not linked".to_string(),
+            ));
+        };
+        for (key, value) in vars.array().into_iter().flat_map(|vars| vars.iter()) {
+            let name = match key {
+                ArrayKey::String(name) => name,
+                ArrayKey::Long(index) => index.to_string(),
+            };
+            let mut name: ZBox<ZendStr> = ZendStr::new(&name, false);
+            // The session's array takes over this reference
+            let mut value = std::mem::ManuallyDrop::new(value.shallow_clone());
+            // SAFETY: called while the session module is decoding, with a
+            // live key and value
+            unsafe { set_var(&mut *name, &mut *value, std::ptr::null_mut()) };
+        }
+        Ok(())
+    });
+    match decoded {
+        Ok(()) => SUCCESS,
+        Err(e) => {
+            log::warn!("sift: failed to decode session data: {}", e);
+            FAILURE
+        }
+    }
+}
+
+/// SessionSerializer - the `sift_json` session format, for code that
+/// reads or writes stored sessions itself.
+#[php_class(name = "Sift\\SessionSerializer")]
+pub struct SessionSerializer;
+
+#[php_impl]
+impl SessionSerializer {
+    /// The `session.serialize_handler` name.
+    const NAME: &'static str = "sift_json";
+
+    /// Encode session variables as the handler stores them.
+    ///
+    /// # Example
+    /// ```php
+    /// $stored = Sift\SessionSerializer::encode(['user_id' => 42]);
+    /// ```
+    pub fn encode(data: &Zval) -> Result<String, SonicError> {
+        guard(|| encode_vars(data.dereference()))
+    }
+
+    /// Decode stored session data into the session variables.
+    ///
+    /// # Example
+    /// ```php
+    /// $vars = Sift\SessionSerializer::decode($stored);
+    /// ```
+    pub fn decode(data: BinarySlice<u8>) -> Result<Zval, SonicError> {
+        guard(|| decode_vars(&data))
+    }
+
+    /// Whether `sift_json` could be registered with ext/session.
+    pub fn is_registered() -> bool {
+        REGISTERED.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,105 @@
+//! Raw splitting of JSON arrays into size-bounded shards and joining
+//! them back, for `Sift::splitTopLevel()` and `Sift::concatArrays()`.
+//!
+//! Usage:
+//! ```php
+//! foreach (Sift::splitTopLevel($events, 1 << 20) as $batch) {
+//!     $http->post('/ingest', ['body' => $batch]); // each at most 1 MiB
+//! }
+//! $all = Sift::concatArrays([$page1, $page2, $page3]);
+//! ```
+//!
+//! Elements are copied as text, in order, without being decoded; each
+//! shard is a valid JSON array of as many elements as fit in the byte
+//! budget, brackets and commas included. Concatenation splices the
+//! contents of the arrays between one pair of brackets.
+
+use crate::errors::SonicError;
+use crate::parser;
+use sonic_rs::{to_array_iter, JsonValueTrait};
+
+/// Split the top-level array of `json` into arrays of at most
+/// `max_bytes` bytes each. An empty array gives no shards.
+pub fn split_top_level(json: &str, max_bytes: i64) -> Result<Vec<String>, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+    if max_bytes < 2 {
+        return Err(SonicError::InvalidOption(format!(
+            "Byte budget must be at least 2, got {}",
+            max_bytes
+        )));
+    }
+    // Elements are copied without being parsed, so validate them up front
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+
+    let root = parser::lazy_at(json, "")?;
+    if !root.is_array() {
+        return Err(
+            SonicError::TypeError("Expected a top-level array".to_string())
+                .with_types("array", parser::type_name(&root)),
+        );
+    }
+    let budget = max_bytes as usize;
+    let mut shards = Vec::new();
+    let mut shard = String::new();
+    for (index, element) in to_array_iter(root.as_raw_str()).enumerate() {
+        let element = element?;
+        let raw = element.as_raw_str().trim();
+        // "[" + raw + "]" on its own
+        if raw.len() + 2 > budget {
+            return Err(SonicError::InvalidOption(format!(
+                "Byte budget of {} is too small for element {} ({} bytes)",
+                max_bytes,
+                index,
+                raw.len()
+            ))
+            .at_pointer(format!("/{}", index)));
+        }
+        // The shard so far, a comma, raw and the closing "]"
+        if !shard.is_empty() && shard.len() + 1 + raw.len() + 1 > budget {
+            shard.push(']');
+            shards.push(std::mem::take(&mut shard));
+        }
+        shard.push(if shard.is_empty() { '[' } else { ',' });
+        shard.push_str(raw);
+    }
+    if !shard.is_empty() {
+        shard.push(']');
+        shards.push(shard);
+    }
+    Ok(shards)
+}
+
+/// Join the elements of the JSON arrays `docs` into one array.
+pub fn concat_arrays(docs: &[String]) -> Result<String, SonicError> {
+    let mut out = String::with_capacity(docs.iter().map(|doc| doc.len()).sum::<usize>() + 2);
+    out.push('[');
+    for (index, doc) in docs.iter().enumerate() {
+        // Validate input size to prevent DoS
+        parser::check_input_size(doc)?;
+        // Elements are spliced without being parsed, so validate them up front
+        sonic_rs::from_str::<sonic_rs::Value>(doc)
+            .map_err(|e| SonicError::ParseError(format!("Document {}: {}", index, e)))?;
+        let root = parser::lazy_at(doc, "")?;
+        if !root.is_array() {
+            return Err(
+                SonicError::TypeError(format!("Document {} is not an array", index))
+                    .with_types("array", parser::type_name(&root)),
+            );
+        }
+        // Between the brackets, whitespace and all
+        let raw = root.as_raw_str().trim();
+        let elements = raw[1..raw.len() - 1].trim();
+        if elements.is_empty() {
+            continue;
+        }
+        if out.len() > 1 {
+            out.push(',');
+        }
+        out.push_str(elements);
+    }
+    out.push(']');
+    // The result is meant to be read back
+    parser::check_input_size(&out)?;
+    Ok(out)
+}
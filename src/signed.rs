@@ -0,0 +1,157 @@
+//! HMAC-verified decoding for `Sift::decodeSigned()` and the canonical
+//! form it signs, `Sift::canonicalize()`.
+//!
+//! Usage:
+//! ```php
+//! // Producer
+//! $signature = hash_hmac('sha256', Sift::canonicalize($json), $key);
+//! $cache->set($id, '{"payload":' . $json . ',"signature":"' . $signature . '"}');
+//! // Consumer: throws unless the payload is exactly what was signed
+//! $data = Sift::decodeSigned($cache->get($id), $key);
+//! ```
+//!
+//! The canonical form makes the signature independent of formatting: no
+//! whitespace, object members sorted by key (bytewise, UTF-8), strings
+//! re-escaped the way `Sift::encodeLines()` writes them, and numbers kept
+//! as written (`1.0` and `1` differ). Repeated keys are kept, in input
+//! order among themselves.
+
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::options::DecodeOptions;
+use crate::parser::{self, MAX_DEPTH};
+use ext_php_rs::types::Zval;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sonic_rs::{to_array_iter, to_object_iter, JsonType, JsonValueTrait, LazyValue};
+
+/// Member of a signed envelope holding the signed value.
+const PAYLOAD: &str = "payload";
+
+/// Member of a signed envelope holding the hex HMAC-SHA256.
+const SIGNATURE: &str = "signature";
+
+/// The canonical form of `json`.
+pub fn canonicalize(json: &str) -> Result<String, SonicError> {
+    let root = parser::lazy_at(json, "")?;
+    let mut out = String::with_capacity(json.len());
+    write_canonical(&mut out, &root, 0)?;
+    Ok(out)
+}
+
+/// Append the canonical form of `value` to `out`.
+pub(crate) fn write_canonical(
+    out: &mut String,
+    value: &LazyValue,
+    depth: usize,
+) -> Result<(), SonicError> {
+    if depth > MAX_DEPTH {
+        return Err(SonicError::ParseError(format!(
+            "Maximum nesting depth ({}) exceeded",
+            MAX_DEPTH
+        )));
+    }
+    match value.get_type() {
+        JsonType::Object => {
+            let mut members = Vec::new();
+            for member in to_object_iter(value.as_raw_str()) {
+                members.push(member?);
+            }
+            // Stable, so repeated keys keep their order
+            members.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+            out.push('{');
+            for (i, (key, item)) in members.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                encoder::write_string(out, key.as_bytes())?;
+                out.push(':');
+                write_canonical(out, item, depth + 1)?;
+            }
+            out.push('}');
+        }
+        JsonType::Array => {
+            out.push('[');
+            for (i, item) in to_array_iter(value.as_raw_str()).enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(out, &item?, depth + 1)?;
+            }
+            out.push(']');
+        }
+        JsonType::String => {
+            let text = value.as_str().unwrap_or_default();
+            encoder::write_string(out, text.as_bytes())?;
+        }
+        // Numbers and literals are already canonical once trimmed
+        _ => out.push_str(value.as_raw_str().trim()),
+    }
+    Ok(())
+}
+
+/// Decode a hex signature; None if it isn't 64 hex digits.
+fn decode_hex(signature: &str) -> Option<Vec<u8>> {
+    let signature = signature.trim().as_bytes();
+    if signature.len() != 64 {
+        return None;
+    }
+    signature
+        .chunks(2)
+        .map(|pair| {
+            let digit = |b: u8| (b as char).to_digit(16);
+            Some((digit(pair[0])? * 16 + digit(pair[1])?) as u8)
+        })
+        .collect()
+}
+
+/// Check `signature` (hex HMAC-SHA256) against the canonical form of
+/// `value`, in constant time.
+fn verify(value: &LazyValue, key: &[u8], signature: &str) -> Result<(), SonicError> {
+    let expected = decode_hex(signature).ok_or_else(|| {
+        SonicError::FormatError("Signature must be 64 hex digits (HMAC-SHA256)".to_string())
+    })?;
+    let mut canonical = String::new();
+    write_canonical(&mut canonical, value, 0)?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(key)
+        .map_err(|e| SonicError::InvalidOption(format!("Invalid HMAC key: {}", e)))?;
+    mac.update(canonical.as_bytes());
+    mac.verify_slice(&expected)
+        .map_err(|_| SonicError::FormatError("Signature does not match the payload".to_string()))
+}
+
+/// Verify and decode `json`. With `signature`, it signs the whole
+/// document; without, `json` is an envelope `{"payload": ..., "signature":
+/// "..."}` and the payload is verified and returned.
+pub fn decode_signed(
+    json: &str,
+    key: &[u8],
+    signature: Option<&str>,
+    options: &DecodeOptions,
+) -> Result<Zval, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+    // Pointer lookups don't look past the value they find
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+
+    let (value, signature) = match signature {
+        Some(signature) => (parser::lazy_at(json, "")?, signature.to_string()),
+        None => {
+            let envelope = parser::lazy_at(json, "")?;
+            if !envelope.is_object() {
+                return Err(SonicError::FormatError(format!(
+                    "Signed envelope must be an object with '{}' and '{}'",
+                    PAYLOAD, SIGNATURE
+                )));
+            }
+            let payload = parser::lazy_at(json, &format!("/{}", PAYLOAD))?;
+            let signature = parser::lazy_at(json, &format!("/{}", SIGNATURE))?;
+            let signature = signature.as_str().map(str::to_string).ok_or_else(|| {
+                SonicError::FormatError(format!("Envelope '{}' must be a string", SIGNATURE))
+            })?;
+            (payload, signature)
+        }
+    };
+    verify(&value, key, &signature)?;
+    parser::lazyvalue_to_zval(value, options)
+}
@@ -0,0 +1,212 @@
+//! Immutable decoded trees shared across calls and requests.
+//!
+//! Usage:
+//! ```php
+//! $snap = Sift\Snapshot::persisted('config')
+//!     ?? Sift::snapshot(file_get_contents('config.json'))->persist('config');
+//! $dsn = $snap->get('/database/dsn');   // Only this value becomes a Zval
+//! $all = $snap->toArray();              // Full conversion, on demand
+//! ```
+//!
+//! The registry is per worker process and holds at most `MAX_PERSISTED`
+//! snapshots; persisting another evicts the one stored or read least
+//! recently.
+
+use crate::arena;
+use crate::errors::SonicError;
+use crate::guard::guard;
+use crate::leaks::Live;
+use crate::memory;
+use crate::options::DecodeOptions;
+use crate::parser;
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendHashTable, Zval};
+use sonic_rs::{JsonValueTrait, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+
+/// Most snapshots persisted at once. A worker persisting per-tenant names
+/// would otherwise keep every tenant's tree until it exits.
+pub const MAX_PERSISTED: usize = 256;
+
+/// Snapshots persisted by name, least recently used evicted first.
+#[derive(Default)]
+struct Registry {
+    /// Each tree with the tick it was last stored or read at
+    entries: HashMap<String, (Arc<Value>, u64)>,
+    /// Ticks once per store or read
+    clock: u64,
+}
+
+impl Registry {
+    /// Store `root` under `name`, evicting the least recently used entry
+    /// if the registry is full.
+    fn insert(&mut self, name: &str, root: Arc<Value>) {
+        if self.entries.len() >= MAX_PERSISTED && !self.entries.contains_key(name) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(name.to_string(), (root, self.clock));
+    }
+
+    /// The tree stored under `name`, marked as just used.
+    fn get(&mut self, name: &str) -> Option<Arc<Value>> {
+        self.clock += 1;
+        let (root, used) = self.entries.get_mut(name)?;
+        *used = self.clock;
+        Some(Arc::clone(root))
+    }
+}
+
+/// The persisted snapshots, shared by every request served by this worker
+/// process (and every thread, under ZTS).
+static PERSISTED: OnceLock<Mutex<Registry>> = OnceLock::new();
+
+/// Lock the persisted-snapshot registry. Every operation on it finishes
+/// its change to the map before it can panic, so it stays consistent even
+/// if a holder panicked.
+fn registry() -> MutexGuard<'static, Registry> {
+    PERSISTED
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// PHP name of `Snapshot`.
+const CLASS: &str = "Sift\\Snapshot";
+
+/// Snapshot - a parsed document held in Rust.
+/// The tree is never mutated, so copies share it through an Arc and PHP
+/// values are only created for what is read.
+#[php_class(name = "Sift\\Snapshot")]
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    /// The decoded document (shared via Arc, never mutated)
+    root: Arc<Value>,
+    /// Counts this object for `sift.leak_check` (see `leaks.rs`)
+    live: Live,
+}
+
+impl Snapshot {
+    /// Parse a JSON string into a new Snapshot.
+    pub fn parse(json: &str) -> Result<Self, SonicError> {
+        // The tree takes at least as much as the text
+        memory::reserve(json.len())?;
+        Ok(Self {
+            root: Arc::new(parser::parse_dom(json)?),
+            live: Live::new(CLASS),
+        })
+    }
+
+    /// Run `f` on the value at `pointer`, or None if the path doesn't exist.
+    fn with_value<R>(
+        &self,
+        pointer: &str,
+        f: impl FnOnce(&Value) -> R,
+    ) -> Result<Option<R>, SonicError> {
+        arena::with_arena(|bump| {
+            let nodes = parser::pointer_nodes(pointer, bump)?;
+            Ok(self.root.pointer(nodes.iter()).map(f))
+        })
+    }
+}
+
+#[php_impl]
+impl Snapshot {
+    /// Convert the whole document into PHP values. Takes the same options
+    /// as `Sift::decode()`.
+    ///
+    /// # Example
+    /// ```php
+    /// $config = $snap->toArray();
+    /// ```
+    pub fn to_array(&self, options: Option<&ZendHashTable>) -> Result<Zval, SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            parser::dom_to_zval(&self.root, &options)
+        })
+    }
+
+    /// Extract a value by JSON pointer (RFC 6901), converting only that subtree.
+    /// Throws if the path doesn't exist. Takes the same options as
+    /// `Sift::decode()`.
+    ///
+    /// # Example
+    /// ```php
+    /// $dsn = $snap->get('/database/dsn');
+    /// $db = $snap->get('/database', ['assoc' => false]);
+    /// ```
+    pub fn get(&self, pointer: &str, options: Option<&ZendHashTable>) -> Result<Zval, SonicError> {
+        guard(|| {
+            let options = DecodeOptions::from_array(options)?;
+            self.with_value(pointer, |value| parser::dom_to_zval(value, &options))?
+                .unwrap_or_else(|| {
+                    Err(SonicError::KeyNotFound("Path not found".to_string()).at_pointer(pointer))
+                })
+        })
+    }
+
+    /// Check whether a JSON pointer exists, without converting anything.
+    pub fn has(&self, pointer: &str) -> Result<bool, SonicError> {
+        guard(|| Ok(self.with_value(pointer, |_| ())?.is_some()))
+    }
+
+    /// Keep this snapshot in the worker process under `name`, replacing any
+    /// snapshot previously stored there. Later requests served by the same
+    /// worker retrieve it with `Snapshot::persisted()` without re-parsing.
+    /// With `MAX_PERSISTED` names already stored, the least recently
+    /// persisted or read one is dropped.
+    ///
+    /// # Example
+    /// ```php
+    /// Sift::snapshot($json)->persist('config');
+    /// ```
+    pub fn persist(&self, name: &str) -> Result<Snapshot, SonicError> {
+        guard(|| {
+            registry().insert(name, Arc::clone(&self.root));
+            Ok(self.clone())
+        })
+    }
+
+    /// Snapshot previously stored with `persist()` in this worker, or null.
+    #[php_static]
+    pub fn persisted(name: &str) -> Result<Option<Snapshot>, SonicError> {
+        guard(|| {
+            Ok(registry().get(name).map(|root| Snapshot {
+                root,
+                live: Live::new(CLASS),
+            }))
+        })
+    }
+
+    /// Drop a persisted snapshot. Returns whether one was stored under `name`.
+    /// Snapshot objects already handed out keep working.
+    #[php_static]
+    pub fn forget(name: &str) -> Result<bool, SonicError> {
+        guard(|| Ok(registry().entries.remove(name).is_some()))
+    }
+
+    /// Drop every persisted snapshot, returning how many there were.
+    /// Snapshot objects already handed out keep working.
+    ///
+    /// # Example
+    /// ```php
+    /// Sift\Snapshot::forgetAll();   // after a deploy, before re-reading configs
+    /// ```
+    #[php_static]
+    pub fn forget_all() -> Result<i64, SonicError> {
+        guard(|| {
+            let mut registry = registry();
+            let count = registry.entries.len();
+            registry.entries.clear();
+            Ok(count as i64)
+        })
+    }
+}
@@ -0,0 +1,119 @@
+//! Format detection and dispatch for `Sift::decodeAuto()`.
+//!
+//! Usage:
+//! ```php
+//! $result = Sift::decodeAuto(file_get_contents('php://input'));
+//! $rows = $result['format'] === 'ndjson' ? $result['data'] : [$result['data']];
+//! ```
+//!
+//! The payload is recognized from its bytes, not a declared content type:
+//! the gzip magic (`1f 8b`) is inflated and sniffed again; a MessagePack
+//! map or array marker as the first byte means MessagePack; anything else
+//! is text, decoded as one JSON document or, when that fails after the
+//! first line, as newline-delimited JSON.
+
+use crate::errors::SonicError;
+use crate::msgpack;
+use crate::options::DecodeOptions;
+use crate::parser::{self, MAX_INPUT_SIZE};
+use ext_php_rs::types::{ZendHashTable, Zval};
+use flate2::read::MultiGzDecoder;
+use std::io::Read;
+
+/// First two bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Decode `bytes` in whatever format they are in, as
+/// `['format' => 'json'|'ndjson'|'msgpack', 'gzip' => bool, 'data' => mixed]`.
+pub fn decode_auto(bytes: &[u8], options: &DecodeOptions) -> Result<Zval, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(bytes)?;
+
+    let gzip = bytes.starts_with(&GZIP_MAGIC);
+    let (format, data) = if gzip {
+        let inflated = inflate(bytes, MAX_INPUT_SIZE)?;
+        if inflated.starts_with(&GZIP_MAGIC) {
+            return Err(SonicError::ParseError(
+                "Nested gzip streams are not supported".to_string(),
+            ));
+        }
+        decode_plain(&inflated, options)?
+    } else {
+        decode_plain(bytes, options)?
+    };
+
+    let mut result = ZendHashTable::new();
+    result
+        .insert("format", format)
+        .and_then(|_| result.insert("gzip", gzip))
+        .and_then(|_| result.insert("data", data))
+        .map_err(|e| SonicError::TypeError(format!("Failed to insert object key: {}", e)))?;
+    let mut zval = Zval::new();
+    zval.set_hashtable(result);
+    Ok(zval)
+}
+
+/// Inflate a gzip stream (all of its members), stopping at `limit` bytes
+/// so a small bomb can't expand without bound.
+pub fn inflate(bytes: &[u8], limit: usize) -> Result<Vec<u8>, SonicError> {
+    let mut inflated = Vec::new();
+    MultiGzDecoder::new(bytes)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut inflated)
+        .map_err(|e| SonicError::ParseError(format!("Malformed gzip data: {}", e)))?;
+    if inflated.len() > limit {
+        // Reading stopped at the limit; the real size is unknown
+        return Err(SonicError::InputTooLarge {
+            size: inflated.len(),
+            limit,
+        });
+    }
+    Ok(inflated)
+}
+
+/// Decode uncompressed bytes, returning the format found and the value.
+fn decode_plain(bytes: &[u8], options: &DecodeOptions) -> Result<(&'static str, Zval), SonicError> {
+    match bytes.first() {
+        None => return Err(SonicError::ParseError("Empty input".to_string())),
+        Some(&first) if msgpack::starts_container(first) => {
+            return Ok(("msgpack", msgpack::decode(bytes)?))
+        }
+        Some(_) => {}
+    }
+
+    let text = parser::decode_text(bytes, options)?;
+    let error = match parser::decode(&text, options) {
+        Ok(value) => return Ok(("json", value)),
+        Err(error) => error,
+    };
+    // A document that fails after a complete first line is NDJSON;
+    // otherwise the error is about the document
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    match (lines.next(), lines.next()) {
+        (Some(first), Some(_)) if parser::is_valid(first) => {
+            Ok(("ndjson", decode_lines(&text, options)?))
+        }
+        _ => Err(error),
+    }
+}
+
+/// Decode each non-blank line of `text` as a JSON document, as a list.
+fn decode_lines(text: &str, options: &DecodeOptions) -> Result<Zval, SonicError> {
+    let mut rows = ZendHashTable::new();
+    let mut offset = 0;
+    for (number, line) in text.split('\n').enumerate() {
+        let start = offset;
+        offset += line.len() + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = parser::decode(line, options).map_err(|e| {
+            SonicError::ParseError(format!("NDJSON line {}: {}", number + 1, e)).at_offset(start)
+        })?;
+        rows.push(row)
+            .map_err(|e| SonicError::TypeError(format!("Failed to push array item: {}", e)))?;
+    }
+    let mut zval = Zval::new();
+    zval.set_hashtable(rows);
+    Ok(zval)
+}
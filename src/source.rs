@@ -0,0 +1,158 @@
+//! Input source detection for `Sift::from()`, and file reading for
+//! `Sift::fromFile()` and `Sift::lines()`.
+//!
+//! Usage:
+//! ```php
+//! $q = Sift::from($json);                          // JSON text
+//! $q = Sift::from(fopen('php://input', 'r'));      // stream resource
+//! $q = Sift::from(new SplFileInfo('config.json')); // file
+//! $q = Sift::from($request->getBody());            // PSR-7 stream
+//! $q = Sift::fromFile('config.json');              // path
+//! $q = Sift::from($decoded);                       // array, re-encoded
+//! ```
+//!
+//! A string is always JSON text, never a path: `Sift::from($body)` must
+//! not read a local file because a client sent its name. Files are only
+//! read when asked for explicitly, through `SplFileInfo` or
+//! `Sift::fromFile()`. They are read in Rust, after the same
+//! `open_basedir` check PHP's own file functions make. Streams are read
+//! from their current position with `stream_get_contents()`. Objects with
+//! `read()` and `eof()` methods, such as PSR-7 `StreamInterface` bodies,
+//! are read from their current position in chunks appended to a Rust
+//! buffer, so the body is never materialized as one PHP string. Arrays and
+//! other objects are encoded as `encoder` does, so the result can be
+//! navigated like any document.
+
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::parser::MAX_INPUT_SIZE;
+use ext_php_rs::flags::DataType;
+use ext_php_rs::types::{ZendCallable, ZendObject, Zval};
+use ext_php_rs::zend::ClassEntry;
+use std::ffi::{c_char, c_int, CString};
+use std::fs::File;
+use std::io::Read;
+
+extern "C" {
+    /// PHP's `open_basedir` check (main/fopen_wrappers.c): 0 if `path`
+    /// may be opened. `warn` 0 keeps it from raising a warning.
+    fn php_check_open_basedir_ex(path: *const c_char, warn: c_int) -> c_int;
+}
+
+/// Bytes asked for per `read()` call on a stream object.
+const READ_CHUNK: i64 = 64 * 1024;
+
+/// The JSON bytes of `source`: JSON text, an `SplFileInfo`, a stream
+/// resource or object, or a value to encode.
+pub fn read(source: &Zval) -> Result<Vec<u8>, SonicError> {
+    let source = source.dereference();
+    match source.get_type() {
+        DataType::String => Ok(source.binary_slice::<u8>().unwrap_or_default().to_vec()),
+        DataType::Resource => read_stream(source),
+        DataType::Object(_) => {
+            let file_info = ClassEntry::try_find("SplFileInfo");
+            match source.object() {
+                Some(object) if file_info.is_some_and(|ce| object.instance_of(ce)) => {
+                    let path = object.try_call_method("getPathname", vec![]).map_err(|e| {
+                        SonicError::TypeError(format!("getPathname() failed: {}", e))
+                    })?;
+                    read_file(path.str().unwrap_or_default())
+                }
+                Some(object) if is_stream(source)? => read_stream_object(object),
+                _ => encode(source),
+            }
+        }
+        DataType::Array => encode(source),
+        _ => Err(SonicError::TypeError(format!(
+            "Source must be a string, stream, SplFileInfo, array or object, got {}",
+            source.get_type()
+        ))),
+    }
+}
+
+/// Open the local file at `path` for reading, if `open_basedir` allows.
+/// Must be called on the PHP thread.
+pub fn open_file(path: &str) -> Result<File, SonicError> {
+    let cannot_open = |reason: &dyn std::fmt::Display| {
+        SonicError::IoError(format!("Cannot open '{}': {}", path, reason))
+    };
+    let c_path = CString::new(path).map_err(|_| cannot_open(&"path contains a NUL byte"))?;
+    // SAFETY: reads the NUL-terminated path and the request's INI settings
+    if unsafe { php_check_open_basedir_ex(c_path.as_ptr(), 0) } != 0 {
+        return Err(cannot_open(&"open_basedir restriction in effect"));
+    }
+    File::open(path).map_err(|e| cannot_open(&e))
+}
+
+/// Read the file at `path`, up to just past the size limit.
+pub fn read_file(path: &str) -> Result<Vec<u8>, SonicError> {
+    let file = open_file(path)?;
+    let mut bytes = Vec::new();
+    file.take(MAX_INPUT_SIZE as u64 + 1)
+        .read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Read the rest of the stream resource `stream`, up to just past the
+/// size limit.
+fn read_stream(stream: &Zval) -> Result<Vec<u8>, SonicError> {
+    let contents = ZendCallable::try_from_name("stream_get_contents")
+        .map_err(|_| SonicError::Internal("stream_get_contents() is unavailable".to_string()))?
+        .try_call(vec![stream, &(MAX_INPUT_SIZE as i64 + 1)])
+        .map_err(|e| SonicError::IoError(format!("Cannot read stream: {}", e)))?;
+    match contents.binary_slice::<u8>() {
+        Some(bytes) => Ok(bytes.to_vec()),
+        None => Err(SonicError::IoError("Cannot read stream".to_string())),
+    }
+}
+
+/// Whether the object `source` has `read()` and `eof()` methods, as a
+/// PSR-7 `StreamInterface` does.
+fn is_stream(source: &Zval) -> Result<bool, SonicError> {
+    let method_exists = ZendCallable::try_from_name("method_exists")
+        .map_err(|_| SonicError::Internal("method_exists() is unavailable".to_string()))?;
+    for method in ["read", "eof"] {
+        let exists = method_exists
+            .try_call(vec![source, &method])
+            .map_err(|e| SonicError::TypeError(format!("method_exists() failed: {}", e)))?;
+        if exists.bool() != Some(true) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Read the stream object `stream` from its current position until
+/// `eof()`, up to just past the size limit.
+fn read_stream_object(stream: &ZendObject) -> Result<Vec<u8>, SonicError> {
+    let mut bytes = Vec::new();
+    while bytes.len() <= MAX_INPUT_SIZE {
+        let eof = stream
+            .try_call_method("eof", vec![])
+            .map_err(|e| SonicError::IoError(format!("eof() failed: {}", e)))?;
+        if eof.bool() == Some(true) {
+            break;
+        }
+        let chunk = stream
+            .try_call_method("read", vec![&READ_CHUNK])
+            .map_err(|e| SonicError::IoError(format!("read() failed: {}", e)))?;
+        let Some(chunk) = chunk.binary_slice::<u8>() else {
+            return Err(SonicError::IoError(
+                "read() did not return a string".to_string(),
+            ));
+        };
+        // Nothing available, though not at the end: stop rather than spin
+        if chunk.is_empty() {
+            break;
+        }
+        bytes.extend_from_slice(chunk);
+    }
+    Ok(bytes)
+}
+
+/// Encode an already decoded value back to JSON text.
+fn encode(value: &Zval) -> Result<Vec<u8>, SonicError> {
+    let mut out = String::new();
+    encoder::encode_into(&mut out, value)?;
+    Ok(out.into_bytes())
+}
@@ -0,0 +1,87 @@
+//! Temp-file output for `Query::rawSpill()`.
+//!
+//! Usage:
+//! ```php
+//! $raw = Sift::query($upstream)->get('export')->rawSpill(1 << 20);
+//! if ($raw['path'] !== null) {
+//!     readfile($raw['path']);
+//!     unlink($raw['path']);
+//! } else {
+//!     echo $raw['raw'];
+//! }
+//! ```
+//!
+//! Files are created with `create_new`, so an existing file (or a link
+//! planted under a predictable name) is never written through, and are
+//! readable by their owner only. Removing them is up to the caller.
+
+use crate::errors::SonicError;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Attempts at a fresh name before giving up.
+const MAX_ATTEMPTS: u32 = 16;
+
+/// Distinguishes files created by this process in the same nanosecond.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Create a new file in `directory` (the system temp directory if None).
+fn create(directory: Option<&str>) -> Result<(PathBuf, File), SonicError> {
+    let directory = directory.map_or_else(std::env::temp_dir, PathBuf::from);
+    for _ in 0..MAX_ATTEMPTS {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.subsec_nanos());
+        let name = format!(
+            "sift-raw-{}-{}-{}.json",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            nanos
+        );
+        let path = directory.join(name);
+        match open_new(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                return Err(SonicError::IoError(format!(
+                    "Cannot create a temp file in '{}': {}",
+                    directory.display(),
+                    e
+                )))
+            }
+        }
+    }
+    Err(SonicError::IoError(format!(
+        "Cannot create a temp file in '{}': no free name",
+        directory.display()
+    )))
+}
+
+/// Open `path` for writing, failing if it exists.
+fn open_new(path: &Path) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// Write `raw` to a new temp file and return its path. A partly written
+/// file is removed.
+pub fn spill(raw: &str, directory: Option<&str>) -> Result<String, SonicError> {
+    let (path, mut file) = create(directory)?;
+    if let Err(e) = file.write_all(raw.as_bytes()).and_then(|_| file.flush()) {
+        let _ = std::fs::remove_file(&path);
+        return Err(e.into());
+    }
+    path.into_os_string().into_string().map_err(|path| {
+        let _ = std::fs::remove_file(&path);
+        SonicError::IoError("Temp file path is not valid UTF-8".to_string())
+    })
+}
@@ -0,0 +1,112 @@
+//! Null member removal for `Sift::stripNulls()`.
+//!
+//! Usage:
+//! ```php
+//! $payload = Sift::stripNulls($json);                    // drop "key": null everywhere
+//! $payload = Sift::stripNulls($json, empties: true);     // and {} / [] members
+//! ```
+//!
+//! Objects that are processed are rewritten compactly, without the
+//! whitespace between their members, and their keys are re-escaped;
+//! numbers, strings and the subtrees that are not processed keep their
+//! text. Array elements are never removed, as that would shift the
+//! indices of the rest.
+
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::parser::{self, MAX_DEPTH};
+use sonic_rs::{to_array_iter, to_object_iter, JsonType, LazyValue};
+
+/// Remove the members of the top-level object of `json` whose value is
+/// null (with `empties`, also `{}` or `[]`), and with `recursive` those of
+/// every nested object too.
+pub fn strip_nulls(json: &str, recursive: bool, empties: bool) -> Result<String, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+    // Subtrees are copied unparsed, so validate them up front
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+
+    let root = parser::lazy_at(json, "")?;
+    let strip = Strip { recursive, empties };
+    let mut out = String::with_capacity(json.len());
+    strip.write(&mut out, &root, 0)?;
+    Ok(out)
+}
+
+struct Strip {
+    /// Process nested containers, not just the top level
+    recursive: bool,
+    /// Remove members holding empty arrays and objects as well
+    empties: bool,
+}
+
+impl Strip {
+    /// Write `value`, stripped if it is processed. Returns whether it
+    /// should be left out as an object member.
+    fn write(&self, out: &mut String, value: &LazyValue, depth: usize) -> Result<bool, SonicError> {
+        if depth > MAX_DEPTH {
+            return Err(SonicError::ParseError(format!(
+                "Maximum nesting depth ({}) exceeded",
+                MAX_DEPTH
+            )));
+        }
+        let raw = value.as_raw_str();
+        let processed = depth == 0 || self.recursive;
+        match value.get_type() {
+            JsonType::Null => {
+                out.push_str("null");
+                Ok(true)
+            }
+            JsonType::Object if processed => {
+                out.push('{');
+                let mut empty = true;
+                for member in to_object_iter(raw) {
+                    let (key, item) = member?;
+                    let start = out.len();
+                    if !empty {
+                        out.push(',');
+                    }
+                    encoder::write_string(out, key.as_bytes())?;
+                    out.push(':');
+                    if self.write(out, &item, depth + 1)? {
+                        out.truncate(start);
+                    } else {
+                        empty = false;
+                    }
+                }
+                out.push('}');
+                Ok(empty && self.empties)
+            }
+            JsonType::Array if processed => {
+                out.push('[');
+                let mut empty = true;
+                for item in to_array_iter(raw) {
+                    if !empty {
+                        out.push(',');
+                    }
+                    self.write(out, &item?, depth + 1)?;
+                    empty = false;
+                }
+                out.push(']');
+                Ok(empty && self.empties)
+            }
+            JsonType::Object | JsonType::Array => {
+                out.push_str(raw.trim());
+                Ok(self.empties && is_empty_container(raw)?)
+            }
+            _ => {
+                out.push_str(raw.trim());
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// Whether the array or object text `raw` has no elements or members.
+fn is_empty_container(raw: &str) -> Result<bool, SonicError> {
+    if raw.trim_start().starts_with('{') {
+        Ok(to_object_iter(raw).next().transpose()?.is_none())
+    } else {
+        Ok(to_array_iter(raw).next().transpose()?.is_none())
+    }
+}
@@ -0,0 +1,253 @@
+//! Token stream with byte offsets for `Sift::tokenize()`.
+//!
+//! Usage:
+//! ```php
+//! foreach (Sift::tokenize($json) as [$type, $start, $end]) {
+//!     $html .= highlight($type, substr($json, $start, $end - $start));
+//! }
+//! ```
+
+use crate::errors::SonicError;
+use crate::guard::guard;
+use crate::leaks::Live;
+use crate::parser;
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendHashTable, Zval};
+use ext_php_rs::zend::ce;
+
+/// The kinds of token `Tokens` yields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenType {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    Colon,
+    Comma,
+    /// A string in key position
+    Key,
+    String,
+    Number,
+    True,
+    False,
+    Null,
+}
+
+impl TokenType {
+    /// The name PHP sees.
+    fn name(self) -> &'static str {
+        match self {
+            TokenType::BeginObject => "begin_object",
+            TokenType::EndObject => "end_object",
+            TokenType::BeginArray => "begin_array",
+            TokenType::EndArray => "end_array",
+            TokenType::Colon => "colon",
+            TokenType::Comma => "comma",
+            TokenType::Key => "key",
+            TokenType::String => "string",
+            TokenType::Number => "number",
+            TokenType::True => "true",
+            TokenType::False => "false",
+            TokenType::Null => "null",
+        }
+    }
+}
+
+/// A token and its byte range in the input, end exclusive.
+#[derive(Clone, Copy, Debug)]
+pub struct Token {
+    pub kind: TokenType,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Scanning state over a validated document, which the caller passes to
+/// each `next_token()` call. Shared by `Tokens` and the structural index.
+#[derive(Debug, Default)]
+pub struct Scanner {
+    /// Where scanning resumes
+    pos: usize,
+    /// Whether each open container is an object, innermost last
+    stack: Vec<bool>,
+    /// Whether a string at `pos` would be a key
+    expect_key: bool,
+}
+
+/// Tokens - iterates over the tokens of a validated document as
+/// `[type, start, end]` lists.
+/// Tokens are scanned one at a time as the iteration advances, so no
+/// token list is built up front.
+#[php_class(name = "Sift\\Tokens")]
+#[implements(ce::iterator())]
+#[derive(Debug)]
+pub struct Tokens {
+    /// The document, validated
+    json: String,
+    scanner: Scanner,
+    /// The token `current()` returns; None once the input is exhausted
+    token: Option<Token>,
+    /// Index of the current token
+    key: i64,
+    /// Counts this object for `sift.leak_check` (see `leaks.rs`)
+    live: Live,
+}
+
+impl Tokens {
+    /// Validate `json` and position the stream at its first token.
+    pub fn new(json: &str) -> Result<Self, SonicError> {
+        // Validate input size to prevent DoS
+        parser::check_input_size(json)?;
+        // The scanner trusts the structure, so the whole document is checked first
+        sonic_rs::from_str::<sonic_rs::Value>(json)?;
+        let mut tokens = Self {
+            json: json.to_string(),
+            scanner: Scanner::default(),
+            token: None,
+            key: 0,
+            live: Live::new("Sift\\Tokens"),
+        };
+        tokens.rewind_scan();
+        Ok(tokens)
+    }
+
+    /// Go back to the first token.
+    fn rewind_scan(&mut self) {
+        self.scanner = Scanner::default();
+        self.key = 0;
+        self.token = self.scanner.next_token(self.json.as_bytes());
+    }
+}
+
+impl Scanner {
+    /// Scan the token at `pos` in `bytes`, which must be the same
+    /// validated document on every call, and move past it; None at the end.
+    pub fn next_token(&mut self, bytes: &[u8]) -> Option<Token> {
+        let start = skip_whitespace(bytes, self.pos);
+        let first = *bytes.get(start)?;
+        let in_object = self.stack.last() == Some(&true);
+        let (kind, end) = match first {
+            b'{' => {
+                self.stack.push(true);
+                self.expect_key = true;
+                (TokenType::BeginObject, start + 1)
+            }
+            b'[' => {
+                self.stack.push(false);
+                self.expect_key = false;
+                (TokenType::BeginArray, start + 1)
+            }
+            b'}' | b']' => {
+                self.stack.pop();
+                self.expect_key = false;
+                let kind = if first == b'}' {
+                    TokenType::EndObject
+                } else {
+                    TokenType::EndArray
+                };
+                (kind, start + 1)
+            }
+            b':' => {
+                self.expect_key = false;
+                (TokenType::Colon, start + 1)
+            }
+            b',' => {
+                self.expect_key = in_object;
+                (TokenType::Comma, start + 1)
+            }
+            b'"' => {
+                let kind = if self.expect_key {
+                    TokenType::Key
+                } else {
+                    TokenType::String
+                };
+                self.expect_key = false;
+                (kind, string_end(bytes, start))
+            }
+            b't' => (TokenType::True, start + 4),
+            b'f' => (TokenType::False, start + 5),
+            b'n' => (TokenType::Null, start + 4),
+            _ => {
+                let len = bytes[start..]
+                    .iter()
+                    .take_while(|b| matches!(b, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E'))
+                    .count();
+                (TokenType::Number, start + len)
+            }
+        };
+        self.pos = end;
+        Some(Token { kind, start, end })
+    }
+}
+
+#[php_impl]
+impl Tokens {
+    /// Start over from the first token.
+    pub fn rewind(&mut self) -> Result<(), SonicError> {
+        guard(|| {
+            self.rewind_scan();
+            Ok(())
+        })
+    }
+
+    /// Whether there is a current token.
+    pub fn valid(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// The current token as `[type, start, end]`: its type name and byte
+    /// range, end exclusive. Returns null once the input is exhausted.
+    pub fn current(&self) -> Result<Zval, SonicError> {
+        guard(|| {
+            let Some(token) = self.token else {
+                return Ok(Zval::new());
+            };
+            let mut list = ZendHashTable::new();
+            list.push(token.kind.name())
+                .and_then(|_| list.push(token.start as i64))
+                .and_then(|_| list.push(token.end as i64))
+                .map_err(|e| SonicError::TypeError(format!("Failed to push array item: {}", e)))?;
+            let mut zval = Zval::new();
+            zval.set_hashtable(list);
+            Ok(zval)
+        })
+    }
+
+    /// Index of the current token (0, 1, 2, ...), or null once exhausted.
+    pub fn key(&self) -> Option<i64> {
+        self.token.map(|_| self.key)
+    }
+
+    /// Move on to the next token.
+    pub fn next(&mut self) -> Result<(), SonicError> {
+        guard(|| {
+            if self.token.is_some() {
+                self.key += 1;
+                self.token = self.scanner.next_token(self.json.as_bytes());
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Skip JSON whitespace from `pos`.
+fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    while matches!(bytes.get(pos), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+        pos += 1;
+    }
+    pos
+}
+
+/// The offset just past the closing quote of the string starting at the
+/// quote `start`. The document is valid, so the string is terminated.
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let mut pos = start + 1;
+    while let Some(found) = bytes[pos..].iter().position(|&b| b == b'"' || b == b'\\') {
+        pos += found;
+        if bytes[pos] == b'"' {
+            return pos + 1;
+        }
+        // Skip the backslash and the character it escapes
+        pos += 2;
+    }
+    bytes.len()
+}
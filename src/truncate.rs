@@ -0,0 +1,257 @@
+//! Size-bounded JSON summaries for `Sift::truncate()`.
+//!
+//! Usage:
+//! ```php
+//! $logger->error('Upstream rejected request', ['body' => Sift::truncate($body, 2048)]);
+//! // {"items":[{"id":1,"note":"Lorem ipsum…"},"… 998 more"],"total":1000}
+//! ```
+
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::parser::{self, MAX_DEPTH};
+use sonic_rs::{to_array_iter_unchecked, to_object_iter_unchecked};
+use sonic_rs::{JsonValueTrait, LazyValue, PointerNode};
+
+/// Marks elided string content and left-out elements.
+const ELLIPSIS: &str = "…";
+
+/// The largest valid JSON that summarizes `json` in at most `max_bytes`
+/// bytes. A document that fits is returned as it is. Otherwise values
+/// are kept in document order while they fit: long strings are cut short
+/// with "…", and the elements of an array (members of an object) that
+/// don't fit are replaced by a final `"… N more"` element
+/// (`"…": "N more"` member). Kept values are copied verbatim.
+pub fn truncate(json: &str, max_bytes: i64) -> Result<String, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+    if max_bytes < 0 {
+        return Err(SonicError::InvalidOption(format!(
+            "Byte budget must not be negative, got {}",
+            max_bytes
+        )));
+    }
+    // Values are summarized lazily, so the whole document is checked first
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+    if json.len() as i64 <= max_bytes {
+        return Ok(json.to_string());
+    }
+
+    let root = sonic_rs::get(json, &[] as &[PointerNode])?;
+    let mut out = String::new();
+    if !write_value(&mut out, &root, max_bytes as usize, 0)? {
+        return Err(SonicError::InvalidOption(format!(
+            "Byte budget of {} is too small to summarize this document",
+            max_bytes
+        )));
+    }
+    Ok(out)
+}
+
+/// Internal: append a summary of `value` of at most `budget` bytes to
+/// `out`. Returns false, leaving `out` as it was, if even the shortest
+/// summary doesn't fit.
+fn write_value(
+    out: &mut String,
+    value: &LazyValue,
+    budget: usize,
+    depth: usize,
+) -> Result<bool, SonicError> {
+    if depth > MAX_DEPTH {
+        return Err(SonicError::ParseError(format!(
+            "Maximum nesting depth ({}) exceeded",
+            MAX_DEPTH
+        )));
+    }
+
+    let raw = value.as_raw_str();
+    if raw.len() <= budget {
+        out.push_str(raw);
+        return Ok(true);
+    }
+    if value.is_str() {
+        Ok(write_string(out, raw, budget))
+    } else if value.is_array() {
+        write_array(out, value, budget, depth)
+    } else if value.is_object() {
+        write_object(out, value, budget, depth)
+    } else {
+        // Numbers, booleans and null can't be shortened
+        Ok(false)
+    }
+}
+
+/// Write the longest prefix of the string `raw` (JSON text, quotes
+/// included) that fits in `budget` with "…" and the closing quote.
+/// Escape sequences and characters are never split.
+fn write_string(out: &mut String, raw: &str, budget: usize) -> bool {
+    // Opening quote, ellipsis, closing quote
+    let overhead = 2 + ELLIPSIS.len();
+    if budget < overhead {
+        return false;
+    }
+
+    let inner = &raw[1..raw.len() - 1];
+    let bytes = inner.as_bytes();
+    let mut end = 0;
+    while end < bytes.len() {
+        let next = end + token_len(bytes, end);
+        if next + overhead > budget {
+            break;
+        }
+        end = next;
+    }
+    out.push('"');
+    out.push_str(&inner[..end]);
+    out.push_str(ELLIPSIS);
+    out.push('"');
+    true
+}
+
+/// Length of the escape sequence or character starting at `at` in the
+/// text of a valid JSON string. A surrogate pair is one token.
+fn token_len(bytes: &[u8], at: usize) -> usize {
+    match bytes[at] {
+        b'\\' if bytes.get(at + 1) == Some(&b'u') => {
+            let high = bytes
+                .get(at + 2..at + 6)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u16::from_str_radix(hex, 16).ok());
+            let paired = bytes.get(at + 6..at + 8) == Some(b"\\u");
+            match high {
+                Some(0xD800..=0xDBFF) if paired => 12,
+                _ => 6,
+            }
+        }
+        b'\\' => 2,
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        _ => 4,
+    }
+}
+
+/// The marker standing in for `left_out` elements: the array element
+/// `"… N more"`, or with `member` the object member `"…":"N more"`.
+fn marker(left_out: usize, member: bool) -> String {
+    if member {
+        format!("\"{}\":\"{} more\"", ELLIPSIS, left_out)
+    } else {
+        format!("\"{} {} more\"", ELLIPSIS, left_out)
+    }
+}
+
+/// Room kept free after an element so the container can still be closed
+/// with a marker for the `after` elements that follow it.
+fn reserve(after: usize, member: bool) -> usize {
+    match after {
+        0 => 1,
+        _ => 2 + marker(after, member).len(),
+    }
+}
+
+/// Write the marker for `left_out` elements, preceded by a comma unless
+/// it is the first entry.
+fn write_marker(out: &mut String, left_out: usize, member: bool, first: bool) {
+    if !first {
+        out.push(',');
+    }
+    out.push_str(&marker(left_out, member));
+}
+
+/// Write as many elements of the array as fit, then a marker for the rest.
+fn write_array(
+    out: &mut String,
+    value: &LazyValue,
+    budget: usize,
+    depth: usize,
+) -> Result<bool, SonicError> {
+    let total = parser::element_count(value)?.unwrap_or(0);
+    let start = out.len();
+    let limit = start + budget;
+    // Brackets plus a marker for every element, in case none fits
+    let shortest = if total == 0 {
+        2
+    } else {
+        2 + marker(total, false).len()
+    };
+    if shortest > budget {
+        return Ok(false);
+    }
+
+    out.push('[');
+    let mut kept = 0;
+    // SAFETY: the caller has verified this is an array via is_array()
+    for item in unsafe { to_array_iter_unchecked(value.as_raw_str()) } {
+        let item = item?;
+        let after = total - kept - 1;
+        let separator = usize::from(kept > 0);
+        let reserve = reserve(after, false);
+        let Some(room) = (limit - out.len()).checked_sub(separator + reserve) else {
+            break;
+        };
+        let mark = out.len();
+        if separator > 0 {
+            out.push(',');
+        }
+        if !write_value(out, &item, room, depth + 1)? {
+            out.truncate(mark);
+            break;
+        }
+        kept += 1;
+    }
+    if kept < total {
+        write_marker(out, total - kept, false, kept == 0);
+    }
+    out.push(']');
+    Ok(true)
+}
+
+/// Write as many members of the object as fit, then a marker for the rest.
+fn write_object(
+    out: &mut String,
+    value: &LazyValue,
+    budget: usize,
+    depth: usize,
+) -> Result<bool, SonicError> {
+    let total = parser::element_count(value)?.unwrap_or(0);
+    let start = out.len();
+    let limit = start + budget;
+    // Braces plus a marker for every member, in case none fits
+    let shortest = if total == 0 {
+        2
+    } else {
+        2 + marker(total, true).len()
+    };
+    if shortest > budget {
+        return Ok(false);
+    }
+
+    out.push('{');
+    let mut kept = 0;
+    // SAFETY: the caller has verified this is an object via is_object()
+    for entry in unsafe { to_object_iter_unchecked(value.as_raw_str()) } {
+        let (key, item) = entry?;
+        let after = total - kept - 1;
+        let mark = out.len();
+        if kept > 0 {
+            out.push(',');
+        }
+        encoder::write_string(out, key.as_bytes())?;
+        out.push(':');
+        let reserve = reserve(after, true);
+        let fits = match (limit - mark).checked_sub(out.len() - mark + reserve) {
+            Some(room) => write_value(out, &item, room, depth + 1)?,
+            None => false,
+        };
+        if !fits {
+            out.truncate(mark);
+            break;
+        }
+        kept += 1;
+    }
+    if kept < total {
+        write_marker(out, total - kept, true, kept == 0);
+    }
+    out.push('}');
+    Ok(true)
+}
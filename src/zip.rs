@@ -0,0 +1,147 @@
+//! Element-wise combination of two JSON arrays for `Sift::zip()`.
+//!
+//! Usage:
+//! ```php
+//! // [[a0, b0], [a1, b1], ...]
+//! $pairs = Sift::zip($ledgerExport, $bankExport);
+//! // [{...a0, ...b0}, ...]
+//! $rows = Sift::zip($orders, $shipments, 'merge');
+//! ```
+//!
+//! The arrays are walked side by side and the result is written as JSON
+//! text: elements are copied without being decoded, and only `'merge'`
+//! looks inside them, at their top-level members. A shorter array is
+//! padded with nulls, as `array_map()` does with several arrays; `'merge'`
+//! treats null as an empty object.
+
+use crate::encoder;
+use crate::errors::SonicError;
+use crate::parser;
+use faststr::FastStr;
+use sonic_rs::{to_array_iter, to_object_iter, JsonType, JsonValueTrait, LazyValue};
+use std::collections::HashMap;
+
+/// How two elements at the same index are combined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// A two-element array `[a, b]`
+    Pairs,
+    /// One object with the members of both; `b`'s win on a clash
+    Merge,
+}
+
+impl Strategy {
+    pub fn from_name(name: &str) -> Result<Self, SonicError> {
+        match name {
+            "pairs" => Ok(Strategy::Pairs),
+            "merge" => Ok(Strategy::Merge),
+            _ => Err(SonicError::InvalidOption(format!(
+                "Unknown zip strategy '{}', expected 'pairs' or 'merge'",
+                name
+            ))),
+        }
+    }
+}
+
+/// Combine the elements of the arrays `left` and `right` pairwise.
+pub fn zip(left: &str, right: &str, strategy: Strategy) -> Result<String, SonicError> {
+    let left_root = array_root(left)?;
+    let right_root = array_root(right)?;
+    let mut left_items = to_array_iter(left_root.as_raw_str());
+    let mut right_items = to_array_iter(right_root.as_raw_str());
+
+    let mut out = String::with_capacity(left.len() + right.len());
+    out.push('[');
+    for index in 0.. {
+        let a = left_items.next().transpose()?;
+        let b = right_items.next().transpose()?;
+        if a.is_none() && b.is_none() {
+            break;
+        }
+        if index > 0 {
+            out.push(',');
+        }
+        match strategy {
+            Strategy::Pairs => {
+                out.push('[');
+                out.push_str(a.as_ref().map_or("null", raw_text));
+                out.push(',');
+                out.push_str(b.as_ref().map_or("null", raw_text));
+                out.push(']');
+            }
+            Strategy::Merge => merge(&mut out, a.as_ref(), b.as_ref(), index)?,
+        }
+    }
+    out.push(']');
+    Ok(out)
+}
+
+/// The top-level array of `json`, which is validated in full since its
+/// elements are copied unparsed.
+fn array_root(json: &str) -> Result<LazyValue<'_>, SonicError> {
+    // Validate input size to prevent DoS
+    parser::check_input_size(json)?;
+    sonic_rs::from_str::<sonic_rs::Value>(json)?;
+    let root = parser::lazy_at(json, "")?;
+    if !root.is_array() {
+        return Err(
+            SonicError::TypeError("zip() expects JSON arrays".to_string())
+                .with_types("array", parser::type_name(&root)),
+        );
+    }
+    Ok(root)
+}
+
+/// The JSON text of `value`.
+fn raw_text<'v>(value: &'v LazyValue) -> &'v str {
+    value.as_raw_str().trim()
+}
+
+/// Write the object holding the members of `a` and then `b` (either
+/// missing or null meaning none), `b`'s value replacing `a`'s for a
+/// repeated key.
+fn merge(
+    out: &mut String,
+    a: Option<&LazyValue>,
+    b: Option<&LazyValue>,
+    index: usize,
+) -> Result<(), SonicError> {
+    let mut members: Vec<(FastStr, LazyValue)> = Vec::new();
+    let mut positions: HashMap<FastStr, usize> = HashMap::new();
+    for item in [a, b].into_iter().flatten() {
+        match item.get_type() {
+            JsonType::Null => continue,
+            JsonType::Object => {}
+            _ => {
+                return Err(SonicError::TypeError(format!(
+                    "Element {} is not an object, so it can't be merged",
+                    index
+                ))
+                .at_pointer(format!("/{}", index))
+                .with_types("object", parser::type_name(item)))
+            }
+        }
+        for member in to_object_iter(item.as_raw_str()) {
+            let (key, value) = member?;
+            match positions.get(&key) {
+                Some(&position) => members[position].1 = value,
+                None => {
+                    positions.insert(key.clone(), members.len());
+                    members.push((key, value));
+                }
+            }
+        }
+    }
+
+    out.push('{');
+    for (i, (key, value)) in members.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        encoder::write_string(out, key.as_bytes())?;
+        out.push(':');
+        out.push_str(raw_text(value));
+    }
+    out.push('}');
+    Ok(())
+}
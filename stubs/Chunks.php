@@ -0,0 +1,82 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * Chunks - an array hydrated a chunk at a time
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Returned by Query::chunks(). Each iteration yields a list of up to N
+ * hydrated elements, keyed by chunk index (0, 1, 2, ...). Chunks are read
+ * from where the previous one stopped, so the array is scanned once and
+ * only the current chunk exists as PHP values. The array is resolved on
+ * the first rewind(); a missing path or a non-array value throws then.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ * @implements \Iterator<int, list<mixed>>
+ */
+class Chunks implements \Iterator
+{
+    /**
+     * Start over from the first element of the array, or from the offset
+     * given to Query::chunks().
+     *
+     * @throws \Sift\Exception If the path is not found or is not an array
+     */
+    public function rewind(): void
+    {
+    }
+
+    /**
+     * Whether there is a current chunk.
+     *
+     * @throws \Sift\Exception If the path is not found or is not an array
+     */
+    public function valid(): bool
+    {
+    }
+
+    /**
+     * The current chunk, or null once the array is exhausted.
+     *
+     * @return list<mixed>|null
+     * @throws \Sift\Exception If an element is malformed or cannot be hydrated
+     */
+    public function current(): mixed
+    {
+    }
+
+    /**
+     * Index of the current chunk, or null once the array is exhausted.
+     */
+    public function key(): ?int
+    {
+    }
+
+    /**
+     * Move on to the next chunk.
+     *
+     * @throws \Sift\Exception If an element is malformed or cannot be hydrated
+     */
+    public function next(): void
+    {
+    }
+
+    /**
+     * Byte offset in the document just past the current chunk.
+     *
+     * Pass it to Query::chunks() to resume after the chunks processed so
+     * far. Once the array is exhausted it is the offset of the closing ']',
+     * which resumes to no chunks.
+     *
+     * @throws \Sift\Exception If the path is not found or is not an array
+     */
+    public function tell(): int
+    {
+    }
+}
@@ -0,0 +1,128 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * Exception - base class for errors thrown by Sift
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Every error except strict-mode decode failures (which stay \JsonException
+ * for json_decode compatibility) is a Sift\Exception. Handle errors by code
+ * and context instead of matching message text:
+ *
+ *     try {
+ *         $id = \Sift::query($json)->pointer('/user/id')->int();
+ *     } catch (\Sift\Exception $e) {
+ *         if ($e->getErrorCode() === \Sift\Exception::KEY_NOT_FOUND) {
+ *             // $e->getPointer() === '/user/id'
+ *         }
+ *     }
+ *
+ * Codes are stable across releases; getCode() returns the same value as
+ * getErrorCode().
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ */
+class Exception extends \Exception
+{
+    /** Invalid JSON or nested too deeply */
+    public const PARSE_ERROR = 1;
+
+    /** Malformed JSON pointer, negative index or too many path segments */
+    public const INVALID_POINTER = 2;
+
+    /** Nothing exists at the requested path */
+    public const KEY_NOT_FOUND = 3;
+
+    /** The value at the path has a different type than requested */
+    public const TYPE_ERROR = 4;
+
+    /** Reading input failed */
+    public const IO_ERROR = 5;
+
+    /** Unknown option or option value */
+    public const INVALID_OPTION = 6;
+
+    /** A string is not in the requested format (see FormatException) */
+    public const FORMAT_ERROR = 7;
+
+    /** Input exceeds the size limit (see InputTooLargeException) */
+    public const INPUT_TOO_LARGE = 8;
+
+    /** A bug in the extension was caught instead of crashing the worker (see InternalException) */
+    public const INTERNAL_ERROR = 9;
+
+    /** A document id refers to a released document (see StaleHandleException) */
+    public const STALE_HANDLE = 10;
+
+    /** Sift objects outlived a leak-check checkpoint (see Sift::checkLeaks()) */
+    public const LEAKED_OBJECTS = 11;
+
+    /**
+     * Stable machine-readable error code, one of the class constants.
+     *
+     * @return int
+     */
+    public function getErrorCode(): int
+    {
+    }
+
+    /**
+     * JSON pointer (RFC 6901) of the value the error is about, if any.
+     *
+     * @return string|null
+     */
+    public function getPointer(): ?string
+    {
+    }
+
+    /**
+     * Byte offset into the input where parsing failed, for parse errors.
+     *
+     * @return int|null
+     */
+    public function getOffset(): ?int
+    {
+    }
+
+    /**
+     * Type that was requested ("string", "integer", "float", "boolean"),
+     * for type errors.
+     *
+     * @return string|null
+     */
+    public function getExpectedType(): ?string
+    {
+    }
+
+    /**
+     * Type found at the pointer (as reported by Query::getType()), for
+     * type errors.
+     *
+     * @return string|null
+     */
+    public function getActualType(): ?string
+    {
+    }
+
+    /**
+     * Existing pointers close to one that wasn't found, closest first, for
+     * key-not-found errors. Up to three, taken from the keys of the object
+     * where the path stopped; also quoted in the message as "did you mean".
+     *
+     * @return string[]
+     *
+     * @example
+     * catch (\Sift\Exception $e) {
+     *     $e->getSuggestions(); // ['/user/email']
+     * }
+     */
+    public function getSuggestions(): array
+    {
+    }
+}
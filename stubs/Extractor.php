@@ -0,0 +1,36 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * Extractor - a compiled extraction template
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Returned by Sift::compileExtractor(). The template's pointers are parsed
+ * and validated once; extract() then only parses each document, giving the
+ * same results as Sift::extract() with the same template. Keep one per
+ * template in long-running workers.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ */
+class Extractor
+{
+    /**
+     * Apply the template to a JSON document.
+     *
+     * @param string $json JSON string
+     * @return array
+     * @throws \Sift\Exception If the JSON is invalid or too large
+     *
+     * @example
+     * $row = $extractor->extract($message);
+     */
+    public function extract(string $json): array
+    {
+    }
+}
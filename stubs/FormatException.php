@@ -0,0 +1,25 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * FormatException - a string is not in the expected format
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Thrown by Query::uuid(), email(), url(), ip() and base64(). The message names the
+ * JSON pointer of the offending value, e.g.
+ * "Value at '/users/0/email' is not a valid email address"; getPointer()
+ * returns the same pointer and getErrorCode() returns FORMAT_ERROR.
+ *
+ * Also thrown by Sift::jwtClaims() for malformed tokens.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ */
+class FormatException extends Exception
+{
+}
@@ -0,0 +1,90 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * FrameReader - JSON frames from a Server-Sent Events stream
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Feed it chunks exactly as they arrive from an SSE response (e.g. an LLM
+ * streaming API read through a chunked-transfer connection); chunks may split
+ * lines, events or UTF-8 sequences anywhere. Each completed event's 'data:'
+ * lines are joined with "\n" and decoded as JSON. Lines end in LF, CRLF or
+ * CR; comments (":keep-alive") and other fields (event, id, retry) are
+ * skipped. A 'data: [DONE]' event is not decoded; it sets isDone().
+ *
+ * Security limits: a single line or event larger than the input size limit
+ * throws \Sift\InputTooLargeException.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ * @see https://html.spec.whatwg.org/multipage/server-sent-events.html
+ */
+class FrameReader
+{
+    /**
+     * @param array|null $options Decode options for the frames, as for Sift::decode()
+     * @param array{max_bytes?: int, when_full?: 'error'}|null $buffer Limit on
+     *     the bytes of incomplete events held between pushes (pending()).
+     *     push() throws \Sift\InputTooLargeException once they exceed
+     *     max_bytes and drops the event in progress. Only 'error' is
+     *     accepted: the caller of push() is the producer and can't be made
+     *     to wait
+     * @throws \Sift\Exception If an option is unknown
+     */
+    public function __construct(?array $options = null, ?array $buffer = null)
+    {
+    }
+
+    /**
+     * Feed the next chunk of the stream.
+     *
+     * @param string $chunk Raw bytes, split anywhere
+     * @return list<mixed> Decoded values of the events this chunk completed, in order
+     * @throws \Sift\Exception If a completed event's data is not valid JSON. The
+     *     event is dropped and the reader can keep going, but values decoded
+     *     earlier in the same call are lost
+     *
+     * @example
+     * $reader = new \Sift\FrameReader();
+     * while (!feof($stream) && !$reader->isDone()) {
+     *     foreach ($reader->push(fread($stream, 8192)) as $delta) {
+     *         echo $delta['choices'][0]['delta']['content'] ?? '';
+     *     }
+     * }
+     */
+    public function push(string $chunk): array
+    {
+    }
+
+    /**
+     * Signal the end of the stream.
+     *
+     * An unterminated last line and an event missing its closing blank line
+     * are decoded rather than discarded.
+     *
+     * @return list<mixed> Decoded values of the remaining events
+     * @throws \Sift\Exception If the remaining data is not valid JSON
+     */
+    public function finish(): array
+    {
+    }
+
+    /**
+     * Whether a 'data: [DONE]' event has been received.
+     */
+    public function isDone(): bool
+    {
+    }
+
+    /**
+     * Bytes received that don't yet form a complete event.
+     */
+    public function pending(): int
+    {
+    }
+}
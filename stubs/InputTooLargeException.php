@@ -0,0 +1,53 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * InputTooLargeException - the input exceeds the size limit
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Thrown before any parsing by every entry point that takes JSON text:
+ * decode(), decodeLazy(), get(), firstOf(), isValidStrict(), snapshot()
+ * and Query reads. (isValid() returns false instead.) Catch it separately from malformed JSON to fall back to a
+ * streaming reader:
+ *
+ *     try {
+ *         $data = \Sift::decode($json);
+ *     } catch (\Sift\InputTooLargeException $e) {
+ *         // $e->getSize() bytes given, $e->getLimit() allowed
+ *     }
+ *
+ * Also thrown when a document of 64 KB or more copied into Rust (query(),
+ * from(), fromRequest(), snapshot(), Pool::addDocument()) would take PHP's
+ * usage plus the extension's past memory_limit; getLimit() is then the
+ * memory left.
+ *
+ * getErrorCode() returns INPUT_TOO_LARGE.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ */
+class InputTooLargeException extends Exception
+{
+    /**
+     * Size of the rejected input in bytes.
+     *
+     * @return int
+     */
+    public function getSize(): int
+    {
+    }
+
+    /**
+     * Maximum accepted input size in bytes.
+     *
+     * @return int
+     */
+    public function getLimit(): int
+    {
+    }
+}
@@ -0,0 +1,23 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * InternalException - a bug in the extension was caught
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Thrown instead of crashing the PHP process when the extension panics.
+ * The message only names the source location, e.g.
+ * "Internal error in the Sift extension at src/parser.rs:123; please report it",
+ * never the input. getErrorCode() returns INTERNAL_ERROR.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ */
+class InternalException extends Exception
+{
+}
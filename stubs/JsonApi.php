@@ -0,0 +1,90 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * JsonApi - lookups over a JSON:API compound document
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Returned by Sift::jsonApi(). The first included()/related() call indexes
+ * the 'included' array by (type, id) in a single pass, recording where each
+ * resource is in the document; every lookup after that is a hash lookup that
+ * parses and hydrates only the resource it returns. Resources without a
+ * string type and id are not indexed.
+ *
+ * Methods that take $index work on the primary resource: omit it when 'data'
+ * is a single resource, pass the position when 'data' is a collection.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ * @see https://jsonapi.org/format/#document-compound-documents
+ */
+class JsonApi
+{
+    /**
+     * The primary data.
+     *
+     * @param int|null $index Position in a collection; null for the whole 'data'
+     * @return array|null The resource, the list of resources, or null
+     * @throws \Sift\Exception If 'data' is missing, or $index is given for a
+     *                        document whose data is not a collection
+     */
+    public function resource(?int $index = null): ?array
+    {
+    }
+
+    /**
+     * Relationship linkage of the primary resource.
+     *
+     * Relationships without 'data' (links only) are left out.
+     *
+     * @param int|null $index Position in a collection; null for a single resource
+     * @return array<string, array|null> Name => resource identifier, list of
+     *     identifiers, or null
+     * @throws \Sift\Exception If there is no such primary resource
+     *
+     * @example
+     * $doc->relationships();
+     * // ['author' => ['type' => 'people', 'id' => '9'], 'comments' => [...]]
+     */
+    public function relationships(?int $index = null): array
+    {
+    }
+
+    /**
+     * The included resource with this type and id.
+     *
+     * @return array|null The resource, or null if it isn't included
+     * @throws \Sift\Exception If 'included' is not an array
+     */
+    public function included(string $type, string $id): ?array
+    {
+    }
+
+    /**
+     * The included resources a relationship of the primary resource links to.
+     *
+     * @param string $relationship Relationship name
+     * @param int|null $index Position in a collection; null for a single resource
+     * @return array|null The resource (or null) for a to-one relationship; a
+     *     list for a to-many one, with null where a resource isn't included
+     * @throws \Sift\Exception If the relationship has no 'data' linkage
+     *
+     * @example
+     * foreach ($doc->related('comments') as $comment) { ... }
+     */
+    public function related(string $relationship, ?int $index = null): ?array
+    {
+    }
+
+    /**
+     * A Query over the whole document, for members such as 'meta' and 'links'.
+     */
+    public function query(): Query
+    {
+    }
+}
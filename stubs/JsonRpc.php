@@ -0,0 +1,70 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * JsonRpc - JSON-RPC 2.0 envelope parsing
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Envelopes are validated in Rust and 'params' is returned as a lazy Query,
+ * so a server can route a call without decoding a potentially large payload.
+ * Malformed bodies and envelopes don't throw: the returned RpcRequest is not
+ * valid and carries the error object the specification prescribes.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ * @see https://www.jsonrpc.org/specification
+ */
+final class JsonRpc
+{
+    /**
+     * Parse a single request.
+     *
+     * A body that is not valid JSON gives -32700 (Parse error); an envelope
+     * that is not a request object, or a batch, gives -32600 (Invalid Request).
+     *
+     * @param string $json The request body
+     * @param array|null $options Decode options, as for Sift::query()
+     * @return RpcRequest
+     * @throws \Sift\Exception If an option is unknown
+     * @throws \Sift\InputTooLargeException If the body exceeds the input size limit
+     *
+     * @example
+     * $request = \Sift\JsonRpc::parseRequest($body);
+     * if (!$request->isValid()) {
+     *     echo json_encode($request->errorResponse());
+     * }
+     */
+    public static function parseRequest(string $json, ?array $options = null): RpcRequest
+    {
+    }
+
+    /**
+     * Parse a batch: one RpcRequest per element, in order.
+     *
+     * A body that is not an array is parsed as a single request. An empty
+     * batch or a body that is not valid JSON gives a single rejected request,
+     * since the specification answers those with one error response rather
+     * than an array.
+     *
+     * @param string $json The request body
+     * @param array|null $options Decode options, as for Sift::query()
+     * @return list<RpcRequest>
+     * @throws \Sift\Exception If an option is unknown
+     * @throws \Sift\InputTooLargeException If the body exceeds the input size limit
+     *
+     * @example
+     * foreach (\Sift\JsonRpc::parseBatch($body) as $request) {
+     *     if ($request->isValid()) {
+     *         $results[] = $router->dispatch($request->method(), $request->params());
+     *     }
+     * }
+     */
+    public static function parseBatch(string $json, ?array $options = null): array
+    {
+    }
+}
@@ -0,0 +1,65 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift\Bridge;
+
+/**
+ * LaravelJson - Eloquent attribute cast storing JSON
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Has the get()/set() methods of Laravel's CastsAttributes, so it is used
+ * like any cast class. The argument after the colon picks what the stored
+ * JSON becomes: 'array' (default), 'object' (stdClass) or 'query' (a lazy
+ * Sift\Query, stored back as its JSON text).
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ *
+ * @example
+ * protected $casts = [
+ *     'settings' => \Sift\Bridge\LaravelJson::class,
+ *     'payload' => \Sift\Bridge\LaravelJson::class . ':query',
+ * ];
+ */
+class LaravelJson
+{
+    /**
+     * @param string|null $mode 'array' (default), 'object' or 'query'
+     * @throws \Sift\Exception If the mode is unknown
+     */
+    public function __construct(?string $mode = null)
+    {
+    }
+
+    /**
+     * Decode the stored JSON; null stays null.
+     *
+     * @param mixed $model The model
+     * @param string $key The attribute name
+     * @param mixed $value The stored JSON text, or null
+     * @param mixed $attributes The model's raw attributes
+     * @return mixed The decoded value, or a \Sift\Query in 'query' mode
+     * @throws \Sift\Exception If the JSON is invalid
+     */
+    public function get(mixed $model, string $key, mixed $value, mixed $attributes): mixed
+    {
+    }
+
+    /**
+     * Encode a value for storage; null stays null, and a \Sift\Query is
+     * stored as the JSON text it points at.
+     *
+     * @param mixed $model The model
+     * @param string $key The attribute name
+     * @param mixed $value The value to store
+     * @param mixed $attributes The model's raw attributes
+     * @return string|null The JSON text, or null
+     * @throws \Sift\Exception If the value can't be encoded
+     */
+    public function set(mixed $model, string $key, mixed $value, mixed $attributes): ?string
+    {
+    }
+}
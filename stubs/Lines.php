@@ -0,0 +1,82 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * Lines - the records of an NDJSON file, parsed in the background
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Returned by Sift::lines(). Each iteration yields one decoded record,
+ * keyed by the byte offset of its line in the file. The file is opened on
+ * the first rewind(), which starts a reader thread and the worker threads;
+ * they read at most the buffer limit given to Sift::lines() ahead of the
+ * loop, and stop when the iterator is rewound or destroyed.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ * @implements \Iterator<int, mixed>
+ */
+class Lines implements \Iterator
+{
+    /**
+     * Start over from the beginning of the file, or from the offset given
+     * to Sift::lines().
+     *
+     * @throws \Sift\Exception If the file can't be opened or the first record is malformed
+     */
+    public function rewind(): void
+    {
+    }
+
+    /**
+     * Whether there is a current record.
+     *
+     * @throws \Sift\Exception If the file can't be opened or the first record is malformed
+     */
+    public function valid(): bool
+    {
+    }
+
+    /**
+     * The current record, or null once the file is exhausted.
+     *
+     * @throws \Sift\Exception If the file can't be opened or the first record is malformed
+     */
+    public function current(): mixed
+    {
+    }
+
+    /**
+     * Byte offset of the current record's line, or null once the file is
+     * exhausted.
+     */
+    public function key(): ?int
+    {
+    }
+
+    /**
+     * Move on to the next record.
+     *
+     * @throws \Sift\Exception If the file can't be read or the line is not valid JSON
+     */
+    public function next(): void
+    {
+    }
+
+    /**
+     * Byte offset in the file just past the current record's line.
+     *
+     * Pass it to Sift::lines() to resume after the records processed so
+     * far. Once the file is exhausted it is the offset after the last
+     * record.
+     *
+     * @throws \Sift\Exception If the file can't be opened
+     */
+    public function tell(): int
+    {
+    }
+}
@@ -0,0 +1,67 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * MissingValue - the null object for a path that doesn't exist
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Returned by Query::lookup() when the path is absent. It answers the
+ * same isMissing() and orElse() calls as a Query, so callers can tell a
+ * missing key from a key holding null without catching exceptions.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ */
+class MissingValue
+{
+    /**
+     * Always true; Query::isMissing() is always false.
+     *
+     * @return bool True
+     */
+    public function isMissing(): bool
+    {
+    }
+
+    /**
+     * Return the default, as there is no value.
+     *
+     * @param mixed $default The value to use instead
+     * @return mixed The default
+     *
+     * @example
+     * $limit = \Sift::query($json)->lookup('/settings/limit')->orElse(100);
+     */
+    public function orElse(mixed $default): mixed
+    {
+    }
+
+    /**
+     * Look up a pointer below the absent path. That is absent too, so
+     * chained lookups never throw for missing keys.
+     *
+     * @param string $pointer JSON pointer path relative to this one
+     * @return MissingValue A MissingValue for the longer path
+     * @throws \Sift\Exception If the pointer format is invalid
+     *
+     * @example
+     * $zip = $q->lookup('/user')->lookup('/address/zip')->orElse(null);
+     */
+    public function lookup(string $pointer): MissingValue
+    {
+    }
+
+    /**
+     * The pointer that was looked up, from the root of the document.
+     *
+     * @return string RFC 6901 JSON pointer, e.g., "/user/phone"
+     */
+    public function path(): string
+    {
+    }
+}
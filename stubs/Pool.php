@@ -0,0 +1,110 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * Pool - many small JSON documents in one buffer
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Returned by Sift::pool(). Documents are validated and appended to a
+ * single Rust-side buffer, so thousands of small payloads per request cost
+ * one growing allocation instead of one each; clear() keeps the buffer for
+ * the next batch. Documents stay raw until read: only the values get()
+ * returns become PHP values. Ids are 0, 1, 2, ... in the order documents
+ * were added, until the first clear(); ids issued before a clear() throw
+ * StaleHandleException rather than reading a later document.
+ *
+ * Security limits:
+ * - Maximum total size of all documents: 64 MB
+ * - Maximum nesting depth: 512 (checked when values are converted)
+ * - Maximum pointer segments: 256
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ */
+class Pool
+{
+    /**
+     * Validate a JSON document and add it to the pool.
+     *
+     * @param string $json JSON document
+     * @return int The document's id
+     * @throws \Sift\InputTooLargeException If the pool would exceed the size limit
+     * @throws \Sift\Exception If the JSON is invalid
+     *
+     * @example
+     * $id = $pool->addDocument($request->getContent());
+     */
+    public function addDocument(string $json): int
+    {
+    }
+
+    /**
+     * Extract a value from a document by RFC 6901 JSON pointer.
+     *
+     * @param int $id Document id from addDocument()
+     * @param string $pointer JSON pointer ("" for the whole document)
+     * @return mixed The value at the path
+     * @throws \Sift\StaleHandleException If the id was issued before the last clear()
+     * @throws \Sift\Exception If the id is unknown or the path doesn't exist
+     *
+     * @example
+     * $type = $pool->get($id, '/event/type');
+     */
+    public function get(int $id, string $pointer): mixed
+    {
+    }
+
+    /**
+     * Check whether a document has a value at a JSON pointer.
+     *
+     * @param int $id Document id from addDocument()
+     * @param string $pointer JSON pointer
+     * @throws \Sift\StaleHandleException If the id was issued before the last clear()
+     * @throws \Sift\Exception If the id is unknown or the pointer is invalid
+     */
+    public function has(int $id, string $pointer): bool
+    {
+    }
+
+    /**
+     * Extract the same JSON pointer from every document.
+     *
+     * @param string $pointer JSON pointer
+     * @return array<int, mixed> Values keyed by document id; documents
+     *                           without the path are left out
+     * @throws \Sift\Exception If the pointer is invalid
+     *
+     * @example
+     * $types = $pool->getAll('/event/type'); // [0 => 'push', 2 => 'issue']
+     */
+    public function getAll(string $pointer): array
+    {
+    }
+
+    /**
+     * Number of documents in the pool.
+     */
+    public function count(): int
+    {
+    }
+
+    /**
+     * Bytes of JSON held by the pool.
+     */
+    public function size(): int
+    {
+    }
+
+    /**
+     * Remove every document, keeping the buffer's memory for reuse. Ids
+     * issued so far become stale; new ids differ from every earlier one.
+     */
+    public function clear(): void
+    {
+    }
+}
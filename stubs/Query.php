@@ -15,21 +15,23 @@ namespace Sift;
  * minimizing memory allocations and PHP/Rust boundary crossings.
  *
  * Security limits:
- * - Maximum input size: 64 MB
+ * - Maximum input size: 64 MB (checked on the first read; throws
+ *   \Sift\InputTooLargeException)
  * - Maximum path segments: 256
  * - Array indices must be non-negative
  *
  * @package Sift
  * @link https://github.com/dmytrokucher/sift
+ * @implements \ArrayAccess<int|string, Query>
  */
-class Query
+class Query implements \ArrayAccess
 {
     /**
      * Navigate using RFC 6901 JSON pointer.
      *
      * @param string $pointer JSON pointer path, e.g., "/users/0/email"
      * @return Query Returns new Query for method chaining
-     * @throws \Exception If pointer format is invalid
+     * @throws \Sift\Exception If pointer format is invalid
      *
      * @example
      * $email = \Sift::query($json)->pointer('/users/0/email')->string();
@@ -38,12 +40,79 @@ class Query
     {
     }
 
+    /**
+     * Navigate using a JSON pointer, or null if the path doesn't exist.
+     *
+     * Unlike pointer(), the path is resolved right away (and the result
+     * kept for the returned Query's reads), so a missing key or index, or a
+     * scalar in the way, gives null for PHP 8 nullsafe chains instead of a
+     * Query that throws when read. A path holding JSON null still returns
+     * a Query.
+     *
+     * @param string $pointer JSON pointer path, e.g., "/users/0/email"
+     * @return Query|null The Query at the path, or null if it is missing
+     * @throws \Sift\Exception If the pointer format is invalid
+     *
+     * @example
+     * $city = \Sift::query($json)->tryPointer('/user/address/city')?->string() ?? 'unknown';
+     */
+    public function tryPointer(string $pointer): ?Query
+    {
+    }
+
+    /**
+     * Navigate using a JSON pointer, or get a MissingValue if the path
+     * doesn't exist.
+     *
+     * Resolves right away like tryPointer(), but an absent path gives a
+     * \Sift\MissingValue null object instead of null. Both answer
+     * isMissing() and orElse(), so "key absent" and "value is null" stay
+     * distinct without exceptions: a path holding JSON null returns a
+     * Query whose orElse() gives null.
+     *
+     * @param string $pointer JSON pointer path, e.g., "/users/0/email"
+     * @return Query|MissingValue The Query at the path, or a MissingValue if it is missing
+     * @throws \Sift\Exception If the pointer format is invalid
+     *
+     * @example
+     * $phone = \Sift::query($json)->lookup('/user/phone');
+     * if ($phone->isMissing()) { ... }
+     * $city = \Sift::query($json)->lookup('/user/address/city')->orElse('unknown');
+     */
+    public function lookup(string $pointer): Query|MissingValue
+    {
+    }
+
+    /**
+     * Always false; MissingValue::isMissing() is always true.
+     *
+     * @return bool False
+     */
+    public function isMissing(): bool
+    {
+    }
+
+    /**
+     * Hydrate the value like value(), or return the default if the path
+     * doesn't exist. A JSON null is a value, so it is returned as null.
+     *
+     * @param mixed $default Returned when the path is missing
+     * @return mixed The hydrated PHP value, or the default
+     * @throws \Sift\Exception If the value cannot be hydrated
+     *
+     * @example
+     * $limit = \Sift::query($json)->pointer('/settings/limit')->orElse(100);
+     */
+    public function orElse(mixed $default): mixed
+    {
+    }
+
     /**
      * Navigate into object by key.
      *
      * @param string $key The object key to navigate to
      * @return Query Returns new Query for method chaining
-     * @throws \Exception If path segment limit exceeded
+     * @throws \Sift\Exception If path segment limit exceeded
      *
      * @example
      * $email = \Sift::query($json)->get('users')?->index(0)?->get('email')?->string();
@@ -57,7 +126,7 @@ class Query
      *
      * @param int $index The array index (must be non-negative)
      * @return Query Returns new Query for method chaining
-     * @throws \Exception If index is negative or path segment limit exceeded
+     * @throws \Sift\Exception If index is negative or path segment limit exceeded
      *
      * @example
      * $firstUser = \Sift::query($json)->get('users')?->index(0)?->value();
@@ -66,11 +135,214 @@ class Query
     {
     }
 
+    /**
+     * Navigate into JSON that is embedded as a string value.
+     *
+     * Many APIs double-encode payloads ("payload": "{\"a\":1}"). This resolves
+     * the current path, unescapes the string in Rust and returns a new Query
+     * rooted in the embedded document, so navigation stays lazy and chainable.
+     * The embedded JSON is parsed on hydration; pointers in later error
+     * messages are relative to it.
+     *
+     * @return Query Returns new Query rooted in the embedded document
+     * @throws \Sift\Exception If path not found or value is not a string
+     *
+     * @example
+     * $id = \Sift::query($webhook)->get('payload')->jsonString()->get('id')->int();
+     */
+    public function jsonString(): Query
+    {
+    }
+
+    /**
+     * Follow internal $ref pointers while navigating.
+     *
+     * OpenAPI and JSON Schema documents reuse definitions through reference
+     * objects such as {"$ref": "#/components/schemas/User"}. With this
+     * enabled, a reference object met before a path step or at the end of
+     * the path is replaced by its target, transitively. Numeric segments
+     * also match object keys, so "/responses/200" works. Only references
+     * within the document (starting with "#") are supported.
+     *
+     * @param bool|null $enabled Pass false to stop following references (default: true)
+     * @return Query Returns new Query with the same path
+     * @throws \Sift\Exception On hydration, if a reference is circular, external or dangling
+     *
+     * @example
+     * $spec = \Sift::query($openapi)->followRefs();
+     * $props = $spec->pointer('/paths/~1users/get/responses/200/content/application~1json/schema/properties')->value();
+     */
+    public function followRefs(?bool $enabled = null): Query
+    {
+    }
+
+    /**
+     * Let property and index reads navigate: $q->users[3]->email is
+     * $q->get('users')->index(3)->get('email').
+     *
+     * Off by default, so a misspelled method in a chain is not silently
+     * taken for a key; until enabled, property and index reads throw and
+     * isset() is false. The setting carries over to every Query derived
+     * from this one. Reads are lazy like get(): a missing key throws on
+     * hydration. Properties named like hydration methods (value, string,
+     * int, float, bool, raw) throw; use get() for keys with those names.
+     * Queries are read-only, so writes and unset() throw.
+     *
+     * @param bool|null $enabled Pass false to turn property access off (default: true)
+     * @return Query Returns new Query with the same path
+     *
+     * @example
+     * $q = \Sift::query($json)->propertyAccess();
+     * $email = $q->users[3]->email->string();
+     * $city = isset($q->user->address) ? $q->user->address->city->string() : null;
+     */
+    public function propertyAccess(?bool $enabled = null): Query
+    {
+    }
+
+    /**
+     * Release the JSON text after the first successful hydration.
+     *
+     * A weak Query, and every Query derived from it, calls release() on
+     * itself once value(), string(), int(), float(), bool() or raw()
+     * succeeds, so one-shot reads in long-running workers don't leave
+     * lingering Queries holding whole documents.
+     *
+     * @param bool|null $enabled Pass false to turn weak mode off (default: true)
+     * @return Query Returns new Query with the same path
+     *
+     * @example
+     * $q = \Sift::query($message)->weak();
+     * $id = $q->get('id')->int(); // that child Query is released now
+     */
+    public function weak(?bool $enabled = null): Query
+    {
+    }
+
+    /**
+     * Drop this Query's reference to its JSON text, defaults and cached
+     * containers. The memory is freed once no other Query shares it;
+     * Queries derived before the call keep their own references. Every
+     * later read throws StaleHandleException. Releasing twice is a no-op.
+     *
+     * @example
+     * $order = $q->get('order')->value();
+     * $q->release();
+     */
+    public function release(): void
+    {
+    }
+
+    /**
+     * Alias of release().
+     */
+    public function close(): void
+    {
+    }
+
+    /**
+     * Whether release(), or a hydration in weak mode, dropped the buffer.
+     *
+     * @return bool True if reads will throw StaleHandleException
+     */
+    public function isReleased(): bool
+    {
+    }
+
+    /**
+     * Navigate into an object key; see propertyAccess().
+     *
+     * @param string $name The object key
+     * @return Query
+     * @throws \Sift\Exception If property access is off, or the name is a hydration method
+     */
+    public function __get(string $name): Query
+    {
+    }
+
+    /**
+     * Whether the key exists and isn't null; see propertyAccess().
+     *
+     * @param string $name The object key
+     * @return bool
+     */
+    public function __isset(string $name): bool
+    {
+    }
+
+    /**
+     * @throws \Sift\Exception Always: Queries are read-only
+     */
+    public function __set(string $name, mixed $value): void
+    {
+    }
+
+    /**
+     * Whether the element or key exists and isn't null; see propertyAccess().
+     *
+     * @param int|string $offset Array index or object key
+     * @return bool
+     */
+    public function offsetExists(mixed $offset): bool
+    {
+    }
+
+    /**
+     * Navigate into an array index or object key; see propertyAccess().
+     *
+     * @param int|string $offset Array index or object key
+     * @return Query
+     * @throws \Sift\Exception If property access is off, or the offset is invalid
+     */
+    public function offsetGet(mixed $offset): Query
+    {
+    }
+
+    /**
+     * @throws \Sift\Exception Always: Queries are read-only
+     */
+    public function offsetSet(mixed $offset, mixed $value): void
+    {
+    }
+
+    /**
+     * @throws \Sift\Exception Always: Queries are read-only
+     */
+    public function offsetUnset(mixed $offset): void
+    {
+    }
+
+    /**
+     * Fall back to a default document where this one has no value.
+     *
+     * A path missing from this Query's document is resolved in $json
+     * instead, then in any defaults added after it, so settings overlays
+     * can be read without decoding and merging the layers. The layer is
+     * chosen per resolved path: a value comes whole from the first document
+     * that has it, and objects are not merged. A null value counts as
+     * present. Each call adds a default below the ones already added.
+     *
+     * @param string $json Default JSON document
+     * @return Query Returns new Query with the same path and the extra default
+     * @throws \Sift\InputTooLargeException If the default exceeds the size limit
+     *
+     * @example
+     * $settings = \Sift::query($user)->withDefaultsFrom($team)->withDefaultsFrom($global);
+     * $theme = $settings->pointer('/ui/theme')->string();
+     */
+    public function withDefaultsFrom(string $json): Query
+    {
+    }
+
     /**
      * Extract value as string.
      *
+     * Control characters are kept, stripped or rejected as the 'control_chars'
+     * option given to Sift::query() says.
+     *
      * @return string The extracted string value
-     * @throws \Exception If path not found or value is not a string
+     * @throws \Sift\Exception If path not found, value is not a string, or it holds
+     *     control characters under 'control_chars' => 'error'
      *
      * @example
      * $email = \Sift::query($json)->get('email')?->string();
@@ -83,7 +355,7 @@ class Query
      * Extract value as integer.
      *
      * @return int The extracted integer value
-     * @throws \Exception If path not found or value is not an integer
+     * @throws \Sift\Exception If path not found or value is not an integer
      *
      * @example
      * $id = \Sift::query($json)->get('id')?->int();
@@ -96,7 +368,7 @@ class Query
      * Extract value as float.
      *
      * @return float The extracted float value
-     * @throws \Exception If path not found or value is not a float
+     * @throws \Sift\Exception If path not found or value is not a float
      *
      * @example
      * $price = \Sift::query($json)->get('price')?->float();
@@ -109,7 +381,7 @@ class Query
      * Extract value as boolean.
      *
      * @return bool The extracted boolean value
-     * @throws \Exception If path not found or value is not a boolean
+     * @throws \Sift\Exception If path not found or value is not a boolean
      *
      * @example
      * $active = \Sift::query($json)->get('active')?->bool();
@@ -118,20 +390,353 @@ class Query
     {
     }
 
+    /**
+     * Extract value as integer, refusing anything but an integer literal.
+     *
+     * Like int(), but a guaranteed contract for financial and auth code:
+     * floats with an integral value (1.0, 1e3), negative zero, numeric
+     * strings, booleans and null all throw. The exception carries the
+     * pointer, the expected and actual types, and says why the value was
+     * refused; it never echoes the value itself.
+     *
+     * @return int The extracted integer value
+     * @throws \Sift\Exception If path not found or value is not an integer literal in PHP's int range
+     *
+     * @example
+     * $cents = \Sift::query($payment)->get('amount_cents')->intStrict();
+     */
+    public function intStrict(): int
+    {
+    }
+
+    /**
+     * Extract value as boolean, refusing anything but true and false.
+     *
+     * The strict counterpart of boolish(): 1, "true", "yes" and null all
+     * throw, with the pointer, the expected and actual types, and the reason
+     * in the exception.
+     *
+     * @return bool The extracted boolean value
+     * @throws \Sift\Exception If path not found or value is not a JSON boolean
+     *
+     * @example
+     * $isAdmin = \Sift::query($claims)->get('admin')->boolStrict();
+     */
+    public function boolStrict(): bool
+    {
+    }
+
+    /**
+     * Extract value as boolean, tolerating loose encodings.
+     *
+     * JSON true/false map to themselves. Strings (trimmed, case-insensitive)
+     * and numbers are looked up by their text:
+     * - truthy by default: "true", "1", "yes", "y", "on" (and the number 1)
+     * - falsy by default: "false", "0", "no", "n", "off" (and the number 0)
+     *
+     * Passing a list replaces the default for that side. Anything in neither
+     * list - including null, "", arrays and objects - is ambiguous and throws.
+     *
+     * @param array<string|int>|null $truthy Values read as true
+     * @param array<string|int>|null $falsy Values read as false
+     * @return bool The normalized boolean
+     * @throws \Sift\Exception If path not found, the value is ambiguous, or a
+     *                    value appears in both lists
+     *
+     * @example
+     * $active = \Sift::query('{"active": "Yes"}')->get('active')->boolish(); // true
+     * $enabled = $q->boolish(['enabled'], ['disabled']);
+     */
+    public function boolish(?array $truthy = null, ?array $falsy = null): bool
+    {
+    }
+
+    /**
+     * Extract a base64-encoded string as the decoded binary string.
+     *
+     * Decoded in Rust directly from the JSON input, so large blobs aren't
+     * copied into a PHP string first and decoded again. Padding is optional;
+     * when present it must complete the final group. Whitespace and
+     * characters outside the chosen alphabet are rejected.
+     *
+     * @param string|null $alphabet 'standard' (+/, default) or 'url' (-_)
+     * @return string The decoded bytes
+     * @throws \Sift\FormatException If the string is not valid base64 (the message names the pointer)
+     * @throws \Sift\Exception If path not found, value is not a string, or the alphabet is unknown
+     *
+     * @example
+     * $png = \Sift::query($payload)->get('attachment')->base64();
+     * $sig = \Sift::query($payload)->get('signature')->base64('url');
+     */
+    public function base64(?string $alphabet = null): string
+    {
+    }
+
+    /**
+     * Extract value as a UUID string.
+     *
+     * Accepts the canonical 8-4-4-4-12 hex form in either case; the string
+     * is returned unchanged.
+     *
+     * @return string The UUID
+     * @throws \Sift\FormatException If the string is not a UUID (the message names the pointer)
+     * @throws \Sift\Exception If path not found or value is not a string
+     *
+     * @example
+     * $id = \Sift::query($json)->get('id')->uuid();
+     */
+    public function uuid(): string
+    {
+    }
+
+    /**
+     * Extract value as an email address.
+     *
+     * Requires a dot-atom local part (max 64 bytes) and a dotted domain
+     * name; quoted local parts and IP literals are rejected.
+     *
+     * @return string The email address
+     * @throws \Sift\FormatException If the string is not an email address
+     * @throws \Sift\Exception If path not found or value is not a string
+     *
+     * @example
+     * $email = \Sift::query($json)->pointer('/users/0/email')->email();
+     */
+    public function email(): string
+    {
+    }
+
+    /**
+     * Extract value as an absolute URL.
+     *
+     * Requires a scheme, "://" and a host (name, IPv4 or [IPv6]), with an
+     * optional port, path, query and fragment. Whitespace is rejected.
+     *
+     * @return string The URL
+     * @throws \Sift\FormatException If the string is not an absolute URL
+     * @throws \Sift\Exception If path not found or value is not a string
+     *
+     * @example
+     * $homepage = \Sift::query($json)->get('homepage')->url();
+     */
+    public function url(): string
+    {
+    }
+
+    /**
+     * Extract value as an IP address.
+     *
+     * @param int|null $version 4 or 6 to accept only that version, null for either
+     * @return string The IP address
+     * @throws \Sift\FormatException If the string is not an IP address of the requested version
+     * @throws \Sift\Exception If path not found, value is not a string, or version is not 4 or 6
+     *
+     * @example
+     * $ip = \Sift::query($json)->get('client_ip')->ip(4);
+     */
+    public function ip(?int $version = null): string
+    {
+    }
+
     /**
      * Full hydration to PHP array/value.
      *
      * Converts the current JSON value into a PHP native type (array, string, int, float, bool, or null).
-     * This performs full parsing of the subtree at the current position.
+     * The subtree at the current position is converted in the same pass that resolved it,
+     * without re-parsing. Uses the options given to Sift::query() (assoc, depth,
+     * bigint, ...); nesting depth is limited to 512 levels unless 'depth' says otherwise.
+     *
+     * With $maxDepth, only that many levels of arrays and objects are converted, so the
+     * top of a huge document can be rendered without hydrating all of it. Arrays and
+     * objects below the limit become \Sift\Query objects over their JSON (pointers in
+     * their error messages are relative to them), to be expanded on demand, or their raw
+     * JSON text with $leaves 'raw'. Scalars are always converted.
      *
-     * @return mixed The fully hydrated PHP value
-     * @throws \Exception If path not found or value cannot be hydrated
+     * @param int|null $maxDepth Levels of arrays and objects to convert (at least 1), or null for all
+     * @param string|null $leaves What deeper arrays and objects become: 'query' (default) or 'raw'
+     * @return mixed The hydrated PHP value
+     * @throws \Sift\Exception If path not found, value cannot be hydrated, or the arguments are invalid
      *
      * @example
      * $user = \Sift::query($json)->get('users')?->index(0)?->value();
      * // Returns: ["id" => 1, "email" => "alice@example.com", ...]
+     *
+     * $top = \Sift::query($json)->value(2);
+     * // ["users" => [0 => Sift\Query, 1 => Sift\Query, ...], "total" => 2]
+     * $first = $top['users'][0]->value();
+     */
+    public function value(?int $maxDepth = null, ?string $leaves = null): mixed
+    {
+    }
+
+    /**
+     * Hydrate the array at this path in chunks of up to $size elements.
+     *
+     * Chunks are read one after another from a single scan of the array,
+     * so peak memory for PHP values is proportional to $size rather than
+     * to the array's length. Elements use the options given to Sift::query().
+     *
+     * To make an import resumable, store Chunks::tell() after each chunk is
+     * processed and pass it back as $offset: iteration then starts right
+     * after those chunks, without hydrating the elements before them.
+     *
+     * @param int $size Maximum elements per chunk (at least 1)
+     * @param int|null $offset A byte offset returned by Chunks::tell() for
+     *                         the same document and path
+     * @return Chunks Iterator yielding lists of hydrated elements
+     * @throws \Sift\Exception If $size is less than 1 or $offset negative;
+     *                          when iteration starts, if the path is not found
+     *                          or is not an array, or $offset is outside it
+     *
+     * @example
+     * foreach (\Sift::query($export)->get('rows')->chunks(1000) as $rows) {
+     *     $db->insertBatch($rows);
+     * }
+     *
+     * // Resumable: checkpoint after every committed batch
+     * $chunks = \Sift::query($export)->get('rows')->chunks(1000, $checkpoint->load());
+     * foreach ($chunks as $rows) {
+     *     $db->insertBatch($rows);
+     *     $checkpoint->save($chunks->tell());
+     * }
+     */
+    public function chunks(int $size, ?int $offset = null): Chunks
+    {
+    }
+
+    /**
+     * Validate the value at this path against a JSON Schema.
+     *
+     * Only this subtree is parsed, so one entity inside a large envelope is
+     * validated without extracting it as a string first. Violation pointers
+     * are relative to this path ('' is the value itself). Supported: type,
+     * enum, const, minimum/maximum (and exclusive), multipleOf,
+     * minLength/maxLength, required, properties, additionalProperties,
+     * min/maxProperties, prefixItems/items, min/maxItems, uniqueItems,
+     * allOf/anyOf/oneOf/not and internal $ref. 'format' is not checked;
+     * schemas using 'pattern' or 'patternProperties' are rejected.
+     *
+     * @param string $schema JSON Schema document
+     * @return list<array{pointer: string, keyword: string, message: string}> Empty if the value matches
+     * @throws \Sift\Exception If the path is not found, or the schema is
+     *                          invalid or uses an unsupported keyword
+     *
+     * @example
+     * $violations = \Sift::query($envelope)->get('order')->matchesSchema($orderSchema);
+     * // [['pointer' => '/items/0/qty', 'keyword' => 'minimum', 'message' => 'Value must be at least 1']]
+     */
+    public function matchesSchema(string $schema): array
+    {
+    }
+
+    /**
+     * Extract the coordinates of a GeoJSON geometry as packed doubles.
+     *
+     * Accepts a geometry or a Feature (whose geometry is used). 'data' holds every
+     * position's coordinates as doubles in machine byte order (unpack('d*', ...)),
+     * 'dims' is the number of coordinates per position (2, or 3 with altitude), and
+     * 'counts' gives the structure between the positions and 'coordinates', from the
+     * outermost level in:
+     * - Point, MultiPoint, LineString: []
+     * - Polygon: [[positions per ring]]; MultiLineString: [[positions per line]]
+     * - MultiPolygon: [[rings per polygon], [positions per ring]]
+     *
+     * @return array{type: string, dims: int, data: string, counts: list<list<int>>}|null
+     *     Null for a Feature whose geometry is null
+     * @throws \Sift\Exception If the path is not found, is not a geometry or Feature
+     *                          (collections are rejected), or positions are
+     *                          malformed or mix dimensions
+     *
+     * @example
+     * $g = \Sift::query($json)->get('features')->index(0)->geometry();
+     * // ['type' => 'Polygon', 'dims' => 2, 'data' => '...', 'counts' => [[5]]]
      */
-    public function value(): mixed
+    public function geometry(): ?array
+    {
+    }
+
+    /**
+     * Pack a 2D numeric array into a flat buffer of doubles.
+     *
+     * Converts e.g. a list of embedding vectors without creating a PHP value per
+     * element. 'data' holds rows*cols doubles, row-major, in machine byte order
+     * (the layout of pack('d*', ...)), ready for FFI or unpack('d*', ...).
+     * Integers are converted to float; every row must have the same length.
+     *
+     * @return array{data: string, rows: int, cols: int}
+     * @throws \Sift\Exception If the path is not found, is not an array, a row is
+     *                          not an array, rows differ in length, or an
+     *                          element is not a number
+     *
+     * @example
+     * $m = \Sift::query($json)->get('embeddings')->floatMatrix();
+     * $buf = \FFI::new("double[{$m['rows']}][{$m['cols']}]");
+     * \FFI::memcpy($buf, $m['data'], strlen($m['data']));
+     */
+    public function floatMatrix(): array
+    {
+    }
+
+    /**
+     * Hydrate the given keys of an array of objects as parallel lists.
+     *
+     * Builds ["id" => [...], "price" => [...]] in one pass over the array,
+     * which is far cheaper than hydrating every row and transposing in PHP:
+     * members that weren't asked for are never converted. Rows missing a key
+     * contribute null, so all lists have one entry per row.
+     *
+     * @param list<string> $keys Keys to extract (duplicates are ignored)
+     * @return array<string, list<mixed>> One list per key, in the order given
+     * @throws \Sift\Exception If the path is not found, is not an array, or
+     *                          an element is not an object
+     *
+     * @example
+     * $cols = \Sift::query($json)->get('orders')->columns(['id', 'price']);
+     * // ['id' => [1, 2], 'price' => [9.5, null]]
+     */
+    public function columns(array $keys): array
+    {
+    }
+
+    /**
+     * Hydrate $n elements sampled uniformly from the array at this path.
+     *
+     * The array is read in one streaming pass (reservoir sampling) and only
+     * the sampled elements are converted, so spot-checking a multi-GB export
+     * costs memory proportional to $n. Elements are returned in document order;
+     * an array shorter than $n is returned whole.
+     *
+     * @param int $n Number of elements to sample (at least 1)
+     * @param int|null $seed Seed for a reproducible sample; random when null
+     * @return list<mixed> The sampled elements
+     * @throws \Sift\Exception If $n is less than 1, the path is not found or
+     *                          is not an array
+     *
+     * @example
+     * $rows = \Sift::query($export)->get('rows')->sample(100, seed: 42);
+     */
+    public function sample(int $n, ?int $seed = null): array
+    {
+    }
+
+    /**
+     * Hydrate the array of arrays at this path as one flat list.
+     *
+     * The elements of each inner array are concatenated in order, so pages
+     * of rows become a single list of rows. By default one level is
+     * flattened; with $recursive, arrays nested at any depth are. Elements
+     * that aren't arrays (including objects) are kept as they are. The
+     * inner arrays are never converted themselves, only their elements.
+     *
+     * @param bool|null $recursive Flatten nested arrays at every depth
+     * @return list<mixed> The flattened elements
+     * @throws \Sift\Exception If the path is not found or is not an array
+     *
+     * @example
+     * // {"pages": [[{"id": 1}, {"id": 2}], [{"id": 3}]]}
+     * $rows = \Sift::query($json)->get('pages')->flattenArrays();
+     */
+    public function flattenArrays(?bool $recursive = null): array
     {
     }
 
@@ -142,7 +747,7 @@ class Query
      * Useful for extracting JSON subtrees to pass to other systems.
      *
      * @return string The raw JSON substring
-     * @throws \Exception If path not found
+     * @throws \Sift\Exception If path not found
      *
      * @example
      * $rawUsers = \Sift::query($json)->get('users')?->raw();
@@ -152,11 +757,61 @@ class Query
     {
     }
 
+    /**
+     * Get the raw JSON at this path, spilling large values to a temp file.
+     *
+     * Text of at most $threshold bytes is returned in 'raw'. Anything
+     * larger is written to a new file in $directory (default: the system
+     * temp directory), readable by its owner only, and its path is returned
+     * in 'path', so proxies can stream a huge subdocument without holding
+     * it as a PHP string. The caller must delete the file.
+     *
+     * @param int $threshold Largest size in bytes returned as a string
+     * @param string|null $directory Where to create the file
+     * @return array{size: int, raw: ?string, path: ?string}
+     * @throws \Sift\Exception If path not found, the threshold is negative, or the file can't be written
+     *
+     * @example
+     * $raw = \Sift::query($upstream)->get('export')->rawSpill(1 << 20);
+     * if ($raw['path'] !== null) {
+     *     readfile($raw['path']);
+     *     unlink($raw['path']);
+     * } else {
+     *     echo $raw['raw'];
+     * }
+     */
+    public function rawSpill(int $threshold, ?string $directory = null): array
+    {
+    }
+
+    /**
+     * Length of the value, without hydrating it.
+     *
+     * Strings give their byte length (strlen() of the decoded string), or
+     * their number of code points (mb_strlen()) with $codepoints. Arrays give
+     * their element count and objects their member count; both are counted by
+     * skipping over the contents, so checking whether a value is too big to
+     * hydrate doesn't hydrate it.
+     *
+     * @param bool|null $codepoints Count a string's code points instead of bytes
+     * @return int The length
+     * @throws \Sift\Exception If path not found or the value is a number, bool or null
+     *
+     * @example
+     * if (\Sift::query($json)->get('items')->len() > 10000) {
+     *     throw new RuntimeException('Too many items');
+     * }
+     * $chars = \Sift::query($json)->get('name')->len(true);
+     */
+    public function len(?bool $codepoints = null): int
+    {
+    }
+
     /**
      * Check if current value is null.
      *
      * @return bool True if value is null, false otherwise
-     * @throws \Exception If path not found
+     * @throws \Sift\Exception If path not found
      *
      * @example
      * if (\Sift::query($json)->get('optional')?->isNull()) {
@@ -171,7 +826,7 @@ class Query
      * Check if current value is an array.
      *
      * @return bool True if value is an array, false otherwise
-     * @throws \Exception If path not found
+     * @throws \Sift\Exception If path not found
      *
      * @example
      * if (\Sift::query($json)->get('items')?->isArray()) {
@@ -186,7 +841,7 @@ class Query
      * Check if current value is an object.
      *
      * @return bool True if value is an object, false otherwise
-     * @throws \Exception If path not found
+     * @throws \Sift\Exception If path not found
      *
      * @example
      * if (\Sift::query($json)->get('user')?->isObject()) {
@@ -201,7 +856,7 @@ class Query
      * Get the type of the current value as a string.
      *
      * @return string One of: "null", "boolean", "integer", "float", "string", "array", "object"
-     * @throws \Exception If path not found
+     * @throws \Sift\Exception If path not found
      *
      * @example
      * $type = \Sift::query($json)->get('field')?->getType();
@@ -0,0 +1,88 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * RpcRequest - one JSON-RPC 2.0 request envelope
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Returned by JsonRpc::parseRequest() and JsonRpc::parseBatch(). A valid
+ * request has a method, optional params and, unless it is a notification,
+ * an id. A rejected envelope has no method or params; error() and
+ * errorResponse() describe why, and id() is the id to answer with (null if
+ * the envelope had no usable id).
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ * @see https://www.jsonrpc.org/specification#request_object
+ */
+final class RpcRequest
+{
+    /**
+     * Whether the envelope is a valid request.
+     */
+    public function isValid(): bool
+    {
+    }
+
+    /**
+     * Whether this is a notification (a valid request without an id), which
+     * must not be answered. Rejected envelopes are never notifications.
+     */
+    public function isNotification(): bool
+    {
+    }
+
+    /**
+     * The method name, or null for a rejected envelope.
+     */
+    public function method(): ?string
+    {
+    }
+
+    /**
+     * The params as a lazy Query, or null if the request has none.
+     *
+     * Pointers in error messages of the Query are relative to params.
+     *
+     * @example
+     * $ids = $request->params()?->get('ids')->value();
+     */
+    public function params(): ?Query
+    {
+    }
+
+    /**
+     * The request id; null for a notification.
+     *
+     * @return string|int|float|null
+     */
+    public function id(): mixed
+    {
+    }
+
+    /**
+     * The error object for a rejected envelope, or null for a valid request.
+     *
+     * @return array{code: int, message: string, data: string}|null
+     *     code is -32700 (Parse error) or -32600 (Invalid Request); data
+     *     says what was wrong
+     */
+    public function error(): ?array
+    {
+    }
+
+    /**
+     * The complete response to a rejected envelope, ready to encode, or null
+     * for a valid request.
+     *
+     * @return array{jsonrpc: string, error: array, id: string|int|float|null}|null
+     */
+    public function errorResponse(): ?array
+    {
+    }
+}
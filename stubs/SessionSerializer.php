@@ -0,0 +1,70 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * SessionSerializer - the sift_json session format
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * The extension registers a "sift_json" session.serialize_handler that
+ * stores $_SESSION as a JSON object of its variables. This class reads and
+ * writes that format directly, for code that handles stored sessions
+ * outside of the session module (migrations, admin tools, other services).
+ *
+ * Values must be JSON-encodable; objects come back as arrays.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ */
+class SessionSerializer
+{
+    /** The session.serialize_handler name */
+    public const NAME = 'sift_json';
+
+    /**
+     * Encode session variables as the sift_json handler stores them.
+     *
+     * @param array $data Session variables, keyed by name
+     * @return string JSON object text
+     * @throws \Sift\Exception If a value can't be encoded as JSON
+     *
+     * @example
+     * $stored = \Sift\SessionSerializer::encode(['user_id' => 42]);
+     */
+    public static function encode(array $data): string
+    {
+    }
+
+    /**
+     * Decode stored sift_json session data. Empty data is an empty session.
+     *
+     * @param string $data Stored session data
+     * @return array Session variables, keyed by name
+     * @throws \Sift\Exception If the data is not a JSON object or array
+     *
+     * @example
+     * $vars = \Sift\SessionSerializer::decode($redis->get("PHPREDIS_SESSION:$id"));
+     */
+    public static function decode(string $data): array
+    {
+    }
+
+    /**
+     * Whether the sift_json handler was registered with ext/session. It
+     * isn't when PHP lacks ext/session or loads it after this extension.
+     *
+     * @return bool True if session.serialize_handler=sift_json can be used
+     *
+     * @example
+     * if (\Sift\SessionSerializer::isRegistered()) {
+     *     ini_set('session.serialize_handler', \Sift\SessionSerializer::NAME);
+     * }
+     */
+    public static function isRegistered(): bool
+    {
+    }
+}
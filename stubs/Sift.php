@@ -23,8 +23,13 @@ class Sift
      * JSON documents. All operations stay in Rust until a hydration method
      * (string(), int(), value(), etc.) is called, minimizing memory usage.
      *
+     * Accepts the same options as decode(); they apply when the Query, or
+     * any Query derived from it, hydrates values with value().
+     *
      * @param string $json The JSON string to query
+     * @param array|null $options Decode options, e.g. ['assoc' => false]
      * @return \Sift\Query A Query object for chainable navigation
+     * @throws \Sift\Exception If an option is unknown or the input is malformed UTF-8
      *
      * @example
      * $json = '{"users": [{"id": 1, "email": "alice@example.com"}]}';
@@ -49,7 +54,179 @@ class Sift
      * // Get raw JSON without parsing
      * $rawUsers = Sift::query($json)->get('users')?->raw();
      */
-    public static function query(string $json): \Sift\Query
+    public static function query(string $json, ?array $options = null): \Sift\Query
+    {
+    }
+
+    /**
+     * Create a Query from wherever the JSON lives.
+     *
+     * - A string is JSON text, never a path, so passing untrusted input
+     *   can't read local files; use fromFile() for paths.
+     * - An SplFileInfo (or SplFileObject) is read from its path.
+     * - A stream resource is read from its current position to the end.
+     * - An object with read() and eof() methods, such as a PSR-7
+     *   StreamInterface body, is read from its current position until
+     *   eof(), in chunks copied into the extension, so the body is never
+     *   materialized as one PHP string. Rewind it first if it was read.
+     * - An array or other object is encoded as Sift encodes, for
+     *   re-encode workflows.
+     *
+     * Files are read by the extension itself, after the open_basedir
+     * check PHP's file functions make.
+     *
+     * @param string|resource|\SplFileInfo|\Psr\Http\Message\StreamInterface|array|object $source The JSON or where to read it from
+     * @param array|null $options Decode options, as for query()
+     * @return \Sift\Query
+     * @throws \Sift\Exception If the source can't be read or encoded, an option is unknown, or the input is malformed UTF-8
+     * @throws \Sift\InputTooLargeException If the JSON exceeds the input size limit
+     *
+     * @example
+     * $body = Sift::from(fopen('php://input', 'r'));
+     * $config = Sift::from(new SplFileInfo('/etc/app/config.json'));
+     * $payload = Sift::from($request->getBody()); // PSR-7
+     * $copy = Sift::from(['user' => ['name' => 'Ada']])->pointer('/user/name')->string();
+     */
+    public static function from(mixed $source, ?array $options = null): \Sift\Query
+    {
+    }
+
+    /**
+     * Create a Query from a local file.
+     *
+     * The file is read by the extension, after the open_basedir check
+     * PHP's file functions make; stream wrappers aren't supported (pass a
+     * stream to from() instead).
+     *
+     * @param string $path Path of the JSON file
+     * @param array|null $options Decode options, as for query()
+     * @return \Sift\Query
+     * @throws \Sift\Exception If the file can't be opened (including an open_basedir restriction) or read, or an option is unknown
+     * @throws \Sift\InputTooLargeException If the file exceeds the input size limit
+     *
+     * @example
+     * $config = Sift::fromFile('/etc/app/config.json');
+     */
+    public static function fromFile(string $path, ?array $options = null): \Sift\Query
+    {
+    }
+
+    /**
+     * Create a Query from the request body.
+     *
+     * Reads php://input into the extension, so no PHP string holding the
+     * body outlives the call, and inflates it if the request was sent with
+     * Content-Encoding: gzip (or x-gzip). The size limit applies to the
+     * body as received and to the inflated text alike.
+     *
+     * @param int|null $maxBytes Largest body accepted, 1 to 64 MB (default: 64 MB)
+     * @param array|null $options Decode options, as for query()
+     * @return \Sift\Query
+     * @throws \Sift\InputTooLargeException If the body exceeds the limit
+     * @throws \Sift\Exception If the body is empty, the encoding is unsupported or the gzip data is malformed, an option is unknown, or the body is malformed UTF-8
+     *
+     * @example
+     * try {
+     *     $body = Sift::fromRequest(1 << 20);
+     * } catch (\Sift\InputTooLargeException $e) {
+     *     http_response_code(413);
+     *     exit;
+     * }
+     * $email = $body->pointer('/user/email')->string();
+     */
+    public static function fromRequest(?int $maxBytes = null, ?array $options = null): \Sift\Query
+    {
+    }
+
+    /**
+     * Wrap a JSON:API compound document for lazy lookups.
+     *
+     * Resolving included resources in PHP means scanning 'included' for every
+     * relationship; the returned helper indexes 'included' by (type, id) in one
+     * pass on first use and then hydrates only the resources you ask for.
+     *
+     * @param string $json The JSON:API document
+     * @param array|null $options Decode options, as for query()
+     * @return \Sift\JsonApi
+     * @throws \Sift\Exception If an option is unknown or the input is malformed UTF-8
+     *
+     * @example
+     * $doc = Sift::jsonApi($json);
+     * $article = $doc->resource();
+     * $author = $doc->related('author');
+     */
+    public static function jsonApi(string $json, ?array $options = null): \Sift\JsonApi
+    {
+    }
+
+    /**
+     * Decode the claims of a JWT into a lazy Query.
+     *
+     * Splits the token, base64url-decodes the header (which must be a JSON
+     * object) and the payload, and returns a Query over the payload.
+     *
+     * WARNING: the signature is NOT verified. Use this only to peek at claims
+     * such as 'exp' or 'sub' cheaply, and never trust them without validating
+     * the token with a JWT library.
+     *
+     * @param string $token JWS compact serialization (header.payload.signature)
+     * @param array|null $options Decode options, as for query()
+     * @return \Sift\Query Query over the claims
+     * @throws \Sift\FormatException If the token does not have three segments,
+     *     a segment is not valid base64url, or the header is not a JSON object
+     * @throws \Sift\Exception If an option is unknown or the payload is
+     *     malformed UTF-8
+     * @throws \Sift\InputTooLargeException If the token exceeds the input size
+     *     limit, or its claims would take the process past memory_limit
+     *
+     * @example
+     * $claims = Sift::jwtClaims($bearerToken);
+     * if ($claims->get('exp')->int() < time()) {
+     *     return $unauthorized;   // Expired - skip the full validation
+     * }
+     */
+    public static function jwtClaims(string $token, ?array $options = null): \Sift\Query
+    {
+    }
+
+    /**
+     * Parse JSON once into an immutable Rust-side tree.
+     *
+     * Use for documents that are read many times (configuration, feature
+     * flags): pointer reads only convert the subtree they return, and the
+     * snapshot can be persisted across requests in the worker process.
+     *
+     * @param string $json The JSON string to parse
+     * @return \Sift\Snapshot Parsed document
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If JSON is invalid or too deeply nested
+     *
+     * @example
+     * $snap = Sift::snapshot($json);
+     * $name = $snap->get('/app/name');
+     */
+    public static function snapshot(string $json): \Sift\Snapshot
+    {
+    }
+
+    /**
+     * Create an empty pool for many small JSON documents.
+     *
+     * For services that juggle thousands of small payloads per request
+     * (webhook fan-out): documents share one Rust-side buffer and are read
+     * by id and JSON pointer without converting the rest.
+     *
+     * @param array<string, mixed>|null $options Decode options, as for decode();
+     *     they apply to every document added and every value read
+     * @return \Sift\Pool Empty pool
+     * @throws \Sift\Exception If an option is invalid
+     *
+     * @example
+     * $pool = Sift::pool();
+     * $id = $pool->addDocument($body);
+     * $type = $pool->get($id, '/event/type');
+     */
+    public static function pool(?array $options = null): \Sift\Pool
     {
     }
 
@@ -66,15 +243,346 @@ class Sift
      *
      * @param string $json The JSON string to parse
      * @param string $pointer JSON pointer path (RFC 6901), e.g., "/users/0/email"
+     * @param array|null $options Decode options, as for decode()
      * @return mixed The extracted value (string, int, float, bool, array, or null)
-     * @throws \Exception If JSON is invalid, pointer path not found, or limits exceeded
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If JSON is invalid, pointer path not found, or limits exceeded
      *
      * @example
      * $json = '{"users": [{"id": 1, "email": "alice@example.com"}]}';
      * $email = Sift::get($json, '/users/0/email');
      * // Returns: "alice@example.com"
      */
-    public static function get(string $json, string $pointer): mixed
+    public static function get(string $json, string $pointer, ?array $options = null): mixed
+    {
+    }
+
+    /**
+     * List the keys of the object at a JSON pointer without decoding it.
+     *
+     * Returns the object's key names in document order, or for an array its
+     * indices (0 to count - 1). Members are skipped over, never converted, so
+     * this is cheap even when the values are large - useful for detecting a
+     * payload's version before deciding what to extract.
+     *
+     * @param string $json The JSON string to inspect
+     * @param string $pointer JSON pointer (RFC 6901); '' is the whole document
+     * @return array<int, string|int> Key names, or indices for an array
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If JSON is invalid, the path is not found, or the
+     *     value is not an object or array (TYPE_ERROR)
+     *
+     * @example
+     * $keys = Sift::keysAt('{"order": {"id": 1, "billing": {}}}', '/order');
+     * // Returns: ["id", "billing"]
+     */
+    public static function keysAt(string $json, string $pointer): array
+    {
+    }
+
+    /**
+     * Build a structural index of a JSON document for repeated lookups.
+     *
+     * The index records the byte range of every value and, for every
+     * container, its members (objects sorted by key). It is a binary string
+     * of roughly 12-20 bytes per value, meant to be cached next to the JSON
+     * and passed to getWithIndex() in later requests.
+     *
+     * @param string $json The JSON string to index
+     * @return string Binary index
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If the JSON is invalid
+     *
+     * @example
+     * $index = $cache->get($key) ?? Sift::index($json);
+     */
+    public static function index(string $json): string
+    {
+    }
+
+    /**
+     * Extract a value by JSON pointer through an index from index().
+     *
+     * Each pointer segment is an array lookup or a binary search over the
+     * object's keys, O(log n), instead of a scan of the document. Pointers
+     * and options are read as get() reads them. The index must have been
+     * built from this same JSON: its length and the containers on the path
+     * are checked, other bytes are not.
+     *
+     * @param string $json The JSON string the index was built from
+     * @param string $index Index returned by index()
+     * @param string $pointer JSON pointer (RFC 6901); '' is the whole document
+     * @param array<string, mixed>|null $options Decode options, as for get()
+     * @return mixed The value at the pointer
+     * @throws \Sift\FormatException If the index is corrupt or belongs to other JSON
+     * @throws \Sift\Exception If the path is not found
+     *
+     * @example
+     * $email = Sift::getWithIndex($json, $index, '/users/1200/email');
+     */
+    public static function getWithIndex(string $json, string $index, string $pointer, ?array $options = null): mixed
+    {
+    }
+
+    /**
+     * Count the elements of the array, or members of the object, at a JSON
+     * pointer without decoding them.
+     *
+     * Elements are skipped over with the lazy iterators, so no PHP values are
+     * built - use it for pagination or pre-allocation decisions on large
+     * collections.
+     *
+     * @param string $json The JSON string to inspect
+     * @param string $pointer JSON pointer (RFC 6901); '' is the whole document
+     * @return int Array length or object member count
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If JSON is invalid, the path is not found, or the
+     *     value is not an object or array (TYPE_ERROR)
+     *
+     * @example
+     * $total = Sift::countAt($json, '/data/items');
+     */
+    public static function countAt(string $json, string $pointer): int
+    {
+    }
+
+    /**
+     * Compute the bounding box of a GeoJSON document.
+     *
+     * Understands FeatureCollection, Feature, GeometryCollection and all
+     * geometry types. Coordinates are read straight into doubles rather than
+     * hydrated as PHP arrays; altitudes are ignored and Features with a null
+     * geometry are skipped.
+     *
+     * @param string $json GeoJSON text (RFC 7946)
+     * @return array{0: float, 1: float, 2: float, 3: float}|null [minX, minY, maxX, maxY],
+     *     or null if the document has no positions
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If JSON is invalid or not valid GeoJSON
+     *     (unknown type, missing coordinates, non-numeric positions)
+     *
+     * @example
+     * [$minX, $minY, $maxX, $maxY] = Sift::geoBounds($featureCollection);
+     */
+    public static function geoBounds(string $json): ?array
+    {
+    }
+
+    /**
+     * Extract the value of the first pointer that exists in the document.
+     *
+     * Candidates are tried in the order given - the result is the same as
+     * calling get() with each pointer until one succeeds - but all of them
+     * are resolved in a single pass over the document, so misses don't
+     * cost a scan each. Useful for schema-version fallbacks.
+     *
+     * @param string $json The JSON string to parse
+     * @param array<int, string> $pointers JSON pointers (RFC 6901), most preferred first
+     * @return mixed The value at the first pointer that exists
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If JSON is invalid, a pointer is malformed, or none of the pointers exist
+     *
+     * @example
+     * // v2 payloads nest items under "data"; v1 had them at the root
+     * $items = Sift::firstOf($json, ['/data/items', '/items']);
+     */
+    public static function firstOf(string $json, array $pointers): mixed
+    {
+    }
+
+    /**
+     * Verify an HMAC-SHA256 signature, then decode.
+     *
+     * With $signature (64 hex digits), it must sign the whole document.
+     * Without, the document is an envelope
+     * {"payload": <value>, "signature": "<hex>"} and the payload is verified
+     * and returned. The MAC is computed over Sift::canonicalize() of the
+     * signed value, so whitespace and key order don't affect it. The
+     * comparison is constant-time.
+     *
+     * @param string $json JSON string or signed envelope
+     * @param string $key HMAC key
+     * @param string|null $signature Hex signature of the whole document, if stored elsewhere
+     * @param array|null $options Decode options, as for decode()
+     * @return mixed The decoded (payload) value
+     * @throws \Sift\FormatException If the signature is malformed or doesn't match, or the envelope is malformed
+     * @throws \Sift\Exception If the JSON is invalid or too large
+     *
+     * @example
+     * $data = Sift::decodeSigned($cached, $_ENV['CACHE_KEY']);
+     * $data = Sift::decodeSigned($body, $secret, $request->getHeaderLine('X-Signature'));
+     */
+    public static function decodeSigned(string $json, string $key, ?string $signature = null, ?array $options = null): mixed
+    {
+    }
+
+    /**
+     * The canonical form of a JSON document, as signed by decodeSigned().
+     *
+     * No whitespace, object members sorted bytewise by key (repeated keys
+     * keep their order), strings re-escaped minimally, and numbers exactly
+     * as written, so 1.0 and 1 differ.
+     *
+     * @param string $json JSON string
+     * @return string
+     * @throws \Sift\Exception If the JSON is invalid or too large
+     *
+     * @example
+     * $signature = hash_hmac('sha256', Sift::canonicalize($json), $key);
+     */
+    public static function canonicalize(string $json): string
+    {
+    }
+
+    /**
+     * Whether two JSON arrays contain the same elements, in any order.
+     *
+     * Compares multisets in linear time: [1, 2, 2] and [2, 1, 2] are the
+     * same members, [1, 2] and [1, 2, 2] are not. Elements are compared by
+     * their canonical form (see canonicalize()), so object key order and
+     * whitespace don't matter but 1 and 1.0 differ. Only the top-level
+     * array is unordered; nested arrays compare element by element.
+     *
+     * With $key, elements must be objects and are matched by that member
+     * alone, e.g. the same set of ids whatever the rest of each record
+     * holds.
+     *
+     * @param string $left JSON array
+     * @param string $right JSON array
+     * @param string|null $key Member to match elements by
+     * @return bool True if both arrays hold the same members
+     * @throws \Sift\Exception If either input is invalid JSON or not an array, or an element lacks $key
+     *
+     * @example
+     * Sift::sameMembers('[{"id":1},{"id":2}]', '[{"id":2},{"id":1}]'); // true
+     * Sift::sameMembers($expectedUsers, $body, 'id');
+     */
+    public static function sameMembers(string $left, string $right, ?string $key = null): bool
+    {
+    }
+
+    /**
+     * Combine two JSON arrays element by element.
+     *
+     * Strategies:
+     *
+     * - 'pairs' (default): [[a0, b0], [a1, b1], ...]
+     * - 'merge': objects with the top-level members of both elements, the
+     *   right element's value winning for a key both have; key order is
+     *   the left element's, then the right's new keys
+     *
+     * The shorter array is padded with nulls, like array_map() with several
+     * arrays; 'merge' treats null as an empty object. Both arrays are walked
+     * side by side in Rust and elements are copied as JSON text, so two large
+     * exports are never hydrated into PHP arrays.
+     *
+     * @param string $left JSON array
+     * @param string $right JSON array
+     * @param string|null $strategy 'pairs' or 'merge'
+     * @return string The combined JSON array
+     * @throws \Sift\Exception If either input is invalid JSON or not an array,
+     *                          the strategy is unknown, or 'merge' meets an
+     *                          element that is neither an object nor null
+     *
+     * @example
+     * Sift::zip('[1, 2, 3]', '["a", "b"]'); // '[[1,"a"],[2,"b"],[3,null]]'
+     * Sift::zip('[{"id":1,"paid":false}]', '[{"paid":true}]', 'merge'); // '[{"id":1,"paid":true}]'
+     */
+    public static function zip(string $left, string $right, ?string $strategy = null): string
+    {
+    }
+
+    /**
+     * Split a top-level JSON array into JSON arrays under a byte budget.
+     *
+     * Each shard is a valid JSON array of consecutive elements, as many as
+     * fit in $maxBytes including brackets and commas, so the shards can be
+     * sent to an API with a payload size limit. Elements are copied as they
+     * are, without being decoded; joining the shards' elements gives back
+     * the original array.
+     *
+     * @param string $json A JSON document whose top level is an array
+     * @param int $maxBytes Largest shard, in bytes (at least 2)
+     * @return list<string> The shards in order; empty for an empty array
+     * @throws \Sift\Exception If the JSON is invalid or not an array, or an
+     *                          element doesn't fit in a shard on its own (with
+     *                          its pointer)
+     *
+     * @example
+     * Sift::splitTopLevel('[1, 22, 333]', 7); // ['[1,22]', '[333]']
+     */
+    public static function splitTopLevel(string $json, int $maxBytes): array
+    {
+    }
+
+    /**
+     * Join JSON arrays into one array.
+     *
+     * The inverse of splitTopLevel(): the elements of every document are
+     * spliced, in order, between one pair of brackets. Each document is
+     * validated but never decoded, and elements keep their text.
+     *
+     * @param list<string> $jsonDocs JSON documents whose top level is an array
+     * @return string One JSON array with all the elements
+     * @throws \Sift\Exception If a document is invalid JSON or not an array
+     *                          (with its index in $jsonDocs), or the result
+     *                          exceeds the input size limit
+     *
+     * @example
+     * Sift::concatArrays(['[1, 2]', '[]', '[{"a": 3}]']); // '[1, 2,{"a": 3}]'
+     */
+    public static function concatArrays(array $jsonDocs): string
+    {
+    }
+
+    /**
+     * Generate a document shaped like $json with randomized values.
+     *
+     * Keys, key order, array lengths and the type at every path are kept.
+     * Strings keep their length in characters: letters become random
+     * letters of the same case and digits random digits, while punctuation
+     * and whitespace are kept, so emails, dates and UUIDs keep their shape.
+     * Numbers keep their sign, digit counts, decimal point and exponent;
+     * booleans are random and null stays null. Output is compact JSON.
+     *
+     * Use it to build load-test fixtures from one sample, or to anonymize a
+     * production payload for a bug report (keys are not anonymized).
+     *
+     * @param string $json Sample JSON document
+     * @param int|null $seed Seed for reproducible output; random if null
+     * @return string Randomized JSON document
+     * @throws \Sift\Exception If the JSON is invalid or too large
+     *
+     * @example
+     * Sift::randomize('{"email":"Ann@corp.io","age":42}', 7);
+     * // {"email":"Qzx@mwle.ka","age":83}
+     */
+    public static function randomize(string $json, ?int $seed = null): string
+    {
+    }
+
+    /**
+     * Set default decode options for the rest of the request.
+     *
+     * Takes the same keys as decode(). The defaults apply to decode(),
+     * decodeLazy(), get(), firstOf(), Query hydration and the Sonic
+     * equivalents; options passed to a call still override them. Set
+     * policy once in bootstrap instead of at every call site.
+     *
+     * Each call replaces the previous configuration (configure([]) restores
+     * the built-in defaults). The configuration is reset when the request
+     * ends, so it never leaks into the next request of a worker process.
+     *
+     * @param array $options Decode options, e.g. ['big_numbers' => 'bcmath']
+     * @return void
+     * @throws \Sift\Exception If an option is unknown or has an invalid value
+     *
+     * @example
+     * Sift::configure(['big_numbers' => 'bcmath', 'warnings' => true]);
+     * $order = Sift::decode($json);              // uses both defaults
+     * $raw = Sift::decode($json, ['warnings' => false]);
+     */
+    public static function configure(array $options): void
     {
     }
 
@@ -88,33 +596,994 @@ class Sift
      * - Maximum input size: 64 MB
      * - Maximum nesting depth: 512
      *
+     * Options:
+     * - strict (bool): Produce exactly what json_decode($json, true) produces,
+     *   throwing \JsonException with json_decode's error codes.
+     *   See docs/COMPATIBILITY.md for the differences this toggles.
+     * - warnings (bool): Record lossy conversions for Sift::lastWarnings().
+     * - presize (bool): Count each array's elements before filling it so the
+     *   PHP array is allocated once instead of growing. Costs an extra skip
+     *   pass per container; helps on arrays with 100k+ elements. Only used by
+     *   the lazy conversion (decodeLazy(), strict mode, warnings).
+     * - strategy (string): 'auto' (default) decodes documents under 16 MB
+     *   through a DOM, which is faster, and larger ones through lazy
+     *   iteration, which needs less memory; 'dom' or 'lazy' forces one.
+     *   Options only lazy iteration implements (strict mode, warnings,
+     *   big_numbers, key renaming, ...) always use it.
+     * - big_numbers (string): What numbers a float can't hold exactly decode
+     *   to. 'float' (default) rounds like json_decode; 'bcmath' returns
+     *   \BCMath\Number objects (PHP 8.4+) for integers beyond PHP_INT_MAX and
+     *   decimals with more than 17 significant digits; 'gmp' returns \GMP
+     *   objects for such integers and leaves decimals as floats.
+     * - assoc (bool): false decodes objects as \stdClass, like
+     *   json_decode($json, false). Default true.
+     * - bigint (string): 'string' decodes integers outside PHP's int range
+     *   as numeric strings, like JSON_BIGINT_AS_STRING; it takes precedence
+     *   over big_numbers for integers. Default 'float'.
+     * - depth (int): Maximum nesting depth, 1 to 2048. Default 512. Also
+     *   accepted as max_depth.
+     * - utf8 (string): Malformed UTF-8 in the input is an error ('error',
+     *   default), dropped ('ignore') or replaced with U+FFFD ('substitute').
+     * - duplicate_keys (string): Which value a repeated object key keeps:
+     *   'last' (default, like json_decode) or 'first'; 'error' rejects it.
+     * - keys (string|callable): Renames object keys as they are inserted:
+     *   'camel' (first_name => firstName), 'snake' (firstName => first_name),
+     *   or a callable fn (string $key): string. Default 'keep'. Keys renamed
+     *   to the same name count as duplicates. A callable is held until the
+     *   end of the request.
+     * - strip_prefix (string): Removes this prefix (e.g. 'attr_') from object
+     *   keys before any other renaming. Keys without it are left alone.
+     * - nest_keys (string): Splits object keys on this separator into nested
+     *   arrays: with '.', {"user.name": "Ada", "user.id": 1} decodes to
+     *   ['user' => ['name' => 'Ada', 'id' => 1]]. Paths merge into arrays
+     *   already there; a non-array in the way counts as a duplicate key.
+     *   keys renames each segment. Requires assoc.
+     * - control_chars (string): What happens to control characters (U+0000 to
+     *   U+001F except tab, LF and CR, and U+007F) in decoded strings and keys,
+     *   for strings headed to C APIs that stop at NUL: 'keep' (default, like
+     *   json_decode), 'strip' removes them, 'error' rejects the document and
+     *   names the string's JSON pointer.
+     * - surrogates (string): What lone UTF-16 surrogates ("\ud800" without its
+     *   pair) and malformed \u escapes ("\u12G4") turn into: 'error' (default,
+     *   like json_decode's JSON_ERROR_UTF16) rejects the document with the
+     *   offset, 'replace' substitutes U+FFFD, and 'wtf8' keeps lone surrogates
+     *   in string values as their WTF-8 bytes ("\xED\xA0\x80"), which are not
+     *   valid UTF-8. Under 'wtf8', keys and malformed escapes get U+FFFD, and
+     *   functions other than decode(), decodeLazy() and get() treat it as
+     *   'replace'.
+     * - interpolate (string|array): Substitutes ${NAME} and {{ name }}
+     *   placeholders in string values (not keys), for configuration files:
+     *   'env' reads the process environment like getenv(), an array of
+     *   strings supplies the variables. An undefined variable throws with
+     *   the string's JSON pointer; text that isn't a well-formed placeholder
+     *   is kept. An array is held until the end of the request.
+     * - numbers (array): Per-pointer number decoding, e.g.
+     *   ['/amounts/*' => 'string', '/rate' => 4]. Keys are JSON pointers
+     *   relative to the decoded value, where a `*` segment matches any key
+     *   or index; the first matching pattern applies. 'string' keeps the
+     *   literal's exact text ("19.90"), an integer from 0 to 17 rounds
+     *   floats half away from zero to that many decimal places (integer
+     *   literals are unchanged), and 'float' decodes normally, to exempt
+     *   part of a broader pattern. Held until the end of the request.
+     *
+     * Object members decode in document order, whichever conversion is used;
+     * a repeated key stays at its first position. encodeOrdered() restores
+     * that order when encoding a modified value.
+     *
+     * assoc, bigint, depth, duplicate_keys, control_chars, 'surrogates' => 'wtf8', interpolate, numbers and the key options use lazy iteration unless
+     * left at their defaults. See docs/COMPATIBILITY.md for how the options
+     * map to json_decode's arguments and flags.
+     *
+     * Options not given here fall back to the request's Sift::configure()
+     * defaults.
+     *
      * @param string $json The JSON string to decode
+     * @param array|null $options Decode options, e.g. ['strict' => true]
      * @return mixed The decoded value (array, string, int, float, bool, or null)
-     * @throws \Exception If JSON is invalid or limits exceeded
+     * @throws \JsonException In strict mode, if JSON is invalid or too deeply nested
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If JSON is invalid, limits exceeded, or an option is unknown
      *
      * @example
      * $data = Sift::decode('{"name": "sonic", "fast": true}');
      * // Returns: ["name" => "sonic", "fast" => true]
+     *
+     * $data = Sift::decode('{"123": -0}', ['strict' => true]);
+     * // Returns: [123 => 0], same as json_decode($json, true)
+     *
+     * $data = Sift::decode('{"amount": 0.1000000000000000000001}', ['big_numbers' => 'bcmath']);
+     * // Returns: ["amount" => BCMath\Number('0.1000000000000000000001')]
      */
-    public static function decode(string $json): mixed
+    public static function decode(string $json, ?array $options = null): mixed
     {
     }
 
     /**
-     * SIMD-accelerated JSON validation.
+     * Decode JSON from any source from() accepts.
      *
-     * Validates JSON syntax. Returns false for invalid JSON or inputs
-     * exceeding the 64 MB size limit.
+     * Meant for PSR-7 middleware: a StreamInterface body (any object with
+     * read() and eof()) is read in chunks into the extension instead of
+     * through (string) $body or getContents(). Paths, SplFileInfo, stream
+     * resources and JSON text work as in from().
      *
-     * @param string $json The JSON string to validate
-     * @return bool True if valid JSON, false otherwise
+     * @param string|resource|\SplFileInfo|\Psr\Http\Message\StreamInterface|array|object $source The JSON or where to read it from
+     * @param array|null $options Decode options, as for decode()
+     * @return mixed The decoded value
+     * @throws \JsonException In strict mode, if JSON is invalid or too deeply nested
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If the source can't be read, JSON is invalid, or an option is unknown
      *
      * @example
-     * if (Sift::isValid($userInput)) {
-     *     $data = Sift::decode($userInput);
+     * $data = Sift::decodeFrom($request->getBody(), ['strict' => true]);
+     */
+    public static function decodeFrom(mixed $source, ?array $options = null): mixed
+    {
+    }
+
+    /**
+     * Decode a payload in whichever supported format it is in.
+     *
+     * The format is sniffed from the bytes rather than a declared content
+     * type, for ingestion endpoints that accept several:
+     * - gzip (magic 1f 8b) is inflated, then sniffed again ('gzip' => true);
+     *   the inflated size is held to the input size limit
+     * - a MessagePack map or array marker as the first byte is MessagePack
+     *   (scalar MessagePack payloads can't be told apart from text)
+     * - anything else is JSON text; if it fails to decode as one document
+     *   after a complete first line, it is decoded as NDJSON, one document
+     *   per non-blank line
+     *
+     * Options apply to JSON text as for decode(). MessagePack maps become
+     * arrays; extension types are rejected.
+     *
+     * @param string $bytes Payload bytes
+     * @param array<string, mixed>|null $options Decode options, as for decode()
+     * @return array{format: 'json'|'ndjson'|'msgpack', gzip: bool, data: mixed} NDJSON data is a list of documents
+     * @throws \Sift\InputTooLargeException If the input or its inflated form exceeds the size limit
+     * @throws \Sift\Exception If the payload is malformed in its detected format
+     *
+     * @example
+     * $result = Sift::decodeAuto(file_get_contents('php://input'));
+     * $rows = $result['format'] === 'ndjson' ? $result['data'] : [$result['data']];
+     */
+    public static function decodeAuto(string $bytes, ?array $options = null): array
+    {
+    }
+
+    /**
+     * Decode a record stream, tolerating individually malformed records.
+     *
+     * A top-level JSON array is split at its top-level commas, anything
+     * else at newlines (NDJSON), and each element is decoded on its own. An
+     * element that fails to parse or convert is left out ('skip') or kept
+     * as null ('null'), and reported in 'errors', so one corrupt record
+     * doesn't fail a whole import. Problems with the array itself (missing
+     * closing bracket, content after it) are reported with a null index.
+     * Splitting is best effort: an element with unbalanced brackets can
+     * swallow the ones after it.
+     *
+     * @param string $bytes JSON array or NDJSON
+     * @param array<string, mixed>|null $options Decode options, as for decode()
+     * @param string|null $onError 'skip' (default) or 'null'
+     * @return array{data: list<mixed>, errors: list<array{index: int|null, message: string, offset: int}>}
+     *     'index' is the element's position in the input, 'offset' a byte offset into it
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If an option or $onError is invalid
+     *
+     * @example
+     * $result = Sift::decodeRecords($export, onError: 'null');
+     * foreach ($result['errors'] as $e) {
+     *     $log->warning("record {$e['index']} at byte {$e['offset']}: {$e['message']}");
      * }
      */
-    public static function isValid(string $json): bool
+    public static function decodeRecords(string $bytes, ?array $options = null, ?string $onError = null): array
+    {
+    }
+
+    /**
+     * Full decode via lazy iteration.
+     *
+     * Produces the same result as decode(), but always converts straight
+     * from the input instead of building an intermediate DOM first, like
+     * decode($json, ['strategy' => 'lazy']). decode() already picks this
+     * path for large documents; run benchmarks/decode_strategies.php to
+     * compare on your payloads.
+     *
+     * Accepts the same options and enforces the same limits as decode();
+     * the strategy option is ignored.
+     *
+     * @param string $json The JSON string to decode
+     * @param array|null $options Decode options, e.g. ['strict' => true]
+     * @return mixed The decoded value (array, string, int, float, bool, or null)
+     * @throws \JsonException In strict mode, if JSON is invalid or too deeply nested
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If JSON is invalid, limits exceeded, or an option is unknown
+     *
+     * @example
+     * $data = Sift::decodeLazy($largeJson);
+     */
+    public static function decodeLazy(string $json, ?array $options = null): mixed
+    {
+    }
+
+    /**
+     * Replace values by JSON pointer, copying every other byte verbatim.
+     *
+     * Only the replaced values are re-encoded; whitespace, key order, number
+     * spelling and string escapes everywhere else are exactly as in the input,
+     * so a signature over untouched regions stays valid. New values are
+     * encoded like encodeLines() rows. Only existing values can be replaced,
+     * and no changed value may contain another. The rest of the document is
+     * not re-validated.
+     *
+     * @param string $json The JSON document
+     * @param array<string, mixed> $changes JSON pointer => new value
+     * @return string The patched document
+     * @throws \Sift\Exception If a pointer is not found, changes overlap, or a
+     *     new value can't be encoded
+     *
+     * @example
+     * $patched = Sift::patch($json, ['/status' => 'paid', '/items/0/qty' => 3]);
+     */
+    public static function patch(string $json, array $changes): string
+    {
+    }
+
+    /**
+     * Anonymize a document by rules, copying everything else verbatim.
+     *
+     * Rule keys are JSON pointers, where a `*` segment matches any key or
+     * index, or plain member names, which match at any depth. The first
+     * matching rule wins. Actions:
+     *
+     * - 'hash' or ['hash', $salt]: strings and numbers become the hex
+     *   SHA-256 of their text, prefixed with the salt
+     * - ['fake', $words]: strings become a word from the list, chosen by a
+     *   hash of the original, so equal values stay equal (joins survive)
+     * - 'zero': numbers become 0, strings "" and booleans false
+     * - 'null': the value, arrays and objects included, becomes null
+     *
+     * Except for 'null', a rule matching an array or object applies to
+     * every scalar inside it. The document is walked once and only replaced
+     * values are re-encoded; keys are never changed.
+     *
+     * @param string $json The JSON document
+     * @param array<string, string|array> $rules Pointer or member name => action
+     * @return string The anonymized document
+     * @throws \Sift\Exception If the JSON is invalid or a rule is malformed
+     *
+     * @example
+     * $safe = Sift::anonymize($archived, [
+     *     'email'    => ['hash', $salt],
+     *     'name'     => ['fake', ['Alex', 'Sam', 'Kim']],
+     *     '/payroll' => 'zero',
+     *     '/users/*' => ['hash', $salt],
+     *     'address'  => 'null',
+     * ]);
+     */
+    public static function anonymize(string $json, array $rules): string
+    {
+    }
+
+    /**
+     * Encrypt the values at the given pointers, copying everything else verbatim.
+     *
+     * Each value's JSON text is sealed with libsodium's crypto_secretbox
+     * (XSalsa20-Poly1305) under a fresh random nonce and replaced by the
+     * base64 string of nonce followed by box. Any value can be encrypted,
+     * including arrays and objects; decryptFields() restores it byte for byte.
+     *
+     * @param string $json The JSON document
+     * @param string[] $pointers JSON pointers of the values to encrypt
+     * @param string $key A SODIUM_CRYPTO_SECRETBOX_KEYBYTES (32 byte) key
+     * @return string The document with the values encrypted
+     * @throws \Sift\Exception If the JSON is invalid, a pointer is missing,
+     *                          values overlap or the key has the wrong length
+     *
+     * @example
+     * $key = sodium_crypto_secretbox_keygen();
+     * $stored = Sift::encryptFields($json, ['/user/ssn', '/card'], $key);
+     *
+     * // A single field can be opened with ext-sodium too
+     * $raw = base64_decode(json_decode($stored, true)['card']);
+     * $card = sodium_crypto_secretbox_open(substr($raw, 24), substr($raw, 0, 24), $key);
+     */
+    public static function encryptFields(string $json, array $pointers, string $key): string
+    {
+    }
+
+    /**
+     * Decrypt values sealed by encryptFields(), copying everything else verbatim.
+     *
+     * @param string $json The JSON document with encrypted fields
+     * @param string[] $pointers JSON pointers of the encrypted fields
+     * @param string $key The key the fields were encrypted with
+     * @return string The document with the original values restored
+     * @throws \Sift\Exception If a field is not a sealed base64 string, fails
+     *                          authentication (wrong key or tampered) or the
+     *                          JSON is invalid
+     *
+     * @example
+     * $json = Sift::decryptFields($stored, ['/user/ssn', '/card'], $key);
+     */
+    public static function decryptFields(string $json, array $pointers, string $key): string
+    {
+    }
+
+    /**
+     * Turn numbers into strings of their literal text, copying everything else verbatim.
+     *
+     * With pointers, every number at or inside each pointed-to value is
+     * quoted (or, if $threshold is given, those whose magnitude exceeds it).
+     * Without them the whole document is scanned and numbers whose
+     * magnitude exceeds $threshold are quoted, by default those beyond
+     * Number.MAX_SAFE_INTEGER (2^53 - 1) that JSON.parse() would round.
+     * The document is never decoded into PHP values.
+     *
+     * @param string $json The JSON document
+     * @param string[]|null $pointers JSON pointers of the values to convert
+     * @param float|null $threshold Magnitude a number must exceed to be quoted
+     * @return string The document with the numbers quoted
+     * @throws \Sift\Exception If the JSON is invalid, a pointer is missing
+     *                          or the threshold is negative
+     *
+     * @example
+     * // {"id": 12345678901234567890, "n": 1} => {"id": "12345678901234567890", "n": 1}
+     * $safe = Sift::convertNumbersToStrings($json);
+     * $safe = Sift::convertNumbersToStrings($json, ['/ids', '/order/total']);
+     */
+    public static function convertNumbersToStrings(string $json, ?array $pointers = null, ?float $threshold = null): string
+    {
+    }
+
+    /**
+     * Summarize a document as the largest valid JSON within a byte budget.
+     *
+     * For log lines and error reports with size limits, where substr() would
+     * cut the document into invalid JSON. A document that fits is returned
+     * unchanged. Otherwise values are kept in document order while they fit:
+     * long strings are cut short with "…", and the elements an array has no
+     * room for are replaced by a final "… N more" element (a "…": "N more"
+     * member in objects). Kept values are copied verbatim.
+     *
+     * @param string $json The JSON document
+     * @param int $maxBytes Maximum length of the result in bytes
+     * @return string Valid JSON of at most $maxBytes bytes
+     * @throws \Sift\Exception If JSON is invalid, or $maxBytes is negative or too
+     *     small for even the shortest summary
+     *
+     * @example
+     * Sift::truncate('{"note":"Lorem ipsum dolor sit amet","ids":[1,2,3,4,5,6,7,8]}', 40);
+     * // {"note":"Lorem ipsum…","…":"1 more"}
+     * Sift::truncate('[1,2,3,4,5,6,7,8,9,10]', 16);
+     * // [1,"… 9 more"]
+     */
+    public static function truncate(string $json, int $maxBytes): string
+    {
+    }
+
+    /**
+     * A compact one-line preview of a document, for debug UIs and log lines.
+     *
+     * Keys and scalars are shown as written (keys unquoted when they look like
+     * identifiers), strings are cut to 'string' characters with "…" and their
+     * full length, and arrays/objects show at most 'items' entries followed by
+     * "… N more". Arrays and objects deeper than 'depth' levels only show their
+     * length. Built in one pass without hydrating any value.
+     *
+     * Options:
+     * - 'depth' (int, default 2): levels of arrays/objects whose entries are shown
+     * - 'items' (int, default 5): entries shown per array/object
+     * - 'string' (int, default 40): characters shown per string or key
+     *
+     * @param string $json The JSON document
+     * @param array<string, int>|null $options Preview limits
+     * @return string The preview (not JSON)
+     * @throws \Sift\Exception If JSON is invalid or an option is unknown or invalid
+     *
+     * @example
+     * echo Sift::preview('{"id":42,"tags":["a","b","c","d","e","f","g"],"meta":{"x":{"y":1}}}');
+     * // {id: 42, tags: ["a", "b", "c", "d", "e", … 2 more], meta: {x: {… 1 key}}}
+     */
+    public static function preview(string $json, ?array $options = null): string
+    {
+    }
+
+    /**
+     * Remove object members whose value is null.
+     *
+     * A payload-slimming step before sending a document downstream: every
+     * "key": null member is dropped, and with $empties also members holding
+     * {} or [], including objects and arrays left empty by the removal.
+     * Array elements are never removed, so indices don't shift. Processed
+     * objects and arrays are written compactly; strings and numbers keep
+     * their text. The document is never hydrated.
+     *
+     * @param string $json JSON document
+     * @param bool $recursive Strip nested objects too, not just the top level
+     * @param bool $empties Also remove members holding empty arrays/objects
+     * @return string The stripped JSON
+     * @throws \Sift\Exception If the JSON is invalid
+     *
+     * @example
+     * Sift::stripNulls('{"a": 1, "b": null, "c": {"d": null}}');        // '{"a":1,"c":{}}'
+     * Sift::stripNulls('{"a": 1, "b": null, "c": {"d": null}}', true, true); // '{"a":1}'
+     */
+    public static function stripNulls(string $json, bool $recursive = true, bool $empties = false): string
+    {
+    }
+
+    /**
+     * Rename object keys per a mapping, keeping everything else as is.
+     *
+     * Every object key equal to an old name in $map, at any depth under
+     * $pointer (the whole document by default), becomes the new name. Only
+     * the key tokens are rewritten: member order, values and whitespace
+     * keep their bytes, so an API version adapter renaming a handful of
+     * fields doesn't decode and re-encode the document. Keys are compared
+     * unescaped. A rename onto a key the object already has leaves both.
+     *
+     * @param string $json JSON document
+     * @param array<string, string> $map Old key name => new key name
+     * @param string|null $pointer JSON pointer of the subtree to rename in
+     * @return string The JSON with the keys renamed
+     * @throws \Sift\Exception If the JSON is invalid, the pointer is not
+     *                          found, or a new name is not a string
+     *
+     * @example
+     * Sift::renameKeys('{"userName": "ada", "meta": {"userName": 1}}', ['userName' => 'username']);
+     * // '{"username": "ada", "meta": {"username": 1}}'
+     */
+    public static function renameKeys(string $json, array $map, ?string $pointer = null): string
+    {
+    }
+
+    /**
+     * Fill in missing object members from a defaults document.
+     *
+     * The complement of a merge: every value $json has wins, even null, and
+     * only keys it lacks are taken from $defaultsJson. Where both hold an
+     * object at the same path, they are combined the same way, recursively;
+     * arrays and scalars are never combined. Combined objects are written
+     * compactly, $json's members first in their order, then the added ones
+     * in the defaults' order. Values are copied as JSON text.
+     *
+     * @param string $json The JSON document to complete, e.g. user settings
+     * @param string $defaultsJson The JSON document with the default values
+     * @return string The completed JSON
+     * @throws \Sift\Exception If either document is invalid JSON
+     *
+     * @example
+     * Sift::defaultsDeep('{"theme": "dark", "mail": {"digest": null}}', '{"theme": "light", "mail": {"digest": "daily", "mentions": true}, "lang": "en"}');
+     * // '{"theme":"dark","mail":{"digest":null,"mentions":true},"lang":"en"}'
+     */
+    public static function defaultsDeep(string $json, string $defaultsJson): string
+    {
+    }
+
+    /**
+     * Encode a value with object keys in the order of an original document.
+     *
+     * Each object (array with non-sequential keys, or object's public
+     * properties) is written with the keys the original has at the same
+     * path first, in the original's order, followed by any new keys in PHP
+     * order. Sorting, rebuilding or unset() and re-adding keys in PHP then
+     * doesn't reorder a file that is re-saved, e.g. a config under code
+     * review. Values are encoded like encodeLines() rows; JsonSerializable
+     * objects are encoded as they serialize, without reordering.
+     *
+     * @param mixed $value The value to encode, typically decoded from $original
+     * @param string $original The JSON document whose key order to follow
+     * @return string The JSON text
+     * @throws \Sift\Exception If $original is invalid JSON or $value can't
+     *                          be encoded (malformed UTF-8, INF/NAN, resources)
+     *
+     * @example
+     * $original = '{"name": "app", "version": 1, "deps": {"b": 1, "a": 2}}';
+     * $config = Sift::decode($original);
+     * ksort($config);
+     * $config['version'] = 2;
+     * Sift::encodeOrdered($config, $original); // '{"name":"app","version":2,"deps":{"b":1,"a":2}}'
+     */
+    public static function encodeOrdered(mixed $value, string $original): string
+    {
+    }
+
+    /**
+     * Encode rows as newline-delimited JSON (NDJSON).
+     *
+     * Each element of $rows is encoded independently into one line, as
+     * json_encode($row, JSON_UNESCAPED_SLASHES | JSON_UNESCAPED_UNICODE |
+     * JSON_PRESERVE_ZERO_FRACTION) would. A row that can't be encoded
+     * (malformed UTF-8, INF or NAN, a resource, nesting deeper than 512) is
+     * left out and reported in 'errors' under its position (0-based, in
+     * iteration order), so one bad row doesn't fail the whole export.
+     * JsonSerializable objects are encoded through jsonSerialize(), other
+     * objects as their public properties.
+     *
+     * @param iterable $rows Array or Traversable (e.g. a generator) of rows
+     * @param callable|null $write Receives the output in chunks of about 64 KiB
+     *     as it is produced, e.g. fn (string $chunk) => fwrite($fp, $chunk)
+     * @return array{lines: string, count: int, errors: array<int, string>}
+     *     'lines' is empty when $write is given; 'count' is the number of
+     *     lines written
+     * @throws \Sift\Exception If $write fails or $rows can't be rewound
+     *
+     * @example
+     * $result = Sift::encodeLines($rows);
+     * file_put_contents('export.ndjson', $result['lines']);
+     * foreach ($result['errors'] as $position => $message) {
+     *     error_log("Row $position skipped: $message");
+     * }
+     */
+    public static function encodeLines(iterable $rows, ?callable $write = null): array
+    {
+    }
+
+    /**
+     * Join the lines of an NDJSON document into a JSON array.
+     *
+     * Blank lines are skipped. Each record is validated and copied as it
+     * is, without being decoded into PHP values.
+     *
+     * @param string $ndjson Newline-delimited JSON
+     * @return string A JSON array of the records, e.g. '[{"a":1},{"a":2}]'
+     * @throws \Sift\Exception If a line is not valid JSON (with its line number and offset)
+     *
+     * @example
+     * $batchApi->post(Sift::linesToArray(file_get_contents('events.ndjson')));
+     */
+    public static function linesToArray(string $ndjson): string
+    {
+    }
+
+    /**
+     * Write the elements of a JSON array as NDJSON, one per line.
+     *
+     * Elements are copied as they are, without being decoded; an element
+     * spread over several lines is put on one by removing the whitespace
+     * between its tokens. Every line, the last included, ends in "\n".
+     *
+     * @param string $json A JSON document whose top level is an array
+     * @return string One line per element; empty for an empty array
+     * @throws \Sift\Exception If the JSON is invalid or not an array
+     *
+     * @example
+     * file_put_contents('events.ndjson', Sift::arrayToLines($response->getBody()), FILE_APPEND);
+     */
+    public static function arrayToLines(string $json): string
+    {
+    }
+
+    /**
+     * Iterate over the records of an NDJSON file with parallel parsing.
+     *
+     * A background thread reads the file in batches of lines and worker
+     * threads parse them, staying a few batches ahead of the loop, so
+     * reading and parsing overlap with the PHP code handling each record.
+     * Records are yielded in file order, keyed by the byte offset of their
+     * line; blank lines are skipped. Options that need the raw text (e.g.
+     * 'strict', 'keys') leave the decoding to the PHP thread, and workers
+     * only read lines and check their UTF-8. With 'warnings', lastWarnings()
+     * holds the current record's warnings; repairs to its text are located
+     * by their byte offset in the file.
+     *
+     * @param string $path Path of the NDJSON file, which must be allowed
+     *                     by open_basedir
+     * @param array<string, mixed>|null $options Decode options, as for decode()
+     * @param int|null $workers Parsing threads, 1 to 32; defaults to one per
+     *                          CPU, at most 4
+     * @param int|null $offset A byte offset returned by Lines::tell() for the
+     *                         same file
+     * @param array{max_bytes?: int, when_full?: 'block'|'error'}|null $buffer
+     *     Bytes of line text the threads may read ahead of the loop (16 MiB
+     *     by default). Once that far ahead, 'block' (default) makes them wait
+     *     for the loop to catch up, 'error' makes iteration throw
+     *     \Sift\InputTooLargeException after the records read so far, so
+     *     a daemon can resume from tell() later
+     * @return \Sift\Lines Iterator yielding decoded records
+     * @throws \Sift\Exception If an option is unknown, $workers is out of
+     *                          range or $offset negative; during iteration,
+     *                          if the file can't be read or a line is not
+     *                          valid JSON (with its byte offset)
+     *
+     * @example
+     * foreach (Sift::lines('/data/events.ndjson', workers: 8) as $event) {
+     *     $importer->add($event);
+     * }
+     */
+    public static function lines(string $path, ?array $options = null, ?int $workers = null, ?int $offset = null, ?array $buffer = null): \Sift\Lines
+    {
+    }
+
+    /**
+     * Version, SIMD support, limits and build flags of the extension.
+     *
+     * sonic-rs picks its SIMD implementation at compile time, so a build for
+     * a generic target runs the portable fallback even on CPUs with AVX2.
+     * Compare 'simd' (in use) with 'cpu_simd' (best this CPU supports); a
+     * warning is also logged at startup when the fallback is in use, unless
+     * the extension was built with the 'portable' cargo feature for hosts
+     * without SIMD support ('build' => ['portable' => true]).
+     *
+     * @return array{version: string, engine: string, simd: string, cpu_simd: string, cpu: array<string, bool>, limits: array{max_input_size: int, max_depth: int, max_depth_option: int, max_pointer_segments: int}, build: array{profile: string, portable: bool, arch: string, os: string, target_features: list<string>}}
+     *
+     * @example
+     * $info = Sift::info();
+     * if ($info['simd'] !== $info['cpu_simd']) {
+     *     error_log('sift: rebuild with RUSTFLAGS="-C target-cpu=native"');
+     * }
+     */
+    public static function info(): array
+    {
+    }
+
+    /**
+     * Return idle memory held between calls.
+     *
+     * Frees the conversion arena kept warm across calls and, with glibc,
+     * asks malloc to return free heap pages to the OS. For long-running
+     * workers (Swoole, RoadRunner) that once converted a large document;
+     * FPM workers are recycled and don't need it. Memory of live Queries,
+     * Pools and Snapshots is untouched: Query::release() them first.
+     *
+     * @return int Bytes of arena memory freed
+     *
+     * @example
+     * $server->on('workerIdle', fn() => Sift::trimMemory());
+     */
+    public static function trimMemory(): int
+    {
+    }
+
+    /**
+     * Memory held by the extension outside the Zend memory manager.
+     *
+     * Query texts, Pool buffers and Snapshot trees live on the Rust heap,
+     * which memory_get_usage() doesn't see. The extension counts it here;
+     * add 'current' to memory_get_usage() for the worker's real usage.
+     * The peak is reset at the end of each request. Documents of 64 KB or
+     * more copied into Rust (query(), from(), fromRequest(), snapshot(),
+     * Pool::addDocument()) are also checked against what memory_limit
+     * leaves after PHP's own usage.
+     *
+     * @return array{current: int, peak: int, arena: int} Bytes held now,
+     *     the peak, and the idle conversion arena's share of 'current'
+     *
+     * @example
+     * $metrics->gauge('memory', memory_get_usage() + Sift::memoryUsage()['current']);
+     */
+    public static function memoryUsage(): array
+    {
+    }
+
+    /**
+     * Sift objects created since the last leak-check checkpoint that are
+     * still alive.
+     *
+     * Queries, Pools, Snapshots and the iterators (Chunks, Lines, Tokens,
+     * FrameReader) are counted only when the sift.leak_check INI flag is
+     * on; otherwise the result is always empty. Checkpoints are the start
+     * of each request and every checkLeaks() call.
+     *
+     * @return array<string, int> Class name => live objects
+     *
+     * @example
+     * // php -d sift.leak_check=1 worker.php
+     * var_dump(Sift::liveObjects());   // ['Sift\Query' => 3]
+     */
+    public static function liveObjects(): array
+    {
+    }
+
+    /**
+     * Start a new leak-check checkpoint, failing if objects from the
+     * previous one are still alive.
+     *
+     * Meant for long-running workers, where an object kept by a static
+     * property, a cache or a reference cycle is never freed: call it at
+     * the end of every job. At the end of a web request, what is left is
+     * logged as a warning instead. Requires the sift.leak_check INI flag
+     * (php.ini or -d only); without it this does nothing and returns 0.
+     *
+     * @param bool $throw False to return the count instead of throwing,
+     *     e.g. to accept the objects a worker keeps from booting
+     * @return int Objects still alive from the previous checkpoint
+     * @throws Sift\Exception With code LEAKED_OBJECTS, listing the classes
+     *     and counts, when $throw is true and any are alive
+     *
+     * @example
+     * Sift::checkLeaks(false);
+     * while ($job = $queue->pop()) {
+     *     handle($job);
+     *     Sift::checkLeaks();
+     * }
+     */
+    public static function checkLeaks(bool $throw = true): int
+    {
+    }
+
+    /**
+     * Extract a shaped result from a JSON document with a template.
+     *
+     * Every string in the template is a JSON pointer, replaced by the value
+     * it resolves to, or null when it doesn't. A '*' segment maps the rest
+     * of the pointer over every element of an array (giving a list) or
+     * member of an object (keeping its keys). A two-element list
+     * [pattern, template] whose pattern has a '*' applies the template to
+     * each value the pattern selects, with pointers relative to that value.
+     * Any other array is built key for key. The template is validated
+     * before the document is parsed, and the document is parsed once.
+     *
+     * @param string $json JSON string
+     * @param array $template Pointers, nested arrays and [pattern, template] pairs
+     * @return array
+     * @throws \Sift\Exception If the template is invalid, or the JSON is invalid or too large
+     *
+     * @example
+     * $view = Sift::extract($json, [
+     *     'user'  => '/data/user/name',
+     *     'items' => ['/data/items/*', ['id' => '/id', 'qty' => '/qty']],
+     * ]);
+     */
+    public static function extract(string $json, array $template): array
+    {
+    }
+
+    /**
+     * Compile an extraction template for reuse.
+     *
+     * The template takes the same forms as in extract(); it is parsed and
+     * validated here, once, and the returned Extractor applies it to any
+     * number of documents.
+     *
+     * @param array $template Pointers, nested arrays and [pattern, template] pairs
+     * @return \Sift\Extractor
+     * @throws \Sift\Exception If the template is invalid
+     *
+     * @example
+     * $extractor = Sift::compileExtractor(['id' => '/id', 'user' => '/user/name']);
+     * foreach ($messages as $message) {
+     *     $rows[] = $extractor->extract($message);
+     * }
+     */
+    public static function compileExtractor(array $template): \Sift\Extractor
+    {
+    }
+
+    /**
+     * Cache compiled schemas and extraction templates in APCu.
+     *
+     * Entries are keyed by the extension version and a SHA-256 of the
+     * source (the schema text, or the template as JSON), so FPM workers
+     * compile each schema or template once per deploy instead of once per
+     * request. Results and errors are the same with or without the cache.
+     * Off by default; the setting lasts for the worker's lifetime.
+     *
+     * @param bool $enabled False to turn the cache off again
+     * @return bool Whether the cache is in use: APCu must be loaded and
+     *              enabled (apc.enable_cli=1 on the CLI)
+     *
+     * @example
+     * // bootstrap.php
+     * Sift::cacheCompiled();
+     */
+    public static function cacheCompiled(bool $enabled = true): bool
+    {
+    }
+
+    /**
+     * Iterate over the tokens of a JSON document with their byte offsets.
+     *
+     * Each token is [type, start, end], end exclusive, so
+     * substr($json, $start, $end - $start) is its source text. Types are
+     * 'begin_object', 'end_object', 'begin_array', 'end_array', 'colon',
+     * 'comma', 'key', 'string', 'number', 'true', 'false' and 'null'; a
+     * string in key position is a 'key'. Whitespace is skipped. The whole
+     * document is validated before the first token.
+     *
+     * @param string $json JSON string
+     * @return Tokens
+     * @throws \Sift\Exception If the JSON is invalid or too large
+     *
+     * @example
+     * foreach (Sift::tokenize($json) as [$type, $start, $end]) {
+     *     $html .= highlight($type, substr($json, $start, $end - $start));
+     * }
+     */
+    public static function tokenize(string $json): Tokens
+    {
+    }
+
+    /**
+     * Micro-benchmark decode(), get() and query() on this machine.
+     *
+     * Times each operation on $json (or a bundled fixture of 1000 user
+     * records) next to json_decode(), using the configured decode options,
+     * and reports which SIMD instruction set the build uses ('simd') and
+     * the best one this CPU supports ('cpu_simd'). If they differ, a build
+     * with RUSTFLAGS="-C target-cpu=native" is faster.
+     *
+     * Options:
+     * - 'iterations' (int, default 100): timed calls per operation
+     * - 'pointer' (string): pointer looked up by get() and query(); defaults
+     *   to the last top-level entry
+     *
+     * Each entry of 'results' (decode, get, query and, when available,
+     * json_decode) holds 'ms' (total), 'per_op_us' and 'mb_per_s'.
+     *
+     * @param string|null $json The JSON document, or null for the fixture
+     * @param array<string, int|string>|null $options Benchmark settings
+     * @return array{simd: string, cpu_simd: string, bytes: int, iterations: int, pointer: string, results: array<string, array{ms: float, per_op_us: float, mb_per_s: float}>, speedup: float|null}
+     * @throws \Sift\Exception If JSON is invalid or an option is unknown or invalid
+     *
+     * @example
+     * $report = Sift::bench(file_get_contents('payload.json'), ['iterations' => 500]);
+     * printf("%s: %.1f MB/s, %.1fx json_decode\n",
+     *     $report['simd'], $report['results']['decode']['mb_per_s'], $report['speedup']);
+     */
+    public static function bench(?string $json = null, ?array $options = null): array
+    {
+    }
+
+    /**
+     * Escape a string as a JSON string literal with the SIMD escaper.
+     *
+     * For templates that build JSON by concatenation. Quotes, backslashes
+     * and control characters are escaped; slashes and non-ASCII characters
+     * are kept, like JSON_UNESCAPED_SLASHES | JSON_UNESCAPED_UNICODE.
+     *
+     * @param string $string The UTF-8 string to escape
+     * @param bool $quote Whether to add the surrounding quotes
+     * @return string The escaped string
+     * @throws \Sift\Exception If the string is not valid UTF-8
+     *
+     * @example
+     * $json = '{"name":' . Sift::escapeString($name) . '}';
+     * $json = '{"name":"' . Sift::escapeString($name, false) . '"}';
+     */
+    public static function escapeString(string $string, bool $quote = true): string
+    {
+    }
+
+    /**
+     * Decode a JSON string literal into the string it stands for.
+     *
+     * @param string $literal The JSON string literal
+     * @param bool $quoted Whether $literal includes the surrounding quotes
+     * @return string The unescaped string
+     * @throws \Sift\Exception If $literal is not a valid JSON string
+     *
+     * @example
+     * Sift::unescapeString('"caf\\u00e9\\n"'); // "café\n"
+     * Sift::unescapeString('a\\"b', false);      // 'a"b'
+     */
+    public static function unescapeString(string $literal, bool $quoted = true): string
+    {
+    }
+
+    /**
+     * SIMD-accelerated UTF-8 validation.
+     *
+     * Checks any binary string, JSON or not; a faster replacement for
+     * mb_check_encoding($s, 'UTF-8') and preg_match('//u', $s). Overlong
+     * encodings, surrogates and code points above U+10FFFF are invalid.
+     *
+     * @param string $string The bytes to check
+     * @return bool True if the whole string is valid UTF-8
+     *
+     * @example
+     * if (!Sift::validateUtf8($name)) {
+     *     throw new InvalidArgumentException('name must be UTF-8');
+     * }
+     */
+    public static function validateUtf8(string $string): bool
+    {
+    }
+
+    /**
+     * Offset of the first malformed UTF-8 byte.
+     *
+     * @param string $string The bytes to check
+     * @return int|null Byte offset where the first invalid sequence starts,
+     *     or null if the whole string is valid UTF-8
+     *
+     * @example
+     * $offset = Sift::invalidUtf8Offset($input);
+     * if ($offset !== null) {
+     *     $input = substr($input, 0, $offset); // keep the valid prefix
+     * }
+     */
+    public static function invalidUtf8Offset(string $string): ?int
+    {
+    }
+
+    /**
+     * SIMD-accelerated JSON validation.
+     *
+     * Validates JSON syntax. Returns false for invalid JSON or inputs
+     * exceeding the 64 MB size limit.
+     *
+     * @param string $json The JSON string to validate
+     * @return bool True if valid JSON, false otherwise
+     *
+     * @example
+     * if (Sift::isValid($userInput)) {
+     *     $data = Sift::decode($userInput);
+     * }
+     */
+    public static function isValid(string $json): bool
+    {
+    }
+
+    /**
+     * JSON validation that separates limit violations from syntax errors.
+     *
+     * Like isValid(), but input exceeding the 64 MB size limit throws
+     * instead of returning false, so false always means malformed JSON.
+     * Use it in health checks, where an oversized payload points at a
+     * configuration problem rather than a bad producer.
+     *
+     * @param string $json The JSON string to validate
+     * @return bool True if valid JSON, false if malformed
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     *
+     * @example
+     * try {
+     *     $ok = Sift::isValidStrict($payload);
+     * } catch (\Sift\InputTooLargeException $e) {
+     *     error_log("payload of {$e->getSize()} bytes over the {$e->getLimit()} byte limit");
+     * }
+     */
+    public static function isValidStrict(string $json): bool
+    {
+    }
+
+    /**
+     * Format a float as JSON number text, independent of the C locale.
+     *
+     * Uses the shortest digits that decode back to the same float and always
+     * includes a fraction or exponent, so the text decodes as a float. The
+     * decimal separator is always '.', whatever setlocale() says.
+     *
+     * Notations:
+     * - 'auto' (default): decimal for magnitudes in [1e-4, 1e15), exponent otherwise
+     * - 'decimal': always plain digits
+     * - 'exponent': always d.ddde±x
+     *
+     * @param float $value The number to format
+     * @param string|null $notation 'auto', 'decimal' or 'exponent'
+     * @return string JSON number text
+     * @throws \Sift\Exception For NAN/INF or an unknown notation
+     *
+     * @example
+     * Sift::formatFloat(1.5);              // "1.5"
+     * Sift::formatFloat(1e25);             // "1.0e+25"
+     * Sift::formatFloat(1e25, 'decimal');  // "10000000000000000000000000.0"
+     */
+    public static function formatFloat(float $value, ?string $notation = null): string
+    {
+    }
+
+    /**
+     * Lossy conversions applied by the most recent decode.
+     *
+     * Only populated when decode was called with ['warnings' => true]; any
+     * other decode resets the list, even one that throws. Kinds:
+     * - bigint_to_float: integer outside PHP's int range, decoded as float
+     * - float_precision: number with more digits than a double can hold
+     * - utf8_substituted: malformed UTF-8 replaced or dropped (`utf8` option)
+     * - surrogate_replaced: lone surrogate escape replaced with U+FFFD (`surrogates` option)
+     *
+     * @return array<int, array{pointer: ?string, kind: string, offset: ?int}> JSON pointer, or
+     *         byte offset for repairs to the input text, and kind of each loss
+     *
+     * @example
+     * $data = Sift::decode('{"id": 18446744073709551615}', ['warnings' => true]);
+     * $warnings = Sift::lastWarnings();
+     * // Returns: [['pointer' => '/id', 'kind' => 'bigint_to_float', 'offset' => null]]
+     */
+    public static function lastWarnings(): array
     {
     }
 }
@@ -0,0 +1,124 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * Snapshot - Immutable decoded JSON held in Rust
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * A Snapshot parses a document once and keeps the tree on the Rust side.
+ * Pointer reads only create PHP values for the subtree they return, and
+ * toArray() converts everything on demand. Snapshots are never modified,
+ * so copies share the same tree.
+ *
+ * persist() keeps a snapshot in the worker process, so later requests
+ * served by the same worker can reuse it without re-parsing (the
+ * config-cache use case). A worker keeps at most 256 persisted snapshots:
+ * persisting another name evicts the one stored or read least recently.
+ * Otherwise they live until forget(), forgetAll() or until the worker
+ * exits; they are per process, not shared between PHP-FPM workers.
+ *
+ * Security limits:
+ * - Maximum input size: 64 MB
+ * - Maximum nesting depth: 512 (checked when values are converted)
+ * - Maximum pointer segments: 256
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ */
+class Snapshot
+{
+    /**
+     * Convert the whole document into PHP values.
+     *
+     * Numbers were parsed into the tree as ints or floats, so the options
+     * that need their original text (strict, warnings, big_numbers, bigint
+     * and numbers) are rejected, also when Sift::configure() set them.
+     *
+     * @param array|null $options The same options as Sift::decode(), except strict, warnings, big_numbers, bigint and numbers
+     * @return mixed The decoded value (array, string, int, float, bool, or null)
+     * @throws \Sift\Exception If the document is nested too deeply, or an option needs the number text (INVALID_OPTION)
+     *
+     * @example
+     * $config = Sift::snapshot($json)->toArray();
+     */
+    public function toArray(?array $options = null): mixed
+    {
+    }
+
+    /**
+     * Extract a value by RFC 6901 JSON pointer, converting only that subtree.
+     *
+     * @param string $pointer JSON pointer path, e.g., "/database/dsn" ("" for the root)
+     * @param array|null $options The same options as toArray()
+     * @return mixed The value at the path
+     * @throws \Sift\Exception If the pointer is invalid, the path doesn't exist or an option needs the number text
+     *
+     * @example
+     * $dsn = $snap->get('/database/dsn');
+     * $db = $snap->get('/database', ['assoc' => false]);
+     */
+    public function get(string $pointer, ?array $options = null): mixed
+    {
+    }
+
+    /**
+     * Check whether a JSON pointer exists, without converting anything.
+     *
+     * @param string $pointer JSON pointer path
+     * @return bool True if the path exists
+     * @throws \Sift\Exception If the pointer is invalid
+     */
+    public function has(string $pointer): bool
+    {
+    }
+
+    /**
+     * Keep this snapshot in the worker process under a name, replacing any
+     * snapshot previously stored under it. With 256 names stored already,
+     * the one stored or read least recently is dropped.
+     *
+     * @param string $name Registry name
+     * @return Snapshot This snapshot, for chaining
+     *
+     * @example
+     * $snap = Snapshot::persisted('config')
+     *     ?? Sift::snapshot(file_get_contents('config.json'))->persist('config');
+     */
+    public function persist(string $name): Snapshot
+    {
+    }
+
+    /**
+     * Snapshot previously stored with persist() in this worker process.
+     *
+     * @param string $name Registry name
+     * @return Snapshot|null The snapshot, or null if none is stored under the name
+     */
+    public static function persisted(string $name): ?Snapshot
+    {
+    }
+
+    /**
+     * Drop a persisted snapshot. Snapshot objects already handed out keep working.
+     *
+     * @param string $name Registry name
+     * @return bool True if a snapshot was stored under the name
+     */
+    public static function forget(string $name): bool
+    {
+    }
+
+    /**
+     * Drop every persisted snapshot. Snapshot objects already handed out keep working.
+     *
+     * @return int How many snapshots were stored
+     */
+    public static function forgetAll(): int
+    {
+    }
+}
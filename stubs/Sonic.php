@@ -27,7 +27,8 @@ class Sonic
      * @param string $json The JSON string to parse
      * @param string $pointer JSON pointer path (RFC 6901), e.g., "/users/0/email"
      * @return mixed The extracted value (string, int, float, bool, array, or null)
-     * @throws \Exception If JSON is invalid, pointer path not found, or limits exceeded
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If JSON is invalid, pointer path not found, or limits exceeded
      *
      * @example
      * $json = '{"users": [{"id": 1, "email": "alice@example.com"}]}';
@@ -50,7 +51,8 @@ class Sonic
      *
      * @param string $json The JSON string to decode
      * @return mixed The decoded value (array, string, int, float, bool, or null)
-     * @throws \Exception If JSON is invalid or limits exceeded
+     * @throws \Sift\InputTooLargeException If the input exceeds the size limit
+     * @throws \Sift\Exception If JSON is invalid or limits exceeded
      *
      * @example
      * $data = Sonic::decode('{"name": "sonic", "fast": true}');
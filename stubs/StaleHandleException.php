@@ -0,0 +1,33 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * StaleHandleException - a document id outlived its document
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Thrown when an id refers to a document that has been released, such
+ * as a Pool id kept across Pool::clear(), and by reads on a Query after
+ * Query::release(). Ids carry the generation they
+ * were issued in, so a stale one is caught rather than reading whichever
+ * document now sits in its slot. Long-running workers that recycle pools
+ * can catch it to detect ids leaking from one batch into the next:
+ *
+ *     try {
+ *         $type = $pool->get($id, '/event/type');
+ *     } catch (\Sift\StaleHandleException $e) {
+ *         // $id was issued before the last clear()
+ *     }
+ *
+ * getErrorCode() returns STALE_HANDLE.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ */
+class StaleHandleException extends Exception
+{
+}
@@ -0,0 +1,80 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift\Bridge;
+
+/**
+ * SymfonyEncoder - Symfony Serializer encoder/decoder for the json format
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Has the methods of Symfony's EncoderInterface and DecoderInterface.
+ * Those interfaces are defined by Symfony, not the extension, and the
+ * Serializer only picks up encoders that implement them, so declare a
+ * subclass that does, once, and register it as the json encoder:
+ *
+ *     final class SiftJsonEncoder extends \Sift\Bridge\SymfonyEncoder
+ *         implements EncoderInterface, DecoderInterface {}
+ *
+ *     # services.yaml
+ *     App\Serializer\SiftJsonEncoder:
+ *         tags: [serializer.encoder]
+ *
+ * Output and decoded values are those of Sift's encoder and Sift::decode().
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ */
+class SymfonyEncoder
+{
+    /**
+     * @param array|null $options Decode options, as for Sift::decode(), used by decode()
+     * @throws \Sift\Exception If an option is unknown
+     */
+    public function __construct(?array $options = null)
+    {
+    }
+
+    /**
+     * Encode normalized data to JSON.
+     *
+     * @param mixed $data The normalized data
+     * @param string $format The format ("json")
+     * @param array|null $context Serializer context (unused)
+     * @return string The JSON text
+     * @throws \Sift\Exception If the data can't be encoded
+     */
+    public function encode(mixed $data, string $format, ?array $context = null): string
+    {
+    }
+
+    /**
+     * Decode JSON. Objects become arrays, as with Symfony's JsonEncoder,
+     * unless the context sets json_decode_associative to false.
+     *
+     * @param string $data The JSON text
+     * @param string $format The format ("json")
+     * @param array|null $context Serializer context
+     * @return mixed The decoded value
+     * @throws \Sift\Exception If the JSON is invalid
+     */
+    public function decode(string $data, string $format, ?array $context = null): mixed
+    {
+    }
+
+    /**
+     * Whether the format is "json".
+     */
+    public function supportsEncoding(string $format, ?array $context = null): bool
+    {
+    }
+
+    /**
+     * Whether the format is "json".
+     */
+    public function supportsDecoding(string $format, ?array $context = null): bool
+    {
+    }
+}
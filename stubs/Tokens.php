@@ -0,0 +1,63 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Sift;
+
+/**
+ * Tokens - the token stream of a JSON document
+ *
+ * This is a stub file for IDE autocompletion. The actual implementation
+ * is provided by the sonic PHP extension (written in Rust).
+ *
+ * Returned by Sift::tokenize(). Each iteration yields [type, start, end]:
+ * the token type and its byte range in the input, end exclusive, keyed by
+ * token index (0, 1, 2, ...). Types are 'begin_object', 'end_object',
+ * 'begin_array', 'end_array', 'colon', 'comma', 'key' (a string in key
+ * position), 'string', 'number', 'true', 'false' and 'null'. Tokens are
+ * scanned as the iteration advances; whitespace is skipped.
+ *
+ * @package Sift
+ * @link https://github.com/dmytrokucher/sift
+ * @implements \Iterator<int, array{string, int, int}>
+ */
+class Tokens implements \Iterator
+{
+    /**
+     * Start over from the first token.
+     */
+    public function rewind(): void
+    {
+    }
+
+    /**
+     * Whether there is a current token.
+     */
+    public function valid(): bool
+    {
+    }
+
+    /**
+     * The current token as [type, start, end], or null once the input is
+     * exhausted.
+     *
+     * @return array{string, int, int}|null
+     */
+    public function current(): mixed
+    {
+    }
+
+    /**
+     * Index of the current token, or null once the input is exhausted.
+     */
+    public function key(): ?int
+    {
+    }
+
+    /**
+     * Move on to the next token.
+     */
+    public function next(): void
+    {
+    }
+}
@@ -0,0 +1,235 @@
+<?php
+
+declare(strict_types=1);
+
+/**
+ * json_decode compatibility tests for Sift's strict mode.
+ * Every case is checked against json_decode($json, true, 512, JSON_THROW_ON_ERROR).
+ * Run with: php tests/php/CompatTest.php
+ */
+
+// Ensure extension is loaded
+if (!class_exists('Sift')) {
+    die("ERROR: Sift extension not loaded. Run 'cargo php install' first.\n");
+}
+
+echo "=== json_decode Compatibility Test Suite ===\n\n";
+
+$passed = 0;
+$failed = 0;
+
+function test(string $name, callable $fn): void {
+    global $passed, $failed;
+    try {
+        $fn();
+        echo "\u{2713} {$name}\n";
+        $passed++;
+    } catch (Throwable $e) {
+        echo "\u{2717} {$name}: {$e->getMessage()}\n";
+        $failed++;
+    }
+}
+
+function assert_true(bool $condition, string $message = ''): void {
+    if (!$condition) {
+        throw new Exception($message ?: 'Assertion failed: expected true');
+    }
+}
+
+/**
+ * Decode with both engines and require identical results: same value
+ * (including int/float and key types), or the same JsonException code.
+ * $options are the Sift options matching $associative, $depth and $flags.
+ */
+function assert_same_as_json_decode(
+    string $json,
+    array $options = [],
+    bool $associative = true,
+    int $depth = 512,
+    int $flags = 0
+): void {
+    $expected = null;
+    $expectedCode = null;
+    try {
+        $expected = json_decode($json, $associative, $depth, $flags | JSON_THROW_ON_ERROR);
+    } catch (JsonException $e) {
+        $expectedCode = $e->getCode();
+    }
+
+    $actual = null;
+    $actualCode = null;
+    try {
+        $actual = Sift::decode($json, ['strict' => true] + $options);
+    } catch (JsonException $e) {
+        $actualCode = $e->getCode();
+    }
+
+    if ($expectedCode !== $actualCode) {
+        throw new Exception(sprintf(
+            'Expected error code %s but got %s',
+            var_export($expectedCode, true),
+            var_export($actualCode, true)
+        ));
+    }
+    // var_export distinguishes -0.0 from 0.0 and int keys from string keys
+    if (var_export($expected, true) !== var_export($actual, true)) {
+        throw new Exception(sprintf(
+            'Expected %s but got %s',
+            var_export($expected, true),
+            var_export($actual, true)
+        ));
+    }
+}
+
+// ==================== Scalars ====================
+echo "--- Scalars ---\n";
+
+$scalarCases = [
+    'integer' => '42',
+    'negative zero integer' => '-0',
+    'negative zero float' => '-0.0',
+    'float' => '3.14159',
+    'exponent' => '1E2',
+    'huge exponent' => '1e400',
+    'i64 max' => '9223372036854775807',
+    'i64 min' => '-9223372036854775808',
+    'u64 overflow' => '18446744073709551615',
+    'beyond u64' => '123456789012345678901234567890',
+    'negative big int' => '-9223372036854775809',
+    'string' => '"hello"',
+    'escaped unicode' => '"Caf\u00e9"',
+    'surrogate pair' => '"\ud83d\ude00"',
+    'true' => 'true',
+    'null' => 'null',
+];
+
+foreach ($scalarCases as $name => $json) {
+    test("Scalar - {$name}", function() use ($json) {
+        assert_same_as_json_decode($json);
+    });
+}
+
+// ==================== Objects ====================
+echo "\n--- Objects ---\n";
+
+$objectCases = [
+    'duplicate keys' => '{"a": 1, "b": 2, "a": 3}',
+    'empty key' => '{"": "empty"}',
+    'numeric string keys' => '{"123": "a", "-5": "b", "0": "c"}',
+    'non-canonical numeric keys' => '{"007": "a", "-0": "b", "+1": "c", "1.5": "d"}',
+    'key beyond i64' => '{"9223372036854775808": "big"}',
+    'nested empties' => '{"a": [], "b": {}}',
+];
+
+foreach ($objectCases as $name => $json) {
+    test("Object - {$name}", function() use ($json) {
+        assert_same_as_json_decode($json);
+    });
+}
+
+// ==================== Errors ====================
+echo "\n--- Errors ---\n";
+
+$errorCases = [
+    'syntax error' => '{"broken": }',
+    'trailing comma' => '[1, 2, ]',
+    'trailing characters' => '[1] x',
+    'empty input' => '',
+    'lone high surrogate' => '"\ud800"',
+    'lone low surrogate' => '"\udc00"',
+    'lone surrogate in key' => '{"\ud800": 1}',
+    'control character' => "\"a\x01b\"",
+];
+
+foreach ($errorCases as $name => $json) {
+    test("Error - {$name}", function() use ($json) {
+        assert_same_as_json_decode($json);
+    });
+}
+
+test('Error - throws JsonException', function() {
+    try {
+        Sift::decode('{', ['strict' => true]);
+        throw new Exception('Expected exception was not thrown');
+    } catch (JsonException $e) {
+        assert_true($e->getCode() === JSON_ERROR_SYNTAX);
+    }
+});
+
+// ==================== Nesting Depth ====================
+echo "\n--- Nesting Depth ---\n";
+
+test('Depth - 512 levels accepted', function() {
+    assert_same_as_json_decode(str_repeat('[', 512) . str_repeat(']', 512));
+});
+
+test('Depth - 513 levels rejected with JSON_ERROR_DEPTH', function() {
+    assert_same_as_json_decode(str_repeat('[', 513) . str_repeat(']', 513));
+});
+
+test('Depth - objects count towards depth', function() {
+    assert_same_as_json_decode(str_repeat('{"a":', 513) . '1' . str_repeat('}', 513));
+});
+
+// ==================== Options ====================
+echo "\n--- Options ---\n";
+
+test('Options - unknown option rejected', function() {
+    try {
+        Sift::decode('1', ['strcit' => true]);
+        throw new Exception('Expected exception was not thrown');
+    } catch (Exception $e) {
+        assert_true(str_contains($e->getMessage(), 'Unknown option'));
+    }
+});
+
+test('Options - strict disabled uses the default decoder', function() {
+    $result = Sift::decode('{"a": 1}', ['strict' => false]);
+    assert_true($result === ['a' => 1]);
+});
+
+// ==================== json_decode Arguments And Flags ====================
+echo "\n--- json_decode Arguments And Flags ---\n";
+
+test('Flags - objects as stdClass', function() {
+    $options = ['assoc' => false];
+    assert_same_as_json_decode('{"a": {"b": [1, {"c": null}]}, "": 2, "123": 3}', $options, false);
+    assert_same_as_json_decode('[{}, [], {"x": {}}]', $options, false);
+    assert_same_as_json_decode('{"a": 1, "a": 2}', $options, false);
+});
+
+test('Flags - NUL-prefixed property names are invalid', function() {
+    assert_same_as_json_decode('{"\\u0000a": 1}', ['assoc' => false], false);
+    assert_same_as_json_decode('{"\\u0000a": 1}');
+});
+
+test('Flags - JSON_BIGINT_AS_STRING', function() {
+    $json = '[12345678901234567890, -98765432109876543210, 9223372036854775807, 1.5, 1e2]';
+    assert_same_as_json_decode($json, ['bigint' => 'string'], true, 512, JSON_BIGINT_AS_STRING);
+});
+
+test('Flags - depth', function() {
+    foreach ([1, 2, 3, 1024] as $depth) {
+        $options = ['depth' => $depth];
+        assert_same_as_json_decode('1', $options, true, $depth);
+        assert_same_as_json_decode('[[1]]', $options, true, $depth);
+        assert_same_as_json_decode('{"a": [{"b": 1}]}', $options, true, $depth);
+    }
+    $deep = str_repeat('[', 1000) . str_repeat(']', 1000);
+    assert_same_as_json_decode($deep, ['depth' => 1000], true, 1000);
+    assert_same_as_json_decode($deep, ['depth' => 999], true, 999);
+});
+
+test('Flags - JSON_INVALID_UTF8_IGNORE and _SUBSTITUTE', function() {
+    $json = "[\"a\xffb\", \"\xc3\xa9\"]";
+    assert_same_as_json_decode($json);
+    assert_same_as_json_decode($json, ['utf8' => 'ignore'], true, 512, JSON_INVALID_UTF8_IGNORE);
+    assert_same_as_json_decode($json, ['utf8' => 'substitute'], true, 512, JSON_INVALID_UTF8_SUBSTITUTE);
+});
+
+// ==================== Summary ====================
+echo "\n=== Results ===\n";
+echo "Passed: {$passed}\n";
+echo "Failed: {$failed}\n";
+
+exit($failed > 0 ? 1 : 0);
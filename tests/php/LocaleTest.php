@@ -0,0 +1,181 @@
+<?php
+
+declare(strict_types=1);
+
+/**
+ * Locale-independence regression tests.
+ * Numbers must parse and format identically whatever LC_NUMERIC says,
+ * including locales whose decimal separator is a comma.
+ * Run with: php tests/php/LocaleTest.php
+ */
+
+// Ensure extension is loaded
+if (!class_exists('Sift')) {
+    die("ERROR: Sift extension not loaded. Run 'cargo php install' first.\n");
+}
+
+echo "=== Locale Independence Test Suite ===\n\n";
+
+$passed = 0;
+$failed = 0;
+
+function test(string $name, callable $fn): void {
+    global $passed, $failed;
+    try {
+        $fn();
+        echo "\u{2713} {$name}\n";
+        $passed++;
+    } catch (Throwable $e) {
+        echo "\u{2717} {$name}: {$e->getMessage()}\n";
+        $failed++;
+    }
+}
+
+function assert_true(bool $condition, string $message = ''): void {
+    if (!$condition) {
+        throw new Exception($message ?: 'Assertion failed: expected true');
+    }
+}
+
+function assert_identical($expected, $actual, string $message = ''): void {
+    if ($expected !== $actual) {
+        throw new Exception($message ?: sprintf(
+            'Expected %s but got %s',
+            var_export($expected, true),
+            var_export($actual, true)
+        ));
+    }
+}
+
+// Locales with a comma decimal separator; the first one installed is used
+$commaLocales = ['de_DE.UTF-8', 'de_DE', 'fr_FR.UTF-8', 'fr_FR', 'ru_RU.UTF-8', 'pt_BR.UTF-8', 'nl_NL.UTF-8'];
+$originalLocale = setlocale(LC_ALL, 0);
+$commaLocale = setlocale(LC_ALL, ...$commaLocales);
+
+if ($commaLocale === false || localeconv()['decimal_point'] !== ',') {
+    echo "No comma-decimal locale installed; running under the default locale only.\n";
+    echo "Install one (e.g. locale-gen de_DE.UTF-8) for full coverage.\n\n";
+    setlocale(LC_ALL, $originalLocale);
+} else {
+    echo "Running under {$commaLocale} (decimal point ',')\n\n";
+}
+
+// ==================== Decoding ====================
+echo "--- Decoding ---\n";
+
+test('Decode - floats', function() {
+    $data = Sift::decode('[1.5, -0.25, 3.14159, 1e-7, 2.5E3]');
+    assert_identical([1.5, -0.25, 3.14159, 1.0E-7, 2500.0], $data);
+});
+
+test('Decode - lazy strategy floats', function() {
+    assert_identical([1.5, 0.001], Sift::decode('[1.5, 0.001]', ['strategy' => 'lazy']));
+});
+
+test('Decode - strict mode floats', function() {
+    assert_identical(['a' => 0.5], Sift::decode('{"a": 0.5}', ['strict' => true]));
+});
+
+test('Decode - comma is never a decimal separator', function() {
+    // "[1,5]" is a two-element array, and a bare "1,5" is invalid
+    assert_identical([1, 5], Sift::decode('[1,5]'));
+    $threw = false;
+    try {
+        Sift::decode('1,5');
+    } catch (Exception $e) {
+        $threw = true;
+    }
+    assert_true($threw, '"1,5" must not parse as a float');
+});
+
+test('Query - float and pointer get', function() {
+    $json = '{"price": 19.99, "items": [{"weight": 0.75}]}';
+    assert_identical(19.99, Sift::query($json)->get('price')->float());
+    assert_identical(0.75, Sift::get($json, '/items/0/weight'));
+});
+
+test('Snapshot - floats', function() {
+    assert_identical(0.125, Sift::snapshot('{"x": 0.125}')->get('/x'));
+});
+
+test('big_numbers - bcmath decimal keeps a dot', function() {
+    if (!class_exists('BCMath\Number')) {
+        return;
+    }
+    $data = Sift::decode('[0.1000000000000000000001]', ['big_numbers' => 'bcmath']);
+    assert_identical('0.1000000000000000000001', (string) $data[0]);
+});
+
+// ==================== Formatting ====================
+echo "\n--- Formatting ---\n";
+
+test('formatFloat - decimal point is always a dot', function() {
+    assert_identical('1.5', Sift::formatFloat(1.5));
+    assert_identical('-0.25', Sift::formatFloat(-0.25));
+});
+
+test('formatFloat - shortest round-trip digits', function() {
+    assert_identical('0.30000000000000004', Sift::formatFloat(0.1 + 0.2));
+    assert_identical(0.1 + 0.2, (float) Sift::formatFloat(0.1 + 0.2));
+});
+
+test('formatFloat - integral floats keep a fraction', function() {
+    assert_identical('1.0', Sift::formatFloat(1.0));
+    assert_identical('-0.0', Sift::formatFloat(-0.0));
+});
+
+test('formatFloat - auto notation', function() {
+    assert_identical('0.0001', Sift::formatFloat(0.0001));
+    assert_identical('1.0e-5', Sift::formatFloat(0.00001));
+    assert_identical('100000000000000.0', Sift::formatFloat(1e14));
+    assert_identical('1.0e+15', Sift::formatFloat(1e15));
+});
+
+test('formatFloat - decimal notation', function() {
+    assert_identical('10000000000000000000000000.0', Sift::formatFloat(1e25, 'decimal'));
+    assert_identical('0.0000001', Sift::formatFloat(1e-7, 'decimal'));
+});
+
+test('formatFloat - exponent notation', function() {
+    assert_identical('1.5e+3', Sift::formatFloat(1500.0, 'exponent'));
+    assert_identical('1.0e-7', Sift::formatFloat(1e-7, 'exponent'));
+});
+
+test('formatFloat - output decodes back to the same float', function() {
+    foreach ([1.5, 1e-7, 1e25, 0.1 + 0.2, -123456.789, 5e-324, PHP_FLOAT_MAX] as $n) {
+        foreach (['auto', 'decimal', 'exponent'] as $notation) {
+            assert_identical($n, Sift::decode(Sift::formatFloat($n, $notation)), "{$n} ({$notation})");
+        }
+    }
+});
+
+test('formatFloat - rejects NAN and INF', function() {
+    foreach ([NAN, INF, -INF] as $n) {
+        $threw = false;
+        try {
+            Sift::formatFloat($n);
+        } catch (Exception $e) {
+            $threw = true;
+        }
+        assert_true($threw);
+    }
+});
+
+test('formatFloat - unknown notation rejected', function() {
+    $threw = false;
+    try {
+        Sift::formatFloat(1.0, 'scientific');
+    } catch (Exception $e) {
+        $threw = str_contains($e->getMessage(), "'auto', 'decimal', 'exponent'");
+    }
+    assert_true($threw);
+});
+
+setlocale(LC_ALL, $originalLocale);
+
+// ==================== Summary ====================
+echo "\n=== Results ===\n";
+echo "Passed: {$passed}\n";
+echo "Failed: {$failed}\n";
+
+exit($failed > 0 ? 1 : 0);
@@ -534,6 +534,4089 @@ test('Large u64 numbers convert to float instead of overflowing', function() {
     assert_true($result['huge'] > 0, 'Large u64 should not overflow to negative');
 });
 
+// ==================== Sift::lastWarnings() ====================
+echo "\n--- Sift::lastWarnings() ---\n";
+
+test('Sift::lastWarnings - reports bigint coercion with pointer', function() {
+    Sift::decode('{"ids": [1, 18446744073709551615]}', ['warnings' => true]);
+    $warnings = Sift::lastWarnings();
+    assert_equals([['pointer' => '/ids/1', 'kind' => 'bigint_to_float', 'offset' => null]], $warnings);
+});
+
+test('Sift::lastWarnings - reports float precision loss', function() {
+    Sift::decode('{"amount": 0.123456789012345678901}', ['warnings' => true]);
+    assert_equals('float_precision', Sift::lastWarnings()[0]['kind']);
+});
+
+test('Sift::lastWarnings - escapes pointer segments', function() {
+    Sift::decode('{"a/b": -99999999999999999999}', ['warnings' => true]);
+    assert_equals('/a~1b', Sift::lastWarnings()[0]['pointer']);
+});
+
+test('Sift::lastWarnings - empty for lossless documents', function() use ($typesJson) {
+    Sift::decode($typesJson, ['warnings' => true]);
+    assert_equals([], Sift::lastWarnings());
+});
+
+test('Sift::lastWarnings - reset by decode without warnings', function() {
+    Sift::decode('[18446744073709551615]', ['warnings' => true]);
+    Sift::decode('[18446744073709551615]');
+    assert_equals([], Sift::lastWarnings());
+});
+
+test('Sift::lastWarnings - reset by a failing decode', function() {
+    Sift::decode('[18446744073709551615]', ['warnings' => true]);
+    assert_throws(fn() => Sift::decode('[18446744073709551615', ['warnings' => true]));
+    assert_equals([], Sift::lastWarnings());
+    Sift::decode('[18446744073709551615]', ['warnings' => true, 'strategy' => 'dom']);
+    assert_throws(fn() => Sift::decode('[1', ['strategy' => 'dom']));
+    assert_equals([], Sift::lastWarnings());
+});
+
+test('Sift::lastWarnings - reports text repairs at their byte offset', function() {
+    Sift::decode("[\"a\xff\", \"\\ud800\"]", ['warnings' => true, 'utf8' => 'substitute', 'surrogates' => 'replace']);
+    assert_equals([
+        ['pointer' => null, 'kind' => 'utf8_substituted', 'offset' => 3],
+        ['pointer' => null, 'kind' => 'surrogate_replaced', 'offset' => 10],
+    ], Sift::lastWarnings());
+});
+
+// ==================== Query::value() Depth Guard ====================
+echo "\n--- Query::value() Depth Guard ---\n";
+
+test('Query::value - depth guard applies to subtree hydration', function() {
+    $json = '{"deep": ' . str_repeat('[', 600) . str_repeat(']', 600) . '}';
+    assert_throws(function() use ($json) {
+        Sift::query($json)->get('deep')?->value();
+    }, 'depth');
+});
+
+test('Query::value - hydrates escaped strings in subtree', function() {
+    $value = Sift::query('{"a": {"s": "line\nbreak é"}}')->get('a')?->value();
+    assert_equals("line\nbreak é", $value['s']);
+});
+
+// ==================== Sift::decodeLazy() ====================
+echo "\n--- Sift::decodeLazy() ---\n";
+
+test('Sift::decodeLazy - matches decode', function() use ($typesJson, $nestedJson, $unicodeJson) {
+    foreach ([$typesJson, $nestedJson, $unicodeJson] as $json) {
+        assert_equals(Sift::decode($json), Sift::decodeLazy($json));
+    }
+});
+
+test('Sift::decodeLazy - scalar root', function() {
+    assert_equals(42, Sift::decodeLazy('42'));
+    assert_equals('hi', Sift::decodeLazy(' "hi" '));
+});
+
+test('Sift::decodeLazy - rejects trailing characters', function() {
+    assert_throws(function() {
+        Sift::decodeLazy('{"a": 1} x');
+    });
+});
+
+test('Sift::decodeLazy - accepts options', function() {
+    assert_true(Sift::decodeLazy('-0', ['strict' => true]) === 0);
+});
+
+test('Sift::get - empty pointer matches decode', function() use ($typesJson) {
+    assert_equals(Sift::decode($typesJson), Sift::get($typesJson, ''));
+});
+
+test('Sift::get - empty pointer rejects trailing characters', function() {
+    assert_throws(function() {
+        Sift::get('[1] [2]', '');
+    });
+});
+
+// ==================== presize option ====================
+echo "\n--- presize option ---\n";
+
+test('presize - same result as default', function() use ($typesJson, $nestedJson) {
+    foreach ([$typesJson, $nestedJson, '[]', '{}'] as $json) {
+        assert_equals(Sift::decodeLazy($json), Sift::decodeLazy($json, ['presize' => true]));
+    }
+});
+
+test('presize - large array', function() {
+    $json = json_encode(range(0, 99_999));
+    $result = Sift::decodeLazy($json, ['presize' => true]);
+    assert_equals(100_000, count($result));
+    assert_equals(99_999, $result[99_999]);
+});
+
+test('presize - combined with strict', function() {
+    $result = Sift::decode('{"1": -0, "b": [1, 2]}', ['strict' => true, 'presize' => true]);
+    assert_true($result === [1 => 0, 'b' => [1, 2]]);
+});
+
+test('presize - rejects non-boolean', function() {
+    assert_throws(function() {
+        Sift::decode('[]', ['presize' => 1]);
+    }, 'must be a boolean');
+});
+
+// ==================== String conversion ====================
+echo "\n--- String conversion ---\n";
+
+test('Strings - long escaped text round-trips', function() {
+    $text = str_repeat("line \"quoted\"\n\ttab \\ slash / \u{00e9}\u{1F600} ", 4096);
+    $json = json_encode(['body' => $text]);
+    assert_true(Sift::decode($json)['body'] === $text);
+    assert_true(Sift::decodeLazy($json)['body'] === $text);
+});
+
+test('Strings - empty and unescaped', function() {
+    assert_true(Sift::decodeLazy('["", "plain"]') === ['', 'plain']);
+});
+
+// ==================== Arena-backed paths ====================
+echo "\n--- Arena-backed paths ---\n";
+
+test('Arena - sibling pointers do not leak between branches', function() {
+    $json = '{"a~": {"x": [1, 18446744073709551615]}, "b": [{"c": 1e400}], "d": 18446744073709551616}';
+    Sift::decode($json, ['warnings' => true, 'strict' => true]);
+    $pointers = array_column(Sift::lastWarnings(), 'pointer');
+    assert_equals(['/a~0/x/1', '/b/0/c', '/d'], $pointers);
+});
+
+test('Arena - repeated calls reuse state', function() use ($nestedJson) {
+    for ($i = 0; $i < 1000; $i++) {
+        assert_equals('bob@example.com', Sift::get($nestedJson, '/users/1/email'));
+    }
+});
+
+test('Arena - escaped pointer segments', function() {
+    assert_equals(1, Sift::get('{"a/b": {"c~d": 1}}', '/a~1b/c~0d'));
+    assert_equals(1, Sift::query('{"a/b": {"c~d": 1}}')->pointer('/a~1b/c~0d')->int());
+});
+
+// ==================== strategy option ====================
+echo "\n--- strategy option ---\n";
+
+test('strategy - all strategies agree', function() use ($typesJson, $deepJson) {
+    $wide = json_encode(array_map(fn($i) => ['id' => $i, 'tags' => ['x', 'y']], range(1, 20_000)));
+    $deep = json_encode(array_fill(0, 500, json_decode(str_repeat('{"a":', 10) . '[1, "s"]' . str_repeat('}', 10), true)));
+    foreach ([$typesJson, $deepJson, $wide, $deep] as $json) {
+        $expected = json_decode($json, true);
+        foreach (['auto', 'dom', 'lazy'] as $strategy) {
+            assert_equals($expected, Sift::decode($json, ['strategy' => $strategy]), "strategy {$strategy}");
+        }
+    }
+});
+
+test('strategy - unknown value rejected', function() {
+    assert_throws(function() {
+        Sift::decode('[]', ['strategy' => 'fast']);
+    }, "must be one of 'auto', 'dom', 'lazy'");
+});
+
+test('strategy - strict overrides dom', function() {
+    assert_true(Sift::decode('-0', ['strict' => true, 'strategy' => 'dom']) === 0);
+});
+
+// ==================== Sift\Snapshot ====================
+echo "\n--- Sift\\Snapshot ---\n";
+
+test('Snapshot - toArray matches decode', function() use ($typesJson) {
+    assert_equals(Sift::decode($typesJson), Sift::snapshot($typesJson)->toArray());
+});
+
+test('Snapshot - get by pointer', function() use ($nestedJson) {
+    $snap = Sift::snapshot($nestedJson);
+    assert_equals('bob@example.com', $snap->get('/users/1/email'));
+    assert_equals(['id' => 1, 'email' => 'alice@example.com', 'active' => true], $snap->get('/users/0'));
+    assert_equals(Sift::decode($nestedJson), $snap->get(''));
+});
+
+test('Snapshot - get missing path throws', function() use ($nestedJson) {
+    assert_throws(function() use ($nestedJson) {
+        Sift::snapshot($nestedJson)->get('/users/5/email');
+    }, 'not found');
+});
+
+test('Snapshot - has', function() use ($escapeJson) {
+    $snap = Sift::snapshot('{"a/b": {"c~d": null}}');
+    assert_true($snap->has('/a~1b/c~0d'));
+    assert_false($snap->has('/a~1b/missing'));
+});
+
+test('Snapshot - invalid JSON throws', function() {
+    assert_throws(function() {
+        Sift::snapshot('{"broken": }');
+    });
+});
+
+test('Snapshot - persist, persisted and forget', function() use ($simpleJson) {
+    assert_true(Sift\Snapshot::persisted('test-config') === null);
+    $snap = Sift::snapshot($simpleJson)->persist('test-config');
+    assert_equals('sift', Sift\Snapshot::persisted('test-config')->get('/name'));
+    assert_true(Sift\Snapshot::forget('test-config'));
+    assert_false(Sift\Snapshot::forget('test-config'));
+    assert_true(Sift\Snapshot::persisted('test-config') === null);
+    // Handed-out snapshots outlive the registry entry
+    assert_equals('sift', $snap->get('/name'));
+});
+
+test('Snapshot - get and toArray take decode options', function() use ($nestedJson) {
+    $snap = Sift::snapshot($nestedJson);
+    $user = $snap->get('/users/0', ['assoc' => false]);
+    assert_true($user instanceof stdClass);
+    assert_equals('alice@example.com', $user->email);
+    assert_equals(Sift::decode($nestedJson, ['assoc' => false]), $snap->toArray(['assoc' => false]));
+    assert_throws(fn() => $snap->get('/users/0', ['strategy' => 'nope']));
+});
+
+test('Snapshot - options that need number text are rejected', function() {
+    $snap = Sift::snapshot('{"id": 12345678901234567890, "price": 1.10}');
+    foreach ([
+        ['bigint' => 'string'],
+        ['big_numbers' => 'bcmath'],
+        ['strict' => true],
+        ['warnings' => true],
+        ['numbers' => ['/price' => 'string']],
+    ] as $options) {
+        assert_throws(fn() => $snap->get('/price', $options), 'original number text');
+        assert_throws(fn() => $snap->toArray($options), 'original number text');
+    }
+    assert_equals(1.1, $snap->get('/price', ['assoc' => false]));
+});
+
+test('Snapshot - registry evicts the least recently used past 256', function() {
+    Sift\Snapshot::forgetAll();
+    $snap = Sift::snapshot('{"a": 1}');
+    for ($i = 0; $i < 256; $i++) {
+        $snap->persist("tenant-$i");
+    }
+    // Reading tenant-0 makes tenant-1 the least recently used
+    assert_true(Sift\Snapshot::persisted('tenant-0') !== null);
+    $snap->persist('tenant-256');
+    assert_true(Sift\Snapshot::persisted('tenant-0') !== null);
+    assert_true(Sift\Snapshot::persisted('tenant-1') === null);
+    assert_true(Sift\Snapshot::persisted('tenant-256') !== null);
+    assert_equals(256, Sift\Snapshot::forgetAll());
+    assert_equals(0, Sift\Snapshot::forgetAll());
+    assert_true(Sift\Snapshot::persisted('tenant-0') === null);
+});
+
+// ==================== big_numbers option ====================
+echo "\n--- big_numbers option ---\n";
+
+test('big_numbers - bcmath keeps integers and decimals exact', function() {
+    if (!class_exists('BCMath\Number')) {
+        return; // PHP < 8.4 or bcmath not loaded
+    }
+    $json = '{"big": 123456789012345678901234567890, "neg": -9223372036854775809, "dec": 0.1000000000000000000001, "exp": 1.2345678901234567891e3, "small": 1.5, "int": 7}';
+    $data = Sift::decode($json, ['big_numbers' => 'bcmath']);
+    assert_true($data['big'] instanceof BCMath\Number);
+    assert_equals('123456789012345678901234567890', (string) $data['big']);
+    assert_equals('-9223372036854775809', (string) $data['neg']);
+    assert_equals('0.1000000000000000000001', (string) $data['dec']);
+    assert_equals('1234.5678901234567891', (string) $data['exp']);
+    assert_true($data['small'] === 1.5);
+    assert_true($data['int'] === 7);
+});
+
+test('big_numbers - bcmath holds out-of-range literals', function() {
+    if (!class_exists('BCMath\Number')) {
+        return;
+    }
+    $data = Sift::decode('[1e400]', ['big_numbers' => 'bcmath']);
+    assert_equals('1' . str_repeat('0', 400), (string) $data[0]);
+});
+
+test('big_numbers - gmp for big integers only', function() {
+    if (!function_exists('gmp_init')) {
+        return; // gmp not loaded
+    }
+    $data = Sift::decode('[18446744073709551616, 0.1000000000000000000001, 5]', ['big_numbers' => 'gmp']);
+    assert_true($data[0] instanceof GMP);
+    assert_equals('18446744073709551616', gmp_strval($data[0]));
+    assert_true(is_float($data[1]));
+    assert_true($data[2] === 5);
+});
+
+test('big_numbers - bcmath numbers encode back as numbers', function() {
+    if (!class_exists('BCMath\Number')) {
+        return;
+    }
+    $json = '{"big":123456789012345678901234567890,"dec":0.1000000000000000000001,"int":7}';
+    $data = Sift::decode($json, ['big_numbers' => 'bcmath']);
+    assert_equals($json . "\n", Sift::encodeLines([$data])['lines']);
+    assert_equals($json, Sift::encodeOrdered($data, $json));
+});
+
+test('big_numbers - gmp numbers encode back as numbers', function() {
+    if (!function_exists('gmp_init')) {
+        return;
+    }
+    $json = '[18446744073709551616,-18446744073709551617]';
+    $data = Sift::decode($json, ['big_numbers' => 'gmp']);
+    assert_equals($json . "\n", Sift::encodeLines([$data])['lines']);
+});
+
+test('big_numbers - no warning for exact conversions', function() {
+    if (!function_exists('gmp_init')) {
+        return;
+    }
+    Sift::decode('[18446744073709551616]', ['big_numbers' => 'gmp', 'warnings' => true]);
+    assert_equals([], Sift::lastWarnings());
+});
+
+test('big_numbers - float is the default', function() {
+    $data = Sift::decode('[18446744073709551616]', ['big_numbers' => 'float']);
+    assert_true(is_float($data[0]));
+});
+
+test('big_numbers - unknown value rejected', function() {
+    assert_throws(function() {
+        Sift::decode('[]', ['big_numbers' => 'decimal']);
+    }, "must be one of 'float', 'bcmath', 'gmp'");
+});
+
+// ==================== Query::boolish() ====================
+echo "\n--- Query::boolish() ---\n";
+
+test('Query::boolish - default table', function() {
+    $json = '{"a": true, "b": false, "c": "true", "d": "FALSE", "e": "1", "f": 0, "g": " Yes ", "h": "off", "i": 1}';
+    $q = Sift::query($json);
+    $expected = ['a' => true, 'b' => false, 'c' => true, 'd' => false, 'e' => true, 'f' => false, 'g' => true, 'h' => false, 'i' => true];
+    foreach ($expected as $key => $value) {
+        assert_true($q->get($key)->boolish() === $value, "key {$key}");
+    }
+});
+
+test('Query::boolish - ambiguous values throw', function() {
+    $q = Sift::query('{"a": "maybe", "b": 2, "c": null, "d": "", "e": [], "f": 1.0}');
+    foreach (['a', 'b', 'c', 'd', 'e', 'f'] as $key) {
+        assert_throws(function() use ($q, $key) {
+            $q->get($key)->boolish();
+        }, 'Ambiguous boolean value');
+    }
+});
+
+test('Query::boolish - custom table', function() {
+    $q = Sift::query('{"on": "Enabled", "off": "disabled", "code": 2}');
+    assert_true($q->get('on')->boolish(['enabled'], ['disabled']));
+    assert_false($q->get('off')->boolish(['enabled'], ['disabled']));
+    assert_true($q->get('code')->boolish([2], [3]));
+    // Only the truthy side replaced; falsy keeps the defaults
+    assert_false(Sift::query('"no"')->boolish(['si']));
+});
+
+test('Query::boolish - conflicting table rejected', function() {
+    assert_throws(function() {
+        Sift::query('"x"')->boolish(['x'], ['X']);
+    }, 'both truthy and falsy');
+});
+
+test('Query::boolish - missing path throws', function() use ($simpleJson) {
+    assert_throws(function() use ($simpleJson) {
+        Sift::query($simpleJson)->get('missing')->boolish();
+    }, 'not found');
+});
+
+// ==================== Query format hydrators ====================
+echo "\n--- Query format hydrators ---\n";
+
+$formatsJson = '{"id": "123e4567-E89B-12d3-a456-426614174000", "email": "a.b+tag@mail.example.com", '
+    . '"url": "https://example.com:8443/p?q=1#f", "v4": "192.168.0.1", "v6": "2001:db8::1", '
+    . '"users": [{"contact/email": "nope@", "n": 5}]}';
+
+test('Query::uuid/email/url/ip - valid values returned unchanged', function() use ($formatsJson) {
+    $q = Sift::query($formatsJson);
+    assert_equals('123e4567-E89B-12d3-a456-426614174000', $q->get('id')->uuid());
+    assert_equals('a.b+tag@mail.example.com', $q->get('email')->email());
+    assert_equals('https://example.com:8443/p?q=1#f', $q->get('url')->url());
+    assert_equals('http://[::1]:80/', Sift::query('"http://[::1]:80/"')->url());
+    assert_equals('192.168.0.1', $q->get('v4')->ip());
+    assert_equals('192.168.0.1', $q->get('v4')->ip(4));
+    assert_equals('2001:db8::1', $q->get('v6')->ip(6));
+});
+
+test('Query::uuid/email/url/ip - invalid values throw FormatException', function() {
+    $cases = [
+        ['"123e4567e89b12d3a456426614174000"', 'uuid'],
+        ['"123e4567-e89b-12d3-a456-42661417400g"', 'uuid'],
+        ['"user@localhost"', 'email'],
+        ['"a..b@example.com"', 'email'],
+        ['"example.com/path"', 'url'],
+        ['"https://exa mple.com"', 'url'],
+        ['"https://example.com:99999"', 'url'],
+        ['"256.1.1.1"', 'ip'],
+    ];
+    foreach ($cases as [$json, $method]) {
+        $thrown = null;
+        try {
+            Sift::query($json)->$method();
+        } catch (\Exception $e) {
+            $thrown = $e;
+        }
+        assert_true($thrown instanceof Sift\FormatException, "{$method} {$json}");
+    }
+});
+
+test('Query::ip - version filter', function() use ($formatsJson) {
+    $q = Sift::query($formatsJson);
+    assert_throws(function() use ($q) { $q->get('v6')->ip(4); }, 'not a valid IPv4 address');
+    assert_throws(function() use ($q) { $q->get('v4')->ip(6); }, 'not a valid IPv6 address');
+    assert_throws(function() use ($q) { $q->get('v4')->ip(5); }, 'must be 4 or 6');
+});
+
+test('Query format hydrators - message names the pointer', function() use ($formatsJson) {
+    assert_throws(function() use ($formatsJson) {
+        Sift::query($formatsJson)->get('users')->index(0)->get('contact/email')->email();
+    }, "Value at '/users/0/contact~1email' is not a valid email address");
+});
+
+test('Query format hydrators - non-string and missing values', function() use ($formatsJson) {
+    $q = Sift::query($formatsJson);
+    assert_throws(function() use ($q) { $q->pointer('/users/0/n')->uuid(); }, 'not a string');
+    assert_throws(function() use ($q) { $q->get('missing')->url(); }, 'not found');
+});
+
+// ==================== Query::base64() ====================
+echo "\n--- Query::base64() ---\n";
+
+test('Query::base64 - standard alphabet', function() {
+    $bytes = "\x00\xfb\xff\x10binary";
+    $q = Sift::query(json_encode(['blob' => base64_encode($bytes), 'short' => 'QQ==', 'bare' => 'QQ', 'empty' => '']));
+    assert_equals($bytes, $q->get('blob')->base64());
+    assert_equals('A', $q->get('short')->base64());
+    assert_equals('A', $q->get('bare')->base64());
+    assert_equals('', $q->get('empty')->base64());
+});
+
+test('Query::base64 - url alphabet', function() {
+    $bytes = "\xfb\xff\xbf";
+    $encoded = rtrim(strtr(base64_encode($bytes), '+/', '-_'), '=');
+    assert_equals('-_-_', $encoded);
+    assert_equals($bytes, Sift::query(json_encode($encoded))->base64('url'));
+    assert_throws(function() use ($encoded) {
+        Sift::query(json_encode($encoded))->base64();
+    }, 'invalid character at offset 0');
+    assert_throws(function() {
+        Sift::query('"+/+/"')->base64('url');
+    }, 'invalid character');
+});
+
+test('Query::base64 - invalid padding throws FormatException', function() {
+    foreach (['"QQ="', '"QQ==="', '"Q"', '"QQ=A"', '"QR=="', '"===="'] as $json) {
+        $thrown = null;
+        try {
+            Sift::query($json)->base64();
+        } catch (\Exception $e) {
+            $thrown = $e;
+        }
+        assert_true($thrown instanceof Sift\FormatException, $json);
+        assert_true(str_contains($thrown->getMessage(), 'invalid padding'), $json);
+    }
+});
+
+test('Query::base64 - message names the pointer', function() {
+    assert_throws(function() {
+        Sift::query('{"files": [{"data": "not base64!"}]}')->pointer('/files/0/data')->base64();
+    }, "Value at '/files/0/data' is not valid base64");
+});
+
+test('Query::base64 - bad alphabet and non-string', function() {
+    assert_throws(function() {
+        Sift::query('"QQ=="')->base64('mime');
+    }, "must be one of 'standard', 'url'");
+    assert_throws(function() {
+        Sift::query('{"n": 1}')->get('n')->base64();
+    }, 'not a string');
+});
+
+// ==================== Query::jsonString() ====================
+echo "\n--- Query::jsonString() ---\n";
+
+$embeddedJson = json_encode([
+    'event' => 'order.created',
+    'payload' => json_encode(['order' => ['id' => 42, 'items' => [['sku' => 'a/b']]], 'note' => "line\n\"quoted\""]),
+    'count' => 3,
+]);
+
+test('Query::jsonString - navigates embedded document', function() use ($embeddedJson) {
+    $inner = Sift::query($embeddedJson)->get('payload')->jsonString();
+    assert_equals(42, $inner->pointer('/order/id')->int());
+    assert_equals('a/b', $inner->get('order')->get('items')->index(0)->get('sku')->string());
+    assert_equals("line\n\"quoted\"", $inner->get('note')->string());
+    assert_true($inner->isObject());
+});
+
+test('Query::jsonString - value() matches json_decode of the string', function() use ($embeddedJson) {
+    $payload = json_decode($embeddedJson, true)['payload'];
+    assert_equals(json_decode($payload, true), Sift::query($embeddedJson)->get('payload')->jsonString()->value());
+});
+
+test('Query::jsonString - nested double encoding', function() {
+    $json = json_encode(['a' => json_encode(['b' => json_encode(['c' => true])])]);
+    assert_true(Sift::query($json)->get('a')->jsonString()->get('b')->jsonString()->get('c')->bool());
+});
+
+test('Query::jsonString - errors', function() use ($embeddedJson) {
+    assert_throws(function() use ($embeddedJson) {
+        Sift::query($embeddedJson)->get('count')->jsonString();
+    }, 'not a string');
+    assert_throws(function() use ($embeddedJson) {
+        Sift::query($embeddedJson)->get('missing')->jsonString();
+    }, 'not found');
+    // Invalid embedded JSON surfaces on hydration
+    $q = Sift::query($embeddedJson)->get('event')->jsonString();
+    assert_throws(function() use ($q) {
+        $q->value();
+    });
+});
+
+// ==================== Sift::firstOf() ====================
+echo "\n--- Sift::firstOf() ---\n";
+
+test('Sift::firstOf - returns first existing candidate', function() {
+    $v1 = '{"items": [1, 2], "meta": {"v": 1}}';
+    $v2 = '{"data": {"items": [3, 4]}, "items": "legacy"}';
+    assert_equals([1, 2], Sift::firstOf($v1, ['/data/items', '/items']));
+    assert_equals([3, 4], Sift::firstOf($v2, ['/data/items', '/items']));
+});
+
+test('Sift::firstOf - candidate order wins over document order', function() {
+    $json = '{"a": 1, "b": {"c": 2}}';
+    assert_equals(2, Sift::firstOf($json, ['/b/c', '/a']));
+    assert_equals(1, Sift::firstOf($json, ['/a', '/b/c']));
+});
+
+test('Sift::firstOf - matches sequential get()', function() use ($nestedJson) {
+    $candidates = [
+        ['/nope', '/users/1/email', '/users'],
+        ['/users/9/email', '/users/0/active'],
+        ['/users/0', '/users/0/email'],
+        ['/users/0/missing', ''],
+    ];
+    foreach ($candidates as $pointers) {
+        $expected = null;
+        foreach ($pointers as $pointer) {
+            try {
+                $expected = Sift::get($nestedJson, $pointer);
+                break;
+            } catch (\Exception $e) {
+            }
+        }
+        assert_equals($expected, Sift::firstOf($nestedJson, $pointers), implode(',', $pointers));
+    }
+});
+
+test('Sift::firstOf - arrays, escapes and null values', function() {
+    $json = '{"a/b": {"~": null}, "list": [{"id": 7}, {"id": 8}]}';
+    assert_true(Sift::firstOf($json, ['/a~1b/~0']) === null);
+    assert_equals(8, Sift::firstOf($json, ['/list/5/id', '/list/1/id', '/list/0/id']));
+});
+
+test('Sift::firstOf - empty pointer is the whole document', function() {
+    assert_equals(['x' => 1], Sift::firstOf('{"x": 1}', ['/y', '']));
+    assert_equals(1, Sift::firstOf('{"x": 1}', ['/x', '']));
+});
+
+test('Sift::firstOf - errors', function() {
+    assert_throws(function() {
+        Sift::firstOf('{"a": 1}', ['/b', '/c']);
+    }, 'None of the pointers were found');
+    assert_throws(function() {
+        Sift::firstOf('{"a": 1}', []);
+    }, 'None of the pointers were found');
+    assert_throws(function() {
+        Sift::firstOf('{"a": 1}', ['a']);
+    }, "must start with '/'");
+    assert_throws(function() {
+        Sift::firstOf('{"a": [1, }', ['/b']);
+    });
+});
+
+// ==================== Error Codes Tests ====================
+
+function catch_sift(callable $fn): \Sift\Exception {
+    try {
+        $fn();
+    } catch (\Sift\Exception $e) {
+        return $e;
+    }
+    throw new \RuntimeException('Expected Sift\Exception');
+}
+
+test('Sift\Exception - missing path has code and pointer', function() use ($nestedJson) {
+    $e = catch_sift(fn() => Sift::query($nestedJson)->pointer('/users/9/email')->string());
+    assert_true($e instanceof \Exception);
+    assert_equals(\Sift\Exception::KEY_NOT_FOUND, $e->getErrorCode());
+    assert_equals($e->getErrorCode(), $e->getCode());
+    assert_equals('/users/9/email', $e->getPointer());
+    assert_true($e->getOffset() === null);
+
+    $e = catch_sift(fn() => Sift::get($nestedJson, '/missing'));
+    assert_equals(\Sift\Exception::KEY_NOT_FOUND, $e->getErrorCode());
+    assert_equals('/missing', $e->getPointer());
+});
+
+test('Sift\Exception - type errors report expected and actual types', function() use ($nestedJson) {
+    $e = catch_sift(fn() => Sift::query($nestedJson)->pointer('/users/0/id')->string());
+    assert_equals(\Sift\Exception::TYPE_ERROR, $e->getErrorCode());
+    assert_equals('/users/0/id', $e->getPointer());
+    assert_equals('string', $e->getExpectedType());
+    assert_equals('integer', $e->getActualType());
+
+    $e = catch_sift(fn() => Sift::query($nestedJson)->pointer('/users/0/email')->bool());
+    assert_equals('boolean', $e->getExpectedType());
+    assert_equals('string', $e->getActualType());
+});
+
+test('Sift\Exception - parse errors report the offset', function() {
+    $e = catch_sift(fn() => Sift::decode('{"a": [1, }'));
+    assert_equals(\Sift\Exception::PARSE_ERROR, $e->getErrorCode());
+    assert_true(is_int($e->getOffset()));
+    assert_true($e->getPointer() === null);
+});
+
+test('Sift\Exception - other codes', function() {
+    $e = catch_sift(fn() => Sift::query('{"a": 1}')->pointer('a'));
+    assert_equals(\Sift\Exception::INVALID_POINTER, $e->getErrorCode());
+
+    $e = catch_sift(fn() => Sift::decode('{}', ['nope' => true]));
+    assert_equals(\Sift\Exception::INVALID_OPTION, $e->getErrorCode());
+
+    $e = catch_sift(fn() => Sift::query('{"id": "nope"}')->get('id')->uuid());
+    assert_true($e instanceof \Sift\FormatException);
+    assert_equals(\Sift\Exception::FORMAT_ERROR, $e->getErrorCode());
+    assert_equals('/id', $e->getPointer());
+});
+
+test('Sift\Exception - file and line point at the caller', function() {
+    $e = catch_sift(fn() => Sift::get('{}', '/x'));
+    assert_equals(__FILE__, $e->getFile());
+    assert_true($e->getLine() > 0);
+});
+
+test('Sift\Exception - strict mode still throws JsonException', function() {
+    try {
+        Sift::decode('{"a": }', ['strict' => true]);
+        throw new \RuntimeException('Expected JsonException');
+    } catch (\JsonException $e) {
+        assert_false($e instanceof \Sift\Exception);
+        assert_equals(JSON_ERROR_SYNTAX, $e->getCode());
+    }
+});
+
+// ==================== Input Size Limit Tests ====================
+
+test('Sift\InputTooLargeException - thrown by every entry point', function() {
+    $limit = 64 * 1024 * 1024;
+    $json = '"' . str_repeat('a', $limit - 1) . '"';
+    $calls = [
+        'decode' => fn() => Sift::decode($json),
+        'decodeLazy' => fn() => Sift::decodeLazy($json),
+        'get' => fn() => Sift::get($json, '/a'),
+        'firstOf' => fn() => Sift::firstOf($json, ['/a']),
+        'isValidStrict' => fn() => Sift::isValidStrict($json),
+        'snapshot' => fn() => Sift::snapshot($json),
+        'query' => fn() => Sift::query($json)->get('a')->string(),
+    ];
+    foreach ($calls as $name => $call) {
+        try {
+            $call();
+            throw new \RuntimeException("Expected InputTooLargeException from {$name}");
+        } catch (\Sift\InputTooLargeException $e) {
+            assert_true($e instanceof \Sift\Exception, $name);
+            assert_equals(\Sift\Exception::INPUT_TOO_LARGE, $e->getErrorCode(), $name);
+            assert_equals($limit + 1, $e->getSize(), $name);
+            assert_equals($limit, $e->getLimit(), $name);
+        }
+    }
+});
+
+test('Sift::isValidStrict - limits throw, syntax errors return false', function() {
+    $json = str_repeat(' ', 64 * 1024 * 1024) . '1';
+    assert_false(Sift::isValid($json));
+    assert_false(Sonic::isValid($json));
+    assert_throws(fn() => Sift::isValidStrict($json), 'exceeds maximum allowed');
+
+    assert_true(Sift::isValidStrict('{"a": [1, 2]}'));
+    assert_false(Sift::isValidStrict('{"a": [1, }'));
+    assert_false(Sift::isValidStrict(''));
+});
+
+test('Sift\InputTooLargeException - not thrown for malformed JSON', function() {
+    assert_false(Sift::isValid('{"a": }'));
+    try {
+        Sift::decode('{"a": }');
+        throw new \RuntimeException('Expected Sift\Exception');
+    } catch (\Sift\Exception $e) {
+        assert_false($e instanceof \Sift\InputTooLargeException);
+        assert_equals(\Sift\Exception::PARSE_ERROR, $e->getErrorCode());
+    }
+});
+
+// ==================== Sift::configure() Tests ====================
+
+test('Sift::configure - defaults apply to later calls', function() {
+    $json = '{"n": 12345678901234567890}';
+    try {
+        Sift::configure(['warnings' => true]);
+        Sift::decode($json);
+        assert_equals(1, count(Sift::lastWarnings()));
+        Sift::get($json, '/n');
+        assert_equals(1, count(Sift::lastWarnings()));
+        Sift::query($json)->get('n')->value();
+        assert_equals(1, count(Sift::lastWarnings()));
+    } finally {
+        Sift::configure([]);
+    }
+});
+
+test('Sift::configure - per-call options override defaults', function() {
+    try {
+        Sift::configure(['strict' => true]);
+        assert_throws(fn() => Sift::decode('{"a": }'));
+        try {
+            Sift::decode('{"a": }');
+        } catch (\JsonException $e) {
+            assert_equals(JSON_ERROR_SYNTAX, $e->getCode());
+        }
+        try {
+            Sift::decode('{"a": }', ['strict' => false]);
+        } catch (\Sift\Exception $e) {
+            assert_equals(\Sift\Exception::PARSE_ERROR, $e->getErrorCode());
+        }
+    } finally {
+        Sift::configure([]);
+    }
+});
+
+test('Sift::configure - accepts max_depth as depth', function() {
+    $deep = str_repeat('[', 1000) . str_repeat(']', 1000);
+    try {
+        Sift::configure(["assoc" => true, "bigint" => "string", "max_depth" => 1024]);
+        assert_true(is_array(Sift::decode($deep)));
+        assert_equals('12345678901234567890', Sift::get('{"id": 12345678901234567890}', '/id'));
+        assert_throws(fn() => Sift::decode($deep, ['max_depth' => 999]), 'Maximum nesting depth (999) exceeded');
+        assert_throws(fn() => Sift::configure(['max_depth' => 0]), "Option 'max_depth' must be an integer");
+    } finally {
+        Sift::configure([]);
+    }
+});
+
+test('Sift::configure - each call replaces the previous one', function() {
+    $json = '{"n": 12345678901234567890}';
+    Sift::configure(['warnings' => true]);
+    Sift::configure(['presize' => true]);
+    Sift::decode($json);
+    assert_equals([], Sift::lastWarnings());
+    Sift::configure([]);
+});
+
+test('Sift::configure - invalid options are rejected and change nothing', function() {
+    $json = '{"n": 12345678901234567890}';
+    Sift::configure(['warnings' => true]);
+    assert_throws(fn() => Sift::configure(['warnings' => false, 'nope' => 1]), "Unknown option 'nope'");
+    assert_throws(fn() => Sift::configure(['strategy' => 'fast']), "must be one of");
+    Sift::decode($json);
+    assert_equals(1, count(Sift::lastWarnings()));
+    Sift::configure([]);
+});
+
+// ==================== Per-call Options Tests ====================
+
+test('Options - assoc false on decode, get and query', function() use ($nestedJson) {
+    $data = Sift::decode($nestedJson, ['assoc' => false]);
+    assert_true($data instanceof \stdClass);
+    assert_true(is_array($data->users));
+    assert_equals($data->users[0]->email, Sift::get($nestedJson, '/users/0', ['assoc' => false])->email);
+    $user = Sift::query($nestedJson, ['assoc' => false])->get('users')->index(0)->value();
+    assert_true($user instanceof \stdClass);
+    assert_true(is_array(Sift::query($nestedJson)->pointer('/users/0')->value()));
+});
+
+test('Options - bigint as string', function() {
+    $json = '{"id": 12345678901234567890, "small": 42}';
+    assert_equals(['id' => '12345678901234567890', 'small' => 42], Sift::decode($json, ['bigint' => 'string']));
+    assert_equals('12345678901234567890', Sift::get($json, '/id', ['bigint' => 'string']));
+    assert_true(is_float(Sift::get($json, '/id')));
+});
+
+test('Options - depth', function() {
+    $json = '{"a": {"b": {"c": 1}}}';
+    assert_throws(fn() => Sift::decode($json, ['depth' => 2]), 'Maximum nesting depth (2) exceeded');
+    assert_equals(['b' => ['c' => 1]], Sift::get($json, '/a', ['depth' => 2]));
+    $deep = str_repeat('[', 1000) . str_repeat(']', 1000);
+    assert_throws(fn() => Sift::decode($deep));
+    assert_true(is_array(Sift::decode($deep, ['depth' => 1024])));
+});
+
+test('Options - duplicate keys', function() {
+    $json = '{"a": 1, "b": 2, "a": 3}';
+    assert_equals(['a' => 3, 'b' => 2], Sift::decode($json));
+    assert_equals(['a' => 1, 'b' => 2], Sift::decode($json, ['duplicate_keys' => 'first']));
+    assert_equals(1, Sift::decode($json, ['duplicate_keys' => 'first', 'assoc' => false])->a);
+    assert_throws(fn() => Sift::decode($json, ['duplicate_keys' => 'error']), 'Duplicate object key');
+    assert_throws(fn() => Sift::query($json, ['duplicate_keys' => 'error'])->value(), 'Duplicate object key');
+});
+
+test('Options - malformed UTF-8', function() {
+    $json = "{\"name\": \"caf\xe9\"}";
+    try {
+        Sift::decode($json);
+        throw new \RuntimeException('Expected Sift\Exception');
+    } catch (\Sift\Exception $e) {
+        assert_equals(\Sift\Exception::PARSE_ERROR, $e->getErrorCode());
+        assert_equals(13, $e->getOffset());
+    }
+    assert_equals(['name' => 'caf'], Sift::decode($json, ['utf8' => 'ignore']));
+    assert_equals("caf\u{FFFD}", Sift::get($json, '/name', ['utf8' => 'substitute']));
+    assert_equals("caf\u{FFFD}", Sift::query($json, ['utf8' => 'substitute'])->get('name')->string());
+});
+
+test('Options - invalid values are rejected', function() {
+    assert_throws(fn() => Sift::decode('1', ['assoc' => 'no']), "Option 'assoc' must be a boolean");
+    assert_throws(fn() => Sift::decode('1', ['bigint' => 'int']), "Option 'bigint' must be one of");
+    assert_throws(fn() => Sift::decode('1', ['depth' => 0]), "Option 'depth' must be an integer between 1 and 2048");
+    assert_throws(fn() => Sift::decode('1', ['depth' => 4096]), 'between 1 and 2048');
+    assert_throws(fn() => Sift::decode('1', ['utf8' => 'strip']), "Option 'utf8' must be one of");
+    assert_throws(fn() => Sift::decode('1', ['duplicate_keys' => 'merge']), "Option 'duplicate_keys' must be one of");
+    assert_throws(fn() => Sift::get('1', '', ['nope' => true]), "Unknown option 'nope'");
+    assert_throws(fn() => Sift::query('1', ['nope' => true]), "Unknown option 'nope'");
+});
+
+// ==================== Sift::keysAt() Tests ====================
+
+test('Sift::keysAt - object keys in document order', function() use ($nestedJson) {
+    assert_equals(['users'], Sift::keysAt($nestedJson, ''));
+    assert_equals(['id', 'email', 'active'], Sift::keysAt($nestedJson, '/users/0'));
+    assert_equals(['z', 'a~b', '', '0'], Sift::keysAt('{"z": {"deep": [1]}, "a~b": 1, "": 2, "0": 3}', ''));
+    assert_equals([], Sift::keysAt('{"o": {}}', '/o'));
+});
+
+test('Sift::keysAt - array indices', function() {
+    assert_equals([0, 1, 2], Sift::keysAt('{"list": [{"a": 1}, [2], "x"]}', '/list'));
+    assert_equals([], Sift::keysAt('[]', ''));
+});
+
+test('Sift::keysAt - errors', function() {
+    try {
+        Sift::keysAt('{"a": "text"}', '/a');
+        throw new \RuntimeException('Expected Sift\Exception');
+    } catch (\Sift\Exception $e) {
+        assert_equals(\Sift\Exception::TYPE_ERROR, $e->getErrorCode());
+        assert_equals('/a', $e->getPointer());
+        assert_equals('string', $e->getActualType());
+    }
+    assert_throws(fn() => Sift::keysAt('{"a": {}}', '/b'), 'Path not found');
+    assert_throws(fn() => Sift::keysAt('{"a": {"b": 1, }}', '/a'));
+});
+
+// ==================== Sift::countAt() Tests ====================
+
+test('Sift::countAt - arrays and objects', function() use ($nestedJson) {
+    assert_equals(count(json_decode($nestedJson, true)['users']), Sift::countAt($nestedJson, '/users'));
+    assert_equals(3, Sift::countAt($nestedJson, '/users/0'));
+    assert_equals(0, Sift::countAt('{"a": [], "b": {}}', '/a'));
+    assert_equals(0, Sift::countAt('{"a": [], "b": {}}', '/b'));
+    assert_equals(4, Sift::countAt('[[1, 2], {"x": [3]}, "s", null]', ''));
+});
+
+test('Sift::countAt - large array', function() {
+    $json = json_encode(['items' => range(1, 10000)]);
+    assert_equals(10000, Sift::countAt($json, '/items'));
+});
+
+test('Sift::countAt - errors', function() {
+    try {
+        Sift::countAt('{"n": 5}', '/n');
+        throw new \RuntimeException('Expected Sift\Exception');
+    } catch (\Sift\Exception $e) {
+        assert_equals(\Sift\Exception::TYPE_ERROR, $e->getErrorCode());
+        assert_equals('integer', $e->getActualType());
+    }
+    assert_throws(fn() => Sift::countAt('{"a": []}', '/b'), 'Path not found');
+    assert_throws(fn() => Sift::countAt('{"a": [1, 2,, 3]}', '/a'));
+});
+
+// ==================== Query::chunks() Tests ====================
+
+test('Query::chunks - yields lists of up to N elements', function() {
+    $json = json_encode(['rows' => range(1, 7)]);
+    $chunks = iterator_to_array(Sift::query($json)->get('rows')->chunks(3));
+    assert_equals([[1, 2, 3], [4, 5, 6], [7]], $chunks);
+    assert_equals([range(1, 7)], iterator_to_array(Sift::query($json)->get('rows')->chunks(100)));
+    assert_equals([], iterator_to_array(Sift::query('{"rows": [ ]}')->get('rows')->chunks(2)));
+});
+
+test('Query::chunks - hydrates elements like value()', function() use ($nestedJson) {
+    $users = json_decode($nestedJson, true)['users'];
+    $rows = [];
+    foreach (Sift::query($nestedJson)->get('users')->chunks(2) as $i => $chunk) {
+        assert_true(count($chunk) <= 2);
+        $rows = array_merge($rows, $chunk);
+    }
+    assert_equals($users, $rows);
+
+    $json = "[ {\"a\": [1, {\"b\": null}]} ,\n\"x\" , 1.5,true ]";
+    assert_equals([[['a' => [1, ['b' => null]]], 'x'], [1.5, true]], iterator_to_array(Sift::query($json)->chunks(2)));
+    $objects = iterator_to_array(Sift::query('[{"a": 1}]', ['assoc' => false])->chunks(1));
+    assert_true($objects[0][0] instanceof \stdClass);
+});
+
+test('Query::chunks - rewind restarts', function() {
+    $chunks = Sift::query('[1, 2, 3]')->chunks(2);
+    assert_equals([[1, 2], [3]], iterator_to_array($chunks));
+    assert_equals([[1, 2], [3]], iterator_to_array($chunks));
+    $chunks->rewind();
+    assert_equals(0, $chunks->key());
+    $chunks->next();
+    $chunks->next();
+    assert_false($chunks->valid());
+    assert_equals(null, $chunks->key());
+});
+
+test('Query::chunks - errors', function() {
+    assert_throws(fn() => Sift::query('[1]')->chunks(0), 'at least 1');
+    assert_throws(fn() => iterator_to_array(Sift::query('{"a": 1}')->get('a')->chunks(2)), 'not an array');
+    assert_throws(fn() => iterator_to_array(Sift::query('{"a": []}')->get('b')->chunks(2)), 'Path not found');
+
+    $chunks = Sift::query('[1, 2, {"x": }, 4]')->chunks(1);
+    assert_throws(fn() => iterator_to_array($chunks));
+});
+
+test('Query::chunks - resumes at tell()', function() {
+    $json = '{"rows": [1, 2, {"a": [3]}, 4, 5], "n": 5}';
+    $chunks = Sift::query($json)->get('rows')->chunks(2);
+    $chunks->rewind();
+    assert_equals([1, 2], $chunks->current());
+    $offset = $chunks->tell();
+    assert_equals(strpos($json, ', {'), $offset);
+
+    $resumed = Sift::query($json)->get('rows')->chunks(2, $offset);
+    assert_equals([[['a' => [3]], 4], [5]], iterator_to_array($resumed));
+    $end = $resumed->tell();
+    assert_equals(']', $json[$end]);
+    assert_equals([], iterator_to_array(Sift::query($json)->get('rows')->chunks(2, $end)));
+});
+
+test('Query::chunks - rejects offsets outside the array', function() {
+    $json = '{"rows": [1, 2], "n": 2}';
+    assert_throws(fn() => Sift::query($json)->get('rows')->chunks(1, -1));
+    assert_throws(fn() => iterator_to_array(Sift::query($json)->get('rows')->chunks(1, 2)));
+    assert_throws(fn() => iterator_to_array(Sift::query($json)->get('rows')->chunks(1, 20)));
+});
+
+// ==================== Query::sample() Tests ====================
+
+test('Query::sample - returns N distinct elements in document order', function() {
+    $json = json_encode(['rows' => range(1, 1000)]);
+    $sample = Sift::query($json)->get('rows')->sample(10);
+    assert_equals(10, count($sample));
+    assert_equals(10, count(array_unique($sample)));
+    $sorted = $sample;
+    sort($sorted);
+    assert_equals($sorted, $sample);
+    foreach ($sample as $v) {
+        assert_true($v >= 1 && $v <= 1000);
+    }
+});
+
+test('Query::sample - seed makes the sample reproducible', function() {
+    $json = json_encode(range(1, 500));
+    $q = Sift::query($json);
+    assert_equals($q->sample(5, 42), $q->sample(5, 42));
+    assert_equals($q->sample(5, seed: 7), $q->sample(5, seed: 7));
+});
+
+test('Query::sample - short arrays are returned whole', function() use ($nestedJson) {
+    $users = json_decode($nestedJson, true)['users'];
+    assert_equals($users, Sift::query($nestedJson)->get('users')->sample(100));
+    assert_equals([], Sift::query('[]')->sample(3));
+});
+
+test('Query::sample - roughly uniform', function() {
+    $json = json_encode(range(0, 9));
+    $hits = array_fill(0, 10, 0);
+    for ($seed = 0; $seed < 2000; $seed++) {
+        foreach (Sift::query($json)->sample(1, $seed) as $v) {
+            $hits[$v]++;
+        }
+    }
+    foreach ($hits as $count) {
+        assert_true($count > 120 && $count < 280, "Skewed sample: " . json_encode($hits));
+    }
+});
+
+test('Query::sample - errors', function() {
+    assert_throws(fn() => Sift::query('[1]')->sample(0), 'at least 1');
+    assert_throws(fn() => Sift::query('{"a": "x"}')->get('a')->sample(1), 'not an array');
+    assert_throws(fn() => Sift::query('{"a": []}')->get('b')->sample(1), 'Path not found');
+});
+
+// ==================== Query::columns() Tests ====================
+
+test('Query::columns - parallel lists per key', function() use ($nestedJson) {
+    $users = json_decode($nestedJson, true)['users'];
+    $cols = Sift::query($nestedJson)->get('users')->columns(['email', 'id']);
+    assert_equals(['email', 'id'], array_keys($cols));
+    assert_equals(array_column($users, 'email'), $cols['email']);
+    assert_equals(array_column($users, 'id'), $cols['id']);
+});
+
+test('Query::columns - missing keys become null', function() {
+    $json = '{"orders": [{"id": 1, "price": 9.5}, {"id": 2}, {"price": {"amount": 3}, "id": 3}]}';
+    $cols = Sift::query($json)->get('orders')->columns(['id', 'price', 'absent', 'id']);
+    assert_equals([
+        'id' => [1, 2, 3],
+        'price' => [9.5, null, ['amount' => 3]],
+        'absent' => [null, null, null],
+    ], $cols);
+    assert_equals(['id' => []], Sift::query('[]')->columns(['id']));
+});
+
+test('Query::columns - duplicate keys follow the duplicate_keys option', function() {
+    $json = '[{"a": 1, "a": 2}]';
+    assert_equals(['a' => [2]], Sift::query($json)->columns(['a']));
+    assert_equals(['a' => [1]], Sift::query($json, ['duplicate_keys' => 'first'])->columns(['a']));
+    assert_throws(fn() => Sift::query($json, ['duplicate_keys' => 'error'])->columns(['a']), 'Duplicate');
+});
+
+test('Query::columns - errors', function() {
+    try {
+        Sift::query('{"rows": [{"id": 1}, 5]}')->get('rows')->columns(['id']);
+        throw new \RuntimeException('Expected Sift\Exception');
+    } catch (\Sift\Exception $e) {
+        assert_equals(\Sift\Exception::TYPE_ERROR, $e->getErrorCode());
+        assert_equals('/rows/1', $e->getPointer());
+        assert_equals('integer', $e->getActualType());
+    }
+    assert_throws(fn() => Sift::query('{"a": {}}')->get('a')->columns(['id']), 'not an array');
+});
+
+// ==================== Query::floatMatrix() Tests ====================
+
+test('Query::floatMatrix - packs rows of doubles', function() {
+    $json = '{"embeddings": [[0.5, -1.25, 3], [1e2, 0, 2.5e-3]]}';
+    $m = Sift::query($json)->get('embeddings')->floatMatrix();
+    assert_equals(2, $m['rows']);
+    assert_equals(3, $m['cols']);
+    assert_equals(6 * 8, strlen($m['data']));
+    assert_equals([0.5, -1.25, 3.0, 100.0, 0.0, 0.0025], array_values(unpack('d*', $m['data'])));
+});
+
+test('Query::floatMatrix - empty matrices', function() {
+    assert_equals(['data' => '', 'rows' => 0, 'cols' => 0], Sift::query('[]')->floatMatrix());
+    assert_equals(['data' => '', 'rows' => 2, 'cols' => 0], Sift::query('[[], []]')->floatMatrix());
+});
+
+test('Query::floatMatrix - errors', function() {
+    try {
+        Sift::query('{"m": [[1, 2], [3, "x"]]}')->get('m')->floatMatrix();
+        throw new \RuntimeException('Expected Sift\Exception');
+    } catch (\Sift\Exception $e) {
+        assert_equals(\Sift\Exception::TYPE_ERROR, $e->getErrorCode());
+        assert_equals('/m/1/1', $e->getPointer());
+        assert_equals('string', $e->getActualType());
+    }
+    assert_throws(fn() => Sift::query('[[1, 2], [3]]')->floatMatrix(), 'Row 1 has 1 elements, expected 2');
+    assert_throws(fn() => Sift::query('[[1], 2]')->floatMatrix(), 'Row 1 is not an array');
+    assert_throws(fn() => Sift::query('{"m": 1}')->get('m')->floatMatrix(), 'not an array');
+});
+
+// ==================== GeoJSON Tests ====================
+
+$geoJson = json_encode([
+    'type' => 'FeatureCollection',
+    'features' => [
+        ['type' => 'Feature', 'properties' => ['name' => 'a'],
+         'geometry' => ['type' => 'Point', 'coordinates' => [10.5, -3.25]]],
+        ['type' => 'Feature', 'properties' => null, 'geometry' => null],
+        ['type' => 'Feature', 'properties' => [],
+         'geometry' => ['type' => 'Polygon', 'coordinates' => [
+             [[0, 0], [4, 0], [4, 4], [0, 4], [0, 0]],
+             [[1, 1], [2, 1], [2, 2], [1, 1]],
+         ]]],
+        ['type' => 'Feature', 'properties' => [],
+         'geometry' => ['type' => 'GeometryCollection', 'geometries' => [
+             ['type' => 'LineString', 'coordinates' => [[-7, 2, 100], [3, 20, 50]]],
+         ]]],
+    ],
+]);
+
+test('Sift::geoBounds - FeatureCollection', function() use ($geoJson) {
+    assert_equals([-7.0, -3.25, 10.5, 20.0], Sift::geoBounds($geoJson));
+});
+
+test('Sift::geoBounds - geometries and empty documents', function() {
+    assert_equals([1.0, 2.0, 1.0, 2.0], Sift::geoBounds('{"type": "Point", "coordinates": [1, 2]}'));
+    assert_equals(
+        [-1.0, -2.0, 5.0, 6.0],
+        Sift::geoBounds('{"type": "MultiPolygon", "coordinates": [[[[-1, 0], [5, 6], [0, -2], [-1, 0]]]]}')
+    );
+    assert_equals(null, Sift::geoBounds('{"type": "FeatureCollection", "features": []}'));
+    assert_equals(null, Sift::geoBounds('{"type": "Feature", "geometry": null, "properties": {}}'));
+});
+
+test('Sift::geoBounds - errors', function() {
+    assert_throws(fn() => Sift::geoBounds('{"type": "Circle", "coordinates": [0, 0]}'), "Unknown GeoJSON type 'Circle'");
+    assert_throws(fn() => Sift::geoBounds('{"coordinates": [0, 0]}'), "no string 'type'");
+    assert_throws(fn() => Sift::geoBounds('{"type": "LineString"}'), "no 'coordinates'");
+    assert_throws(fn() => Sift::geoBounds('{"type": "LineString", "coordinates": [[0, 0], [1]]}'), 'at least 2');
+    try {
+        Sift::geoBounds('{"type": "LineString", "coordinates": [[0, 0], [1, "x"]]}');
+        throw new \RuntimeException('Expected Sift\Exception');
+    } catch (\Sift\Exception $e) {
+        assert_equals('/coordinates/1/1', $e->getPointer());
+    }
+});
+
+test('Query::geometry - packs coordinates', function() use ($geoJson) {
+    $features = Sift::query($geoJson)->get('features');
+
+    $point = $features->index(0)->geometry();
+    assert_equals(['type' => 'Point', 'dims' => 2, 'counts' => []], array_diff_key($point, ['data' => 0]));
+    assert_equals([10.5, -3.25], array_values(unpack('d*', $point['data'])));
+
+    $polygon = $features->index(2)->get('geometry')->geometry();
+    assert_equals('Polygon', $polygon['type']);
+    assert_equals([[5, 4]], $polygon['counts']);
+    assert_equals(9 * 2 * 8, strlen($polygon['data']));
+    assert_equals([0.0, 0.0, 4.0, 0.0], array_slice(array_values(unpack('d*', $polygon['data'])), 0, 4));
+
+    assert_equals(null, $features->index(1)->geometry());
+});
+
+test('Query::geometry - nesting levels and dimensions', function() {
+    $multi = Sift::query('{"type": "MultiPolygon", "coordinates": [[[[0,0],[1,0],[0,1],[0,0]]], [[[5,5],[6,5],[5,6],[5,5]], [[5.1,5.1],[5.2,5.1],[5.1,5.2],[5.1,5.1]]]]}')->geometry();
+    assert_equals([[1, 2], [4, 4, 4]], $multi['counts']);
+
+    $line = Sift::query('{"type": "LineString", "coordinates": [[1, 2, 3], [4, 5, 6]]}')->geometry();
+    assert_equals(3, $line['dims']);
+    assert_equals([1.0, 2.0, 3.0, 4.0, 5.0, 6.0], array_values(unpack('d*', $line['data'])));
+
+    assert_throws(fn() => Sift::query('{"type": "LineString", "coordinates": [[1, 2, 3], [4, 5]]}')->geometry(), 'expected 3');
+    assert_throws(fn() => Sift::query('{"type": "Polygon", "coordinates": [[1, 2]]}')->geometry(), 'must be arrays');
+    assert_throws(fn() => Sift::query('{"type": "FeatureCollection", "features": []}')->geometry(), 'not a single geometry');
+});
+
+// ==================== Sift::jsonApi() Tests ====================
+
+$jsonApiDoc = json_encode([
+    'data' => [
+        'type' => 'articles', 'id' => '1',
+        'attributes' => ['title' => 'JSON:API paints my bikeshed!'],
+        'relationships' => [
+            'author' => ['links' => ['self' => '/articles/1/relationships/author'],
+                         'data' => ['type' => 'people', 'id' => '9']],
+            'comments' => ['data' => [['type' => 'comments', 'id' => '5'], ['type' => 'comments', 'id' => '12'],
+                                      ['type' => 'comments', 'id' => '99']]],
+            'editor' => ['data' => null],
+            'tags' => ['links' => ['related' => '/articles/1/tags']],
+        ],
+    ],
+    'included' => [
+        ['type' => 'people', 'id' => '9', 'attributes' => ['name' => 'Dan']],
+        ['type' => 'comments', 'id' => '5', 'attributes' => ['body' => 'First!']],
+        ['type' => 'comments', 'id' => '12', 'attributes' => ['body' => 'I like XML better']],
+    ],
+    'meta' => ['total' => 1],
+]);
+
+test('JsonApi::resource and relationships', function() use ($jsonApiDoc) {
+    $doc = Sift::jsonApi($jsonApiDoc);
+    assert_equals(json_decode($jsonApiDoc, true)['data'], $doc->resource());
+    assert_equals([
+        'author' => ['type' => 'people', 'id' => '9'],
+        'comments' => [['type' => 'comments', 'id' => '5'], ['type' => 'comments', 'id' => '12'],
+                       ['type' => 'comments', 'id' => '99']],
+        'editor' => null,
+    ], $doc->relationships());
+    assert_equals(1, $doc->query()->pointer('/meta/total')->int());
+});
+
+test('JsonApi::included and related', function() use ($jsonApiDoc) {
+    $doc = Sift::jsonApi($jsonApiDoc);
+    assert_equals('Dan', $doc->included('people', '9')['attributes']['name']);
+    assert_equals(null, $doc->included('people', '10'));
+    assert_equals(null, $doc->included('comments', '9'));
+
+    assert_equals('Dan', $doc->related('author')['attributes']['name']);
+    $comments = $doc->related('comments');
+    assert_equals(3, count($comments));
+    assert_equals('First!', $comments[0]['attributes']['body']);
+    assert_equals('12', $comments[1]['id']);
+    assert_equals(null, $comments[2]);
+    assert_equals(null, $doc->related('editor'));
+    assert_throws(fn() => $doc->related('tags'), "No linkage for relationship 'tags'");
+});
+
+test('JsonApi - collection documents', function() {
+    $json = '{"data": [{"type": "a", "id": "1", "relationships": {"b": {"data": {"type": "b", "id": "2"}}}},'
+        . ' {"type": "a", "id": "3"}], "included": [{"type": "b", "id": "2", "attributes": {"n": 2}}]}';
+    $doc = Sift::jsonApi($json);
+    assert_equals(2, count($doc->resource()));
+    assert_equals('3', $doc->resource(1)['id']);
+    assert_equals(['n' => 2], $doc->related('b', 0)['attributes']);
+    assert_equals([], $doc->relationships(1));
+    assert_throws(fn() => $doc->relationships(), 'pass the index');
+    assert_throws(fn() => $doc->resource(5), 'Path not found');
+    assert_throws(fn() => Sift::jsonApi('{"data": {"type": "a", "id": "1"}}')->resource(0), 'not a collection');
+});
+
+test('JsonApi - documents without included', function() {
+    $doc = Sift::jsonApi('{"data": {"type": "a", "id": "1", "relationships": {"b": {"data": {"type": "b", "id": "2"}}}}}');
+    assert_equals(null, $doc->included('b', '2'));
+    assert_equals(null, $doc->related('b'));
+    assert_equals(null, Sift::jsonApi('{"data": null}')->resource());
+    assert_throws(fn() => Sift::jsonApi('{"data": {}, "included": {}}')->included('a', '1'), "'included' must be an array");
+});
+
+// ==================== Sift::jwtClaims() Tests ====================
+
+function jwt_segment(string $data): string {
+    return rtrim(strtr(base64_encode($data), '+/', '-_'), '=');
+}
+
+function make_jwt(array $claims, string $header = '{"alg":"HS256","typ":"JWT"}'): string {
+    return jwt_segment($header) . '.' . jwt_segment(json_encode($claims)) . '.' . jwt_segment('not-a-real-signature');
+}
+
+test('Sift::jwtClaims - decodes the payload into a Query', function() {
+    $token = make_jwt(['sub' => 'user-42', 'exp' => 1893456000, 'roles' => ['admin', 'ops'], 'name' => "Zoë ~?>"]);
+    $claims = Sift::jwtClaims($token);
+    assert_true($claims instanceof \Sift\Query);
+    assert_equals('user-42', $claims->get('sub')->string());
+    assert_equals(1893456000, $claims->get('exp')->int());
+    assert_equals('ops', $claims->pointer('/roles/1')->string());
+    assert_equals("Zoë ~?>", $claims->get('name')->string());
+});
+
+test('Sift::jwtClaims - signature is not verified', function() {
+    $token = make_jwt(['sub' => 'x']);
+    $tampered = substr($token, 0, strrpos($token, '.')) . '.';
+    assert_equals('x', Sift::jwtClaims($tampered)->get('sub')->string());
+});
+
+test('Sift::jwtClaims - options apply to the claims', function() {
+    $claims = Sift::jwtClaims(make_jwt(['meta' => ['a' => 1]]), ['assoc' => false]);
+    assert_true($claims->get('meta')->value() instanceof \stdClass);
+});
+
+test('Sift::jwtClaims - malformed tokens', function() {
+    $payload = jwt_segment('{"sub":"x"}');
+    assert_throws(fn() => Sift::jwtClaims('abc.def'), '3 segments, got 2');
+    assert_throws(fn() => Sift::jwtClaims('a.b.c.d.e'), 'JWE');
+    assert_throws(fn() => Sift::jwtClaims(jwt_segment('{}') . '.' . $payload . '+.sig'), 'not valid base64url');
+    assert_throws(fn() => Sift::jwtClaims(jwt_segment('[1]') . '.' . $payload . '.sig'), 'header is not a JSON object');
+    try {
+        Sift::jwtClaims('!!.' . $payload . '.sig');
+        throw new \RuntimeException('Expected Sift\FormatException');
+    } catch (\Sift\FormatException $e) {
+        assert_equals(\Sift\Exception::FORMAT_ERROR, $e->getErrorCode());
+    }
+});
+
+// ==================== Sift\FrameReader Tests ====================
+
+test('FrameReader - decodes complete data frames', function() {
+    $reader = new \Sift\FrameReader();
+    $frames = $reader->push("data: {\"a\": 1}\n\ndata: [1, 2]\n\n");
+    assert_equals([['a' => 1], [1, 2]], $frames);
+    assert_equals(0, $reader->pending());
+    assert_false($reader->isDone());
+});
+
+test('FrameReader - frames split across chunks', function() {
+    $stream = "event: delta\r\nid: 7\r\ndata: {\"text\": \"héllo\"}\r\n\r\n: keep-alive\n\ndata:{\"text\":\"wörld\"}\r\r";
+    $reader = new \Sift\FrameReader();
+    $texts = [];
+    foreach (str_split($stream, 3) as $chunk) {
+        foreach ($reader->push($chunk) as $frame) {
+            $texts[] = $frame['text'];
+        }
+    }
+    foreach ($reader->finish() as $frame) {
+        $texts[] = $frame['text'];
+    }
+    assert_equals(['héllo', 'wörld'], $texts);
+});
+
+test('FrameReader - multi-line data and [DONE]', function() {
+    $reader = new \Sift\FrameReader(['assoc' => false]);
+    $frames = $reader->push("data: {\"a\":\ndata:  [1,\ndata: 2]}\n\n");
+    assert_true($frames[0] instanceof \stdClass);
+    assert_equals([1, 2], $frames[0]->a);
+    assert_equals([], $reader->push("data: [DONE]\n\n"));
+    assert_true($reader->isDone());
+});
+
+test('FrameReader - pending events and finish', function() {
+    $reader = new \Sift\FrameReader();
+    assert_equals([], $reader->push("data: {\"partial\": tr"));
+    assert_true($reader->pending() > 0);
+    assert_equals([], $reader->push("ue}\n"));
+    assert_equals([['partial' => true]], $reader->finish());
+    assert_equals([], $reader->finish());
+});
+
+test('FrameReader - errors', function() {
+    $reader = new \Sift\FrameReader();
+    assert_throws(fn() => $reader->push("data: {oops}\n\n"));
+    assert_equals([['ok' => 1]], $reader->push("data: {\"ok\": 1}\n\n"));
+    assert_throws(fn() => new \Sift\FrameReader(['no_such_option' => 1]));
+});
+
+test('FrameReader - buffer limit', function() {
+    $reader = new \Sift\FrameReader(null, ['max_bytes' => 16]);
+    assert_equals([], $reader->push("data: {\"a\": 1"));
+    try {
+        $reader->push(str_repeat(' ', 16));
+        assert_true(false, 'expected an exception');
+    } catch (\Sift\InputTooLargeException $e) {
+        assert_equals(16, $e->getLimit());
+    }
+    assert_equals(0, $reader->pending());
+    assert_equals([['b' => 2]], $reader->push("data: {\"b\": 2}\n\n"));
+    assert_throws(fn() => new \Sift\FrameReader(null, ['max_bytes' => 16, 'when_full' => 'block']));
+    assert_throws(fn() => new \Sift\FrameReader(null, ['max_bytes' => 0]));
+});
+
+// ============================================================================
+// Query::followRefs() Tests
+// ============================================================================
+
+echo "\n=== Query::followRefs() Tests ===\n";
+
+$specJson = json_encode([
+    'paths' => [
+        '/users' => [
+            'get' => [
+                'responses' => [
+                    '200' => ['$ref' => '#/components/responses/UserList'],
+                ],
+            ],
+        ],
+    ],
+    'components' => [
+        'responses' => [
+            'UserList' => ['schema' => ['$ref' => '#/components/schemas/Users']],
+        ],
+        'schemas' => [
+            'Users' => ['type' => 'array', 'items' => ['$ref' => '#/components/schemas/User']],
+            'User' => ['type' => 'object', 'properties' => ['name' => ['type' => 'string']]],
+            'Alias' => ['$ref' => '#/components/schemas/User'],
+            'Loop' => ['$ref' => '#/components/schemas/Loop2'],
+            'Loop2' => ['$ref' => '#/components/schemas/Loop'],
+            'Remote' => ['$ref' => 'other.json#/User'],
+            'Dangling' => ['$ref' => '#/components/schemas/Missing'],
+        ],
+    ],
+]);
+
+test('followRefs follows references mid-path', function () use ($specJson) {
+    $spec = Sift::query($specJson)->followRefs();
+    $type = $spec->pointer('/paths/~1users/get/responses/200/schema/items/properties/name/type')->string();
+    assert_equals('string', $type);
+});
+
+test('followRefs follows a reference at the end of the path', function () use ($specJson) {
+    $user = Sift::query($specJson)->followRefs()->pointer('/components/schemas/Alias')->value();
+    assert_equals('object', $user['type']);
+});
+
+test('followRefs treats numeric segments as object keys', function () use ($specJson) {
+    $spec = Sift::query($specJson)->followRefs();
+    assert_true($spec->pointer('/paths/~1users/get/responses/200')->isObject());
+});
+
+test('followRefs detects circular references', function () use ($specJson) {
+    assert_throws(function () use ($specJson) {
+        Sift::query($specJson)->followRefs()->pointer('/components/schemas/Loop')->value();
+    }, 'Circular $ref');
+});
+
+test('followRefs rejects external references', function () use ($specJson) {
+    assert_throws(function () use ($specJson) {
+        Sift::query($specJson)->followRefs()->pointer('/components/schemas/Remote')->value();
+    }, 'External $ref');
+});
+
+test('followRefs reports dangling references', function () use ($specJson) {
+    assert_throws(function () use ($specJson) {
+        Sift::query($specJson)->followRefs()->pointer('/components/schemas/Dangling')->value();
+    }, 'not found');
+});
+
+test('references are not followed by default', function () use ($specJson) {
+    $alias = Sift::query($specJson)->pointer('/components/schemas/Alias')->value();
+    assert_equals(['$ref' => '#/components/schemas/User'], $alias);
+    $off = Sift::query($specJson)->followRefs()->followRefs(false);
+    assert_equals($alias, $off->pointer('/components/schemas/Alias')->value());
+});
+
+// ============================================================================
+// Sift\JsonRpc Tests
+// ============================================================================
+
+echo "\n=== Sift\\JsonRpc Tests ===\n";
+
+test('parseRequest returns method, lazy params and id', function () {
+    $request = Sift\JsonRpc::parseRequest('{"jsonrpc":"2.0","method":"sum","params":{"values":[1,2,3]},"id":7}');
+    assert_true($request->isValid());
+    assert_false($request->isNotification());
+    assert_equals('sum', $request->method());
+    assert_true($request->params() instanceof Sift\Query);
+    assert_equals([1, 2, 3], $request->params()->get('values')->value());
+    assert_equals(7, $request->id());
+    assert_equals(null, $request->error());
+    assert_equals(null, $request->errorResponse());
+});
+
+test('parseRequest recognizes notifications and missing params', function () {
+    $request = Sift\JsonRpc::parseRequest('{"jsonrpc":"2.0","method":"ping"}');
+    assert_true($request->isValid());
+    assert_true($request->isNotification());
+    assert_equals(null, $request->params());
+    assert_equals(null, $request->id());
+});
+
+test('parseRequest answers malformed JSON with a parse error', function () {
+    $request = Sift\JsonRpc::parseRequest('{"jsonrpc":"2.0","method"');
+    assert_false($request->isValid());
+    assert_equals(-32700, $request->error()['code']);
+    assert_equals('Parse error', $request->error()['message']);
+    $response = $request->errorResponse();
+    assert_equals('2.0', $response['jsonrpc']);
+    assert_equals(null, $response['id']);
+});
+
+test('parseRequest rejects invalid envelopes with the request id', function () {
+    $cases = [
+        '{"jsonrpc":"1.0","method":"a","id":1}' => 'jsonrpc',
+        '{"jsonrpc":"2.0","method":5,"id":1}' => 'method',
+        '{"jsonrpc":"2.0","method":"a","params":"x","id":1}' => 'params',
+    ];
+    foreach ($cases as $json => $member) {
+        $request = Sift\JsonRpc::parseRequest($json);
+        assert_false($request->isValid());
+        assert_equals(-32600, $request->error()['code']);
+        assert_true(str_contains($request->error()['data'], $member));
+        assert_equals(1, $request->errorResponse()['id']);
+    }
+});
+
+test('parseRequest answers an invalid id with null', function () {
+    $request = Sift\JsonRpc::parseRequest('{"jsonrpc":"2.0","method":"a","id":{"x":1}}');
+    assert_false($request->isValid());
+    assert_false($request->isNotification());
+    assert_equals(null, $request->errorResponse()['id']);
+});
+
+test('parseRequest rejects non-object and batch bodies', function () {
+    assert_equals(-32600, Sift\JsonRpc::parseRequest('"call"')->error()['code']);
+    assert_equals(-32600, Sift\JsonRpc::parseRequest('[{"jsonrpc":"2.0","method":"a"}]')->error()['code']);
+});
+
+test('parseBatch returns one request per element', function () {
+    $batch = Sift\JsonRpc::parseBatch('[
+        {"jsonrpc":"2.0","method":"a","id":"1"},
+        {"jsonrpc":"2.0","method":"b"},
+        1
+    ]');
+    assert_equals(3, count($batch));
+    assert_equals('a', $batch[0]->method());
+    assert_equals('1', $batch[0]->id());
+    assert_true($batch[1]->isNotification());
+    assert_false($batch[2]->isValid());
+});
+
+test('parseBatch handles single, empty and malformed bodies', function () {
+    $single = Sift\JsonRpc::parseBatch('{"jsonrpc":"2.0","method":"a","id":1}');
+    assert_equals(1, count($single));
+    assert_true($single[0]->isValid());
+
+    $empty = Sift\JsonRpc::parseBatch('[]');
+    assert_equals(1, count($empty));
+    assert_equals(-32600, $empty[0]->error()['code']);
+
+    $malformed = Sift\JsonRpc::parseBatch('[{"jsonrpc":"2.0","method":"a"},');
+    assert_equals(1, count($malformed));
+    assert_equals(-32700, $malformed[0]->error()['code']);
+});
+
+// ============================================================================
+// Key Renaming Option Tests
+// ============================================================================
+
+echo "\n=== Key Renaming Option Tests ===\n";
+
+test('keys camel renames snake_case and kebab-case keys', function () {
+    $data = Sift::decode('{"first_name":"Ada","last-name":"L","_id":1,"nested":{"zip_code":"1"},"list":[{"a_b":1}]}', ['keys' => 'camel']);
+    assert_equals([
+        'firstName' => 'Ada',
+        'lastName' => 'L',
+        '_id' => 1,
+        'nested' => ['zipCode' => '1'],
+        'list' => [['aB' => 1]],
+    ], $data);
+});
+
+test('keys snake renames camelCase keys and keeps acronyms together', function () {
+    $data = Sift::decode('{"firstName":1,"userID":2,"HTTPServer":3,"already_snake":4,"kebab-case":5}', ['keys' => 'snake']);
+    assert_equals(['first_name', 'user_id', 'http_server', 'already_snake', 'kebab_case'], array_keys($data));
+});
+
+test('keys accepts a callable', function () {
+    $data = Sift::decode('{"a":{"b":1}}', ['keys' => fn (string $key) => strtoupper($key)]);
+    assert_equals(['A' => ['B' => 1]], $data);
+});
+
+test('keys applies to stdClass objects and Query hydration', function () {
+    $object = Sift::decode('{"first_name":"Ada"}', ['keys' => 'camel', 'assoc' => false]);
+    assert_equals('Ada', $object->firstName);
+
+    $user = Sift::query('{"user":{"first_name":"Ada"}}', ['keys' => 'camel'])->get('user')->value();
+    assert_equals(['firstName' => 'Ada'], $user);
+});
+
+test('keys renamed to the same name follow duplicate_keys', function () {
+    $json = '{"first_name":1,"firstName":2}';
+    assert_equals(['firstName' => 2], Sift::decode($json, ['keys' => 'camel']));
+    assert_equals(['firstName' => 1], Sift::decode($json, ['keys' => 'camel', 'duplicate_keys' => 'first']));
+});
+
+test('keys rejects invalid values and callback results', function () {
+    assert_throws(function () {
+        Sift::decode('{}', ['keys' => 'kebab']);
+    }, "Option 'keys'");
+    assert_throws(function () {
+        Sift::decode('{"a":1}', ['keys' => fn (string $key) => 42]);
+    }, 'must return a string');
+});
+
+// ============================================================================
+// Key Prefix And Nesting Option Tests
+// ============================================================================
+
+echo "\n=== Key Prefix And Nesting Option Tests ===\n";
+
+test('strip_prefix removes a key prefix', function () {
+    $data = Sift::decode('{"attr_color":"red","attr_size":{"attr_w":1},"id":7,"attr_":0}', ['strip_prefix' => 'attr_']);
+    assert_equals(['color' => 'red', 'size' => ['w' => 1], 'id' => 7, 'attr_' => 0], $data);
+});
+
+test('nest_keys expands separated keys into nested arrays', function () {
+    $data = Sift::decode('{"user.name":"Ada","user.address.city":"London","user.id":1,"plain":true}', ['nest_keys' => '.']);
+    assert_equals([
+        'user' => ['name' => 'Ada', 'address' => ['city' => 'London'], 'id' => 1],
+        'plain' => true,
+    ], $data);
+});
+
+test('nest_keys merges into existing arrays and resolves conflicts as duplicates', function () {
+    $merged = Sift::decode('{"user":{"name":"Ada"},"user.id":1}', ['nest_keys' => '.']);
+    assert_equals(['user' => ['name' => 'Ada', 'id' => 1]], $merged);
+
+    $json = '{"user":"x","user.id":1}';
+    assert_equals(['user' => ['id' => 1]], Sift::decode($json, ['nest_keys' => '.']));
+    assert_equals(['user' => 'x'], Sift::decode($json, ['nest_keys' => '.', 'duplicate_keys' => 'first']));
+    assert_throws(function () use ($json) {
+        Sift::decode($json, ['nest_keys' => '.', 'duplicate_keys' => 'error']);
+    }, 'Duplicate');
+});
+
+test('strip_prefix, nest_keys and keys combine', function () {
+    $data = Sift::decode('{"attr_user__first_name":"Ada"}', [
+        'strip_prefix' => 'attr_',
+        'nest_keys' => '__',
+        'keys' => 'camel',
+    ]);
+    assert_equals(['user' => ['firstName' => 'Ada']], $data);
+});
+
+test('nest_keys respects the depth limit', function () {
+    assert_throws(function () {
+        Sift::decode('{"a.b.c.d":1}', ['nest_keys' => '.', 'depth' => 2]);
+    }, 'depth');
+});
+
+test('key prefix and nesting options are validated', function () {
+    assert_throws(function () {
+        Sift::decode('{}', ['strip_prefix' => '']);
+    }, "Option 'strip_prefix'");
+    assert_throws(function () {
+        Sift::decode('{}', ['nest_keys' => '.', 'assoc' => false]);
+    }, "requires 'assoc'");
+});
+
+// ============================================================================
+// Sift::encodeLines() Tests
+// ============================================================================
+
+echo "\n=== Sift::encodeLines() Tests ===\n";
+
+test('encodeLines writes one JSON line per row', function () {
+    $result = Sift::encodeLines([
+        ['id' => 1, 'name' => 'Ada', 'tags' => ['a', 'b']],
+        ['id' => 2, 'price' => 1.0, 'path' => 'a/b', 'city' => 'Zürich'],
+        [],
+        null,
+    ]);
+    assert_equals(
+        "{\"id\":1,\"name\":\"Ada\",\"tags\":[\"a\",\"b\"]}\n{\"id\":2,\"price\":1.0,\"path\":\"a/b\",\"city\":\"Zürich\"}\n[]\nnull\n",
+        $result['lines']
+    );
+    assert_equals(4, $result['count']);
+    assert_equals([], $result['errors']);
+});
+
+test('encodeLines output round-trips through decode', function () {
+    $rows = [['s' => "quote \" backslash \\ newline \n tab \t ctrl \x01"], ['n' => -5, 'f' => 2.5e-7, 'b' => false]];
+    $lines = explode("\n", rtrim(Sift::encodeLines($rows)['lines'], "\n"));
+    assert_equals($rows, array_map(fn ($line) => Sift::decode($line), $lines));
+});
+
+test('encodeLines reports bad rows without failing the export', function () {
+    $result = Sift::encodeLines([['ok' => 1], ['bad' => "\xff"], ['nan' => NAN], ['ok' => 2]]);
+    assert_equals("{\"ok\":1}\n{\"ok\":2}\n", $result['lines']);
+    assert_equals(2, $result['count']);
+    assert_equals([1, 2], array_keys($result['errors']));
+    assert_true(str_contains($result['errors'][1], 'UTF-8'));
+});
+
+test('encodeLines accepts generators and streams to a callback', function () {
+    $rows = (function () {
+        for ($i = 0; $i < 3; $i++) {
+            yield "key$i" => ['i' => $i];
+        }
+    })();
+    $chunks = [];
+    $result = Sift::encodeLines($rows, function (string $chunk) use (&$chunks) {
+        $chunks[] = $chunk;
+    });
+    assert_equals('', $result['lines']);
+    assert_equals(3, $result['count']);
+    assert_equals("{\"i\":0}\n{\"i\":1}\n{\"i\":2}\n", implode('', $chunks));
+});
+
+test('encodeLines encodes objects', function () {
+    $point = new class implements JsonSerializable {
+        public function jsonSerialize(): mixed
+        {
+            return [1, 2];
+        }
+    };
+    $user = new class {
+        public $name = 'Ada';
+        protected $secret = 'x';
+        private $hidden = 'y';
+    };
+    $result = Sift::encodeLines([$point, $user, (object) []]);
+    assert_equals("[1,2]\n{\"name\":\"Ada\"}\n{}\n", $result['lines']);
+});
+
+// ============================================================================
+// Sift::patch() Tests
+// ============================================================================
+
+echo "\n=== Sift::patch() Tests ===\n";
+
+test('patch replaces values and keeps other bytes verbatim', function () {
+    $json = "{ \"status\" : \"pending\",\n  \"total\": 1.50E2, \"items\": [ {\"qty\": 1}, {\"qty\": 2} ], \"note\": \"caf\\u00e9\" }";
+    $patched = Sift::patch($json, ['/status' => 'paid', '/items/1/qty' => 5]);
+    assert_equals(
+        "{ \"status\" : \"paid\",\n  \"total\": 1.50E2, \"items\": [ {\"qty\": 1}, {\"qty\": 5} ], \"note\": \"caf\\u00e9\" }",
+        $patched
+    );
+});
+
+test('patch encodes structured values and replaces the root', function () {
+    assert_equals('{"a": {"b":[1,2]}}', Sift::patch('{"a": null}', ['/a' => ['b' => [1, 2]]]));
+    assert_equals(' true ', Sift::patch(' {"a":1} ', ['' => true]));
+});
+
+test('patch with no changes returns the input', function () {
+    $json = '{"a" :  1}';
+    assert_equals($json, Sift::patch($json, []));
+});
+
+test('patch rejects missing pointers and overlapping changes', function () {
+    assert_throws(function () {
+        Sift::patch('{"a":1}', ['/b' => 2]);
+    }, 'Path not found');
+    assert_throws(function () {
+        Sift::patch('{"a":{"b":1}}', ['/a' => 1, '/a/b' => 2]);
+    }, 'overlap');
+    assert_throws(function () {
+        Sift::patch('{"a":1}', ['/a' => NAN]);
+    }, 'cannot be represented');
+});
+
+// ============================================================================
+// UTF-8 Validation Tests
+// ============================================================================
+
+echo "\n=== UTF-8 Validation Tests ===\n";
+
+test('validateUtf8 accepts valid UTF-8', function () {
+    assert_true(Sift::validateUtf8(''));
+    assert_true(Sift::validateUtf8('plain ascii'));
+    assert_true(Sift::validateUtf8("Zürich \u{1F600} \u{10FFFF}"));
+    assert_true(Sift::validateUtf8(str_repeat('é', 10000)));
+});
+
+test('validateUtf8 rejects malformed sequences', function () {
+    assert_false(Sift::validateUtf8("\xff"));
+    assert_false(Sift::validateUtf8("\xc0\xaf"));       // overlong
+    assert_false(Sift::validateUtf8("\xed\xa0\x80"));   // surrogate
+    assert_false(Sift::validateUtf8("\xf4\x90\x80\x80")); // above U+10FFFF
+    assert_false(Sift::validateUtf8("ok \xe2\x82"));    // truncated
+});
+
+test('invalidUtf8Offset reports the first malformed byte', function () {
+    assert_equals(null, Sift::invalidUtf8Offset('valid é'));
+    assert_equals(3, Sift::invalidUtf8Offset("abc\xffdef"));
+    assert_equals(5, Sift::invalidUtf8Offset(str_repeat('é', 2) . "a\xe2\x82"));
+});
+
+// ============================================================================
+// Query::len() Tests
+// ============================================================================
+
+echo "\n=== Query::len() Tests ===\n";
+
+test('len counts string bytes and code points', function () {
+    $q = Sift::query('{"name":"Zürich","escaped":"a\\u00e9\\n"}');
+    assert_equals(7, $q->get('name')->len());
+    assert_equals(6, $q->get('name')->len(true));
+    assert_equals(4, $q->get('escaped')->len());
+    assert_equals(3, $q->get('escaped')->len(true));
+});
+
+test('len counts array elements and object members', function () {
+    $q = Sift::query('{"items":[1,[2,3],{"a":4}],"meta":{"a":1,"b":2},"empty":[]}');
+    assert_equals(3, $q->get('items')->len());
+    assert_equals(2, $q->get('meta')->len());
+    assert_equals(0, $q->get('empty')->len());
+    assert_equals(3, $q->len());
+});
+
+test('len rejects scalars without a length', function () {
+    assert_throws(function () {
+        Sift::query('{"n":5}')->get('n')->len();
+    }, 'no length');
+});
+
+// ============================================================================
+// Depth-limited Query::value() Tests
+// ============================================================================
+
+echo "\n=== Depth-limited Query::value() Tests ===\n";
+
+test('value with maxDepth leaves deeper containers as queries', function () {
+    $json = '{"total":2,"users":[{"name":"Ada","tags":["a","b"]},{"name":"Bob","tags":[]}]}';
+    $top = Sift::query($json)->value(2);
+    assert_equals(2, $top['total']);
+    assert_true($top['users'][0] instanceof Sift\Query);
+    assert_equals(['name' => 'Ada', 'tags' => ['a', 'b']], $top['users'][0]->value());
+    assert_equals('b', $top['users'][0]->get('tags')->index(1)->string());
+});
+
+test('value with maxDepth 1 hydrates only the top level', function () {
+    $top = Sift::query('{"a":1,"b":{"c":2},"d":[3]}')->value(1);
+    assert_equals(1, $top['a']);
+    assert_true($top['b'] instanceof Sift\Query);
+    assert_true($top['d'] instanceof Sift\Query);
+});
+
+test('value with raw leaves keeps deeper JSON text', function () {
+    $top = Sift::query('{"a":{"b": [1, 2]},"c":"x"}')->value(1, 'raw');
+    assert_equals(['a' => '{"b": [1, 2]}', 'c' => 'x'], $top);
+});
+
+test('value depth limit is relative to the query path', function () {
+    $q = Sift::query('{"data":{"items":[{"id":1}]}}')->get('data');
+    $value = $q->value(2, 'raw');
+    assert_equals(['items' => ['{"id":1}']], $value);
+});
+
+test('value leaf queries keep the query options', function () {
+    $top = Sift::query('{"a":{"b":{"first_name":"Ada"}}}', ['keys' => 'camel'])->value(1);
+    assert_equals(['b' => ['firstName' => 'Ada']], $top['a']->value());
+});
+
+test('value rejects invalid depth and leaves', function () {
+    assert_throws(function () {
+        Sift::query('[1]')->value(0);
+    }, 'at least 1');
+    assert_throws(function () {
+        Sift::query('[1]')->value(1, 'lazy');
+    }, "'query' or 'raw'");
+});
+
+// ============================================================================
+// Sift::truncate() Tests
+// ============================================================================
+
+echo "\n=== Sift::truncate() Tests ===\n";
+
+test('truncate returns documents that fit unchanged', function () {
+    $json = '{ "a": [1, 2] }';
+    assert_equals($json, Sift::truncate($json, 100));
+    assert_equals($json, Sift::truncate($json, strlen($json)));
+});
+
+test('truncate cuts arrays with a count marker', function () {
+    $out = Sift::truncate('[1,2,3,4,5,6,7,8,9,10]', 16);
+    assert_equals('[1,"… 9 more"]', $out);
+    assert_true(strlen($out) <= 16);
+    assert_equals([1, '… 9 more'], json_decode($out, true));
+});
+
+test('truncate elides long strings and left-out members', function () {
+    $out = Sift::truncate('{"note":"Lorem ipsum dolor sit amet","ids":[1,2,3,4,5,6,7,8]}', 40);
+    assert_equals('{"note":"Lorem ipsum…","…":"1 more"}', $out);
+    assert_true(strlen($out) <= 40);
+});
+
+test('truncate never splits escape sequences', function () {
+    assert_equals('"a\u00e9…"', Sift::truncate('"a\u00e9bcdef"', 12));
+    assert_equals('"a…"', Sift::truncate('"a\u00e9bcdef"', 11));
+});
+
+test('truncate output is always valid JSON within budget', function () {
+    $json = json_encode([
+        'users' => array_map(fn ($i) => ['id' => $i, 'bio' => str_repeat('é', 50)], range(1, 50)),
+        'total' => 50,
+    ]);
+    foreach ([20, 64, 100, 257, 1000, 4096] as $budget) {
+        $out = Sift::truncate($json, $budget);
+        assert_true(strlen($out) <= $budget, "Budget {$budget}");
+        assert_true(json_decode($out) !== null, "Valid at {$budget}");
+    }
+});
+
+test('truncate rejects invalid JSON and impossible budgets', function () {
+    assert_throws(function () {
+        Sift::truncate('[1,2', 100);
+    });
+    assert_throws(function () {
+        Sift::truncate('[1,2,3]', 5);
+    }, 'too small');
+    assert_throws(function () {
+        Sift::truncate('[1]', -1);
+    }, 'negative');
+});
+
+// ============================================================================
+// Sift::preview() Tests
+// ============================================================================
+
+echo "\n=== Sift::preview() Tests ===\n";
+
+test('preview shows keys, scalars and counts', function () {
+    $json = '{"id":42,"name":"Ada","tags":["a","b","c","d","e","f","g"],"meta":{"x":{"y":1}}}';
+    assert_equals(
+        '{id: 42, name: "Ada", tags: ["a", "b", "c", "d", "e", … 2 more], meta: {x: {… 1 key}}}',
+        Sift::preview($json)
+    );
+});
+
+test('preview cuts long strings', function () {
+    $json = json_encode(str_repeat('x', 50));
+    assert_equals('"xxxxxxxxxx…" (50 chars)', Sift::preview($json, ['string' => 10]));
+    assert_equals('"é"', Sift::preview('"é"'));
+});
+
+test('preview quotes keys that are not identifiers', function () {
+    assert_equals('{"first name": 1, $ref: 2, "1st": 3}', Sift::preview('{"first name":1,"$ref":2,"1st":3}'));
+});
+
+test('preview respects depth and items limits', function () {
+    assert_equals('[… 3 items]', Sift::preview('[1,2,3]', ['depth' => 0]));
+    assert_equals('[]', Sift::preview('[]', ['depth' => 0]));
+    assert_equals('{… 2 more}', Sift::preview('{"a":1,"b":2}', ['items' => 0]));
+    assert_equals('[[[… 1 item]]]', Sift::preview('[[[[1]]]]', ['depth' => 3]));
+});
+
+test('preview rejects invalid JSON and options', function () {
+    assert_throws(function () {
+        Sift::preview('{"a":');
+    });
+    assert_throws(function () {
+        Sift::preview('[]', ['lines' => 2]);
+    }, 'Unknown option');
+    assert_throws(function () {
+        Sift::preview('[]', ['string' => 0]);
+    }, 'at least 1');
+});
+
+// ============================================================================
+// control_chars Option Tests
+// ============================================================================
+
+echo "\n=== control_chars Option Tests ===\n";
+
+test('control_chars keeps NUL bytes by default', function () {
+    $data = Sift::decode('{"a":"x\u0000y","b\u0001":1}');
+    assert_equals("x\0y", $data['a']);
+    assert_equals(1, $data["b\x01"]);
+    assert_equals(json_decode('{"a":"x\u0000y"}', true), Sift::decode('{"a":"x\u0000y"}', ['control_chars' => 'keep']));
+});
+
+test('control_chars strip removes controls but keeps whitespace', function () {
+    $data = Sift::decode('{"a":"x\u0000y\u001f\u007f","b":"line\n\ttab\r","k\u0000ey":1}', ['control_chars' => 'strip']);
+    assert_equals('xy', $data['a']);
+    assert_equals("line\n\ttab\r", $data['b']);
+    assert_equals(1, $data['key']);
+});
+
+test('control_chars strip applies to stdClass properties', function () {
+    $obj = Sift::decode('{"\u0000secret":"v\u0000"}', ['control_chars' => 'strip', 'assoc' => false]);
+    assert_equals('v', $obj->secret);
+});
+
+test('control_chars error names the pointer', function () {
+    try {
+        Sift::decode('{"users":[{"name":"ok"},{"name":"bad\u0000"}]}', ['control_chars' => 'error']);
+        throw new RuntimeException('Expected exception');
+    } catch (Sift\Exception $e) {
+        assert_true(str_contains($e->getMessage(), 'U+0000'), $e->getMessage());
+        assert_equals('/users/1/name', $e->getPointer());
+    }
+    assert_throws(function () {
+        Sift::decode('{"a\u0008":1}', ['control_chars' => 'error']);
+    }, 'U+0008');
+});
+
+test('control_chars applies to Query::string()', function () {
+    $q = Sift::query('{"a":"x\u0000y"}', ['control_chars' => 'strip']);
+    assert_equals('xy', $q->get('a')->string());
+    assert_throws(function () {
+        Sift::query('{"a":"x\u0000y"}', ['control_chars' => 'error'])->get('a')->string();
+    }, 'control character');
+});
+
+test('control_chars rejects unknown policies', function () {
+    assert_throws(function () {
+        Sift::decode('[]', ['control_chars' => 'replace']);
+    }, "'keep', 'strip', 'error'");
+});
+
+// ============================================================================
+// surrogates Option Tests
+// ============================================================================
+
+echo "\n=== surrogates Option Tests ===\n";
+
+test('surrogates error rejects lone surrogates by default', function () {
+    assert_throws(function () {
+        Sift::decode('["\ud800"]');
+    });
+    assert_throws(function () {
+        Sift::decode('["x\udc00"]', ['surrogates' => 'error']);
+    });
+    try {
+        Sift::decode('["\ud800"]', ['strict' => true]);
+        throw new RuntimeException('Expected exception');
+    } catch (JsonException $e) {
+        assert_equals(JSON_ERROR_UTF16, $e->getCode());
+    }
+});
+
+test('surrogates replace substitutes U+FFFD', function () {
+    $data = Sift::decode('["a\ud800b","\udc00","\u12G4","\ud83d\ude00","\\\\ud800"]', ['surrogates' => 'replace']);
+    assert_equals(["a\u{FFFD}b", "\u{FFFD}", "\u{FFFD}G4", "\u{1F600}", '\\ud800'], $data);
+});
+
+test('surrogates replace works on the DOM path and in queries', function () {
+    assert_equals(["\u{FFFD}"], Sift::decode('["\ud800"]', ['surrogates' => 'replace', 'strategy' => 'dom']));
+    $q = Sift::query('{"a":"\udbff!"}', ['surrogates' => 'replace']);
+    assert_equals("\u{FFFD}!", $q->get('a')->string());
+});
+
+test('surrogates wtf8 passes lone surrogates through as bytes', function () {
+    $data = Sift::decode('{"k\ud800":"a\ud800b","low":"\udfff","pair":"\ud83d\ude00"}', ['surrogates' => 'wtf8']);
+    assert_equals("a\xED\xA0\x80b", $data["k\u{FFFD}"]);
+    assert_equals("\xED\xBF\xBF", $data['low']);
+    assert_equals("\u{1F600}", $data['pair']);
+    assert_equals("\xED\xA0\x80", Sift::get('{"a":["\ud800"]}', '/a/0', ['surrogates' => 'wtf8']));
+});
+
+test('surrogates wtf8 falls back to replace outside decode', function () {
+    $q = Sift::query('["\ud800"]', ['surrogates' => 'wtf8']);
+    assert_equals("\u{FFFD}", $q->index(0)->string());
+});
+
+test('surrogates wtf8 rejects documents with its stand-in characters', function () {
+    assert_throws(function () {
+        Sift::decode("[\"\u{10FFFF}\", \"\\ud800\"]", ['surrogates' => 'wtf8']);
+    }, 'U+10F800');
+    assert_throws(function () {
+        Sift::decode('["\udbff\udfff"]', ['surrogates' => 'wtf8']);
+    }, 'U+10F800');
+});
+
+test('surrogates rejects unknown policies', function () {
+    assert_throws(function () {
+        Sift::decode('[]', ['surrogates' => 'ignore']);
+    }, "'error', 'replace', 'wtf8'");
+});
+
+// ============================================================================
+// String Escaping Tests
+// ============================================================================
+
+echo "\n=== String Escaping Tests ===\n";
+
+test('escapeString escapes quotes, backslashes and control characters', function () {
+    assert_equals('"a\"b\\\\c\n\t\u0001"', Sift::escapeString("a\"b\\c\n\t\x01"));
+});
+
+test('escapeString keeps slashes and unicode', function () {
+    assert_equals('"a/b café 😀"', Sift::escapeString('a/b café 😀'));
+});
+
+test('escapeString without quotes', function () {
+    assert_equals('say \"hi\"', Sift::escapeString('say "hi"', false));
+    assert_equals('', Sift::escapeString('', false));
+});
+
+test('escapeString output decodes to the input', function () {
+    $input = "line\nbreak \"quoted\" \\ tab\t \x7f é";
+    assert_equals($input, json_decode(Sift::escapeString($input)));
+    assert_equals($input, Sift::unescapeString(Sift::escapeString($input)));
+});
+
+test('escapeString rejects malformed UTF-8', function () {
+    assert_throws(fn() => Sift::escapeString("ok\xff"), 'Malformed UTF-8');
+});
+
+test('unescapeString decodes escapes and surrogate pairs', function () {
+    assert_equals("café\n\"/", Sift::unescapeString('"café\n\"\/"'));
+    assert_equals('😀', Sift::unescapeString('"😀"'));
+});
+
+test('unescapeString without quotes', function () {
+    assert_equals('a"b', Sift::unescapeString('a\"b', false));
+});
+
+test('unescapeString rejects invalid literals', function () {
+    assert_throws(fn() => Sift::unescapeString('"unterminated'), '');
+    assert_throws(fn() => Sift::unescapeString('123'), '');
+    assert_throws(fn() => Sift::unescapeString('a"b', false), '');
+    assert_throws(fn() => Sift::unescapeString('"\x"'), '');
+});
+
+// ============================================================================
+// Benchmark Tests
+// ============================================================================
+
+echo "\n=== Benchmark Tests ===\n";
+
+test('bench reports timings for the bundled fixture', function () {
+    $report = Sift::bench(null, ['iterations' => 2]);
+    assert_true(in_array($report['simd'], ['avx2', 'neon', 'fallback'], true));
+    assert_true(in_array($report['cpu_simd'], ['avx2', 'neon', 'fallback'], true));
+    assert_equals(2, $report['iterations']);
+    assert_equals('/total', $report['pointer']);
+    assert_true($report['bytes'] > 0);
+    foreach (['decode', 'get', 'query', 'json_decode'] as $op) {
+        assert_true(isset($report['results'][$op]));
+        assert_true(is_float($report['results'][$op]['ms']));
+        assert_true(is_float($report['results'][$op]['per_op_us']));
+        assert_true(is_float($report['results'][$op]['mb_per_s']));
+    }
+});
+
+test('bench measures a given payload and pointer', function () {
+    $report = Sift::bench('[1, {"a": [true]}, 3]', ['iterations' => 1]);
+    assert_equals('/2', $report['pointer']);
+    assert_equals(21, $report['bytes']);
+
+    $report = Sift::bench('{"a/b": {"c": 1}}', ['iterations' => 1, 'pointer' => '/a~1b/c']);
+    assert_equals('/a~1b/c', $report['pointer']);
+});
+
+test('bench rejects invalid input and options', function () {
+    assert_throws(fn() => Sift::bench('{"a":'), '');
+    assert_throws(fn() => Sift::bench(null, ['iterations' => 0]), "Option 'iterations'");
+    assert_throws(fn() => Sift::bench(null, ['pointer' => 5]), "Option 'pointer'");
+    assert_throws(fn() => Sift::bench(null, ['rounds' => 5]), "Unknown option 'rounds'");
+    assert_throws(fn() => Sift::bench('{"a": 1}', ['pointer' => '/missing']), '');
+});
+
+// ============================================================================
+// Info Tests
+// ============================================================================
+
+echo "\n=== Info Tests ===\n";
+
+test('info reports version, SIMD path and CPU features', function () {
+    $info = Sift::info();
+    assert_true(is_string($info['version']) && $info['version'] !== '');
+    assert_true(str_starts_with($info['engine'], 'sonic-rs'));
+    assert_true(in_array($info['simd'], ['avx2', 'neon', 'fallback'], true));
+    assert_true(in_array($info['cpu_simd'], ['avx2', 'neon', 'fallback'], true));
+    foreach ($info['cpu'] as $feature => $supported) {
+        assert_true(is_string($feature));
+        assert_true(is_bool($supported));
+    }
+    if ($info['simd'] === 'avx2') {
+        assert_true($info['cpu']['avx2']);
+    }
+});
+
+test('info reports limits and build flags', function () {
+    $info = Sift::info();
+    assert_equals(64 * 1024 * 1024, $info['limits']['max_input_size']);
+    assert_equals(512, $info['limits']['max_depth']);
+    assert_equals(2048, $info['limits']['max_depth_option']);
+    assert_equals(256, $info['limits']['max_pointer_segments']);
+    assert_true(in_array($info['build']['profile'], ['debug', 'release'], true));
+    assert_true(is_bool($info['build']['portable']));
+    assert_true($info['build']['arch'] !== '' && $info['build']['os'] !== '');
+    assert_true(is_array($info['build']['target_features']));
+});
+
+// ============================================================================
+// Panic Containment Tests
+// ============================================================================
+
+echo "\n=== Panic Containment Tests ===\n";
+
+test('INTERNAL_ERROR code is exposed', function () {
+    assert_equals(9, \Sift\Exception::INTERNAL_ERROR);
+});
+
+test('InternalException extends Sift\\Exception', function () {
+    assert_true(class_exists(\Sift\InternalException::class));
+    assert_true(is_subclass_of(\Sift\InternalException::class, \Sift\Exception::class));
+});
+
+test('guarded methods keep their results', function () {
+    assert_true(Sift::validateUtf8('ok'));
+    assert_equals(2, Sift::invalidUtf8Offset("ok\xff"));
+    assert_true(Sift::isValid('[1]'));
+    assert_false(Sonic::isValid('[1'));
+    $snap = Sift::snapshot('{"a": 1}')->persist('guard-test');
+    assert_equals(1, Sift\Snapshot::persisted('guard-test')->get('/a'));
+    assert_true(Sift\Snapshot::forget('guard-test'));
+});
+
+test('guarded entry points still report ordinary errors', function () {
+    try {
+        Sift::decode('{"a": [1, 2');
+        throw new Exception('Expected exception was not thrown');
+    } catch (\Sift\Exception $e) {
+        assert_equals(\Sift\Exception::PARSE_ERROR, $e->getErrorCode());
+    }
+    try {
+        Sift::query('{"a": 1}')->get('b')->value();
+        throw new Exception('Expected exception was not thrown');
+    } catch (\Sift\Exception $e) {
+        assert_equals(\Sift\Exception::KEY_NOT_FOUND, $e->getErrorCode());
+    }
+});
+
+// ============================================================================
+// Tokenize Tests
+// ============================================================================
+
+echo "\n=== Tokenize Tests ===\n";
+
+test('tokenize yields types and byte offsets', function () {
+    $json = '{"a": [1, "x", true, null]}';
+    $tokens = iterator_to_array(Sift::tokenize($json));
+    assert_equals([
+        ['begin_object', 0, 1],
+        ['key', 1, 4],
+        ['colon', 4, 5],
+        ['begin_array', 6, 7],
+        ['number', 7, 8],
+        ['comma', 8, 9],
+        ['string', 10, 13],
+        ['comma', 13, 14],
+        ['true', 15, 19],
+        ['comma', 19, 20],
+        ['null', 21, 25],
+        ['end_array', 25, 26],
+        ['end_object', 26, 27],
+    ], $tokens);
+});
+
+test('tokenize offsets slice the source text', function () {
+    $json = '{"k\\"ey": "va\\\\l", "n": -1.5e3, "o": {"x": false}}';
+    $texts = [];
+    foreach (Sift::tokenize($json) as [$type, $start, $end]) {
+        $texts[] = $type . ' ' . substr($json, $start, $end - $start);
+    }
+    assert_equals([
+        'begin_object {', 'key "k\\"ey"', 'colon :', 'string "va\\\\l"', 'comma ,',
+        'key "n"', 'colon :', 'number -1.5e3', 'comma ,',
+        'key "o"', 'colon :', 'begin_object {', 'key "x"', 'colon :', 'false false',
+        'end_object }', 'end_object }',
+    ], $texts);
+});
+
+test('tokenize handles scalar documents', function () {
+    assert_equals([['string', 1, 6]], iterator_to_array(Sift::tokenize(' "abc" ')));
+});
+
+test('tokenize can be iterated twice', function () {
+    $tokens = Sift::tokenize('[1, 2]');
+    assert_equals(5, count(iterator_to_array($tokens)));
+    assert_equals(5, count(iterator_to_array($tokens)));
+});
+
+test('tokenize rejects invalid JSON', function () {
+    assert_throws(fn() => Sift::tokenize('{"a": }'));
+});
+
+// ============================================================================
+// Structural Index Tests
+// ============================================================================
+
+echo "\n=== Structural Index Tests ===\n";
+
+test('getWithIndex resolves pointers like get', function () {
+    $json = '{"users": [{"id": 1, "email": "a@x"}, {"id": 2, "email": "b@x", "tags": ["x", "y"]}], "meta": {"z": 0, "a": null}}';
+    $index = Sift::index($json);
+    assert_true(is_string($index));
+    foreach (['', '/users', '/users/1/email', '/users/1/tags/1', '/meta/a', '/meta/z', '/users/0'] as $pointer) {
+        assert_equals(Sift::get($json, $pointer), Sift::getWithIndex($json, $index, $pointer));
+    }
+});
+
+test('getWithIndex handles escaped and duplicate keys', function () {
+    $json = '{"a\\/b": 1, "c~d": 2, "dup": "first", "dup": "second", "\\u00e9": 3}';
+    $index = Sift::index($json);
+    assert_equals(1, Sift::getWithIndex($json, $index, '/a~1b'));
+    assert_equals(2, Sift::getWithIndex($json, $index, '/c~0d'));
+    assert_equals(Sift::get($json, '/dup'), Sift::getWithIndex($json, $index, '/dup'));
+    assert_equals(3, Sift::getWithIndex($json, $index, "/\u{e9}"));
+});
+
+test('getWithIndex throws for missing paths', function () {
+    $json = '{"list": [1, 2], "n": 5}';
+    $index = Sift::index($json);
+    assert_throws(fn() => Sift::getWithIndex($json, $index, '/missing'));
+    assert_throws(fn() => Sift::getWithIndex($json, $index, '/list/2'));
+    assert_throws(fn() => Sift::getWithIndex($json, $index, '/n/0'));
+});
+
+test('getWithIndex applies decode options', function () {
+    $json = '{"obj": {"k": 1}}';
+    $value = Sift::getWithIndex($json, Sift::index($json), '/obj', ['assoc' => false]);
+    assert_true($value instanceof \stdClass);
+});
+
+test('getWithIndex rejects a foreign or corrupt index', function () {
+    $json = '{"a": [1, 2, 3]}';
+    $index = Sift::index($json);
+    assert_throws(fn() => Sift::getWithIndex('{"a": 1}', $index, '/a'));
+    assert_throws(fn() => Sift::getWithIndex($json, 'not an index', '/a'));
+    assert_throws(fn() => Sift::getWithIndex($json, substr($index, 0, -4), '/a/1'));
+    // Same length, different structure
+    assert_throws(fn() => Sift::getWithIndex('[10, 20, 30, 40]', $index, '/a'));
+});
+
+test('index rejects invalid JSON', function () {
+    assert_throws(fn() => Sift::index('{"a": '));
+});
+
+// ============================================================================
+// Schema Validation Tests
+// ============================================================================
+
+echo "\n=== Schema Validation Tests ===\n";
+
+$orderSchema = '{
+    "type": "object",
+    "required": ["id", "items"],
+    "additionalProperties": false,
+    "properties": {
+        "id": {"type": "integer"},
+        "note": {"type": ["string", "null"], "maxLength": 5},
+        "items": {"type": "array", "minItems": 1, "items": {"$ref": "#/$defs/item"}}
+    },
+    "$defs": {
+        "item": {"type": "object", "required": ["sku"], "properties": {"qty": {"minimum": 1}}}
+    }
+}';
+
+test('matchesSchema returns no violations for a valid subtree', function () use ($orderSchema) {
+    $json = '{"envelope": {"order": {"id": 7, "note": null, "items": [{"sku": "a", "qty": 2}]}}}';
+    assert_equals([], Sift::query($json)->pointer('/envelope/order')->matchesSchema($orderSchema));
+});
+
+test('matchesSchema reports violations relative to the subtree', function () use ($orderSchema) {
+    $json = '{"envelope": {"order": {"id": 1.5, "note": "too long", "extra": 1, "items": [{"qty": 0}]}}}';
+    $violations = Sift::query($json)->pointer('/envelope/order')->matchesSchema($orderSchema);
+    $found = array_map(fn($v) => $v['pointer'] . ' ' . $v['keyword'], $violations);
+    sort($found);
+    assert_equals([
+        '/extra additionalProperties',
+        '/id type',
+        '/items/0 required',
+        '/items/0/qty minimum',
+        '/note maxLength',
+    ], $found);
+    assert_true(is_string($violations[0]['message']));
+});
+
+test('matchesSchema supports enum, const and combinators', function () {
+    $q = Sift::query('{"a": "red", "b": 3, "c": [1, 1]}');
+    assert_equals([], $q->get('a')->matchesSchema('{"enum": ["red", "green"]}'));
+    assert_equals('enum', $q->get('a')->matchesSchema('{"enum": ["blue"]}')[0]['keyword']);
+    assert_equals([], $q->get('b')->matchesSchema('{"const": 3.0}'));
+    assert_equals([], $q->get('b')->matchesSchema('{"anyOf": [{"type": "string"}, {"minimum": 2}]}'));
+    assert_equals('oneOf', $q->get('b')->matchesSchema('{"oneOf": [{"type": "integer"}, {"minimum": 2}]}')[0]['keyword']);
+    assert_equals('not', $q->get('b')->matchesSchema('{"not": {"type": "number"}}')[0]['keyword']);
+    assert_equals('uniqueItems', $q->get('c')->matchesSchema('{"uniqueItems": true}')[0]['keyword']);
+    assert_equals('', $q->get('c')->matchesSchema('false')[0]['pointer']);
+});
+
+test('matchesSchema rejects unsupported or invalid schemas', function () {
+    $q = Sift::query('{"a": "x"}');
+    assert_throws(fn() => $q->matchesSchema('{"properties": {"a": {"pattern": "^x$"}}}'));
+    assert_throws(fn() => $q->matchesSchema('{"type": "text"}'));
+    assert_throws(fn() => $q->matchesSchema('{"$ref": "https://example.com/s.json"}'));
+    assert_throws(fn() => $q->matchesSchema('{"$ref": "#/missing"}'));
+    assert_throws(fn() => $q->matchesSchema('{"$ref": "#"}'));
+    assert_throws(fn() => $q->get('missing')->matchesSchema('true'));
+});
+
+// ============================================================================
+// Auto Decode Tests
+// ============================================================================
+
+echo "\n=== Auto Decode Tests ===\n";
+
+test('decodeAuto decodes a JSON document', function () {
+    $result = Sift::decodeAuto("{\n  \"a\": [1, 2]\n}");
+    assert_equals(['format' => 'json', 'gzip' => false, 'data' => ['a' => [1, 2]]], $result);
+});
+
+test('decodeAuto decodes NDJSON as a list of documents', function () {
+    $result = Sift::decodeAuto("{\"id\": 1}\n\n{\"id\": 2}\r\n[3]\n");
+    assert_equals('ndjson', $result['format']);
+    assert_equals([['id' => 1], ['id' => 2], [3]], $result['data']);
+});
+
+test('decodeAuto reports the NDJSON line that fails', function () {
+    try {
+        Sift::decodeAuto("{\"id\": 1}\n{\"id\": }\n");
+        assert_true(false, 'Expected an exception');
+    } catch (\Sift\Exception $e) {
+        assert_true(str_contains($e->getMessage(), 'line 2'));
+    }
+});
+
+test('decodeAuto inflates gzip payloads', function () {
+    if (!function_exists('gzencode')) {
+        return;
+    }
+    $result = Sift::decodeAuto(gzencode('{"a": true}'));
+    assert_equals(['format' => 'json', 'gzip' => true, 'data' => ['a' => true]], $result);
+    $result = Sift::decodeAuto(gzencode("1\n2\n"));
+    assert_equals(['ndjson', true, [1, 2]], [$result['format'], $result['gzip'], $result['data']]);
+    assert_throws(fn() => Sift::decodeAuto("\x1f\x8bnot gzip"));
+});
+
+test('decodeAuto decodes MessagePack maps and arrays', function () {
+    // {"a": 1, "b": [true, null, -1, "xy"], "c": 1.5}
+    $packed = "\x83\xa1a\x01\xa1b\x94\xc3\xc0\xff\xa2xy\xa1c\xcb" . pack('E', 1.5);
+    $result = Sift::decodeAuto($packed);
+    assert_equals('msgpack', $result['format']);
+    assert_equals(['a' => 1, 'b' => [true, null, -1, 'xy'], 'c' => 1.5], $result['data']);
+    // uint16 and int8 in an array
+    assert_equals([300, -100], Sift::decodeAuto("\x92\xcd\x01\x2c\xd0\x9c")['data']);
+});
+
+test('decodeAuto rejects malformed MessagePack', function () {
+    assert_throws(fn() => Sift::decodeAuto("\x92\x01"));
+    assert_throws(fn() => Sift::decodeAuto("\x91\x01\x02"));
+    assert_throws(fn() => Sift::decodeAuto("\x91\xd6\xff\x00\x00\x00\x00"));
+    assert_throws(fn() => Sift::decodeAuto("\xdd\xff\xff\xff\xff"));
+});
+
+test('decodeAuto rejects empty and invalid input', function () {
+    assert_throws(fn() => Sift::decodeAuto(''));
+    assert_throws(fn() => Sift::decodeAuto('{"a": '));
+});
+
+// ============================================================================
+// Record Decode Tests
+// ============================================================================
+
+echo "\n=== Record Decode Tests ===\n";
+
+test('decodeRecords skips malformed array elements', function () {
+    $result = Sift::decodeRecords('[{"a": 1}, {"a": }, 3, "x,]"]');
+    assert_equals([['a' => 1], 3, 'x,]'], $result['data']);
+    assert_equals(1, count($result['errors']));
+    assert_equals(1, $result['errors'][0]['index']);
+    assert_true($result['errors'][0]['offset'] >= 11 && $result['errors'][0]['offset'] < 19);
+    assert_true($result['errors'][0]['message'] !== '');
+});
+
+test('decodeRecords can keep positions with null', function () {
+    $result = Sift::decodeRecords('[1, tru, [2]]', null, 'null');
+    assert_equals([1, null, [2]], $result['data']);
+    assert_equals(1, $result['errors'][0]['index']);
+});
+
+test('decodeRecords decodes NDJSON line by line', function () {
+    $result = Sift::decodeRecords("{\"id\":1}\nnot json\n\n{\"id\":3}\n");
+    assert_equals([['id' => 1], ['id' => 3]], $result['data']);
+    assert_equals(1, $result['errors'][0]['index']);
+    assert_true($result['errors'][0]['offset'] >= 9 && $result['errors'][0]['offset'] < 17);
+});
+
+test('decodeRecords reports problems with the array itself', function () {
+    $result = Sift::decodeRecords('[1, 2');
+    assert_equals([1, 2], $result['data']);
+    assert_equals([['index' => null, 'message' => 'Unterminated array', 'offset' => 5]], $result['errors']);
+    $result = Sift::decodeRecords('[1] x');
+    assert_equals([1], $result['data']);
+    assert_equals(4, $result['errors'][0]['offset']);
+    assert_equals(['data' => [], 'errors' => []], Sift::decodeRecords('[ ]'));
+    assert_equals(1, Sift::decodeRecords('[1, ]')['errors'][0]['index']);
+});
+
+test('decodeRecords applies options per element', function () {
+    $result = Sift::decodeRecords('[{"k": 1}]', ['assoc' => false]);
+    assert_true($result['data'][0] instanceof \stdClass);
+});
+
+test('decodeRecords rejects an unknown onError mode', function () {
+    assert_throws(fn() => Sift::decodeRecords('[1]', null, 'drop'));
+});
+
+// ============================================================================
+// Pool Tests
+// ============================================================================
+
+echo "\n=== Pool Tests ===\n";
+
+test('Pool adds documents and reads them by id', function () {
+    $pool = Sift::pool();
+    assert_true($pool instanceof \Sift\Pool);
+    $a = $pool->addDocument('{"event": {"type": "push"}, "data": [1, 2]}');
+    $b = $pool->addDocument('{"event": {"type": "issue"}}');
+    assert_equals([0, 1], [$a, $b]);
+    assert_equals('push', $pool->get($a, '/event/type'));
+    assert_equals('issue', $pool->get($b, '/event/type'));
+    assert_equals(['event' => ['type' => 'issue']], $pool->get($b, ''));
+    assert_equals(2, $pool->count());
+});
+
+test('Pool has and getAll', function () {
+    $pool = Sift::pool();
+    $pool->addDocument('{"a": 1}');
+    $pool->addDocument('{"b": 2}');
+    $pool->addDocument('{"a": 3}');
+    assert_true($pool->has(0, '/a'));
+    assert_false($pool->has(1, '/a'));
+    assert_equals([0 => 1, 2 => 3], $pool->getAll('/a'));
+});
+
+test('Pool rejects invalid documents and unknown ids', function () {
+    $pool = Sift::pool();
+    assert_throws(fn() => $pool->addDocument('{"a": '));
+    assert_equals(0, $pool->count());
+    $pool->addDocument('[1]');
+    assert_throws(fn() => $pool->get(1, ''));
+    assert_throws(fn() => $pool->get(-1, ''));
+    assert_throws(fn() => $pool->has(5, '/a'));
+    assert_throws(fn() => $pool->get(0, '/5'));
+    assert_throws(fn() => $pool->getAll('no-slash'));
+});
+
+test('Pool clear resets count and size', function () {
+    $pool = Sift::pool(['assoc' => false]);
+    $pool->addDocument('{"k": {"v": 1}}');
+    assert_equals(15, $pool->size());
+    assert_true($pool->get(0, '/k') instanceof \stdClass);
+    $pool->clear();
+    assert_equals([0, 0], [$pool->count(), $pool->size()]);
+    $id = $pool->addDocument('true');
+    assert_true($id !== 0);
+    assert_true($pool->get($id, ''));
+    assert_equals([$id => true], $pool->getAll(''));
+});
+
+test('Pool ids kept past clear are stale', function () {
+    $pool = Sift::pool();
+    $old = $pool->addDocument('{"type": "old"}');
+    $pool->clear();
+    $new = $pool->addDocument('{"type": "new"}');
+    assert_throws(fn() => $pool->get($old, '/type'), 'stale');
+    assert_throws(fn() => $pool->has($old, '/type'), 'stale');
+    assert_equals('new', $pool->get($new, '/type'));
+    try {
+        $pool->get($old, '/type');
+    } catch (\Sift\StaleHandleException $e) {
+        assert_equals(\Sift\Exception::STALE_HANDLE, $e->getErrorCode());
+        assert_equals(10, $e->getErrorCode());
+    }
+    // An id from a later generation than the pool's is just unknown
+    assert_throws(fn() => $pool->get($new + (1 << 32), ''), 'Unknown document id');
+    assert_true(is_subclass_of(\Sift\StaleHandleException::class, \Sift\Exception::class));
+});
+
+// ============================================================================
+// Extract Tests
+// ============================================================================
+
+echo "\n=== Extract Tests ===\n";
+
+$extractJson = '{"data":{"user":{"name":"Ada"},"items":[{"id":1,"qty":2,"tag":"a"},{"id":2,"qty":5,"tag":"b"}],"meta":{"x":1,"y":2}}}';
+
+test('extract maps pointers into a nested shape', function() use ($extractJson) {
+    $result = Sift::extract($extractJson, [
+        'user' => '/data/user/name',
+        'info' => ['first' => '/data/items/0/id'],
+    ]);
+    assert_equals(['user' => 'Ada', 'info' => ['first' => 1]], $result);
+});
+
+test('extract applies a template to each wildcard match', function() use ($extractJson) {
+    $result = Sift::extract($extractJson, [
+        'items' => ['/data/items/*', ['id' => '/id', 'qty' => '/qty']],
+    ]);
+    assert_equals(['items' => [['id' => 1, 'qty' => 2], ['id' => 2, 'qty' => 5]]], $result);
+});
+
+test('extract wildcard pointers give lists and keyed maps', function() use ($extractJson) {
+    $result = Sift::extract($extractJson, ['tags' => '/data/items/*/tag', 'meta' => '/data/meta/*']);
+    assert_equals(['tags' => ['a', 'b'], 'meta' => ['x' => 1, 'y' => 2]], $result);
+});
+
+test('extract gives null for missing paths', function() use ($extractJson) {
+    $result = Sift::extract($extractJson, ['missing' => '/data/nope', 'wild' => '/data/user/name/*']);
+    assert_equals(['missing' => null, 'wild' => null], $result);
+});
+
+test('extract rejects an invalid template', function() use ($extractJson) {
+    assert_throws(function() use ($extractJson) {
+        Sift::extract($extractJson, ['bad' => 'data/user']);
+    });
+    assert_throws(function() use ($extractJson) {
+        Sift::extract($extractJson, ['bad' => 42]);
+    });
+});
+
+test('extract rejects invalid JSON', function() {
+    assert_throws(function() {
+        Sift::extract('{"a":', ['a' => '/a']);
+    });
+});
+
+// ============================================================================
+// Extractor Tests
+// ============================================================================
+
+echo "\n=== Extractor Tests ===\n";
+
+test('compileExtractor reuses a template across documents', function() {
+    $extractor = Sift::compileExtractor(['id' => '/id', 'names' => '/tags/*/name']);
+    assert_true($extractor instanceof \Sift\Extractor);
+    assert_equals(['id' => 1, 'names' => ['a']], $extractor->extract('{"id":1,"tags":[{"name":"a"}]}'));
+    assert_equals(['id' => 2, 'names' => []], $extractor->extract('{"id":2,"tags":[]}'));
+});
+
+test('compileExtractor matches extract', function() {
+    $json = '{"data":{"items":[{"id":1},{"id":2}]}}';
+    $template = ['ids' => ['/data/items/*', ['id' => '/id']]];
+    assert_equals(Sift::extract($json, $template), Sift::compileExtractor($template)->extract($json));
+});
+
+test('compileExtractor rejects an invalid template before any document', function() {
+    assert_throws(function() {
+        Sift::compileExtractor(['bad' => 'no-slash']);
+    });
+});
+
+test('Extractor extract rejects invalid JSON', function() {
+    $extractor = Sift::compileExtractor(['a' => '/a']);
+    assert_throws(function() use ($extractor) {
+        $extractor->extract('not json');
+    });
+});
+
+// ============================================================================
+// Query Defaults Tests
+// ============================================================================
+
+echo "\n=== Query Defaults Tests ===\n";
+
+test('withDefaultsFrom falls back for missing paths', function() {
+    $q = Sift::query('{"ui":{"theme":"dark"}}')
+        ->withDefaultsFrom('{"ui":{"theme":"light","lang":"en"},"beta":false}');
+    assert_equals('dark', $q->pointer('/ui/theme')->string());
+    assert_equals('en', $q->pointer('/ui/lang')->string());
+    assert_false($q->get('beta')->bool());
+});
+
+test('withDefaultsFrom tries defaults in order', function() {
+    $q = Sift::query('{}')
+        ->withDefaultsFrom('{"a":1}')
+        ->withDefaultsFrom('{"a":2,"b":3}');
+    assert_equals(1, $q->get('a')->int());
+    assert_equals(3, $q->get('b')->int());
+});
+
+test('withDefaultsFrom does not merge objects', function() {
+    $q = Sift::query('{"ui":{"theme":"dark"}}')->withDefaultsFrom('{"ui":{"lang":"en"}}');
+    assert_equals(['theme' => 'dark'], $q->get('ui')->value());
+});
+
+test('withDefaultsFrom treats null as present', function() {
+    $q = Sift::query('{"a":null}')->withDefaultsFrom('{"a":1}');
+    assert_true($q->get('a')->isNull());
+});
+
+test('withDefaultsFrom still throws when no layer has the path', function() {
+    $q = Sift::query('{"a":1}')->withDefaultsFrom('{"b":2}');
+    assert_throws(function() use ($q) {
+        $q->get('c')->value();
+    });
+});
+
+test('withDefaultsFrom siblings with different defaults do not share cached prefixes', function() {
+    $q = Sift::query('{}');
+    $a = $q->withDefaultsFrom('{"user":{"name":"from-b","id":1}}');
+    $b = $q->withDefaultsFrom('{"padding":"xxxxxxxxxxxxxxxxxxxxxxxx","user":{"id":2,"name":"from-c"}}');
+    assert_equals('from-b', $a->pointer('/user/name')->string());
+    assert_equals('from-c', $b->pointer('/user/name')->string());
+    assert_equals(2, $b->pointer('/user/id')->int());
+    assert_equals(1, $a->pointer('/user/id')->int());
+});
+
+test('withDefaultsFrom chunks an array from a default', function() {
+    $q = Sift::query('{}')->withDefaultsFrom('{"rows":[1,2,3]}');
+    $chunks = iterator_to_array($q->get('rows')->chunks(2));
+    assert_equals([[1, 2], [3]], $chunks);
+});
+
+// ============================================================================
+// Interpolate Option Tests
+// ============================================================================
+
+echo "\n=== Interpolate Option Tests ===\n";
+
+test('interpolate substitutes from a variables array', function() {
+    $json = '{"greeting":"Hello {{ name }}","dsn":"host=${HOST};port=${PORT}","n":1}';
+    $data = Sift::decode($json, ['interpolate' => ['name' => 'Ada', 'HOST' => 'db', 'PORT' => '5432']]);
+    assert_equals(['greeting' => 'Hello Ada', 'dsn' => 'host=db;port=5432', 'n' => 1], $data);
+});
+
+test('interpolate reads the environment', function() {
+    putenv('SIFT_TEST_INTERPOLATE=from-env');
+    $data = Sift::decode('{"v":"${SIFT_TEST_INTERPOLATE}"}', ['interpolate' => 'env']);
+    assert_equals(['v' => 'from-env'], $data);
+});
+
+test('interpolate leaves keys and malformed placeholders alone', function() {
+    $data = Sift::decode('{"${A}":"$ {A} ${} {{ }} ${A"}', ['interpolate' => ['A' => 'x']]);
+    assert_equals(['${A}' => '$ {A} ${} {{ }} ${A'], $data);
+});
+
+test('interpolate throws for undefined variables', function() {
+    assert_throws(function() {
+        Sift::decode('{"a":["${MISSING_VAR}"]}', ['interpolate' => []]);
+    });
+});
+
+test('interpolate rejects invalid option values', function() {
+    assert_throws(function() {
+        Sift::decode('{}', ['interpolate' => 'nope']);
+    });
+    assert_throws(function() {
+        Sift::decode('{}', ['interpolate' => ['a' => 1]]);
+    });
+});
+
+test('interpolate is off by default', function() {
+    assert_equals(['v' => '${HOME}'], Sift::decode('{"v":"${HOME}"}'));
+});
+
+// ============================================================================
+// Signed Decode Tests
+// ============================================================================
+
+echo "\n=== Signed Decode Tests ===\n";
+
+test('canonicalize sorts keys and drops whitespace', function() {
+    $json = "{ \"b\": {\"d\": null, \"c\": \"x\\u00e9\"},\n \"a\": [1, 2.50] }";
+    assert_equals('{"a":[1,2.50],"b":{"c":"xé","d":null}}', Sift::canonicalize($json));
+});
+
+test('decodeSigned verifies a detached signature', function() {
+    $json = '{"user": 1, "roles": ["admin"]}';
+    $signature = hash_hmac('sha256', Sift::canonicalize($json), 'secret');
+    assert_equals(['user' => 1, 'roles' => ['admin']], Sift::decodeSigned($json, 'secret', $signature));
+    $reformatted = "{\"roles\":[\"admin\"],\n\"user\":1}";
+    assert_equals(['user' => 1, 'roles' => ['admin']], Sift::decodeSigned($reformatted, 'secret', $signature));
+});
+
+test('decodeSigned verifies an envelope payload', function() {
+    $payload = '{"id":7}';
+    $signature = hash_hmac('sha256', Sift::canonicalize($payload), 'secret');
+    $envelope = '{"payload":' . $payload . ',"signature":"' . $signature . '"}';
+    assert_equals(['id' => 7], Sift::decodeSigned($envelope, 'secret'));
+});
+
+test('decodeSigned throws on a tampered payload or wrong key', function() {
+    $signature = hash_hmac('sha256', Sift::canonicalize('{"id":7}'), 'secret');
+    assert_throws(function() use ($signature) {
+        Sift::decodeSigned('{"id":8}', 'secret', $signature);
+    });
+    assert_throws(function() use ($signature) {
+        Sift::decodeSigned('{"id":7}', 'other', $signature);
+    });
+});
+
+test('decodeSigned rejects malformed signatures and envelopes', function() {
+    assert_throws(function() {
+        Sift::decodeSigned('{"id":7}', 'secret', 'abc');
+    });
+    assert_throws(function() {
+        Sift::decodeSigned('[1,2]', 'secret');
+    });
+    assert_throws(function() {
+        Sift::decodeSigned('{"payload":{}}', 'secret');
+    });
+});
+
+// ============================================================================
+// Raw Spill Tests
+// ============================================================================
+
+echo "\n=== Raw Spill Tests ===\n";
+
+test('rawSpill returns small values inline', function() {
+    $raw = Sift::query('{"a": [1, 2]}')->get('a')->rawSpill(100);
+    assert_equals(['size' => 6, 'raw' => '[1, 2]', 'path' => null], $raw);
+});
+
+test('rawSpill writes large values to a temp file', function() {
+    $raw = Sift::query('{"a": {"b": "xyz"}}')->get('a')->rawSpill(4);
+    assert_equals(12, $raw['size']);
+    assert_true($raw['raw'] === null);
+    assert_true(is_file($raw['path']));
+    assert_equals('{"b": "xyz"}', file_get_contents($raw['path']));
+    unlink($raw['path']);
+});
+
+test('rawSpill honours the directory argument', function() {
+    $dir = sys_get_temp_dir() . '/sift-spill-' . getmypid();
+    @mkdir($dir);
+    $raw = Sift::query('[1,2,3]')->rawSpill(0, $dir);
+    assert_equals($dir, dirname($raw['path']));
+    unlink($raw['path']);
+    rmdir($dir);
+});
+
+test('rawSpill rejects a negative threshold and a missing directory', function() {
+    assert_throws(function() {
+        Sift::query('[1]')->rawSpill(-1);
+    });
+    assert_throws(function() {
+        Sift::query('[1]')->rawSpill(0, '/nonexistent/sift/dir');
+    });
+});
+
+// ============================================================================
+// Query Memoization Tests
+// ============================================================================
+
+echo "\n=== Query Memoization Tests ===\n";
+
+test('repeated hydrators on one Query agree', function() {
+    $q = Sift::query('{"user": {"name": "Ada", "tags": [1, 2]}}')->get('user');
+    assert_equals('object', $q->getType());
+    assert_false($q->isNull());
+    assert_equals('{"name": "Ada", "tags": [1, 2]}', $q->raw());
+    assert_equals(['name' => 'Ada', 'tags' => [1, 2]], $q->value());
+    assert_equals('object', $q->getType());
+});
+
+test('derived Queries resolve their own paths after the parent resolved', function() {
+    $q = Sift::query('{"a": {"b": 1, "c": [2]}}');
+    $a = $q->get('a');
+    assert_equals('object', $a->getType());
+    assert_equals(1, $a->get('b')->int());
+    assert_equals([2], $a->get('c')->value());
+    assert_equals('object', $q->getType());
+});
+
+test('failed resolution keeps throwing on a memoizing Query', function() {
+    $q = Sift::query('{"a": 1}')->get('missing');
+    assert_throws(function() use ($q) { $q->value(); });
+    assert_throws(function() use ($q) { $q->getType(); });
+});
+
+test('withDefaultsFrom and followRefs do not reuse a resolved value', function() {
+    $q = Sift::query('{"a": {"$ref": "#/b"}, "b": 5}')->get('a');
+    assert_equals('object', $q->getType());
+    assert_equals(5, $q->followRefs()->int());
+    $miss = Sift::query('{}')->get('x');
+    assert_throws(function() use ($miss) { $miss->value(); });
+    assert_equals(3, $miss->withDefaultsFrom('{"x": 3}')->int());
+});
+
+// ============================================================================
+// Shared Prefix Cache Tests
+// ============================================================================
+
+echo "\n=== Shared Prefix Cache Tests ===\n";
+
+test('sibling Queries read the right fields through a shared prefix', function() {
+    $root = Sift::query('{"users": [{"id": 1, "name": "Ada"}, {"id": 2, "name": "Bob", "tags": ["x"]}]}');
+    $user = $root->get('users')->index(1);
+    assert_equals(2, $user->get('id')->int());
+    assert_equals('Bob', $user->get('name')->string());
+    assert_equals('x', $user->get('tags')->index(0)->string());
+    assert_equals('Ada', $root->pointer('/users/0/name')->string());
+    assert_equals('Bob', $root->pointer('/users/1/name')->string());
+});
+
+test('a cached prefix does not hide missing or scalar paths', function() {
+    $root = Sift::query('{"a": {"b": 1}}');
+    assert_equals(1, $root->pointer('/a/b')->int());
+    assert_throws(function() use ($root) { $root->pointer('/a/c')->value(); });
+    assert_throws(function() use ($root) { $root->pointer('/a/b/c')->value(); });
+    assert_equals(['b' => 1], $root->get('a')->value());
+});
+
+test('the prefix cache keeps layers apart', function() {
+    $q = Sift::query('{"a": {"x": 1}}')->withDefaultsFrom('{"a": {"x": 9, "y": 2}}');
+    assert_equals(1, $q->pointer('/a/x')->int());
+    assert_equals(2, $q->pointer('/a/y')->int());
+    assert_equals(1, $q->pointer('/a/x')->int());
+});
+
+// ============================================================================
+// Strict Hydrator Tests
+// ============================================================================
+
+echo "\n=== Strict Hydrator Tests ===\n";
+
+$strictJson = '{"n": 42, "neg": -7, "f": 1.0, "e": 1e3, "z": -0, "s": "5", "big": 9223372036854775808, "t": true, "one": 1, "str": "true", "nil": null}';
+
+test('intStrict accepts integer literals only', function() use ($strictJson) {
+    $q = Sift::query($strictJson);
+    assert_equals(42, $q->get('n')->intStrict());
+    assert_equals(-7, $q->get('neg')->intStrict());
+    foreach (['f', 'e', 'z', 's', 'big', 't', 'nil'] as $key) {
+        assert_throws(function() use ($q, $key) { $q->get($key)->intStrict(); });
+    }
+});
+
+test('intStrict errors carry pointer and types', function() use ($strictJson) {
+    $e = catch_sift(fn() => Sift::query($strictJson)->get('f')->intStrict());
+    assert_equals(\Sift\Exception::TYPE_ERROR, $e->getErrorCode());
+    assert_equals('/f', $e->getPointer());
+    assert_equals('integer', $e->getExpectedType());
+    assert_true(str_contains($e->getMessage(), 'float'));
+});
+
+test('boolStrict accepts true and false only', function() use ($strictJson) {
+    $q = Sift::query($strictJson);
+    assert_true($q->get('t')->boolStrict());
+    assert_false(Sift::query('[false]')->index(0)->boolStrict());
+    foreach (['one', 'str', 'nil', 'n'] as $key) {
+        assert_throws(function() use ($q, $key) { $q->get($key)->boolStrict(); });
+    }
+    $e = catch_sift(fn() => $q->get('str')->boolStrict());
+    assert_equals('/str', $e->getPointer());
+    assert_equals('boolean', $e->getExpectedType());
+});
+
+// ============================================================================
+// Same Members Tests
+// ============================================================================
+
+echo "\n=== Same Members Tests ===\n";
+
+test('sameMembers ignores element order', function() {
+    assert_true(Sift::sameMembers('[1, 2, 3]', '[3, 1, 2]'));
+    assert_true(Sift::sameMembers('[{"a":1,"b":2}, "x"]', '["x", {"b":2, "a":1}]'));
+    assert_true(Sift::sameMembers('[]', ' [ ] '));
+});
+
+test('sameMembers compares multisets', function() {
+    assert_true(Sift::sameMembers('[1, 2, 2]', '[2, 1, 2]'));
+    assert_false(Sift::sameMembers('[1, 2]', '[1, 2, 2]'));
+    assert_false(Sift::sameMembers('[1, 1, 2]', '[1, 2, 2]'));
+    assert_false(Sift::sameMembers('[1]', '[1.0]'));
+    assert_false(Sift::sameMembers('[[1, 2]]', '[[2, 1]]'));
+});
+
+test('sameMembers matches by key', function() {
+    $left = '[{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]';
+    assert_true(Sift::sameMembers($left, '[{"id": 2}, {"id": 1, "name": "z"}]', 'id'));
+    assert_false(Sift::sameMembers($left, '[{"id": 2}, {"id": 3}]', 'id'));
+    $e = catch_sift(fn() => Sift::sameMembers($left, '[{"id": 1}, {"name": "b"}]', 'id'));
+    assert_equals(\Sift\Exception::KEY_NOT_FOUND, $e->getErrorCode());
+});
+
+test('sameMembers rejects non-arrays', function() {
+    assert_throws(fn() => Sift::sameMembers('{"a": 1}', '[]'));
+    assert_throws(fn() => Sift::sameMembers('[1', '[1]'));
+});
+
+// ============================================================================
+// Randomize Tests
+// ============================================================================
+
+echo "\n=== Randomize Tests ===\n";
+
+$sampleDoc = '{"email": "Ann.Lee@corp.io", "age": 42, "score": -3.25, "big": 1.5e10, "ok": true, "none": null, "tags": ["a", "bb"], "nested": {"id": "550e8400-e29b-41d4-a716-446655440000"}}';
+
+test('randomize keeps structure and types', function() use ($sampleDoc) {
+    $out = json_decode(Sift::randomize($sampleDoc, 1), true);
+    $in = json_decode($sampleDoc, true);
+    assert_equals(array_keys($in), array_keys($out));
+    assert_equals(2, count($out['tags']));
+    assert_true(is_int($out['age']));
+    assert_true($out['age'] >= 10 && $out['age'] <= 99);
+    assert_true(is_float($out['score']) && $out['score'] <= 0);
+    assert_true(is_bool($out['ok']));
+    assert_equals(null, $out['none']);
+});
+
+test('randomize keeps string shape', function() use ($sampleDoc) {
+    $out = json_decode(Sift::randomize($sampleDoc, 2), true);
+    assert_equals(16, strlen($out['email']));
+    assert_true((bool) preg_match('/^[A-Z][a-z]{2}\.[A-Z][a-z]{2}@[a-z]{4}\.[a-z]{2}$/', $out['email']));
+    assert_true((bool) preg_match('/^[0-9a-z]{8}-[0-9a-z]{4}-[0-9a-z]{4}-[0-9a-z]{4}-[0-9a-z]{12}$/', $out['nested']['id']));
+    assert_equals(2, strlen($out['tags'][1]));
+});
+
+test('randomize is reproducible with a seed', function() use ($sampleDoc) {
+    assert_equals(Sift::randomize($sampleDoc, 7), Sift::randomize($sampleDoc, 7));
+    assert_true(Sift::randomize($sampleDoc, 7) !== Sift::randomize($sampleDoc, 8));
+    assert_true(Sift::isValid(Sift::randomize($sampleDoc)));
+});
+
+test('randomize rejects invalid JSON', function() {
+    assert_throws(fn() => Sift::randomize('{"a":'));
+});
+
+// ============================================================================
+// Anonymize Tests
+// ============================================================================
+
+echo "\n=== Anonymize Tests ===\n";
+
+$people = '{"users": [{"name": "Ann", "email": "ann@corp.io", "age": 42, "vip": true},
+  {"name": "Bob", "email": "bob@corp.io", "age": 37, "vip": false}], "meta": {"email": "ops@corp.io"}}';
+
+test('anonymize hashes by member name at any depth', function() use ($people) {
+    $out = json_decode(Sift::anonymize($people, ['email' => 'hash']), true);
+    assert_equals(hash('sha256', 'ann@corp.io'), $out['users'][0]['email']);
+    assert_equals(hash('sha256', 'ops@corp.io'), $out['meta']['email']);
+    assert_equals('Ann', $out['users'][0]['name']);
+    $salted = json_decode(Sift::anonymize($people, ['email' => ['hash', 'pepper']]), true);
+    assert_equals(hash('sha256', 'pepperbob@corp.io'), $salted['users'][1]['email']);
+});
+
+test('anonymize applies pointer patterns', function() use ($people) {
+    $out = json_decode(Sift::anonymize($people, ['/users/*/age' => 'zero', '/users/1' => 'null']), true);
+    assert_equals(0, $out['users'][0]['age']);
+    assert_equals(null, $out['users'][1]);
+    assert_equals('ops@corp.io', $out['meta']['email']);
+});
+
+test('anonymize zeroes scalars inside matched containers', function() use ($people) {
+    $out = json_decode(Sift::anonymize($people, ['/users/0' => 'zero']), true);
+    assert_equals(['name' => '', 'email' => '', 'age' => 0, 'vip' => false], $out['users'][0]);
+    assert_equals('Bob', $out['users'][1]['name']);
+});
+
+test('anonymize fakes consistently from a word list', function() {
+    $words = ['Alex', 'Sam', 'Kim'];
+    $out = json_decode(Sift::anonymize('["Ann", "Bob", "Ann"]', ['/*' => ['fake', $words]]), true);
+    assert_true(in_array($out[0], $words, true));
+    assert_equals($out[0], $out[2]);
+});
+
+test('anonymize copies untouched bytes verbatim', function() {
+    $json = "{ \"keep\" : 1.50 ,\n \"secret\" : \"x\" }";
+    assert_equals("{ \"keep\" : 1.50 ,\n \"secret\" : null }", Sift::anonymize($json, ['secret' => 'null']));
+});
+
+test('anonymize rejects malformed rules', function() use ($people) {
+    assert_throws(fn() => Sift::anonymize($people, ['email' => 'shred']));
+    assert_throws(fn() => Sift::anonymize($people, ['name' => 'fake']));
+    assert_throws(fn() => Sift::anonymize($people, ['name' => ['fake', []]]));
+    assert_throws(fn() => Sift::anonymize('{"a":', ['a' => 'null']));
+});
+
+// ============================================================================
+// Field Encryption Tests
+// ============================================================================
+
+echo "\n=== Field Encryption Tests ===\n";
+
+$fieldKey = str_repeat("\x42", 32);
+
+test('encryptFields seals values and decryptFields restores them', function() use ($fieldKey) {
+    $json = '{"user": {"name": "Ann", "ssn": "123-45-6789"}, "card": {"no": 4111, "exp": "12/29"}}';
+    $stored = Sift::encryptFields($json, ['/user/ssn', '/card'], $fieldKey);
+    $decoded = json_decode($stored, true);
+    assert_equals('Ann', $decoded['user']['name']);
+    assert_true(is_string($decoded['card']));
+    assert_false(str_contains($stored, '4111'));
+    assert_equals($json, Sift::decryptFields($stored, ['/user/ssn', '/card'], $fieldKey));
+});
+
+test('encryptFields uses a fresh nonce each time', function() use ($fieldKey) {
+    $a = Sift::encryptFields('{"a": 1}', ['/a'], $fieldKey);
+    $b = Sift::encryptFields('{"a": 1}', ['/a'], $fieldKey);
+    assert_true($a !== $b);
+});
+
+test('encryptFields output opens with ext-sodium', function() use ($fieldKey) {
+    if (!function_exists('sodium_crypto_secretbox_open')) {
+        return;
+    }
+    $stored = Sift::encryptFields('{"a": [1, 2]}', ['/a'], $fieldKey);
+    $raw = base64_decode(json_decode($stored, true)['a']);
+    assert_equals('[1, 2]', sodium_crypto_secretbox_open(substr($raw, 24), substr($raw, 0, 24), $fieldKey));
+});
+
+test('decryptFields rejects a wrong key or tampered field', function() use ($fieldKey) {
+    $stored = Sift::encryptFields('{"a": "secret"}', ['/a'], $fieldKey);
+    assert_throws(fn() => Sift::decryptFields($stored, ['/a'], str_repeat("\x43", 32)));
+    $raw = base64_decode(json_decode($stored, true)['a']);
+    $raw[30] = chr(ord($raw[30]) ^ 1);
+    $tampered = json_encode(['a' => base64_encode($raw)]);
+    assert_throws(fn() => Sift::decryptFields($tampered, ['/a'], $fieldKey));
+    assert_throws(fn() => Sift::decryptFields('{"a": 5}', ['/a'], $fieldKey));
+});
+
+test('encryptFields rejects a short key or missing pointer', function() use ($fieldKey) {
+    assert_throws(fn() => Sift::encryptFields('{"a": 1}', ['/a'], 'short'));
+    assert_throws(fn() => Sift::encryptFields('{"a": 1}', ['/b'], $fieldKey));
+});
+
+// ============================================================================
+// Numbers Option Tests
+// ============================================================================
+
+echo "\n=== Numbers Option Tests ===\n";
+
+test('numbers keeps matched literals as strings', function() {
+    $json = '{"amounts": [19.90, 0.1000, 12345678901234567890], "total": 19.90}';
+    $data = Sift::decode($json, ['numbers' => ['/amounts/*' => 'string']]);
+    assert_equals(['19.90', '0.1000', '12345678901234567890'], $data['amounts']);
+    assert_equals(19.9, $data['total']);
+});
+
+test('numbers rounds floats to decimal places', function() {
+    $json = '{"rate": 0.123456789, "fee": 1.005, "neg": -2.675, "count": 7, "big": 9.99}';
+    $data = Sift::decode($json, ['numbers' => ['/rate' => 4, '/fee' => 2, '/neg' => 2, '/count' => 2, '/big' => 0]]);
+    assert_equals(0.1235, $data['rate']);
+    assert_equals(1.01, $data['fee']);
+    assert_equals(-2.68, $data['neg']);
+    assert_equals(7, $data['count']);
+    assert_equals(10.0, $data['big']);
+});
+
+test('numbers applies the first matching pattern', function() {
+    $json = '{"items": [{"price": 1.50, "weight": 0.25}]}';
+    $data = Sift::decode($json, ['numbers' => ['/items/*/weight' => 'float', '/items/*/*' => 'string']]);
+    assert_equals(['price' => '1.50', 'weight' => 0.25], $data['items'][0]);
+});
+
+test('numbers leaves unmatched values and non-numbers alone', function() {
+    $data = Sift::decode('{"a": "1.50", "b": [1.50]}', ['numbers' => ['/a' => 'string', '/b' => 'string']]);
+    assert_equals(['a' => '1.50', 'b' => [1.5]], $data);
+});
+
+test('numbers rejects invalid policies', function() {
+    assert_throws(fn() => Sift::decode('{}', ['numbers' => 'string']));
+    assert_throws(fn() => Sift::decode('{}', ['numbers' => ['amounts' => 'string']]));
+    assert_throws(fn() => Sift::decode('{}', ['numbers' => ['/a' => 'decimal']]));
+    assert_throws(fn() => Sift::decode('{}', ['numbers' => ['/a' => 18]]));
+});
+
+// ============================================================================
+// Convert Numbers To Strings Tests
+// ============================================================================
+
+echo "\n=== Convert Numbers To Strings Tests ===\n";
+
+test('convertNumbersToStrings quotes unsafe integers by default', function() {
+    $json = '{"id": 12345678901234567890, "n": 9007199254740991, "m": -9007199254740993, "f": 1.5}';
+    assert_equals(
+        '{"id": "12345678901234567890", "n": 9007199254740991, "m": "-9007199254740993", "f": 1.5}',
+        Sift::convertNumbersToStrings($json)
+    );
+});
+
+test('convertNumbersToStrings quotes every number under pointers', function() {
+    $json = '{"ids": [1, 2.50, {"x": 3}], "total": 10, "count": 4}';
+    assert_equals(
+        '{"ids": ["1", "2.50", {"x": "3"}], "total": "10", "count": 4}',
+        Sift::convertNumbersToStrings($json, ['/ids', '/total', '/ids/0'])
+    );
+});
+
+test('convertNumbersToStrings applies a custom threshold', function() {
+    $json = "[1,\n 1000 , 50000, \"text\", -2e6]";
+    assert_equals("[1,\n 1000 , \"50000\", \"text\", \"-2e6\"]", Sift::convertNumbersToStrings($json, null, 1000));
+    assert_equals('{"a": [5, "50"]}', Sift::convertNumbersToStrings('{"a": [5, 50]}', ['/a'], 10));
+});
+
+test('convertNumbersToStrings rejects bad input', function() {
+    assert_throws(fn() => Sift::convertNumbersToStrings('{"a":'));
+    assert_throws(fn() => Sift::convertNumbersToStrings('{"a": 1}', ['/b']));
+    assert_throws(fn() => Sift::convertNumbersToStrings('{"a": 1}', null, -1));
+});
+
+// ============================================================================
+// NDJSON Conversion Tests
+// ============================================================================
+
+echo "\n=== NDJSON Conversion Tests ===\n";
+
+test('linesToArray joins records into an array', function() {
+    $ndjson = "{\"a\": 1}\r\n\n  [1, 2]\n\"text\"\n";
+    assert_equals('[{"a": 1},[1, 2],"text"]', Sift::linesToArray($ndjson));
+    assert_equals('[]', Sift::linesToArray("\n\n"));
+});
+
+test('linesToArray reports the bad line', function() {
+    try {
+        Sift::linesToArray("{\"a\": 1}\n{\"a\":\n");
+        assert_true(false, 'expected an exception');
+    } catch (\Sift\Exception $e) {
+        assert_true(str_contains($e->getMessage(), 'line 2'));
+    }
+});
+
+test('arrayToLines writes one element per line', function() {
+    $json = "[{\"a\": 1}, \"x y\",\n  {\n    \"b\": [1,\n 2],\n    \"s\": \"a b\"\n  }\n]";
+    assert_equals("{\"a\": 1}\n\"x y\"\n{\"b\":[1,2],\"s\":\"a b\"}\n", Sift::arrayToLines($json));
+    assert_equals('', Sift::arrayToLines('[]'));
+});
+
+test('arrayToLines round-trips with linesToArray', function() {
+    $json = '[{"id":1},{"id":2,"tags":["a"]},null]';
+    assert_equals($json, Sift::linesToArray(Sift::arrayToLines($json)));
+});
+
+test('arrayToLines rejects non-arrays and invalid JSON', function() {
+    assert_throws(fn() => Sift::arrayToLines('{"a": 1}'));
+    assert_throws(fn() => Sift::arrayToLines('[1,'));
+});
+
+// ============================================================================
+// Query::flattenArrays() Tests
+// ============================================================================
+
+echo "\n=== Query::flattenArrays() Tests ===\n";
+
+test('flattenArrays concatenates pages one level deep', function() {
+    $json = '{"pages": [[{"id": 1}, {"id": 2}], [], [{"id": 3}, [4, 5]], 6]}';
+    $rows = Sift::query($json)->get('pages')->flattenArrays();
+    assert_equals([['id' => 1], ['id' => 2], ['id' => 3], [4, 5], 6], $rows);
+});
+
+test('flattenArrays flattens recursively', function() {
+    $json = '[[1, [2, [3, [4]]]], 5, {"a": [6]}]';
+    assert_equals([1, 2, 3, 4, 5, ['a' => [6]]], Sift::query($json)->flattenArrays(true));
+});
+
+test('flattenArrays rejects non-arrays', function() {
+    assert_throws(fn() => Sift::query('{"a": 1}')->flattenArrays());
+    assert_throws(fn() => Sift::query('{"a": 1}')->get('b')->flattenArrays());
+});
+
+// ============================================================================
+// Zip Tests
+// ============================================================================
+
+echo "\n=== Zip Tests ===\n";
+
+test('zip pairs elements and pads with null', function() {
+    assert_equals('[[1,"a"],[2,"b"],[3,null]]', Sift::zip('[1, 2, 3]', '["a", "b"]'));
+    assert_equals('[[null,{"x": 1}]]', Sift::zip('[]', '[{"x": 1}]', 'pairs'));
+    assert_equals('[]', Sift::zip('[]', '[]'));
+});
+
+test('zip merges objects with the right side winning', function() {
+    $left = '[{"id": 1, "paid": false}, {"id": 2}, null]';
+    $right = '[{"paid": true, "ref": "x"}, null, {"id": 3}]';
+    $rows = json_decode(Sift::zip($left, $right, 'merge'), true);
+    assert_equals([['id' => 1, 'paid' => true, 'ref' => 'x'], ['id' => 2], ['id' => 3]], $rows);
+});
+
+test('zip rejects bad input', function() {
+    assert_throws(fn() => Sift::zip('{"a": 1}', '[]'));
+    assert_throws(fn() => Sift::zip('[1,', '[]'));
+    assert_throws(fn() => Sift::zip('[1]', '[2]', 'interleave'));
+    assert_throws(fn() => Sift::zip('[1]', '[{"a": 1}]', 'merge'));
+});
+
+// ============================================================================
+// Sift::splitTopLevel() Tests
+// ============================================================================
+
+echo "\n=== Sift::splitTopLevel() Tests ===\n";
+
+test('splitTopLevel fills shards up to the budget', function() {
+    assert_equals(['[1,22]', '[333]'], Sift::splitTopLevel('[1, 22, 333]', 7));
+    assert_equals(['[1,22,333]'], Sift::splitTopLevel('[1, 22, 333]', 100));
+    assert_equals([], Sift::splitTopLevel('[]', 2));
+});
+
+test('splitTopLevel shards are valid and keep every element', function() {
+    $rows = [];
+    for ($i = 0; $i < 50; $i++) {
+        $rows[] = ['id' => $i, 'name' => str_repeat('x', $i % 7)];
+    }
+    $shards = Sift::splitTopLevel(json_encode($rows), 128);
+    $joined = [];
+    foreach ($shards as $shard) {
+        assert_true(strlen($shard) <= 128);
+        $joined = array_merge($joined, json_decode($shard, true));
+    }
+    assert_equals($rows, $joined);
+});
+
+test('splitTopLevel rejects oversized elements and bad input', function() {
+    try {
+        Sift::splitTopLevel('[1, "too long"]', 8);
+        assert_true(false, 'expected an exception');
+    } catch (\Sift\Exception $e) {
+        assert_equals('/1', $e->getPointer());
+    }
+    assert_throws(fn() => Sift::splitTopLevel('{"a": 1}', 10));
+    assert_throws(fn() => Sift::splitTopLevel('[1,', 10));
+    assert_throws(fn() => Sift::splitTopLevel('[1]', 1));
+});
+
+test('concatArrays splices arrays together', function() {
+    assert_equals('[1, 2,{"a": 3}]', Sift::concatArrays(['[1, 2]', '[]', ' [ {"a": 3} ] ']));
+    assert_equals('[]', Sift::concatArrays([]));
+    assert_equals('[]', Sift::concatArrays(['[]', "[\n]"]));
+});
+
+test('concatArrays undoes splitTopLevel', function() {
+    $json = '[{"id":1},"two",[3],null,4.5]';
+    assert_equals($json, Sift::concatArrays(Sift::splitTopLevel($json, 12)));
+});
+
+test('concatArrays rejects invalid documents and non-arrays', function() {
+    try {
+        Sift::concatArrays(['[1]', '[2,']);
+        assert_true(false, 'expected an exception');
+    } catch (\Sift\Exception $e) {
+        assert_true(str_contains($e->getMessage(), 'Document 1'));
+    }
+    assert_throws(fn() => Sift::concatArrays(['[1]', '{"a": 1}']));
+});
+
+// ============================================================================
+// Key Order Tests
+// ============================================================================
+
+echo "\n=== Key Order Tests ===\n";
+
+test('decode keeps member order on every path', function() {
+    $json = '{"zeta": 1, "alpha": {"y": 2, "b": 3}, "mid": 4, "alpha2": 5}';
+    $expected = ['zeta', 'alpha', 'mid', 'alpha2'];
+    foreach ([[], ['strategy' => 'dom'], ['strategy' => 'lazy'], ['strict' => true]] as $options) {
+        $data = Sift::decode($json, $options);
+        assert_equals($expected, array_keys($data));
+        assert_equals(['y', 'b'], array_keys($data['alpha']));
+    }
+    assert_equals($expected, array_keys(get_object_vars(Sift::decode($json, ['assoc' => false]))));
+    assert_equals(['b', 'a'], array_keys(Sift::decode('{"b": 1, "a": 2, "b": 3}')));
+});
+
+test('encodeOrdered restores the original key order', function() {
+    $original = '{"name": "app", "version": 1, "deps": {"b": 1, "a": 2}, "list": [{"y": 1, "x": 2}]}';
+    $config = Sift::decode($original);
+    ksort($config);
+    ksort($config['deps']);
+    ksort($config['list'][0]);
+    $config['version'] = 2;
+    assert_equals(
+        '{"name":"app","version":2,"deps":{"b":1,"a":2},"list":[{"y":1,"x":2}]}',
+        Sift::encodeOrdered($config, $original)
+    );
+});
+
+test('encodeOrdered appends new keys in PHP order', function() {
+    $original = '{"a": 1, "b": {"c": 1}}';
+    $value = ['new2' => true, 'b' => ['d' => 0, 'c' => 1], 'new1' => null];
+    assert_equals('{"b":{"c":1,"d":0},"new2":true,"new1":null}', Sift::encodeOrdered($value, $original));
+    $object = (object) ['b' => 2, 'a' => 1];
+    assert_equals('{"a":1,"b":2}', Sift::encodeOrdered($object, $original));
+    assert_equals('[1,"x"]', Sift::encodeOrdered([1, 'x'], $original));
+});
+
+test('encodeOrdered rejects invalid originals and values', function() {
+    assert_throws(fn() => Sift::encodeOrdered(['a' => 1], '{"a":'));
+    assert_throws(fn() => Sift::encodeOrdered(['a' => NAN], '{"a": 1}'));
+});
+
+// ============================================================================
+// Sift::stripNulls() Tests
+// ============================================================================
+
+echo "\n=== Sift::stripNulls() Tests ===\n";
+
+test('stripNulls removes null members recursively', function() {
+    $json = '{"a": 1, "b": null, "c": {"d": null, "e": "x"}, "f": [null, {"g": null}]}';
+    assert_equals('{"a":1,"c":{"e":"x"},"f":[null,{}]}', Sift::stripNulls($json));
+    assert_equals('{"a":1,"c":{"d": null, "e": "x"},"f":[null, {"g": null}]}', Sift::stripNulls($json, false));
+});
+
+test('stripNulls removes empty members with empties', function() {
+    $json = '{"a": {}, "b": [], "c": {"d": null}, "e": [[]], "f": 0, "g": ""}';
+    assert_equals('{"e":[[]],"f":0,"g":""}', Sift::stripNulls($json, true, true));
+    assert_equals('{"c":{"d": null},"e":[[]],"f":0,"g":""}', Sift::stripNulls($json, false, true));
+});
+
+test('stripNulls keeps scalars, arrays and text', function() {
+    assert_equals('null', Sift::stripNulls('null'));
+    assert_equals('[1,null,"a b"]', Sift::stripNulls('[1, null, "a b"]'));
+    assert_equals('{"n":1.50E2}', Sift::stripNulls('{"n": 1.50E2, "z": null}'));
+    assert_throws(fn() => Sift::stripNulls('{"a":'));
+});
+
+// ============================================================================
+// Sift::defaultsDeep() Tests
+// ============================================================================
+
+echo "\n=== Sift::defaultsDeep() Tests ===\n";
+
+test('defaultsDeep fills missing members recursively', function() {
+    $settings = '{"theme": "dark", "mail": {"digest": null}}';
+    $defaults = '{"theme": "light", "mail": {"digest": "daily", "mentions": true}, "lang": "en"}';
+    assert_equals(
+        '{"theme":"dark","mail":{"digest":null,"mentions":true},"lang":"en"}',
+        Sift::defaultsDeep($settings, $defaults)
+    );
+});
+
+test('defaultsDeep never combines arrays or mismatched types', function() {
+    assert_equals('{"tags":["a"],"n":{"x":1}}', Sift::defaultsDeep('{"tags": ["a"], "n": {"x": 1}}', '{"tags": ["b", "c"], "n": 5}'));
+    assert_equals('[1]', Sift::defaultsDeep('[1]', '{"a": 1}'));
+    assert_equals('{"a":1}', Sift::defaultsDeep('{}', '{"a": 1}'));
+});
+
+test('defaultsDeep rejects invalid documents', function() {
+    assert_throws(fn() => Sift::defaultsDeep('{"a":', '{}'));
+    assert_throws(fn() => Sift::defaultsDeep('{}', '{"a":'));
+});
+
+// ============================================================================
+// Sift::renameKeys() Tests
+// ============================================================================
+
+echo "\n=== Sift::renameKeys() Tests ===\n";
+
+test('renameKeys rewrites only the key tokens', function() {
+    $json = "{ \"userName\" : \"ada\",\n  \"meta\": {\"userName\": 1, \"x\": [ {\"mail\":2} ]}, \"mail\": \"userName\" }";
+    $expected = "{ \"username\" : \"ada\",\n  \"meta\": {\"username\": 1, \"x\": [ {\"email\":2} ]}, \"email\": \"userName\" }";
+    assert_equals($expected, Sift::renameKeys($json, ['userName' => 'username', 'mail' => 'email']));
+});
+
+test('renameKeys matches escaped keys and scopes to a pointer', function() {
+    assert_equals('{"b":1}', Sift::renameKeys('{"\\u0061":1}', ['a' => 'b']));
+    assert_equals('{"id":1,"data":{"uuid":2}}', Sift::renameKeys('{"id":1,"data":{"id":2}}', ['id' => 'uuid'], '/data'));
+    assert_equals('{"1":true,"two":2}', Sift::renameKeys('{"1":true,"2":2}', ['2' => 'two']));
+});
+
+test('renameKeys rejects bad input', function() {
+    assert_throws(fn() => Sift::renameKeys('{"a":', ['a' => 'b']));
+    assert_throws(fn() => Sift::renameKeys('{"a": 1}', ['a' => 1]));
+    assert_throws(fn() => Sift::renameKeys('{"a": 1}', ['a' => 'b'], '/missing'));
+});
+
+// ============================================================================
+// Query::tryPointer() Tests
+// ============================================================================
+
+echo "\n=== Query::tryPointer() Tests ===\n";
+
+test('tryPointer returns a Query for existing paths', function() {
+    $q = Sift::query('{"user": {"address": {"city": "Oslo"}, "phone": null}}');
+    assert_equals('Oslo', $q->tryPointer('/user/address/city')?->string());
+    assert_true($q->tryPointer('/user/phone')->isNull());
+    assert_equals(['city' => 'Oslo'], $q->get('user')->tryPointer('/address')->value());
+});
+
+test('tryPointer returns null for missing paths', function() {
+    $q = Sift::query('{"user": {"name": "Ada", "tags": [1]}}');
+    assert_true($q->tryPointer('/user/address/city') === null);
+    assert_true($q->tryPointer('/user/name/first') === null);
+    assert_true($q->tryPointer('/user/tags/5') === null);
+    assert_equals('none', $q->tryPointer('/user/address/city')?->string() ?? 'none');
+});
+
+test('tryPointer rejects malformed pointers', function() {
+    assert_throws(fn() => Sift::query('{}')->tryPointer('user'));
+});
+
+// ============================================================================
+// Sift\MissingValue Tests
+// ============================================================================
+
+echo "\n=== Sift\\MissingValue Tests ===\n";
+
+test('lookup tells absent keys from null values', function() {
+    $q = Sift::query('{"user": {"name": "Ada", "phone": null}}');
+    $phone = $q->lookup('/user/phone');
+    assert_true($phone instanceof Sift\Query);
+    assert_false($phone->isMissing());
+    assert_equals(null, $phone->orElse('none'));
+
+    $email = $q->lookup('/user/email');
+    assert_true($email instanceof Sift\MissingValue);
+    assert_true($email->isMissing());
+    assert_equals('none', $email->orElse('none'));
+    assert_equals('/user/email', $email->path());
+});
+
+test('lookup chains stay soft on MissingValue', function() {
+    $q = Sift::query('{"user": {"name": "Ada"}}');
+    $zip = $q->lookup('/account')->lookup('/address/zip');
+    assert_true($zip->isMissing());
+    assert_equals('/account/address/zip', $zip->path());
+    assert_equals(0, $zip->orElse(0));
+    assert_throws(fn() => $zip->lookup('address'));
+});
+
+test('Query::orElse hydrates or falls back', function() {
+    $q = Sift::query('{"settings": {"limit": 5, "tags": ["a"]}}');
+    assert_equals(5, $q->pointer('/settings/limit')->orElse(100));
+    assert_equals(['a'], $q->pointer('/settings/tags')->orElse([]));
+    assert_equals(100, $q->pointer('/settings/max')->orElse(100));
+    assert_equals(100, $q->pointer('/settings/limit/x')->orElse(100));
+});
+
+// ============================================================================
+// Pointer Suggestion Tests
+// ============================================================================
+
+echo "\n=== Pointer Suggestion Tests ===\n";
+
+test('KeyNotFound suggests close keys', function() {
+    $json = '{"user": {"name": "Ada", "email": "ada@example.com", "emails": []}}';
+    try {
+        Sift::get($json, '/user/emial');
+        throw new Exception('Expected exception was not thrown');
+    } catch (Sift\Exception $e) {
+        assert_equals(Sift\Exception::KEY_NOT_FOUND, $e->getErrorCode());
+        assert_equals('/user/emial', $e->getPointer());
+        assert_equals(['/user/email', '/user/emails'], $e->getSuggestions());
+        assert_true(str_contains($e->getMessage(), 'did you mean /user/email or /user/emails?'));
+    }
+});
+
+test('Query reads suggest close keys', function() {
+    $q = Sift::query('{"data": {"items": [1, 2]}}');
+    try {
+        $q->pointer('/Data/items')->value();
+        throw new Exception('Expected exception was not thrown');
+    } catch (Sift\Exception $e) {
+        assert_equals(['/data'], $e->getSuggestions());
+    }
+    assert_throws(fn() => $q->get('data')->get('itme')->value(), 'did you mean /data/items?');
+});
+
+test('KeyNotFound without close keys has no suggestions', function() {
+    try {
+        Sift::get('{"user": {"name": "Ada"}}', '/user/address');
+        throw new Exception('Expected exception was not thrown');
+    } catch (Sift\Exception $e) {
+        assert_equals([], $e->getSuggestions());
+        assert_false(str_contains($e->getMessage(), 'did you mean'));
+    }
+    try {
+        Sift::get('{"tags": [1, 2]}', '/tags/5');
+        throw new Exception('Expected exception was not thrown');
+    } catch (Sift\Exception $e) {
+        assert_equals([], $e->getSuggestions());
+    }
+    assert_equals([], (new Sift\Exception('custom'))->getSuggestions());
+});
+
+// ============================================================================
+// Query Property Access Tests
+// ============================================================================
+
+echo "\n=== Query Property Access Tests ===\n";
+
+test('propertyAccess navigates properties and indices', function() {
+    $q = Sift::query('{"users": [{"email": "a@x.io"}, {"email": "b@x.io", "phone": null}]}')->propertyAccess();
+    assert_equals('b@x.io', $q->users[1]->email->string());
+    assert_equals('a@x.io', $q['users'][0]['email']->string());
+    assert_equals($q->get('users')->index(1)->get('email')->string(), $q->users[1]->email->string());
+    assert_true(isset($q->users[1]->email));
+    assert_false(isset($q->users[1]->phone));
+    assert_false(isset($q->users[5]));
+    assert_equals('none', $q->users[0]->phone->orElse('none'));
+});
+
+test('property access is off by default', function() {
+    $q = Sift::query('{"user": {"name": "Ada"}}');
+    assert_throws(fn() => $q->user, 'propertyAccess()');
+    assert_throws(fn() => $q['user'], 'propertyAccess()');
+    assert_false(isset($q->user));
+    assert_throws(fn() => $q->propertyAccess()->propertyAccess(false)->user, 'propertyAccess()');
+});
+
+test('property access rejects hydration names and writes', function() {
+    $q = Sift::query('{"price": {"value": 5}}')->propertyAccess();
+    assert_throws(fn() => $q->price->value, "get('value')");
+    assert_equals(5, $q->price->get('value')->int());
+    assert_throws(function() use ($q) { $q->price = 1; }, 'read-only');
+    assert_throws(function() use ($q) { $q['price'] = 1; }, 'read-only');
+    assert_throws(function() use ($q) { unset($q['price']); }, 'read-only');
+});
+
+// ============================================================================
+// Sift::from() Tests
+// ============================================================================
+
+echo "\n=== Sift::from() Tests ===\n";
+
+test('from() accepts JSON text', function() {
+    assert_equals('Ada', Sift::from('{"user": {"name": "Ada"}}')->pointer('/user/name')->string());
+    assert_equals([1, 2], Sift::from("  [1, 2]")->value());
+    assert_equals(42, Sift::from('42')->int());
+    assert_equals('x', Sift::from('"x"')->string());
+});
+
+test('from() reads SplFileInfo and streams, fromFile() reads paths', function() {
+    $path = tempnam(sys_get_temp_dir(), 'sift');
+    file_put_contents($path, '{"a": {"b": [1, 2, 3]}}');
+    try {
+        assert_equals(3, Sift::fromFile($path)->pointer('/a/b/2')->int());
+        assert_equals([1, 2, 3], Sift::from(new SplFileInfo($path))->pointer('/a/b')->value());
+
+        $stream = fopen($path, 'r');
+        assert_equals(2, Sift::from($stream)->pointer('/a/b/1')->int());
+        fclose($stream);
+
+        $memory = fopen('php://memory', 'w+');
+        fwrite($memory, 'ignored{"ok": true}');
+        fseek($memory, 7);
+        assert_true(Sift::from($memory)->get('ok')->bool());
+        fclose($memory);
+    } finally {
+        unlink($path);
+    }
+});
+
+test('from() re-encodes decoded values', function() {
+    $q = Sift::from(['user' => ['name' => 'Ada', 'tags' => ['a', 'b']]]);
+    assert_equals('b', $q->pointer('/user/tags/1')->string());
+    assert_equals('{"name":"Ada"}', Sift::from((object) ['name' => 'Ada'])->raw());
+});
+
+test('from() rejects unreadable sources', function() {
+    assert_throws(fn() => Sift::from(1.5), 'Source must be');
+    assert_throws(fn() => Sift::from('no/such/file.json')->value());
+    assert_throws(fn() => Sift::from(new SplFileInfo('/no/such/file.json')), 'Cannot open');
+    assert_throws(fn() => Sift::fromFile('/no/such/file.json'), 'Cannot open');
+});
+
+test('from() never reads a string as a path', function() {
+    $path = tempnam(sys_get_temp_dir(), 'sift');
+    file_put_contents($path, '{"secret": 1}');
+    try {
+        assert_throws(fn() => Sift::from($path)->value(), 'parse');
+        assert_throws(fn() => Sift::decodeFrom($path), 'parse');
+    } finally {
+        unlink($path);
+    }
+});
+
+test('file reads respect open_basedir', function() {
+    if (!function_exists('shell_exec')) {
+        return;
+    }
+    $dir = sys_get_temp_dir();
+    $outside = __FILE__;
+    $code = 'try { Sift::fromFile($argv[1]); echo "read"; } catch (Sift\\Exception $e) { echo $e->getMessage(); }';
+    $cmd = escapeshellarg(PHP_BINARY) . ' -d open_basedir=' . escapeshellarg($dir)
+        . ' -r ' . escapeshellarg($code) . ' ' . escapeshellarg($outside);
+    $output = (string) shell_exec($cmd);
+    if (!str_contains($output, 'read') && !str_contains($output, 'Cannot open')) {
+        return;  // extension not loaded in the child process
+    }
+    assert_true(str_contains($output, 'open_basedir restriction'), $output);
+});
+
+// ============================================================================
+// Sift::fromRequest() Tests
+// ============================================================================
+
+echo "\n=== Sift::fromRequest() Tests ===\n";
+
+test('fromRequest validates the size limit', function() {
+    assert_throws(fn() => Sift::fromRequest(0), 'Body size limit');
+    assert_throws(fn() => Sift::fromRequest(-5), 'Body size limit');
+    assert_throws(fn() => Sift::fromRequest(PHP_INT_MAX), 'Body size limit');
+});
+
+test('fromRequest rejects an empty body', function() {
+    // The CLI has no request body
+    assert_throws(fn() => Sift::fromRequest(), 'Empty request body');
+    assert_throws(fn() => Sift::fromRequest(1024, ['unknown' => true]), 'Unknown option');
+});
+
+// ============================================================================
+// PSR-7 Stream Tests
+// ============================================================================
+
+echo "\n=== PSR-7 Stream Tests ===\n";
+
+// Minimal stand-in for Psr\Http\Message\StreamInterface
+class SiftTestStream {
+    public int $reads = 0;
+    private int $position = 0;
+    public function __construct(private string $contents, private int $chunk = 3) {}
+    public function eof(): bool { return $this->position >= strlen($this->contents); }
+    public function read(int $length): string {
+        $this->reads++;
+        $data = substr($this->contents, $this->position, min($length, $this->chunk));
+        $this->position += strlen($data);
+        return $data;
+    }
+}
+
+test('from() and decodeFrom() read stream objects', function() {
+    $stream = new SiftTestStream('{"user": {"name": "Ada", "tags": [1, 2]}}');
+    assert_equals('Ada', Sift::from($stream)->pointer('/user/name')->string());
+    assert_true($stream->reads > 1);
+    assert_true($stream->eof());
+
+    $data = Sift::decodeFrom(new SiftTestStream('[1, {"a": null}]', 4));
+    assert_equals([1, ['a' => null]], $data);
+    assert_equals(['a' => 1], Sift::decodeFrom('{"a": 1}'));
+});
+
+test('stream objects are read from their current position', function() {
+    $stream = new SiftTestStream('xx{"ok": true}', 2);
+    $stream->read(2);
+    assert_equals(['ok' => true], Sift::decodeFrom($stream));
+    assert_throws(fn() => Sift::decodeFrom(new SiftTestStream('')), 'parse');
+});
+
+test('objects without read() and eof() are encoded', function() {
+    $q = Sift::from(new class { public $read = 1; public $eof = 2; });
+    assert_equals(['read' => 1, 'eof' => 2], $q->value());
+});
+
+// ============================================================================
+// Framework Bridge Tests
+// ============================================================================
+
+echo "\n=== Framework Bridge Tests ===\n";
+
+test('SymfonyEncoder encodes and decodes json', function() {
+    $encoder = new Sift\Bridge\SymfonyEncoder();
+    assert_true($encoder->supportsEncoding('json'));
+    assert_false($encoder->supportsDecoding('xml'));
+    assert_equals('{"name":"Ada","tags":["a"]}', $encoder->encode(['name' => 'Ada', 'tags' => ['a']], 'json'));
+    assert_equals(['user' => ['id' => 1]], $encoder->decode('{"user": {"id": 1}}', 'json'));
+    $object = $encoder->decode('{"id": 1}', 'json', ['json_decode_associative' => false]);
+    assert_true($object instanceof stdClass);
+    assert_throws(fn() => $encoder->decode('{', 'json'));
+});
+
+test('SymfonyEncoder can be extended to implement interfaces', function() {
+    $encoder = new class(['bigint' => 'string']) extends Sift\Bridge\SymfonyEncoder implements Countable {
+        public function count(): int { return 1; }
+    };
+    assert_equals(['n' => '99999999999999999999'], $encoder->decode('{"n": 99999999999999999999}', 'json'));
+    assert_throws(fn() => new Sift\Bridge\SymfonyEncoder(['unknown' => 1]), 'Unknown option');
+});
+
+test('LaravelJson casts attributes', function() {
+    $cast = new Sift\Bridge\LaravelJson();
+    assert_equals(['theme' => 'dark'], $cast->get(null, 'settings', '{"theme": "dark"}', []));
+    assert_equals(null, $cast->get(null, 'settings', null, []));
+    assert_equals('{"theme":"dark"}', $cast->set(null, 'settings', ['theme' => 'dark'], []));
+    assert_equals(null, $cast->set(null, 'settings', null, []));
+
+    $object = (new Sift\Bridge\LaravelJson('object'))->get(null, 'settings', '{"theme": "dark"}', []);
+    assert_equals('dark', $object->theme);
+
+    $query = new Sift\Bridge\LaravelJson('query');
+    $payload = $query->get(null, 'payload', '{"a": [1, 2]}', []);
+    assert_equals(2, $payload->pointer('/a/1')->int());
+    assert_equals('{"a": [1, 2]}', $query->set(null, 'payload', $payload, []));
+    assert_throws(fn() => new Sift\Bridge\LaravelJson('collection'), 'Cast mode');
+});
+
+// ============================================================================
+// Session Serializer Tests
+// ============================================================================
+
+echo "\n=== Session Serializer Tests ===\n";
+
+test('SessionSerializer round-trips session variables', function() {
+    $vars = ['user_id' => 42, 'cart' => ['items' => [1, 2]], 'flash' => null];
+    $stored = Sift\SessionSerializer::encode($vars);
+    assert_equals('{"user_id":42,"cart":{"items":[1,2]},"flash":null}', $stored);
+    assert_equals($vars, Sift\SessionSerializer::decode($stored));
+    assert_equals([], Sift\SessionSerializer::decode(''));
+    assert_equals('sift_json', Sift\SessionSerializer::NAME);
+});
+
+test('SessionSerializer rejects non-object data', function() {
+    assert_throws(fn() => Sift\SessionSerializer::decode('42'), 'JSON object or array');
+    assert_throws(fn() => Sift\SessionSerializer::decode('{"a":'));
+    assert_throws(fn() => Sift\SessionSerializer::encode(['handle' => STDIN]));
+});
+
+test('sift_json session handler stores $_SESSION as JSON', function() {
+    if (!extension_loaded('session') || !Sift\SessionSerializer::isRegistered()) {
+        return;
+    }
+    ini_set('session.use_cookies', '0');
+    ini_set('session.cache_limiter', '');
+    ini_set('session.serialize_handler', 'sift_json');
+    session_start();
+    $_SESSION = ['user_id' => 42, 'roles' => ['admin']];
+    assert_equals('{"user_id":42,"roles":["admin"]}', session_encode());
+    assert_true(session_decode('{"theme":"dark"}'));
+    assert_equals('dark', $_SESSION['theme']);
+    session_destroy();
+});
+
+// ============================================================================
+// Compiled Artifact Cache Tests
+// ============================================================================
+
+echo "\n=== Compiled Artifact Cache Tests ===\n";
+
+test('cacheCompiled keeps results and errors unchanged', function() {
+    $inUse = Sift::cacheCompiled();
+    assert_equals(function_exists('apcu_enabled') && apcu_enabled(), $inUse);
+    try {
+        $template = ['id' => '/id', 'tags' => ['/tags/*', ['name' => '/name']], 7 => '/a~1b'];
+        $json = '{"id": 1, "tags": [{"name": "x"}, {"name": "y"}], "a/b": true}';
+        $expected = ['id' => 1, 'tags' => [['name' => 'x'], ['name' => 'y']], 7 => true];
+        // The second compile reads the first one's entry when APCu is on
+        assert_equals($expected, Sift::compileExtractor($template)->extract($json));
+        assert_equals($expected, Sift::compileExtractor($template)->extract($json));
+        assert_equals(['/tags/*/name' => ['x', 'y']], Sift::extract($json, ['/tags/*/name' => '/tags/*/name']));
+
+        $schema = '{"type": "object", "required": ["id"]}';
+        assert_equals([], Sift::query($json)->matchesSchema($schema));
+        assert_equals(1, count(Sift::query('{}')->matchesSchema($schema)));
+        assert_throws(fn() => Sift::query($json)->matchesSchema('{"pattern": "x"}'), 'not supported');
+        assert_throws(fn() => Sift::compileExtractor(['id' => 'id']), 'must start with');
+    } finally {
+        Sift::cacheCompiled(false);
+    }
+    assert_false(Sift::cacheCompiled(false));
+});
+
+// ============================================================================
+// Query Release Tests
+// ============================================================================
+
+echo "\n=== Query Release Tests ===\n";
+
+test('release drops the buffer and later reads throw', function() {
+    $q = Sift::query('{"order": {"id": 7}, "note": "x"}');
+    $order = $q->get('order');
+    assert_false($q->isReleased());
+    $q->release();
+    assert_true($q->isReleased());
+    assert_throws(fn() => $q->get('note')->string(), 'released');
+    assert_throws(fn() => $q->value(), 'released');
+    // Queries derived before the release keep their own reference
+    assert_equals(7, $order->get('id')->int());
+    $q->release();
+    $order->close();
+    assert_true($order->isReleased());
+    try {
+        $order->raw();
+    } catch (\Sift\StaleHandleException $e) {
+        assert_equals(\Sift\Exception::STALE_HANDLE, $e->getErrorCode());
+    }
+});
+
+test('weak Queries release after a successful hydration', function() {
+    $q = Sift::query('{"id": 1, "name": "Ada", "tags": ["a"]}')->weak();
+    $id = $q->get('id');
+    assert_equals(1, $id->int());
+    assert_true($id->isReleased());
+    assert_throws(fn() => $id->int(), 'released');
+
+    // A failed hydration keeps the buffer
+    $name = $q->get('name');
+    assert_throws(fn() => $name->int(), 'not an integer');
+    assert_false($name->isReleased());
+    assert_equals('Ada', $name->string());
+    assert_true($name->isReleased());
+
+    // The root was never hydrated, so it still reads
+    assert_false($q->isReleased());
+    assert_equals(['a'], $q->get('tags')->value());
+    assert_equals(['a'], $q->weak(false)->get('tags')->value());
+    $tags = $q->weak(false)->get('tags');
+    $tags->raw();
+    assert_false($tags->isReleased());
+});
+
+test('trimMemory frees the conversion arena', function() {
+    Sift::decode(str_repeat('[', 100) . str_repeat(']', 100));
+    assert_true(Sift::trimMemory() >= 0);
+    assert_equals(0, Sift::trimMemory());
+    assert_equals([1], Sift::decode('[1]'));
+});
+
+// ============================================================================
+// Memory Accounting Tests
+// ============================================================================
+
+echo "\n=== Memory Accounting Tests ===\n";
+
+test('memoryUsage counts documents held in Rust', function() {
+    $usage = Sift::memoryUsage();
+    assert_equals(['current', 'peak', 'arena'], array_keys($usage));
+    assert_true($usage['peak'] >= $usage['current']);
+    $before = $usage['current'];
+    $q = Sift::query('[' . str_repeat('1,', 500000) . '1]');
+    assert_true(Sift::memoryUsage()['current'] >= $before + 1000000);
+    $q->release();
+    assert_true(Sift::memoryUsage()['current'] < $before + 1000000);
+    assert_true(Sift::memoryUsage()['peak'] >= $before + 1000000);
+});
+
+test('documents held in Rust respect memory_limit', function() {
+    $limit = ini_get('memory_limit');
+    $json = '"' . str_repeat('x', 8 << 20) . '"';
+    // PHP won't lower the limit below its real usage
+    ini_set('memory_limit', (string) (memory_get_usage(true) + (2 << 20)));
+    try {
+        assert_throws(fn() => Sift::query($json), 'exceeds maximum allowed');
+        assert_throws(fn() => Sift::snapshot($json));
+        assert_equals(1, Sift::query('1')->int());
+    } finally {
+        ini_set('memory_limit', $limit);
+    }
+    assert_equals(8 << 20, Sift::query($json)->len());
+});
+
+// ============================================================================
+// Leak Check Tests
+// ============================================================================
+
+echo "\n=== Leak Check Tests ===\n";
+
+test('LEAKED_OBJECTS code is exposed', function() {
+    assert_equals(11, \Sift\Exception::LEAKED_OBJECTS);
+});
+
+test('leak checks do nothing with sift.leak_check off', function() {
+    if (ini_get('sift.leak_check')) {
+        return;
+    }
+    $kept = Sift::query('{"a": 1}');
+    assert_equals([], Sift::liveObjects());
+    assert_equals(0, Sift::checkLeaks());
+    assert_equals(1, $kept->get('a')->int());
+});
+
+test('checkLeaks reports objects outliving a checkpoint', function() {
+    // Run with: php -d sift.leak_check=1 tests/php/SiftTest.php
+    if (!ini_get('sift.leak_check')) {
+        return;
+    }
+    Sift::checkLeaks(false);
+    $q = Sift::query('{"a": [1, 2]}');
+    $child = $q->get('a');
+    $pool = Sift::pool();
+    assert_equals(['Sift\Pool' => 1, 'Sift\Query' => 2], Sift::liveObjects());
+    unset($child);
+    assert_equals(['Sift\Pool' => 1, 'Sift\Query' => 1], Sift::liveObjects());
+    try {
+        Sift::checkLeaks();
+        assert_true(false, 'checkLeaks should have thrown');
+    } catch (\Sift\Exception $e) {
+        assert_equals(\Sift\Exception::LEAKED_OBJECTS, $e->getErrorCode());
+        assert_true(str_contains($e->getMessage(), '1 Sift\Pool, 1 Sift\Query'));
+    }
+    // Already reported: neither counts again, alive or freed
+    assert_equals([], Sift::liveObjects());
+    unset($q, $pool);
+    assert_equals(0, Sift::checkLeaks());
+    foreach (Sift::query('[1, 2, 3]')->chunks(2) as $chunk) {
+    }
+    assert_equals(0, Sift::checkLeaks());
+});
+
+// ============================================================================
+// Large String Hydration Tests
+// ============================================================================
+
+echo "\n=== Large String Hydration Tests ===\n";
+
+test('string() returns multi-megabyte values intact', function() {
+    $html = str_repeat('<p>plain text</p>', 200000);
+    $q = Sift::query(json_encode(['html' => $html, 'n' => 1], JSON_UNESCAPED_SLASHES));
+    assert_equals($html, $q->get('html')->string());
+    assert_equals(1, $q->get('n')->int());
+});
+
+test('string() unescapes large values with escapes', function() {
+    $text = str_repeat("line \"quoted\" \\ é\n", 100000);
+    $q = Sift::query(json_encode(['text' => $text]));
+    assert_equals($text, $q->get('text')->string());
+    assert_equals($text, $q->get('text')->value());
+});
+
+test('raw() returns a large slice of the input unchanged', function() {
+    $blob = base64_encode(str_repeat("\x00\xff", 1 << 20));
+    $json = '{"blob":"' . $blob . '","tail":[1,2]}';
+    $q = Sift::query($json);
+    assert_equals('"' . $blob . '"', $q->get('blob')->raw());
+    assert_equals('[1,2]', $q->get('tail')->raw());
+});
+
+test('string() still strips control characters', function() {
+    $q = Sift::query('{"s": "a\u0001b"}', ['control_chars' => 'strip']);
+    assert_equals('ab', $q->get('s')->string());
+    assert_throws(fn() => Sift::query('[1]')->index(0)->string(), 'not a string');
+});
+
+// ============================================================================
+// Sift::lines() Tests
+// ============================================================================
+
+echo "\n=== Sift::lines() Tests ===\n";
+
+function lines_fixture(string $contents): string {
+    $path = tempnam(sys_get_temp_dir(), 'sift-lines');
+    file_put_contents($path, $contents);
+    return $path;
+}
+
+test('lines yields records in file order keyed by offset', function() {
+    $path = lines_fixture("{\"id\": 1}\n\n[2]\r\n\"three\"");
+    $records = iterator_to_array(Sift::lines($path));
+    assert_equals([0 => ['id' => 1], 11 => [2], 16 => 'three'], $records);
+    unlink($path);
+});
+
+test('lines keeps order across workers and batches', function() {
+    $ndjson = '';
+    for ($i = 0; $i < 3000; $i++) {
+        $ndjson .= "{\"n\": $i}\n";
+    }
+    $path = lines_fixture($ndjson);
+    $seen = [];
+    foreach (Sift::lines($path, null, 3) as $record) {
+        $seen[] = $record['n'];
+    }
+    assert_equals(range(0, 2999), $seen);
+    unlink($path);
+});
+
+test('lines decodes with options on the PHP thread', function() {
+    $path = lines_fixture("{\"user_id\": 1}\n{\"user_id\": 2}\n");
+    $records = iterator_to_array(Sift::lines($path, ['keys' => 'camel']), false);
+    assert_equals([['userId' => 1], ['userId' => 2]], $records);
+    unlink($path);
+});
+
+test('lines publishes text repair warnings with each record', function() {
+    $path = lines_fixture("{\"a\": \"x\xff\"}\n{\"a\": \"ok\"}\n[\"\xfe\"]\n");
+    $options = ['warnings' => true, 'utf8' => 'substitute'];
+    $seen = [];
+    foreach (Sift::lines($path, $options, 2) as $offset => $record) {
+        $seen[$offset] = Sift::lastWarnings();
+    }
+    // Offsets are in the file, not the line
+    assert_equals([
+        0 => [['pointer' => null, 'kind' => 'utf8_substituted', 'offset' => 8]],
+        12 => [],
+        24 => [['pointer' => null, 'kind' => 'utf8_substituted', 'offset' => 26]],
+    ], $seen);
+    unlink($path);
+});
+
+test('lines resumes from tell()', function() {
+    $path = lines_fixture("1\n2\n3\n4\n");
+    $lines = Sift::lines($path, null, 2);
+    foreach ($lines as $value) {
+        if ($value === 2) {
+            break;
+        }
+    }
+    $offset = $lines->tell();
+    assert_equals(4, $offset);
+    assert_equals([3, 4], iterator_to_array(Sift::lines($path, null, 1, $offset), false));
+    assert_equals([1, 2, 3, 4], iterator_to_array($lines, false));
+    unlink($path);
+});
+
+test('lines reports a bad line with its offset', function() {
+    $path = lines_fixture("{\"a\": 1}\n{\"a\":\n");
+    try {
+        iterator_to_array(Sift::lines($path));
+        assert_true(false, 'expected an exception');
+    } catch (\Sift\Exception $e) {
+        assert_true(str_contains($e->getMessage(), 'byte 9'));
+    }
+    unlink($path);
+});
+
+test('lines blocks readers at the buffer limit', function() {
+    $path = lines_fixture(implode("\n", range(1, 200)) . "\n");
+    $values = iterator_to_array(Sift::lines($path, null, 2, null, ['max_bytes' => 8]), false);
+    assert_equals(range(1, 200), $values);
+    unlink($path);
+});
+
+test('lines throws after the records read within the buffer limit', function() {
+    $path = lines_fixture("1\n2\n3\n");
+    $lines = Sift::lines($path, null, 1, null, ['max_bytes' => 1, 'when_full' => 'error']);
+    $lines->rewind();
+    // The reader runs into the limit while the first record is held
+    usleep(100000);
+    $values = [];
+    try {
+        for (; $lines->valid(); $lines->next()) {
+            $values[] = $lines->current();
+        }
+        assert_true(false, 'expected an exception');
+    } catch (\Sift\InputTooLargeException $e) {
+        assert_equals(1, $e->getLimit());
+    }
+    assert_equals([1], $values);
+    assert_equals(2, $lines->tell());
+    unlink($path);
+});
+
+test('lines rejects bad arguments and missing files', function() {
+    assert_throws(fn() => Sift::lines('/tmp/x.ndjson', null, 0));
+    assert_throws(fn() => Sift::lines('/tmp/x.ndjson', null, 33));
+    assert_throws(fn() => Sift::lines('/tmp/x.ndjson', null, null, -1));
+    assert_throws(fn() => Sift::lines('/tmp/x.ndjson', null, null, null, ['max_bytes' => -1]));
+    assert_throws(fn() => Sift::lines('/tmp/x.ndjson', null, null, null, ['when_full' => 'drop']));
+    assert_throws(fn() => iterator_to_array(Sift::lines('/nonexistent/sift/x.ndjson')));
+});
+
 // ==================== Summary ====================
 echo "\n=== Results ===\n";
 echo "Passed: {$passed}\n";